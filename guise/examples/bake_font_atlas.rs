@@ -0,0 +1,69 @@
+// Rasterizes a font into a baked font atlas, so that applications can load it
+// with FontAtlas::from_baked_in and build guise without runtime_rasterize, and
+// therefore without fontdue.
+//
+// Usage: bake_font_atlas <font.ttf> <font size> <scale factor> <out> [--all] [--sdf]
+//
+// Contains all latin ranges by default, or all ranges supported by guise with
+// --all. With --sdf, the atlas contains signed distance fields.
+
+#![feature(allocator_api)]
+
+use std::alloc::Global;
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (positional, flags): (Vec<&String>, Vec<&String>) =
+        args.iter().partition(|arg| !arg.starts_with("--"));
+
+    let [font_path, font_size, font_scale_factor, out_path] = positional[..] else {
+        eprintln!(
+            "Usage: bake_font_atlas <font.ttf> <font size> <scale factor> <out> [--all] [--sdf]"
+        );
+        process::exit(1);
+    };
+
+    let font_size: f32 = font_size.parse().expect("Failed to parse font size");
+    let font_scale_factor: f32 = font_scale_factor
+        .parse()
+        .expect("Failed to parse scale factor");
+
+    let mut unicode_range_flags = guise::UnicodeRangeFlags::ALL_LATIN;
+    let mut sdf = false;
+    for flag in flags {
+        match flag.as_str() {
+            "--all" => unicode_range_flags = guise::UnicodeRangeFlags::ALL,
+            "--sdf" => sdf = true,
+            _ => {
+                eprintln!("Unknown flag {flag}");
+                process::exit(1);
+            }
+        }
+    }
+
+    let font_bytes = fs::read(font_path).expect("Failed to read font");
+
+    let mut font_atlas = guise::FontAtlas::new_in(
+        &font_bytes,
+        unicode_range_flags,
+        font_size,
+        font_scale_factor,
+        u16::MAX,
+        Global,
+    );
+    if sdf {
+        font_atlas.set_sdf(true);
+    }
+
+    let baked = font_atlas.to_baked();
+    fs::write(out_path, &baked).expect("Failed to write baked font atlas");
+
+    let (image_width, image_height) = font_atlas.image_size();
+    println!(
+        "Baked {image_width}x{image_height} atlas into {out_path} ({} bytes)",
+        baked.len()
+    );
+}
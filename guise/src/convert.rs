@@ -1,5 +1,18 @@
 use core::fmt::Debug;
 
+/// Losslessly converts `n` to [`u8`] using [`TryFrom`] or panics.
+///
+/// # Panics
+///
+/// Panics if the conversion errors.
+pub fn cast_u8<T>(n: T) -> u8
+where
+    T: TryInto<u8>,
+    <T as TryInto<u8>>::Error: Debug,
+{
+    n.try_into().expect("Expected N to fit in u8")
+}
+
 /// Losslessly converts `n` to [`u16`] using [`TryFrom`] or panics.
 ///
 /// # Panics
@@ -0,0 +1,147 @@
+/// A color packed into a u32 as `0xRRGGBBAA`, with the color channels encoded
+/// in sRGB and a linear alpha channel. This is the same packing all widgets
+/// and themes use for their u32 colors, so converting between the two is
+/// free.
+///
+/// Colors are always specified in sRGB, regardless of the renderer's surface
+/// format. If the surface expects linear colors, the conversion happens once,
+/// when the draw list is built (see [`crate::Ui::set_linear_color_output`]).
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Color(pub u32);
+
+impl Color {
+    pub const TRANSPARENT: Self = Self(0x00000000);
+    pub const BLACK: Self = Self(0x000000ff);
+    pub const WHITE: Self = Self(0xffffffff);
+
+    pub const fn from_srgb8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self(u32::from_be_bytes([r, g, b, a]))
+    }
+
+    /// Encodes linear color channels from 0 to 1 to sRGB. Values outside the
+    /// range are clamped.
+    pub fn from_linear_f32(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self::from_srgb8(
+            linear_to_srgb8(r),
+            linear_to_srgb8(g),
+            linear_to_srgb8(b),
+            unorm_to_u8(a),
+        )
+    }
+
+    pub const fn to_srgb8(self) -> [u8; 4] {
+        self.0.to_be_bytes()
+    }
+
+    /// Decodes the color channels to linear values from 0 to 1.
+    pub fn to_linear_f32(self) -> [f32; 4] {
+        let [r, g, b, a] = self.to_srgb8();
+        [
+            srgb8_to_linear(r),
+            srgb8_to_linear(g),
+            srgb8_to_linear(b),
+            f32::from(a) / 255.0,
+        ]
+    }
+
+    pub const fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for Color {
+    fn from(color: u32) -> Self {
+        Self(color)
+    }
+}
+
+impl From<Color> for u32 {
+    fn from(color: Color) -> Self {
+        color.0
+    }
+}
+
+/// Linearly interpolates between two colors in linear space, e.g. to animate a
+/// control between its normal and hovered colors. Interpolating in linear
+/// space keeps the midpoint from looking darker than both ends.
+pub fn color_lerp(a: u32, b: u32, t: f32) -> u32 {
+    let a = Color(a).to_linear_f32();
+    let b = Color(b).to_linear_f32();
+    let lerp = |i: usize| a[i] + (b[i] - a[i]) * t;
+
+    Color::from_linear_f32(lerp(0), lerp(1), lerp(2), lerp(3)).0
+}
+
+/// Multiplies the alpha of a color by a factor from 0 to 1, e.g. to fade a
+/// color out. Alpha is stored linearly, so the color channels are untouched.
+pub fn color_multiply_alpha(color: u32, factor: f32) -> u32 {
+    let [r, g, b, a] = color.to_be_bytes();
+    let a = unorm_to_u8(f32::from(a) / 255.0 * factor.clamp(0.0, 1.0));
+
+    u32::from_be_bytes([r, g, b, a])
+}
+
+// Converts the color channels of an sRGB color to linear, keeping the 8-bit
+// packing. Alpha is already linear.
+pub(crate) fn linearize_color(color: u32) -> u32 {
+    let [r, g, b, a] = color.to_be_bytes();
+    let linearize = |c: u8| unorm_to_u8(srgb8_to_linear(c));
+
+    u32::from_be_bytes([linearize(r), linearize(g), linearize(b), a])
+}
+
+fn srgb8_to_linear(c: u8) -> f32 {
+    let c = f32::from(c) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        libm::powf((c + 0.055) / 1.055, 2.4)
+    }
+}
+
+fn linear_to_srgb8(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * libm::powf(c, 1.0 / 2.4) - 0.055
+    };
+
+    unorm_to_u8(c)
+}
+
+fn unorm_to_u8(c: f32) -> u8 {
+    libm::roundf(c.clamp(0.0, 1.0) * 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_round_trips_through_linear() {
+        for c in 0..=255 {
+            let color = Color::from_srgb8(c, c, c, c);
+            let [r, g, b, a] = color.to_linear_f32();
+            assert_eq!(Color::from_linear_f32(r, g, b, a), color);
+        }
+
+        assert_eq!(linearize_color(0xffffff80), 0xffffff80);
+        assert_eq!(linearize_color(0x80808080), 0x37373780);
+    }
+
+    #[test]
+    fn test_color_lerp_in_linear_space() {
+        assert_eq!(color_lerp(0x00ff0080, 0xff00ff80, 0.5), 0xbcbcbc80);
+        assert_eq!(color_lerp(0x00000000, 0xffffffff, 2.0), 0xffffffff);
+        assert_eq!(color_lerp(0x12345678, 0x9abcdef0, 0.0), 0x12345678);
+        assert_eq!(color_lerp(0x12345678, 0x9abcdef0, 1.0), 0x9abcdef0);
+    }
+
+    #[test]
+    fn test_color_multiply_alpha() {
+        assert_eq!(color_multiply_alpha(0x12345680, 0.5), 0x12345640);
+        assert_eq!(color_multiply_alpha(0x123456ff, 2.0), 0x123456ff);
+    }
+}
@@ -1,8 +1,10 @@
 use alloc::vec::Vec;
 use core::alloc::Allocator;
+use core::f32::consts::{FRAC_PI_2, PI, TAU};
 
-use crate::convert::cast_u32;
-use crate::core::math::Rect;
+use crate::convert::{cast_u16, cast_u32, cast_u8, cast_usize};
+use crate::core::color::linearize_color;
+use crate::core::math::{Rect, Vec2};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -11,9 +13,57 @@ pub struct Command {
     pub scissor_rect: Rect,
     pub texture_id: u64,
     pub index_count: u32,
-    // NB: Explicit padding, so that bytemuck doesn't cast uninitialized padding
-    // bytes to something.
-    pub _pad: u32,
+    /// Added to the command's indices to get the index of the vertex to read.
+    /// Always zero for 32-bit indices, but 16-bit indices are relative to this.
+    pub base_vertex: u32,
+    /// The range of vertices the command's indices reference, for renderers
+    /// that upload or bind vertices per command. Unlike the base vertex, this
+    /// is set for 32-bit indices too.
+    pub vertex_offset: u32,
+    pub vertex_count: u32,
+    /// Whether the command draws the indexed geometry, or calls back into the
+    /// renderer's own drawing code. See [`CommandKind::CALLBACK`].
+    pub kind: CommandKind,
+    /// How the renderer should filter the texture for this command.
+    pub sampling: Sampling,
+    /// The id passed to [`crate::Ctrl::draw_callback`], for callback commands.
+    pub callback_user_id: u64,
+}
+
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+pub struct CommandKind(u32);
+
+impl CommandKind {
+    /// Draws `index_count` indices with the command's texture and scissor
+    /// rect.
+    pub const DRAW: Self = Self(0);
+    /// Draws nothing and consumes no indices. Instead, the renderer should
+    /// run its own drawing for the command's callback user id, clipped to the
+    /// scissor rect, and restore its own pipeline state before drawing the
+    /// next command. The texture id is meaningless.
+    pub const CALLBACK: Self = Self(1);
+}
+
+/// A hint for how the renderer should filter the texture of a command.
+/// Textures drawn by guise itself, like the font atlas, are always sampled
+/// linearly, but user images can ask for nearest neighbor filtering, e.g. for
+/// pixel art.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+pub struct Sampling(u32);
+
+impl Sampling {
+    pub const LINEAR: Self = Self(0);
+    pub const NEAREST: Self = Self(1);
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self::LINEAR
+    }
 }
 
 #[repr(C)]
@@ -25,11 +75,51 @@ pub struct Vertex {
     pub color: u32,
 }
 
+/// Statistics about how many rects were drawn into a draw list, and how many
+/// commands they were merged into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawListStats {
+    /// The number of rects drawn.
+    pub rect_count: usize,
+    /// The number of commands there would be, if only consecutive rects with
+    /// the same texture and scissor rect were merged.
+    pub unmerged_command_count: usize,
+    /// The number of commands after also merging consecutive rects with the
+    /// same texture and different scissor rects, if neither scissor rect clips
+    /// them.
+    pub command_count: usize,
+    /// Whether vertex colors are premultiplied with their alpha, so that
+    /// renderers can pick the matching blend state. See
+    /// [`crate::Ui::set_premultiplied_alpha`].
+    pub premultiplied_alpha: bool,
+    /// Whether vertex colors are converted from sRGB to linear, for renderers
+    /// drawing to sRGB surfaces. See [`crate::Ui::set_linear_color_output`].
+    pub linear_color_output: bool,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DrawList<A: Allocator + Clone> {
     commands: Vec<Command, A>,
     vertices: Vec<Vertex, A>,
     indices: Vec<u32, A>,
+
+    rect_count: usize,
+    unmerged_command_count: usize,
+    last_scissor_rect: Rect,
+    last_texture_id: u64,
+    last_sampling: Sampling,
+
+    premultiplied_alpha: bool,
+    linear_color_output: bool,
+    anti_aliasing_fringe: Option<f32>,
+
+    // Scratch space for the outline of the shape being drawn.
+    path: Vec<Vec2, A>,
+
+    // Built on demand from the commands and indices above.
+    commands_u16: Vec<Command, A>,
+    indices_u16: Vec<u16, A>,
+    u16_valid: bool,
 }
 
 impl<A: Allocator + Clone> DrawList<A> {
@@ -40,7 +130,25 @@ impl<A: Allocator + Clone> DrawList<A> {
         Self {
             commands: Vec::with_capacity_in(capacity, allocator.clone()),
             vertices: Vec::with_capacity_in(vertex_capacity, allocator.clone()),
-            indices: Vec::with_capacity_in(index_capacity, allocator),
+            indices: Vec::with_capacity_in(index_capacity, allocator.clone()),
+
+            rect_count: 0,
+            unmerged_command_count: 0,
+            last_scissor_rect: Rect::ZERO,
+            last_texture_id: 0,
+            last_sampling: Sampling::LINEAR,
+
+            premultiplied_alpha: false,
+            linear_color_output: false,
+            anti_aliasing_fringe: None,
+
+            path: Vec::with_capacity_in(64, allocator.clone()),
+
+            // NB: The 16-bit draw list is not allocated up front, because most
+            // renderers don't need it.
+            commands_u16: Vec::new_in(allocator.clone()),
+            indices_u16: Vec::new_in(allocator),
+            u16_valid: false,
         }
     }
 
@@ -56,6 +164,126 @@ impl<A: Allocator + Clone> DrawList<A> {
         &self.indices
     }
 
+    /// Returns the draw list with 16-bit indices, for renderers that can't use
+    /// 32-bit indices. Commands are split, so that each references at most
+    /// 65536 vertices, and their indices are relative to their base vertex.
+    /// The vertices are the same as for the 32-bit draw list.
+    pub fn u16(&mut self) -> (&[Command], &[Vertex], &[u16]) {
+        if !self.u16_valid {
+            self.build_u16();
+            self.u16_valid = true;
+        }
+
+        (&self.commands_u16, &self.vertices, &self.indices_u16)
+    }
+
+    fn build_u16(&mut self) {
+        const MAX_VERTEX_COUNT: u32 = u16::MAX as u32 + 1;
+
+        self.commands_u16.clear();
+        self.indices_u16.clear();
+
+        // NB: This relies on every triangle's vertices being close together,
+        // which is true for everything we draw. A new command starts whenever
+        // a triangle would reference a vertex outside of the current one's
+        // range.
+        let mut index_offset = 0;
+        for command in &self.commands {
+            if command.kind == CommandKind::CALLBACK {
+                self.commands_u16.push(*command);
+                continue;
+            }
+
+            let index_count = cast_usize(command.index_count);
+            let indices = &self.indices[index_offset..index_offset + index_count];
+            index_offset += index_count;
+
+            let mut split = true;
+            for triangle in indices.chunks_exact(3) {
+                let min_vertex = triangle.iter().copied().min().unwrap();
+                let max_vertex = triangle.iter().copied().max().unwrap();
+
+                if let Some(last_command) = self.commands_u16.last() {
+                    if min_vertex < last_command.base_vertex
+                        || max_vertex + 1 - last_command.base_vertex > MAX_VERTEX_COUNT
+                    {
+                        split = true;
+                    }
+                }
+
+                if split {
+                    self.commands_u16.push(Command {
+                        scissor_rect: command.scissor_rect,
+                        texture_id: command.texture_id,
+                        index_count: 0,
+                        base_vertex: min_vertex,
+                        vertex_offset: min_vertex,
+                        vertex_count: 0,
+                        kind: CommandKind::DRAW,
+                        sampling: command.sampling,
+                        callback_user_id: 0,
+                    });
+
+                    split = false;
+                }
+
+                let last_command = self.commands_u16.last_mut().unwrap();
+                for index in triangle {
+                    self.indices_u16
+                        .push(cast_u16(index - last_command.base_vertex));
+                }
+
+                last_command.index_count += 3;
+                last_command.vertex_count = u32::max(
+                    last_command.vertex_count,
+                    max_vertex + 1 - last_command.vertex_offset,
+                );
+            }
+        }
+    }
+
+    pub(crate) fn premultiplied_alpha(&self) -> bool {
+        self.premultiplied_alpha
+    }
+
+    pub(crate) fn linear_color_output(&self) -> bool {
+        self.linear_color_output
+    }
+
+    pub(crate) fn anti_aliasing_fringe(&self) -> Option<f32> {
+        self.anti_aliasing_fringe
+    }
+
+    /// If enabled, colors of rects drawn from now on are premultiplied with
+    /// their alpha.
+    pub fn set_premultiplied_alpha(&mut self, premultiplied_alpha: bool) {
+        self.premultiplied_alpha = premultiplied_alpha;
+    }
+
+    /// If enabled, colors of shapes drawn from now on are converted from sRGB
+    /// to linear (before being premultiplied, if that is enabled too).
+    pub fn set_linear_color_output(&mut self, linear_color_output: bool) {
+        self.linear_color_output = linear_color_output;
+    }
+
+    /// If set, lines, circles and rounded rects drawn from now on get a
+    /// translucent fringe of the given width along their edges, which smooths
+    /// out jagged edges at the cost of extra vertices. Rects are axis-aligned
+    /// and don't need it.
+    pub fn set_anti_aliasing_fringe(&mut self, anti_aliasing_fringe: Option<f32>) {
+        self.anti_aliasing_fringe = anti_aliasing_fringe;
+    }
+
+    pub fn stats(&self) -> DrawListStats {
+        DrawListStats {
+            rect_count: self.rect_count,
+            unmerged_command_count: self.unmerged_command_count,
+            command_count: self.commands.len(),
+            premultiplied_alpha: self.premultiplied_alpha,
+            linear_color_output: self.linear_color_output,
+        }
+    }
+
     pub fn draw_rect(
         &mut self,
         rect: Rect,
@@ -64,39 +292,119 @@ impl<A: Allocator + Clone> DrawList<A> {
         scissor_rect: Rect,
         texture_id: u64,
     ) {
+        self.push_rect(
+            rect,
+            texture_rect,
+            [color; 4],
+            scissor_rect,
+            texture_id,
+            false,
+            Sampling::LINEAR,
+        );
+    }
+
+    /// Like [`DrawList::draw_rect`], but can flip the texture vertically and
+    /// tells the renderer how to filter it. Rects with different sampling are
+    /// never merged into the same command.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_image(
+        &mut self,
+        rect: Rect,
+        texture_rect: Rect,
+        color: u32,
+        scissor_rect: Rect,
+        texture_id: u64,
+        flip_y: bool,
+        sampling: Sampling,
+    ) {
+        self.push_rect(
+            rect,
+            texture_rect,
+            [color; 4],
+            scissor_rect,
+            texture_id,
+            flip_y,
+            sampling,
+        );
+    }
+
+    /// Draws a rect with a different color in each corner, in the order
+    /// top-left, top-right, bottom-right, bottom-left. The colors are
+    /// interpolated across the two triangles of the rect, so gradients with
+    /// more than two distinct colors will show the diagonal between the
+    /// triangles.
+    pub fn draw_rect_with_corner_colors(
+        &mut self,
+        rect: Rect,
+        texture_rect: Rect,
+        colors: [u32; 4],
+        scissor_rect: Rect,
+        texture_id: u64,
+    ) {
+        self.push_rect(
+            rect,
+            texture_rect,
+            colors,
+            scissor_rect,
+            texture_id,
+            false,
+            Sampling::LINEAR,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_rect(
+        &mut self,
+        rect: Rect,
+        texture_rect: Rect,
+        colors: [u32; 4],
+        scissor_rect: Rect,
+        texture_id: u64,
+        flip_y: bool,
+        sampling: Sampling,
+    ) {
+        let (top_tex_coord, bottom_tex_coord) = if flip_y {
+            (texture_rect.max_y(), texture_rect.y)
+        } else {
+            (texture_rect.y, texture_rect.max_y())
+        };
+
         let tl_position = [rect.x, rect.y];
-        let tl_tex_coord = [texture_rect.x, texture_rect.y];
+        let tl_tex_coord = [texture_rect.x, top_tex_coord];
 
         let tr_position = [rect.max_x(), rect.y];
-        let tr_tex_coord = [texture_rect.max_x(), texture_rect.y];
+        let tr_tex_coord = [texture_rect.max_x(), top_tex_coord];
 
         let bl_position = [rect.x, rect.max_y()];
-        let bl_tex_coord = [texture_rect.x, texture_rect.max_y()];
+        let bl_tex_coord = [texture_rect.x, bottom_tex_coord];
 
         let br_position = [rect.max_x(), rect.max_y()];
-        let br_tex_coord = [texture_rect.max_x(), texture_rect.max_y()];
+        let br_tex_coord = [texture_rect.max_x(), bottom_tex_coord];
+
+        let [tl_color, tr_color, br_color, bl_color] = colors.map(|color| self.pack_color(color));
 
         let index_base = cast_u32(self.vertices.len());
+        self.u16_valid = false;
 
         self.vertices.push(Vertex {
             position: bl_position,
             tex_coord: bl_tex_coord,
-            color,
+            color: bl_color,
         });
         self.vertices.push(Vertex {
             position: br_position,
             tex_coord: br_tex_coord,
-            color,
+            color: br_color,
         });
         self.vertices.push(Vertex {
             position: tr_position,
             tex_coord: tr_tex_coord,
-            color,
+            color: tr_color,
         });
         self.vertices.push(Vertex {
             position: tl_position,
             tex_coord: tl_tex_coord,
-            color,
+            color: tl_color,
         });
 
         // 0, 1, 2
@@ -115,30 +423,944 @@ impl<A: Allocator + Clone> DrawList<A> {
         self.indices.push(i5);
         self.indices.push(i6);
 
+        self.rect_count += 1;
+        self.push_command(rect, index_base, 6, scissor_rect, texture_id, sampling);
+    }
+
+    /// Draws a line from `a` to `b`. The line is drawn untextured, i.e. it
+    /// samples the texture at its origin, same as a rect with a zero texture
+    /// rect.
+    pub fn draw_line(
+        &mut self,
+        a: Vec2,
+        b: Vec2,
+        thickness: f32,
+        color: u32,
+        scissor_rect: Rect,
+        texture_id: u64,
+    ) {
+        self.path.clear();
+        self.path.push(a);
+        self.path.push(b);
+
+        self.stroke_path(false, thickness, color, scissor_rect, texture_id);
+    }
+
+    /// Draws a filled circle. Untextured, same as [`DrawList::draw_line`].
+    pub fn draw_circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        color: u32,
+        scissor_rect: Rect,
+        texture_id: u64,
+    ) {
+        if radius <= 0.0 {
+            return;
+        }
+
+        let segment_count = circle_segment_count(radius);
+
+        self.path.clear();
+        for i in 0..segment_count {
+            let angle = TAU * i as f32 / segment_count as f32;
+            self.path.push(center + radius * angle_direction(angle));
+        }
+
+        self.fill_path(color, scissor_rect, texture_id);
+    }
+
+    /// Draws a filled rect with rounded corners. Untextured, same as
+    /// [`DrawList::draw_line`].
+    pub fn draw_rounded_rect(
+        &mut self,
+        rect: Rect,
+        radius: f32,
+        color: u32,
+        scissor_rect: Rect,
+        texture_id: u64,
+    ) {
+        self.path.clear();
+        self.path_rounded_rect(rect, radius);
+
+        self.fill_path(color, scissor_rect, texture_id);
+    }
+
+    /// Draws the border of a rect with rounded corners. The border is inside
+    /// the rect. Untextured, same as [`DrawList::draw_line`].
+    pub fn draw_rounded_rect_border(
+        &mut self,
+        rect: Rect,
+        radius: f32,
+        thickness: f32,
+        color: u32,
+        scissor_rect: Rect,
+        texture_id: u64,
+    ) {
+        let half_thickness = 0.5 * f32::min(thickness, 0.5 * f32::min(rect.width, rect.height));
+
+        self.path.clear();
+        self.path_rounded_rect(rect.inset(half_thickness), radius - half_thickness);
+
+        self.stroke_path(true, thickness, color, scissor_rect, texture_id);
+    }
+
+    fn path_rounded_rect(&mut self, rect: Rect, radius: f32) {
+        let radius = f32::min(radius, 0.5 * f32::min(rect.width, rect.height));
+
+        if radius <= 0.0 {
+            self.path.push(Vec2::new(rect.x, rect.y));
+            self.path.push(Vec2::new(rect.max_x(), rect.y));
+            self.path.push(Vec2::new(rect.max_x(), rect.max_y()));
+            self.path.push(Vec2::new(rect.x, rect.max_y()));
+
+            return;
+        }
+
+        // Each corner gets a quarter of the segments of a full circle, at
+        // least one.
+        let segment_count = usize::max(1, circle_segment_count(radius) / 4);
+
+        // Corner centers and the angles their arcs start at, going around the
+        // rect in the same direction as circles.
+        let corners = [
+            (Vec2::new(rect.x + radius, rect.y + radius), PI),
+            (
+                Vec2::new(rect.max_x() - radius, rect.y + radius),
+                PI + FRAC_PI_2,
+            ),
+            (Vec2::new(rect.max_x() - radius, rect.max_y() - radius), 0.0),
+            (Vec2::new(rect.x + radius, rect.max_y() - radius), FRAC_PI_2),
+        ];
+
+        for (center, start_angle) in corners {
+            for i in 0..=segment_count {
+                let angle = start_angle + FRAC_PI_2 * i as f32 / segment_count as f32;
+                self.path.push(center + radius * angle_direction(angle));
+            }
+        }
+    }
+
+    // Fills the convex polygon in self.path. The path must go around the
+    // polygon in the direction of increasing angle, i.e. clockwise on screen.
+    fn fill_path(&mut self, color: u32, scissor_rect: Rect, texture_id: u64) {
+        let point_count = self.path.len();
+        if point_count < 3 {
+            return;
+        }
+
+        let (color, transparent_color) = self.fringe_colors(color);
+        let index_base = cast_u32(self.vertices.len());
+        let mut index_count = 0;
+
+        match self.anti_aliasing_fringe {
+            Some(fringe) => {
+                // Each point gets an inner vertex with the full color, and an
+                // outer transparent one, half the fringe away on either side.
+                for i in 0..point_count {
+                    let prev = self.path[(i + point_count - 1) % point_count];
+                    let point = self.path[i];
+                    let next = self.path[(i + 1) % point_count];
+
+                    let normal = miter_normal(edge_normal(prev, point), edge_normal(point, next));
+                    let offset = 0.5 * fringe * normal;
+
+                    self.push_vertex(point - offset, color);
+                    self.push_vertex(point + offset, transparent_color);
+                }
+
+                for i in 1..cast_u32(point_count) - 1 {
+                    self.indices.push(index_base);
+                    self.indices.push(index_base + 2 * i);
+                    self.indices.push(index_base + 2 * (i + 1));
+                    index_count += 3;
+                }
+
+                for i in 0..cast_u32(point_count) {
+                    let inner_a = index_base + 2 * i;
+                    let inner_b = index_base + 2 * ((i + 1) % cast_u32(point_count));
+
+                    index_count +=
+                        self.push_quad_indices(inner_a, inner_b, inner_b + 1, inner_a + 1);
+                }
+            }
+            None => {
+                for i in 0..point_count {
+                    let point = self.path[i];
+                    self.push_vertex(point, color);
+                }
+
+                for i in 1..cast_u32(point_count) - 1 {
+                    self.indices.push(index_base);
+                    self.indices.push(index_base + i);
+                    self.indices.push(index_base + i + 1);
+                    index_count += 3;
+                }
+            }
+        }
+
+        let bounds = self.vertex_bounds(index_base);
+        self.push_command(
+            bounds,
+            index_base,
+            index_count,
+            scissor_rect,
+            texture_id,
+            Sampling::LINEAR,
+        );
+    }
+
+    // Strokes the polyline in self.path, centered on the path.
+    fn stroke_path(
+        &mut self,
+        closed: bool,
+        thickness: f32,
+        color: u32,
+        scissor_rect: Rect,
+        texture_id: u64,
+    ) {
+        let point_count = self.path.len();
+        if point_count < 2 || thickness <= 0.0 {
+            return;
+        }
+
+        let segment_count = if closed { point_count } else { point_count - 1 };
+
+        let index_base = cast_u32(self.vertices.len());
+        let mut index_count = 0;
+
+        let point_normal = |path: &[Vec2], i: usize| {
+            let prev = if closed || i > 0 {
+                Some(edge_normal(
+                    path[(i + point_count - 1) % point_count],
+                    path[i],
+                ))
+            } else {
+                None
+            };
+            let next = if closed || i < point_count - 1 {
+                Some(edge_normal(path[i], path[(i + 1) % point_count]))
+            } else {
+                None
+            };
+
+            match (prev, next) {
+                (Some(prev), Some(next)) => miter_normal(prev, next),
+                (Some(normal), None) | (None, Some(normal)) => normal,
+                (None, None) => unreachable!(),
+            }
+        };
+
+        match self.anti_aliasing_fringe {
+            Some(fringe) => {
+                // Lines thinner than the fringe don't get a solid core, and
+                // are faded out instead.
+                let color = if thickness < fringe {
+                    scale_alpha(color, thickness / fringe)
+                } else {
+                    color
+                };
+                let (color, transparent_color) = self.fringe_colors(color);
+
+                let half_core = 0.5 * f32::max(thickness - fringe, 0.0);
+
+                // Each point gets four vertices across the line: the outer
+                // edge of the fringe, the edge of the solid core, the other
+                // edge of the core, and the other edge of the fringe.
+                for i in 0..point_count {
+                    let point = self.path[i];
+                    let normal = point_normal(&self.path, i);
+
+                    self.push_vertex(point + (half_core + fringe) * normal, transparent_color);
+                    self.push_vertex(point + half_core * normal, color);
+                    self.push_vertex(point - half_core * normal, color);
+                    self.push_vertex(point - (half_core + fringe) * normal, transparent_color);
+                }
+
+                for i in 0..cast_u32(segment_count) {
+                    let a = index_base + 4 * i;
+                    let b = index_base + 4 * ((i + 1) % cast_u32(point_count));
+
+                    for j in 0..3 {
+                        index_count += self.push_quad_indices(a + j, b + j, b + j + 1, a + j + 1);
+                    }
+                }
+            }
+            None => {
+                let (color, _) = self.fringe_colors(color);
+                let half_thickness = 0.5 * thickness;
+
+                for i in 0..point_count {
+                    let point = self.path[i];
+                    let normal = point_normal(&self.path, i);
+
+                    self.push_vertex(point + half_thickness * normal, color);
+                    self.push_vertex(point - half_thickness * normal, color);
+                }
+
+                for i in 0..cast_u32(segment_count) {
+                    let a = index_base + 2 * i;
+                    let b = index_base + 2 * ((i + 1) % cast_u32(point_count));
+
+                    index_count += self.push_quad_indices(a, b, b + 1, a + 1);
+                }
+            }
+        }
+
+        let bounds = self.vertex_bounds(index_base);
+        self.push_command(
+            bounds,
+            index_base,
+            index_count,
+            scissor_rect,
+            texture_id,
+            Sampling::LINEAR,
+        );
+    }
+
+    /// Inserts a callback command, so that the renderer can run its own
+    /// drawing at this point in the draw order (see [`CommandKind::CALLBACK`]).
+    /// Nothing drawn before or after is merged with it.
+    pub fn draw_callback(&mut self, user_id: u64, scissor_rect: Rect) {
+        self.u16_valid = false;
+        self.unmerged_command_count += 1;
+        self.commands.push(Command {
+            scissor_rect,
+            texture_id: 0,
+            index_count: 0,
+            base_vertex: 0,
+            vertex_offset: cast_u32(self.vertices.len()),
+            vertex_count: 0,
+            kind: CommandKind::CALLBACK,
+            sampling: Sampling::LINEAR,
+            callback_user_id: user_id,
+        });
+    }
+
+    // Returns the packed color and its fully transparent counterpart for the
+    // outer edge of anti-aliasing fringes.
+    fn fringe_colors(&self, color: u32) -> (u32, u32) {
+        let transparent_color = color & 0xffffff00;
+        (self.pack_color(color), self.pack_color(transparent_color))
+    }
+
+    // Converts a color given in sRGB to what goes into vertices. This is the
+    // single place where the output color space and alpha mode are applied.
+    fn pack_color(&self, color: u32) -> u32 {
+        let color = if self.linear_color_output {
+            linearize_color(color)
+        } else {
+            color
+        };
+
+        if self.premultiplied_alpha {
+            premultiply_alpha(color)
+        } else {
+            color
+        }
+    }
+
+    fn push_vertex(&mut self, position: Vec2, color: u32) {
+        self.vertices.push(Vertex {
+            position: [position.x, position.y],
+            tex_coord: [0.0, 0.0],
+            color,
+        });
+    }
+
+    // Pushes two triangles for the quad a, b, c, d and returns the number of
+    // indices pushed.
+    fn push_quad_indices(&mut self, a: u32, b: u32, c: u32, d: u32) -> u32 {
+        self.indices.push(a);
+        self.indices.push(b);
+        self.indices.push(c);
+        self.indices.push(c);
+        self.indices.push(d);
+        self.indices.push(a);
+
+        6
+    }
+
+    fn vertex_bounds(&self, index_base: u32) -> Rect {
+        vertices_bounds(&self.vertices[cast_usize(index_base)..])
+    }
+
+    // NB: Draws only ever reference the vertices they pushed, which are
+    // appended after the vertices of previous draws, so the vertex range of a
+    // merged command always ends at the end of the vertex buffer.
+    fn push_command(
+        &mut self,
+        bounds: Rect,
+        index_base: u32,
+        index_count: u32,
+        scissor_rect: Rect,
+        texture_id: u64,
+        sampling: Sampling,
+    ) {
+        let after_callback = self
+            .commands
+            .last()
+            .map_or(false, |command| command.kind == CommandKind::CALLBACK);
+
+        if self.commands.is_empty()
+            || after_callback
+            || self.last_scissor_rect != scissor_rect
+            || self.last_texture_id != texture_id
+            || self.last_sampling != sampling
+        {
+            self.unmerged_command_count += 1;
+        }
+
+        self.last_scissor_rect = scissor_rect;
+        self.last_texture_id = texture_id;
+        self.last_sampling = sampling;
+
+        // Consecutive draws with the same texture are merged into the same
+        // command, if they have the same scissor rect. If the scissor rects
+        // differ, but neither clips what's drawn, it doesn't matter which one
+        // is used and we can merge too. This happens often, because most
+        // things are drawn with the font atlas texture, and most controls
+        // don't clip their contents, only different scissor rects.
+        let vertex_end = cast_u32(self.vertices.len());
         if let Some(ref mut last_command) = self.commands.last_mut() {
-            if last_command.scissor_rect == scissor_rect && last_command.texture_id == texture_id {
-                last_command.index_count += 6;
+            let scissor_rect_compatible = last_command.scissor_rect == scissor_rect
+                || (last_command.scissor_rect.contains_rect(bounds)
+                    && scissor_rect.contains_rect(bounds));
+
+            if !after_callback
+                && scissor_rect_compatible
+                && last_command.texture_id == texture_id
+                && last_command.sampling == sampling
+            {
+                last_command.index_count += index_count;
+                last_command.vertex_count = vertex_end - last_command.vertex_offset;
             } else {
                 self.commands.push(Command {
                     scissor_rect,
                     texture_id,
-                    index_count: 6,
-                    _pad: 0,
+                    index_count,
+                    base_vertex: 0,
+                    vertex_offset: index_base,
+                    vertex_count: vertex_end - index_base,
+                    kind: CommandKind::DRAW,
+                    sampling,
+                    callback_user_id: 0,
                 });
             }
         } else {
             self.commands.push(Command {
                 scissor_rect,
                 texture_id,
-                index_count: 6,
-                _pad: 0,
+                index_count,
+                base_vertex: 0,
+                vertex_offset: index_base,
+                vertex_count: vertex_end - index_base,
+                kind: CommandKind::DRAW,
+                sampling,
+                callback_user_id: 0,
             });
         }
     }
 
+    // Remembers where the draw list ends, so that what is drawn after can be
+    // recorded with DrawList::record.
+    pub(crate) fn mark(&self) -> DrawListMark {
+        DrawListMark {
+            command_count: self.commands.len(),
+            last_command_index_count: self
+                .commands
+                .last()
+                .map_or(0, |command| command.index_count),
+            vertex_count: self.vertices.len(),
+            index_count: self.indices.len(),
+            rect_count: self.rect_count,
+            unmerged_command_count: self.unmerged_command_count,
+        }
+    }
+
+    // Records everything drawn since the mark into the recording, replacing
+    // its previous contents.
+    pub(crate) fn record(&self, mark: &DrawListMark, recording: &mut DrawListRecording<A>) {
+        let vertex_base = cast_u32(mark.vertex_count);
+
+        recording.vertices.clear();
+        recording
+            .vertices
+            .extend_from_slice(&self.vertices[mark.vertex_count..]);
+
+        recording.indices.clear();
+        recording.indices.extend(
+            self.indices[mark.index_count..]
+                .iter()
+                .map(|&index| index - vertex_base),
+        );
+
+        // The first thing drawn may have been merged into the last command
+        // before the mark. Only the part added after the mark is recorded.
+        let first_command_idx = match mark.command_count.checked_sub(1) {
+            Some(last_command_idx)
+                if self.commands[last_command_idx].index_count > mark.last_command_index_count =>
+            {
+                last_command_idx
+            }
+            _ => mark.command_count,
+        };
+
+        recording.runs.clear();
+        for (command_idx, command) in self.commands.iter().enumerate().skip(first_command_idx) {
+            let index_count = if command_idx < mark.command_count {
+                command.index_count - mark.last_command_index_count
+            } else {
+                command.index_count
+            };
+
+            let vertex_offset = u32::max(command.vertex_offset, vertex_base);
+            let vertex_end = command.vertex_offset + command.vertex_count;
+
+            recording.runs.push(DrawListRun {
+                scissor_rect: command.scissor_rect,
+                texture_id: command.texture_id,
+                bounds: vertices_bounds(
+                    &self.vertices[cast_usize(vertex_offset)..cast_usize(vertex_end)],
+                ),
+                index_count,
+                vertex_offset: vertex_offset - vertex_base,
+                vertex_count: vertex_end - vertex_offset,
+                kind: command.kind,
+                sampling: command.sampling,
+                callback_user_id: command.callback_user_id,
+            });
+        }
+
+        recording.rect_count = self.rect_count - mark.rect_count;
+        recording.unmerged_command_count =
+            self.unmerged_command_count - mark.unmerged_command_count;
+    }
+
+    // Appends the recorded geometry, as if it was drawn again. The first
+    // recorded command may merge with the last command, like it would have
+    // when drawing, but the rest never do, because they didn't the first
+    // time.
+    pub(crate) fn replay(&mut self, recording: &DrawListRecording<A>) {
+        let vertex_base = cast_u32(self.vertices.len());
+        self.u16_valid = false;
+
+        self.vertices.extend_from_slice(&recording.vertices);
+        self.indices
+            .extend(recording.indices.iter().map(|&index| index + vertex_base));
+
+        for (i, run) in recording.runs.iter().enumerate() {
+            let vertex_offset = vertex_base + run.vertex_offset;
+            let vertex_end = vertex_offset + run.vertex_count;
+
+            let merged = i == 0
+                && run.kind == CommandKind::DRAW
+                && match self.commands.last_mut() {
+                    Some(last_command) => {
+                        let scissor_rect_compatible = last_command.scissor_rect == run.scissor_rect
+                            || (last_command.scissor_rect.contains_rect(run.bounds)
+                                && run.scissor_rect.contains_rect(run.bounds));
+
+                        if scissor_rect_compatible
+                            && last_command.kind == CommandKind::DRAW
+                            && last_command.texture_id == run.texture_id
+                            && last_command.sampling == run.sampling
+                        {
+                            last_command.index_count += run.index_count;
+                            last_command.vertex_count = vertex_end - last_command.vertex_offset;
+
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                    None => false,
+                };
+
+            if !merged {
+                self.commands.push(Command {
+                    scissor_rect: run.scissor_rect,
+                    texture_id: run.texture_id,
+                    index_count: run.index_count,
+                    base_vertex: 0,
+                    vertex_offset,
+                    vertex_count: run.vertex_count,
+                    kind: run.kind,
+                    sampling: run.sampling,
+                    callback_user_id: run.callback_user_id,
+                });
+            }
+
+            self.last_scissor_rect = run.scissor_rect;
+            self.last_texture_id = run.texture_id;
+            self.last_sampling = run.sampling;
+        }
+
+        self.rect_count += recording.rect_count;
+        self.unmerged_command_count += recording.unmerged_command_count;
+    }
+
     pub fn clear(&mut self) {
         self.commands.clear();
         self.vertices.clear();
         self.indices.clear();
+
+        self.rect_count = 0;
+        self.unmerged_command_count = 0;
+
+        self.commands_u16.clear();
+        self.indices_u16.clear();
+        self.u16_valid = false;
+    }
+}
+
+// Where a draw list ended at some point. See DrawList::mark.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DrawListMark {
+    command_count: usize,
+    last_command_index_count: u32,
+    vertex_count: usize,
+    index_count: usize,
+    rect_count: usize,
+    unmerged_command_count: usize,
+}
+
+// Geometry recorded from a draw list, with indices relative to the first
+// recorded vertex. See DrawList::record and DrawList::replay.
+#[derive(Debug, Clone)]
+pub(crate) struct DrawListRecording<A: Allocator + Clone> {
+    vertices: Vec<Vertex, A>,
+    indices: Vec<u32, A>,
+    runs: Vec<DrawListRun, A>,
+    rect_count: usize,
+    unmerged_command_count: usize,
+}
+
+impl<A: Allocator + Clone> DrawListRecording<A> {
+    pub(crate) fn new_in(allocator: A) -> Self {
+        Self {
+            vertices: Vec::new_in(allocator.clone()),
+            indices: Vec::new_in(allocator.clone()),
+            runs: Vec::new_in(allocator),
+            rect_count: 0,
+            unmerged_command_count: 0,
+        }
+    }
+}
+
+// The recorded part of a command.
+#[derive(Debug, Clone, Copy)]
+struct DrawListRun {
+    scissor_rect: Rect,
+    texture_id: u64,
+    bounds: Rect,
+    index_count: u32,
+    vertex_offset: u32,
+    vertex_count: u32,
+    kind: CommandKind,
+    sampling: Sampling,
+    callback_user_id: u64,
+}
+
+fn vertices_bounds(vertices: &[Vertex]) -> Rect {
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+
+    for vertex in vertices {
+        let position = Vec2::from(vertex.position);
+        min = min.min(position);
+        max = max.max(position);
+    }
+
+    Rect::from_points(min, max)
+}
+
+// Enough segments for the polygon to deviate from the circle by less than a
+// third of a pixel.
+fn circle_segment_count(radius: f32) -> usize {
+    const MAX_ERROR: f32 = 0.3;
+
+    let segment_count = libm::ceilf(PI / libm::acosf(1.0 - f32::min(MAX_ERROR, radius) / radius));
+    usize::clamp(segment_count as usize, 12, 512)
+}
+
+fn angle_direction(angle: f32) -> Vec2 {
+    Vec2::new(libm::cosf(angle), libm::sinf(angle))
+}
+
+// The normal of the edge from a to b, pointing outwards of shapes whose paths
+// go in the direction of increasing angle.
+fn edge_normal(a: Vec2, b: Vec2) -> Vec2 {
+    let d = b - a;
+    let length = libm::sqrtf(d.length_squared());
+    if length > 0.0 {
+        Vec2::new(d.y, -d.x) / length
+    } else {
+        Vec2::ZERO
+    }
+}
+
+// Averages the normals of two edges meeting at a point, scaled so that
+// offsetting both edges by one along their normals meets at the point offset by
+// one along the result. Very sharp corners are limited, so that they don't
+// shoot off to infinity.
+fn miter_normal(normal_a: Vec2, normal_b: Vec2) -> Vec2 {
+    let normal = 0.5 * (normal_a + normal_b);
+    let length_squared = normal.length_squared();
+    if length_squared > 0.000001 {
+        normal * f32::min(1.0 / length_squared, 100.0)
+    } else {
+        normal
+    }
+}
+
+fn scale_alpha(color: u32, scale: f32) -> u32 {
+    let [r, g, b, a] = color.to_be_bytes();
+    let a = libm::roundf(f32::from(a) * scale.clamp(0.0, 1.0)) as u8;
+
+    u32::from_be_bytes([r, g, b, a])
+}
+
+fn premultiply_alpha(color: u32) -> u32 {
+    let [r, g, b, a] = color.to_be_bytes();
+    let premultiply = |c: u8| cast_u8((u32::from(c) * u32::from(a) + 127) / 255);
+
+    u32::from_be_bytes([premultiply(r), premultiply(g), premultiply(b), a])
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+
+    #[test]
+    fn test_draw_list_merges_commands_with_compatible_scissor_rects() {
+        let mut draw_list = DrawList::with_capacity_in(16, Global);
+
+        let scissor_a = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let scissor_b = Rect::new(10.0, 10.0, 50.0, 50.0);
+
+        // Unclipped by either scissor rect, all three can share a command.
+        draw_list.draw_rect(
+            Rect::new(20.0, 20.0, 10.0, 10.0),
+            Rect::ZERO,
+            0,
+            scissor_a,
+            1,
+        );
+        draw_list.draw_rect(
+            Rect::new(20.0, 20.0, 10.0, 10.0),
+            Rect::ZERO,
+            0,
+            scissor_b,
+            1,
+        );
+        draw_list.draw_rect(
+            Rect::new(30.0, 30.0, 10.0, 10.0),
+            Rect::ZERO,
+            0,
+            scissor_a,
+            1,
+        );
+
+        // Clipped by scissor_b, so it needs its own command.
+        draw_list.draw_rect(Rect::new(0.0, 0.0, 20.0, 20.0), Rect::ZERO, 0, scissor_b, 1);
+
+        // Different texture always needs its own command.
+        draw_list.draw_rect(Rect::new(0.0, 0.0, 20.0, 20.0), Rect::ZERO, 0, scissor_b, 2);
+
+        let stats = draw_list.stats();
+        assert_eq!(stats.rect_count, 5);
+        assert_eq!(stats.unmerged_command_count, 5);
+        assert_eq!(stats.command_count, 3);
+
+        let commands = draw_list.commands();
+        assert_eq!(commands[0].scissor_rect, scissor_a);
+        assert_eq!(commands[0].index_count, 18);
+        assert_eq!(commands[1].scissor_rect, scissor_b);
+        assert_eq!(commands[1].index_count, 6);
+        assert_eq!(commands[2].texture_id, 2);
+
+        // Each command covers exactly the vertices of its rects.
+        assert_eq!(
+            (commands[0].vertex_offset, commands[0].vertex_count),
+            (0, 12)
+        );
+        assert_eq!(
+            (commands[1].vertex_offset, commands[1].vertex_count),
+            (12, 4)
+        );
+        assert_eq!(
+            (commands[2].vertex_offset, commands[2].vertex_count),
+            (16, 4)
+        );
+
+        draw_list.clear();
+        assert_eq!(draw_list.stats(), DrawListStats::default());
+    }
+
+    #[test]
+    fn test_draw_list_u16_splits_commands_to_keep_indices_in_range() {
+        let mut draw_list = DrawList::with_capacity_in(16, Global);
+
+        let scissor_rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+
+        // Each rect has 4 vertices, so one more rect than fits into 65536
+        // vertices.
+        for _ in 0..16385 {
+            draw_list.draw_rect(rect, Rect::ZERO, 0, scissor_rect, 1);
+        }
+
+        assert_eq!(draw_list.commands().len(), 1);
+
+        let (commands, vertices, indices) = draw_list.u16();
+        assert_eq!(vertices.len(), 65540);
+        assert_eq!(indices.len(), 16385 * 6);
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].base_vertex, 0);
+        assert_eq!(commands[0].index_count, 16384 * 6);
+        assert_eq!(commands[0].vertex_offset, 0);
+        assert_eq!(commands[0].vertex_count, 65536);
+        assert_eq!(commands[1].base_vertex, 65536);
+        assert_eq!(commands[1].index_count, 6);
+        assert_eq!(commands[1].vertex_offset, 65536);
+        assert_eq!(commands[1].vertex_count, 4);
+        assert_eq!(&indices[indices.len() - 6..], &[0, 1, 2, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_draw_list_callback_splits_commands() {
+        let mut draw_list = DrawList::with_capacity_in(16, Global);
+        let scissor_rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let callback_scissor_rect = Rect::new(10.0, 10.0, 20.0, 20.0);
+
+        draw_list.draw_rect(Rect::ZERO, Rect::ZERO, 0, scissor_rect, 1);
+        draw_list.draw_callback(42, callback_scissor_rect);
+        draw_list.draw_rect(Rect::ZERO, Rect::ZERO, 0, scissor_rect, 1);
+
+        assert_eq!(draw_list.stats().unmerged_command_count, 3);
+
+        let commands = draw_list.commands();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0].kind, CommandKind::DRAW);
+        assert_eq!(commands[0].index_count, 6);
+        assert_eq!(commands[1].kind, CommandKind::CALLBACK);
+        assert_eq!(commands[1].callback_user_id, 42);
+        assert_eq!(commands[1].scissor_rect, callback_scissor_rect);
+        assert_eq!(commands[1].index_count, 0);
+        assert_eq!(commands[1].vertex_offset, 4);
+        assert_eq!(commands[2].kind, CommandKind::DRAW);
+        assert_eq!(commands[2].index_count, 6);
+        assert_eq!(commands[2].vertex_offset, 4);
+
+        let (commands_u16, _, indices_u16) = draw_list.u16();
+        assert_eq!(commands_u16.len(), 3);
+        assert_eq!(commands_u16[1].kind, CommandKind::CALLBACK);
+        assert_eq!(commands_u16[1].callback_user_id, 42);
+        assert_eq!(indices_u16.len(), 12);
+    }
+
+    #[test]
+    fn test_draw_list_premultiplies_vertex_colors() {
+        let mut draw_list = DrawList::with_capacity_in(16, Global);
+        let scissor_rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+
+        draw_list.draw_rect(Rect::ZERO, Rect::ZERO, 0xff804080, scissor_rect, 1);
+        draw_list.set_premultiplied_alpha(true);
+        draw_list.draw_rect(Rect::ZERO, Rect::ZERO, 0xff804080, scissor_rect, 1);
+        draw_list.draw_rect(Rect::ZERO, Rect::ZERO, 0xffffffff, scissor_rect, 1);
+        draw_list.draw_rect(Rect::ZERO, Rect::ZERO, 0xffffff00, scissor_rect, 1);
+
+        let vertices = draw_list.vertices();
+        assert_eq!(vertices[0].color, 0xff804080);
+        assert_eq!(vertices[4].color, 0x80402080);
+        assert_eq!(vertices[8].color, 0xffffffff);
+        assert_eq!(vertices[12].color, 0x00000000);
+    }
+
+    #[test]
+    fn test_draw_list_corner_colors_follow_positions() {
+        let mut draw_list = DrawList::with_capacity_in(16, Global);
+        let scissor_rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let colors = [0x000000ff, 0x0000ffff, 0x00ff00ff, 0xff0000ff];
+
+        draw_list.draw_rect_with_corner_colors(
+            Rect::new(10.0, 20.0, 30.0, 40.0),
+            Rect::ZERO,
+            colors,
+            scissor_rect,
+            1,
+        );
+
+        let vertices = draw_list.vertices();
+        assert_eq!(vertices[0].position, [10.0, 60.0]);
+        assert_eq!(vertices[0].color, colors[3]);
+        assert_eq!(vertices[1].position, [40.0, 60.0]);
+        assert_eq!(vertices[1].color, colors[2]);
+        assert_eq!(vertices[2].position, [40.0, 20.0]);
+        assert_eq!(vertices[2].color, colors[1]);
+        assert_eq!(vertices[3].position, [10.0, 20.0]);
+        assert_eq!(vertices[3].color, colors[0]);
+    }
+
+    #[test]
+    fn test_draw_list_anti_aliasing_adds_transparent_fringe() {
+        let mut draw_list = DrawList::with_capacity_in(16, Global);
+        let scissor_rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let center = Vec2::new(50.0, 50.0);
+
+        draw_list.draw_circle(center, 10.0, 0xffffffff, scissor_rect, 1);
+        let point_count = draw_list.vertices().len();
+        assert_eq!(draw_list.indices().len(), 3 * (point_count - 2));
+
+        draw_list.clear();
+        draw_list.set_anti_aliasing_fringe(Some(1.0));
+        draw_list.draw_circle(center, 10.0, 0xffffffff, scissor_rect, 1);
+
+        let vertices = draw_list.vertices();
+        assert_eq!(vertices.len(), 2 * point_count);
+        assert_eq!(
+            draw_list.indices().len(),
+            3 * (point_count - 2) + 6 * point_count
+        );
+
+        for pair in vertices.chunks_exact(2) {
+            let inner = Vec2::from(pair[0].position) - center;
+            let outer = Vec2::from(pair[1].position) - center;
+
+            assert_eq!(pair[0].color, 0xffffffff);
+            assert_eq!(pair[1].color, 0xffffff00);
+            assert!(outer.length_squared() > inner.length_squared());
+        }
+
+        let commands = draw_list.commands();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].index_count, cast_u32(draw_list.indices().len()));
+    }
+
+    #[test]
+    fn test_draw_list_thin_anti_aliased_lines_fade_out() {
+        let mut draw_list = DrawList::with_capacity_in(16, Global);
+        let scissor_rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let a = Vec2::new(10.0, 10.0);
+        let b = Vec2::new(90.0, 10.0);
+
+        draw_list.draw_line(a, b, 2.0, 0xffffffff, scissor_rect, 1);
+        assert_eq!(draw_list.vertices().len(), 4);
+        assert_eq!(draw_list.indices().len(), 6);
+
+        draw_list.clear();
+        draw_list.set_anti_aliasing_fringe(Some(1.0));
+        draw_list.draw_line(a, b, 2.0, 0xffffffff, scissor_rect, 1);
+        draw_list.draw_line(a, b, 0.5, 0xffffffff, scissor_rect, 1);
+
+        let vertices = draw_list.vertices();
+        assert_eq!(vertices.len(), 16);
+        assert_eq!(draw_list.indices().len(), 36);
+        assert_eq!(vertices[1].color, 0xffffffff);
+        assert_eq!(vertices[9].color, 0xffffff80);
+        assert_eq!(vertices[8].color, 0xffffff00);
     }
 }
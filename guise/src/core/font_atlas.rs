@@ -1,22 +1,26 @@
+#[cfg(feature = "runtime_rasterize")]
 use alloc::vec;
 use alloc::vec::Vec;
 use core::alloc::Allocator;
+#[cfg(feature = "runtime_rasterize")]
 use core::char;
-use core::ops::{BitOr, BitOrAssign, RangeInclusive};
+use core::mem;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, RangeInclusive, Sub, SubAssign};
 
-use hashbrown::hash_map::{DefaultHashBuilder, Entry, HashMap};
+#[cfg(feature = "runtime_rasterize")]
+use hashbrown::hash_map::Entry;
+use hashbrown::hash_map::{DefaultHashBuilder, HashMap};
 
 use crate::convert::{cast_u16, cast_u32, cast_usize};
 use crate::core::math::Rect;
 
-// TODO(yan): @Portability @Speed @Memory @Bloat Have the user provide the font
-// atlas and font metrics, glyph metrics and kerning info (need a flat format
-// for that) and create companion library that can be invoked from a build
-// script.
+// NB: Rasterizing fonts with fontdue at runtime is optional. Atlases can also
+// be baked ahead of time, e.g. from a build script, into a flat binary format
+// and loaded with FontAtlas::from_baked_in. See BakedFontAtlasHeader.
 //
 // Because the font may not be densely defined (and in fact, unicode has
-// reserved gaps), the storage format also has to store a mapping from
-// characters to the glyph info storage location.
+// reserved gaps), the baked format also stores a mapping from characters to the
+// glyph info storage location.
 
 #[cfg(feature = "font_ibm_plex_mono")]
 pub static FONT_IBM_PLEX_MONO: &[u8] = include_bytes!("../../assets/IBMPlexMono-Regular.ttf");
@@ -43,6 +47,12 @@ impl UnicodeRangeFlags {
     pub const KATAKANA: Self = Self(0x40);
     pub const CJK_UNIFIED_IDEOGRAPHS: Self = Self(0x80);
 
+    pub const GREEK: Self = Self(0x100);
+    pub const CYRILLIC: Self = Self(0x200);
+    pub const GENERAL_PUNCTUATION: Self = Self(0x400);
+    pub const CURRENCY_SYMBOLS: Self = Self(0x800);
+    pub const ARROWS: Self = Self(0x1000);
+
     pub const ALL_LATIN: Self = Self::BASIC_LATIN
         | Self::LATIN_1_SUPPLEMENT
         | Self::LATIN_EXTENDED_A
@@ -53,7 +63,13 @@ impl UnicodeRangeFlags {
         | Self::KATAKANA
         | Self::CJK_UNIFIED_IDEOGRAPHS;
 
-    pub const ALL: Self = Self::ALL_LATIN | Self::ALL_JAPANESE;
+    pub const ALL: Self = Self::ALL_LATIN
+        | Self::ALL_JAPANESE
+        | Self::GREEK
+        | Self::CYRILLIC
+        | Self::GENERAL_PUNCTUATION
+        | Self::CURRENCY_SYMBOLS
+        | Self::ARROWS;
 
     const R_BASIC_LATIN: RangeInclusive<u32> = 0x00..=0x7f;
     const R_LATIN_1_SUPPLEMENT: RangeInclusive<u32> = 0x80..=0xff;
@@ -65,6 +81,12 @@ impl UnicodeRangeFlags {
     const R_KATAKANA: RangeInclusive<u32> = 0x30a0..=0x30ff;
     const R_CJK_UNIFIED_IDEOGRAPHS: RangeInclusive<u32> = 0x4e00..=0x9fff;
 
+    const R_GREEK: RangeInclusive<u32> = 0x0370..=0x03ff;
+    const R_CYRILLIC: RangeInclusive<u32> = 0x0400..=0x04ff;
+    const R_GENERAL_PUNCTUATION: RangeInclusive<u32> = 0x2000..=0x206f;
+    const R_CURRENCY_SYMBOLS: RangeInclusive<u32> = 0x20a0..=0x20cf;
+    const R_ARROWS: RangeInclusive<u32> = 0x2190..=0x21ff;
+
     pub fn bits(&self) -> u32 {
         self.0
     }
@@ -81,6 +103,10 @@ impl UnicodeRangeFlags {
         self.0 & other.0 != 0
     }
 
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
     pub fn codepoint_count(&self) -> u32 {
         let mut count: u32 = 0;
 
@@ -118,6 +144,26 @@ impl UnicodeRangeFlags {
                 1 + Self::R_CJK_UNIFIED_IDEOGRAPHS.end() - Self::R_CJK_UNIFIED_IDEOGRAPHS.start();
         }
 
+        if self.0 & Self::GREEK.0 != 0 {
+            count += 1 + Self::R_GREEK.end() - Self::R_GREEK.start();
+        }
+
+        if self.0 & Self::CYRILLIC.0 != 0 {
+            count += 1 + Self::R_CYRILLIC.end() - Self::R_CYRILLIC.start();
+        }
+
+        if self.0 & Self::GENERAL_PUNCTUATION.0 != 0 {
+            count += 1 + Self::R_GENERAL_PUNCTUATION.end() - Self::R_GENERAL_PUNCTUATION.start();
+        }
+
+        if self.0 & Self::CURRENCY_SYMBOLS.0 != 0 {
+            count += 1 + Self::R_CURRENCY_SYMBOLS.end() - Self::R_CURRENCY_SYMBOLS.start();
+        }
+
+        if self.0 & Self::ARROWS.0 != 0 {
+            count += 1 + Self::R_ARROWS.end() - Self::R_ARROWS.start();
+        }
+
         count
     }
 
@@ -143,6 +189,42 @@ impl BitOrAssign for UnicodeRangeFlags {
     }
 }
 
+impl const BitAnd for UnicodeRangeFlags {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl BitAndAssign for UnicodeRangeFlags {
+    fn bitand_assign(&mut self, other: Self) {
+        self.0 &= other.0;
+    }
+}
+
+impl Not for UnicodeRangeFlags {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(Self::ALL.0 & !self.0)
+    }
+}
+
+impl Sub for UnicodeRangeFlags {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl SubAssign for UnicodeRangeFlags {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
 pub struct CodepointRangesIter {
     next: usize,
     flags: UnicodeRangeFlags,
@@ -158,6 +240,11 @@ impl Iterator for CodepointRangesIter {
             (F::LATIN_1_SUPPLEMENT, F::R_LATIN_1_SUPPLEMENT),
             (F::LATIN_EXTENDED_A, F::R_LATIN_EXTENDED_A),
             (F::LATIN_EXTENDED_B, F::R_LATIN_EXTENDED_B),
+            (F::GREEK, F::R_GREEK),
+            (F::CYRILLIC, F::R_CYRILLIC),
+            (F::GENERAL_PUNCTUATION, F::R_GENERAL_PUNCTUATION),
+            (F::CURRENCY_SYMBOLS, F::R_CURRENCY_SYMBOLS),
+            (F::ARROWS, F::R_ARROWS),
             (
                 F::CJK_SYMBOLS_AND_PUNCTUATION,
                 F::R_CJK_SYMBOLS_AND_PUNCTUATION,
@@ -182,7 +269,9 @@ impl Iterator for CodepointRangesIter {
     }
 }
 
+#[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
 pub struct GlyphInfo {
     // Glyph advance with in logical pixels with subpixel precision.
     pub advance_width: f32,
@@ -193,25 +282,196 @@ pub struct GlyphInfo {
     pub atlas_rect: Rect,
 }
 
-// TODO(yan): Allocate everything in provided allocator. This is gated on moving
-// fontdue to build pipeline.
-pub struct FontAtlas<A: Allocator + Clone> {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMetrics {
+    // Distance from the baseline to the top of the line in logical pixels.
+    pub ascent: f32,
+    // Distance from the baseline to the bottom of the line in logical pixels.
+    // Typically negative.
+    pub descent: f32,
+    pub line_gap: f32,
+    pub new_line_size: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontAtlasPackingStats {
+    // Cells occupied by glyphs, including the opaque cell for solid fills.
+    pub used_cell_count: u32,
+    // Cells the atlas image has room for.
+    pub cell_count: u32,
+    // Glyphs that didn't fit the maximum image dimension. These are rendered
+    // as the missing glyph.
+    pub skipped_glyph_count: u32,
+}
+
+/// Header of a baked font atlas, as written by [`FontAtlas::to_baked`] and
+/// read by [`FontAtlas::from_baked_in`].
+///
+/// The header is followed by `glyph_count` [`GlyphInfo`]s, `codepoint_count`
+/// [`BakedCodepoint`]s sorted by codepoint, and finally the RGBA8 atlas image
+/// with straight alpha. All values are stored in native endianness, without
+/// any padding between the sections. If the SDF flag is set, the image contains
+/// signed distance fields. See [`FontAtlas::sdf`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+pub struct BakedFontAtlasHeader {
+    pub magic: [u8; 4],
+    pub version: u32,
+    pub flags: u32,
+    pub font_size: f32,
+    pub font_scale_factor: f32,
+    pub line_ascent: f32,
+    pub line_descent: f32,
+    pub line_gap: f32,
+    pub line_new_line_size: f32,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub glyph_count: u32,
+    pub codepoint_count: u32,
+    pub missing_glyph_info: GlyphInfo,
+    pub used_cell_count: u32,
+    pub cell_count: u32,
+    pub skipped_glyph_count: u32,
+}
+
+impl BakedFontAtlasHeader {
+    pub const MAGIC: [u8; 4] = *b"GFNT";
+    pub const VERSION: u32 = 2;
+
+    pub const FLAG_SDF: u32 = 0x01;
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+pub struct BakedCodepoint {
+    pub codepoint: u32,
+    // Index into the glyph infos following the header.
+    pub glyph_info_index: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BakedFontAtlasError {
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    // The data ends before all sections announced by the header are read.
+    UnexpectedEnd,
+    // A codepoint refers to a glyph info that doesn't exist.
+    InvalidGlyphInfoIndex,
+}
+
+/// Where a [`crate::Ui`] gets its font atlas from.
+//
+// NB: The lifetime only exists for the TTF bytes, which can only be rasterized
+// with runtime_rasterize.
+#[allow(clippy::large_enum_variant)]
+pub enum FontSource<#[cfg(feature = "runtime_rasterize")] 'a, A: Allocator + Clone> {
+    /// A TTF font, rasterized at runtime. See [`FontAtlas::new_in_with_ranges`].
+    #[cfg(feature = "runtime_rasterize")]
+    Ttf {
+        bytes: &'a [u8],
+        unicode_range_flags: UnicodeRangeFlags,
+        codepoint_ranges: &'a [RangeInclusive<u32>],
+        size: f32,
+        // Maximum window scale factor the rasterizer is prepared for. If
+        // displaying on a single monitor, that display's scale factor should
+        // be used. If there are multiple monitors with different scales, pick
+        // highest for sharpest looking fonts, or lower, if memory or speed is
+        // an issue.
+        rasterization_scale_factor: f32,
+        atlas_max_image_dimension: u16,
+    },
+    /// A font atlas created up front, e.g. one baked ahead of time and loaded
+    /// with [`FontAtlas::from_baked_in`].
+    Atlas(FontAtlas<A>),
+}
+
+#[cfg(feature = "runtime_rasterize")]
+impl<'a, A: Allocator + Clone> FontSource<'a, A> {
+    /// A TTF font with codepoints from the unicode range flags only, and an
+    /// atlas image of unbounded size.
+    pub fn ttf(
+        bytes: &'a [u8],
+        unicode_range_flags: UnicodeRangeFlags,
+        size: f32,
+        rasterization_scale_factor: f32,
+    ) -> Self {
+        Self::Ttf {
+            bytes,
+            unicode_range_flags,
+            codepoint_ranges: &[],
+            size,
+            rasterization_scale_factor,
+            atlas_max_image_dimension: u16::MAX,
+        }
+    }
+}
+
+// Kept around for atlases rasterized at runtime, so that they can be rebuilt.
+#[cfg(feature = "runtime_rasterize")]
+struct Rasterizer<A: Allocator + Clone> {
     font: fontdue::Font,
+    // Sorted, non-overlapping and non-adjacent.
+    codepoint_ranges: Vec<RangeInclusive<u32>, A>,
+    max_image_dimension: u16,
+    sdf: bool,
+}
+
+// TODO(yan): Allocate the image in the provided allocator too. This is gated on
+// not having to rasterize the glyphs before knowing the image size.
+pub struct FontAtlas<A: Allocator + Clone> {
+    #[cfg(feature = "runtime_rasterize")]
+    rasterizer: Option<Rasterizer<A>>,
     font_size: f32,
-    font_horizontal_line_metrics: fontdue::LineMetrics,
+    font_scale_factor: f32,
+    font_horizontal_line_metrics: LineMetrics,
+    sdf: bool,
     image: Vec<u8>,
     image_width: u16,
     image_height: u16,
-    glyph_index_to_info: HashMap<u16, GlyphInfo, DefaultHashBuilder, A>,
+    glyph_infos: Vec<GlyphInfo, A>,
+    codepoint_to_glyph_info_index: HashMap<u32, u32, DefaultHashBuilder, A>,
     missing_glyph_info: GlyphInfo,
+    packing_stats: FontAtlasPackingStats,
 }
 
+#[cfg(feature = "runtime_rasterize")]
 impl<A: Allocator + Clone> FontAtlas<A> {
+    /// Rasterizes the font into an atlas image no larger than
+    /// max_image_dimension in either direction. If the glyphs don't fit, the
+    /// ones for higher codepoints are skipped and rendered as the missing
+    /// glyph. See [`FontAtlas::packing_stats`].
     pub fn new_in(
         font_bytes: &[u8],
         unicode_range_flags: UnicodeRangeFlags,
         font_size: f32,
         font_scale_factor: f32,
+        max_image_dimension: u16,
+        allocator: A,
+    ) -> FontAtlas<A> {
+        Self::new_in_with_ranges(
+            font_bytes,
+            unicode_range_flags,
+            &[],
+            font_size,
+            font_scale_factor,
+            max_image_dimension,
+            allocator,
+        )
+    }
+
+    /// Like [`FontAtlas::new_in`], but also rasterizes codepoints from
+    /// arbitrary ranges, e.g. for characters not covered by any of the
+    /// [`UnicodeRangeFlags`]. The ranges may overlap with each other and with
+    /// the flags.
+    pub fn new_in_with_ranges(
+        font_bytes: &[u8],
+        unicode_range_flags: UnicodeRangeFlags,
+        codepoint_ranges: &[RangeInclusive<u32>],
+        font_size: f32,
+        font_scale_factor: f32,
+        max_image_dimension: u16,
         allocator: A,
     ) -> FontAtlas<A> {
         let font_size_scaled = font_size * font_scale_factor;
@@ -228,12 +488,57 @@ impl<A: Allocator + Clone> FontAtlas<A> {
         };
         let font = fontdue::Font::from_bytes(font_bytes, settings).unwrap();
 
+        let mut merged_codepoint_ranges = Vec::with_capacity_in(
+            UnicodeRangeFlags::ALL.codepoint_ranges_iter().count() + codepoint_ranges.len(),
+            allocator.clone(),
+        );
+        merged_codepoint_ranges.extend(unicode_range_flags.codepoint_ranges_iter());
+        merged_codepoint_ranges.extend_from_slice(codepoint_ranges);
+        merge_codepoint_ranges(&mut merged_codepoint_ranges);
+
+        Self::new_from_rasterizer_in(
+            Rasterizer {
+                font,
+                codepoint_ranges: merged_codepoint_ranges,
+                max_image_dimension,
+                sdf: false,
+            },
+            font_size,
+            font_scale_factor,
+            allocator,
+        )
+    }
+
+    fn new_from_rasterizer_in(
+        rasterizer: Rasterizer<A>,
+        font_size: f32,
+        font_scale_factor: f32,
+        allocator: A,
+    ) -> FontAtlas<A> {
+        let font = &rasterizer.font;
+        let codepoint_ranges = &rasterizer.codepoint_ranges;
+        let max_image_dimension = rasterizer.max_image_dimension;
+        let sdf = rasterizer.sdf;
+
+        let font_size_scaled = font_size * font_scale_factor;
+
         // Keep line metrics in logical pixels (w/o font_scale_factor applied) so
         // that all layout computation is in logical units, but rasterize the
         // atlas scaled for high DPI, if requested.
-        let font_horizontal_line_metrics = font.horizontal_line_metrics(font_size).unwrap();
+        let font_horizontal_line_metrics = {
+            let line_metrics = font.horizontal_line_metrics(font_size).unwrap();
+            LineMetrics {
+                ascent: line_metrics.ascent,
+                descent: line_metrics.descent,
+                line_gap: line_metrics.line_gap,
+                new_line_size: line_metrics.new_line_size,
+            }
+        };
 
-        let codepoint_count = unicode_range_flags.codepoint_count();
+        let codepoint_count: u32 = codepoint_ranges
+            .iter()
+            .map(|range| 1 + range.end() - range.start())
+            .sum();
         guise_log!("Generating font atlas from {} codepoints", codepoint_count);
 
         let mut max_atlas_glyph_width: u16 = 0;
@@ -243,13 +548,17 @@ impl<A: Allocator + Clone> FontAtlas<A> {
         // provides a simple allocator, like a bump allocator, allocating
         // temporary memory after permanent memory will allow it to be
         // reclaimed.
-        let mut glyph_index_to_info =
+        let mut glyph_infos = Vec::with_capacity_in(cast_usize(codepoint_count), allocator.clone());
+        let mut codepoint_to_glyph_info_index =
             HashMap::with_capacity_in(cast_usize(codepoint_count), allocator.clone());
         let mut glyph_index_to_rasterized =
             HashMap::with_capacity_in(cast_usize(codepoint_count), &allocator);
+        let mut glyph_index_to_glyph_info_index =
+            HashMap::with_capacity_in(cast_usize(codepoint_count), &allocator);
 
-        for c in unicode_range_flags
-            .codepoint_ranges_iter()
+        for c in codepoint_ranges
+            .iter()
+            .cloned()
             .flatten()
             .filter_map(char::from_u32)
         {
@@ -266,8 +575,24 @@ impl<A: Allocator + Clone> FontAtlas<A> {
                 let (metrics, image) = font.rasterize_indexed(glyph_index, font_size_scaled);
                 let unscaled_metrics = font.metrics_indexed(glyph_index, font_size);
 
-                let width = cast_u16(metrics.width);
-                let height = cast_u16(metrics.height);
+                // Empty glyphs, like space, have nothing to compute the
+                // distance to, and are kept empty.
+                let (image_width, image_height, image) =
+                    if sdf && metrics.width > 0 && metrics.height > 0 {
+                        let spread = usize::from(Self::SDF_SPREAD);
+                        let image = coverage_to_sdf(&image, metrics.width, metrics.height, spread);
+
+                        (
+                            metrics.width + 2 * spread,
+                            metrics.height + 2 * spread,
+                            image,
+                        )
+                    } else {
+                        (metrics.width, metrics.height, image)
+                    };
+
+                let width = cast_u16(image_width);
+                let height = cast_u16(image_height);
 
                 if width > max_atlas_glyph_width {
                     max_atlas_glyph_width = width;
@@ -276,7 +601,7 @@ impl<A: Allocator + Clone> FontAtlas<A> {
                     max_atlas_glyph_height = height;
                 }
 
-                vacant_entry.insert((metrics, unscaled_metrics, image));
+                vacant_entry.insert((image_width, image_height, unscaled_metrics, image));
             }
         }
 
@@ -286,9 +611,11 @@ impl<A: Allocator + Clone> FontAtlas<A> {
             atlas_cell_count,
             max_atlas_glyph_width,
             max_atlas_glyph_height,
+            max_image_dimension,
         );
         let atlas_grid_width = atlas_pixel_width / max_atlas_glyph_width;
-        let atlas_grid_height = atlas_cell_count / u32::from(atlas_grid_width) + 1;
+        let atlas_grid_height = atlas_pixel_height / max_atlas_glyph_height;
+        let atlas_grid_cell_count = u32::from(atlas_grid_width) * u32::from(atlas_grid_height);
 
         guise_log!(
             "Generating font atlas: {}x{} ({}x{})",
@@ -297,6 +624,14 @@ impl<A: Allocator + Clone> FontAtlas<A> {
             atlas_grid_width,
             atlas_grid_height,
         );
+        if atlas_cell_count > atlas_grid_cell_count {
+            guise_log!(
+                "Font atlas capped at {}x{}, skipping {} glyphs",
+                max_image_dimension,
+                max_image_dimension,
+                atlas_cell_count - atlas_grid_cell_count,
+            );
+        }
 
         let mut atlas_image =
             vec![0; usize::from(atlas_pixel_width) * usize::from(atlas_pixel_height) * 4];
@@ -316,67 +651,92 @@ impl<A: Allocator + Clone> FontAtlas<A> {
         }
 
         let mut cell_index = 1;
-        for c in unicode_range_flags
-            .codepoint_ranges_iter()
+        for c in codepoint_ranges
+            .iter()
+            .cloned()
             .flatten()
             .filter_map(char::from_u32)
         {
             let glyph_index = font.lookup_glyph_index(c);
 
-            if let Entry::Vacant(vacant_entry) = glyph_index_to_info.entry(glyph_index) {
-                let (metrics, unscaled_metrics, image) = &glyph_index_to_rasterized[&glyph_index];
-
-                let grid_x = cell_index % usize::from(atlas_grid_width);
-                let grid_y = cell_index / usize::from(atlas_grid_width);
-
-                let pixel_x = grid_x * usize::from(max_atlas_glyph_width);
-                let pixel_y = grid_y * usize::from(max_atlas_glyph_height);
-
-                // Blit glyph into font atlas. Fill RGB with white so that we
-                // don't bleed. This works, because the rendering backend is
-                // expected to multiply this with a color.
-                debug_assert!(usize::from(max_atlas_glyph_width) >= metrics.width);
-                debug_assert!(usize::from(max_atlas_glyph_height) >= metrics.height);
-                for src_pixel_y in 0..metrics.height {
-                    for src_pixel_x in 0..metrics.width {
-                        let dst_pixel_x = pixel_x + src_pixel_x;
-                        let dst_pixel_y = pixel_y + src_pixel_y;
-
-                        let src_index = src_pixel_x + src_pixel_y * metrics.width;
-                        let dst_index =
-                            (dst_pixel_x + dst_pixel_y * usize::from(atlas_pixel_width)) * 4;
-
-                        // TODO(yan): Casey put premultiplied alpha everywhere,
-                        // [a, a, a, a]. Should we as well?
-                        atlas_image[dst_index] = 255;
-                        atlas_image[dst_index + 1] = 255;
-                        atlas_image[dst_index + 2] = 255;
-                        atlas_image[dst_index + 3] = image[src_index];
+            let glyph_info_index = match glyph_index_to_glyph_info_index.entry(glyph_index) {
+                Entry::Occupied(occupied_entry) => *occupied_entry.get(),
+                // If the atlas is capped and full, the remaining glyphs are
+                // skipped. Codepoints mapping to already packed glyphs are
+                // still processed.
+                Entry::Vacant(_) if cell_index == cast_usize(atlas_grid_cell_count) => continue,
+                Entry::Vacant(vacant_entry) => {
+                    let &(image_width, image_height, unscaled_metrics, ref image) =
+                        &glyph_index_to_rasterized[&glyph_index];
+
+                    let grid_x = cell_index % usize::from(atlas_grid_width);
+                    let grid_y = cell_index / usize::from(atlas_grid_width);
+
+                    let pixel_x = grid_x * usize::from(max_atlas_glyph_width);
+                    let pixel_y = grid_y * usize::from(max_atlas_glyph_height);
+
+                    // Blit glyph into font atlas. Fill RGB with white so that
+                    // we don't bleed. This works, because the rendering backend
+                    // is expected to multiply this with a color.
+                    debug_assert!(usize::from(max_atlas_glyph_width) >= image_width);
+                    debug_assert!(usize::from(max_atlas_glyph_height) >= image_height);
+                    for src_pixel_y in 0..image_height {
+                        for src_pixel_x in 0..image_width {
+                            let dst_pixel_x = pixel_x + src_pixel_x;
+                            let dst_pixel_y = pixel_y + src_pixel_y;
+
+                            let src_index = src_pixel_x + src_pixel_y * image_width;
+                            let dst_index =
+                                (dst_pixel_x + dst_pixel_y * usize::from(atlas_pixel_width)) * 4;
+
+                            // NB: The atlas starts with straight alpha. See
+                            // FontAtlas::set_premultiplied_alpha.
+                            atlas_image[dst_index] = 255;
+                            atlas_image[dst_index + 1] = 255;
+                            atlas_image[dst_index + 2] = 255;
+                            atlas_image[dst_index + 3] = image[src_index];
+                        }
                     }
+
+                    let atlas_pixel_width = f32::from(atlas_pixel_width);
+                    let atlas_pixel_height = f32::from(atlas_pixel_height);
+
+                    // The distance field spreads outside of the glyph bounds,
+                    // so the glyph rect grows to match the atlas rect.
+                    let padding = if image_width > 0 && image_height > 0 && sdf {
+                        f32::from(Self::SDF_SPREAD) / font_scale_factor
+                    } else {
+                        0.0
+                    };
+
+                    let glyph_info_index = cast_u32(glyph_infos.len());
+                    glyph_infos.push(GlyphInfo {
+                        advance_width: unscaled_metrics.advance_width,
+                        rect: Rect::new(
+                            unscaled_metrics.bounds.xmin - padding,
+                            // NB: Flip Y
+                            -unscaled_metrics.bounds.height
+                                - unscaled_metrics.bounds.ymin
+                                - padding,
+                            unscaled_metrics.bounds.width + 2.0 * padding,
+                            unscaled_metrics.bounds.height + 2.0 * padding,
+                        ),
+                        atlas_rect: Rect::new(
+                            grid_x as f32 * f32::from(max_atlas_glyph_width) / atlas_pixel_width,
+                            grid_y as f32 * f32::from(max_atlas_glyph_height) / atlas_pixel_height,
+                            image_width as f32 / atlas_pixel_width,
+                            image_height as f32 / atlas_pixel_height,
+                        ),
+                    });
+                    vacant_entry.insert(glyph_info_index);
+
+                    cell_index += 1;
+
+                    glyph_info_index
                 }
+            };
 
-                let atlas_pixel_width = f32::from(atlas_pixel_width);
-                let atlas_pixel_height = f32::from(atlas_pixel_height);
-
-                vacant_entry.insert(GlyphInfo {
-                    advance_width: unscaled_metrics.advance_width,
-                    rect: Rect::new(
-                        unscaled_metrics.bounds.xmin,
-                        // NB: Flip Y
-                        -unscaled_metrics.bounds.height - unscaled_metrics.bounds.ymin,
-                        unscaled_metrics.bounds.width,
-                        unscaled_metrics.bounds.height,
-                    ),
-                    atlas_rect: Rect::new(
-                        grid_x as f32 * f32::from(max_atlas_glyph_width) / atlas_pixel_width,
-                        grid_y as f32 * f32::from(max_atlas_glyph_height) / atlas_pixel_height,
-                        metrics.width as f32 / atlas_pixel_width,
-                        metrics.height as f32 / atlas_pixel_height,
-                    ),
-                });
-
-                cell_index += 1;
-            }
+            codepoint_to_glyph_info_index.insert(u32::from(c), glyph_info_index);
         }
 
         let missing_glyph_info = {
@@ -400,21 +760,229 @@ impl<A: Allocator + Clone> FontAtlas<A> {
         };
 
         Self {
-            font,
+            rasterizer: Some(rasterizer),
             font_size,
+            font_scale_factor,
             font_horizontal_line_metrics,
+            sdf,
             image: atlas_image,
             image_width: atlas_pixel_width,
             image_height: atlas_pixel_height,
-            glyph_index_to_info,
+            glyph_infos,
+            codepoint_to_glyph_info_index,
             missing_glyph_info,
+            packing_stats: FontAtlasPackingStats {
+                used_cell_count: cast_u32(cell_index),
+                cell_count: atlas_grid_cell_count,
+                skipped_glyph_count: atlas_cell_count - cast_u32(cell_index),
+            },
         }
     }
 
+    /// Regenerates the atlas for a different font size or scale factor,
+    /// keeping the font and unicode ranges. The atlas image changes, so it
+    /// must be uploaded to the renderer again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the atlas was baked ahead of time, as it doesn't have the
+    /// font to rasterize from.
+    pub fn rebuild(&mut self, font_size: f32, font_scale_factor: f32) {
+        // NB: The font keeps the geometry subdivision scale it was parsed
+        // with. This is only visible for large size increases, and avoids
+        // having to keep the font bytes around.
+        let allocator = self.glyph_infos.allocator().clone();
+        let rasterizer = self
+            .rasterizer
+            .take()
+            .expect("Baked font atlases can't be rebuilt");
+
+        *self = Self::new_from_rasterizer_in(rasterizer, font_size, font_scale_factor, allocator);
+    }
+
+    /// Switches between rasterizing glyph coverage and signed distance fields,
+    /// and regenerates the atlas. See [`FontAtlas::sdf`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the atlas was baked ahead of time, as it doesn't have the
+    /// font to rasterize from.
+    pub fn set_sdf(&mut self, sdf: bool) {
+        let allocator = self.glyph_infos.allocator().clone();
+        let mut rasterizer = self
+            .rasterizer
+            .take()
+            .expect("Baked font atlases can't be rebuilt");
+        rasterizer.sdf = sdf;
+
+        *self = Self::new_from_rasterizer_in(
+            rasterizer,
+            self.font_size,
+            self.font_scale_factor,
+            allocator,
+        );
+    }
+}
+
+impl<A: Allocator + Clone> FontAtlas<A> {
+    /// How far the signed distance fields reach outside and inside of the
+    /// glyph outlines, in atlas pixels.
+    pub const SDF_SPREAD: u16 = 4;
+
+    /// Loads an atlas baked ahead of time with [`FontAtlas::to_baked`]. This
+    /// doesn't need the font rasterizer, so it works without the
+    /// `runtime_rasterize` feature. The bytes don't have to be aligned, e.g.
+    /// they can come from `include_bytes!`.
+    pub fn from_baked_in(bytes: &[u8], allocator: A) -> Result<FontAtlas<A>, BakedFontAtlasError> {
+        let mut reader = BakedReader { bytes };
+
+        let header: BakedFontAtlasHeader = reader.read()?;
+        if header.magic != BakedFontAtlasHeader::MAGIC {
+            return Err(BakedFontAtlasError::InvalidMagic);
+        }
+        if header.version != BakedFontAtlasHeader::VERSION {
+            return Err(BakedFontAtlasError::UnsupportedVersion(header.version));
+        }
+
+        let mut glyph_infos =
+            Vec::with_capacity_in(cast_usize(header.glyph_count), allocator.clone());
+        for _ in 0..header.glyph_count {
+            glyph_infos.push(reader.read::<GlyphInfo>()?);
+        }
+
+        let mut codepoint_to_glyph_info_index =
+            HashMap::with_capacity_in(cast_usize(header.codepoint_count), allocator);
+        for _ in 0..header.codepoint_count {
+            let codepoint: BakedCodepoint = reader.read()?;
+            if codepoint.glyph_info_index >= header.glyph_count {
+                return Err(BakedFontAtlasError::InvalidGlyphInfoIndex);
+            }
+
+            codepoint_to_glyph_info_index.insert(codepoint.codepoint, codepoint.glyph_info_index);
+        }
+
+        let image_width = cast_u16(header.image_width);
+        let image_height = cast_u16(header.image_height);
+        let image_len = usize::from(image_width) * usize::from(image_height) * 4;
+        let image = reader.read_bytes(image_len)?.to_vec();
+
+        Ok(Self {
+            #[cfg(feature = "runtime_rasterize")]
+            rasterizer: None,
+            font_size: header.font_size,
+            font_scale_factor: header.font_scale_factor,
+            font_horizontal_line_metrics: LineMetrics {
+                ascent: header.line_ascent,
+                descent: header.line_descent,
+                line_gap: header.line_gap,
+                new_line_size: header.line_new_line_size,
+            },
+            sdf: header.flags & BakedFontAtlasHeader::FLAG_SDF != 0,
+            image,
+            image_width,
+            image_height,
+            glyph_infos,
+            codepoint_to_glyph_info_index,
+            missing_glyph_info: header.missing_glyph_info,
+            packing_stats: FontAtlasPackingStats {
+                used_cell_count: header.used_cell_count,
+                cell_count: header.cell_count,
+                skipped_glyph_count: header.skipped_glyph_count,
+            },
+        })
+    }
+
+    /// Serializes the atlas into the format loaded by
+    /// [`FontAtlas::from_baked_in`], e.g. from a build script. The image is
+    /// stored as is, so bake before setting premultiplied alpha.
+    pub fn to_baked(&self) -> Vec<u8> {
+        let header = BakedFontAtlasHeader {
+            magic: BakedFontAtlasHeader::MAGIC,
+            version: BakedFontAtlasHeader::VERSION,
+            flags: if self.sdf {
+                BakedFontAtlasHeader::FLAG_SDF
+            } else {
+                0
+            },
+            font_size: self.font_size,
+            font_scale_factor: self.font_scale_factor,
+            line_ascent: self.font_horizontal_line_metrics.ascent,
+            line_descent: self.font_horizontal_line_metrics.descent,
+            line_gap: self.font_horizontal_line_metrics.line_gap,
+            line_new_line_size: self.font_horizontal_line_metrics.new_line_size,
+            image_width: u32::from(self.image_width),
+            image_height: u32::from(self.image_height),
+            glyph_count: cast_u32(self.glyph_infos.len()),
+            codepoint_count: cast_u32(self.codepoint_to_glyph_info_index.len()),
+            missing_glyph_info: self.missing_glyph_info,
+            used_cell_count: self.packing_stats.used_cell_count,
+            cell_count: self.packing_stats.cell_count,
+            skipped_glyph_count: self.packing_stats.skipped_glyph_count,
+        };
+
+        // Sort the codepoints, so that baking the same atlas always produces
+        // the same bytes.
+        let mut codepoints: Vec<BakedCodepoint> = self
+            .codepoint_to_glyph_info_index
+            .iter()
+            .map(|(&codepoint, &glyph_info_index)| BakedCodepoint {
+                codepoint,
+                glyph_info_index,
+            })
+            .collect();
+        codepoints.sort_unstable_by_key(|codepoint| codepoint.codepoint);
+
+        let mut bytes = Vec::with_capacity(
+            mem::size_of::<BakedFontAtlasHeader>()
+                + mem::size_of_val(&self.glyph_infos[..])
+                + mem::size_of_val(&codepoints[..])
+                + self.image.len(),
+        );
+        bytes.extend_from_slice(bytemuck::bytes_of(&header));
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.glyph_infos));
+        bytes.extend_from_slice(bytemuck::cast_slice(&codepoints));
+        bytes.extend_from_slice(&self.image);
+
+        bytes
+    }
+
     pub fn font_size(&self) -> f32 {
         self.font_size
     }
 
+    pub fn font_scale_factor(&self) -> f32 {
+        self.font_scale_factor
+    }
+
+    /// Whether the atlas contains signed distance fields instead of glyph
+    /// coverage. Distance fields keep glyph edges sharp when text is scaled up,
+    /// but need a different fragment shader for sampling the atlas:
+    ///
+    /// - The color channels of glyphs are white, regardless of premultiplied
+    ///   alpha. The alpha channel stores the distance from the glyph outline,
+    ///   where 0.5 is exactly on the outline, larger values are inside and
+    ///   smaller values outside. The distance changes by
+    ///   `0.5 / FontAtlas::SDF_SPREAD` per atlas pixel.
+    ///
+    /// - The shader computes coverage from the distance, e.g. with
+    ///   `smoothstep(0.5 - w, 0.5 + w, distance)`, where `w` is about half a
+    ///   screen pixel in distance units, e.g. `fwidth(distance) * 0.5`. The
+    ///   vertex color is then multiplied by the coverage, either just its alpha
+    ///   channel for straight alpha, or all channels for premultiplied alpha.
+    ///
+    /// - The opaque cell used for solid fills is fully inside, so the same
+    ///   shader works for all draws textured with the font atlas. Other
+    ///   textures must still be sampled as usual.
+    pub fn sdf(&self) -> bool {
+        self.sdf
+    }
+
+    /// How well the glyphs fill the atlas image, e.g. for tuning which
+    /// ranges to include, or the maximum image dimension.
+    pub fn packing_stats(&self) -> FontAtlasPackingStats {
+        self.packing_stats
+    }
+
     pub fn image_size(&self) -> (u16, u16) {
         (self.image_width, self.image_height)
     }
@@ -423,20 +991,37 @@ impl<A: Allocator + Clone> FontAtlas<A> {
         &self.image
     }
 
-    pub fn font_horizontal_line_metrics(&self) -> fontdue::LineMetrics {
+    /// Converts the atlas image between straight and premultiplied alpha. The
+    /// color channels are recomputed from the alpha channel only, so this can
+    /// be toggled back and forth.
+    pub(crate) fn set_premultiplied_alpha(&mut self, premultiplied_alpha: bool) {
+        // Distance fields are premultiplied in the shader, after computing
+        // coverage.
+        if self.sdf {
+            return;
+        }
+
+        for pixel in self.image.chunks_exact_mut(4) {
+            let rgb = if premultiplied_alpha { pixel[3] } else { 255 };
+
+            pixel[0] = rgb;
+            pixel[1] = rgb;
+            pixel[2] = rgb;
+        }
+    }
+
+    pub fn font_horizontal_line_metrics(&self) -> LineMetrics {
         self.font_horizontal_line_metrics
     }
 
     pub fn glyph_info(&self, c: char) -> GlyphInfo {
-        // This has two tiers of failure. If fontdue doesn't recognize the
-        // glyph, it returns the index for the missing character. However, there
-        // might not be a glyph info for the missing character, and we fabricate
-        // one if it doesn't exist.
-        let glyph_index = self.font.lookup_glyph_index(c);
-        if let Some(glyph_info) = self.glyph_index_to_info.get(&glyph_index) {
-            *glyph_info
-        } else {
-            self.missing_glyph_info()
+        // Codepoints outside of the atlas ranges, or ones skipped because the
+        // atlas was full, don't have a glyph info, and we fabricate one.
+        // Codepoints within the ranges that the font doesn't have a glyph for
+        // use the font's default glyph.
+        match self.codepoint_to_glyph_info_index.get(&u32::from(c)) {
+            Some(&glyph_info_index) => self.glyph_infos[cast_usize(glyph_info_index)],
+            None => self.missing_glyph_info(),
         }
     }
 
@@ -445,7 +1030,124 @@ impl<A: Allocator + Clone> FontAtlas<A> {
     }
 }
 
-fn find_atlas_image_size(cell_count: u32, cell_width: u16, cell_height: u16) -> (u16, u16) {
+struct BakedReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BakedReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], BakedFontAtlasError> {
+        if self.bytes.len() < len {
+            return Err(BakedFontAtlasError::UnexpectedEnd);
+        }
+
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(bytes)
+    }
+
+    fn read<T: bytemuck::Pod>(&mut self) -> Result<T, BakedFontAtlasError> {
+        let bytes = self.read_bytes(mem::size_of::<T>())?;
+        Ok(bytemuck::pod_read_unaligned(bytes))
+    }
+}
+
+#[cfg(feature = "runtime_rasterize")]
+// Computes a signed distance field from glyph coverage, padded by spread on
+// each side. Pixels with at least half coverage are inside. The distance to the
+// nearest pixel on the other side of the outline is searched for by brute
+// force, but only within the spread, which is small.
+fn coverage_to_sdf(coverage: &[u8], width: usize, height: usize, spread: usize) -> Vec<u8> {
+    let sdf_width = width + 2 * spread;
+    let sdf_height = height + 2 * spread;
+
+    let is_inside = |x: isize, y: isize| -> bool {
+        // Coordinates are in the padded image.
+        let x = x - spread as isize;
+        let y = y - spread as isize;
+        if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+            false
+        } else {
+            coverage[x as usize + y as usize * width] >= 128
+        }
+    };
+
+    let spread_f32 = spread as f32;
+    let mut sdf = vec![0; sdf_width * sdf_height];
+
+    for y in 0..sdf_height as isize {
+        for x in 0..sdf_width as isize {
+            let inside = is_inside(x, y);
+
+            let mut min_distance_squared = usize::MAX;
+            let radius = spread as isize;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if is_inside(x + dx, y + dy) != inside {
+                        let distance_squared = (dx * dx + dy * dy) as usize;
+                        if distance_squared < min_distance_squared {
+                            min_distance_squared = distance_squared;
+                        }
+                    }
+                }
+            }
+
+            // The outline lies halfway between the pixel centers on either
+            // side of it.
+            let distance = if min_distance_squared == usize::MAX {
+                spread_f32
+            } else {
+                libm::sqrtf(min_distance_squared as f32) - 0.5
+            };
+            let signed_distance = if inside { distance } else { -distance };
+            let value = 0.5 + 0.5 * signed_distance / spread_f32;
+
+            sdf[cast_usize(x) + cast_usize(y) * sdf_width] =
+                libm::roundf(value.clamp(0.0, 1.0) * 255.0) as u8;
+        }
+    }
+
+    sdf
+}
+
+#[cfg(feature = "runtime_rasterize")]
+// Sorts the ranges and merges the overlapping or adjacent ones, so that every
+// codepoint is present at most once. Empty ranges are removed.
+fn merge_codepoint_ranges<A: Allocator>(ranges: &mut Vec<RangeInclusive<u32>, A>) {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_unstable_by_key(|range| *range.start());
+
+    let mut merged_len = 0;
+    for i in 0..ranges.len() {
+        let range = ranges[i].clone();
+        if merged_len > 0 {
+            let last = &mut ranges[merged_len - 1];
+            if *range.start() <= last.end().saturating_add(1) {
+                if range.end() > last.end() {
+                    *last = *last.start()..=*range.end();
+                }
+
+                continue;
+            }
+        }
+
+        ranges[merged_len] = range;
+        merged_len += 1;
+    }
+
+    ranges.truncate(merged_len);
+}
+
+#[cfg(feature = "runtime_rasterize")]
+// Finds the smallest power of two sized image the cells fit in, but no larger
+// than max_dimension. If the cells don't fit, returns the maximum size, and the
+// caller must only pack as many cells as fit.
+fn find_atlas_image_size(
+    cell_count: u32,
+    cell_width: u16,
+    cell_height: u16,
+    max_dimension: u16,
+) -> (u16, u16) {
     fn evaluate(
         atlas_width: u16,
         atlas_height: u16,
@@ -464,12 +1166,15 @@ fn find_atlas_image_size(cell_count: u32, cell_width: u16, cell_height: u16) ->
         required_pixel_height <= u32::from(atlas_height)
     }
 
+    // NB: We need room for at least the opaque cell.
+    assert!(
+        max_dimension >= cell_width && max_dimension >= cell_height,
+        "Maximum font atlas dimension {max_dimension} is smaller than a glyph \
+         ({cell_width}x{cell_height})",
+    );
+
     let mut power_of_two_prev: u16 = 1;
-    let mut power_of_two: u16 = power_of_two_prev
-        .checked_add(1)
-        .unwrap()
-        .checked_next_power_of_two()
-        .unwrap();
+    let mut power_of_two: u16 = 2;
 
     while !evaluate(
         power_of_two,
@@ -478,12 +1183,13 @@ fn find_atlas_image_size(cell_count: u32, cell_width: u16, cell_height: u16) ->
         cell_width,
         cell_height,
     ) {
-        power_of_two_prev = power_of_two;
-        power_of_two = power_of_two
-            .checked_add(1)
-            .unwrap()
-            .checked_next_power_of_two()
-            .unwrap();
+        match power_of_two.checked_mul(2) {
+            Some(next) if next <= max_dimension => {
+                power_of_two_prev = power_of_two;
+                power_of_two = next;
+            }
+            _ => return (max_dimension, max_dimension),
+        }
     }
 
     if evaluate(
@@ -498,3 +1204,214 @@ fn find_atlas_image_size(cell_count: u32, cell_width: u16, cell_height: u16) ->
         (power_of_two, power_of_two)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "runtime_rasterize")]
+    use alloc::alloc::Global;
+    #[cfg(feature = "runtime_rasterize")]
+    use alloc::vec;
+
+    use super::*;
+    #[cfg(feature = "runtime_rasterize")]
+    use crate::fixtures::FONT;
+
+    #[test]
+    fn test_codepoint_count_matches_ranges() {
+        let flags = [
+            UnicodeRangeFlags::ALL_LATIN,
+            UnicodeRangeFlags::ALL_JAPANESE,
+            UnicodeRangeFlags::GREEK | UnicodeRangeFlags::ARROWS,
+            UnicodeRangeFlags::ALL,
+        ];
+
+        for flags in flags {
+            let count: u32 = flags
+                .codepoint_ranges_iter()
+                .map(|range| 1 + range.end() - range.start())
+                .sum();
+
+            assert_eq!(flags.codepoint_count(), count);
+        }
+    }
+
+    #[test]
+    fn test_codepoint_ranges_are_sorted_and_disjoint() {
+        let mut prev_end = None;
+        for range in UnicodeRangeFlags::ALL.codepoint_ranges_iter() {
+            if let Some(prev_end) = prev_end {
+                assert!(*range.start() > prev_end);
+            }
+
+            prev_end = Some(*range.end());
+        }
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_merge_codepoint_ranges() {
+        let mut ranges = vec![
+            0x30..=0x40,
+            0x00..=0x10,
+            0x35..=0x38,
+            0x11..=0x20,
+            0x3f..=0x45,
+        ];
+        merge_codepoint_ranges(&mut ranges);
+
+        assert_eq!(ranges, [0x00..=0x20, 0x30..=0x45]);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_find_atlas_image_size_respects_max_dimension() {
+        assert_eq!(find_atlas_image_size(100, 10, 20, u16::MAX), (256, 128));
+        assert_eq!(find_atlas_image_size(100, 10, 20, 200), (200, 200));
+        assert_eq!(find_atlas_image_size(1, 10, 20, 20), (20, 20));
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_capped_atlas_skips_glyphs() {
+        let atlas = FontAtlas::new_in(FONT, UnicodeRangeFlags::ALL_LATIN, 13.0, 1.0, 64, Global);
+        let stats = atlas.packing_stats();
+
+        assert_eq!(atlas.image_size(), (64, 64));
+        assert_eq!(stats.used_cell_count, stats.cell_count);
+        assert!(stats.skipped_glyph_count > 0);
+
+        // Glyphs are packed in codepoint order, so the last ones are skipped.
+        assert_eq!(atlas.glyph_info('\u{24f}'), atlas.missing_glyph_info());
+        assert_ne!(atlas.glyph_info('!'), atlas.missing_glyph_info());
+
+        let atlas = FontAtlas::new_in(
+            FONT,
+            UnicodeRangeFlags::ALL_LATIN,
+            13.0,
+            1.0,
+            u16::MAX,
+            Global,
+        );
+        assert_eq!(atlas.packing_stats().skipped_glyph_count, 0);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_baked_atlas_round_trip() {
+        let atlas = FontAtlas::new_in(
+            FONT,
+            UnicodeRangeFlags::BASIC_LATIN | UnicodeRangeFlags::GREEK,
+            13.0,
+            2.0,
+            u16::MAX,
+            Global,
+        );
+        let baked = atlas.to_baked();
+
+        // Make sure loading doesn't depend on alignment.
+        let mut unaligned = vec![0];
+        unaligned.extend_from_slice(&baked);
+        let loaded = FontAtlas::from_baked_in(&unaligned[1..], Global).unwrap();
+
+        assert_eq!(loaded.font_size(), atlas.font_size());
+        assert_eq!(loaded.font_scale_factor(), atlas.font_scale_factor());
+        assert_eq!(
+            loaded.font_horizontal_line_metrics(),
+            atlas.font_horizontal_line_metrics(),
+        );
+        assert_eq!(loaded.image_size(), atlas.image_size());
+        assert_eq!(loaded.image_rgba8_unorm(), atlas.image_rgba8_unorm());
+        assert_eq!(loaded.packing_stats(), atlas.packing_stats());
+        assert_eq!(loaded.missing_glyph_info(), atlas.missing_glyph_info());
+        for c in ['a', 'Z', '~', '\u{3b1}', '\u{100}'] {
+            assert_eq!(loaded.glyph_info(c), atlas.glyph_info(c));
+        }
+
+        assert_eq!(loaded.to_baked(), baked);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_malformed_baked_atlas() {
+        let atlas = FontAtlas::new_in(
+            FONT,
+            UnicodeRangeFlags::BASIC_LATIN,
+            13.0,
+            1.0,
+            u16::MAX,
+            Global,
+        );
+        let mut baked = atlas.to_baked();
+
+        assert_eq!(
+            FontAtlas::from_baked_in(&baked[..baked.len() - 1], Global).err(),
+            Some(BakedFontAtlasError::UnexpectedEnd),
+        );
+
+        baked[0] = b'X';
+        assert_eq!(
+            FontAtlas::from_baked_in(&baked, Global).err(),
+            Some(BakedFontAtlasError::InvalidMagic),
+        );
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_coverage_to_sdf() {
+        #[rustfmt::skip]
+        let coverage = [
+            0, 0, 0,
+            0, 255, 0,
+            0, 0, 0,
+        ];
+        let sdf = coverage_to_sdf(&coverage, 3, 3, 2);
+        let value = |x: usize, y: usize| sdf[x + y * 7];
+
+        // The covered pixel is inside, its neighbors are outside and the
+        // distance decreases away from it.
+        assert!(value(3, 3) > 128);
+        assert!(value(2, 3) < 128);
+        assert!(value(1, 3) < value(2, 3));
+        assert_eq!(value(0, 0), 0);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_sdf_atlas() {
+        let mut atlas = FontAtlas::new_in(
+            FONT,
+            UnicodeRangeFlags::BASIC_LATIN,
+            13.0,
+            1.0,
+            u16::MAX,
+            Global,
+        );
+        let glyph_info = atlas.glyph_info('A');
+        let space_glyph_info = atlas.glyph_info(' ');
+
+        atlas.set_sdf(true);
+        assert!(atlas.sdf());
+
+        // Glyph rects grow by the spread, but advances don't change.
+        let spread = f32::from(FontAtlas::<Global>::SDF_SPREAD);
+        let sdf_glyph_info = atlas.glyph_info('A');
+        assert_eq!(sdf_glyph_info.advance_width, glyph_info.advance_width);
+        assert_eq!(
+            sdf_glyph_info.rect.min_point().x,
+            glyph_info.rect.min_point().x - spread
+        );
+        assert_eq!(
+            sdf_glyph_info.rect.size().x,
+            glyph_info.rect.size().x + 2.0 * spread
+        );
+        assert_eq!(atlas.glyph_info(' ').rect, space_glyph_info.rect);
+
+        // Distance fields are premultiplied in the shader.
+        let image = atlas.image_rgba8_unorm().to_vec();
+        atlas.set_premultiplied_alpha(true);
+        assert_eq!(atlas.image_rgba8_unorm(), image);
+
+        let loaded = FontAtlas::from_baked_in(&atlas.to_baked(), Global).unwrap();
+        assert!(loaded.sdf());
+    }
+}
@@ -0,0 +1,183 @@
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+
+// Matches at the start of a word, e.g. after a space or an underscore.
+const BONUS_BOUNDARY: i32 = 8;
+// Matches at a lowercase to uppercase transition, or at the start of a number.
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+// The first character of the needle matching at a boundary is a strong signal
+// of what the user is looking for.
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// Matches `needle` against `haystack` as a case-insensitive subsequence, i.e.
+/// all characters of the needle must appear in the haystack in order, but not
+/// necessarily next to each other. Returns [`None`] if the needle doesn't
+/// match. Otherwise returns a score, higher for better matches, e.g. for
+/// characters matching at word boundaries or right after each other. An empty
+/// needle matches everything with a score of zero.
+///
+/// Doesn't allocate. Apps can use this to filter and sort their own lists the
+/// same way the widgets do, e.g. the autocomplete of text inputs.
+pub fn fuzzy_match(needle: &str, haystack: &str) -> Option<i32> {
+    fuzzy_match_with_positions(needle, haystack, |_| {})
+}
+
+/// Like [`fuzzy_match`], but also reports the byte index in `haystack` of each
+/// matched character in ascending order, e.g. to highlight them. The
+/// positions are only reported if the needle matches.
+pub fn fuzzy_match_with_positions<F>(needle: &str, haystack: &str, mut position: F) -> Option<i32>
+where
+    F: FnMut(usize),
+{
+    if needle.is_empty() {
+        return Some(0);
+    }
+
+    // NB: Instead of searching all ways the needle can match for the best
+    // score, which needs memory proportional to the needle and haystack
+    // lengths, we find the first place where the needle matches, and then
+    // shrink it from the back to the shortest window containing the needle.
+    // This mostly finds the best match in practice.
+
+    let mut needle_chars = needle.chars().map(fold_case);
+    let mut needle_char = needle_chars.next();
+    let mut end = None;
+    for (i, c) in haystack.char_indices() {
+        if needle_char == Some(fold_case(c)) {
+            needle_char = needle_chars.next();
+            if needle_char.is_none() {
+                end = Some(i + c.len_utf8());
+                break;
+            }
+        }
+    }
+
+    let end = end?;
+
+    let mut needle_chars = needle.chars().rev().map(fold_case);
+    let mut needle_char = needle_chars.next();
+    let mut start = 0;
+    for (i, c) in haystack[..end].char_indices().rev() {
+        if needle_char == Some(fold_case(c)) {
+            needle_char = needle_chars.next();
+            if needle_char.is_none() {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    let mut needle_chars = needle.chars().map(fold_case);
+    let mut needle_char = needle_chars.next();
+    let mut prev_char = haystack[..start].chars().next_back();
+    let mut prev_matched = false;
+    let mut in_gap = false;
+    let mut first = true;
+    let mut score = 0;
+
+    for (i, c) in haystack[start..end].char_indices() {
+        if needle_char == Some(fold_case(c)) {
+            let mut bonus = boundary_bonus(prev_char, c);
+            if prev_matched {
+                bonus = i32::max(bonus, BONUS_CONSECUTIVE);
+            }
+            if first {
+                bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+                first = false;
+            }
+
+            score += SCORE_MATCH + bonus;
+            position(start + i);
+
+            needle_char = needle_chars.next();
+            prev_matched = true;
+            in_gap = false;
+        } else {
+            score += if in_gap {
+                SCORE_GAP_EXTENSION
+            } else {
+                SCORE_GAP_START
+            };
+
+            prev_matched = false;
+            in_gap = true;
+        }
+
+        prev_char = Some(c);
+    }
+
+    Some(score)
+}
+
+fn boundary_bonus(prev: Option<char>, c: char) -> i32 {
+    match prev {
+        None => BONUS_BOUNDARY,
+        Some(prev) if !prev.is_alphanumeric() && c.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(prev) if prev.is_lowercase() && c.is_uppercase() => BONUS_CAMEL,
+        Some(prev) if !prev.is_numeric() && c.is_numeric() => BONUS_CAMEL,
+        Some(_) => 0,
+    }
+}
+
+// Simple case folding: characters that lowercase to more than one character,
+// e.g. 'İ', are compared as they are.
+fn fold_case(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+
+    let mut lowercase = c.to_lowercase();
+    match (lowercase.next(), lowercase.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayVec;
+
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match_ignores_case_and_gaps() {
+        assert!(fuzzy_match("ap", "Apple").is_some());
+        assert!(fuzzy_match("APL", "apple").is_some());
+        assert!(fuzzy_match("žlu", "ŽLUŤOUČKÝ").is_some());
+        assert!(fuzzy_match("ae", "apple").is_some());
+        assert!(fuzzy_match("ea", "apple").is_none());
+        assert!(fuzzy_match("apples", "apple").is_none());
+        assert_eq!(fuzzy_match("", "apple"), Some(0));
+    }
+
+    #[test]
+    fn test_fuzzy_match_prefers_boundaries_and_consecutive_matches() {
+        let prefix = fuzzy_match("app", "apple").unwrap();
+        let scattered = fuzzy_match("app", "a pile of paper").unwrap();
+        assert!(prefix > scattered);
+
+        let boundary = fuzzy_match("fb", "foo_bar").unwrap();
+        let middle = fuzzy_match("fb", "fooobar").unwrap();
+        assert!(boundary > middle);
+
+        let camel = fuzzy_match("fb", "fooBar").unwrap();
+        assert!(camel > middle);
+    }
+
+    #[test]
+    fn test_fuzzy_match_reports_positions_of_shortest_window() {
+        let mut positions: ArrayVec<usize, 8> = ArrayVec::new();
+        let score = fuzzy_match_with_positions("ab", "a xab", |i| positions.push(i));
+
+        assert!(score.is_some());
+        assert_eq!(positions.as_slice(), &[3, 4]);
+
+        positions.clear();
+        let score = fuzzy_match_with_positions("ba", "a xab", |i| positions.push(i));
+
+        assert!(score.is_none());
+        assert!(positions.is_empty());
+    }
+}
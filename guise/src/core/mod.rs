@@ -1,10 +1,14 @@
+mod color;
 mod draw_list;
 mod font_atlas;
+mod fuzzy;
 mod math;
 mod string;
+mod theme;
 mod ui;
 
-pub use self::draw_list::{Command, Vertex};
+pub use self::color::{color_lerp, color_multiply_alpha, Color};
+pub use self::draw_list::{Command, CommandKind, DrawListStats, Sampling, Vertex};
 #[cfg(feature = "font_ibm_plex_mono")]
 pub use self::font_atlas::FONT_IBM_PLEX_MONO;
 #[cfg(feature = "font_ibm_plex_sans_jp")]
@@ -15,7 +19,50 @@ pub use self::font_atlas::FONT_LIBERATION_MONO;
 pub use self::font_atlas::FONT_PROGGY_CLEAN;
 #[cfg(feature = "font_roboto")]
 pub use self::font_atlas::FONT_ROBOTO;
-pub use self::font_atlas::{FontAtlas, UnicodeRangeFlags};
+pub use self::font_atlas::{
+    BakedCodepoint,
+    BakedFontAtlasError,
+    BakedFontAtlasHeader,
+    FontAtlas,
+    FontAtlasPackingStats,
+    FontSource,
+    GlyphInfo,
+    LineMetrics,
+    UnicodeRangeFlags,
+};
+pub use self::fuzzy::{fuzzy_match, fuzzy_match_with_positions};
 pub use self::math::{Rect, Vec2};
 pub use self::string::{TextCapacityError, TextStorage, VecString};
-pub use self::ui::{Align, Ctrl, CtrlFlags, CtrlState, Frame, Inputs, Layout, Modifiers, Ui, Wrap};
+pub(crate) use self::theme::with_theme_fields;
+pub use self::theme::{StyleVar, Theme};
+#[cfg(any(test, feature = "testing"))]
+pub(crate) use self::ui::mix_id;
+pub(crate) use self::ui::{char_advance_width, split_text_lines, text_width, TextLine};
+pub use self::ui::{
+    disabled_color,
+    Align,
+    AspectFit,
+    Ctrl,
+    CtrlFlags,
+    CtrlLayout,
+    CtrlState,
+    CursorIcon,
+    DebugCtrl,
+    DebugCtrls,
+    Direction,
+    Easing,
+    Frame,
+    FrameStats,
+    ImageOptions,
+    InputEvent,
+    Inputs,
+    KeyRepeat,
+    Layout,
+    Modifiers,
+    Shortcut,
+    ToastKind,
+    TouchOptions,
+    Ui,
+    UiCore,
+    Wrap,
+};
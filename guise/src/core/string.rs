@@ -294,7 +294,9 @@ impl TextStorage for String {
             let dst = index + insert_byte_count;
 
             v.copy_within(range, dst);
-            self.truncate(new_len);
+            // NB: Not String::truncate, because that checks for a char boundary
+            // in the leftovers of the moved tail past new_len.
+            v.truncate(new_len);
         }
 
         Ok(())
@@ -387,7 +389,10 @@ impl<const N: usize> TextStorage for ArrayString<N> {
                 b.copy_within(range, dst);
             }
 
-            self.truncate(new_len);
+            // SAFETY: Everything up to new_len is valid UTF8 again. Not
+            // ArrayString::truncate, because that checks for a char boundary in
+            // the leftovers of the moved tail past new_len.
+            unsafe { self.set_len(new_len) };
         }
 
         Ok(())
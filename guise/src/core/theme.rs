@@ -0,0 +1,1143 @@
+use core::mem;
+
+use crate::core::color_lerp;
+
+// TODO(yan): Split theme into themes for each component, so that when the user
+// wants to edit something in the theme, they don't have to copy the whole
+// struct.
+
+// TODO(yan): Values for margin, border and padding could be split into
+// horizontal and vertical, or even per rect side, but only do that if it is
+// actually useful as it otherwise takes a lot of space in the Ctrl struct.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub button_border_color: u32,
+    pub button_border_color_hovered: u32,
+    pub button_border_color_active: u32,
+    pub button_background_color: u32,
+    pub button_background_color_hovered: u32,
+    pub button_background_color_active: u32,
+    pub button_text_color: u32,
+    pub button_text_color_hovered: u32,
+    pub button_text_color_active: u32,
+    pub button_border_color_toggled: u32,
+    pub button_background_color_toggled: u32,
+    pub button_text_color_toggled: u32,
+    pub button_border_color_disabled: u32,
+    pub button_background_color_disabled: u32,
+    pub button_text_color_disabled: u32,
+    pub button_height: f32,
+    pub button_margin: f32,
+    pub button_border: f32,
+
+    pub image_button_border_color: u32,
+    pub image_button_border_color_hovered: u32,
+    pub image_button_border_color_active: u32,
+    pub image_button_background_color: u32,
+    pub image_button_background_color_hovered: u32,
+    pub image_button_background_color_active: u32,
+    pub image_button_border_color_disabled: u32,
+    pub image_button_background_color_disabled: u32,
+    pub image_button_image_color_disabled: u32,
+    pub image_button_width: f32,
+    pub image_button_height: f32,
+    pub image_button_margin: f32,
+    pub image_button_border: f32,
+
+    pub checkbox_handle_color: u32,
+    pub checkbox_handle_color_hovered: u32,
+    pub checkbox_handle_color_active: u32,
+    pub checkbox_text_color: u32,
+    pub checkbox_text_color_hovered: u32,
+    pub checkbox_text_color_active: u32,
+    pub checkbox_mark_color: u32,
+    pub checkbox_mark_color_indeterminate: u32,
+    pub checkbox_width: f32,
+    pub checkbox_height: f32,
+    pub checkbox_margin: f32,
+    pub checkbox_border: f32,
+
+    pub text_border_color: u32,
+    pub text_background_color: u32,
+    pub text_text_color: u32,
+    pub text_selection_color: u32,
+    pub text_margin: f32,
+    pub text_border: f32,
+    pub text_padding: f32,
+
+    pub text_tooltip_border_color: u32,
+    pub text_tooltip_background_color: u32,
+    pub text_tooltip_text_color: u32,
+    pub text_tooltip_border: f32,
+    pub text_tooltip_padding: f32,
+
+    pub tooltip_delay: f32,
+    pub tooltip_cursor_offset: f32,
+    pub tooltip_max_width: f32,
+
+    pub context_menu_border_color: u32,
+    pub context_menu_background_color: u32,
+    pub context_menu_width: f32,
+    pub context_menu_border: f32,
+    pub context_menu_padding: f32,
+
+    pub text_input_border_color: u32,
+    pub text_input_border_color_hovered: u32,
+    pub text_input_border_color_active: u32,
+    pub text_input_background_color: u32,
+    pub text_input_background_color_hovered: u32,
+    pub text_input_background_color_active: u32,
+    pub text_input_text_color: u32,
+    pub text_input_text_color_hovered: u32,
+    pub text_input_text_color_active: u32,
+    pub text_input_overlay_match_text_color: u32,
+    pub text_input_height: f32,
+    pub text_input_margin: f32,
+    pub text_input_border: f32,
+    pub text_input_overlay_max_height: f32,
+
+    pub float_slider_border_color: u32,
+    pub float_slider_border_color_hovered: u32,
+    pub float_slider_border_color_active: u32,
+    pub float_slider_background_color: u32,
+    pub float_slider_background_color_hovered: u32,
+    pub float_slider_background_color_active: u32,
+    pub float_slider_text_color: u32,
+    pub float_slider_text_color_hovered: u32,
+    pub float_slider_text_color_active: u32,
+    pub float_slider_height: f32,
+    pub float_slider_margin: f32,
+    pub float_slider_border: f32,
+
+    pub int_slider_border_color: u32,
+    pub int_slider_border_color_hovered: u32,
+    pub int_slider_border_color_active: u32,
+    pub int_slider_background_color: u32,
+    pub int_slider_background_color_hovered: u32,
+    pub int_slider_background_color_active: u32,
+    pub int_slider_text_color: u32,
+    pub int_slider_text_color_hovered: u32,
+    pub int_slider_text_color_active: u32,
+    pub int_slider_height: f32,
+    pub int_slider_margin: f32,
+    pub int_slider_border: f32,
+
+    pub dropdown_border_color: u32,
+    pub dropdown_border_color_hovered: u32,
+    pub dropdown_border_color_active: u32,
+    pub dropdown_background_color: u32,
+    pub dropdown_background_color_hovered: u32,
+    pub dropdown_background_color_active: u32,
+    pub dropdown_text_color: u32,
+    pub dropdown_text_color_hovered: u32,
+    pub dropdown_text_color_active: u32,
+    pub dropdown_height: f32,
+    pub dropdown_margin: f32,
+    pub dropdown_border: f32,
+    pub dropdown_overlay_max_height: f32,
+    pub dropdown_option_text_color_disabled: u32,
+
+    pub panel_border_color: u32,
+    pub panel_background_color: u32,
+    pub panel_margin: f32,
+    pub panel_border: f32,
+    pub panel_padding: f32,
+    pub panel_header_text_color: u32,
+    pub panel_header_background_color: u32,
+    pub panel_header_background_color_hovered: u32,
+    pub panel_header_height: f32,
+
+    pub window_border_color: u32,
+    pub window_border_color_hovered: u32,
+    pub window_background_color: u32,
+    pub window_background_color_hovered: u32,
+    pub window_border: f32,
+    pub window_padding: f32,
+
+    pub separator_color: u32,
+    pub separator_height: f32,
+    pub separator_margin: f32,
+    pub separator_label_color: u32,
+    pub separator_label_spacing: f32,
+
+    pub spinner_color: u32,
+    pub spinner_margin: f32,
+    // In turns per second.
+    pub spinner_speed: f32,
+
+    pub splitter_color: u32,
+    pub splitter_color_hovered: u32,
+    pub splitter_color_active: u32,
+    pub splitter_width: f32,
+
+    pub drag_ghost_border_color: u32,
+    pub drag_ghost_background_color: u32,
+    pub drag_ghost_border: f32,
+
+    pub toast_border_color: u32,
+    pub toast_background_color_info: u32,
+    pub toast_background_color_warn: u32,
+    pub toast_background_color_error: u32,
+    pub toast_text_color: u32,
+    pub toast_width: f32,
+    pub toast_margin: f32,
+    pub toast_border: f32,
+    pub toast_padding: f32,
+    // In seconds, how long toasts are shown before they start fading out, and
+    // how long the fade takes.
+    pub toast_duration: f32,
+    pub toast_fade_duration: f32,
+
+    pub modal_scrim_color: u32,
+    pub modal_width: f32,
+
+    pub command_palette_width: f32,
+    // The height of the result list, which scrolls beyond it.
+    pub command_palette_max_height: f32,
+
+    pub reorderable_list_handle_color: u32,
+    pub reorderable_list_handle_color_hovered: u32,
+    pub reorderable_list_handle_color_active: u32,
+    pub reorderable_list_indicator_color: u32,
+    pub reorderable_list_handle_width: f32,
+    pub reorderable_list_indicator_height: f32,
+
+    pub scrollbar_track_color: u32,
+    pub scrollbar_thumb_color: u32,
+    pub scrollbar_width: f32,
+
+    // How fast controls transition between their normal, hovered and active
+    // colors, in transitions per second. Zero means instant.
+    pub animation_speed: f32,
+}
+
+// Calls the given macro with the names of all theme fields, grouped into
+// sections per widget family and split into colors and metrics. Every field
+// must be listed here, which the struct literal in Theme::lerp checks.
+macro_rules! with_theme_fields {
+    ($callback:ident) => {
+        $callback! {
+            "Button" {
+                colors: [
+                    button_border_color,
+                    button_border_color_hovered,
+                    button_border_color_active,
+                    button_background_color,
+                    button_background_color_hovered,
+                    button_background_color_active,
+                    button_text_color,
+                    button_text_color_hovered,
+                    button_text_color_active,
+                    button_border_color_toggled,
+                    button_background_color_toggled,
+                    button_text_color_toggled,
+                    button_border_color_disabled,
+                    button_background_color_disabled,
+                    button_text_color_disabled,
+                ],
+                metrics: [
+                    button_height,
+                    button_margin,
+                    button_border,
+                ],
+            }
+            "Image Button" {
+                colors: [
+                    image_button_border_color,
+                    image_button_border_color_hovered,
+                    image_button_border_color_active,
+                    image_button_background_color,
+                    image_button_background_color_hovered,
+                    image_button_background_color_active,
+                    image_button_border_color_disabled,
+                    image_button_background_color_disabled,
+                    image_button_image_color_disabled,
+                ],
+                metrics: [
+                    image_button_width,
+                    image_button_height,
+                    image_button_margin,
+                    image_button_border,
+                ],
+            }
+            "Checkbox" {
+                colors: [
+                    checkbox_handle_color,
+                    checkbox_handle_color_hovered,
+                    checkbox_handle_color_active,
+                    checkbox_text_color,
+                    checkbox_text_color_hovered,
+                    checkbox_text_color_active,
+                    checkbox_mark_color,
+                    checkbox_mark_color_indeterminate,
+                ],
+                metrics: [
+                    checkbox_width,
+                    checkbox_height,
+                    checkbox_margin,
+                    checkbox_border,
+                ],
+            }
+            "Text" {
+                colors: [
+                    text_border_color,
+                    text_background_color,
+                    text_text_color,
+                    text_selection_color,
+                ],
+                metrics: [
+                    text_margin,
+                    text_border,
+                    text_padding,
+                ],
+            }
+            "Tooltip" {
+                colors: [
+                    text_tooltip_border_color,
+                    text_tooltip_background_color,
+                    text_tooltip_text_color,
+                ],
+                metrics: [
+                    text_tooltip_border,
+                    text_tooltip_padding,
+                    tooltip_delay,
+                    tooltip_cursor_offset,
+                    tooltip_max_width,
+                ],
+            }
+            "Context Menu" {
+                colors: [
+                    context_menu_border_color,
+                    context_menu_background_color,
+                ],
+                metrics: [
+                    context_menu_width,
+                    context_menu_border,
+                    context_menu_padding,
+                ],
+            }
+            "Text Input" {
+                colors: [
+                    text_input_border_color,
+                    text_input_border_color_hovered,
+                    text_input_border_color_active,
+                    text_input_background_color,
+                    text_input_background_color_hovered,
+                    text_input_background_color_active,
+                    text_input_text_color,
+                    text_input_text_color_hovered,
+                    text_input_text_color_active,
+                    text_input_overlay_match_text_color,
+                ],
+                metrics: [
+                    text_input_height,
+                    text_input_margin,
+                    text_input_border,
+                    text_input_overlay_max_height,
+                ],
+            }
+            "Float Slider" {
+                colors: [
+                    float_slider_border_color,
+                    float_slider_border_color_hovered,
+                    float_slider_border_color_active,
+                    float_slider_background_color,
+                    float_slider_background_color_hovered,
+                    float_slider_background_color_active,
+                    float_slider_text_color,
+                    float_slider_text_color_hovered,
+                    float_slider_text_color_active,
+                ],
+                metrics: [
+                    float_slider_height,
+                    float_slider_margin,
+                    float_slider_border,
+                ],
+            }
+            "Int Slider" {
+                colors: [
+                    int_slider_border_color,
+                    int_slider_border_color_hovered,
+                    int_slider_border_color_active,
+                    int_slider_background_color,
+                    int_slider_background_color_hovered,
+                    int_slider_background_color_active,
+                    int_slider_text_color,
+                    int_slider_text_color_hovered,
+                    int_slider_text_color_active,
+                ],
+                metrics: [
+                    int_slider_height,
+                    int_slider_margin,
+                    int_slider_border,
+                ],
+            }
+            "Dropdown" {
+                colors: [
+                    dropdown_border_color,
+                    dropdown_border_color_hovered,
+                    dropdown_border_color_active,
+                    dropdown_background_color,
+                    dropdown_background_color_hovered,
+                    dropdown_background_color_active,
+                    dropdown_text_color,
+                    dropdown_text_color_hovered,
+                    dropdown_text_color_active,
+                    dropdown_option_text_color_disabled,
+                ],
+                metrics: [
+                    dropdown_height,
+                    dropdown_margin,
+                    dropdown_border,
+                    dropdown_overlay_max_height,
+                ],
+            }
+            "Panel" {
+                colors: [
+                    panel_border_color,
+                    panel_background_color,
+                    panel_header_text_color,
+                    panel_header_background_color,
+                    panel_header_background_color_hovered,
+                ],
+                metrics: [
+                    panel_margin,
+                    panel_border,
+                    panel_padding,
+                    panel_header_height,
+                ],
+            }
+            "Window" {
+                colors: [
+                    window_border_color,
+                    window_border_color_hovered,
+                    window_background_color,
+                    window_background_color_hovered,
+                ],
+                metrics: [
+                    window_border,
+                    window_padding,
+                ],
+            }
+            "Separator" {
+                colors: [
+                    separator_color,
+                    separator_label_color,
+                ],
+                metrics: [
+                    separator_height,
+                    separator_margin,
+                    separator_label_spacing,
+                ],
+            }
+            "Spinner" {
+                colors: [
+                    spinner_color,
+                ],
+                metrics: [
+                    spinner_margin,
+                    spinner_speed,
+                ],
+            }
+            "Splitter" {
+                colors: [
+                    splitter_color,
+                    splitter_color_hovered,
+                    splitter_color_active,
+                ],
+                metrics: [
+                    splitter_width,
+                ],
+            }
+            "Drag Ghost" {
+                colors: [
+                    drag_ghost_border_color,
+                    drag_ghost_background_color,
+                ],
+                metrics: [
+                    drag_ghost_border,
+                ],
+            }
+            "Toast" {
+                colors: [
+                    toast_border_color,
+                    toast_background_color_info,
+                    toast_background_color_warn,
+                    toast_background_color_error,
+                    toast_text_color,
+                ],
+                metrics: [
+                    toast_width,
+                    toast_margin,
+                    toast_border,
+                    toast_padding,
+                    toast_duration,
+                    toast_fade_duration,
+                ],
+            }
+            "Modal" {
+                colors: [
+                    modal_scrim_color,
+                ],
+                metrics: [
+                    modal_width,
+                ],
+            }
+            "Command Palette" {
+                colors: [],
+                metrics: [
+                    command_palette_width,
+                    command_palette_max_height,
+                ],
+            }
+            "Reorderable List" {
+                colors: [
+                    reorderable_list_handle_color,
+                    reorderable_list_handle_color_hovered,
+                    reorderable_list_handle_color_active,
+                    reorderable_list_indicator_color,
+                ],
+                metrics: [
+                    reorderable_list_handle_width,
+                    reorderable_list_indicator_height,
+                ],
+            }
+            "Scrollbar" {
+                colors: [
+                    scrollbar_track_color,
+                    scrollbar_thumb_color,
+                ],
+                metrics: [
+                    scrollbar_width,
+                ],
+            }
+            "Animation" {
+                colors: [],
+                metrics: [
+                    animation_speed,
+                ],
+            }
+        }
+    };
+}
+
+pub(crate) use with_theme_fields;
+
+const TRANSPARENT: u32 = 0xffffff00;
+
+const WINDOW_BACKGROUND_COLOR: u32 = 0x080808fa;
+const WINDOW_BORDER_COLOR: u32 = 0x202020ff;
+const WINDOW_HEADER_BACKGROUND_COLOR: u32 = 0x202080fa;
+const WINDOW_HEADER_BACKGROUND_COLOR_HOVERED: u32 = 0x2a2aa0fa;
+
+const BORDER_COLOR: u32 = 0x202020ff;
+const BORDER_COLOR_HOVERED: u32 = 0x303030ff;
+const BORDER_COLOR_ACTIVE: u32 = 0x505050ff;
+const BORDER_COLOR_DISABLED: u32 = 0x181818ff;
+
+const BACKGROUND_COLOR: u32 = 0;
+const BACKGROUND_COLOR_HOVERED: u32 = 0x101010fa;
+const BACKGROUND_COLOR_ACTIVE: u32 = 0x151515fa;
+
+const TEXT_COLOR: u32 = 0xd0d0d0ff;
+const TEXT_COLOR_HEADER: u32 = 0xf0f0f0ff;
+const TEXT_COLOR_DISABLED: u32 = 0x606060ff;
+
+const LIGHT_WINDOW_BACKGROUND_COLOR: u32 = 0xf0f0f0fa;
+const LIGHT_WINDOW_BORDER_COLOR: u32 = 0xc0c0c0ff;
+const LIGHT_WINDOW_HEADER_BACKGROUND_COLOR: u32 = 0xa0b4e8fa;
+const LIGHT_WINDOW_HEADER_BACKGROUND_COLOR_HOVERED: u32 = 0xb4c4f0fa;
+
+const LIGHT_BORDER_COLOR: u32 = 0xb8b8b8ff;
+const LIGHT_BORDER_COLOR_HOVERED: u32 = 0x989898ff;
+const LIGHT_BORDER_COLOR_ACTIVE: u32 = 0x707070ff;
+const LIGHT_BORDER_COLOR_DISABLED: u32 = 0xd8d8d8ff;
+
+const LIGHT_BACKGROUND_COLOR: u32 = 0;
+const LIGHT_BACKGROUND_COLOR_HOVERED: u32 = 0xe0e0e0fa;
+const LIGHT_BACKGROUND_COLOR_ACTIVE: u32 = 0xd0d0d0fa;
+
+const LIGHT_TEXT_COLOR: u32 = 0x202020ff;
+const LIGHT_TEXT_COLOR_HEADER: u32 = 0x000000ff;
+const LIGHT_TEXT_COLOR_DISABLED: u32 = 0xa0a0a0ff;
+
+impl Theme {
+    pub const DEFAULT: Self = Self {
+        button_border_color: BORDER_COLOR,
+        button_border_color_hovered: BORDER_COLOR_HOVERED,
+        button_border_color_active: BORDER_COLOR_ACTIVE,
+        button_background_color: BACKGROUND_COLOR,
+        button_background_color_hovered: BACKGROUND_COLOR_HOVERED,
+        button_background_color_active: BACKGROUND_COLOR_ACTIVE,
+        button_text_color: TEXT_COLOR,
+        button_text_color_hovered: TEXT_COLOR,
+        button_text_color_active: TEXT_COLOR,
+        button_border_color_toggled: BORDER_COLOR_ACTIVE,
+        button_background_color_toggled: BACKGROUND_COLOR_ACTIVE,
+        button_text_color_toggled: TEXT_COLOR_HEADER,
+        button_border_color_disabled: BORDER_COLOR_DISABLED,
+        button_background_color_disabled: BACKGROUND_COLOR,
+        button_text_color_disabled: TEXT_COLOR_DISABLED,
+        button_height: 30.0,
+        button_margin: 2.0,
+        button_border: 1.0,
+
+        image_button_border_color: BORDER_COLOR,
+        image_button_border_color_hovered: BORDER_COLOR_HOVERED,
+        image_button_border_color_active: BORDER_COLOR_ACTIVE,
+        image_button_background_color: BACKGROUND_COLOR,
+        image_button_background_color_hovered: BACKGROUND_COLOR_HOVERED,
+        image_button_background_color_active: BACKGROUND_COLOR_ACTIVE,
+        image_button_border_color_disabled: BORDER_COLOR_DISABLED,
+        image_button_background_color_disabled: BACKGROUND_COLOR,
+        image_button_image_color_disabled: 0xffffff60,
+        image_button_width: 30.0,
+        image_button_height: 30.0,
+        image_button_margin: 2.0,
+        image_button_border: 1.0,
+
+        checkbox_handle_color: 0xffffff50,
+        checkbox_handle_color_hovered: 0xffffff70,
+        checkbox_handle_color_active: 0xffffffa0,
+        checkbox_text_color: TEXT_COLOR,
+        checkbox_text_color_hovered: TEXT_COLOR,
+        checkbox_text_color_active: TEXT_COLOR,
+        checkbox_mark_color: 0xffffffff,
+        checkbox_mark_color_indeterminate: 0xffffffa0,
+        checkbox_width: 250.0,
+        checkbox_height: 30.0,
+        checkbox_margin: 2.0,
+        checkbox_border: 1.0,
+
+        text_border_color: TRANSPARENT,
+        text_background_color: TRANSPARENT,
+        text_text_color: TEXT_COLOR,
+        text_selection_color: 0x40ffa040,
+        text_margin: 0.0,
+        text_border: 0.0,
+        text_padding: 10.0,
+
+        text_tooltip_border_color: BORDER_COLOR,
+        text_tooltip_background_color: WINDOW_BACKGROUND_COLOR,
+        text_tooltip_text_color: TEXT_COLOR,
+        text_tooltip_border: 1.0,
+        text_tooltip_padding: 10.0,
+
+        tooltip_delay: 0.5,
+        tooltip_cursor_offset: 16.0,
+        tooltip_max_width: 400.0,
+
+        context_menu_border_color: BORDER_COLOR,
+        context_menu_background_color: WINDOW_BACKGROUND_COLOR,
+        context_menu_width: 200.0,
+        context_menu_border: 1.0,
+        context_menu_padding: 5.0,
+
+        text_input_border_color: BORDER_COLOR,
+        text_input_border_color_hovered: BORDER_COLOR_HOVERED,
+        text_input_border_color_active: BORDER_COLOR_ACTIVE,
+        text_input_background_color: BACKGROUND_COLOR,
+        text_input_background_color_hovered: BACKGROUND_COLOR_HOVERED,
+        text_input_background_color_active: BACKGROUND_COLOR_ACTIVE,
+        text_input_text_color: TEXT_COLOR,
+        text_input_text_color_hovered: TEXT_COLOR,
+        text_input_text_color_active: TEXT_COLOR,
+        text_input_overlay_match_text_color: 0xf0c040ff,
+        text_input_height: 30.0,
+        text_input_margin: 2.0,
+        text_input_border: 1.0,
+        text_input_overlay_max_height: 400.0,
+
+        float_slider_border_color: BORDER_COLOR,
+        float_slider_border_color_hovered: BORDER_COLOR_HOVERED,
+        float_slider_border_color_active: BORDER_COLOR_ACTIVE,
+        float_slider_background_color: TRANSPARENT,
+        float_slider_background_color_hovered: TRANSPARENT,
+        float_slider_background_color_active: TRANSPARENT,
+        float_slider_text_color: TEXT_COLOR,
+        float_slider_text_color_hovered: TEXT_COLOR,
+        float_slider_text_color_active: TEXT_COLOR,
+        float_slider_height: 30.0,
+        float_slider_margin: 2.0,
+        float_slider_border: 1.0,
+
+        int_slider_border_color: BORDER_COLOR,
+        int_slider_border_color_hovered: BORDER_COLOR_HOVERED,
+        int_slider_border_color_active: BORDER_COLOR_ACTIVE,
+        int_slider_background_color: TRANSPARENT,
+        int_slider_background_color_hovered: TRANSPARENT,
+        int_slider_background_color_active: TRANSPARENT,
+        int_slider_text_color: TEXT_COLOR,
+        int_slider_text_color_hovered: TEXT_COLOR,
+        int_slider_text_color_active: TEXT_COLOR,
+        int_slider_height: 30.0,
+        int_slider_margin: 2.0,
+        int_slider_border: 1.0,
+
+        dropdown_border_color: BORDER_COLOR,
+        dropdown_border_color_hovered: BORDER_COLOR_HOVERED,
+        dropdown_border_color_active: BORDER_COLOR_ACTIVE,
+        dropdown_background_color: BACKGROUND_COLOR,
+        dropdown_background_color_hovered: BACKGROUND_COLOR_HOVERED,
+        dropdown_background_color_active: BACKGROUND_COLOR_ACTIVE,
+        dropdown_text_color: TEXT_COLOR,
+        dropdown_text_color_hovered: TEXT_COLOR,
+        dropdown_text_color_active: TEXT_COLOR,
+        dropdown_height: 30.0,
+        dropdown_margin: 2.0,
+        dropdown_border: 1.0,
+        dropdown_overlay_max_height: 400.0,
+        dropdown_option_text_color_disabled: TEXT_COLOR_DISABLED,
+
+        panel_border_color: TRANSPARENT,
+        panel_background_color: TRANSPARENT,
+        panel_margin: 5.0,
+        panel_border: 0.0,
+        panel_padding: 5.0,
+        panel_header_text_color: TEXT_COLOR_HEADER,
+        panel_header_background_color: WINDOW_HEADER_BACKGROUND_COLOR,
+        panel_header_background_color_hovered: WINDOW_HEADER_BACKGROUND_COLOR_HOVERED,
+        panel_header_height: 20.0,
+
+        window_border_color: BORDER_COLOR,
+        window_border_color_hovered: WINDOW_BORDER_COLOR,
+        window_background_color: WINDOW_BACKGROUND_COLOR,
+        window_background_color_hovered: WINDOW_BACKGROUND_COLOR,
+        window_border: 1.0,
+        window_padding: 5.0,
+
+        separator_color: BORDER_COLOR,
+        separator_height: 1.0,
+        separator_margin: 8.0,
+        separator_label_color: TEXT_COLOR_HEADER,
+        separator_label_spacing: 8.0,
+
+        spinner_color: TEXT_COLOR,
+        spinner_margin: 2.0,
+        spinner_speed: 1.0,
+
+        splitter_color: BORDER_COLOR,
+        splitter_color_hovered: BORDER_COLOR_HOVERED,
+        splitter_color_active: BORDER_COLOR_ACTIVE,
+        splitter_width: 4.0,
+
+        drag_ghost_border_color: BORDER_COLOR_ACTIVE,
+        drag_ghost_background_color: 0x50505060,
+        drag_ghost_border: 1.0,
+
+        toast_border_color: BORDER_COLOR,
+        toast_background_color_info: WINDOW_BACKGROUND_COLOR,
+        toast_background_color_warn: 0x403008fa,
+        toast_background_color_error: 0x481010fa,
+        toast_text_color: TEXT_COLOR,
+        toast_width: 300.0,
+        toast_margin: 5.0,
+        toast_border: 1.0,
+        toast_padding: 10.0,
+        toast_duration: 3.0,
+        toast_fade_duration: 0.5,
+        modal_scrim_color: 0x00000080,
+        modal_width: 400.0,
+
+        command_palette_width: 500.0,
+        command_palette_max_height: 300.0,
+
+        reorderable_list_handle_color: BORDER_COLOR,
+        reorderable_list_handle_color_hovered: BORDER_COLOR_HOVERED,
+        reorderable_list_handle_color_active: BORDER_COLOR_ACTIVE,
+        reorderable_list_indicator_color: BORDER_COLOR_ACTIVE,
+        reorderable_list_handle_width: 12.0,
+        reorderable_list_indicator_height: 2.0,
+
+        scrollbar_track_color: 0x10101080,
+        scrollbar_thumb_color: 0x505050ff,
+        scrollbar_width: 6.0,
+
+        animation_speed: 0.0,
+    };
+
+    pub const LIGHT: Self = Self {
+        button_border_color: LIGHT_BORDER_COLOR,
+        button_border_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        button_border_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        button_background_color: LIGHT_BACKGROUND_COLOR,
+        button_background_color_hovered: LIGHT_BACKGROUND_COLOR_HOVERED,
+        button_background_color_active: LIGHT_BACKGROUND_COLOR_ACTIVE,
+        button_text_color: LIGHT_TEXT_COLOR,
+        button_text_color_hovered: LIGHT_TEXT_COLOR,
+        button_text_color_active: LIGHT_TEXT_COLOR,
+        button_border_color_toggled: LIGHT_BORDER_COLOR_ACTIVE,
+        button_background_color_toggled: LIGHT_BACKGROUND_COLOR_ACTIVE,
+        button_text_color_toggled: LIGHT_TEXT_COLOR_HEADER,
+        button_border_color_disabled: LIGHT_BORDER_COLOR_DISABLED,
+        button_background_color_disabled: LIGHT_BACKGROUND_COLOR,
+        button_text_color_disabled: LIGHT_TEXT_COLOR_DISABLED,
+        button_height: 30.0,
+        button_margin: 2.0,
+        button_border: 1.0,
+
+        image_button_border_color: LIGHT_BORDER_COLOR,
+        image_button_border_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        image_button_border_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        image_button_background_color: LIGHT_BACKGROUND_COLOR,
+        image_button_background_color_hovered: LIGHT_BACKGROUND_COLOR_HOVERED,
+        image_button_background_color_active: LIGHT_BACKGROUND_COLOR_ACTIVE,
+        image_button_border_color_disabled: LIGHT_BORDER_COLOR_DISABLED,
+        image_button_background_color_disabled: LIGHT_BACKGROUND_COLOR,
+        image_button_image_color_disabled: 0xffffff80,
+        image_button_width: 30.0,
+        image_button_height: 30.0,
+        image_button_margin: 2.0,
+        image_button_border: 1.0,
+
+        checkbox_handle_color: 0x00000020,
+        checkbox_handle_color_hovered: 0x00000030,
+        checkbox_handle_color_active: 0x00000050,
+        checkbox_text_color: LIGHT_TEXT_COLOR,
+        checkbox_text_color_hovered: LIGHT_TEXT_COLOR,
+        checkbox_text_color_active: LIGHT_TEXT_COLOR,
+        checkbox_mark_color: 0x202020ff,
+        checkbox_mark_color_indeterminate: 0x202020a0,
+        checkbox_width: 250.0,
+        checkbox_height: 30.0,
+        checkbox_margin: 2.0,
+        checkbox_border: 1.0,
+
+        text_border_color: TRANSPARENT,
+        text_background_color: TRANSPARENT,
+        text_text_color: LIGHT_TEXT_COLOR,
+        text_selection_color: 0x2080ff50,
+        text_margin: 0.0,
+        text_border: 0.0,
+        text_padding: 10.0,
+
+        text_tooltip_border_color: LIGHT_BORDER_COLOR,
+        text_tooltip_background_color: LIGHT_WINDOW_BACKGROUND_COLOR,
+        text_tooltip_text_color: LIGHT_TEXT_COLOR,
+        text_tooltip_border: 1.0,
+        text_tooltip_padding: 10.0,
+
+        tooltip_delay: 0.5,
+        tooltip_cursor_offset: 16.0,
+        tooltip_max_width: 400.0,
+
+        context_menu_border_color: LIGHT_BORDER_COLOR,
+        context_menu_background_color: LIGHT_WINDOW_BACKGROUND_COLOR,
+        context_menu_width: 200.0,
+        context_menu_border: 1.0,
+        context_menu_padding: 5.0,
+
+        text_input_border_color: LIGHT_BORDER_COLOR,
+        text_input_border_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        text_input_border_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        text_input_background_color: LIGHT_BACKGROUND_COLOR,
+        text_input_background_color_hovered: LIGHT_BACKGROUND_COLOR_HOVERED,
+        text_input_background_color_active: LIGHT_BACKGROUND_COLOR_ACTIVE,
+        text_input_text_color: LIGHT_TEXT_COLOR,
+        text_input_text_color_hovered: LIGHT_TEXT_COLOR,
+        text_input_text_color_active: LIGHT_TEXT_COLOR,
+        text_input_overlay_match_text_color: 0xa05a00ff,
+        text_input_height: 30.0,
+        text_input_margin: 2.0,
+        text_input_border: 1.0,
+        text_input_overlay_max_height: 400.0,
+
+        float_slider_border_color: LIGHT_BORDER_COLOR,
+        float_slider_border_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        float_slider_border_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        float_slider_background_color: TRANSPARENT,
+        float_slider_background_color_hovered: TRANSPARENT,
+        float_slider_background_color_active: TRANSPARENT,
+        float_slider_text_color: LIGHT_TEXT_COLOR,
+        float_slider_text_color_hovered: LIGHT_TEXT_COLOR,
+        float_slider_text_color_active: LIGHT_TEXT_COLOR,
+        float_slider_height: 30.0,
+        float_slider_margin: 2.0,
+        float_slider_border: 1.0,
+
+        int_slider_border_color: LIGHT_BORDER_COLOR,
+        int_slider_border_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        int_slider_border_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        int_slider_background_color: TRANSPARENT,
+        int_slider_background_color_hovered: TRANSPARENT,
+        int_slider_background_color_active: TRANSPARENT,
+        int_slider_text_color: LIGHT_TEXT_COLOR,
+        int_slider_text_color_hovered: LIGHT_TEXT_COLOR,
+        int_slider_text_color_active: LIGHT_TEXT_COLOR,
+        int_slider_height: 30.0,
+        int_slider_margin: 2.0,
+        int_slider_border: 1.0,
+
+        dropdown_border_color: LIGHT_BORDER_COLOR,
+        dropdown_border_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        dropdown_border_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        dropdown_background_color: LIGHT_BACKGROUND_COLOR,
+        dropdown_background_color_hovered: LIGHT_BACKGROUND_COLOR_HOVERED,
+        dropdown_background_color_active: LIGHT_BACKGROUND_COLOR_ACTIVE,
+        dropdown_text_color: LIGHT_TEXT_COLOR,
+        dropdown_text_color_hovered: LIGHT_TEXT_COLOR,
+        dropdown_text_color_active: LIGHT_TEXT_COLOR,
+        dropdown_height: 30.0,
+        dropdown_margin: 2.0,
+        dropdown_border: 1.0,
+        dropdown_overlay_max_height: 400.0,
+        dropdown_option_text_color_disabled: LIGHT_TEXT_COLOR_DISABLED,
+
+        panel_border_color: TRANSPARENT,
+        panel_background_color: TRANSPARENT,
+        panel_margin: 5.0,
+        panel_border: 0.0,
+        panel_padding: 5.0,
+        panel_header_text_color: LIGHT_TEXT_COLOR_HEADER,
+        panel_header_background_color: LIGHT_WINDOW_HEADER_BACKGROUND_COLOR,
+        panel_header_background_color_hovered: LIGHT_WINDOW_HEADER_BACKGROUND_COLOR_HOVERED,
+        panel_header_height: 20.0,
+
+        window_border_color: LIGHT_BORDER_COLOR,
+        window_border_color_hovered: LIGHT_WINDOW_BORDER_COLOR,
+        window_background_color: LIGHT_WINDOW_BACKGROUND_COLOR,
+        window_background_color_hovered: LIGHT_WINDOW_BACKGROUND_COLOR,
+        window_border: 1.0,
+        window_padding: 5.0,
+
+        separator_color: LIGHT_BORDER_COLOR,
+        separator_height: 1.0,
+        separator_margin: 8.0,
+        separator_label_color: LIGHT_TEXT_COLOR_HEADER,
+        separator_label_spacing: 8.0,
+
+        spinner_color: LIGHT_TEXT_COLOR,
+        spinner_margin: 2.0,
+        spinner_speed: 1.0,
+
+        splitter_color: LIGHT_BORDER_COLOR,
+        splitter_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        splitter_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        splitter_width: 4.0,
+
+        drag_ghost_border_color: LIGHT_BORDER_COLOR_ACTIVE,
+        drag_ghost_background_color: 0xa0a0a060,
+        drag_ghost_border: 1.0,
+
+        toast_border_color: LIGHT_BORDER_COLOR,
+        toast_background_color_info: LIGHT_WINDOW_BACKGROUND_COLOR,
+        toast_background_color_warn: 0xf8e8b0fa,
+        toast_background_color_error: 0xf8c8c8fa,
+        toast_text_color: LIGHT_TEXT_COLOR,
+        toast_width: 300.0,
+        toast_margin: 5.0,
+        toast_border: 1.0,
+        toast_padding: 10.0,
+        toast_duration: 3.0,
+        toast_fade_duration: 0.5,
+        modal_scrim_color: 0x00000040,
+        modal_width: 400.0,
+
+        command_palette_width: 500.0,
+        command_palette_max_height: 300.0,
+
+        reorderable_list_handle_color: LIGHT_BORDER_COLOR,
+        reorderable_list_handle_color_hovered: LIGHT_BORDER_COLOR_HOVERED,
+        reorderable_list_handle_color_active: LIGHT_BORDER_COLOR_ACTIVE,
+        reorderable_list_indicator_color: LIGHT_BORDER_COLOR_ACTIVE,
+        reorderable_list_handle_width: 12.0,
+        reorderable_list_indicator_height: 2.0,
+
+        scrollbar_track_color: 0xd0d0d080,
+        scrollbar_thumb_color: 0xa0a0a0ff,
+        scrollbar_width: 6.0,
+
+        animation_speed: 0.0,
+    };
+
+    /// Linearly interpolates between two themes, e.g. to animate switching
+    /// between them. Colors are interpolated in linear space.
+    pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        macro_rules! lerp_fields {
+            ($(
+                $section:literal {
+                    colors: [$($color:ident),* $(,)?],
+                    metrics: [$($metric:ident),* $(,)?] $(,)?
+                }
+            )*) => {
+                Self {
+                    $(
+                        $($color: color_lerp(a.$color, b.$color, t),)*
+                        $($metric: lerp(a.$metric, b.$metric, t),)*
+                    )*
+                }
+            };
+        }
+
+        with_theme_fields!(lerp_fields)
+    }
+}
+
+/// Overrides a single metric of the theme, e.g. to make buttons larger in one
+/// section of the UI. Push with [`crate::Frame::push_style_var`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StyleVar {
+    ButtonHeight(f32),
+    ButtonMargin(f32),
+    ButtonBorder(f32),
+    ImageButtonWidth(f32),
+    ImageButtonHeight(f32),
+    ImageButtonMargin(f32),
+    ImageButtonBorder(f32),
+    CheckboxWidth(f32),
+    CheckboxHeight(f32),
+    CheckboxMargin(f32),
+    CheckboxBorder(f32),
+    TextMargin(f32),
+    TextBorder(f32),
+    TextPadding(f32),
+    TextTooltipBorder(f32),
+    TextTooltipPadding(f32),
+    TooltipDelay(f32),
+    TooltipCursorOffset(f32),
+    TooltipMaxWidth(f32),
+    ContextMenuWidth(f32),
+    ContextMenuBorder(f32),
+    ContextMenuPadding(f32),
+    TextInputHeight(f32),
+    TextInputMargin(f32),
+    TextInputBorder(f32),
+    TextInputOverlayMaxHeight(f32),
+    FloatSliderHeight(f32),
+    FloatSliderMargin(f32),
+    FloatSliderBorder(f32),
+    IntSliderHeight(f32),
+    IntSliderMargin(f32),
+    IntSliderBorder(f32),
+    DropdownHeight(f32),
+    DropdownMargin(f32),
+    DropdownBorder(f32),
+    DropdownOverlayMaxHeight(f32),
+    PanelMargin(f32),
+    PanelBorder(f32),
+    PanelPadding(f32),
+    PanelHeaderHeight(f32),
+    WindowBorder(f32),
+    WindowPadding(f32),
+    SeparatorHeight(f32),
+    SeparatorMargin(f32),
+    SeparatorLabelSpacing(f32),
+    SpinnerMargin(f32),
+    SpinnerSpeed(f32),
+    SplitterWidth(f32),
+    DragGhostBorder(f32),
+    ToastWidth(f32),
+    ToastMargin(f32),
+    ToastBorder(f32),
+    ToastPadding(f32),
+    ToastDuration(f32),
+    ToastFadeDuration(f32),
+    ModalWidth(f32),
+    CommandPaletteWidth(f32),
+    CommandPaletteMaxHeight(f32),
+    ReorderableListHandleWidth(f32),
+    ReorderableListIndicatorHeight(f32),
+    ScrollbarWidth(f32),
+    AnimationSpeed(f32),
+}
+
+macro_rules! impl_style_var {
+    ($($variant:ident => $field:ident,)*) => {
+        impl StyleVar {
+            // Writes the value into the theme and returns the value it
+            // replaced, so that it can be restored later.
+            pub(crate) fn swap(self, theme: &mut Theme) -> Self {
+                match self {
+                    $(Self::$variant(value) => Self::$variant(mem::replace(&mut theme.$field, value)),)*
+                }
+            }
+        }
+    };
+}
+
+impl_style_var! {
+    ButtonHeight => button_height,
+    ButtonMargin => button_margin,
+    ButtonBorder => button_border,
+    ImageButtonWidth => image_button_width,
+    ImageButtonHeight => image_button_height,
+    ImageButtonMargin => image_button_margin,
+    ImageButtonBorder => image_button_border,
+    CheckboxWidth => checkbox_width,
+    CheckboxHeight => checkbox_height,
+    CheckboxMargin => checkbox_margin,
+    CheckboxBorder => checkbox_border,
+    TextMargin => text_margin,
+    TextBorder => text_border,
+    TextPadding => text_padding,
+    TextTooltipBorder => text_tooltip_border,
+    TextTooltipPadding => text_tooltip_padding,
+    TooltipDelay => tooltip_delay,
+    TooltipCursorOffset => tooltip_cursor_offset,
+    TooltipMaxWidth => tooltip_max_width,
+    ContextMenuWidth => context_menu_width,
+    ContextMenuBorder => context_menu_border,
+    ContextMenuPadding => context_menu_padding,
+    TextInputHeight => text_input_height,
+    TextInputMargin => text_input_margin,
+    TextInputBorder => text_input_border,
+    TextInputOverlayMaxHeight => text_input_overlay_max_height,
+    FloatSliderHeight => float_slider_height,
+    FloatSliderMargin => float_slider_margin,
+    FloatSliderBorder => float_slider_border,
+    IntSliderHeight => int_slider_height,
+    IntSliderMargin => int_slider_margin,
+    IntSliderBorder => int_slider_border,
+    DropdownHeight => dropdown_height,
+    DropdownMargin => dropdown_margin,
+    DropdownBorder => dropdown_border,
+    DropdownOverlayMaxHeight => dropdown_overlay_max_height,
+    PanelMargin => panel_margin,
+    PanelBorder => panel_border,
+    PanelPadding => panel_padding,
+    PanelHeaderHeight => panel_header_height,
+    WindowBorder => window_border,
+    WindowPadding => window_padding,
+    SeparatorHeight => separator_height,
+    SeparatorMargin => separator_margin,
+    SeparatorLabelSpacing => separator_label_spacing,
+    SpinnerMargin => spinner_margin,
+    SpinnerSpeed => spinner_speed,
+    SplitterWidth => splitter_width,
+    DragGhostBorder => drag_ghost_border,
+    ToastWidth => toast_width,
+    ToastMargin => toast_margin,
+    ToastBorder => toast_border,
+    ToastPadding => toast_padding,
+    ToastDuration => toast_duration,
+    ToastFadeDuration => toast_fade_duration,
+    ModalWidth => modal_width,
+    CommandPaletteWidth => command_palette_width,
+    CommandPaletteMaxHeight => command_palette_max_height,
+    ReorderableListHandleWidth => reorderable_list_handle_width,
+    ReorderableListIndicatorHeight => reorderable_list_indicator_height,
+    ScrollbarWidth => scrollbar_width,
+    AnimationSpeed => animation_speed,
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lerp_themes() {
+        assert_eq!(
+            Theme::lerp(&Theme::DEFAULT, &Theme::LIGHT, 0.0),
+            Theme::DEFAULT
+        );
+        assert_eq!(
+            Theme::lerp(&Theme::DEFAULT, &Theme::LIGHT, 1.0),
+            Theme::LIGHT
+        );
+    }
+}
@@ -1,20 +1,64 @@
-use alloc::string::String;
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::alloc::Allocator;
-use core::mem;
-use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Range};
-
-use arrayvec::ArrayString;
-
-use crate::core::draw_list::{Command, DrawList, Vertex};
-use crate::core::font_atlas::{FontAtlas, UnicodeRangeFlags};
+use core::hash::{Hash, Hasher};
+use core::num::NonZeroU32;
+use core::ops::{
+    BitAnd,
+    BitAndAssign,
+    BitOr,
+    BitOrAssign,
+    Deref,
+    DerefMut,
+    Not,
+    Range,
+    Sub,
+    SubAssign,
+};
+use core::{fmt, mem};
+
+use arrayvec::{ArrayString, ArrayVec};
+use hashbrown::hash_map::{DefaultHashBuilder, HashMap};
+#[cfg(debug_assertions)]
+use hashbrown::HashSet;
+
+use crate::core::color::color_multiply_alpha;
+use crate::core::draw_list::{
+    Command,
+    DrawList,
+    DrawListRecording,
+    DrawListStats,
+    Sampling,
+    Vertex,
+};
+use crate::core::font_atlas::{FontAtlas, FontSource, LineMetrics};
 use crate::core::math::{Rect, Vec2};
+use crate::core::string::{TextStorage, VecString};
+use crate::core::theme::{StyleVar, Theme};
 
 const ROOT_IDX: usize = 0;
 const OVERLAY_ROOT_IDX: usize = 1;
 
+const INPUT_EVENT_CAPACITY: usize = 64;
+
+// Shortcuts that controls wanting text input, e.g. text inputs, handle on
+// their own. Keep in sync with text_input.rs.
+const TEXT_EDITING_SHORTCUTS: [Shortcut; 8] = [
+    Shortcut::new(Inputs::KB_A, Modifiers::CTRL),
+    Shortcut::new(Inputs::KB_X, Modifiers::CTRL),
+    Shortcut::new(Inputs::KB_C, Modifiers::CTRL),
+    Shortcut::new(Inputs::KB_V, Modifiers::CTRL),
+    Shortcut::new(Inputs::KB_B, Modifiers::CTRL),
+    Shortcut::new(Inputs::KB_B, Modifiers::CTRL | Modifiers::SHIFT),
+    Shortcut::new(Inputs::KB_F, Modifiers::CTRL),
+    Shortcut::new(Inputs::KB_F, Modifiers::CTRL | Modifiers::SHIFT),
+];
+
+// Assumed frame time, if the platform doesn't provide one.
+const FALLBACK_DELTA_TIME: f32 = 1.0 / 60.0;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
-pub struct Inputs(u32);
+pub struct Inputs(u64);
 
 impl Inputs {
     pub const MB_LEFT: Self = Self(0x01);
@@ -39,6 +83,7 @@ impl Inputs {
     pub const KB_BACKSPACE: Self = Self(0x40000);
     pub const KB_ENTER: Self = Self(0x80000);
     pub const KB_ESCAPE: Self = Self(0x100000);
+    pub const KB_SPACE: Self = Self(0x10000000);
 
     // Selection:
     pub const KB_A: Self = Self(0x200000);
@@ -52,6 +97,28 @@ impl Inputs {
     pub const KB_C: Self = Self(0x2000000);
     pub const KB_V: Self = Self(0x8000000);
 
+    // Other letters, e.g. for application shortcuts:
+    pub const KB_D: Self = Self(0x20000000);
+    pub const KB_E: Self = Self(0x40000000);
+    pub const KB_G: Self = Self(0x80000000);
+    pub const KB_H: Self = Self(0x100000000);
+    pub const KB_I: Self = Self(0x200000000);
+    pub const KB_J: Self = Self(0x400000000);
+    pub const KB_K: Self = Self(0x800000000);
+    pub const KB_L: Self = Self(0x1000000000);
+    pub const KB_M: Self = Self(0x2000000000);
+    pub const KB_N: Self = Self(0x4000000000);
+    pub const KB_O: Self = Self(0x8000000000);
+    pub const KB_P: Self = Self(0x10000000000);
+    pub const KB_Q: Self = Self(0x20000000000);
+    pub const KB_R: Self = Self(0x40000000000);
+    pub const KB_S: Self = Self(0x80000000000);
+    pub const KB_T: Self = Self(0x100000000000);
+    pub const KB_U: Self = Self(0x200000000000);
+    pub const KB_W: Self = Self(0x400000000000);
+    pub const KB_Y: Self = Self(0x800000000000);
+    pub const KB_Z: Self = Self(0x1000000000000);
+
     // TODO(yan): Add gamepad buttons.
 
     pub const NONE: Self = Self(0);
@@ -76,18 +143,39 @@ impl Inputs {
         | Self::KB_BACKSPACE
         | Self::KB_ENTER
         | Self::KB_ESCAPE
+        | Self::KB_SPACE
         | Self::KB_F
         | Self::KB_B
         | Self::KB_A
         | Self::KB_X
         | Self::KB_C
-        | Self::KB_V;
-
-    pub fn bits(&self) -> u32 {
+        | Self::KB_V
+        | Self::KB_D
+        | Self::KB_E
+        | Self::KB_G
+        | Self::KB_H
+        | Self::KB_I
+        | Self::KB_J
+        | Self::KB_K
+        | Self::KB_L
+        | Self::KB_M
+        | Self::KB_N
+        | Self::KB_O
+        | Self::KB_P
+        | Self::KB_Q
+        | Self::KB_R
+        | Self::KB_S
+        | Self::KB_T
+        | Self::KB_U
+        | Self::KB_W
+        | Self::KB_Y
+        | Self::KB_Z;
+
+    pub fn bits(&self) -> u64 {
         self.0
     }
 
-    pub fn from_bits_truncate(bits: u32) -> Self {
+    pub fn from_bits_truncate(bits: u64) -> Self {
         Self(Self::ALL.0 & bits)
     }
 
@@ -98,6 +186,10 @@ impl Inputs {
     pub fn intersects(&self, other: Self) -> bool {
         self.0 & other.0 != 0
     }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
 }
 
 impl const BitOr for Inputs {
@@ -114,6 +206,42 @@ impl BitOrAssign for Inputs {
     }
 }
 
+impl const BitAnd for Inputs {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl BitAndAssign for Inputs {
+    fn bitand_assign(&mut self, other: Self) {
+        self.0 &= other.0;
+    }
+}
+
+impl Not for Inputs {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(Self::ALL.0 & !self.0)
+    }
+}
+
+impl Sub for Inputs {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl SubAssign for Inputs {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Modifiers(u32);
 
@@ -140,6 +268,10 @@ impl Modifiers {
     pub fn intersects(&self, other: Self) -> bool {
         self.0 & other.0 != 0
     }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
 }
 
 impl const BitOr for Modifiers {
@@ -174,10 +306,167 @@ impl Not for Modifiers {
     type Output = Self;
 
     fn not(self) -> Self {
-        Self(!self.0)
+        Self(Self::ALL.0 & !self.0)
+    }
+}
+
+impl Sub for Modifiers {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl SubAssign for Modifiers {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
+/// A combination of inputs and modifiers, e.g. Ctrl+Shift+Left, that can be
+/// matched against inputs pressed by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub inputs: Inputs,
+    pub modifiers: Modifiers,
+}
+
+impl Shortcut {
+    pub const fn new(inputs: Inputs, modifiers: Modifiers) -> Self {
+        Self { inputs, modifiers }
+    }
+
+    /// Returns whether all of the shortcut's inputs are pressed, and exactly
+    /// the shortcut's modifiers are held.
+    ///
+    /// Other pressed inputs (e.g. a held mouse button, or another key pressed
+    /// in the same frame) don't prevent a match, but an extra held modifier
+    /// does, so that e.g. Ctrl+B and Ctrl+Shift+B can be told apart.
+    pub fn matches(&self, pressed: Inputs, modifiers: Modifiers) -> bool {
+        pressed.contains(self.inputs) && modifiers & Modifiers::ALL == self.modifiers
+    }
+}
+
+/// How touches are turned into taps, scrolling and long presses. See
+/// [`Ui::set_touch_options`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchOptions {
+    /// How far, in logical pixels, a touch can move before it stops being a
+    /// potential tap, and starts scrolling or dragging instead.
+    pub slop: f32,
+    /// Seconds a touch has to be held in place to synthesize a right click,
+    /// e.g. to open context menus. None disables long presses.
+    pub long_press_duration: Option<f32>,
+}
+
+impl TouchOptions {
+    pub const DEFAULT: Self = Self {
+        slop: 8.0,
+        long_press_duration: Some(0.5),
+    };
+}
+
+impl Default for TouchOptions {
+    fn default() -> Self {
+        Self::DEFAULT
     }
 }
 
+// The primary touch, i.e. the first finger that touched the screen while no
+// other did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Touch {
+    id: u64,
+    start_position: Vec2,
+    last_position: Vec2,
+    held_time: f32,
+    state: TouchState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TouchState {
+    // Could still be a tap or a long press. Nothing was pressed yet.
+    Pending,
+    // Scrolls the control under the touch instead of pressing anything.
+    Scrolling,
+    // Holds the left mouse button, e.g. to drag a slider or a window.
+    Pressed,
+    // Held in place long enough to press the right mouse button.
+    LongPressed,
+}
+
+/// How held keys repeat their presses. See [`Ui::set_key_repeat`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyRepeat {
+    /// Seconds from pressing the key to the first repeat.
+    pub delay: f32,
+    /// Seconds between repeats after that.
+    pub interval: f32,
+}
+
+impl KeyRepeat {
+    pub const DEFAULT: Self = Self {
+        delay: 0.5,
+        interval: 0.05,
+    };
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How [`Ctrl::draw_image_with_options`] draws a texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageOptions {
+    /// The part of the texture to draw, in normalized texture coordinates.
+    /// See [`Ctrl::draw_image_uv_px`] for pixel coordinates.
+    pub texture_rect: Rect,
+    /// Multiplied with the texture's colors.
+    pub tint: u32,
+    /// Flips the texture upside down, e.g. for render targets of graphics
+    /// APIs whose texture coordinates start at the bottom.
+    pub flip_y: bool,
+    pub sampling: Sampling,
+}
+
+impl ImageOptions {
+    pub const DEFAULT: Self = Self {
+        texture_rect: Rect::ONE,
+        tint: 0xffffffff,
+        flip_y: false,
+        sampling: Sampling::LINEAR,
+    };
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// An input event received from the platform. Events are queued in the order
+/// they were received and processed in that order by widgets, so that multiple
+/// inputs arriving within a single frame are not lost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    /// Repeated presses of held keys are synthesized by the Ui, if key repeat
+    /// is enabled.
+    KeyPress {
+        input: Inputs,
+        modifiers: Modifiers,
+        repeat: bool,
+    },
+    KeyRelease {
+        input: Inputs,
+        modifiers: Modifiers,
+    },
+    Char(char),
+    Scroll(Vec2),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Layout {
     Free,
@@ -185,6 +474,28 @@ pub enum Layout {
     Vertical,
 }
 
+/// How a control with an aspect ratio fits the space available to it. See
+/// [`Ctrl::set_aspect_ratio_with_fit`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AspectFit {
+    /// The control is as large as it can be without exceeding the available
+    /// space, leaving the rest empty, e.g. letterboxing a game viewport.
+    #[default]
+    Contain,
+    /// The control is as small as it can be while covering all of the
+    /// available space, overflowing it in one direction.
+    Cover,
+}
+
+/// The kind of a notification queued with [`Frame::toast`], picking its
+/// colors from the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToastKind {
+    Info,
+    Warn,
+    Error,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Align {
     Start,
@@ -199,6 +510,82 @@ pub enum Wrap {
     None,
 }
 
+/// How values animated with [`Frame::animate_with_easing`] move from where
+/// they were towards their target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    Linear,
+    /// Starts fast and slows down towards the target.
+    #[default]
+    EaseOut,
+    /// Starts and ends slowly.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps the linear progress of an animation, from 0 to 1, to the eased
+    /// progress, also from 0 to 1.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = f32::clamp(t, 0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseOut => {
+                let u = 1.0 - t;
+                1.0 - u * u * u
+            }
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// The direction text is laid out in. For right-to-left text, glyphs are
+/// positioned from the right and horizontal alignment is mirrored, so that
+/// [`Align::Start`] aligns to the right edge. Wrapping and whitespace trimming
+/// still operate on the text in logical order.
+///
+/// Only the direction of the whole text is supported, there is no bidi
+/// reordering of mixed-direction runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+/// The mouse cursor shape controls ask for, e.g. a hand over buttons, a text
+/// cursor over text inputs, or a diagonal resize cursor over the resize handle
+/// of windows. Controls request it with [`Ctrl::set_cursor_icon`], and the
+/// platform reads the result with [`Ui::cursor_icon`] after each frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorIcon {
+    #[default]
+    Default,
+    Text,
+    ResizeNESW,
+    ResizeNWSE,
+    ResizeHorizontal,
+    ResizeVertical,
+    Hand,
+    Move,
+}
+
+impl CursorIcon {
+    // If multiple controls request a cursor icon in the same frame, the one
+    // with the highest priority wins. Icons signalling an ongoing (or
+    // possible) drag beat icons signalling what a click would do.
+    fn priority(self) -> u8 {
+        match self {
+            Self::Default => 0,
+            Self::Hand => 1,
+            Self::Text => 2,
+            Self::ResizeNESW => 3,
+            Self::ResizeNWSE => 3,
+            Self::ResizeHorizontal => 3,
+            Self::ResizeVertical => 3,
+            Self::Move => 4,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum DrawPrimitive {
     Rect {
@@ -206,8 +593,64 @@ enum DrawPrimitive {
         texture_rect: Rect,
         texture_id: u64,
         color: u32,
+        flip_y: bool,
+        sampling: Sampling,
+        // Clips the primitive in addition to the control's scissor rect. In
+        // the same coordinates as the primitive's rect.
+        clip_rect: Option<Rect>,
+    },
+    // Separate from Rect, so that the most common primitive, text glyphs,
+    // doesn't have to carry four colors.
+    GradientRect {
+        rect: Rect,
+        texture_rect: Rect,
+        texture_id: u64,
+        // Top-left, top-right, bottom-right, bottom-left.
+        colors: [u32; 4],
+        clip_rect: Option<Rect>,
+    },
+    Line {
+        a: Vec2,
+        b: Vec2,
+        thickness: f32,
+        texture_id: u64,
+        color: u32,
+        clip_rect: Option<Rect>,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+        texture_id: u64,
+        color: u32,
+        clip_rect: Option<Rect>,
+    },
+    RoundedRect {
+        rect: Rect,
+        radius: f32,
+        // Draws just the border of this thickness, if set.
+        border_thickness: Option<f32>,
+        texture_id: u64,
+        color: u32,
+        clip_rect: Option<Rect>,
     },
-    // TODO(yan): Circles, Rounded arcs, whatever..
+    Callback {
+        rect: Rect,
+        user_id: u64,
+        clip_rect: Option<Rect>,
+    },
+}
+
+impl DrawPrimitive {
+    fn clip_rect(&self) -> Option<Rect> {
+        match *self {
+            Self::Rect { clip_rect, .. } => clip_rect,
+            Self::GradientRect { clip_rect, .. } => clip_rect,
+            Self::Line { clip_rect, .. } => clip_rect,
+            Self::Circle { clip_rect, .. } => clip_rect,
+            Self::RoundedRect { clip_rect, .. } => clip_rect,
+            Self::Callback { clip_rect, .. } => clip_rect,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -257,16 +700,82 @@ impl CtrlFlags {
     /// only has layout data from last frame, if any.
     pub const RESIZE_TO_FIT_VERTICAL: Self = Self(0x20);
 
+    /// Whether to draw scroll bars when the control's contents overflow its
+    /// inner rect. The scroll bars are drawn over the control's padding and
+    /// contents, and their thumbs can be dragged with the mouse. Their
+    /// dimensions and colors are set with [`Ctrl::set_scrollbar_width`],
+    /// [`Ctrl::set_scrollbar_track_color`] and
+    /// [`Ctrl::set_scrollbar_thumb_color`].
+    pub const SHOW_SCROLLBARS: Self = Self(0x40);
+
+    /// Whether vertical user scrolling (e.g. the mouse wheel) should scroll the
+    /// control horizontally, if it can only scroll horizontally. Useful for
+    /// horizontal layouts, where the contents usually only overflow
+    /// horizontally. Has no effect without [`CtrlFlags::CAPTURE_SCROLL`].
+    pub const CAPTURE_SCROLL_HORIZONTAL: Self = Self(0x80);
+
+    /// Whether to keep the geometry generated for the control and its
+    /// descendants, and reuse it in the next frame instead of generating it
+    /// again, if nothing that goes into rendering them changed, e.g. their
+    /// layout, scroll offsets, or what they draw. Useful for large and mostly
+    /// static parts of the UI, like panels of text.
+    ///
+    /// Checking for changes still visits the whole subtree every frame, so
+    /// this doesn't pay off for controls that change often, or draw little.
+    /// See [`FrameStats::draw_cache_hit_count`].
+    pub const CACHE_DRAW: Self = Self(0x100);
+
+    /// Whether the control's drawing should ignore the scissor rects of the
+    /// control and its ancestors, and only be clipped to the window (or
+    /// viewport). Useful for focus rings, shadows, badges and other
+    /// decorations that extend past the control's edges, without having to
+    /// build them in the overlay.
+    ///
+    /// NB: This also applies to the control's descendants, which can
+    /// therefore overflow the control and its ancestors. The control is still
+    /// drawn in the tree order, so later siblings can draw over the overflow.
+    pub const NO_CLIP: Self = Self(0x200);
+
+    /// Whether to resize the control's rect width to fill its parent. In
+    /// horizontal layouts, the control takes the width left over by its
+    /// siblings, shared equally with other filling siblings. Otherwise, it
+    /// takes the parent's inner width, less its own horizontal position.
+    ///
+    /// Like with resizing to fit, the size is computed by the layout pass, so
+    /// interactivity may lag one frame behind. Filling controls don't count
+    /// towards the content size of parents resizing to fit in the same
+    /// direction, and fill the size the parent fits to instead.
+    pub const FILL_HORIZONTAL: Self = Self(0x400);
+
+    /// Whether to resize the control's rect height to fill its parent. In
+    /// vertical layouts, the control takes the height left over by its
+    /// siblings, shared equally with other filling siblings. Otherwise, it
+    /// takes the parent's inner height, less its own vertical position.
+    ///
+    /// Like with resizing to fit, the size is computed by the layout pass, so
+    /// interactivity may lag one frame behind. Filling controls don't count
+    /// towards the content size of parents resizing to fit in the same
+    /// direction, and fill the size the parent fits to instead.
+    pub const FILL_VERTICAL: Self = Self(0x800);
+
     pub const NONE: Self = Self(0);
     pub const ALL: Self = Self::CAPTURE_SCROLL
         | Self::CAPTURE_HOVER
         | Self::CAPTURE_ACTIVE
         | Self::RESIZE_TO_FIT_HORIZONTAL
-        | Self::RESIZE_TO_FIT_VERTICAL;
+        | Self::RESIZE_TO_FIT_VERTICAL
+        | Self::SHOW_SCROLLBARS
+        | Self::CAPTURE_SCROLL_HORIZONTAL
+        | Self::CACHE_DRAW
+        | Self::NO_CLIP
+        | Self::FILL_HORIZONTAL
+        | Self::FILL_VERTICAL;
 
     pub const ALL_RESIZE_TO_FIT: Self =
         Self::RESIZE_TO_FIT_HORIZONTAL | Self::RESIZE_TO_FIT_VERTICAL;
 
+    pub const ALL_FILL: Self = Self::FILL_HORIZONTAL | Self::FILL_VERTICAL;
+
     pub fn bits(self) -> u32 {
         self.0
     }
@@ -278,6 +787,10 @@ impl CtrlFlags {
     pub fn intersects(&self, other: Self) -> bool {
         self.0 & other.0 != 0
     }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
 }
 
 impl const BitOr for CtrlFlags {
@@ -294,24 +807,101 @@ impl BitOrAssign for CtrlFlags {
     }
 }
 
+impl const BitAnd for CtrlFlags {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl BitAndAssign for CtrlFlags {
+    fn bitand_assign(&mut self, other: Self) {
+        self.0 &= other.0;
+    }
+}
+
+impl Not for CtrlFlags {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(Self::ALL.0 & !self.0)
+    }
+}
+
+impl Sub for CtrlFlags {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl SubAssign for CtrlFlags {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
 pub type CtrlState = [u8; 64];
 
+// Widgets cast their state to structs, so it must be aligned for them.
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct AlignedCtrlState(CtrlState);
+
+// Read by controls that don't have any state stored.
+static ZERO_CTRL_STATE: AlignedCtrlState = AlignedCtrlState([0; 64]);
+
+// Index of a control in the tree, stored off by one, so that Option<CtrlIdx>
+// is 4 bytes instead of the 16 of Option<usize>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CtrlIdx(NonZeroU32);
+
+impl CtrlIdx {
+    fn new(idx: usize) -> Self {
+        let value = u32::try_from(idx + 1).expect("Control index does not fit into u32");
+        Self(NonZeroU32::new(value).unwrap())
+    }
+
+    fn get(self) -> usize {
+        (self.0.get() - 1) as usize
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct CtrlNode {
     // Unique across siblings, but no further.
-    id: u64,
-
-    // TODO(yan): @Speed @Memory Make indices more compact. Option<usize> is 16
-    // bytes, but we could carve out a niche.
-    parent_idx: Option<usize>,
-    child_idx: Option<usize>,
-    sibling_idx: Option<usize>,
+    id: u32,
+    // Derived from the ids of the control and all its ancestors. Stays the
+    // same for as long as the control lives, regardless of where it is stored
+    // in the tree.
+    persistent_id: u64,
+
+    parent_idx: Option<CtrlIdx>,
+    child_idx: Option<CtrlIdx>,
+    sibling_idx: Option<CtrlIdx>,
+    // For controls directly under the overlay root, the control in the base
+    // layer that was being built when this control was pushed, e.g. a dropdown
+    // for its options. Controls in the overlay relinquish their active status
+    // to the owner, as if it was their parent.
+    overlay_owner_idx: Option<CtrlIdx>,
+    // The viewport the control is rendered into and hovered in, or None for
+    // the main one. Inherited from the parent, except for controls directly
+    // under the roots, which take the viewport being built.
+    viewport: Option<u32>,
 
     first_frame: u32,
     // Deallocate if not current.
     last_frame: u32,
     // Used to sort free layout controls for detecting hover and rendering.
     last_frame_in_active_path: u32,
+    // Sorts free layout controls before last_frame_in_active_path does.
+    z_index: i32,
+
+    // Recorded from the disabled stack when pushed. Disabled controls are
+    // never hovered or active, and render faded.
+    disabled: bool,
 
     // Layout things
     flags: CtrlFlags,
@@ -322,35 +912,127 @@ struct CtrlNode {
     margin: f32,
 
     inline_content_rect: Option<Rect>,
+    aspect_ratio: Option<(f32, AspectFit)>,
+
+    // Set with Ctrl::close_on_outside_click, and answered in the next
+    // begin_frame.
+    close_on_outside_click: bool,
+    clicked_outside: bool,
 
     scroll_offset: Vec2,
+    // Where scroll_offset is heading, if scrolling is smoothed. Otherwise the
+    // same as scroll_offset.
+    scroll_offset_target: Vec2,
 
-    // TODO(yan): @Memory For some controls this is too much memory, and for
-    // some others this is not enough. We should eventually make this as small
-    // as possible, e.g. just enough to satisfy the larger-but-still-small
-    // controls, like Window, and create optional extra state storage for
-    // controls that require a lot more.
+    // Index into Ui::ctrl_states, allocated when the control first asks for
+    // mutable state. Controls that never do, e.g. text, don't pay for it.
     //
-    // Alternatively, we can do memory size-classed slabs with freelists outside
-    // of CtrlNode, and make memory node contain either no state memory at all,
-    // or just the smallest size-class.
-    state: CtrlState,
+    // TODO(yan): @Memory For some controls the state is too much memory, and
+    // for some others this is not enough. We could do size-classed slabs, and
+    // create optional extra state storage for controls that require a lot
+    // more.
+    state_idx: Option<usize>,
+    // Whether the state is saved by Ui::save_persistent_state. Controls have
+    // to opt in on every frame, like with flags.
+    persistent: bool,
 
     draw_self: bool,
     draw_self_border_color: u32,
     draw_self_background_color: u32,
     draw_range: Range<usize>,
+    // The number of draw primitives of the control and all its descendants.
+    // Only counted while the debug overlay is enabled.
+    debug_subtree_draw_primitive_count: usize,
+
+    scrollbar_width: f32,
+    scrollbar_track_color: u32,
+    scrollbar_thumb_color: u32,
 
     layout_cache_absolute_position: Vec2,
     layout_cache_content_size: Vec2,
+    // The scroll offset used for laying out children. Scrolling can change
+    // between layouts, and we need to know where exactly the children are.
+    layout_cache_scroll_offset: Vec2,
+}
+
+// Hover and active transitions of a single control, each from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct CtrlAnimation {
+    hover: Transition,
+    active: Transition,
+}
+
+// The drag and drop state machine. Owned by the Ui, so that drag sources and
+// drop targets don't have to know about each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Drag {
+    payload_id: u64,
+    source_persistent_id: u64,
+    // The source's absolute rect and the cursor position when it was pressed,
+    // so that a ghost of the source can follow the cursor.
+    source_rect: Rect,
+    start_cursor_position: Vec2,
+    // The drag only starts once the cursor moves far enough from where the
+    // source was pressed, so that clicking the source is not a drag.
+    dragging: bool,
+}
+
+// A value animated with Frame::animate, interpolating from where it was when
+// the target last changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AnimatedValue {
+    from: f32,
+    target: f32,
+    // Linear progress from 0 to 1, eased when computing the value.
+    t: f32,
+    value: f32,
+    last_frame: u32,
+}
+
+// The build state of the overlay, saved while a nested overlay is built.
+#[derive(Debug, Clone, Copy)]
+struct OverlayBuildCursor {
+    parent_idx: usize,
+    sibling_idx: Option<usize>,
+    depth: usize,
+    // The z index of the top level overlay control the nested overlay was
+    // begun in. The nested overlay stays above it.
+    z_index: i32,
+}
+
+// A notification queued with Frame::toast, shown until it is older than the
+// theme's toast duration and fade duration combined.
+struct Toast<A: Allocator> {
+    text: VecString<A>,
+    kind: ToastKind,
+    // In seconds.
+    age: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Transition {
+    t: f32,
+    // So that querying the transition twice in a frame doesn't advance it
+    // twice.
+    last_frame: u32,
+}
+
+/// The UI. Holds everything that persists across frames, and the allocator
+/// for temporary memory, which can be of a different type than the main
+/// allocator. See [`Ui::set_temp_allocator`].
+///
+/// Dereferences to [`UiCore`], which has the rest of the UI's methods.
+pub struct Ui<A: Allocator + Clone, T: Allocator + Clone = A> {
+    core: UiCore<A>,
+    // For the line buffers of widgets that lay out text themselves, which
+    // don't outlive the widget call. Defaults to a clone of the allocator.
+    // Kept out of UiCore, so that frames can lend it to widgets while they
+    // mutate the core.
+    temp_allocator: T,
 }
 
-pub struct Ui<A: Allocator + Clone> {
-    // TODO(yan): @Memory We use this allocator for both permanent and temporary
-    // memory, which requires some acrobatics to ensure we don't prevent
-    // temporary memory reclamation, if the allocator is a bump
-    // allocator. Should we split off a temporary allocator from this one for
-    // internal use?
+/// Everything in [`Ui`] except the temp allocator.
+pub struct UiCore<A: Allocator + Clone> {
     allocator: A,
 
     draw_primitives: Vec<DrawPrimitive, A>,
@@ -358,37 +1040,191 @@ pub struct Ui<A: Allocator + Clone> {
 
     font_atlas: FontAtlas<A>,
     font_atlas_texture_id: u64,
+    font_atlas_generation: u64,
+    premultiplied_alpha: bool,
+    linear_color_output: bool,
+    anti_aliasing: bool,
+    text_pixel_snap: bool,
+    pixel_snapping: bool,
+    // In multiples of the advance width of space.
+    tab_width: u32,
+
+    // Themes used by widget functions that don't take a theme argument. The
+    // first one is the theme set on the UI, the others are pushed during the
+    // frame, and the last one is used. They are boxed, so that with_theme can
+    // cheaply take the current theme out of the stack and lend it to a widget
+    // that also needs the frame.
+    #[allow(clippy::vec_box)]
+    theme_stack: Vec<Box<Theme, A>, A>,
+    // Whether the current theme is taken out of the stack by with_theme.
+    theme_taken: bool,
+    // Style vars are written directly into the current theme. The stack
+    // remembers the pushed values, the values they replaced, and the length
+    // of the theme stack they were pushed onto.
+    style_var_stack: Vec<(StyleVar, StyleVar, usize), A>,
+    // Whether controls pushed now are disabled. Each entry is already combined
+    // with the one below it.
+    disabled_stack: Vec<bool, A>,
 
     tree: Vec<CtrlNode, A>,
+    // The tree is rebuilt into this buffer by the GC every frame, and then the
+    // two are swapped. Empty outside of end_frame.
+    tree_next: Vec<CtrlNode, A>,
+    // State memory of controls, allocated on demand. Slots of collected
+    // controls are put on the free list and reused.
+    ctrl_states: Vec<AlignedCtrlState, A>,
+    ctrl_state_free_list: Vec<usize, A>,
     id_namespace_stack: Vec<u32, A>,
+    // Clip rects pushed by the control currently being drawn, each already
+    // intersected with the one below it.
+    clip_rect_stack: Vec<Rect, A>,
+
+    // Scratch buffers for the UI's own per-frame work, cleared (but not
+    // deallocated) before use, so that steady frames don't allocate. Siblings
+    // of free layouts are sorted in the siblings buffer, with nested free
+    // layouts using the part past their parent's siblings.
+    scratch_siblings: Vec<(usize, (i32, u32)), A>,
+    // Maps indices in the old tree to indices in the new tree in the GC.
+    scratch_new_idxs: Vec<Option<CtrlIdx>, A>,
+    scratch_text_lines: Vec<TextLine, A>,
+
+    draw_caches: DrawCaches<A>,
+
+    // Viewports built in the last frame, each with its own draw list. See
+    // Frame::begin_viewport.
+    viewports: Vec<Viewport<A>, A>,
+    // Index into viewports of the viewport being built, if any.
+    build_viewport_idx: Option<usize>,
 
     building_overlay: bool,
+    // Where building continues once a nested overlay ends, see
+    // Frame::begin_overlay.
+    overlay_build_stack: ArrayVec<OverlayBuildCursor, 8>,
+    // The last control pushed at the top level of the overlay this frame.
+    // Nested overlays are built after it.
+    overlay_build_last_top_level_idx: Option<usize>,
     build_parent_idx: Option<usize>,
     build_sibling_idx: Option<usize>,
     overlay_build_parent_idx: Option<usize>,
     overlay_build_sibling_idx: Option<usize>,
+    // The ids of the controls pushed this frame, paired with the index of
+    // their parent, so that id collisions between siblings are detected
+    // without rescanning the siblings.
+    #[cfg(debug_assertions)]
+    build_sibling_ids: HashSet<(usize, u32), DefaultHashBuilder, A>,
+    // The number of controls pushed, but not yet popped, in the tree currently
+    // being built (and in the other one). Used to detect unbalanced push_ctrl
+    // and pop_ctrl calls.
+    build_depth: usize,
+    overlay_build_depth: usize,
+    // The ids of the pushed, but not yet popped controls, as passed to
+    // push_ctrl. Only kept for diagnostics.
+    #[cfg(debug_assertions)]
+    build_ctrl_id_stack: Vec<u32, A>,
+    #[cfg(debug_assertions)]
+    overlay_build_ctrl_id_stack: Vec<u32, A>,
 
     current_frame: u32,
 
     window_size: Vec2,
     window_scale_factor: f32,
     scroll_delta: Vec2,
+    scroll_smoothing_time_constant: Option<f32>,
+    key_repeat: Option<KeyRepeat>,
+    // The last pressed repeatable key, while it is held, for how long it has
+    // been held, and how many times it repeated.
+    key_repeat_input: Inputs,
+    key_repeat_held_time: f32,
+    key_repeat_count: u32,
+    touch_options: TouchOptions,
+    touch: Option<Touch>,
+    // Released after the current frame, so that a tap's press and release
+    // don't arrive in the same frame.
+    touch_release_next_frame: Inputs,
+    delta_time: Option<f32>,
+    // Set with Ui::set_delta_time and consumed by the next Ui::begin_frame.
+    next_delta_time: Option<f32>,
+    time: f32,
     cursor_position: Vec2,
-    inputs_pressed: Inputs,
-    inputs_released: Inputs,
+    // The viewport the cursor is in, or None for the main one.
+    cursor_viewport: Option<u32>,
+    // NB: If the platform sends more events than we have space for in a
+    // single frame, characters and scrolls are dropped (the scroll is still
+    // applied from scroll_delta), but presses and releases fall back to the
+    // overflow bitmasks below, so that controls don't get stuck active.
+    input_events: ArrayVec<InputEvent, INPUT_EVENT_CAPACITY>,
+    overflow_inputs_pressed: Inputs,
+    overflow_inputs_released: Inputs,
+
+    // One bit for each of the input events, set once a key press was claimed
+    // by a shortcut, so that it isn't handled twice.
+    claimed_input_events: u64,
     modifiers: Modifiers,
+    // Derived from input_events, but kept separately so that we can hand out
+    // a &str.
     received_characters: ArrayString<32>,
-    // TODO(yan): @Memory Would we great if we didn't allocate the String
-    // here.. somehow.
-    clipboard_getter: fn() -> String,
-    clipboard_setter: fn(&str),
+    clipboard_getter: Option<fn(&mut dyn TextStorage)>,
+    clipboard_setter: Option<fn(&str)>,
+    // Reused between calls to the clipboard getter, so that pasting doesn't
+    // allocate every time.
+    clipboard_buffer: VecString<A>,
 
     active_ctrl_idx: Option<usize>,
     hovered_ctrl_idx: Option<usize>,
     hovered_capturing_ctrl_idx: Option<usize>,
+    // The control blocking interaction with everything outside of it, as set
+    // with Ctrl::set_modal. The build one becomes the current one in
+    // end_frame, and is used to find the hovered control next frame.
+    modal_ctrl_idx: Option<usize>,
+    build_modal_ctrl_idx: Option<usize>,
+    // For how long, in seconds, the hover capturing control has been hovered.
+    // Only advances with frame time, so it stays zero without it.
+    hovered_capturing_ctrl_duration: f32,
+
+    // Only for controls that asked for their hover and active transitions, by
+    // persistent id. Collected together with the controls.
+    ctrl_animations: HashMap<u64, CtrlAnimation, DefaultHashBuilder, A>,
+    // Values animated with Frame::animate, by the same hash as persistent ids
+    // of controls. Collected when not animated for a frame.
+    animated_values: HashMap<u64, AnimatedValue, DefaultHashBuilder, A>,
+    // Oldest first. Built into the overlay at the end of each frame.
+    toasts: Vec<Toast<A>, A>,
+    // States passed to Ui::load_persistent_state, by persistent id. Each seeds
+    // the state of the control with the matching persistent id, once it asks
+    // for state, and is removed then.
+    loaded_ctrl_states: HashMap<u64, AlignedCtrlState, DefaultHashBuilder, A>,
 
     last_ctrl_idx: Option<usize>,
 
+    // The control whose scroll bar thumb is being dragged, if any, and the
+    // cursor position and scroll offset when the drag started.
+    scrollbar_drag_ctrl_idx: Option<usize>,
+    scrollbar_drag_vertical: bool,
+    scrollbar_drag_start_cursor_position: Vec2,
+    scrollbar_drag_start_scroll_offset: Vec2,
+
+    // Pending or ongoing drag and drop, cleared once the left mouse button is
+    // released.
+    drag: Option<Drag>,
+
+    cursor_icon: CursorIcon,
+
+    // Whether end_frame draws control outlines and the inspector on top of
+    // everything. See Ui::set_debug_overlay.
+    debug_overlay: bool,
+
+    frame_stats: FrameStats,
+    // Counted while building, and moved to frame_stats in end_frame.
+    build_glyph_count: usize,
+
+    // Requests made by controls while building the frame. Resolved into the
+    // want_* flags below in end_frame. We remember which control requested
+    // keyboard capture and text input, because only requests from the active
+    // control are honored.
+    build_want_capture_keyboard_ctrl_idx: Option<usize>,
+    build_want_text_input_ctrl_idx: Option<usize>,
+    build_want_capture_mouse: bool,
+
     // TODO(yan): When exactly should we be capturing keyboard and mouse
     // automatically, when no control requests it? Currently we capture mouse
     // when something is hovered (ImGui does the same). ImGui also automatically
@@ -396,6 +1232,7 @@ pub struct Ui<A: Allocator + Clone> {
     // windows are being dragged around.
     want_capture_keyboard: bool,
     want_capture_mouse: bool,
+    want_text_input: bool,
 }
 
 impl<A: Allocator + Clone> Ui<A> {
@@ -403,45 +1240,187 @@ impl<A: Allocator + Clone> Ui<A> {
         window_width: f32,
         window_height: f32,
         window_scale_factor: f32,
-        font_bytes: &[u8],
-        font_unicode_range_flags: UnicodeRangeFlags,
-        font_size: f32,
-        // Maximum window scale factor the rasterizer is prepared for. If
-        // displaying on a single monitor, that display's scale factor should be
-        // used. If there are multiple monitors with different scales, pick
-        // highest for sharpest looking fonts, or lower, if memory or speed is
-        // an issue.
-        font_rasterization_scale_factor: f32,
+        font_source: FontSource<A>,
+        allocator: A,
+    ) -> Self {
+        Ui::new_in_with_temp_allocator(
+            window_width,
+            window_height,
+            window_scale_factor,
+            font_source,
+            allocator.clone(),
+            allocator,
+        )
+    }
+}
+
+impl<A: Allocator + Clone, T: Allocator + Clone> Ui<A, T> {
+    /// Like [`Ui::new_in`], but temporary memory is allocated from a separate
+    /// allocator, e.g. a bump allocator that is reset every frame. See
+    /// [`Ui::set_temp_allocator`].
+    pub fn new_in_with_temp_allocator(
+        window_width: f32,
+        window_height: f32,
+        window_scale_factor: f32,
+        font_source: FontSource<A>,
+        allocator: A,
+        temp_allocator: T,
+    ) -> Self {
+        #[cfg_attr(
+            not(feature = "runtime_rasterize"),
+            allow(clippy::infallible_destructuring_match)
+        )]
+        let font_atlas = match font_source {
+            #[cfg(feature = "runtime_rasterize")]
+            FontSource::Ttf {
+                bytes,
+                unicode_range_flags,
+                codepoint_ranges,
+                size,
+                rasterization_scale_factor,
+                atlas_max_image_dimension,
+            } => FontAtlas::new_in_with_ranges(
+                bytes,
+                unicode_range_flags,
+                codepoint_ranges,
+                size,
+                rasterization_scale_factor,
+                atlas_max_image_dimension,
+                allocator.clone(),
+            ),
+            FontSource::Atlas(font_atlas) => font_atlas,
+        };
+
+        Self {
+            core: UiCore::new(
+                window_width,
+                window_height,
+                window_scale_factor,
+                font_atlas,
+                allocator,
+            ),
+            temp_allocator,
+        }
+    }
+
+    /// Sets the allocator for temporary memory. Defaults to the allocator the
+    /// UI was created with. Only widgets that lay out text themselves, i.e.
+    /// [`selectable_text`](crate::selectable_text) and
+    /// [`text_input`](crate::text_input), allocate from it, for the lines of
+    /// their text, and free the memory before they return. Nothing allocated
+    /// from it is held across calls, so the host may reset it between
+    /// [`Ui::end_frame`] and the next [`Ui::begin_frame`].
+    ///
+    /// Everything else lives in the main allocator, including the scratch
+    /// buffers the UI sorts controls and splits drawn text into lines in.
+    /// These are kept across frames, so once the UI is warmed up, i.e. its
+    /// control tree, draw list, control states and scratch buffers have grown
+    /// to fit what is being built, steady frames don't allocate from the main
+    /// allocator at all.
+    pub fn set_temp_allocator(&mut self, temp_allocator: T) {
+        self.temp_allocator = temp_allocator;
+    }
+
+    pub fn begin_frame(&mut self) -> Frame<'_, A> {
+        self.core.do_begin_frame(&self.temp_allocator)
+    }
+
+    /// Like [`Ui::begin_frame`], but also receives the time in seconds since
+    /// the last frame, which drives animations, such as smooth scrolling.
+    /// Shorthand for [`Ui::set_delta_time`] followed by [`Ui::begin_frame`],
+    /// so it replaces any time set before.
+    pub fn begin_frame_with_delta_time(&mut self, delta_time: f32) -> Frame<'_, A> {
+        self.core.set_delta_time(delta_time);
+        self.begin_frame()
+    }
+
+    pub fn end_frame(&mut self) {
+        self.core.do_end_frame();
+    }
+}
+
+impl<A: Allocator + Clone, T: Allocator + Clone> Deref for Ui<A, T> {
+    type Target = UiCore<A>;
+
+    fn deref(&self) -> &UiCore<A> {
+        &self.core
+    }
+}
+
+impl<A: Allocator + Clone, T: Allocator + Clone> DerefMut for Ui<A, T> {
+    fn deref_mut(&mut self) -> &mut UiCore<A> {
+        &mut self.core
+    }
+}
+
+impl<A: Allocator + Clone> UiCore<A> {
+    fn new(
+        window_width: f32,
+        window_height: f32,
+        window_scale_factor: f32,
+        font_atlas: FontAtlas<A>,
         allocator: A,
     ) -> Self {
         const TREE_CAPACITY: usize = 1024;
         const ID_NAMESPACE_STACK_CAPACITY: usize = 64;
+        const CLIP_RECT_STACK_CAPACITY: usize = 16;
+        const THEME_STACK_CAPACITY: usize = 4;
+        const STYLE_VAR_STACK_CAPACITY: usize = 16;
+        const DISABLED_STACK_CAPACITY: usize = 16;
+        const CTRL_STATE_CAPACITY: usize = 128;
+        const SCRATCH_SIBLINGS_CAPACITY: usize = 64;
+        const SCRATCH_TEXT_LINES_CAPACITY: usize = 64;
+        const VIEWPORTS_CAPACITY: usize = 4;
+        #[cfg(debug_assertions)]
+        const BUILD_CTRL_ID_STACK_CAPACITY: usize = 64;
 
-        let a1 = allocator.clone();
         let a2 = allocator.clone();
         let a3 = allocator.clone();
         let a4 = allocator.clone();
         let a5 = allocator.clone();
+        let a6 = allocator.clone();
+        let a7 = allocator.clone();
+        let a8 = allocator.clone();
+        let a9 = allocator.clone();
+        let a10 = allocator.clone();
+        let a11 = allocator.clone();
+        let a12 = allocator.clone();
+        let a13 = allocator.clone();
+        let a14 = allocator.clone();
+        let a15 = allocator.clone();
+        let a16 = allocator.clone();
+        let a17 = allocator.clone();
+        let a18 = allocator.clone();
+        let a19 = allocator.clone();
+        let a20 = allocator.clone();
+        let a21 = allocator.clone();
+        let a22 = allocator.clone();
+        let a23 = allocator.clone();
+        #[cfg(debug_assertions)]
+        let a24 = allocator.clone();
+        #[cfg(debug_assertions)]
+        let a25 = allocator.clone();
+        #[cfg(debug_assertions)]
+        let a26 = allocator.clone();
 
         let window_size = Vec2::new(window_width, window_height);
-        let font_atlas = FontAtlas::new_in(
-            font_bytes,
-            font_unicode_range_flags,
-            font_size,
-            font_rasterization_scale_factor,
-            a1,
-        );
 
         let root_ctrl = CtrlNode {
             id: 0,
+            persistent_id: 0,
 
             parent_idx: None,
             child_idx: None,
             sibling_idx: None,
+            overlay_owner_idx: None,
+            viewport: None,
 
             first_frame: 0,
             last_frame: 0,
             last_frame_in_active_path: 0,
+            z_index: 0,
+
+            disabled: false,
 
             flags: CtrlFlags::NONE,
             layout: Layout::Free,
@@ -451,23 +1430,42 @@ impl<A: Allocator + Clone> Ui<A> {
             margin: 0.0,
 
             inline_content_rect: None,
+            aspect_ratio: None,
+            close_on_outside_click: false,
+            clicked_outside: false,
 
             scroll_offset: Vec2::ZERO,
+            scroll_offset_target: Vec2::ZERO,
 
-            state: [0; 64],
+            state_idx: None,
+            persistent: false,
 
             draw_self: false,
             draw_self_border_color: 0,
             draw_self_background_color: 0,
             draw_range: 0..0,
+            debug_subtree_draw_primitive_count: 0,
+
+            scrollbar_width: 0.0,
+            scrollbar_track_color: 0,
+            scrollbar_thumb_color: 0,
 
             layout_cache_absolute_position: Vec2::ZERO,
             layout_cache_content_size: Vec2::ZERO,
+            layout_cache_scroll_offset: Vec2::ZERO,
         };
 
         let mut tree = Vec::with_capacity_in(TREE_CAPACITY, a2);
         tree.push(root_ctrl.clone());
-        tree.push(root_ctrl);
+        // The overlay root needs a different persistent id, so that its
+        // children don't share persistent ids with children of the base root.
+        tree.push(CtrlNode {
+            persistent_id: 1,
+            ..root_ctrl
+        });
+
+        let mut theme_stack = Vec::with_capacity_in(THEME_STACK_CAPACITY, a8);
+        theme_stack.push(Box::new_in(Theme::DEFAULT, a9));
 
         Self {
             allocator,
@@ -477,37 +1475,122 @@ impl<A: Allocator + Clone> Ui<A> {
 
             font_atlas,
             font_atlas_texture_id: 0,
+            font_atlas_generation: 0,
+            premultiplied_alpha: false,
+            linear_color_output: false,
+            anti_aliasing: false,
+            text_pixel_snap: false,
+            pixel_snapping: false,
+            tab_width: 4,
+
+            theme_stack,
+            theme_taken: false,
+            style_var_stack: Vec::with_capacity_in(STYLE_VAR_STACK_CAPACITY, a10),
+            disabled_stack: Vec::with_capacity_in(DISABLED_STACK_CAPACITY, a11),
 
             tree,
+            tree_next: Vec::with_capacity_in(TREE_CAPACITY, a15),
+            ctrl_states: Vec::with_capacity_in(CTRL_STATE_CAPACITY, a13),
+            ctrl_state_free_list: Vec::with_capacity_in(CTRL_STATE_CAPACITY, a14),
             id_namespace_stack: Vec::with_capacity_in(ID_NAMESPACE_STACK_CAPACITY, a5),
+            clip_rect_stack: Vec::with_capacity_in(CLIP_RECT_STACK_CAPACITY, a7),
+
+            scratch_siblings: Vec::with_capacity_in(SCRATCH_SIBLINGS_CAPACITY, a17),
+            scratch_new_idxs: Vec::with_capacity_in(TREE_CAPACITY, a18),
+            scratch_text_lines: Vec::with_capacity_in(SCRATCH_TEXT_LINES_CAPACITY, a19),
+
+            // NB: Not allocated up front, because caching is opt-in.
+            draw_caches: DrawCaches {
+                entries: HashMap::new_in(a20.clone()),
+                allocator: a20,
+                hit_count: 0,
+                miss_count: 0,
+            },
+
+            viewports: Vec::with_capacity_in(VIEWPORTS_CAPACITY, a21),
+            build_viewport_idx: None,
 
             building_overlay: false,
+            overlay_build_stack: ArrayVec::new(),
+            overlay_build_last_top_level_idx: None,
             build_parent_idx: None,
             build_sibling_idx: None,
             overlay_build_parent_idx: None,
             overlay_build_sibling_idx: None,
+            #[cfg(debug_assertions)]
+            build_sibling_ids: HashSet::new_in(a24),
+            build_depth: 0,
+            overlay_build_depth: 0,
+            #[cfg(debug_assertions)]
+            build_ctrl_id_stack: Vec::with_capacity_in(BUILD_CTRL_ID_STACK_CAPACITY, a25),
+            #[cfg(debug_assertions)]
+            overlay_build_ctrl_id_stack: Vec::with_capacity_in(BUILD_CTRL_ID_STACK_CAPACITY, a26),
 
             current_frame: 0,
 
             window_size,
             window_scale_factor,
             scroll_delta: Vec2::ZERO,
+            scroll_smoothing_time_constant: None,
+            key_repeat: None,
+            key_repeat_input: Inputs::NONE,
+            key_repeat_held_time: 0.0,
+            key_repeat_count: 0,
+            touch_options: TouchOptions::DEFAULT,
+            touch: None,
+            touch_release_next_frame: Inputs::NONE,
+            delta_time: None,
+            next_delta_time: None,
+            time: 0.0,
             cursor_position: Vec2::ZERO,
-            inputs_pressed: Inputs::empty(),
-            inputs_released: Inputs::empty(),
+            cursor_viewport: None,
+            input_events: ArrayVec::new(),
+            overflow_inputs_pressed: Inputs::empty(),
+            overflow_inputs_released: Inputs::empty(),
+
+            claimed_input_events: 0,
             modifiers: Modifiers::empty(),
             received_characters: ArrayString::new(),
-            clipboard_getter: empty_clipboard_getter,
-            clipboard_setter: empty_clipboard_setter,
+            clipboard_getter: None,
+            clipboard_setter: None,
+            clipboard_buffer: VecString::new_in(a6),
 
             active_ctrl_idx: None,
             hovered_ctrl_idx: None,
             hovered_capturing_ctrl_idx: None,
+            modal_ctrl_idx: None,
+            build_modal_ctrl_idx: None,
+            hovered_capturing_ctrl_duration: 0.0,
+
+            // NB: Not allocated up front, because animations are opt-in.
+            ctrl_animations: HashMap::new_in(a12),
+            animated_values: HashMap::new_in(a22),
+            toasts: Vec::new_in(a23),
+            loaded_ctrl_states: HashMap::new_in(a16),
 
             last_ctrl_idx: None,
 
+            scrollbar_drag_ctrl_idx: None,
+            scrollbar_drag_vertical: false,
+            scrollbar_drag_start_cursor_position: Vec2::ZERO,
+            scrollbar_drag_start_scroll_offset: Vec2::ZERO,
+
+            drag: None,
+
+            cursor_icon: CursorIcon::Default,
+
+            debug_overlay: false,
+
+            frame_stats: FrameStats::default(),
+            build_glyph_count: 0,
+
+            build_want_capture_keyboard_ctrl_idx: None,
+            build_want_text_input_ctrl_idx: None,
+            build_want_capture_mouse: false,
+
             want_capture_keyboard: false,
             want_capture_mouse: false,
+            want_text_input: false,
         }
     }
 
@@ -515,6 +1598,48 @@ impl<A: Allocator + Clone> Ui<A> {
         self.font_atlas_texture_id = font_atlas_texture_id;
     }
 
+    /// Regenerates the font atlas for a new font size or rasterization scale
+    /// factor, e.g. when the window moves to a monitor with a higher scale
+    /// factor. This bumps [`Ui::font_atlas_generation`]. The renderer should
+    /// then upload the new atlas image, and if that means creating a new
+    /// texture, pass its id to [`Ui::set_font_atlas_texture_id`].
+    #[cfg(feature = "runtime_rasterize")]
+    pub fn rebuild_font_atlas(&mut self, font_size: f32, font_rasterization_scale_factor: f32) {
+        self.font_atlas
+            .rebuild(font_size, font_rasterization_scale_factor);
+        if self.premultiplied_alpha {
+            self.font_atlas.set_premultiplied_alpha(true);
+        }
+
+        self.font_atlas_generation = self.font_atlas_generation.wrapping_add(1);
+    }
+
+    /// Switches the font atlas between glyph coverage and signed distance
+    /// fields, and regenerates it. This bumps [`Ui::font_atlas_generation`].
+    /// The renderer must sample the atlas differently for distance fields. See
+    /// [`FontAtlas::sdf`].
+    #[cfg(feature = "runtime_rasterize")]
+    pub fn set_font_atlas_sdf(&mut self, sdf: bool) {
+        self.font_atlas.set_sdf(sdf);
+        if self.premultiplied_alpha {
+            self.font_atlas.set_premultiplied_alpha(true);
+        }
+
+        self.font_atlas_generation = self.font_atlas_generation.wrapping_add(1);
+    }
+
+    /// Whether the font atlas contains signed distance fields, so that the
+    /// renderer can pick the matching fragment shader.
+    pub fn font_atlas_sdf(&self) -> bool {
+        self.font_atlas.sdf()
+    }
+
+    /// Returns a number that changes every time the font atlas image changes,
+    /// so that renderers can detect when to upload it again.
+    pub fn font_atlas_generation(&self) -> u64 {
+        self.font_atlas_generation
+    }
+
     pub fn set_window_size(&mut self, window_width: f32, window_height: f32) {
         self.window_size = Vec2::new(window_width, window_height);
     }
@@ -523,20 +1648,201 @@ impl<A: Allocator + Clone> Ui<A> {
         self.window_scale_factor = window_scale_factor;
     }
 
+    /// Enables or disables smooth scrolling. When enabled, scrolling moves the
+    /// control's target scroll offset, and the actual scroll offset approaches
+    /// it exponentially, covering ~63% of the remaining distance every
+    /// time_constant seconds. This requires the frame time to be passed to
+    /// [`Ui::begin_frame_with_delta_time`] or [`Ui::set_delta_time`],
+    /// otherwise scrolling happens instantly, as if smoothing was disabled.
+    pub fn set_scroll_smoothing(&mut self, time_constant: Option<f32>) {
+        self.scroll_smoothing_time_constant = time_constant;
+    }
+
+    /// Enables or disables key repeat. When enabled, holding a navigation or
+    /// deletion key, e.g. an arrow key or backspace, synthesizes repeated
+    /// [`InputEvent::KeyPress`] events, at most one per frame. Timing uses the
+    /// frame time passed to [`Ui::begin_frame_with_delta_time`], or assumes
+    /// 60 frames per second without it.
+    ///
+    /// Disabled by default, because many platforms already send repeated
+    /// presses for held keys.
+    pub fn set_key_repeat(&mut self, key_repeat: Option<KeyRepeat>) {
+        self.key_repeat = key_repeat;
+        self.key_repeat_input = Inputs::NONE;
+    }
+
+    /// Sets the distance between tab stops in text, in multiples of the
+    /// advance width of space. Tabs advance the text to the next tab stop. Zero
+    /// disables tab stops, and tabs advance by their width in the font instead.
+    /// Defaults to 4.
+    pub fn set_tab_width(&mut self, tab_width: u32) {
+        self.tab_width = tab_width;
+    }
+
+    /// Switches the draw list output between straight and premultiplied
+    /// alpha. Defaults to straight alpha.
+    ///
+    /// With straight alpha, vertex colors and the font atlas image are not
+    /// premultiplied, and the renderer should blend with `src * src_alpha +
+    /// dst * (1 - src_alpha)` for color and alpha.
+    ///
+    /// With premultiplied alpha, vertex colors are premultiplied with their
+    /// alpha, the font atlas image is converted to premultiplied alpha, and
+    /// the renderer should blend with `src + dst * (1 - src_alpha)` for color
+    /// and alpha. Textures provided by the user (e.g. for images) must be
+    /// premultiplied too. This composites correctly over non-opaque
+    /// backgrounds.
+    ///
+    /// Changing this modifies the font atlas image, so it must be uploaded to
+    /// the renderer again (see [`Ui::font_atlas_generation`]).
+    pub fn set_premultiplied_alpha(&mut self, premultiplied_alpha: bool) {
+        if self.premultiplied_alpha != premultiplied_alpha {
+            self.premultiplied_alpha = premultiplied_alpha;
+            self.font_atlas.set_premultiplied_alpha(premultiplied_alpha);
+            self.font_atlas_generation = self.font_atlas_generation.wrapping_add(1);
+            self.draw_list.set_premultiplied_alpha(premultiplied_alpha);
+            for viewport in &mut self.viewports {
+                viewport
+                    .draw_list
+                    .set_premultiplied_alpha(premultiplied_alpha);
+            }
+        }
+    }
+
+    pub fn premultiplied_alpha(&self) -> bool {
+        self.premultiplied_alpha
+    }
+
+    /// Switches the color space of vertex colors in the draw list between
+    /// sRGB and linear. Defaults to sRGB.
+    ///
+    /// Colors in themes and passed to widgets are always sRGB. If the renderer
+    /// draws to a non-sRGB surface (e.g. `Bgra8Unorm`), the shader outputs
+    /// colors as they are, so keep this disabled. Blending then happens in
+    /// sRGB space, which is what most UI toolkits do.
+    ///
+    /// If the renderer draws to an sRGB surface (e.g. `Bgra8UnormSrgb`), the
+    /// GPU encodes the shader output to sRGB when writing it, and blends in
+    /// linear space. Enable this, so that vertex colors are linearized first
+    /// and don't come out washed out. User textures with color data should
+    /// then be uploaded with sRGB formats too, so that sampling them
+    /// linearizes them. The font atlas holds coverage, not color, and should
+    /// stay in a non-sRGB format.
+    ///
+    /// Vertex colors stay 8 bits per channel, so very dark linearized colors
+    /// lose some precision.
+    pub fn set_linear_color_output(&mut self, linear_color_output: bool) {
+        self.linear_color_output = linear_color_output;
+        self.draw_list.set_linear_color_output(linear_color_output);
+        for viewport in &mut self.viewports {
+            viewport
+                .draw_list
+                .set_linear_color_output(linear_color_output);
+        }
+    }
+
+    pub fn linear_color_output(&self) -> bool {
+        self.linear_color_output
+    }
+
+    /// Enables or disables anti-aliasing of lines, circles and rounded rects.
+    /// When enabled, their edges get a translucent fringe one physical pixel
+    /// wide, which increases the vertex count. Rects and text are not
+    /// affected. Defaults to disabled.
+    pub fn set_anti_aliasing(&mut self, anti_aliasing: bool) {
+        self.anti_aliasing = anti_aliasing;
+    }
+
+    pub fn anti_aliasing(&self) -> bool {
+        self.anti_aliasing
+    }
+
+    /// Enables or disables snapping glyph quads to whole physical pixels. The
+    /// font atlas is rasterized at pixel size, so snapping keeps text crisp
+    /// when it would otherwise land on fractional positions, at the cost of
+    /// slightly uneven glyph spacing. Defaults to disabled.
+    pub fn set_text_pixel_snap(&mut self, text_pixel_snap: bool) {
+        self.text_pixel_snap = text_pixel_snap;
+    }
+
+    pub fn text_pixel_snap(&self) -> bool {
+        self.text_pixel_snap
+    }
+
+    /// Enables or disables snapping the edges of control borders, backgrounds
+    /// and solid color rects to whole physical pixels, so that thin borders
+    /// and separators stay sharp at fractional window scale factors. Rects
+    /// that are not empty stay at least one physical pixel wide and tall.
+    /// Glyphs and images are not affected, see
+    /// [`Ui::set_text_pixel_snap`] for text. Defaults to disabled.
+    pub fn set_pixel_snapping(&mut self, pixel_snapping: bool) {
+        self.pixel_snapping = pixel_snapping;
+    }
+
+    pub fn pixel_snapping(&self) -> bool {
+        self.pixel_snapping
+    }
+
+    /// Sets the theme used by widget functions that don't take a theme
+    /// argument. The `*_with_theme` variants still use the theme they are
+    /// given. Defaults to [`Theme::DEFAULT`].
+    pub fn set_theme(&mut self, theme: Theme) {
+        *self.theme_stack[0] = theme;
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme_stack[0]
+    }
+
     pub fn scroll(&mut self, delta_x: f32, delta_y: f32) {
-        self.scroll_delta += Vec2::new(delta_x, delta_y);
+        let delta = Vec2::new(delta_x, delta_y);
+
+        self.scroll_delta += delta;
+
+        // Consecutive scrolls are merged, so that a burst of them from a
+        // trackpad doesn't fill the event queue.
+        if let Some(InputEvent::Scroll(last_delta)) = self.input_events.last_mut() {
+            *last_delta += delta;
+        } else {
+            let _ = self.input_events.try_push(InputEvent::Scroll(delta));
+        }
     }
 
     pub fn set_cursor_position(&mut self, cursor_x: f32, cursor_y: f32) {
         self.cursor_position = Vec2::new(cursor_x, cursor_y);
+        self.cursor_viewport = None;
+    }
+
+    /// Like [`Ui::set_cursor_position`], but the position is relative to the
+    /// viewport with the id, e.g. when the cursor moves over a second OS
+    /// window. Only controls in that viewport can be hovered until the cursor
+    /// position is set again. See [`Frame::begin_viewport`].
+    pub fn set_cursor_position_for_viewport(&mut self, id: u32, cursor_x: f32, cursor_y: f32) {
+        self.cursor_position = Vec2::new(cursor_x, cursor_y);
+        self.cursor_viewport = Some(id);
     }
 
     pub fn press_inputs(&mut self, inputs: Inputs) {
-        self.inputs_pressed |= inputs;
+        let event = InputEvent::KeyPress {
+            input: inputs,
+            modifiers: self.modifiers,
+            repeat: false,
+        };
+
+        if self.input_events.try_push(event).is_err() {
+            self.overflow_inputs_pressed |= inputs;
+        }
     }
 
     pub fn release_inputs(&mut self, inputs: Inputs) {
-        self.inputs_released |= inputs;
+        let event = InputEvent::KeyRelease {
+            input: inputs,
+            modifiers: self.modifiers,
+        };
+
+        if self.input_events.try_push(event).is_err() {
+            self.overflow_inputs_released |= inputs;
+        }
     }
 
     pub fn set_modifiers(&mut self, modifiers: Modifiers) {
@@ -552,15 +1858,144 @@ impl<A: Allocator + Clone> Ui<A> {
     }
 
     pub fn send_character(&mut self, character: char) {
+        let _ = self.input_events.try_push(InputEvent::Char(character));
         let _ = self.received_characters.try_push(character);
     }
 
-    pub fn set_clipboard_getter(&mut self, getter: fn() -> String) {
-        self.clipboard_getter = getter;
+    /// Sets how touches are turned into taps, scrolling and long presses. See
+    /// [`Ui::touch_start`].
+    pub fn set_touch_options(&mut self, touch_options: TouchOptions) {
+        self.touch_options = touch_options;
+    }
+
+    /// Starts a touch at the logical position. The first touch while no other
+    /// is held moves the cursor, so that existing widgets work on touch
+    /// screens:
+    ///
+    /// - Lifting the finger before it moves further than
+    ///   [`TouchOptions::slop`] is a tap, i.e. a left click.
+    /// - Moving further scrolls the control under the touch, if it can scroll,
+    ///   and otherwise presses the left mouse button, e.g. to drag a slider.
+    /// - Holding the finger in place for
+    ///   [`TouchOptions::long_press_duration`] is a right click, e.g. to open
+    ///   a context menu. This is measured with the frame time, so it only
+    ///   happens if the platform keeps building frames.
+    ///
+    /// Other touches are ignored for now, but their ids are passed in, so that
+    /// multi-touch gestures can be recognized later.
+    pub fn touch_start(&mut self, id: u64, x: f32, y: f32) {
+        if self.touch.is_some() {
+            return;
+        }
+
+        let position = Vec2::new(x, y);
+        self.set_cursor_position(x, y);
+        self.touch = Some(Touch {
+            id,
+            start_position: position,
+            last_position: position,
+            held_time: 0.0,
+            state: TouchState::Pending,
+        });
+    }
+
+    pub fn touch_move(&mut self, id: u64, x: f32, y: f32) {
+        let Some(mut touch) = self.touch.filter(|touch| touch.id == id) else {
+            return;
+        };
+
+        let position = Vec2::new(x, y);
+
+        match touch.state {
+            TouchState::Pending => {
+                let distance_squared = (position - touch.start_position).length_squared();
+                if distance_squared > self.touch_options.slop * self.touch_options.slop {
+                    // NB: The cursor stays where the touch started for this
+                    // move, so that the press lands on what was touched.
+                    if self.hovered_ctrl_can_scroll() {
+                        touch.state = TouchState::Scrolling;
+                        let delta = position - touch.start_position;
+                        self.scroll(delta.x, delta.y);
+                    } else {
+                        touch.state = TouchState::Pressed;
+                        self.press_inputs(Inputs::MB_LEFT);
+                    }
+                }
+            }
+            TouchState::Scrolling => {
+                let delta = position - touch.last_position;
+                self.scroll(delta.x, delta.y);
+            }
+            TouchState::Pressed | TouchState::LongPressed => {
+                self.set_cursor_position(x, y);
+            }
+        }
+
+        touch.last_position = position;
+        self.touch = Some(touch);
+    }
+
+    pub fn touch_end(&mut self, id: u64) {
+        let Some(touch) = self.touch.filter(|touch| touch.id == id) else {
+            return;
+        };
+
+        match touch.state {
+            TouchState::Pending => {
+                self.press_inputs(Inputs::MB_LEFT);
+                self.touch_release_next_frame |= Inputs::MB_LEFT;
+            }
+            // TODO(yan): Keep scrolling with the touch's momentum.
+            TouchState::Scrolling => (),
+            TouchState::Pressed => self.release_inputs(Inputs::MB_LEFT),
+            TouchState::LongPressed => self.release_inputs(Inputs::MB_RIGHT),
+        }
+
+        self.touch = None;
+    }
+
+    /// Like [`Ui::touch_end`], but a pending touch isn't a tap, e.g. when the
+    /// platform took over the gesture.
+    pub fn touch_cancel(&mut self, id: u64) {
+        if let Some(touch) = self.touch.filter(|touch| touch.id == id) {
+            if touch.state == TouchState::Pending {
+                self.touch = None;
+            } else {
+                self.touch_end(id);
+            }
+        }
+    }
+
+    fn hovered_ctrl_can_scroll(&self) -> bool {
+        let mut ctrl_idx = self.hovered_ctrl_idx;
+        while let Some(idx) = ctrl_idx {
+            let ctrl = &self.tree[idx];
+            if ctrl.flags.intersects(CtrlFlags::CAPTURE_SCROLL) && scroll_size(ctrl) != Vec2::ZERO {
+                return true;
+            }
+
+            ctrl_idx = ctrl.parent_idx.map(CtrlIdx::get);
+        }
+
+        false
+    }
+
+    /// Sets the function used to read text from the platform clipboard.
+    ///
+    /// The getter receives an empty text buffer owned by the UI and should
+    /// extend it with the clipboard contents. The buffer is reused between
+    /// calls, so it only allocates (using the UI's allocator) when the
+    /// clipboard contents outgrow it. If no getter is set, pasting does
+    /// nothing.
+    pub fn set_clipboard_getter(&mut self, getter: fn(&mut dyn TextStorage)) {
+        self.clipboard_getter = Some(getter);
     }
 
+    /// Sets the function used to write text to the platform clipboard. The
+    /// text is only borrowed for the duration of the call. If no setter is
+    /// set, copying does nothing.
     pub fn set_clipboard_setter(&mut self, setter: fn(&str)) {
-        self.clipboard_setter = setter;
+        self.clipboard_setter = Some(setter);
     }
 
     pub fn font_atlas(&self) -> &FontAtlas<A> {
@@ -583,14 +2018,105 @@ impl<A: Allocator + Clone> Ui<A> {
         self.tree.len()
     }
 
+    /// Whether the UI wants to receive keyboard input, and the platform should
+    /// not handle it on its own.
+    ///
+    /// This is computed in [`Ui::end_frame`] and stays valid until the next
+    /// frame ends, so it can be queried at any time between frames, e.g. when
+    /// dispatching events. It describes the last built frame, which means there
+    /// is a one frame latency: the keypress that activates a control is not
+    /// captured, but all keypresses after it are.
+    ///
+    /// Keyboard is captured only if the active control requested it.
     pub fn want_capture_keyboard(&self) -> bool {
         self.want_capture_keyboard
     }
 
+    /// Whether the UI wants to receive mouse input, and the platform should
+    /// not handle it on its own. Like [`Ui::want_capture_keyboard`], this is
+    /// computed in [`Ui::end_frame`] and describes the last built frame.
+    ///
+    /// Mouse is captured if a hover-capturing control is hovered, or if a
+    /// control requested it.
     pub fn want_capture_mouse(&self) -> bool {
         self.want_capture_mouse
     }
 
+    /// Whether a text-editing control is active. Platforms can use this to
+    /// enable IME or show an on-screen keyboard. Like
+    /// [`Ui::want_capture_keyboard`], this is computed in [`Ui::end_frame`]
+    /// and describes the last built frame.
+    pub fn want_text_input(&self) -> bool {
+        self.want_text_input
+    }
+
+    /// Whether a modal control was built last frame, see [`Ctrl::set_modal`].
+    /// Controls outside of it can't be hovered or become active until it
+    /// closes.
+    pub fn modal_active(&self) -> bool {
+        self.modal_ctrl_idx.is_some()
+    }
+
+    /// The cursor icon requested by controls in the last built frame. Only
+    /// valid after [`Ui::end_frame`], the platform should map this to its own
+    /// cursor representation.
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.cursor_icon
+    }
+
+    /// The persistent id of the control capturing hover, if any. Stays valid
+    /// between frames, so that the platform or application can tell which
+    /// control the cursor is over. Disabled controls are never reported.
+    ///
+    /// See [`Ctrl::persistent_id`].
+    pub fn hovered_ctrl_id(&self) -> Option<u64> {
+        self.enabled_ctrl_persistent_id(self.hovered_capturing_ctrl_idx)
+    }
+
+    /// The persistent id of the active control, if any. Like
+    /// [`Ui::hovered_ctrl_id`], disabled controls are never reported.
+    pub fn active_ctrl_id(&self) -> Option<u64> {
+        self.enabled_ctrl_persistent_id(self.active_ctrl_idx)
+    }
+
+    fn enabled_ctrl_persistent_id(&self, ctrl_idx: Option<usize>) -> Option<u64> {
+        let ctrl = &self.tree[ctrl_idx?];
+        if ctrl.disabled {
+            None
+        } else {
+            Some(ctrl.persistent_id)
+        }
+    }
+
+    /// Enables or disables the debug overlay. When enabled,
+    /// [`Ui::end_frame`] outlines every control on top of everything else,
+    /// colored by layout: free in magenta, horizontal in yellow and vertical
+    /// in cyan. Holding Ctrl+Shift shows the id path, flags and layout
+    /// metrics of the hovered control next to the cursor. Defaults to
+    /// disabled.
+    pub fn set_debug_overlay(&mut self, debug_overlay: bool) {
+        self.debug_overlay = debug_overlay;
+    }
+
+    pub fn debug_overlay(&self) -> bool {
+        self.debug_overlay
+    }
+
+    /// Walks the controls of the last built frame depth-first, the base layer
+    /// first and the overlay second, and reports a read-only snapshot of
+    /// each, e.g. for building an inspector with regular widgets. The roots
+    /// of the layers are not reported.
+    pub fn debug_ctrls(&self) -> DebugCtrls<'_> {
+        DebugCtrls::new(&self.tree)
+    }
+
+    /// Returns counters collected while building and rendering the last
+    /// frame. Like [`Ui::want_capture_keyboard`], this is computed in
+    /// [`Ui::end_frame`].
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
     pub fn draw_list(&self) -> (&[Command], &[Vertex], &[u32]) {
         (
             self.draw_list.commands(),
@@ -599,11 +2125,97 @@ impl<A: Allocator + Clone> Ui<A> {
         )
     }
 
-    pub fn begin_frame(&mut self) -> Frame<'_, A> {
+    /// Like [`Ui::draw_list`], but for the viewport with the id, so that each
+    /// OS window can render only its own controls. The draw lists are empty
+    /// for viewports not built in the last frame. See
+    /// [`Frame::begin_viewport`].
+    pub fn draw_list_for_viewport(&self, id: u32) -> (&[Command], &[Vertex], &[u32]) {
+        match self.viewports.iter().find(|viewport| viewport.id == id) {
+            Some(viewport) => (
+                viewport.draw_list.commands(),
+                viewport.draw_list.vertices(),
+                viewport.draw_list.indices(),
+            ),
+            None => (&[], &[], &[]),
+        }
+    }
+
+    /// Like [`Ui::draw_list`], but with 16-bit indices, for renderers that
+    /// can't use 32-bit indices. Draw commands are split so that the indices
+    /// stay in range, and each command's indices are relative to its
+    /// [`Command::base_vertex`].
+    pub fn draw_list_u16(&mut self) -> (&[Command], &[Vertex], &[u16]) {
+        self.draw_list.u16()
+    }
+
+    /// Calls the function with the persistent id and state of each control that
+    /// was marked with [`Ctrl::mark_persistent`] in the last frame, e.g. to
+    /// save window positions when the application exits. Should be called
+    /// between frames. Controls that never asked for state are skipped.
+    ///
+    /// The saved states can be passed to [`Ui::load_persistent_state`] when
+    /// the application starts again. Persistent ids are stable across runs, as
+    /// long as the ids of the control and its ancestors stay the same: each is
+    /// the 64-bit FNV-1a hash of the parent's persistent id followed by the
+    /// control's id, both as little endian bytes. The roots of the base layer
+    /// and the overlay have persistent ids 0 and 1. The state is passed as is,
+    /// so its layout is up to the widget that owns it.
+    pub fn save_persistent_state<F>(&self, mut f: F)
+    where
+        F: FnMut(u64, &CtrlState),
+    {
+        for ctrl in &self.tree {
+            if ctrl.persistent {
+                if let Some(state_idx) = ctrl.state_idx {
+                    f(ctrl.persistent_id, &self.ctrl_states[state_idx].0);
+                }
+            }
+        }
+    }
+
+    /// Seeds the state of the control with the persistent id, once the control
+    /// is built, e.g. to restore window positions saved by
+    /// [`Ui::save_persistent_state`] in a previous run. Should be called
+    /// before the first frame, or at least before the control is built for the
+    /// first time, as the state of existing controls is not overwritten.
+    pub fn load_persistent_state(&mut self, persistent_id: u64, state: &CtrlState) {
+        self.loaded_ctrl_states
+            .insert(persistent_id, AlignedCtrlState(*state));
+    }
+
+    /// Returns statistics about the draw list built in the last frame,
+    /// including how many commands were saved by merging.
+    pub fn draw_list_stats(&self) -> DrawListStats {
+        self.draw_list.stats()
+    }
+
+    /// Sets the time in seconds since the last frame for the next
+    /// [`Ui::begin_frame`], for platforms that measure time separately from
+    /// building the frame. Has to be set again for each frame. Setting it
+    /// again before the frame begins replaces the previous value.
+    pub fn set_delta_time(&mut self, delta_time: f32) {
+        self.next_delta_time = Some(delta_time);
+    }
+
+    fn do_begin_frame<'a>(&'a mut self, temp_allocator: &'a (dyn Allocator + 'a)) -> Frame<'a, A> {
+        let delta_time = self.next_delta_time.take();
+        self.delta_time = delta_time;
+        self.time += delta_time.unwrap_or(FALLBACK_DELTA_TIME);
+        self.repeat_held_key(delta_time);
+        self.advance_touch(delta_time);
+        self.age_toasts(delta_time);
+
         self.draw_primitives.clear();
         self.draw_list.clear();
-        self.want_capture_keyboard = false;
-        self.want_capture_mouse = false;
+        for viewport in &mut self.viewports {
+            viewport.draw_list.clear();
+        }
+        self.build_want_capture_keyboard_ctrl_idx = None;
+        self.build_want_text_input_ctrl_idx = None;
+        self.build_want_capture_mouse = false;
+        self.build_modal_ctrl_idx = None;
+        self.cursor_icon = CursorIcon::Default;
+        self.build_glyph_count = 0;
 
         self.current_frame = self.current_frame.wrapping_add(1);
 
@@ -630,20 +2242,62 @@ impl<A: Allocator + Clone> Ui<A> {
         // TODO(yan): Audit this. Not sure why we look for hovered node in the
         // base layer if we don't find hover-capturing node in the overlay.
         //
+        // Only controls in the viewport the cursor is in are considered. The
+        // roots span the whole viewport, as its size can differ from the
+        // window size.
+        //
+        // While a modal control is open, only it, its descendants and the
+        // overlays they own can be hovered. If something else in the overlay
+        // is under the cursor, we look just at the modal control.
+        //
+        let prev_hovered_capturing_ctrl_idx = self.hovered_capturing_ctrl_idx;
+
+        let cursor_viewport_size = match self.cursor_viewport {
+            Some(id) => self
+                .viewports
+                .iter()
+                .find(|viewport| viewport.id == id)
+                .map_or(Vec2::ZERO, |viewport| viewport.size),
+            None => self.window_size,
+        };
+        let cursor_viewport_rect = Rect::from_points(Vec2::ZERO, cursor_viewport_size);
+
         self.hovered_capturing_ctrl_idx = None;
         self.hovered_ctrl_idx = find_hovered_ctrl(
             &self.tree,
             OVERLAY_ROOT_IDX,
             self.cursor_position,
-            &self.allocator,
+            self.cursor_viewport,
+            cursor_viewport_rect,
+            &mut self.scratch_siblings,
         );
 
+        if let Some(modal_ctrl_idx) = self.modal_ctrl_idx {
+            let hovered_in_modal = self
+                .hovered_ctrl_idx
+                .map_or(false, |idx| ctrl_is_within(&self.tree, idx, modal_ctrl_idx));
+
+            if !hovered_in_modal {
+                self.hovered_ctrl_idx = find_hovered_ctrl(
+                    &self.tree,
+                    modal_ctrl_idx,
+                    self.cursor_position,
+                    self.cursor_viewport,
+                    cursor_viewport_rect,
+                    &mut self.scratch_siblings,
+                );
+            }
+        }
+
         if let Some(hovered_ctrl_idx) = self.hovered_ctrl_idx {
             let mut ctrl_idx = hovered_ctrl_idx;
             let mut ctrl = &self.tree[hovered_ctrl_idx];
 
-            while !ctrl.flags.intersects(CtrlFlags::CAPTURE_HOVER) && ctrl.parent_idx.is_some() {
-                let parent_idx = ctrl.parent_idx.unwrap();
+            while !ctrl.flags.intersects(CtrlFlags::CAPTURE_HOVER)
+                && ctrl.parent_idx.is_some()
+                && Some(ctrl_idx) != self.modal_ctrl_idx
+            {
+                let parent_idx = ctrl.parent_idx.unwrap().get();
 
                 ctrl_idx = parent_idx;
                 ctrl = &self.tree[parent_idx];
@@ -651,13 +2305,18 @@ impl<A: Allocator + Clone> Ui<A> {
 
             if ctrl.flags.intersects(CtrlFlags::CAPTURE_HOVER) {
                 self.hovered_capturing_ctrl_idx = Some(ctrl_idx);
-                self.want_capture_mouse = true;
             }
         }
 
-        if self.hovered_capturing_ctrl_idx == None {
-            self.hovered_ctrl_idx =
-                find_hovered_ctrl(&self.tree, ROOT_IDX, self.cursor_position, &self.allocator);
+        if self.hovered_capturing_ctrl_idx == None && self.modal_ctrl_idx.is_none() {
+            self.hovered_ctrl_idx = find_hovered_ctrl(
+                &self.tree,
+                ROOT_IDX,
+                self.cursor_position,
+                self.cursor_viewport,
+                cursor_viewport_rect,
+                &mut self.scratch_siblings,
+            );
         }
 
         if let Some(hovered_ctrl_idx) = self.hovered_ctrl_idx {
@@ -665,7 +2324,7 @@ impl<A: Allocator + Clone> Ui<A> {
             let mut ctrl = &self.tree[hovered_ctrl_idx];
 
             while !ctrl.flags.intersects(CtrlFlags::CAPTURE_HOVER) && ctrl.parent_idx.is_some() {
-                let parent_idx = ctrl.parent_idx.unwrap();
+                let parent_idx = ctrl.parent_idx.unwrap().get();
 
                 ctrl_idx = parent_idx;
                 ctrl = &self.tree[parent_idx];
@@ -673,23 +2332,80 @@ impl<A: Allocator + Clone> Ui<A> {
 
             if ctrl.flags.intersects(CtrlFlags::CAPTURE_HOVER) {
                 self.hovered_capturing_ctrl_idx = Some(ctrl_idx);
-                self.want_capture_mouse = true;
             }
         }
 
-        fn find_hovered_ctrl<T: Allocator>(
+        // Controls outside of the modal control can't stay active either, e.g.
+        // a text input focused before the modal opened.
+        if let (Some(modal_ctrl_idx), Some(active_ctrl_idx)) =
+            (self.modal_ctrl_idx, self.active_ctrl_idx)
+        {
+            if !ctrl_is_within(&self.tree, active_ctrl_idx, modal_ctrl_idx) {
+                self.active_ctrl_idx = None;
+            }
+        }
+
+        // Tell the controls that asked last frame, whether a mouse button was
+        // pressed outside of them and the overlays they own.
+        //
+        // TODO(yan): @Speed This visits the whole tree, but only a few
+        // controls ask.
+        let mouse_pressed = self
+            .inputs_pressed()
+            .intersects(Inputs::MB_LEFT | Inputs::MB_RIGHT | Inputs::MB_MIDDLE);
+        for ctrl_idx in 0..self.tree.len() {
+            let ctrl = &self.tree[ctrl_idx];
+            let clicked_outside = mouse_pressed
+                && ctrl.close_on_outside_click
+                && !self
+                    .hovered_ctrl_idx
+                    .map_or(false, |idx| ctrl_is_within(&self.tree, idx, ctrl_idx));
+
+            self.tree[ctrl_idx].clicked_outside = clicked_outside;
+        }
+
+        if self.hovered_capturing_ctrl_idx.is_some()
+            && self.hovered_capturing_ctrl_idx == prev_hovered_capturing_ctrl_idx
+        {
+            self.hovered_capturing_ctrl_duration += delta_time.unwrap_or(0.0);
+        } else {
+            self.hovered_capturing_ctrl_duration = 0.0;
+        }
+
+        if let Some(drag) = &mut self.drag {
+            const DRAG_THRESHOLD: f32 = 4.0;
+
+            let distance_squared =
+                (self.cursor_position - drag.start_cursor_position).length_squared();
+            if distance_squared > DRAG_THRESHOLD * DRAG_THRESHOLD {
+                drag.dragging = true;
+            }
+        }
+
+        fn find_hovered_ctrl<A: Allocator>(
             tree: &[CtrlNode],
             ctrl_idx: usize,
             cursor_position: Vec2,
-            temp_allocator: &T,
+            viewport: Option<u32>,
+            viewport_rect: Rect,
+            siblings: &mut Vec<(usize, (i32, u32)), A>,
         ) -> Option<usize> {
             let ctrl = &tree[ctrl_idx];
-            let ctrl_rect_absolute = Rect::new(
-                ctrl.layout_cache_absolute_position.x,
-                ctrl.layout_cache_absolute_position.y,
-                ctrl.rect.width,
-                ctrl.rect.height,
-            );
+            let is_root = ctrl.parent_idx.is_none();
+            if !is_root && ctrl.viewport != viewport {
+                return None;
+            }
+
+            let ctrl_rect_absolute = if is_root {
+                viewport_rect
+            } else {
+                Rect::new(
+                    ctrl.layout_cache_absolute_position.x,
+                    ctrl.layout_cache_absolute_position.y,
+                    ctrl.rect.width,
+                    ctrl.rect.height,
+                )
+            };
 
             if ctrl_rect_absolute.contains_point(cursor_position) {
                 if ctrl.layout == Layout::Free {
@@ -697,49 +2413,75 @@ impl<A: Allocator + Clone> Ui<A> {
                     // of controls when determining hover. The most recently
                     // active control (on top) has priority when determining
                     // hover, followed by the next most recently active control,
-                    // all the way up to the least recently active control.
-
-                    let mut siblings: Vec<(usize, u32), _> = Vec::new_in(temp_allocator);
-                    if let Some(child_idx) = ctrl.child_idx {
+                    // all the way up to the least recently active control. An
+                    // explicit z index takes precedence over all that.
+                    //
+                    // NB: Nested free layouts push their siblings past ours,
+                    // and truncate them before returning, so the indices of
+                    // ours stay valid.
+                    let siblings_start = siblings.len();
+                    if let Some(child_idx) = ctrl.child_idx.map(CtrlIdx::get) {
                         let mut child = &tree[child_idx];
-                        siblings.push((child_idx, child.last_frame_in_active_path));
+                        siblings.push((child_idx, render_order_key(child)));
 
-                        while let Some(sibling_idx) = child.sibling_idx {
+                        while let Some(sibling_idx) = child.sibling_idx.map(CtrlIdx::get) {
                             child = &tree[sibling_idx];
-                            siblings.push((sibling_idx, child.last_frame_in_active_path));
+                            siblings.push((sibling_idx, render_order_key(child)));
                         }
                     }
 
-                    siblings.sort_unstable_by_key(|&(_, frame)| frame);
+                    let siblings_end = siblings.len();
+                    siblings[siblings_start..].sort_unstable_by_key(|&(_, key)| key);
 
-                    for (sibling_idx, _) in siblings.into_iter().rev() {
-                        if let Some(hovered_ctrl) =
-                            find_hovered_ctrl(tree, sibling_idx, cursor_position, temp_allocator)
-                        {
+                    // This control is hovered, but maybe none of its children
+                    // are.
+                    let mut hovered_ctrl = Some(ctrl_idx);
+                    for i in (siblings_start..siblings_end).rev() {
+                        let (sibling_idx, _) = siblings[i];
+                        if let Some(hovered_child_ctrl) = find_hovered_ctrl(
+                            tree,
+                            sibling_idx,
+                            cursor_position,
+                            viewport,
+                            viewport_rect,
+                            siblings,
+                        ) {
                             // This control is hovered, but also one of its
                             // children is.
-                            return Some(hovered_ctrl);
+                            hovered_ctrl = Some(hovered_child_ctrl);
+                            break;
                         }
                     }
 
-                    // This control is hovered, but none of its children are.
-                    Some(ctrl_idx)
-                } else if let Some(child_idx) = ctrl.child_idx {
-                    if let Some(hovered_ctrl) =
-                        find_hovered_ctrl(tree, child_idx, cursor_position, temp_allocator)
-                    {
+                    siblings.truncate(siblings_start);
+
+                    hovered_ctrl
+                } else if let Some(child_idx) = ctrl.child_idx.map(CtrlIdx::get) {
+                    if let Some(hovered_ctrl) = find_hovered_ctrl(
+                        tree,
+                        child_idx,
+                        cursor_position,
+                        viewport,
+                        viewport_rect,
+                        siblings,
+                    ) {
                         // This control is hovered, but also one of its
                         // children is.
                         return Some(hovered_ctrl);
                     }
 
                     let mut child = &tree[child_idx];
-                    while let Some(sibling_idx) = child.sibling_idx {
+                    while let Some(sibling_idx) = child.sibling_idx.map(CtrlIdx::get) {
                         child = &tree[sibling_idx];
 
-                        if let Some(hovered_ctrl) =
-                            find_hovered_ctrl(tree, sibling_idx, cursor_position, temp_allocator)
-                        {
+                        if let Some(hovered_ctrl) = find_hovered_ctrl(
+                            tree,
+                            sibling_idx,
+                            cursor_position,
+                            viewport,
+                            viewport_rect,
+                            siblings,
+                        ) {
                             // This control is hovered, but also one of its
                             // children is.
                             return Some(hovered_ctrl);
@@ -758,6 +2500,87 @@ impl<A: Allocator + Clone> Ui<A> {
             }
         }
 
+        //
+        // Drag scroll bars.
+        //
+        // Pressing the scroll bar thumb of the hovered control (or one of its
+        // ancestors) starts a drag, which lasts until the mouse button is
+        // released. The press is consumed, so that controls under the scroll
+        // bar don't react to it.
+        //
+        if let Some(ctrl_idx) = self.scrollbar_drag_ctrl_idx {
+            let lmb_released = self.overflow_inputs_released.contains(Inputs::MB_LEFT)
+                || self.input_events.iter().any(|event| match event {
+                    InputEvent::KeyRelease { input, .. } => input.contains(Inputs::MB_LEFT),
+                    _ => false,
+                });
+
+            if lmb_released {
+                self.scrollbar_drag_ctrl_idx = None;
+            } else {
+                let ctrl = &mut self.tree[ctrl_idx];
+                let (vertical, horizontal) = scrollbars(ctrl);
+                let cursor_delta = self.cursor_position - self.scrollbar_drag_start_cursor_position;
+                let start_scroll_offset = self.scrollbar_drag_start_scroll_offset;
+
+                // Dragging is never smoothed, the thumb should stick to the
+                // cursor.
+                if self.scrollbar_drag_vertical {
+                    if let Some(scrollbar) = vertical {
+                        let scroll_offset = scrollbar.drag(start_scroll_offset.y, cursor_delta.y);
+                        ctrl.scroll_offset.y = scroll_offset;
+                        ctrl.scroll_offset_target.y = scroll_offset;
+                    }
+                } else if let Some(scrollbar) = horizontal {
+                    let scroll_offset = scrollbar.drag(start_scroll_offset.x, cursor_delta.x);
+                    ctrl.scroll_offset.x = scroll_offset;
+                    ctrl.scroll_offset_target.x = scroll_offset;
+                }
+            }
+        } else if let Some(hovered_ctrl_idx) = self.hovered_ctrl_idx {
+            let lmb_pressed = self.overflow_inputs_pressed.contains(Inputs::MB_LEFT)
+                || self.input_events.iter().any(|event| match event {
+                    InputEvent::KeyPress { input, .. } => input.contains(Inputs::MB_LEFT),
+                    _ => false,
+                });
+
+            if lmb_pressed {
+                let mut ctrl_idx = Some(hovered_ctrl_idx);
+                while let Some(idx) = ctrl_idx {
+                    let ctrl = &self.tree[idx];
+                    let (vertical, horizontal) = scrollbars(ctrl);
+
+                    let cursor_position = self.cursor_position;
+                    let vertical_pressed =
+                        matches!(vertical, Some(s) if s.thumb.contains_point(cursor_position));
+                    let horizontal_pressed =
+                        matches!(horizontal, Some(s) if s.thumb.contains_point(cursor_position));
+
+                    if vertical_pressed || horizontal_pressed {
+                        self.scrollbar_drag_ctrl_idx = Some(idx);
+                        self.scrollbar_drag_vertical = vertical_pressed;
+                        self.scrollbar_drag_start_cursor_position = self.cursor_position;
+                        self.scrollbar_drag_start_scroll_offset = ctrl.scroll_offset;
+
+                        self.overflow_inputs_pressed.0 &= !Inputs::MB_LEFT.0;
+                        for event in &mut self.input_events {
+                            if let InputEvent::KeyPress { input, .. } = event {
+                                input.0 &= !Inputs::MB_LEFT.0;
+                            }
+                        }
+                        self.input_events.retain(|event| match event {
+                            InputEvent::KeyPress { input, .. } => *input != Inputs::NONE,
+                            _ => true,
+                        });
+
+                        break;
+                    }
+
+                    ctrl_idx = ctrl.parent_idx.map(CtrlIdx::get);
+                }
+            }
+        }
+
         //
         // Scroll a control.
         //
@@ -765,58 +2588,140 @@ impl<A: Allocator + Clone> Ui<A> {
         // overflow it could scroll, walk the tree up to the first eligible
         // control and scroll that!
         //
+        // Scrolling moves the target scroll offset, and the actual offset
+        // catches up below. The target is what gets clamped, so that the
+        // scroll offset never animates past the content.
+        //
+        // By convention, holding shift while scrolling vertically scrolls
+        // horizontally instead. Some platforms already do this for us, in
+        // which case the delta arrives with just the X component set.
+        //
+        let scroll_smoothing_factor = self.scroll_smoothing_factor();
         if self.scroll_delta != Vec2::ZERO {
-            if let Some(idx) = self.hovered_ctrl_idx {
-                let mut ctrl = &mut self.tree[idx];
-                let mut ctrl_scroll_size = Vec2::ZERO.max(
-                    ctrl.layout_cache_content_size - ctrl.rect.size()
-                        + 2.0 * ctrl.padding
-                        + 2.0 * ctrl.border,
-                );
-                let mut ctrl_scroll_offset_new =
-                    (ctrl.scroll_offset - self.scroll_delta).clamp(Vec2::ZERO, ctrl_scroll_size);
-                let mut ctrl_can_scroll = ctrl.flags.intersects(CtrlFlags::CAPTURE_SCROLL)
-                    && ctrl_scroll_offset_new != ctrl.scroll_offset;
-
-                while !ctrl_can_scroll && ctrl.parent_idx.is_some() {
-                    let parent_idx = ctrl.parent_idx.unwrap();
-
-                    ctrl = &mut self.tree[parent_idx];
-                    ctrl_scroll_size = Vec2::ZERO.max(
-                        ctrl.layout_cache_content_size - ctrl.rect.size()
-                            + 2.0 * ctrl.padding
-                            + 2.0 * ctrl.border,
-                    );
-                    ctrl_scroll_offset_new = (ctrl.scroll_offset - self.scroll_delta)
+            let scroll_delta =
+                if self.modifiers.contains(Modifiers::SHIFT) && self.scroll_delta.x == 0.0 {
+                    Vec2::new(self.scroll_delta.y, 0.0)
+                } else {
+                    self.scroll_delta
+                };
+
+            let mut ctrl_idx = self.hovered_ctrl_idx;
+            while let Some(idx) = ctrl_idx {
+                let ctrl = &mut self.tree[idx];
+
+                if ctrl.flags.intersects(CtrlFlags::CAPTURE_SCROLL) {
+                    let ctrl_scroll_size = scroll_size(ctrl);
+
+                    // Prefer the axis that has somewhere to scroll to, if the
+                    // control asked for it.
+                    let ctrl_scroll_delta =
+                        if ctrl.flags.intersects(CtrlFlags::CAPTURE_SCROLL_HORIZONTAL)
+                            && scroll_delta.x == 0.0
+                            && ctrl_scroll_size.y == 0.0
+                        {
+                            Vec2::new(scroll_delta.y, 0.0)
+                        } else {
+                            scroll_delta
+                        };
+
+                    let ctrl_scroll_offset_new = (ctrl.scroll_offset_target - ctrl_scroll_delta)
                         .clamp(Vec2::ZERO, ctrl_scroll_size);
-                    ctrl_can_scroll = ctrl.flags.intersects(CtrlFlags::CAPTURE_SCROLL)
-                        && ctrl_scroll_offset_new != ctrl.scroll_offset;
-                }
 
-                if ctrl_can_scroll {
-                    ctrl.scroll_offset = ctrl_scroll_offset_new;
+                    if ctrl_scroll_offset_new != ctrl.scroll_offset_target {
+                        ctrl.scroll_offset_target = ctrl_scroll_offset_new;
+                        break;
+                    }
                 }
+
+                ctrl_idx = ctrl.parent_idx.map(CtrlIdx::get);
             }
         }
 
-        self.build_parent_idx = Some(ROOT_IDX);
-        self.build_sibling_idx = None;
-        self.overlay_build_parent_idx = Some(OVERLAY_ROOT_IDX);
-        self.overlay_build_sibling_idx = None;
-
-        Frame { ui: self }
-    }
+        if let Some(factor) = scroll_smoothing_factor {
+            for ctrl in &mut self.tree {
+                if ctrl.scroll_offset != ctrl.scroll_offset_target {
+                    let distance = ctrl.scroll_offset_target - ctrl.scroll_offset;
 
-    pub fn end_frame(&mut self) {
+                    // Snap to the target once we are close enough, otherwise
+                    // we'd be approaching it forever.
+                    if distance.length_squared() < 0.01 {
+                        ctrl.scroll_offset = ctrl.scroll_offset_target;
+                    } else {
+                        ctrl.scroll_offset += distance * factor;
+                    }
+                }
+            }
+        } else {
+            for ctrl in &mut self.tree {
+                ctrl.scroll_offset = ctrl.scroll_offset_target;
+            }
+        }
+
+        self.build_parent_idx = Some(ROOT_IDX);
+        self.build_sibling_idx = None;
+        self.overlay_build_parent_idx = Some(OVERLAY_ROOT_IDX);
+        self.overlay_build_sibling_idx = None;
+        self.overlay_build_last_top_level_idx = None;
+        #[cfg(debug_assertions)]
+        self.build_sibling_ids.clear();
+        self.build_depth = 0;
+        self.overlay_build_depth = 0;
+        #[cfg(debug_assertions)]
+        {
+            self.build_ctrl_id_stack.clear();
+            self.overlay_build_ctrl_id_stack.clear();
+        }
+
+        Frame {
+            ui: self,
+            temp_allocator,
+        }
+    }
+
+    fn do_end_frame(&mut self) {
+        assert!(
+            !self.building_overlay,
+            "Is there an end_overlay for every begin_overlay?",
+        );
+        assert!(
+            self.build_viewport_idx.is_none(),
+            "Is there an end_viewport for every begin_viewport?",
+        );
+        if self.build_depth != 0 {
+            self.panic_unpopped_ctrls("Is there a pop_ctrl for every push_ctrl?");
+        }
+        // NB: The overlay build depth was already checked in end_overlay.
+        debug_assert!(self.overlay_build_depth == 0);
+        debug_assert!(self.build_parent_idx == Some(ROOT_IDX));
+        debug_assert!(self.overlay_build_parent_idx == Some(OVERLAY_ROOT_IDX));
+        assert!(
+            self.id_namespace_stack.is_empty(),
+            "Is there a pop_id for every push_id?",
+        );
+        assert!(
+            self.theme_stack.len() == 1,
+            "Is there a pop_theme for every push_theme?",
+        );
+        if let Some((style_var, _, _)) = self.style_var_stack.last() {
+            panic!("Is there a pop_style_var for every push_style_var? Unpopped: {style_var:?}");
+        }
         assert!(
-            self.build_parent_idx == Some(ROOT_IDX),
-            "Is there a pop_ctrl for every push_ctrl?",
+            self.disabled_stack.is_empty(),
+            "Is there a pop_disabled for every push_disabled?",
         );
         assert!(
-            self.overlay_build_parent_idx == Some(OVERLAY_ROOT_IDX),
-            "Is there a pop_ctrl for every push_ctrl?",
+            self.clip_rect_stack.is_empty(),
+            "Is there a pop_clip_rect for every push_clip_rect?",
         );
 
+        // Toasts are built last, so that they are on top of the rest of the
+        // overlay.
+        self.build_toasts();
+
+        let current_frame = self.current_frame;
+        self.animated_values
+            .retain(|_, animated_value| animated_value.last_frame == current_frame);
+
         // Perform cleanup on the roots analogous to the cleanup that happens in
         // pop_ctrl for other (not root) controls.
         {
@@ -838,6 +2743,24 @@ impl<A: Allocator + Clone> Ui<A> {
             }
         }
 
+        // Resolve capture requests. Only requests made by the active control
+        // count, so that e.g. a control that deactivated itself after
+        // requesting capture doesn't keep the keyboard. This happens before
+        // collecting garbage, so the indices are still valid.
+        {
+            let active_requested = |requester_idx: Option<usize>| {
+                requester_idx.is_some() && requester_idx == self.active_ctrl_idx
+            };
+
+            self.want_capture_keyboard =
+                active_requested(self.build_want_capture_keyboard_ctrl_idx);
+            self.want_text_input = active_requested(self.build_want_text_input_ctrl_idx);
+            self.want_capture_mouse = self.build_want_capture_mouse
+                || self.hovered_capturing_ctrl_idx.is_some()
+                || self.scrollbar_drag_ctrl_idx.is_some()
+                || self.drag.map_or(false, |drag| drag.dragging);
+        }
+
         // Discover reachachable dead controls in the tree. If there are any, we
         // did something wrong. There can be dead nodes, but they must not be
         // reachable.
@@ -854,10 +2777,10 @@ impl<A: Allocator + Clone> Ui<A> {
                     panic!("Reachable dead control found at {ctrl_idx}, id: {id}");
                 }
 
-                if let Some(child_idx) = ctrl.child_idx {
+                if let Some(child_idx) = ctrl.child_idx.map(CtrlIdx::get) {
                     dead_discovery(tree, child_idx, current_frame);
 
-                    while let Some(sibling_idx) = ctrl.sibling_idx {
+                    while let Some(sibling_idx) = ctrl.sibling_idx.map(CtrlIdx::get) {
                         dead_discovery(tree, sibling_idx, current_frame);
                         ctrl = &tree[sibling_idx];
                     }
@@ -868,66 +2791,98 @@ impl<A: Allocator + Clone> Ui<A> {
         //
         // Collect dead controls.
         //
-        // Go over every control and see if it's dead. If it is, swap_remove
-        // it. This invalidates all indices pointing to the last, possibly live
-        // control, so we record the fact that a relocation happened and later
-        // fix up the references.
+        // Copy the live controls into the other tree buffer in breadth-first
+        // order, so that siblings end up next to each other, and swap the
+        // buffers. The roots keep their indices. The new tree doubles as the
+        // queue of controls whose children are yet to be copied.
         //
-        // By this point, dead controls should not be referenced by any live
-        // control, so there is no need to fix references to them.
-        //
-        // TODO(yan): @Speed This GC sucks at maintaining locality between
-        // siblings. Do some kind of double-buffering and compaction.
+        // By this point, dead controls should not be reachable from the roots,
+        // so every control we don't reach is dead.
         //
 
-        let mut relocations: Vec<(usize, usize), _> =
-            Vec::with_capacity_in(self.tree.len(), &self.allocator);
+        // Maps indices in the old tree to indices in the new tree, or None
+        // for dead controls.
+        let new_idxs = &mut self.scratch_new_idxs;
+        new_idxs.clear();
+        new_idxs.resize(self.tree.len(), None);
 
-        fn apply_relocation(idx_to_relocate: &mut Option<usize>, src: usize, dst: usize) {
-            if let Some(idx) = idx_to_relocate.as_mut() {
-                if *idx == src {
-                    *idx = dst;
-                }
-            }
+        debug_assert!(self.tree_next.is_empty());
+        for root_idx in [ROOT_IDX, OVERLAY_ROOT_IDX] {
+            self.tree_next.push(self.tree[root_idx].clone());
+            new_idxs[root_idx] = Some(CtrlIdx::new(root_idx));
         }
 
-        let mut ctrl_idx = 0;
-        while ctrl_idx < self.tree.len() {
-            if self.tree[ctrl_idx].last_frame != self.current_frame {
-                // The swapped in control could be dead too. Keep doing
-                // swap_remove until we find a live control, only then record
-                // the relocation.
-                while ctrl_idx < self.tree.len()
-                    && self.tree[ctrl_idx].last_frame != self.current_frame
-                {
-                    self.tree.swap_remove(ctrl_idx);
-                }
+        let mut parent_idx = 0;
+        while parent_idx < self.tree_next.len() {
+            // Until the parent is processed, its child index still points
+            // to the old tree.
+            let mut old_child_idx = self.tree_next[parent_idx].child_idx.take();
+            let mut prev_sibling_idx: Option<usize> = None;
+
+            while let Some(old_idx) = old_child_idx.map(CtrlIdx::get) {
+                let idx = CtrlIdx::new(self.tree_next.len());
+                let mut ctrl = self.tree[old_idx].clone();
+
+                old_child_idx = ctrl.sibling_idx;
+
+                ctrl.parent_idx = Some(CtrlIdx::new(parent_idx));
+                ctrl.sibling_idx = None;
 
-                // Only record the relocation if we found a live control - the
-                // previous loop either stopped at the end of the tree vec, or
-                // by finding a live control.
-                if ctrl_idx < self.tree.len() {
-                    relocations.push((self.tree.len(), ctrl_idx));
+                self.tree_next.push(ctrl);
+                new_idxs[old_idx] = Some(idx);
+
+                match prev_sibling_idx {
+                    Some(prev_sibling_idx) => {
+                        self.tree_next[prev_sibling_idx].sibling_idx = Some(idx);
+                    }
+                    None => self.tree_next[parent_idx].child_idx = Some(idx),
                 }
+
+                prev_sibling_idx = Some(idx.get());
             }
 
-            ctrl_idx += 1;
+            parent_idx += 1;
+        }
+
+        for ctrl in &mut self.tree_next {
+            ctrl.overlay_owner_idx = ctrl
+                .overlay_owner_idx
+                .and_then(|owner_idx| new_idxs[owner_idx.get()]);
         }
 
-        // Apply relocations.
-        for &(src, dst) in &relocations {
-            apply_relocation(&mut self.active_ctrl_idx, src, dst);
+        // Release resources of the dead controls.
+        let mut collected_ctrl_count = 0;
+        for (ctrl, new_idx) in self.tree.iter().zip(new_idxs.iter()) {
+            if new_idx.is_none() {
+                collected_ctrl_count += 1;
 
-            for ctrl in &mut self.tree {
-                apply_relocation(&mut ctrl.parent_idx, src, dst);
-                apply_relocation(&mut ctrl.child_idx, src, dst);
-                apply_relocation(&mut ctrl.sibling_idx, src, dst);
+                debug_assert!(
+                    ctrl.last_frame != self.current_frame,
+                    "Unreachable live control found, id: {}",
+                    ctrl.id,
+                );
+
+                self.ctrl_animations.remove(&ctrl.persistent_id);
+                self.draw_caches.entries.remove(&ctrl.persistent_id);
+                if let Some(state_idx) = ctrl.state_idx {
+                    self.ctrl_state_free_list.push(state_idx);
+                }
             }
         }
 
-        // NB: Drop relocations eagerly, so that if the allocator is a bump
-        // allocator, we don't prevent it from reclaiming the memory.
-        drop(relocations);
+        // Indices of dead controls map to None, e.g. if a button closed its
+        // own window while active, or a scrolled control disappeared while we
+        // were dragging its scroll bar.
+        let new_idx = |idx: Option<usize>| idx.and_then(|idx| new_idxs[idx]).map(CtrlIdx::get);
+        self.active_ctrl_idx = new_idx(self.active_ctrl_idx);
+        self.scrollbar_drag_ctrl_idx = new_idx(self.scrollbar_drag_ctrl_idx);
+        self.hovered_ctrl_idx = new_idx(self.hovered_ctrl_idx);
+        self.hovered_capturing_ctrl_idx = new_idx(self.hovered_capturing_ctrl_idx);
+        self.modal_ctrl_idx = new_idx(self.build_modal_ctrl_idx);
+        self.build_modal_ctrl_idx = None;
+
+        mem::swap(&mut self.tree, &mut self.tree_next);
+        self.tree_next.clear();
 
         //
         // Update layout.
@@ -941,79 +2896,23 @@ impl<A: Allocator + Clone> Ui<A> {
         layout(&mut self.tree, OVERLAY_ROOT_IDX, Vec2::ZERO);
 
         fn layout(tree: &mut [CtrlNode], ctrl_idx: usize, ctrl_absolute_position_base: Vec2) {
-            // TODO(yan): For horizontal and vertical layouts we advance the
-            // position by the width and height of the rect of the current
-            // control, but what if that control has its position offset by the
-            // X or Y of the rect? (e.g. if X=100, should we advance the
-            // horizontal cursor by an additional 100 pixels?)
-
             let ctrl = &tree[ctrl_idx];
             let ctrl_flags = ctrl.flags;
-            let ctrl_layout = ctrl.layout;
             let ctrl_inline_content_rect = ctrl.inline_content_rect;
+            let ctrl_scroll_offset = ctrl.scroll_offset;
             let ctrl_absolute_position =
                 ctrl_absolute_position_base + ctrl.rect.min_point() + ctrl.margin;
+            let child_absolute_position_base =
+                ctrl_absolute_position + ctrl.border + ctrl.padding - ctrl.scroll_offset;
 
-            if let Some(child_idx) = ctrl.child_idx {
-                let child_absolute_position_base =
-                    ctrl_absolute_position + ctrl.border + ctrl.padding - ctrl.scroll_offset;
-
-                layout(tree, child_idx, child_absolute_position_base);
-
-                let mut child = &tree[child_idx];
-                let mut child_margin_rect = child.rect.offset(child.margin);
-                let mut child_absolute_position_offset = match ctrl_layout {
-                    Layout::Free => Vec2::ZERO,
-                    Layout::Horizontal => Vec2::new(child_margin_rect.width, 0.0),
-                    Layout::Vertical => Vec2::new(0.0, child_margin_rect.height),
-                };
-
-                let mut max_point = child_margin_rect.max_point();
-
-                while let Some(sibling_idx) = child.sibling_idx {
-                    layout(
-                        tree,
-                        sibling_idx,
-                        child_absolute_position_base + child_absolute_position_offset,
-                    );
-
-                    child = &tree[sibling_idx];
-                    child_margin_rect = child.rect.offset(child.margin);
-
-                    match ctrl_layout {
-                        Layout::Free => {
-                            max_point = max_point.max(child_margin_rect.max_point());
-                        }
-                        Layout::Horizontal => {
-                            child_absolute_position_offset += Vec2::X * child_margin_rect.width;
-                            max_point.x += child_margin_rect.width;
-                            max_point.y = max_point.y.max(child_margin_rect.max_y());
-                        }
-                        Layout::Vertical => {
-                            child_absolute_position_offset += Vec2::Y * child_margin_rect.height;
-                            max_point.x = max_point.x.max(child_margin_rect.max_x());
-                            max_point.y += child_margin_rect.height;
-                        }
-                    }
-                }
-
-                if let Some(inline_content_rect) = ctrl_inline_content_rect {
-                    max_point = max_point.max(inline_content_rect.max_point());
-                }
-
-                let ctrl_mut = &mut tree[ctrl_idx];
-                ctrl_mut.layout_cache_absolute_position = ctrl_absolute_position;
-                ctrl_mut.layout_cache_content_size = max_point;
-            } else {
-                let ctrl_mut = &mut tree[ctrl_idx];
+            resize_fill_children(tree, ctrl_idx, child_absolute_position_base);
+            let children_max_point = layout_children(tree, ctrl_idx, child_absolute_position_base);
 
-                ctrl_mut.layout_cache_absolute_position = ctrl_absolute_position;
-                if let Some(inline_content_rect) = ctrl_inline_content_rect {
-                    ctrl_mut.layout_cache_content_size = inline_content_rect.max_point();
-                } else {
-                    ctrl_mut.layout_cache_content_size = Vec2::ZERO;
-                }
-            }
+            let ctrl_mut = &mut tree[ctrl_idx];
+            ctrl_mut.layout_cache_absolute_position = ctrl_absolute_position;
+            ctrl_mut.layout_cache_scroll_offset = ctrl_scroll_offset;
+            ctrl_mut.layout_cache_content_size =
+                content_size(children_max_point, ctrl_inline_content_rect);
 
             if ctrl_flags.intersects(CtrlFlags::ALL_RESIZE_TO_FIT) {
                 let ctrl_mut = &mut tree[ctrl_idx];
@@ -1035,32 +2934,313 @@ impl<A: Allocator + Clone> Ui<A> {
                 };
 
                 ctrl_mut.rect = Rect::new(x, y, width, height);
+
+                // Children filling the control in the direction it resizes to
+                // fit in didn't count towards its content size, and only now
+                // know the size they fill.
+                let mut has_fill_children = false;
+                let mut next_idx = tree[ctrl_idx].child_idx.map(CtrlIdx::get);
+                while let Some(child_idx) = next_idx {
+                    let child = &tree[child_idx];
+                    has_fill_children |= fills_fitting_parent(ctrl_flags, child.flags);
+                    next_idx = child.sibling_idx.map(CtrlIdx::get);
+                }
+
+                if has_fill_children {
+                    resize_fill_children(tree, ctrl_idx, child_absolute_position_base);
+                    layout_children(tree, ctrl_idx, child_absolute_position_base);
+                }
+            }
+
+            if let Some((aspect_ratio, aspect_fit)) = tree[ctrl_idx].aspect_ratio {
+                let ctrl = &tree[ctrl_idx];
+                let available_height = match ctrl.parent_idx.map(CtrlIdx::get) {
+                    Some(parent_idx) => {
+                        let parent = &tree[parent_idx];
+                        let parent_inner_rect = parent.rect.inset(parent.border + parent.padding);
+
+                        f32::max(
+                            0.0,
+                            parent_inner_rect.height - ctrl.rect.y - 2.0 * ctrl.margin,
+                        )
+                    }
+                    None => f32::INFINITY,
+                };
+
+                let mut width = ctrl.rect.width;
+                let mut height = width / aspect_ratio;
+                let clamp = match aspect_fit {
+                    AspectFit::Contain => height > available_height,
+                    AspectFit::Cover => height < available_height,
+                };
+                if clamp {
+                    height = available_height;
+                    width = height * aspect_ratio;
+                }
+
+                let rect = Rect::new(ctrl.rect.x, ctrl.rect.y, width, height);
+                if rect != ctrl.rect {
+                    tree[ctrl_idx].rect = rect;
+
+                    // Children (and thus the content size) may depend on the
+                    // size, if they fill the control.
+                    resize_fill_children(tree, ctrl_idx, child_absolute_position_base);
+                    let children_max_point =
+                        layout_children(tree, ctrl_idx, child_absolute_position_base);
+                    tree[ctrl_idx].layout_cache_content_size =
+                        content_size(children_max_point, ctrl_inline_content_rect);
+                }
+            }
+        }
+
+        fn content_size(
+            children_max_point: Option<Vec2>,
+            inline_content_rect: Option<Rect>,
+        ) -> Vec2 {
+            match (children_max_point, inline_content_rect) {
+                (Some(max_point), Some(inline_content_rect)) => {
+                    max_point.max(inline_content_rect.max_point())
+                }
+                (Some(max_point), None) => max_point,
+                (None, Some(inline_content_rect)) => inline_content_rect.max_point(),
+                (None, None) => Vec2::ZERO,
+            }
+        }
+
+        // Lays out the children of a control, and returns the max point of
+        // their margin rects, relative to the control's inner rect.
+        fn layout_children(
+            tree: &mut [CtrlNode],
+            ctrl_idx: usize,
+            child_absolute_position_base: Vec2,
+        ) -> Option<Vec2> {
+            // TODO(yan): For horizontal and vertical layouts we advance the
+            // position by the width and height of the rect of the current
+            // control, but what if that control has its position offset by the
+            // X or Y of the rect? (e.g. if X=100, should we advance the
+            // horizontal cursor by an additional 100 pixels?)
+
+            let ctrl = &tree[ctrl_idx];
+            let ctrl_flags = ctrl.flags;
+            let ctrl_layout = ctrl.layout;
+
+            let mut child_absolute_position_offset = Vec2::ZERO;
+            let mut max_point: Option<Vec2> = None;
+
+            // NB: The content size is measured from where the children
+            // actually end up, so that the rect offsets of non-first children
+            // in horizontal and vertical layouts are accounted for, and the
+            // content can be scrolled to in its entirety.
+            let mut next_idx = ctrl.child_idx.map(CtrlIdx::get);
+            while let Some(child_idx) = next_idx {
+                layout(
+                    tree,
+                    child_idx,
+                    child_absolute_position_base + child_absolute_position_offset,
+                );
+
+                let child = &tree[child_idx];
+                let child_margin_rect = child.rect.offset(child.margin);
+
+                // Children filling a parent resizing to fit only count with
+                // their position in that direction.
+                let mut child_max_point = child_margin_rect.max_point();
+                if fills_fitting_parent(
+                    ctrl_flags & CtrlFlags::RESIZE_TO_FIT_HORIZONTAL,
+                    child.flags,
+                ) {
+                    child_max_point.x = child_margin_rect.x;
+                }
+                if fills_fitting_parent(ctrl_flags & CtrlFlags::RESIZE_TO_FIT_VERTICAL, child.flags)
+                {
+                    child_max_point.y = child_margin_rect.y;
+                }
+
+                let child_max_point = child_max_point + child_absolute_position_offset;
+                max_point = Some(match max_point {
+                    Some(max_point) => max_point.max(child_max_point),
+                    None => child_max_point,
+                });
+
+                match ctrl_layout {
+                    Layout::Free => (),
+                    Layout::Horizontal => {
+                        child_absolute_position_offset += Vec2::X * child_margin_rect.width;
+                    }
+                    Layout::Vertical => {
+                        child_absolute_position_offset += Vec2::Y * child_margin_rect.height;
+                    }
+                }
+
+                next_idx = child.sibling_idx.map(CtrlIdx::get);
+            }
+
+            max_point
+        }
+
+        // Resizes children with CtrlFlags::ALL_FILL to the space left over by
+        // their siblings in the control's layout direction, and to all of the
+        // space across it.
+        fn resize_fill_children(
+            tree: &mut [CtrlNode],
+            ctrl_idx: usize,
+            child_absolute_position_base: Vec2,
+        ) {
+            let ctrl = &tree[ctrl_idx];
+            let ctrl_layout = ctrl.layout;
+            let inner_size = ctrl.rect.inset(ctrl.border + ctrl.padding).size();
+
+            let (along_fill_flag, along_fit_flag) = match ctrl_layout {
+                Layout::Free => (CtrlFlags::NONE, CtrlFlags::NONE),
+                Layout::Horizontal => (
+                    CtrlFlags::FILL_HORIZONTAL,
+                    CtrlFlags::RESIZE_TO_FIT_HORIZONTAL,
+                ),
+                Layout::Vertical => (CtrlFlags::FILL_VERTICAL, CtrlFlags::RESIZE_TO_FIT_VERTICAL),
+            };
+
+            let mut has_fill_children = false;
+            let mut along_fill_count: u32 = 0;
+            let mut next_idx = ctrl.child_idx.map(CtrlIdx::get);
+            while let Some(child_idx) = next_idx {
+                let child = &tree[child_idx];
+                has_fill_children |= child.flags.intersects(CtrlFlags::ALL_FILL);
+                if child.flags.intersects(along_fill_flag) {
+                    along_fill_count += 1;
+                }
+
+                next_idx = child.sibling_idx.map(CtrlIdx::get);
+            }
+
+            if !has_fill_children {
+                return;
+            }
+
+            // Measure the space taken by the other children in the layout
+            // direction. Children resizing to fit are laid out first to learn
+            // their size, and then again once they are in their place.
+            let mut along_taken = 0.0;
+            if along_fill_count > 0 {
+                let mut next_idx = tree[ctrl_idx].child_idx.map(CtrlIdx::get);
+                while let Some(child_idx) = next_idx {
+                    let child_flags = tree[child_idx].flags;
+                    if !child_flags.intersects(along_fill_flag) {
+                        if child_flags.intersects(along_fit_flag) {
+                            layout(tree, child_idx, child_absolute_position_base);
+                        }
+
+                        let child = &tree[child_idx];
+                        let child_margin_rect = child.rect.offset(child.margin);
+                        along_taken += if ctrl_layout == Layout::Horizontal {
+                            child_margin_rect.width
+                        } else {
+                            child_margin_rect.height
+                        };
+                    }
+
+                    next_idx = tree[child_idx].sibling_idx.map(CtrlIdx::get);
+                }
+            }
+
+            let along_size =
+                |inner_size: f32| f32::max(0.0, inner_size - along_taken) / along_fill_count as f32;
+
+            let mut next_idx = tree[ctrl_idx].child_idx.map(CtrlIdx::get);
+            while let Some(child_idx) = next_idx {
+                let child = &mut tree[child_idx];
+                let mut width = child.rect.width;
+                let mut height = child.rect.height;
+
+                if child.flags.intersects(CtrlFlags::FILL_HORIZONTAL) {
+                    let available = if ctrl_layout == Layout::Horizontal {
+                        along_size(inner_size.x)
+                    } else {
+                        inner_size.x - child.rect.x
+                    };
+
+                    width = f32::max(0.0, available - 2.0 * child.margin);
+                }
+
+                if child.flags.intersects(CtrlFlags::FILL_VERTICAL) {
+                    let available = if ctrl_layout == Layout::Vertical {
+                        along_size(inner_size.y)
+                    } else {
+                        inner_size.y - child.rect.y
+                    };
+
+                    height = f32::max(0.0, available - 2.0 * child.margin);
+                }
+
+                child.rect = Rect::new(child.rect.x, child.rect.y, width, height);
+                next_idx = child.sibling_idx.map(CtrlIdx::get);
             }
         }
 
+        // Whether a child fills its parent in a direction the parent resizes
+        // to fit in.
+        fn fills_fitting_parent(parent_flags: CtrlFlags, child_flags: CtrlFlags) -> bool {
+            (parent_flags.intersects(CtrlFlags::RESIZE_TO_FIT_HORIZONTAL)
+                && child_flags.intersects(CtrlFlags::FILL_HORIZONTAL))
+                || (parent_flags.intersects(CtrlFlags::RESIZE_TO_FIT_VERTICAL)
+                    && child_flags.intersects(CtrlFlags::FILL_VERTICAL))
+        }
+
         //
-        // Render into the draw lists. First the base, then the overlay.
+        // Render into the draw lists. First the base, then the overlay, for
+        // the main viewport and then for each of the others.
         //
-        render(
-            &self.tree,
-            ROOT_IDX,
-            Rect::from_points(Vec2::ZERO, self.window_size),
-            &self.draw_primitives,
-            self.font_atlas_texture_id,
-            &mut self.draw_list,
-            &self.allocator,
-            self.window_scale_factor,
-        );
-        render(
-            &self.tree,
-            OVERLAY_ROOT_IDX,
-            Rect::from_points(Vec2::ZERO, self.window_size),
-            &self.draw_primitives,
-            self.font_atlas_texture_id,
-            &mut self.draw_list,
-            &self.allocator,
-            self.window_scale_factor,
-        );
+        // Viewports not built this frame are gone, along with their draw
+        // lists.
+        let current_frame = self.current_frame;
+        self.viewports
+            .retain(|viewport| viewport.last_frame == current_frame);
+
+        let anti_aliasing_fringe = self.anti_aliasing.then_some(1.0 / self.window_scale_factor);
+        self.draw_caches.hit_count = 0;
+        self.draw_caches.miss_count = 0;
+
+        self.draw_list
+            .set_anti_aliasing_fringe(anti_aliasing_fringe);
+        for root_idx in [ROOT_IDX, OVERLAY_ROOT_IDX] {
+            let window_rect = Rect::from_points(Vec2::ZERO, self.window_size);
+            render(
+                &self.tree,
+                root_idx,
+                window_rect,
+                window_rect,
+                None,
+                &self.draw_primitives,
+                self.font_atlas_texture_id,
+                &mut self.draw_list,
+                &mut self.scratch_siblings,
+                &mut self.draw_caches,
+                self.window_scale_factor,
+                self.pixel_snapping,
+            );
+        }
+
+        for viewport in &mut self.viewports {
+            viewport
+                .draw_list
+                .set_anti_aliasing_fringe(anti_aliasing_fringe);
+            for root_idx in [ROOT_IDX, OVERLAY_ROOT_IDX] {
+                let viewport_rect = Rect::from_points(Vec2::ZERO, viewport.size);
+                render(
+                    &self.tree,
+                    root_idx,
+                    viewport_rect,
+                    viewport_rect,
+                    Some(viewport.id),
+                    &self.draw_primitives,
+                    self.font_atlas_texture_id,
+                    &mut viewport.draw_list,
+                    &mut self.scratch_siblings,
+                    &mut self.draw_caches,
+                    self.window_scale_factor,
+                    self.pixel_snapping,
+                );
+            }
+        }
 
         // TODO(yan): @Memory If the allocator is a bump allocator, we
         // potentially prevent it from reclaiming memory if draw_list grows.
@@ -1068,23 +3248,160 @@ impl<A: Allocator + Clone> Ui<A> {
             tree: &[CtrlNode],
             ctrl_idx: usize,
             parent_ctrl_scissor_rect: Rect,
+            viewport_rect: Rect,
+            viewport: Option<u32>,
             draw_primitives: &[DrawPrimitive],
             font_atlas_texture_id: u64,
             draw_list: &mut DrawList<A>,
-            temp_allocator: &A,
+            siblings: &mut Vec<(usize, (i32, u32)), A>,
+            draw_caches: &mut DrawCaches<A>,
             window_scale_factor: f32,
+            pixel_snapping: bool,
         ) {
             let ctrl = &tree[ctrl_idx];
-            let ctrl_rect_absolute = Rect::new(
-                ctrl.layout_cache_absolute_position.x,
-                ctrl.layout_cache_absolute_position.y,
-                ctrl.rect.width,
-                ctrl.rect.height,
+            let is_root = ctrl.parent_idx.is_none();
+            if !is_root && ctrl.viewport != viewport {
+                return;
+            }
+
+            // Controls that don't clip are only clipped to the viewport, and
+            // so are their descendants.
+            let parent_ctrl_scissor_rect = if ctrl.flags.intersects(CtrlFlags::NO_CLIP) {
+                viewport_rect
+            } else {
+                parent_ctrl_scissor_rect
+            };
+
+            // Controls entirely outside the parent's scissor rect, e.g. rows
+            // scrolled out of view, are culled along with their descendants,
+            // before we spend time hashing or recursing into them.
+            if !is_root {
+                let ctrl_rect_absolute = Rect::new(
+                    ctrl.layout_cache_absolute_position.x,
+                    ctrl.layout_cache_absolute_position.y,
+                    ctrl.rect.width,
+                    ctrl.rect.height,
+                );
+
+                if !parent_ctrl_scissor_rect.intersects_rect(ctrl_rect_absolute) {
+                    return;
+                }
+            }
+
+            if !ctrl.flags.intersects(CtrlFlags::CACHE_DRAW) {
+                render_ctrl(
+                    tree,
+                    ctrl_idx,
+                    parent_ctrl_scissor_rect,
+                    viewport_rect,
+                    viewport,
+                    draw_primitives,
+                    font_atlas_texture_id,
+                    draw_list,
+                    siblings,
+                    draw_caches,
+                    window_scale_factor,
+                    pixel_snapping,
+                );
+
+                return;
+            }
+
+            // Everything that goes into rendering the subtree is hashed, and
+            // the geometry from last time is reused, if the hash is the same.
+            let mut hasher = Fnv1aHasher::new();
+            hash_rect(&mut hasher, parent_ctrl_scissor_rect);
+            hash_rect(&mut hasher, viewport_rect);
+            hasher.write_u32(window_scale_factor.to_bits());
+            hasher.write_u8(u8::from(pixel_snapping));
+            hasher.write_u64(font_atlas_texture_id);
+            hasher.write_u8(u8::from(draw_list.premultiplied_alpha()));
+            hasher.write_u8(u8::from(draw_list.linear_color_output()));
+            match draw_list.anti_aliasing_fringe() {
+                Some(fringe) => {
+                    hasher.write_u8(1);
+                    hasher.write_u32(fringe.to_bits());
+                }
+                None => hasher.write_u8(0),
+            }
+            hash_ctrl_subtree(&mut hasher, tree, ctrl_idx, draw_primitives, siblings);
+            let key = hasher.finish();
+
+            if let Some(cache) = draw_caches.entries.get(&ctrl.persistent_id) {
+                if cache.key == key {
+                    draw_list.replay(&cache.recording);
+                    draw_caches.hit_count += 1;
+
+                    return;
+                }
+            }
+
+            draw_caches.miss_count += 1;
+
+            let mark = draw_list.mark();
+            render_ctrl(
+                tree,
+                ctrl_idx,
+                parent_ctrl_scissor_rect,
+                viewport_rect,
+                viewport,
+                draw_primitives,
+                font_atlas_texture_id,
+                draw_list,
+                siblings,
+                draw_caches,
+                window_scale_factor,
+                pixel_snapping,
             );
 
-            let ctrl_scissor_rect = parent_ctrl_scissor_rect
-                .clamp_rect(ctrl_rect_absolute)
-                .inset(ctrl.border);
+            let allocator = &draw_caches.allocator;
+            let cache = draw_caches
+                .entries
+                .entry(ctrl.persistent_id)
+                .or_insert_with(|| DrawCache {
+                    key,
+                    recording: DrawListRecording::new_in(allocator.clone()),
+                });
+
+            cache.key = key;
+            draw_list.record(&mark, &mut cache.recording);
+        }
+
+        fn render_ctrl<A: Allocator + Clone>(
+            tree: &[CtrlNode],
+            ctrl_idx: usize,
+            parent_ctrl_scissor_rect: Rect,
+            viewport_rect: Rect,
+            viewport: Option<u32>,
+            draw_primitives: &[DrawPrimitive],
+            font_atlas_texture_id: u64,
+            draw_list: &mut DrawList<A>,
+            siblings: &mut Vec<(usize, (i32, u32)), A>,
+            draw_caches: &mut DrawCaches<A>,
+            window_scale_factor: f32,
+            pixel_snapping: bool,
+        ) {
+            let ctrl = &tree[ctrl_idx];
+            // The roots span the whole viewport being rendered, as its size can
+            // differ from the window size.
+            let ctrl_rect_absolute = if ctrl.parent_idx.is_none() {
+                parent_ctrl_scissor_rect
+            } else {
+                Rect::new(
+                    ctrl.layout_cache_absolute_position.x,
+                    ctrl.layout_cache_absolute_position.y,
+                    ctrl.rect.width,
+                    ctrl.rect.height,
+                )
+            };
+
+            let ctrl_scissor_rect = if ctrl.flags.intersects(CtrlFlags::NO_CLIP) {
+                viewport_rect
+            } else {
+                parent_ctrl_scissor_rect
+                    .clamp_rect(ctrl_rect_absolute)
+                    .inset(ctrl.border)
+            };
 
             // Some renderer backends dislike scissor rect with zero or negative
             // dimensions, as well as dimensions greater than the surface
@@ -1094,9 +3411,29 @@ impl<A: Allocator + Clone> Ui<A> {
                 return;
             }
 
+            // Solid color rects are either snapped to physical pixels, or just
+            // sized in whole physical pixels.
+            let snap = |rect: Rect| {
+                if pixel_snapping {
+                    snap_rect_edges_to_physical_pixels(rect, window_scale_factor)
+                } else {
+                    rect.round_size_for_scale_factor(window_scale_factor)
+                }
+            };
+
+            // NB: Scrollbars are not faded, because disabled controls can
+            // still be scrolled.
+            let fade = |color: u32| {
+                if ctrl.disabled {
+                    disabled_color(color)
+                } else {
+                    color
+                }
+            };
+
             if ctrl.draw_self {
-                let border_color = ctrl.draw_self_border_color;
-                let background_color = ctrl.draw_self_background_color;
+                let border_color = fade(ctrl.draw_self_border_color);
+                let background_color = fade(ctrl.draw_self_background_color);
 
                 let ctrl_padding_rect_absolute = ctrl_rect_absolute.inset(ctrl.border);
 
@@ -1134,7 +3471,7 @@ impl<A: Allocator + Clone> Ui<A> {
 
                     if !left.is_empty() {
                         draw_list.draw_rect(
-                            left.round_size_for_scale_factor(window_scale_factor),
+                            snap(left),
                             Rect::ZERO,
                             border_color,
                             parent_ctrl_scissor_rect,
@@ -1144,7 +3481,7 @@ impl<A: Allocator + Clone> Ui<A> {
 
                     if !top.is_empty() {
                         draw_list.draw_rect(
-                            top.round_size_for_scale_factor(window_scale_factor),
+                            snap(top),
                             Rect::ZERO,
                             border_color,
                             parent_ctrl_scissor_rect,
@@ -1154,7 +3491,7 @@ impl<A: Allocator + Clone> Ui<A> {
 
                     if !right.is_empty() {
                         draw_list.draw_rect(
-                            right.round_size_for_scale_factor(window_scale_factor),
+                            snap(right),
                             Rect::ZERO,
                             border_color,
                             parent_ctrl_scissor_rect,
@@ -1164,7 +3501,7 @@ impl<A: Allocator + Clone> Ui<A> {
 
                     if !bottom.is_empty() {
                         draw_list.draw_rect(
-                            bottom.round_size_for_scale_factor(window_scale_factor),
+                            snap(bottom),
                             Rect::ZERO,
                             border_color,
                             parent_ctrl_scissor_rect,
@@ -1174,7 +3511,7 @@ impl<A: Allocator + Clone> Ui<A> {
                 }
 
                 draw_list.draw_rect(
-                    ctrl_padding_rect_absolute.round_size_for_scale_factor(window_scale_factor),
+                    snap(ctrl_padding_rect_absolute),
                     Rect::ZERO,
                     background_color,
                     parent_ctrl_scissor_rect,
@@ -1184,967 +3521,6803 @@ impl<A: Allocator + Clone> Ui<A> {
 
             for draw_primitive_idx in ctrl.draw_range.clone() {
                 let draw_primitive = &draw_primitives[draw_primitive_idx];
-                match draw_primitive {
+
+                let offset = ctrl_rect_absolute.min_point() - ctrl.scroll_offset;
+                let scissor_rect = match draw_primitive.clip_rect() {
+                    Some(clip_rect) => {
+                        let scissor_rect = ctrl_scissor_rect.clamp_rect(clip_rect + offset);
+
+                        // Same as for the control's scissor rect, don't give
+                        // tiny scissor rects to the renderer.
+                        if scissor_rect.width < 1.0 || scissor_rect.height < 1.0 {
+                            continue;
+                        }
+
+                        scissor_rect
+                    }
+                    None => ctrl_scissor_rect,
+                };
+
+                match *draw_primitive {
                     DrawPrimitive::Rect {
                         rect,
                         texture_rect,
                         texture_id,
                         color,
+                        flip_y,
+                        sampling,
+                        ..
                     } => {
-                        let rect = *rect + ctrl_rect_absolute.min_point() - ctrl.scroll_offset;
-                        draw_list.draw_rect(
-                            rect.round_size_for_scale_factor(window_scale_factor),
-                            *texture_rect,
-                            *color,
-                            ctrl_scissor_rect,
-                            *texture_id,
+                        // Glyphs and images keep their size, so that they
+                        // don't get stretched.
+                        let rect = if texture_rect == Rect::ZERO {
+                            snap(rect + offset)
+                        } else {
+                            (rect + offset).round_size_for_scale_factor(window_scale_factor)
+                        };
+
+                        draw_list.draw_image(
+                            rect,
+                            texture_rect,
+                            fade(color),
+                            scissor_rect,
+                            texture_id,
+                            flip_y,
+                            sampling,
                         );
                     }
-                }
-            }
+                    DrawPrimitive::GradientRect {
+                        rect,
+                        texture_rect,
+                        texture_id,
+                        colors,
+                        ..
+                    } => {
+                        let rect = if texture_rect == Rect::ZERO {
+                            snap(rect + offset)
+                        } else {
+                            (rect + offset).round_size_for_scale_factor(window_scale_factor)
+                        };
 
-            if ctrl.layout == Layout::Free {
-                // For free layout, we'd like to preserve render order of
-                // controls, e.g. we render least recently active control first,
-                // then a more recently active control, all the way up to the
+                        draw_list.draw_rect_with_corner_colors(
+                            rect,
+                            texture_rect,
+                            colors.map(fade),
+                            scissor_rect,
+                            texture_id,
+                        );
+                    }
+                    DrawPrimitive::Line {
+                        a,
+                        b,
+                        thickness,
+                        texture_id,
+                        color,
+                        ..
+                    } => {
+                        draw_list.draw_line(
+                            a + offset,
+                            b + offset,
+                            thickness,
+                            fade(color),
+                            scissor_rect,
+                            texture_id,
+                        );
+                    }
+                    DrawPrimitive::Circle {
+                        center,
+                        radius,
+                        texture_id,
+                        color,
+                        ..
+                    } => {
+                        draw_list.draw_circle(
+                            center + offset,
+                            radius,
+                            fade(color),
+                            scissor_rect,
+                            texture_id,
+                        );
+                    }
+                    DrawPrimitive::RoundedRect {
+                        rect,
+                        radius,
+                        border_thickness,
+                        texture_id,
+                        color,
+                        ..
+                    } => match border_thickness {
+                        Some(border_thickness) => draw_list.draw_rounded_rect_border(
+                            rect + offset,
+                            radius,
+                            border_thickness,
+                            fade(color),
+                            scissor_rect,
+                            texture_id,
+                        ),
+                        None => draw_list.draw_rounded_rect(
+                            rect + offset,
+                            radius,
+                            fade(color),
+                            scissor_rect,
+                            texture_id,
+                        ),
+                    },
+                    DrawPrimitive::Callback { rect, user_id, .. } => {
+                        let scissor_rect = scissor_rect.clamp_rect(rect + offset);
+                        if scissor_rect.width >= 1.0 && scissor_rect.height >= 1.0 {
+                            draw_list.draw_callback(user_id, scissor_rect);
+                        }
+                    }
+                }
+            }
+
+            if ctrl.layout == Layout::Free {
+                // For free layout, we'd like to preserve render order of
+                // controls, e.g. we render least recently active control first,
+                // then a more recently active control, all the way up to the
                 // currently active control. To that end, we sort the the
-                // siblings by last frame in active path.
-                let mut siblings: Vec<(usize, u32), _> = Vec::new_in(temp_allocator);
-                if let Some(child_idx) = ctrl.child_idx {
+                // siblings by z index and last frame in active path.
+                //
+                // NB: Nested free layouts use the siblings buffer past ours,
+                // same as when finding the hovered control.
+                let siblings_start = siblings.len();
+                if let Some(child_idx) = ctrl.child_idx.map(CtrlIdx::get) {
                     let mut ctrl = &tree[child_idx];
 
-                    siblings.push((child_idx, ctrl.last_frame_in_active_path));
+                    siblings.push((child_idx, render_order_key(ctrl)));
 
-                    while let Some(sibling_idx) = ctrl.sibling_idx {
+                    while let Some(sibling_idx) = ctrl.sibling_idx.map(CtrlIdx::get) {
                         ctrl = &tree[sibling_idx];
-                        siblings.push((sibling_idx, ctrl.last_frame_in_active_path));
+                        siblings.push((sibling_idx, render_order_key(ctrl)));
                     }
                 }
 
-                siblings.sort_unstable_by_key(|&(_, frame)| frame);
+                let siblings_end = siblings.len();
+                siblings[siblings_start..].sort_unstable_by_key(|&(_, key)| key);
 
-                for (sibling_idx, _) in siblings {
+                for i in siblings_start..siblings_end {
+                    let (sibling_idx, _) = siblings[i];
                     render(
                         tree,
                         sibling_idx,
                         ctrl_scissor_rect,
+                        viewport_rect,
+                        viewport,
                         draw_primitives,
                         font_atlas_texture_id,
                         draw_list,
-                        temp_allocator,
+                        siblings,
+                        draw_caches,
                         window_scale_factor,
+                        pixel_snapping,
                     );
                 }
+
+                siblings.truncate(siblings_start);
             } else {
                 // For horizontal and vertical layouts, we don't need any
                 // sorting and just iterate over the controls in definition
                 // order.
-                if let Some(child_idx) = ctrl.child_idx {
+                if let Some(child_idx) = ctrl.child_idx.map(CtrlIdx::get) {
                     render(
                         tree,
                         child_idx,
                         ctrl_scissor_rect,
+                        viewport_rect,
+                        viewport,
                         draw_primitives,
                         font_atlas_texture_id,
                         draw_list,
-                        temp_allocator,
+                        siblings,
+                        draw_caches,
                         window_scale_factor,
+                        pixel_snapping,
                     );
 
                     let mut child = &tree[child_idx];
-                    while let Some(sibling_idx) = child.sibling_idx {
+                    while let Some(sibling_idx) = child.sibling_idx.map(CtrlIdx::get) {
                         child = &tree[sibling_idx];
 
                         render(
                             tree,
                             sibling_idx,
                             ctrl_scissor_rect,
+                            viewport_rect,
+                            viewport,
                             draw_primitives,
                             font_atlas_texture_id,
                             draw_list,
-                            temp_allocator,
+                            siblings,
+                            draw_caches,
                             window_scale_factor,
+                            pixel_snapping,
                         );
                     }
                 }
             }
+
+            // Scroll bars are drawn last, so that they are on top of the
+            // control's contents.
+            let (vertical, horizontal) = scrollbars(ctrl);
+            for scrollbar in [vertical, horizontal].into_iter().flatten() {
+                draw_list.draw_rect(
+                    scrollbar
+                        .track
+                        .round_size_for_scale_factor(window_scale_factor),
+                    Rect::ZERO,
+                    ctrl.scrollbar_track_color,
+                    ctrl_scissor_rect,
+                    font_atlas_texture_id,
+                );
+                draw_list.draw_rect(
+                    scrollbar
+                        .thumb
+                        .round_size_for_scale_factor(window_scale_factor),
+                    Rect::ZERO,
+                    ctrl.scrollbar_thumb_color,
+                    ctrl_scissor_rect,
+                    font_atlas_texture_id,
+                );
+            }
+        }
+
+        if self.debug_overlay {
+            self.count_debug_subtree_draw_primitives();
+            self.draw_debug_overlay();
         }
 
+        self.frame_stats = FrameStats {
+            ctrl_count: self.tree.len(),
+            collected_ctrl_count,
+            draw_primitive_count: self.draw_primitives.len(),
+            glyph_count: self.build_glyph_count,
+            command_count: self.draw_list.commands().len(),
+            vertex_count: self.draw_list.vertices().len(),
+            index_count: self.draw_list.indices().len(),
+            draw_cache_hit_count: self.draw_caches.hit_count,
+            draw_cache_miss_count: self.draw_caches.miss_count,
+        };
+
         self.build_parent_idx = None;
         self.build_sibling_idx = None;
 
+        // Drop targets had their chance to accept the payload this frame.
+        if self.inputs_released().contains(Inputs::MB_LEFT) {
+            self.drag = None;
+        }
+
         // NB: Clear inputs from platform to GUI.
         self.scroll_delta = Vec2::ZERO;
-        self.inputs_pressed = Inputs::empty();
-        self.inputs_released = Inputs::empty();
+        self.input_events.clear();
+        self.overflow_inputs_pressed = Inputs::empty();
+        self.overflow_inputs_released = Inputs::empty();
+
+        self.claimed_input_events = 0;
         self.received_characters.clear();
-    }
 
-    pub fn allocator(&self) -> &A {
-        &self.allocator
+        if self.touch_release_next_frame != Inputs::NONE {
+            self.release_inputs(self.touch_release_next_frame);
+            self.touch_release_next_frame = Inputs::NONE;
+        }
     }
-}
-
-pub struct Frame<'a, A: Allocator + Clone> {
-    ui: &'a mut Ui<A>,
-}
 
-impl<'a, A: Allocator + Clone> Frame<'a, A> {
-    pub fn push_id_namespace(&mut self, id: u32) {
-        self.ui.id_namespace_stack.push(id);
-    }
+    fn count_debug_subtree_draw_primitives(&mut self) {
+        for ctrl in &mut self.tree {
+            ctrl.debug_subtree_draw_primitive_count = ctrl.draw_range.len();
+        }
 
-    pub fn pop_id_namespace(&mut self) {
-        self.ui.id_namespace_stack.pop();
+        // NB: The GC copies controls breadth-first, so children are always
+        // stored after their parents, and are counted before them.
+        for ctrl_idx in (0..self.tree.len()).rev() {
+            if let Some(parent_idx) = self.tree[ctrl_idx].parent_idx.map(CtrlIdx::get) {
+                let count = self.tree[ctrl_idx].debug_subtree_draw_primitive_count;
+                self.tree[parent_idx].debug_subtree_draw_primitive_count += count;
+            }
+        }
     }
 
-    pub fn push_ctrl(&mut self, ctrl_id: u32) -> Ctrl<'_, A> {
-        // TODO(yan): @Bug @Correctness We need to include every id in the
-        // namespace stack, otherwise they just overwrite each other. One way to
-        // do this would be hashing.
-        let base_id = self.ui.id_namespace_stack.last().copied().unwrap_or(0);
-        let id = join_id(base_id, ctrl_id);
+    fn draw_debug_overlay(&mut self) {
+        use core::fmt::Write as _;
 
-        // Push a control onto the tree. The control can either be completely
-        // new, or already present in the tree from previous frame. Controls are
-        // identified by their ID, which has to be unique among children of a
-        // single control.
-        //
-        // Whether inserting a new control or updating an existing one, we must
-        // never unlink a control already present in the tree, because it may
-        // yet be updated later this frame and we would have lost its state.
-        //
-        // Updated controls are first temporarily unlinked from the tree, and
-        // then re-inserted at the current position. This means that dead
-        // controls (if any) will be located after the live controls once the UI
-        // is built.
-        //
-        // Current position in the tree is tracked by two indices:
-        // build_parent_idx and build_sibling_idx, pointing to the current
-        // parent and last inserted sibling, respectively.
-        //
-        // Note: Changing the control's layout options invalidates the layout
-        // from last frame for this control and all its sibling successors, and
-        // their children, but so does re-ordering, or not updating a control
-        // from earlier frame. The layout will become valid on the next frame
-        // once again.
+        const COLOR_FREE: u32 = 0xff00ffff;
+        const COLOR_HORIZONTAL: u32 = 0xffff00ff;
+        const COLOR_VERTICAL: u32 = 0x00ffffff;
+        const COLOR_INSPECTED: u32 = 0xffffffff;
+        const COLOR_INSPECTOR_BACKGROUND: u32 = 0x000000e0;
+        const COLOR_INSPECTOR_TEXT: u32 = 0xffffffff;
+        const INSPECTOR_CURSOR_OFFSET: f32 = 16.0;
+        const INSPECTOR_PADDING: f32 = 4.0;
 
-        let build_parent_idx = self.ui.build_parent_idx.unwrap();
-        let draw_range = {
-            let next_idx = self.ui.draw_primitives.len();
-            next_idx..next_idx
-        };
+        let window_rect = Rect::from_points(Vec2::ZERO, self.window_size);
+        let texture_id = self.font_atlas_texture_id;
+        let thickness = 1.0 / self.window_scale_factor;
 
-        // TODO(yan): @Speed only search from build_sibling.sibling_idx, if
-        // build_sibling already exists.
-        let found_idx_and_prev_idx = {
-            let parent = &self.ui.tree[build_parent_idx];
+        for (ctrl_idx, ctrl) in self.tree.iter().enumerate() {
+            if ctrl_idx == ROOT_IDX || ctrl_idx == OVERLAY_ROOT_IDX {
+                continue;
+            }
 
-            // TODO(yan): @Speed This is quadratic. Not great.
-            if let Some(child_idx) = parent.child_idx {
-                let mut ctrl = &mut self.ui.tree[child_idx];
+            // Zero-size controls, e.g. ones that only hold state, have
+            // nothing to outline.
+            let rect = debug_ctrl_absolute_rect(ctrl);
+            if rect.width <= 0.0 || rect.height <= 0.0 {
+                continue;
+            }
 
-                if ctrl.id == id {
-                    Some((child_idx, None))
-                } else {
-                    let mut result = None;
+            let color = match ctrl.layout {
+                Layout::Free => COLOR_FREE,
+                Layout::Horizontal => COLOR_HORIZONTAL,
+                Layout::Vertical => COLOR_VERTICAL,
+            };
 
-                    let mut ctrl_idx = child_idx;
-                    while let Some(sibling_idx) = ctrl.sibling_idx {
-                        let prev_ctrl_idx = ctrl_idx;
-                        ctrl_idx = sibling_idx;
-                        ctrl = &mut self.ui.tree[sibling_idx];
+            self.draw_list.draw_rounded_rect_border(
+                rect,
+                0.0,
+                thickness,
+                color,
+                window_rect,
+                texture_id,
+            );
+        }
 
-                        if ctrl.id == id {
-                            result = Some((ctrl_idx, Some(prev_ctrl_idx)));
-                            break;
-                        }
-                    }
+        if !self.modifiers.contains(Modifiers::CTRL | Modifiers::SHIFT) {
+            return;
+        }
 
-                    result
-                }
-            } else {
-                None
-            }
+        let ctrl_idx = match self.hovered_ctrl_idx {
+            Some(ctrl_idx) if ctrl_idx != ROOT_IDX && ctrl_idx != OVERLAY_ROOT_IDX => ctrl_idx,
+            _ => return,
         };
 
-        let current_idx = if let Some((found_idx, found_prev_idx)) = found_idx_and_prev_idx {
-            let ctrl = &mut self.ui.tree[found_idx];
+        let ctrl = &self.tree[ctrl_idx];
+        let rect = debug_ctrl_absolute_rect(ctrl);
+
+        // NB: Overflowing text is cut off. That's fine for a debugging aid.
+        let mut text: ArrayString<512> = ArrayString::new();
+        let _ = write!(text, "id path: ");
+        write_debug_id_path(&self.tree, ctrl_idx, &mut text);
+        let _ = write!(
+            text,
+            "\npersistent id: {:#018x}\nflags: {:?}\nlayout: {:?}\nrect: {} {} {} {}\npadding: \
+             {}, border: {}, margin: {}\ncontent size: {} {}\nscroll offset: {} {}\ndraw \
+             primitives: {}, with descendants: {}",
+            ctrl.persistent_id,
+            ctrl.flags,
+            ctrl.layout,
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            ctrl.padding,
+            ctrl.border,
+            ctrl.margin,
+            ctrl.layout_cache_content_size.x,
+            ctrl.layout_cache_content_size.y,
+            ctrl.scroll_offset.x,
+            ctrl.scroll_offset.y,
+            ctrl.draw_range.len(),
+            ctrl.debug_subtree_draw_primitive_count,
+        );
 
-            // We do not support re-entrancy. Controls can only be updated
-            // once. This simplifies things:
-            //
-            // - We know that found_idx != build_sibling_idx, because the build
-            //   sibling would have to be pushed and popped before,
-            //
-            // - We know that found_idx hasn't been pushed yet.
-            //
-            // TODO(yan): @Correctness This assert goes off if we render the
-            // component only on some frames (discoverd by drawing a conditional
-            // window in the game). We most definitely were not updating the
-            // same component multiple times per frame, so this is an issue with
-            // unlinking dead controls and/or GC?
-            assert!(
-                ctrl.last_frame != self.ui.current_frame,
-                "Attempt to update the same control ({id}) twice in one frame",
-            );
+        self.draw_list.draw_rounded_rect_border(
+            rect,
+            0.0,
+            2.0 * thickness,
+            COLOR_INSPECTED,
+            window_rect,
+            texture_id,
+        );
 
-            ctrl.last_frame = self.ui.current_frame;
-            ctrl.inline_content_rect = None;
-            ctrl.draw_range = draw_range;
+        let line_metrics = self.font_atlas.font_horizontal_line_metrics();
+        let mut text_size = Vec2::ZERO;
+        for line in text.lines() {
+            let line_width: f32 = line
+                .chars()
+                .map(|c| self.font_atlas.glyph_info(c).advance_width)
+                .sum();
 
-            // After updating the control's data, we unlink the control from its
-            // original place and re-link as either the next sibling of the
-            // build sibling (if build sibling already exists) or first child of
-            // the build parent (if the build sibling doesn't exist yet).
-            if let Some(found_prev_idx) = found_prev_idx {
-                self.ui.tree[found_prev_idx].sibling_idx = ctrl.sibling_idx;
+            text_size.x = f32::max(text_size.x, line_width);
+            text_size.y += line_metrics.new_line_size;
+        }
+
+        let size = text_size + Vec2::splat(2.0 * INSPECTOR_PADDING);
+        let position = (self.cursor_position + Vec2::splat(INSPECTOR_CURSOR_OFFSET))
+            .min(self.window_size - size)
+            .max(Vec2::ZERO);
+
+        self.draw_list.draw_rect(
+            Rect::new(position.x, position.y, size.x, size.y),
+            Rect::ZERO,
+            COLOR_INSPECTOR_BACKGROUND,
+            window_rect,
+            texture_id,
+        );
+
+        let mut line_position = position + Vec2::splat(INSPECTOR_PADDING);
+        for line in text.lines() {
+            let mut glyph_position = line_position;
+            for c in line.chars() {
+                let glyph_info = self.font_atlas.glyph_info(c);
+                self.draw_list.draw_rect(
+                    glyph_info.rect + glyph_position + Vec2::y(line_metrics.ascent),
+                    glyph_info.atlas_rect,
+                    COLOR_INSPECTOR_TEXT,
+                    window_rect,
+                    texture_id,
+                );
+
+                glyph_position.x += glyph_info.advance_width;
             }
 
-            // Re-link the control as next sibling of the build sibling or
-            // as first child of build parent (in case there is no build
-            // sibling yet).
-            if let Some(build_sibling_idx) = self.ui.build_sibling_idx {
-                let build_sibling = &mut self.ui.tree[build_sibling_idx];
-                let build_sibling_next_sibling_idx = build_sibling.sibling_idx;
+            line_position.y += line_metrics.new_line_size;
+        }
+    }
 
-                // If we are already positioned correctly, relinking would
-                // create a cycle.
-                if build_sibling_next_sibling_idx != Some(found_idx) {
-                    build_sibling.sibling_idx = Some(found_idx);
-                    self.ui.tree[found_idx].sibling_idx = build_sibling_next_sibling_idx;
-                }
-            } else {
-                let build_parent = &mut self.ui.tree[build_parent_idx];
-                let build_parent_child_idx = build_parent.child_idx;
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
 
-                // If we are already positioned correctly, relinking would
-                // create a cycle.
-                if build_parent_child_idx != Some(found_idx) {
-                    build_parent.child_idx = Some(found_idx);
-                    self.ui.tree[found_idx].sibling_idx = build_parent_child_idx;
-                }
+    fn inputs_pressed(&self) -> Inputs {
+        let mut inputs = self.overflow_inputs_pressed;
+        for event in &self.input_events {
+            if let InputEvent::KeyPress {
+                input,
+                repeat: false,
+                ..
+            } = event
+            {
+                inputs |= *input;
             }
+        }
 
-            found_idx
-        } else {
-            let idx = self.ui.tree.len();
+        inputs
+    }
 
-            // Preserve links to controls from previous frame so that they can be
-            // found by future calls to push_ctrl in this subtree and depth.
-            let sibling_idx = if let Some(build_sibling_idx) = self.ui.build_sibling_idx {
-                let build_sibling = &mut self.ui.tree[build_sibling_idx];
-                let build_sibling_next_sibling_idx = build_sibling.sibling_idx;
+    fn inputs_pressed_or_repeated(&self) -> Inputs {
+        let mut inputs = Inputs::empty();
+        for event in &self.input_events {
+            if let InputEvent::KeyPress { input, .. } = event {
+                inputs |= *input;
+            }
+        }
 
-                build_sibling.sibling_idx = Some(idx);
-                build_sibling_next_sibling_idx
-            } else {
-                let build_parent = &mut self.ui.tree[build_parent_idx];
-                let build_parent_child_idx = build_parent.child_idx;
+        inputs
+    }
 
-                build_parent.child_idx = Some(idx);
-                build_parent_child_idx
-            };
+    // Ages the toasts, and forgets the ones that faded out.
+    fn age_toasts(&mut self, delta_time: Option<f32>) {
+        let delta_time = delta_time.unwrap_or(FALLBACK_DELTA_TIME);
+        let theme = self.theme();
+        let lifetime = theme.toast_duration + theme.toast_fade_duration;
 
-            self.ui.tree.push(CtrlNode {
-                id,
+        for toast in &mut self.toasts {
+            toast.age += delta_time;
+        }
 
-                parent_idx: Some(build_parent_idx),
-                child_idx: None,
-                sibling_idx,
+        self.toasts.retain(|toast| toast.age < lifetime);
+    }
 
-                first_frame: self.ui.current_frame,
-                last_frame: self.ui.current_frame,
-                last_frame_in_active_path: 0,
+    // Builds the toasts in the overlay, stacked in the top right corner of the
+    // window, newest at the bottom.
+    fn build_toasts(&mut self) {
+        if self.toasts.is_empty() {
+            return;
+        }
 
-                flags: CtrlFlags::NONE,
-                layout: Layout::Free,
-                rect: Rect::ZERO,
-                padding: 0.0,
-                border: 0.0,
-                margin: 0.0,
+        let window_size = self.window_size;
+        // Toasts only draw text, which doesn't need the temporary allocator.
+        let allocator = self.allocator.clone();
 
-                inline_content_rect: None,
+        let mut frame = Frame {
+            ui: self,
+            temp_allocator: &allocator,
+        };
+        frame.with_theme(|frame, theme| {
+            let container_id = frame.id("guise::toasts");
+            frame.begin_overlay();
+
+            let mut ctrl = frame.push_ctrl(container_id);
+            ctrl.set_flags(CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+            ctrl.set_layout(Layout::Vertical);
+            ctrl.set_rect(Rect::new(
+                f32::max(
+                    0.0,
+                    window_size.x - theme.toast_width - 2.0 * theme.toast_margin,
+                ),
+                0.0,
+                theme.toast_width + 2.0 * theme.toast_margin,
+                0.0,
+            ));
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(0.0);
+
+            for i in 0..frame.ui.toasts.len() {
+                let toast = &frame.ui.toasts[i];
+                let kind = toast.kind;
+                let fade_age = toast.age - theme.toast_duration;
+                let alpha = if fade_age > 0.0 && theme.toast_fade_duration > 0.0 {
+                    1.0 - fade_age / theme.toast_fade_duration
+                } else {
+                    1.0
+                };
+                let fade = |color: u32| color_multiply_alpha(color, alpha);
 
-                scroll_offset: Vec2::ZERO,
+                let background_color = match kind {
+                    ToastKind::Info => theme.toast_background_color_info,
+                    ToastKind::Warn => theme.toast_background_color_warn,
+                    ToastKind::Error => theme.toast_background_color_error,
+                };
 
-                state: [0; 64],
+                // NB: The text is swapped out of the toast while drawing, because
+                // drawing needs the whole Ui.
+                let text = mem::replace(
+                    &mut frame.ui.toasts[i].text,
+                    VecString::new_in(frame.ui.allocator.clone()),
+                );
 
-                draw_self: false,
-                draw_self_border_color: 0,
-                draw_self_background_color: 0,
-                draw_range,
+                let mut ctrl = frame.push_ctrl(i as u32);
+                ctrl.set_flags(CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+                ctrl.set_layout(Layout::Free);
+                ctrl.set_rect(Rect::new(0.0, 0.0, theme.toast_width, 0.0));
+                ctrl.set_padding(0.0);
+                ctrl.set_border(theme.toast_border);
+                ctrl.set_margin(theme.toast_margin);
+
+                ctrl.set_draw_self(true);
+                ctrl.set_draw_self_border_color(fade(theme.toast_border_color));
+                ctrl.set_draw_self_background_color(fade(background_color));
+                ctrl.draw_text_inset_and_extend_content_rect(
+                    &text,
+                    Align::Start,
+                    Align::Start,
+                    Wrap::Word,
+                    Direction::Ltr,
+                    fade(theme.toast_text_color),
+                    theme.toast_padding,
+                );
 
-                layout_cache_absolute_position: Vec2::ZERO,
-                layout_cache_content_size: Vec2::ZERO,
-            });
+                frame.pop_ctrl();
 
-            idx
+                frame.ui.toasts[i].text = text;
+            }
+
+            frame.pop_ctrl();
+            frame.end_overlay();
+        });
+    }
+
+    // Ages the primary touch, and turns it into a long press (a right click),
+    // if it has been held in place long enough.
+    fn advance_touch(&mut self, delta_time: Option<f32>) {
+        let Some(touch) = &mut self.touch else {
+            return;
         };
 
-        self.ui.build_parent_idx = Some(current_idx);
-        self.ui.build_sibling_idx = None;
+        if touch.state == TouchState::Pending {
+            touch.held_time += delta_time.unwrap_or(FALLBACK_DELTA_TIME);
 
-        Ctrl {
-            idx: current_idx,
-            ui: self.ui,
+            let long_press_duration = self.touch_options.long_press_duration;
+            if long_press_duration.map_or(false, |duration| touch.held_time >= duration) {
+                touch.state = TouchState::LongPressed;
+                self.press_inputs(Inputs::MB_RIGHT);
+            }
         }
     }
 
-    pub fn pop_ctrl(&mut self) {
-        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+    // Tracks the last pressed repeatable key until it is released, and
+    // synthesizes a repeated press, if it has been held long enough.
+    fn repeat_held_key(&mut self, delta_time: Option<f32>) {
+        const REPEATABLE_INPUTS: Inputs = Inputs::KB_LEFT_ARROW
+            | Inputs::KB_RIGHT_ARROW
+            | Inputs::KB_UP_ARROW
+            | Inputs::KB_DOWN_ARROW
+            | Inputs::KB_PAGE_UP
+            | Inputs::KB_PAGE_DOWN
+            | Inputs::KB_DELETE
+            | Inputs::KB_BACKSPACE;
+
+        let Some(key_repeat) = self.key_repeat else {
+            return;
+        };
 
-        self.ui.last_ctrl_idx = Some(build_parent_idx);
+        let mut pressed_this_frame = false;
+        for event in &self.input_events {
+            match *event {
+                InputEvent::KeyPress { input, .. } if input.intersects(REPEATABLE_INPUTS) => {
+                    self.key_repeat_input = input & REPEATABLE_INPUTS;
+                    self.key_repeat_held_time = 0.0;
+                    self.key_repeat_count = 0;
+                    pressed_this_frame = true;
+                }
+                InputEvent::KeyRelease { input, .. } if input.intersects(self.key_repeat_input) => {
+                    self.key_repeat_input = Inputs::NONE;
+                }
+                _ => (),
+            }
+        }
 
-        // Finalize the parent and last inserted sibling controls and clean
-        // their indices so that they only reference live controls. If no child
-        // controls were inserted, clear the parent's child references (which
-        // could contain dead controls from previous frame). Also cut off the
-        // dead sibling controls of the last sibling here, so that they are not
-        // reachable.
+        if self.key_repeat_input == Inputs::NONE || pressed_this_frame {
+            return;
+        }
 
-        // TODO(yan): @Correctness Assert that push_ctrl and pop_ctrl are
-        // parenthesized correctly! Count current tree depth and assert
-        // something in both pop_ctrl and end_frame?
+        self.key_repeat_held_time += delta_time.unwrap_or(FALLBACK_DELTA_TIME);
 
-        let build_parent = &mut self.ui.tree[build_parent_idx];
-        let build_parent_parent_idx = build_parent.parent_idx;
+        let repeat_time = self.key_repeat_held_time - key_repeat.delay;
+        if repeat_time >= 0.0 {
+            let interval = f32::max(key_repeat.interval, f32::EPSILON);
+            let repeat_count = 1 + libm::floorf(repeat_time / interval) as u32;
 
-        if let Some(build_sibling_idx) = self.ui.build_sibling_idx {
-            self.ui.tree[build_sibling_idx].sibling_idx = None;
+            if repeat_count > self.key_repeat_count {
+                self.key_repeat_count = repeat_count;
+
+                let _ = self.input_events.try_push(InputEvent::KeyPress {
+                    input: self.key_repeat_input,
+                    modifiers: self.modifiers,
+                    repeat: true,
+                });
+            }
+        }
+    }
+
+    fn inputs_released(&self) -> Inputs {
+        let mut inputs = self.overflow_inputs_released;
+        for event in &self.input_events {
+            if let InputEvent::KeyRelease { input, .. } = event {
+                inputs |= *input;
+            }
+        }
+
+        inputs
+    }
+
+    fn swap_build_state_with_overlay(&mut self) {
+        mem::swap(
+            &mut self.build_parent_idx,
+            &mut self.overlay_build_parent_idx,
+        );
+        mem::swap(
+            &mut self.build_sibling_idx,
+            &mut self.overlay_build_sibling_idx,
+        );
+        mem::swap(&mut self.build_depth, &mut self.overlay_build_depth);
+        #[cfg(debug_assertions)]
+        mem::swap(
+            &mut self.build_ctrl_id_stack,
+            &mut self.overlay_build_ctrl_id_stack,
+        );
+    }
+
+    // Claims the first unclaimed keypress matching the shortcut, if any.
+    fn claim_shortcut(&mut self, shortcut: Shortcut) -> bool {
+        for (i, event) in self.input_events.iter().enumerate() {
+            let claimed = self.claimed_input_events & (1 << i) != 0;
+            if let InputEvent::KeyPress {
+                input, modifiers, ..
+            } = event
+            {
+                if !claimed && shortcut.matches(*input, *modifiers) {
+                    self.claimed_input_events |= 1 << i;
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn tab_stop_width(&self) -> f32 {
+        self.tab_width as f32 * self.font_atlas.glyph_info(' ').advance_width
+    }
+
+    fn panic_unpopped_ctrls(&self, message: &str) -> ! {
+        #[cfg(debug_assertions)]
+        panic!(
+            "{message} Unpopped controls (ids as passed to push_ctrl): {:?}",
+            &self.build_ctrl_id_stack[..],
+        );
+
+        #[cfg(not(debug_assertions))]
+        panic!("{message} Innermost unpopped control: {}", IdPath {
+            tree: &self.tree,
+            idx: self.build_parent_idx.unwrap(),
+        },);
+    }
+
+    // Returns how much of the remaining distance to their targets should
+    // scroll offsets cover this frame, or None if scrolling should happen
+    // instantly.
+    fn scroll_smoothing_factor(&self) -> Option<f32> {
+        match (self.scroll_smoothing_time_constant, self.delta_time) {
+            (Some(time_constant), Some(delta_time)) if time_constant > 0.0 => {
+                Some(1.0 - libm::expf(-delta_time / time_constant))
+            }
+            _ => None,
+        }
+    }
+
+    fn ctrl_state(&self, ctrl_idx: usize) -> &CtrlState {
+        let ctrl = &self.tree[ctrl_idx];
+        match ctrl.state_idx {
+            Some(state_idx) => &self.ctrl_states[state_idx].0,
+            None => match self.loaded_ctrl_states.get(&ctrl.persistent_id) {
+                Some(loaded_state) => &loaded_state.0,
+                None => &ZERO_CTRL_STATE.0,
+            },
+        }
+    }
+
+    fn ctrl_state_mut(&mut self, ctrl_idx: usize) -> &mut CtrlState {
+        let state_idx = match self.tree[ctrl_idx].state_idx {
+            Some(state_idx) => state_idx,
+            None => {
+                // NB: Checking the map is cheap while it is empty, which it is
+                // unless the application loads persistent state.
+                let state = self
+                    .loaded_ctrl_states
+                    .remove(&self.tree[ctrl_idx].persistent_id)
+                    .unwrap_or(AlignedCtrlState([0; 64]));
+
+                let state_idx = if let Some(state_idx) = self.ctrl_state_free_list.pop() {
+                    self.ctrl_states[state_idx] = state;
+                    state_idx
+                } else {
+                    self.ctrl_states.push(state);
+                    self.ctrl_states.len() - 1
+                };
+
+                self.tree[ctrl_idx].state_idx = Some(state_idx);
+                state_idx
+            }
+        };
+
+        &mut self.ctrl_states[state_idx].0
+    }
+}
+
+pub struct Frame<'a, A: Allocator + Clone> {
+    ui: &'a mut UiCore<A>,
+    temp_allocator: &'a (dyn Allocator + 'a),
+}
+
+impl<'a, A: Allocator + Clone> Frame<'a, A> {
+    /// Pushes an id onto the id stack. Controls pushed while it is on the
+    /// stack have their ids mixed with it, so that the same control ids can be
+    /// reused in loops (e.g. by pushing the iteration index) or reusable widget
+    /// functions without colliding. The id is mixed with the current top of
+    /// the stack (if any), so nested ids are unique across the whole stack,
+    /// not just the innermost id. Pop it with [`Frame::pop_id`].
+    pub fn push_id(&mut self, id: u32) {
+        let namespace = mix_id(self.current_id_namespace(), id);
+        self.ui.id_namespace_stack.push(namespace);
+    }
+
+    /// Pops an id pushed with [`Frame::push_id`] or [`Frame::push_id_str`].
+    pub fn pop_id(&mut self) {
+        let popped = self.ui.id_namespace_stack.pop();
+        assert!(popped.is_some(), "Is there a push_id for every pop_id?");
+    }
+
+    /// Pushes an id derived from a string onto the id stack. Pop it with
+    /// [`Frame::pop_id`].
+    pub fn push_id_str(&mut self, s: &str) {
+        // Frame::id already mixes in the current namespace.
+        let namespace = self.id(s);
+        self.ui.id_namespace_stack.push(namespace);
+    }
+
+    /// Returns the id a control with the local id (e.g. `line!()`) gets when
+    /// pushed in the current id namespace.
+    pub fn current_id(&self, local: u32) -> u32 {
+        mix_id(self.current_id_namespace(), local)
+    }
+
+    /// Hashes a string into a control id, seeded by the current id namespace,
+    /// if any. Unlike `line!()`, the resulting ids stay the same when code
+    /// moves around and don't collide across files.
+    pub fn id(&self, s: &str) -> u32 {
+        hash_str(self.current_id_namespace(), s)
+    }
+
+    /// Returns the theme used by widget functions that don't take a theme
+    /// argument. This is the theme last pushed with [`Frame::push_theme`], or
+    /// the one set with [`Ui::set_theme`], if there is none.
+    pub fn theme(&self) -> &Theme {
+        self.assert_theme_not_taken();
+        self.ui.theme_stack.last().unwrap()
+    }
+
+    /// Lends the current theme (see [`Frame::theme`]) to the closure, together
+    /// with the frame, without copying the theme. Widget functions that don't
+    /// take a theme argument use this to call their `*_with_theme` variant.
+    ///
+    /// The theme stack can't be used from inside the closure, so widgets built
+    /// there must be given the theme explicitly. The closure also can't return
+    /// a [`Ctrl`], because it borrows the frame, but the control can be
+    /// reborrowed afterwards with [`Frame::ctrl`].
+    pub fn with_theme<R>(&mut self, f: impl FnOnce(&mut Self, &Theme) -> R) -> R {
+        self.assert_theme_not_taken();
+
+        // NB: Moving the box out of the stack is cheap and lets us borrow the
+        // frame mutably while the theme is borrowed.
+        let theme = self.ui.theme_stack.pop().unwrap();
+        self.ui.theme_taken = true;
+
+        let result = f(self, &theme);
+
+        self.ui.theme_taken = false;
+        self.ui.theme_stack.push(theme);
+
+        result
+    }
+
+    /// Overrides the theme for widgets built until the matching
+    /// [`Frame::pop_theme`], e.g. to style one window differently.
+    pub fn push_theme(&mut self, theme: &Theme) {
+        self.assert_theme_not_taken();
+
+        let allocator = self.ui.theme_stack.allocator().clone();
+        self.ui
+            .theme_stack
+            .push(Box::new_in(theme.clone(), allocator));
+    }
+
+    pub fn pop_theme(&mut self) {
+        self.assert_theme_not_taken();
+        assert!(
+            self.ui.theme_stack.len() > 1,
+            "Is there a push_theme for every pop_theme?"
+        );
+        if let Some(&(style_var, _, theme_stack_len)) = self.ui.style_var_stack.last() {
+            assert!(
+                theme_stack_len < self.ui.theme_stack.len(),
+                "Is there a pop_style_var for every push_style_var before pop_theme? Unpopped: \
+                 {style_var:?}",
+            );
+        }
+
+        self.ui.theme_stack.pop();
+    }
+
+    fn assert_theme_not_taken(&self) {
+        assert!(
+            !self.ui.theme_taken,
+            "The theme stack can't be used while a widget borrows the theme. Call the \
+             *_with_theme variant of the widget instead.",
+        );
+    }
+
+    /// Overrides a single metric of the current theme for widgets built until
+    /// the matching [`Frame::pop_style_var`]. Unlike [`Frame::push_theme`],
+    /// this doesn't copy the theme. Style vars must be popped before popping
+    /// the theme they were pushed onto.
+    pub fn push_style_var(&mut self, style_var: StyleVar) {
+        let theme_stack_len = self.ui.theme_stack.len();
+        let theme = self.theme_mut();
+        let replaced = style_var.swap(theme);
+        self.ui
+            .style_var_stack
+            .push((style_var, replaced, theme_stack_len));
+    }
+
+    pub fn pop_style_var(&mut self) {
+        let popped = self.ui.style_var_stack.pop();
+        let (style_var, replaced, theme_stack_len) =
+            popped.expect("Is there a push_style_var for every pop_style_var?");
+        assert!(
+            theme_stack_len == self.ui.theme_stack.len(),
+            "Is there a pop_theme for every push_theme after push_style_var? Unpopped: \
+             {style_var:?}",
+        );
+
+        let theme = self.theme_mut();
+        replaced.swap(theme);
+    }
+
+    /// Disables controls built until the matching [`Frame::pop_disabled`], if
+    /// `disabled` is true. Disabled controls don't become hovered or active,
+    /// but still capture hover, so that clicks don't fall through them to
+    /// controls underneath. They are drawn with [`disabled_color`] applied to
+    /// all their colors.
+    ///
+    /// Disabling nests, so `push_disabled(false)` inside a disabled scope
+    /// doesn't enable anything.
+    pub fn push_disabled(&mut self, disabled: bool) {
+        let disabled = disabled || self.is_disabled();
+        self.ui.disabled_stack.push(disabled);
+    }
+
+    pub fn pop_disabled(&mut self) {
+        let popped = self.ui.disabled_stack.pop();
+        assert!(
+            popped.is_some(),
+            "Is there a push_disabled for every pop_disabled?"
+        );
+    }
+
+    /// Whether controls pushed now are disabled.
+    pub fn is_disabled(&self) -> bool {
+        self.ui.disabled_stack.last().copied().unwrap_or(false)
+    }
+
+    fn theme_mut(&mut self) -> &mut Theme {
+        self.assert_theme_not_taken();
+        self.ui.theme_stack.last_mut().unwrap()
+    }
+
+    fn current_id_namespace(&self) -> u32 {
+        // NB: Namespaces on the stack are already mixed with their parents, so
+        // the top of the stack identifies the whole stack.
+        self.ui.id_namespace_stack.last().copied().unwrap_or(0)
+    }
+
+    pub fn push_ctrl(&mut self, ctrl_id: u32) -> Ctrl<'_, A> {
+        let id = self.current_id(ctrl_id);
+
+        assert!(
+            self.ui.clip_rect_stack.is_empty(),
+            "Is there a pop_clip_rect for every push_clip_rect?",
+        );
+
+        self.ui.build_depth += 1;
+        #[cfg(debug_assertions)]
+        self.ui.build_ctrl_id_stack.push(ctrl_id);
+
+        // Push a control onto the tree. The control can either be completely
+        // new, or already present in the tree from previous frame. Controls are
+        // identified by their ID, which has to be unique among children of a
+        // single control.
+        //
+        // Whether inserting a new control or updating an existing one, we must
+        // never unlink a control already present in the tree, because it may
+        // yet be updated later this frame and we would have lost its state.
+        //
+        // Updated controls are first temporarily unlinked from the tree, and
+        // then re-inserted at the current position. This means that dead
+        // controls (if any) will be located after the live controls once the UI
+        // is built.
+        //
+        // Current position in the tree is tracked by two indices:
+        // build_parent_idx and build_sibling_idx, pointing to the current
+        // parent and last inserted sibling, respectively.
+        //
+        // Note: Changing the control's layout options invalidates the layout
+        // from last frame for this control and all its sibling successors, and
+        // their children, but so does re-ordering, or not updating a control
+        // from earlier frame. The layout will become valid on the next frame
+        // once again.
+
+        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+        let disabled = self.is_disabled();
+        let draw_range = {
+            let next_idx = self.ui.draw_primitives.len();
+            next_idx..next_idx
+        };
+
+        // NB: While building the overlay, the base layer's build parent is
+        // swapped out to overlay_build_parent_idx.
+        // Top level controls of nested overlays are owned by the control the
+        // overlay was begun in.
+        let overlay_owner_idx = if self.ui.building_overlay && build_parent_idx == OVERLAY_ROOT_IDX
+        {
+            self.ui
+                .overlay_build_stack
+                .last()
+                .map(|cursor| cursor.parent_idx)
+                .filter(|&parent_idx| parent_idx != OVERLAY_ROOT_IDX)
+                .or(self.ui.overlay_build_parent_idx)
+                .map(CtrlIdx::new)
         } else {
-            build_parent.child_idx = None;
+            None
+        };
+
+        let viewport = if build_parent_idx == ROOT_IDX || build_parent_idx == OVERLAY_ROOT_IDX {
+            self.ui
+                .build_viewport_idx
+                .map(|viewport_idx| self.ui.viewports[viewport_idx].id)
+        } else {
+            self.ui.tree[build_parent_idx].viewport
+        };
+
+        // Detect id collisions between siblings.
+        #[cfg(debug_assertions)]
+        if !self.ui.build_sibling_ids.insert((build_parent_idx, id)) {
+            let path = IdPath {
+                tree: &self.ui.tree,
+                idx: build_parent_idx,
+            };
+            panic!(
+                "Control id {ctrl_id} (mixed with the id stack: {id}) is used by multiple \
+                 siblings under control {path}. Do two widgets share the same id? Use push_id to \
+                 disambiguate widgets built in loops or reusable functions.",
+            );
         }
 
-        self.ui.build_parent_idx = build_parent_parent_idx;
-        self.ui.build_sibling_idx = Some(build_parent_idx);
+        // TODO(yan): @Speed only search from build_sibling.sibling_idx, if
+        // build_sibling already exists.
+        let found_idx_and_prev_idx = {
+            let parent = &self.ui.tree[build_parent_idx];
+
+            // TODO(yan): @Speed This is quadratic. Not great.
+            if let Some(child_idx) = parent.child_idx.map(CtrlIdx::get) {
+                let mut ctrl = &mut self.ui.tree[child_idx];
+
+                if ctrl.id == id {
+                    Some((child_idx, None))
+                } else {
+                    let mut result = None;
+
+                    let mut ctrl_idx = child_idx;
+                    while let Some(sibling_idx) = ctrl.sibling_idx.map(CtrlIdx::get) {
+                        let prev_ctrl_idx = ctrl_idx;
+                        ctrl_idx = sibling_idx;
+                        ctrl = &mut self.ui.tree[sibling_idx];
+
+                        if ctrl.id == id {
+                            result = Some((ctrl_idx, Some(prev_ctrl_idx)));
+                            break;
+                        }
+                    }
+
+                    result
+                }
+            } else {
+                None
+            }
+        };
+
+        let current_idx = if let Some((found_idx, found_prev_idx)) = found_idx_and_prev_idx {
+            // We do not support re-entrancy. Controls can only be updated
+            // once. This simplifies things:
+            //
+            // - We know that found_idx != build_sibling_idx, because the build
+            //   sibling would have to be pushed and popped before,
+            //
+            // - We know that found_idx hasn't been pushed yet.
+            //
+            // NB: Unlinking and GC never leave links to stale controls, even
+            // for controls only built on some frames (e.g. conditional
+            // windows), so this only goes off if two widgets share an id.
+            if self.ui.tree[found_idx].last_frame == self.ui.current_frame {
+                let path = IdPath {
+                    tree: &self.ui.tree,
+                    idx: found_idx,
+                };
+                panic!(
+                    "Attempt to update the same control ({path}) twice in one frame. Do two \
+                     widgets share the same id?",
+                );
+            }
+
+            let ctrl = &mut self.ui.tree[found_idx];
+            ctrl.overlay_owner_idx = overlay_owner_idx;
+            ctrl.viewport = viewport;
+            ctrl.disabled = disabled;
+            ctrl.last_frame = self.ui.current_frame;
+
+            // A control disabled while active, e.g. a text input being edited
+            // when its section is made read-only, loses the active status.
+            if disabled && self.ui.active_ctrl_idx == Some(found_idx) {
+                self.ui.active_ctrl_idx = None;
+            }
+            ctrl.inline_content_rect = None;
+            ctrl.aspect_ratio = None;
+            ctrl.close_on_outside_click = false;
+            ctrl.persistent = false;
+            ctrl.draw_range = draw_range;
+
+            // After updating the control's data, we unlink the control from its
+            // original place and re-link as either the next sibling of the
+            // build sibling (if build sibling already exists) or first child of
+            // the build parent (if the build sibling doesn't exist yet).
+            if let Some(found_prev_idx) = found_prev_idx {
+                self.ui.tree[found_prev_idx].sibling_idx = ctrl.sibling_idx;
+            }
+
+            // Re-link the control as next sibling of the build sibling or
+            // as first child of build parent (in case there is no build
+            // sibling yet).
+            if let Some(build_sibling_idx) = self.ui.build_sibling_idx {
+                let build_sibling = &mut self.ui.tree[build_sibling_idx];
+                let build_sibling_next_sibling_idx = build_sibling.sibling_idx;
+
+                // If we are already positioned correctly, relinking would
+                // create a cycle.
+                if build_sibling_next_sibling_idx != Some(CtrlIdx::new(found_idx)) {
+                    build_sibling.sibling_idx = Some(CtrlIdx::new(found_idx));
+                    self.ui.tree[found_idx].sibling_idx = build_sibling_next_sibling_idx;
+                }
+            } else {
+                let build_parent = &mut self.ui.tree[build_parent_idx];
+                let build_parent_child_idx = build_parent.child_idx;
+
+                // If we are already positioned correctly, relinking would
+                // create a cycle.
+                if build_parent_child_idx != Some(CtrlIdx::new(found_idx)) {
+                    build_parent.child_idx = Some(CtrlIdx::new(found_idx));
+                    self.ui.tree[found_idx].sibling_idx = build_parent_child_idx;
+                }
+            }
+
+            found_idx
+        } else {
+            let idx = self.ui.tree.len();
+
+            // Preserve links to controls from previous frame so that they can be
+            // found by future calls to push_ctrl in this subtree and depth.
+            let sibling_idx = if let Some(build_sibling_idx) = self.ui.build_sibling_idx {
+                let build_sibling = &mut self.ui.tree[build_sibling_idx];
+                let build_sibling_next_sibling_idx = build_sibling.sibling_idx;
+
+                build_sibling.sibling_idx = Some(CtrlIdx::new(idx));
+                build_sibling_next_sibling_idx
+            } else {
+                let build_parent = &mut self.ui.tree[build_parent_idx];
+                let build_parent_child_idx = build_parent.child_idx;
+
+                build_parent.child_idx = Some(CtrlIdx::new(idx));
+                build_parent_child_idx
+            };
+
+            let persistent_id = mix_persistent_id(self.ui.tree[build_parent_idx].persistent_id, id);
+
+            self.ui.tree.push(CtrlNode {
+                id,
+                persistent_id,
+
+                parent_idx: Some(CtrlIdx::new(build_parent_idx)),
+                child_idx: None,
+                sibling_idx,
+                overlay_owner_idx,
+                viewport,
+
+                first_frame: self.ui.current_frame,
+                last_frame: self.ui.current_frame,
+                last_frame_in_active_path: 0,
+                z_index: 0,
+
+                disabled,
+
+                flags: CtrlFlags::NONE,
+                layout: Layout::Free,
+                rect: Rect::ZERO,
+                padding: 0.0,
+                border: 0.0,
+                margin: 0.0,
+
+                inline_content_rect: None,
+                aspect_ratio: None,
+                close_on_outside_click: false,
+                clicked_outside: false,
+
+                scroll_offset: Vec2::ZERO,
+                scroll_offset_target: Vec2::ZERO,
+
+                state_idx: None,
+                persistent: false,
+
+                draw_self: false,
+                draw_self_border_color: 0,
+                draw_self_background_color: 0,
+                draw_range,
+                debug_subtree_draw_primitive_count: 0,
+
+                scrollbar_width: 0.0,
+                scrollbar_track_color: 0,
+                scrollbar_thumb_color: 0,
+
+                layout_cache_absolute_position: Vec2::ZERO,
+                layout_cache_content_size: Vec2::ZERO,
+                layout_cache_scroll_offset: Vec2::ZERO,
+            });
+
+            idx
+        };
+
+        if self.ui.building_overlay && build_parent_idx == OVERLAY_ROOT_IDX {
+            self.ui.overlay_build_last_top_level_idx = Some(current_idx);
+
+            if let Some(cursor) = self.ui.overlay_build_stack.last() {
+                self.ui.tree[current_idx].z_index = cursor.z_index.saturating_add(1);
+            }
+        }
+
+        self.ui.build_parent_idx = Some(current_idx);
+        self.ui.build_sibling_idx = None;
+
+        Ctrl {
+            idx: current_idx,
+            ui: self.ui,
+            temp_allocator: self.temp_allocator,
+        }
+    }
+
+    pub fn pop_ctrl(&mut self) {
+        assert!(
+            self.ui.build_depth > 0,
+            "Attempt to pop_ctrl without a matching push_ctrl{}.",
+            if self.ui.building_overlay {
+                " in the overlay"
+            } else {
+                ""
+            },
+        );
+
+        assert!(
+            self.ui.clip_rect_stack.is_empty(),
+            "Is there a pop_clip_rect for every push_clip_rect?",
+        );
+
+        self.ui.build_depth -= 1;
+        #[cfg(debug_assertions)]
+        self.ui.build_ctrl_id_stack.pop();
+
+        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+
+        self.ui.last_ctrl_idx = Some(build_parent_idx);
+
+        // Finalize the parent and last inserted sibling controls and clean
+        // their indices so that they only reference live controls. If no child
+        // controls were inserted, clear the parent's child references (which
+        // could contain dead controls from previous frame). Also cut off the
+        // dead sibling controls of the last sibling here, so that they are not
+        // reachable.
+
+        let build_parent = &mut self.ui.tree[build_parent_idx];
+        let build_parent_parent_idx = build_parent.parent_idx.map(CtrlIdx::get);
+
+        if let Some(build_sibling_idx) = self.ui.build_sibling_idx {
+            self.ui.tree[build_sibling_idx].sibling_idx = None;
+        } else {
+            build_parent.child_idx = None;
+        }
+
+        // NB: Nested overlays could have added top level overlay controls
+        // after this one, so we continue after the last of them.
+        self.ui.build_parent_idx = build_parent_parent_idx;
+        self.ui.build_sibling_idx = if build_parent_parent_idx == Some(OVERLAY_ROOT_IDX) {
+            self.ui.overlay_build_last_top_level_idx
+        } else {
+            Some(build_parent_idx)
+        };
+    }
+
+    /// Starts building controls in the overlay, on top of the base layer,
+    /// until [`Frame::end_overlay`]. Can be called multiple times per frame,
+    /// each time continuing after the controls already in the overlay.
+    ///
+    /// Overlays can also be nested, e.g. for a dropdown in a modal dialog.
+    /// The nested overlay's controls are placed at the top level of the
+    /// overlay, above the overlay they were begun in, and are owned by the
+    /// control being built when the nested overlay began.
+    pub fn begin_overlay(&mut self) {
+        if self.ui.building_overlay {
+            let parent_idx = self.ui.build_parent_idx.unwrap();
+
+            // Find the top level overlay control we are building in.
+            let mut top_level_idx = parent_idx;
+            while let Some(idx) = self.ui.tree[top_level_idx].parent_idx.map(CtrlIdx::get) {
+                if idx == OVERLAY_ROOT_IDX {
+                    break;
+                }
+                top_level_idx = idx;
+            }
+            let z_index = if top_level_idx == OVERLAY_ROOT_IDX {
+                0
+            } else {
+                self.ui.tree[top_level_idx].z_index
+            };
+
+            let cursor = OverlayBuildCursor {
+                parent_idx,
+                sibling_idx: self.ui.build_sibling_idx,
+                depth: self.ui.build_depth,
+                z_index,
+            };
+            assert!(
+                self.ui.overlay_build_stack.try_push(cursor).is_ok(),
+                "Attempt to nest overlays too deep. Is there an end_overlay for every \
+                 begin_overlay?",
+            );
+
+            self.ui.build_parent_idx = Some(OVERLAY_ROOT_IDX);
+            self.ui.build_sibling_idx = self.ui.overlay_build_last_top_level_idx;
+            self.ui.build_depth = 0;
+        } else {
+            self.ui.swap_build_state_with_overlay();
+
+            self.ui.building_overlay = true;
+        }
+    }
+
+    pub fn end_overlay(&mut self) {
+        assert!(
+            self.ui.building_overlay,
+            "Attempt to end_overlay without a matching begin_overlay.",
+        );
+        if self.ui.build_depth != 0 {
+            self.ui.panic_unpopped_ctrls(
+                "Is there a pop_ctrl for every push_ctrl between begin_overlay and end_overlay?",
+            );
+        }
+
+        if let Some(cursor) = self.ui.overlay_build_stack.pop() {
+            self.ui.build_parent_idx = Some(cursor.parent_idx);
+            self.ui.build_depth = cursor.depth;
+
+            // If the nested overlay was begun at the top level, the controls
+            // it added are now our siblings.
+            self.ui.build_sibling_idx = if cursor.parent_idx == OVERLAY_ROOT_IDX {
+                self.ui.overlay_build_last_top_level_idx
+            } else {
+                cursor.sibling_idx
+            };
+        } else {
+            self.ui.swap_build_state_with_overlay();
+
+            self.ui.building_overlay = false;
+        }
+    }
+
+    /// Starts building controls for the viewport with the id, e.g. a second
+    /// OS window, until [`Frame::end_viewport`]. The viewport is a control
+    /// with the id and size at the top level, and the controls built in it
+    /// are positioned relative to its top left corner. Its controls,
+    /// including their overlays, are rendered into a separate draw list (see
+    /// [`Ui::draw_list_for_viewport`]), and are only hovered while the cursor
+    /// is in the viewport (see [`Ui::set_cursor_position_for_viewport`]).
+    ///
+    /// Controls built outside any viewport belong to the main one, which
+    /// renders into [`Ui::draw_list`]. Viewports not built in a frame are
+    /// removed.
+    pub fn begin_viewport(&mut self, id: u32, size: Vec2) {
+        assert!(
+            self.ui.build_viewport_idx.is_none(),
+            "Attempt to begin_viewport while already building a viewport. Is there an \
+             end_viewport for every begin_viewport?",
+        );
+        assert!(
+            !self.ui.building_overlay,
+            "Attempt to begin_viewport while building the overlay.",
+        );
+        if self.ui.build_depth != 0 {
+            self.ui.panic_unpopped_ctrls(
+                "Viewports can only be started at the top level. Is there a pop_ctrl for every \
+                 push_ctrl before begin_viewport?",
+            );
+        }
+
+        let current_frame = self.ui.current_frame;
+        let viewport_idx = match self
+            .ui
+            .viewports
+            .iter()
+            .position(|viewport| viewport.id == id)
+        {
+            Some(viewport_idx) => {
+                let viewport = &mut self.ui.viewports[viewport_idx];
+                assert!(
+                    viewport.last_frame != current_frame,
+                    "Attempt to begin the same viewport twice in one frame.",
+                );
+
+                viewport.size = size;
+                viewport.last_frame = current_frame;
+
+                viewport_idx
+            }
+            None => {
+                let mut draw_list = DrawList::with_capacity_in(0, self.ui.allocator.clone());
+                draw_list.set_premultiplied_alpha(self.ui.premultiplied_alpha);
+                draw_list.set_linear_color_output(self.ui.linear_color_output);
+
+                self.ui.viewports.push(Viewport {
+                    id,
+                    size,
+                    last_frame: current_frame,
+                    draw_list,
+                });
+
+                self.ui.viewports.len() - 1
+            }
+        };
+
+        self.ui.build_viewport_idx = Some(viewport_idx);
+
+        let mut ctrl = self.push_ctrl(id);
+        ctrl.set_flags(CtrlFlags::NONE);
+        ctrl.set_layout(Layout::Free);
+        ctrl.set_rect(Rect::from_points(Vec2::ZERO, size));
+        ctrl.set_padding(0.0);
+        ctrl.set_border(0.0);
+        ctrl.set_margin(0.0);
+        ctrl.set_draw_self(false);
+    }
+
+    pub fn end_viewport(&mut self) {
+        assert!(
+            self.ui.build_viewport_idx.is_some(),
+            "Attempt to end_viewport without a matching begin_viewport.",
+        );
+        assert!(
+            !self.ui.building_overlay,
+            "Attempt to end_viewport while building the overlay. Is there an end_overlay for \
+             every begin_overlay?",
+        );
+        if self.ui.build_depth != 1 {
+            self.ui.panic_unpopped_ctrls(
+                "Is there a pop_ctrl for every push_ctrl between begin_viewport and end_viewport?",
+            );
+        }
+
+        self.pop_ctrl();
+        self.ui.build_viewport_idx = None;
+    }
+
+    pub fn font_atlas(&self) -> &FontAtlas<A> {
+        &self.ui.font_atlas
+    }
+
+    pub fn font_atlas_texture_id(&self) -> u64 {
+        self.ui.font_atlas_texture_id
+    }
+
+    /// The size of the window, or of the viewport being built, if any.
+    pub fn window_size(&self) -> Vec2 {
+        match self.ui.build_viewport_idx {
+            Some(viewport_idx) => self.ui.viewports[viewport_idx].size,
+            None => self.ui.window_size,
+        }
+    }
+
+    pub fn cursor_position(&self) -> Vec2 {
+        self.ui.cursor_position
+    }
+
+    /// The frame time passed to [`Ui::begin_frame_with_delta_time`] or
+    /// [`Ui::set_delta_time`], if any. Widgets that animate towards a target
+    /// snap to it without frame time.
+    pub fn delta_time(&self) -> Option<f32> {
+        self.ui.delta_time
+    }
+
+    /// Like [`Frame::delta_time`], but assumes 60 frames per second if the
+    /// platform doesn't provide frame time, for animations that should keep
+    /// running regardless, such as spinners.
+    pub fn delta_time_or_fallback(&self) -> f32 {
+        self.ui.delta_time.unwrap_or(FALLBACK_DELTA_TIME)
+    }
+
+    /// Seconds since the UI was created, accumulated from
+    /// [`Frame::delta_time_or_fallback`] of each frame, including this one.
+    pub fn time(&self) -> f32 {
+        self.ui.time
+    }
+
+    /// Like [`Frame::animate_with_easing`], with [`Easing::EaseOut`].
+    pub fn animate(&mut self, id: u32, target: f32, animation_speed: f32) -> f32 {
+        self.animate_with_easing(id, target, animation_speed, Easing::EaseOut)
+    }
+
+    /// Animates a value towards `target` and returns its current value. The
+    /// value is stored by `id`, which is scoped to the current control and id
+    /// namespace like control ids, and forgotten if not animated for a frame.
+    ///
+    /// When the target changes, the value starts moving from where it was,
+    /// and reaches the target in `1.0 / animation_speed` seconds of the frame
+    /// time passed to [`Ui::begin_frame_with_delta_time`] or
+    /// [`Ui::set_delta_time`]. The first value is the target itself. If the
+    /// speed is zero, or there is no frame time, the value snaps to the
+    /// target.
+    pub fn animate_with_easing(
+        &mut self,
+        id: u32,
+        target: f32,
+        animation_speed: f32,
+        easing: Easing,
+    ) -> f32 {
+        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+        let key = mix_persistent_id(
+            self.ui.tree[build_parent_idx].persistent_id,
+            self.current_id(id),
+        );
+
+        let current_frame = self.ui.current_frame;
+        let delta_time = match self.ui.delta_time {
+            Some(delta_time) if animation_speed > 0.0 => delta_time,
+            _ => 0.0,
+        };
+
+        let animated_value = self.ui.animated_values.entry(key).or_insert(AnimatedValue {
+            from: target,
+            target,
+            t: 1.0,
+            value: target,
+            last_frame: current_frame,
+        });
+
+        if animated_value.target != target {
+            animated_value.from = animated_value.value;
+            animated_value.target = target;
+            animated_value.t = 0.0;
+        }
+
+        // Querying the value again in the same frame doesn't advance it.
+        if animated_value.last_frame != current_frame {
+            animated_value.last_frame = current_frame;
+            animated_value.t = f32::min(animated_value.t + animation_speed * delta_time, 1.0);
+        }
+
+        if delta_time == 0.0 {
+            animated_value.t = 1.0;
+        }
+
+        let AnimatedValue { from, t, .. } = *animated_value;
+        animated_value.value = from + (target - from) * easing.apply(t);
+        animated_value.value
+    }
+
+    pub fn last_ctrl_is_hovered(&self) -> bool {
+        self.ui.last_ctrl_idx == self.ui.hovered_capturing_ctrl_idx && !self.last_ctrl_is_disabled()
+    }
+
+    pub fn last_ctrl_is_active(&self) -> bool {
+        self.ui.last_ctrl_idx == self.ui.active_ctrl_idx && !self.last_ctrl_is_disabled()
+    }
+
+    fn last_ctrl_is_disabled(&self) -> bool {
+        self.ui
+            .last_ctrl_idx
+            .map_or(false, |ctrl_idx| self.ui.tree[ctrl_idx].disabled)
+    }
+
+    /// For how long, in seconds, the last control has been hovered, or
+    /// [`None`], if it is not hovered. The duration is measured with the frame
+    /// time passed to [`Ui::begin_frame_with_delta_time`], and stays zero
+    /// without it.
+    pub fn last_ctrl_hover_duration(&self) -> Option<f32> {
+        if self.ui.last_ctrl_idx.is_some() && self.last_ctrl_is_hovered() {
+            Some(self.ui.hovered_capturing_ctrl_duration)
+        } else {
+            None
+        }
+    }
+
+    pub fn last_ctrl_is_new(&self) -> bool {
+        if let Some(last_ctrl_idx) = self.ui.last_ctrl_idx {
+            self.ui.tree[last_ctrl_idx].first_frame == self.ui.current_frame
+        } else {
+            false
+        }
+    }
+
+    /// Whether the last control was built in the current parent control, i.e.
+    /// it is the sibling right before the next control to be pushed. This is
+    /// not the case right after pushing a control, before any of its children
+    /// were built.
+    pub fn last_ctrl_is_sibling(&self) -> bool {
+        self.ui.last_ctrl_idx.is_some() && self.ui.last_ctrl_idx == self.ui.build_sibling_idx
+    }
+
+    /// Whether the current parent control is hovered, i.e. the cursor is over
+    /// the parent, but not over any of its hover-capturing children.
+    pub fn ctrl_is_hovered(&self) -> bool {
+        self.ui.build_parent_idx == self.ui.hovered_capturing_ctrl_idx && !self.ctrl_is_disabled()
+    }
+
+    pub fn ctrl_is_active(&self) -> bool {
+        self.ui.build_parent_idx == self.ui.active_ctrl_idx && !self.ctrl_is_disabled()
+    }
+
+    fn ctrl_is_disabled(&self) -> bool {
+        self.ui
+            .build_parent_idx
+            .map_or(false, |ctrl_idx| self.ui.tree[ctrl_idx].disabled)
+    }
+
+    /// Input events received since last frame, in the order they were
+    /// received.
+    pub fn input_events(&self) -> &[InputEvent] {
+        &self.ui.input_events
+    }
+
+    /// All inputs pressed since last frame. Prefer iterating
+    /// [`Frame::input_events`], which preserves ordering.
+    pub fn inputs_pressed(&self) -> Inputs {
+        self.ui.inputs_pressed()
+    }
+
+    /// Like [`Frame::inputs_pressed`], but also includes presses repeated
+    /// for held keys. See [`Ui::set_key_repeat`].
+    pub fn inputs_pressed_or_repeated(&self) -> Inputs {
+        self.ui.inputs_pressed_or_repeated()
+    }
+
+    /// All inputs released since last frame. Prefer iterating
+    /// [`Frame::input_events`], which preserves ordering.
+    pub fn inputs_released(&self) -> Inputs {
+        self.ui.inputs_released()
+    }
+
+    /// The payload being dragged, if a drag started by [`Ctrl::drag_source`]
+    /// is in progress, e.g. to draw a preview at the cursor, or to highlight
+    /// the drop targets. Still returned in the frame the payload is dropped.
+    pub fn dragging_payload(&self) -> Option<u64> {
+        self.ui
+            .drag
+            .filter(|drag| drag.dragging)
+            .map(|drag| drag.payload_id)
+    }
+
+    /// Where the rect of the drag source would be, if it followed the cursor
+    /// since the drag started, e.g. to draw a ghost of the dragged control
+    /// (see [`crate::drag_ghost`]). Only set while [`Frame::dragging_payload`]
+    /// is.
+    /// Queues a transient notification, e.g. "Saved!", shown in the top
+    /// right corner of the window for [`Theme::toast_duration`] seconds, and
+    /// then fading out. Toasts don't capture hover and are shown on top of the
+    /// overlay. They age with frame time, see [`Ui::set_delta_time`].
+    pub fn toast(&mut self, text: &str, kind: ToastKind) {
+        let text = VecString::from_str_in(text, self.ui.allocator.clone());
+        self.ui.toasts.push(Toast {
+            text,
+            kind,
+            age: 0.0,
+        });
+    }
+
+    pub fn drag_ghost_rect(&self) -> Option<Rect> {
+        let drag = self.ui.drag.filter(|drag| drag.dragging)?;
+        Some(drag.source_rect + (self.ui.cursor_position - drag.start_cursor_position))
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.ui.modifiers
+    }
+
+    /// Returns whether the shortcut was pressed since last frame, using the
+    /// modifiers that were held at the time of the keypress, e.g. for app-wide
+    /// shortcuts like Ctrl+S. Each keypress is claimed by the first shortcut
+    /// that asks for it, so asking again returns false until it is pressed
+    /// again.
+    ///
+    /// While a control wants text input (see [`Ui::want_text_input`]), it gets
+    /// typing and editing shortcuts like Ctrl+C first, regardless of whether
+    /// it is built before or after this is called, and this returns false for
+    /// them.
+    pub fn shortcut_pressed(&mut self, shortcut: Shortcut) -> bool {
+        // NB: Whether a control wants text input is only known from last
+        // frame, so a text input focused this frame doesn't take precedence
+        // yet.
+        if self.ui.want_text_input
+            && (shortcut.modifiers - Modifiers::SHIFT == Modifiers::NONE
+                || TEXT_EDITING_SHORTCUTS.contains(&shortcut))
+        {
+            return false;
+        }
+
+        self.ui.claim_shortcut(shortcut)
+    }
+
+    pub fn received_characters(&self) -> &str {
+        &self.ui.received_characters
+    }
+
+    /// Returns the control currently being built, i.e. the one last pushed
+    /// with [`Frame::push_ctrl`] and not yet popped.
+    pub fn ctrl(&mut self) -> Ctrl<'_, A> {
+        Ctrl {
+            idx: self.ui.build_parent_idx.unwrap(),
+            ui: self.ui,
+            temp_allocator: self.temp_allocator,
+        }
+    }
+
+    pub fn ctrl_state(&self) -> &CtrlState {
+        self.ui.ctrl_state(self.ui.build_parent_idx.unwrap())
+    }
+
+    pub fn ctrl_state_mut(&mut self) -> &mut CtrlState {
+        self.ui.ctrl_state_mut(self.ui.build_parent_idx.unwrap())
+    }
+
+    pub fn ctrl_absolute_position(&self) -> Vec2 {
+        self.ui.tree[self.ui.build_parent_idx.unwrap()].layout_cache_absolute_position
+    }
+
+    pub fn ctrl_inner_size(&self) -> Vec2 {
+        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+        let parent = &self.ui.tree[build_parent_idx];
+        let rect = parent.rect.inset(parent.border + parent.padding);
+
+        rect.size()
+    }
+
+    /// Returns the range of rows visible in the current control, if it lays
+    /// out rows of the height (including margins) from the top of its
+    /// content, e.g. so that a list with thousands of rows only builds the
+    /// visible ones. The rows before and after the range should be replaced
+    /// with spacer controls of their total height, so that the scroll extent
+    /// stays the same.
+    ///
+    /// Uses the scroll offset from last frame, and the control's size from
+    /// this frame.
+    pub fn visible_range(&self, row_height: f32, row_count: usize) -> Range<usize> {
+        if row_height <= 0.0 {
+            return 0..row_count;
+        }
+
+        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+        let parent = &self.ui.tree[build_parent_idx];
+        let inner_height = parent.rect.inset(parent.border + parent.padding).height;
+        let scroll_offset_y = parent.scroll_offset.y;
+
+        // NB: Float to int casts saturate, so negative values become zero.
+        let start = libm::floorf(scroll_offset_y / row_height) as usize;
+        let end = libm::ceilf((scroll_offset_y + inner_height) / row_height) as usize;
+
+        usize::min(start, row_count)..usize::min(end, row_count)
+    }
+
+    pub fn ctrl_count(&self) -> usize {
+        self.ui.ctrl_count()
+    }
+
+    /// Looks up layout of the control with the persistent id, as computed at
+    /// the end of last frame. Returns [`None`], if there was no such control
+    /// last frame.
+    ///
+    /// If the control was already pushed this frame, its rect reflects any
+    /// changes made to it this frame.
+    pub fn ctrl_layout(&self, persistent_id: u64) -> Option<CtrlLayout> {
+        // TODO(yan): @Speed This is a linear search over the whole tree.
+        let ctrl = self
+            .ui
+            .tree
+            .iter()
+            .find(|ctrl| ctrl.persistent_id == persistent_id)?;
+
+        Some(CtrlLayout {
+            rect: ctrl.rect,
+            absolute_position: ctrl.layout_cache_absolute_position,
+            content_size: ctrl.layout_cache_content_size,
+        })
+    }
+
+    /// Looks up the state of a control by the path of ids leading to it from
+    /// the root, e.g. to inspect widget internals while debugging. The ids are
+    /// the ones the controls were pushed with, mixed with the id namespace,
+    /// i.e. what [`Frame::current_id`] returned when they were pushed. Controls
+    /// in the overlay are looked up under the overlay root, if they are not
+    /// found in the base layer.
+    ///
+    /// Returns [`None`], if there is no such control. Controls not yet pushed
+    /// this frame have their state from last frame.
+    pub fn ctrl_state_by_id(&self, path: &[u32]) -> Option<&CtrlState> {
+        if path.is_empty() {
+            return None;
+        }
+
+        let tree = &self.ui.tree;
+        let ctrl_idx = find_ctrl_by_id_path(tree, ROOT_IDX, path)
+            .or_else(|| find_ctrl_by_id_path(tree, OVERLAY_ROOT_IDX, path))?;
+
+        Some(self.ui.ctrl_state(ctrl_idx))
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.ui.allocator
+    }
+
+    /// The allocator for memory that doesn't outlive the current widget call.
+    /// See [`Ui::set_temp_allocator`].
+    pub fn temp_allocator(&self) -> &'a (dyn Allocator + 'a) {
+        self.temp_allocator
+    }
+}
+
+/// Layout of a control, as computed at the end of a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CtrlLayout {
+    pub rect: Rect,
+    pub absolute_position: Vec2,
+    pub content_size: Vec2,
+}
+
+pub struct Ctrl<'a, A: Allocator + Clone> {
+    idx: usize,
+    ui: &'a mut UiCore<A>,
+    temp_allocator: &'a (dyn Allocator + 'a),
+}
+
+impl<'a, A: Allocator + Clone> Ctrl<'a, A> {
+    pub fn set_active(&mut self, active: bool) {
+        if active {
+            if self.ui.tree[self.idx].disabled {
+                return;
+            }
+
+            self.ui.active_ctrl_idx = Some(self.idx);
+
+            let mut ctrl = &mut self.ui.tree[self.idx];
+            ctrl.last_frame_in_active_path = self.ui.current_frame;
+
+            while let Some(ctrl_idx) = ctrl.parent_idx.map(CtrlIdx::get) {
+                ctrl = &mut self.ui.tree[ctrl_idx];
+                ctrl.last_frame_in_active_path = self.ui.current_frame;
+            }
+        } else if let Some(active_ctrl_idx) = self.ui.active_ctrl_idx {
+            if active_ctrl_idx == self.idx {
+                // If this was the active control, it relinquishes the active
+                // status the the first control up the tree that wants to
+                // capture it. When that happens, the capturing control and all
+                // its parents get their last_frame_in_active_path updated.
+                //
+                // Top-level overlay controls continue the search from their
+                // owner in the base layer, so that e.g. clicking a button in a
+                // dropdown's overlay hands the active status back to the
+                // dropdown once the button is released.
+
+                let current_ctrl = &self.ui.tree[self.idx];
+
+                if let Some(parent_idx) = current_ctrl.overlay_owner_idx.or(current_ctrl.parent_idx)
+                {
+                    let parent_idx = parent_idx.get();
+                    let mut ctrl_idx = parent_idx;
+                    let mut ctrl = &mut self.ui.tree[parent_idx];
+
+                    while !ctrl.flags.intersects(CtrlFlags::CAPTURE_ACTIVE) {
+                        if let Some(next_idx) = ctrl.overlay_owner_idx.or(ctrl.parent_idx) {
+                            ctrl_idx = next_idx.get();
+                            ctrl = &mut self.ui.tree[ctrl_idx];
+                        } else {
+                            break;
+                        }
+                    }
+
+                    if ctrl.flags.intersects(CtrlFlags::CAPTURE_ACTIVE) {
+                        self.ui.active_ctrl_idx = Some(ctrl_idx);
+
+                        ctrl.last_frame_in_active_path = self.ui.current_frame;
+
+                        while let Some(ctrl_idx) = ctrl.parent_idx.map(CtrlIdx::get) {
+                            ctrl = &mut self.ui.tree[ctrl_idx];
+                            ctrl.last_frame_in_active_path = self.ui.current_frame
+                        }
+                    } else {
+                        self.ui.active_ctrl_idx = None;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn flags(&self) -> CtrlFlags {
+        self.ui.tree[self.idx].flags
+    }
+
+    pub fn set_flags(&mut self, flags: CtrlFlags) {
+        self.ui.tree[self.idx].flags = flags;
+    }
+
+    /// Within a parent with [`Layout::Free`], controls with a higher z index
+    /// are rendered on top of their siblings and get hovered first, e.g. to
+    /// keep a HUD above windows, or an image behind them. Among siblings with
+    /// the same z index, the most recently active one is on top, so activating
+    /// a control with [`Ctrl::set_active`] can't raise it above siblings with
+    /// a higher z index. Defaults to zero.
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.ui.tree[self.idx].z_index = z_index;
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.ui.tree[self.idx].layout = layout;
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.ui.tree[self.idx].rect = rect;
+    }
+
+    /// Makes the layout keep the control's width to height ratio, e.g. for
+    /// images or game viewports. The height follows from the width, but is
+    /// clamped to the height available in the parent (its inner height, less
+    /// the control's vertical position), reducing the width to match. See
+    /// [`Ctrl::set_aspect_ratio_with_fit`] for other ways to fit.
+    ///
+    /// The aspect ratio is applied last, after the rect is filled or resized
+    /// to fit with [`CtrlFlags`], and has to be set on every frame.
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        self.set_aspect_ratio_with_fit(aspect_ratio, AspectFit::Contain);
+    }
+
+    pub fn set_aspect_ratio_with_fit(&mut self, aspect_ratio: f32, fit: AspectFit) {
+        assert!(aspect_ratio > 0.0);
+        self.ui.tree[self.idx].aspect_ratio = Some((aspect_ratio, fit));
+    }
+
+    pub fn set_padding(&mut self, padding: f32) {
+        self.ui.tree[self.idx].padding = padding;
+    }
+
+    pub fn set_border(&mut self, border: f32) {
+        self.ui.tree[self.idx].border = border;
+    }
+
+    pub fn set_margin(&mut self, margin: f32) {
+        self.ui.tree[self.idx].margin = margin;
+    }
+
+    pub fn set_scroll_offset_x(&mut self, scroll_offset: f32) {
+        let ctrl = &mut self.ui.tree[self.idx];
+        ctrl.scroll_offset.x = scroll_offset;
+        ctrl.scroll_offset_target.x = scroll_offset;
+    }
+
+    pub fn set_scroll_offset_y(&mut self, scroll_offset: f32) {
+        let ctrl = &mut self.ui.tree[self.idx];
+        ctrl.scroll_offset.y = scroll_offset;
+        ctrl.scroll_offset_target.y = scroll_offset;
+    }
+
+    /// Scrolls this control by the smallest amount that makes the rect
+    /// visible. The rect is in the coordinates of the control's contents,
+    /// e.g. a rect at 0,0 is at the top left corner of the contents, regardless
+    /// of the current scroll offset. If the rect doesn't fit, its top left
+    /// corner is preferred. Animated, if smooth scrolling is enabled.
+    pub fn scroll_to_rect(&mut self, rect: Rect) {
+        let smooth = self.ui.scroll_smoothing_factor().is_some();
+        scroll_to_rect(&mut self.ui.tree[self.idx], rect, smooth);
+    }
+
+    /// Scrolls this control's ancestors so that it becomes visible. Uses the
+    /// layout from last frame, so controls created this frame can only be
+    /// scrolled into view starting next frame.
+    pub fn scroll_into_view(&mut self) {
+        let smooth = self.ui.scroll_smoothing_factor().is_some();
+
+        let mut ctrl_idx = self.idx;
+        while let Some(parent_idx) = self.ui.tree[ctrl_idx].parent_idx.map(CtrlIdx::get) {
+            let ctrl = &self.ui.tree[ctrl_idx];
+            let parent = &self.ui.tree[parent_idx];
+
+            let parent_content_position =
+                parent.layout_cache_absolute_position + parent.border + parent.padding
+                    - parent.layout_cache_scroll_offset;
+            let position = ctrl.layout_cache_absolute_position - ctrl.margin;
+            let rect = Rect::new(
+                position.x - parent_content_position.x,
+                position.y - parent_content_position.y,
+                ctrl.rect.width + 2.0 * ctrl.margin,
+                ctrl.rect.height + 2.0 * ctrl.margin,
+            );
+
+            if parent.flags.intersects(CtrlFlags::CAPTURE_SCROLL) {
+                scroll_to_rect(&mut self.ui.tree[parent_idx], rect, smooth);
+            }
+
+            ctrl_idx = parent_idx;
+        }
+    }
+
+    /// Makes this control modal for as long as it is built with it. Only the
+    /// control and its descendants can be hovered or become active, starting
+    /// next frame, e.g. for a confirm dialog covering the window. If more
+    /// controls are made modal in a frame, the last one wins. See
+    /// [`Ui::modal_active`].
+    pub fn set_modal(&mut self, modal: bool) {
+        if modal {
+            self.ui.build_modal_ctrl_idx = Some(self.idx);
+        } else if self.ui.build_modal_ctrl_idx == Some(self.idx) {
+            self.ui.build_modal_ctrl_idx = None;
+        }
+    }
+
+    /// Asks whether the next frame's mouse press lands outside of this
+    /// control, its descendants, and the overlays they own, e.g. for closing a
+    /// popup. The answer is read with [`Ctrl::clicked_outside`] next frame.
+    /// Has to be asked each frame.
+    pub fn close_on_outside_click(&mut self) {
+        self.ui.tree[self.idx].close_on_outside_click = true;
+    }
+
+    /// Whether a mouse button was pressed outside of this control this frame,
+    /// if it asked with [`Ctrl::close_on_outside_click`] last frame.
+    pub fn clicked_outside(&self) -> bool {
+        self.ui.tree[self.idx].clicked_outside
+    }
+
+    pub fn set_draw_self(&mut self, draw_self: bool) {
+        self.ui.tree[self.idx].draw_self = draw_self;
+    }
+
+    pub fn set_draw_self_border_color(&mut self, border_color: u32) {
+        self.ui.tree[self.idx].draw_self_border_color = border_color;
+    }
+
+    pub fn set_draw_self_background_color(&mut self, background_color: u32) {
+        self.ui.tree[self.idx].draw_self_background_color = background_color;
+    }
+
+    pub fn set_scrollbar_width(&mut self, scrollbar_width: f32) {
+        self.ui.tree[self.idx].scrollbar_width = scrollbar_width;
+    }
+
+    pub fn set_scrollbar_track_color(&mut self, scrollbar_track_color: u32) {
+        self.ui.tree[self.idx].scrollbar_track_color = scrollbar_track_color;
+    }
+
+    pub fn set_scrollbar_thumb_color(&mut self, scrollbar_thumb_color: u32) {
+        self.ui.tree[self.idx].scrollbar_thumb_color = scrollbar_thumb_color;
+    }
+
+    pub fn is_new(&self) -> bool {
+        if let Some(build_parent_idx) = self.ui.build_parent_idx {
+            self.ui.tree[build_parent_idx].first_frame == self.ui.current_frame
+        } else {
+            false
+        }
+    }
+
+    pub fn is_hovered(&self) -> bool {
+        self.ui.build_parent_idx == self.ui.hovered_capturing_ctrl_idx
+            && !self.ui.tree[self.idx].disabled
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.ui.active_ctrl_idx == Some(self.idx) && !self.ui.tree[self.idx].disabled
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.ui.tree[self.idx].disabled
+    }
+
+    /// How far the control is in its transition to being hovered, from 0 to
+    /// 1. Moves towards 1 while the control is hovered, and back towards 0
+    /// otherwise, by `animation_speed` per second of the frame time passed to
+    /// [`Ui::begin_frame_with_delta_time`]. If the speed is zero, or there is
+    /// no frame time, this is exactly 1 when hovered and 0 otherwise.
+    pub fn hover_t(&mut self, animation_speed: f32) -> f32 {
+        let hovered = self.is_hovered();
+        self.advance_transition(hovered, animation_speed, |animation| &mut animation.hover)
+    }
+
+    /// Like [`Ctrl::hover_t`], but for being active.
+    pub fn active_t(&mut self, animation_speed: f32) -> f32 {
+        let active = self.is_active();
+        self.advance_transition(active, animation_speed, |animation| &mut animation.active)
+    }
+
+    fn advance_transition(
+        &mut self,
+        on: bool,
+        animation_speed: f32,
+        transition: fn(&mut CtrlAnimation) -> &mut Transition,
+    ) -> f32 {
+        let target = if on { 1.0 } else { 0.0 };
+        let delta_time = match self.ui.delta_time {
+            Some(delta_time) if animation_speed > 0.0 => delta_time,
+            _ => return target,
+        };
+
+        let persistent_id = self.ui.tree[self.idx].persistent_id;
+        let animation = self.ui.ctrl_animations.entry(persistent_id).or_default();
+        let transition = transition(animation);
+
+        if transition.last_frame != self.ui.current_frame {
+            transition.last_frame = self.ui.current_frame;
+
+            let step = animation_speed * delta_time;
+            transition.t = if target > transition.t {
+                f32::min(transition.t + step, target)
+            } else {
+                f32::max(transition.t - step, target)
+            };
+        }
+
+        transition.t
+    }
+
+    /// Controls that never asked for mutable state with [`Ctrl::state_mut`]
+    /// read as zeroed.
+    pub fn state(&self) -> &CtrlState {
+        self.ui.ctrl_state(self.idx)
+    }
+
+    /// State memory is allocated on first call, zeroed, and kept for as long as
+    /// the control lives.
+    pub fn state_mut(&mut self) -> &mut CtrlState {
+        self.ui.ctrl_state_mut(self.idx)
+    }
+
+    /// Opts the control into [`Ui::save_persistent_state`] for this frame, e.g.
+    /// for windows, so that their positions can be restored on the next run.
+    /// Has to be called on every frame the control is built.
+    pub fn mark_persistent(&mut self) {
+        self.ui.tree[self.idx].persistent = true;
+    }
+
+    /// Returns an id derived from the ids of this control and all its
+    /// ancestors. Unlike the control id, it is unique across the whole UI, and
+    /// stays the same across frames for as long as the control lives. Can be
+    /// used to key application data associated with the control, or to look up
+    /// the control's layout with [`Frame::ctrl_layout`].
+    pub fn persistent_id(&self) -> u64 {
+        self.ui.tree[self.idx].persistent_id
+    }
+
+    pub fn absolute_position(&self) -> Vec2 {
+        self.ui.tree[self.idx].layout_cache_absolute_position
+    }
+
+    /// The control's rect in window coordinates, as positioned by last frame's
+    /// layout. The size reflects any changes made to the rect this frame. New
+    /// controls haven't been laid out yet and are positioned at zero.
+    pub fn absolute_rect(&self) -> Rect {
+        let ctrl = &self.ui.tree[self.idx];
+        Rect::new(
+            ctrl.layout_cache_absolute_position.x,
+            ctrl.layout_cache_absolute_position.y,
+            ctrl.rect.width,
+            ctrl.rect.height,
+        )
+    }
+
+    pub fn inner_size(&self) -> Vec2 {
+        let ctrl = &self.ui.tree[self.idx];
+        let rect = ctrl.rect.inset(ctrl.border + ctrl.padding);
+
+        rect.size()
+    }
+
+    pub fn scroll_offset_x(&self) -> f32 {
+        self.ui.tree[self.idx].scroll_offset.x
+    }
+
+    pub fn scroll_offset_y(&self) -> f32 {
+        self.ui.tree[self.idx].scroll_offset.y
+    }
+
+    /// Moves a glyph rect in control-local coordinates so that it starts on a
+    /// whole physical pixel, if text pixel snapping is enabled. Otherwise
+    /// returns the rect unchanged.
+    pub fn snap_text_rect(&self, rect: Rect) -> Rect {
+        if self.ui.text_pixel_snap {
+            let ctrl = &self.ui.tree[self.idx];
+            let origin = ctrl.layout_cache_absolute_position - ctrl.scroll_offset;
+            snap_rect_to_physical_pixels(rect, origin, self.ui.window_scale_factor)
+        } else {
+            rect
+        }
+    }
+
+    /// Reads text from the clipboard. Returns an empty string, if no
+    /// clipboard getter is set.
+    pub fn get_clipboard_text(&mut self) -> &str {
+        let buffer = &mut self.ui.clipboard_buffer;
+        buffer.clear();
+
+        if let Some(getter) = self.ui.clipboard_getter {
+            getter(buffer);
+        }
+
+        buffer
+    }
+
+    /// Like [`Frame::shortcut_pressed`], but for widgets handling shortcuts
+    /// while they have focus, so controls wanting text input don't take
+    /// precedence. Only ask while the shortcut would do something, so that
+    /// it isn't claimed for nothing.
+    pub fn shortcut_pressed(&mut self, shortcut: Shortcut) -> bool {
+        self.ui.claim_shortcut(shortcut)
+    }
+
+    /// Writes text to the clipboard. Does nothing, if no clipboard setter is
+    /// set.
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        if let Some(setter) = self.ui.clipboard_setter {
+            setter(text);
+        }
+    }
+
+    /// Requests the keyboard to be captured. Only honored if this control is
+    /// active at the end of the frame.
+    pub fn request_want_capture_keyboard(&mut self) {
+        self.ui.build_want_capture_keyboard_ctrl_idx = Some(self.idx);
+    }
+
+    /// Requests text input (IME, on-screen keyboard) from the platform. Only
+    /// honored if this control is active at the end of the frame.
+    pub fn request_want_text_input(&mut self) {
+        self.ui.build_want_text_input_ctrl_idx = Some(self.idx);
+    }
+
+    pub fn request_want_capture_mouse(&mut self) {
+        self.ui.build_want_capture_mouse = true;
+    }
+
+    /// Makes the control a source of drag and drop, carrying the given
+    /// payload. Pressing the left mouse button over the control and moving the
+    /// cursor a few pixels starts the drag, which lasts until the button is
+    /// released. Returns whether the control's payload is being dragged.
+    ///
+    /// Only hover-capturing controls can be pressed.
+    pub fn drag_source(&mut self, payload_id: u64) -> bool {
+        let persistent_id = self.ui.tree[self.idx].persistent_id;
+
+        if self.ui.drag.is_none()
+            && self.is_hovered()
+            && self.ui.inputs_pressed().contains(Inputs::MB_LEFT)
+        {
+            self.ui.drag = Some(Drag {
+                payload_id,
+                source_persistent_id: persistent_id,
+                source_rect: self.absolute_rect(),
+                start_cursor_position: self.ui.cursor_position,
+                dragging: false,
+            });
+        }
+
+        let dragging = match &mut self.ui.drag {
+            Some(drag) if drag.source_persistent_id == persistent_id => {
+                drag.payload_id = payload_id;
+                drag.dragging
+            }
+            _ => false,
+        };
+
+        if dragging {
+            self.set_cursor_icon(CursorIcon::Move);
+        }
+
+        dragging
+    }
+
+    /// Returns the dragged payload, if it was dropped on this control this
+    /// frame, i.e. the left mouse button was released while the control was
+    /// hovered. Whether a drag is in progress at all can be queried with
+    /// [`Frame::dragging_payload`].
+    ///
+    /// Only hover-capturing controls can be dropped on.
+    pub fn drop_target(&self) -> Option<u64> {
+        let drag = self.ui.drag?;
+        if drag.dragging && self.is_hovered() && self.ui.inputs_released().contains(Inputs::MB_LEFT)
+        {
+            Some(drag.payload_id)
+        } else {
+            None
+        }
+    }
+
+    /// Requests a cursor icon for this frame, usually while the control is
+    /// hovered or dragged. If multiple controls request one, the icon for
+    /// dragging beats the icon for resizing, which beats the text cursor,
+    /// which beats the hand. Without requests, the icon is
+    /// [`CursorIcon::Default`].
+    pub fn set_cursor_icon(&mut self, cursor_icon: CursorIcon) {
+        if cursor_icon.priority() > self.ui.cursor_icon.priority() {
+            self.ui.cursor_icon = cursor_icon;
+        }
+    }
+
+    /// Clips everything drawn by this control from now on to the given rect,
+    /// in the same coordinates as [`Ctrl::draw_rect`], until the matching
+    /// [`Ctrl::pop_clip_rect`]. Clip rects nest, and are intersected with the
+    /// control's own clipping. They must be popped before pushing or popping
+    /// another control.
+    pub fn push_clip_rect(&mut self, clip_rect: Rect) {
+        let clip_rect = match self.ui.clip_rect_stack.last() {
+            Some(parent_clip_rect) => parent_clip_rect.clamp_rect(clip_rect),
+            None => clip_rect,
+        };
+
+        self.ui.clip_rect_stack.push(clip_rect);
+    }
+
+    pub fn pop_clip_rect(&mut self) {
+        assert!(
+            self.ui.clip_rect_stack.pop().is_some(),
+            "Attempt to pop_clip_rect without a matching push_clip_rect.",
+        );
+    }
+
+    pub fn draw_rect(&mut self, rect: Rect, texture_rect: Rect, color: u32, texture_id: u64) {
+        self.push_draw_primitive(DrawPrimitive::Rect {
+            rect,
+            texture_rect,
+            texture_id,
+            color,
+            flip_y: false,
+            sampling: Sampling::LINEAR,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    /// Draws the whole texture into the rect, untinted. For textures provided
+    /// by the user, as opposed to the font atlas.
+    pub fn draw_image(&mut self, rect: Rect, texture_id: u64) {
+        self.draw_image_with_options(rect, texture_id, &ImageOptions::DEFAULT);
+    }
+
+    /// Draws the part of the texture given by the rect in pixels into the
+    /// rect, e.g. a sprite from a sprite sheet of the given size.
+    pub fn draw_image_uv_px(
+        &mut self,
+        rect: Rect,
+        texture_id: u64,
+        texture_size: Vec2,
+        texture_rect_px: Rect,
+    ) {
+        let texture_rect = Rect::new(
+            texture_rect_px.x / texture_size.x,
+            texture_rect_px.y / texture_size.y,
+            texture_rect_px.width / texture_size.x,
+            texture_rect_px.height / texture_size.y,
+        );
+
+        self.draw_image_with_options(rect, texture_id, &ImageOptions {
+            texture_rect,
+            ..ImageOptions::DEFAULT
+        });
+    }
+
+    pub fn draw_image_with_options(&mut self, rect: Rect, texture_id: u64, options: &ImageOptions) {
+        self.push_draw_primitive(DrawPrimitive::Rect {
+            rect,
+            texture_rect: options.texture_rect,
+            texture_id,
+            color: options.tint,
+            flip_y: options.flip_y,
+            sampling: options.sampling,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    /// Draws a texture stretched over the rect without stretching its corners,
+    /// e.g. to skin buttons and panels with bitmaps. Both the rect and the
+    /// texture rect are split into a 3x3 grid. The corners are `border`
+    /// logical pixels in the rect and `texture_border` in texture coordinates,
+    /// the edges stretch in one direction and the center in both.
+    ///
+    /// If the rect is smaller than both its borders, the borders shrink to
+    /// fit, squashing the corners.
+    pub fn draw_rect_nine_slice(
+        &mut self,
+        rect: Rect,
+        texture_rect: Rect,
+        color: u32,
+        texture_id: u64,
+        border: f32,
+        texture_border: Vec2,
+    ) {
+        let border_x = f32::clamp(border, 0.0, 0.5 * rect.width);
+        let border_y = f32::clamp(border, 0.0, 0.5 * rect.height);
+        let texture_border_x = f32::clamp(texture_border.x, 0.0, 0.5 * texture_rect.width);
+        let texture_border_y = f32::clamp(texture_border.y, 0.0, 0.5 * texture_rect.height);
+
+        let xs = [
+            rect.x,
+            rect.x + border_x,
+            rect.x + rect.width - border_x,
+            rect.x + rect.width,
+        ];
+        let ys = [
+            rect.y,
+            rect.y + border_y,
+            rect.y + rect.height - border_y,
+            rect.y + rect.height,
+        ];
+        let us = [
+            texture_rect.x,
+            texture_rect.x + texture_border_x,
+            texture_rect.x + texture_rect.width - texture_border_x,
+            texture_rect.x + texture_rect.width,
+        ];
+        let vs = [
+            texture_rect.y,
+            texture_rect.y + texture_border_y,
+            texture_rect.y + texture_rect.height - texture_border_y,
+            texture_rect.y + texture_rect.height,
+        ];
+
+        for j in 0..3 {
+            for i in 0..3 {
+                let slice_rect = Rect::new(xs[i], ys[j], xs[i + 1] - xs[i], ys[j + 1] - ys[j]);
+                // Slices collapse if the rect is exactly as large as its
+                // borders, or there are no borders.
+                if slice_rect.width == 0.0 || slice_rect.height == 0.0 {
+                    continue;
+                }
+
+                let slice_texture_rect =
+                    Rect::new(us[i], vs[j], us[i + 1] - us[i], vs[j + 1] - vs[j]);
+
+                self.draw_rect(slice_rect, slice_texture_rect, color, texture_id);
+            }
+        }
+    }
+
+    /// Draws a rect with a linear gradient from `color_a` to `color_b`, top to
+    /// bottom if `vertical`, otherwise left to right.
+    pub fn draw_rect_gradient(
+        &mut self,
+        rect: Rect,
+        texture_rect: Rect,
+        color_a: u32,
+        color_b: u32,
+        vertical: bool,
+        texture_id: u64,
+    ) {
+        let colors = if vertical {
+            [color_a, color_a, color_b, color_b]
+        } else {
+            [color_a, color_b, color_b, color_a]
+        };
+
+        self.draw_rect_corner_gradient(rect, texture_rect, colors, texture_id);
+    }
+
+    /// Draws a rect with a color in each corner, in the order top-left,
+    /// top-right, bottom-right, bottom-left. Colors are interpolated across
+    /// the two triangles of the rect, so gradients with more than two distinct
+    /// colors show the diagonal between them.
+    pub fn draw_rect_corner_gradient(
+        &mut self,
+        rect: Rect,
+        texture_rect: Rect,
+        colors: [u32; 4],
+        texture_id: u64,
+    ) {
+        self.push_draw_primitive(DrawPrimitive::GradientRect {
+            rect,
+            texture_rect,
+            texture_id,
+            colors,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    /// Draws a line from `a` to `b`, in the same coordinates as
+    /// [`Ctrl::draw_rect`]. Lines are untextured and sample the texture at its
+    /// origin, same as a rect with a zero texture rect.
+    pub fn draw_line(&mut self, a: Vec2, b: Vec2, thickness: f32, color: u32, texture_id: u64) {
+        self.push_draw_primitive(DrawPrimitive::Line {
+            a,
+            b,
+            thickness,
+            texture_id,
+            color,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    /// Draws a filled circle. Untextured, same as [`Ctrl::draw_line`].
+    pub fn draw_circle(&mut self, center: Vec2, radius: f32, color: u32, texture_id: u64) {
+        self.push_draw_primitive(DrawPrimitive::Circle {
+            center,
+            radius,
+            texture_id,
+            color,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    /// Draws a filled rect with rounded corners. Untextured, same as
+    /// [`Ctrl::draw_line`].
+    pub fn draw_rounded_rect(&mut self, rect: Rect, radius: f32, color: u32, texture_id: u64) {
+        self.push_draw_primitive(DrawPrimitive::RoundedRect {
+            rect,
+            radius,
+            border_thickness: None,
+            texture_id,
+            color,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    /// Draws the border of a rect with rounded corners, inside the rect.
+    /// Untextured, same as [`Ctrl::draw_line`].
+    pub fn draw_rounded_rect_border(
+        &mut self,
+        rect: Rect,
+        radius: f32,
+        thickness: f32,
+        color: u32,
+        texture_id: u64,
+    ) {
+        self.push_draw_primitive(DrawPrimitive::RoundedRect {
+            rect,
+            radius,
+            border_thickness: Some(thickness),
+            texture_id,
+            color,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    /// Asks the renderer to run its own drawing in the rect, e.g. to show a
+    /// live 3D scene, at this point in the draw order. This emits a callback
+    /// command with the user id into the draw list (see
+    /// [`crate::CommandKind::CALLBACK`]). Its scissor rect is the rect,
+    /// clipped like any other primitive, and the callback is skipped entirely
+    /// if nothing is visible.
+    pub fn draw_callback(&mut self, user_id: u64, rect: Rect) {
+        self.push_draw_primitive(DrawPrimitive::Callback {
+            rect,
+            user_id,
+            clip_rect: self.ui.clip_rect_stack.last().copied(),
+        });
+    }
+
+    fn push_draw_primitive(&mut self, draw_primitive: DrawPrimitive) {
+        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+        let next_draw_primitive_idx = self.ui.draw_primitives.len();
+
+        let parent = &mut self.ui.tree[build_parent_idx];
+        assert!(parent.draw_range.end == next_draw_primitive_idx);
+
+        self.ui.draw_primitives.push(draw_primitive);
+
+        parent.draw_range.end += 1;
+    }
+
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        halign: Align,
+        valign: Align,
+        wrap: Wrap,
+        direction: Direction,
+        color: u32,
+    ) {
+        self.draw_text_and_do_dishes(
+            false, None, 0.0, 1.0, text, halign, valign, wrap, direction, color, None,
+        );
+    }
+
+    /// Like [`Ctrl::draw_text`], but scales the font's metrics, e.g. for
+    /// zoomable content. Glyphs are still sampled from the atlas rasterized for
+    /// the font size, so scaled up text is blurry, unless the atlas contains
+    /// signed distance fields. See [`FontAtlas::sdf`].
+    pub fn draw_text_scaled(
+        &mut self,
+        text: &str,
+        halign: Align,
+        valign: Align,
+        wrap: Wrap,
+        direction: Direction,
+        color: u32,
+        scale: f32,
+    ) {
+        self.draw_text_and_do_dishes(
+            false, None, 0.0, scale, text, halign, valign, wrap, direction, color, None,
+        );
+    }
+
+    pub fn draw_text_fitted(
+        &mut self,
+        text: &str,
+        halign: Align,
+        valign: Align,
+        wrap: Wrap,
+        direction: Direction,
+        color: u32,
+        fitting: Rect,
+    ) {
+        self.draw_text_and_do_dishes(
+            true,
+            Some(fitting),
+            0.0,
+            1.0,
+            text,
+            halign,
+            valign,
+            wrap,
+            direction,
+            color,
+            None,
+        );
+    }
+
+    pub fn draw_text_inset_and_extend_content_rect(
+        &mut self,
+        text: &str,
+        halign: Align,
+        valign: Align,
+        wrap: Wrap,
+        direction: Direction,
+        color: u32,
+        inset: f32,
+    ) {
+        self.draw_text_and_do_dishes(
+            true, None, inset, 1.0, text, halign, valign, wrap, direction, color, None,
+        );
+    }
+
+    /// Like [`Ctrl::draw_text`], but draws the characters starting at the
+    /// `highlighted` byte indices with `highlight_color`, e.g. the characters
+    /// matched by [`fuzzy_match_with_positions`]. The indices must be sorted.
+    ///
+    /// [`fuzzy_match_with_positions`]: crate::fuzzy_match_with_positions
+    pub fn draw_text_highlighted(
+        &mut self,
+        text: &str,
+        halign: Align,
+        valign: Align,
+        wrap: Wrap,
+        direction: Direction,
+        color: u32,
+        highlighted: &[usize],
+        highlight_color: u32,
+    ) {
+        self.draw_text_and_do_dishes(
+            false,
+            None,
+            0.0,
+            1.0,
+            text,
+            halign,
+            valign,
+            wrap,
+            direction,
+            color,
+            Some((highlighted, highlight_color)),
+        );
+    }
+
+    fn draw_text_and_do_dishes(
+        &mut self,
+        extend_inline_content_rect: bool,
+        fitting: Option<Rect>,
+        inset: f32,
+        scale: f32,
+        text: &str,
+        halign: Align,
+        valign: Align,
+        wrap: Wrap,
+        direction: Direction,
+        color: u32,
+        highlight: Option<(&[usize], u32)>,
+    ) {
+        assert!(inset >= 0.0);
+        assert!(scale > 0.0);
+
+        // TODO(yan): @Bug @Correctness #Antialiasing? This has layout issues
+        // (characters not being aligned vertically to the baseline) on Roboto,
+        // IBM Plex Mono, and Liberation Mono fonts, but not on Proggy
+        // Clean. Pixel peeping in RenderDoc showed a consistent 1px or
+        // sometimes 2px error in some characters, e.g. for 'r' and 'i' in
+        // Liberation Mono, 'i' is rendered one pixel higher than expected (or
+        // 'r' is rendered one pixel lower). Weirdly, they have the same ymin
+        // (zero) in metrics returned by fontdue, even though they are visibly
+        // offset in the rasterized atlas. Could this be an error in fontdue -
+        // either in metrics, or rasterization? Do we want to remove fontdue and
+        // do our own thing?
+
+        let build_parent_idx = self.ui.build_parent_idx.unwrap();
+        let next_draw_primitive_idx = self.ui.draw_primitives.len();
+        let tab_stop_width = self.ui.tab_stop_width();
+        let text_pixel_snap = self.ui.text_pixel_snap;
+        let window_scale_factor = self.ui.window_scale_factor;
+
+        let parent = &mut self.ui.tree[build_parent_idx];
+
+        assert!(parent.draw_range.end == next_draw_primitive_idx);
+
+        // NB: Layout for this frame hasn't happened yet, so snapping uses last
+        // frame's position. Controls that moved snap correctly one frame late.
+        let snap_origin = parent.layout_cache_absolute_position - parent.scroll_offset;
+
+        // NB: Vertical align only makes sense, if there is any free space to
+        // align in. If we are going to shrink/resize, there is no free space
+        // and it simplifies things for us to align to start and not care later.
+        //
+        // Note that horizontal align still makes sense for shrinking, because
+        // the lines will still be jagged and the width difference between
+        // longest line and current line will provide the alignment space.
+        let valign = if parent.flags.intersects(CtrlFlags::RESIZE_TO_FIT_VERTICAL) {
+            Align::Start
+        } else {
+            valign
+        };
+
+        // NB: We zero X and Y of the default parent rect, because emiting draw
+        // commands insider a control already uses that control's transform. Not
+        // zeroing would apply them twice.
+        let fitting = fitting
+            .unwrap_or_else(|| Rect::new(0.0, 0.0, parent.rect.width, parent.rect.height))
+            .inset(inset);
+        let available_width = fitting.width;
+        let available_height = fitting.height;
+
+        // If we are expected to wrap text, but there's not enough space to
+        // render a missing character, don't attempt anything.
+        if wrap != Wrap::None
+            && scale * self.ui.font_atlas.missing_glyph_info().advance_width > available_width
+        {
+            return;
+        }
+
+        // NB: Lines are split in unscaled font units, and everything measured
+        // in those units is scaled from here on.
+        let lines = &mut self.ui.scratch_text_lines;
+        lines.clear();
+        split_text_lines(
+            &self.ui.font_atlas,
+            text,
+            available_width / scale,
+            wrap,
+            tab_stop_width,
+            lines,
+        );
+
+        //
+        // Emit rects based on generated line data.
+        //
+        let line_metrics = self.ui.font_atlas.font_horizontal_line_metrics();
+        let line_metrics = LineMetrics {
+            ascent: scale * line_metrics.ascent,
+            descent: scale * line_metrics.descent,
+            line_gap: scale * line_metrics.line_gap,
+            new_line_size: scale * line_metrics.new_line_size,
+        };
+
+        let mut position_y = if lines.len() as f32 * line_metrics.new_line_size < available_height {
+            match valign {
+                Align::Start => line_metrics.line_gap + fitting.y,
+                Align::Center => {
+                    let line_gap = line_metrics.line_gap;
+                    let new_line_size = line_metrics.new_line_size;
+                    let text_block_size = new_line_size * lines.len() as f32 - line_gap;
+
+                    line_gap + fitting.y + (available_height - text_block_size) / 2.0
+                }
+                Align::End => {
+                    let line_gap = line_metrics.line_gap;
+                    let new_line_size = line_metrics.new_line_size;
+                    let text_block_size = new_line_size * lines.len() as f32 - line_gap;
+
+                    line_gap + fitting.y + available_height - text_block_size
+                }
+            }
+        } else {
+            line_metrics.line_gap
+        };
+
+        for line in lines.iter() {
+            let line_slice = &text[line.range.clone()];
+            let line_width = scale * line.width;
+
+            // For right-to-left text, alignment is mirrored and glyphs are
+            // positioned leftwards from the right edge of the line.
+            let line_x = match (direction, halign) {
+                (Direction::Ltr, Align::Start) | (Direction::Rtl, Align::End) => fitting.x,
+                (_, Align::Center) => fitting.x + (available_width - line_width) / 2.0,
+                (Direction::Ltr, Align::End) | (Direction::Rtl, Align::Start) => {
+                    fitting.x + available_width - line_width
+                }
+            };
+
+            // Tab stops are measured from the start of the line, so we track
+            // the offset from there separately from the glyph position.
+            let mut line_offset_x = 0.0;
+
+            for (i, c) in line_slice.char_indices() {
+                let glyph_info = self.ui.font_atlas.glyph_info(c);
+                let advance_width = scale
+                    * char_advance_width(
+                        &self.ui.font_atlas,
+                        c,
+                        line_offset_x / scale,
+                        tab_stop_width,
+                    );
+
+                let position_x = match direction {
+                    Direction::Ltr => line_x + line_offset_x,
+                    Direction::Rtl => line_x + line_width - line_offset_x - advance_width,
+                };
+                line_offset_x += advance_width;
+
+                // Tabs are just space, and fonts either don't have a glyph for
+                // them, or have one that shouldn't be drawn.
+                if c == '\t' {
+                    continue;
+                }
+
+                let position = Vec2::new(position_x, position_y);
+                let glyph_rect = Rect::new(
+                    scale * glyph_info.rect.x,
+                    scale * glyph_info.rect.y,
+                    scale * glyph_info.rect.width,
+                    scale * glyph_info.rect.height,
+                );
+                let rect = glyph_rect + position + Vec2::y(line_metrics.ascent);
+                let rect = if text_pixel_snap {
+                    snap_rect_to_physical_pixels(rect, snap_origin, window_scale_factor)
+                } else {
+                    rect
+                };
+
+                // TODO(yan): @Speed @Memory Does early software scissor make
+                // sense here? We also do it later, when translating to the
+                // low-level draw list, but we could have less things to
+                // translate.
+                let color = match highlight {
+                    Some((highlighted, highlight_color))
+                        if highlighted.binary_search(&(line.range.start + i)).is_ok() =>
+                    {
+                        highlight_color
+                    }
+                    _ => color,
+                };
+
+                self.ui.draw_primitives.push(DrawPrimitive::Rect {
+                    rect,
+                    texture_rect: glyph_info.atlas_rect,
+                    texture_id: self.ui.font_atlas_texture_id,
+                    color,
+                    flip_y: false,
+                    sampling: Sampling::LINEAR,
+                    clip_rect: self.ui.clip_rect_stack.last().copied(),
+                });
+
+                parent.draw_range.end += 1;
+                self.ui.build_glyph_count += 1;
+                if extend_inline_content_rect {
+                    if let Some(inline_content_rect) = &mut parent.inline_content_rect {
+                        *inline_content_rect = inline_content_rect.extend_by_rect(rect);
+                    } else {
+                        parent.inline_content_rect = Some(rect);
+                    }
+                }
+            }
+
+            position_y += line_metrics.new_line_size;
+        }
+
+        // NB: Because this isn't real padding/border, we need to ensure that if
+        // we used inset, the final content rect reflects that. This happens
+        // automatically for top and left, but we need to add the inset
+        // to its size.
+        if extend_inline_content_rect {
+            if let Some(inline_content_rect) = &mut parent.inline_content_rect {
+                *inline_content_rect = inline_content_rect.resize(Vec2::splat(inset));
+            }
+        }
+    }
+
+    pub fn cursor_position(&self) -> Vec2 {
+        self.ui.cursor_position
+    }
+
+    pub fn font_atlas(&self) -> &FontAtlas<A> {
+        &self.ui.font_atlas
+    }
+
+    pub fn font_atlas_texture_id(&self) -> u64 {
+        self.ui.font_atlas_texture_id
+    }
+
+    /// Returns the distance between tab stops in text, in pixels. Zero, if tab
+    /// stops are disabled.
+    pub fn tab_stop_width(&self) -> f32 {
+        self.ui.tab_stop_width()
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.ui.allocator
+    }
+
+    /// See [`Frame::temp_allocator`].
+    pub fn temp_allocator(&self) -> &'a (dyn Allocator + 'a) {
+        self.temp_allocator
+    }
+}
+
+/// Maps a color to how it is drawn by disabled controls, i.e. with halved
+/// alpha. Useful for widgets that draw disabled-looking things outside of a
+/// disabled scope.
+pub fn disabled_color(color: u32) -> u32 {
+    let [r, g, b, a] = color.to_be_bytes();
+    u32::from_be_bytes([r, g, b, a / 2])
+}
+
+// Moves a rect relative to origin so that its absolute position lands on a
+// whole physical pixel.
+fn snap_rect_to_physical_pixels(rect: Rect, origin: Vec2, scale_factor: f32) -> Rect {
+    let position = origin + rect.min_point();
+    let snapped_position = (position * scale_factor).round() / scale_factor;
+
+    rect + (snapped_position - position)
+}
+
+// Moves the edges of a rect to the nearest whole physical pixels. Rects that
+// are not empty are kept at least one physical pixel wide and tall, so that
+// thin borders don't disappear.
+fn snap_rect_edges_to_physical_pixels(rect: Rect, scale_factor: f32) -> Rect {
+    let snap_edges = |min: f32, size: f32| {
+        let physical_min = libm::roundf(min * scale_factor);
+        let mut physical_max = libm::roundf((min + size) * scale_factor);
+        if size > 0.0 && physical_max <= physical_min {
+            physical_max = physical_min + 1.0;
+        }
+
+        (
+            physical_min / scale_factor,
+            f32::max(0.0, physical_max - physical_min) / scale_factor,
+        )
+    };
+
+    let (x, width) = snap_edges(rect.x, rect.width);
+    let (y, height) = snap_edges(rect.y, rect.height);
+
+    Rect::new(x, y, width, height)
+}
+
+// A line of text, as split by split_text_lines.
+pub(crate) struct TextLine {
+    pub range: Range<usize>,
+    pub width: f32,
+}
+
+// Splits text into lines that fit the available width, and trims whitespace
+// around them. This is the line splitting used by Ctrl::draw_text and friends,
+// exposed so that widgets drawing text themselves can lay it out identically.
+pub(crate) fn split_text_lines<A: Allocator + Clone, B: Allocator>(
+    font_atlas: &FontAtlas<A>,
+    text: &str,
+    available_width: f32,
+    wrap: Wrap,
+    tab_stop_width: f32,
+    lines: &mut Vec<TextLine, B>,
+) {
+    let mut last_char_was_whitespace = false;
+    let mut begun_word: bool;
+    let mut begun_word_start = 0;
+
+    let mut line_range = 0..0;
+    let mut line_width = 0.0;
+
+    for (i, c) in text.char_indices() {
+        begun_word = !c.is_whitespace();
+        if last_char_was_whitespace && !c.is_whitespace() {
+            begun_word_start = i;
+        }
+        last_char_was_whitespace = c.is_whitespace();
+
+        if c == '\n' && !line_range.is_empty() {
+            // Note that this could be an empty line, but that's fine.
+            lines.push(TextLine {
+                range: line_range,
+                width: line_width,
+            });
+
+            // 1 is the byte width of the '\n', so i + 1 is ok.
+            line_range = i + 1..i + 1;
+            line_width = 0.0;
+
+            continue;
+        }
+
+        // NB: Tabs that would overflow wrap just like any other whitespace, and
+        // are then trimmed from the line end.
+        let glyph_advance_width = char_advance_width(font_atlas, c, line_width, tab_stop_width);
+
+        if line_width + glyph_advance_width > available_width {
+            match wrap {
+                Wrap::Word => {
+                    let begun_word_width = if begun_word {
+                        let slice = &text[begun_word_start..i];
+
+                        let mut width = 0.0;
+                        for c in slice.chars() {
+                            width += font_atlas.glyph_info(c).advance_width;
+                        }
+
+                        width
+                    } else {
+                        0.0
+                    };
+
+                    if !begun_word || begun_word_width + glyph_advance_width > available_width {
+                        // If we are not inside a word right now, or the
+                        // begun word is wide enough to cause wrapping by
+                        // itself, fall back to letter wrapping.
+                        lines.push(TextLine {
+                            range: line_range,
+                            width: line_width,
+                        });
+
+                        line_range = i..i + c.len_utf8();
+                        line_width = char_advance_width(font_atlas, c, 0.0, tab_stop_width);
+                    } else {
+                        // Otherwise commit previous line and move the word
+                        // to the next.
+                        lines.push(TextLine {
+                            range: line_range.start..begun_word_start,
+                            width: line_width - begun_word_width,
+                        });
+
+                        line_range = begun_word_start..i + c.len_utf8();
+                        line_width = begun_word_width + glyph_advance_width;
+                    }
+
+                    continue;
+                }
+                Wrap::Letter => {
+                    lines.push(TextLine {
+                        range: line_range,
+                        width: line_width,
+                    });
+
+                    line_range = i..i + c.len_utf8();
+                    line_width = char_advance_width(font_atlas, c, 0.0, tab_stop_width);
+
+                    continue;
+                }
+                Wrap::None => (),
+            }
+        }
+
+        line_range.end += c.len_utf8();
+        line_width += glyph_advance_width;
+    }
+
+    lines.push(TextLine {
+        range: line_range,
+        width: line_width,
+    });
+
+    //
+    // Trim whitespace.
+    //
+    // Shorten ranges and decrease widths. The widths can only be decreased
+    // here, because the lines were already split and the whitespace widths
+    // already contributed to computing text wrap.
+    //
+    // The widths are measured again, because trimming the start of the line
+    // moves any tabs in it to different tab stops. Tab stops are never further
+    // right after moving the text left, so the widths still only decrease.
+    for line in lines.iter_mut() {
+        let line_slice = &text[line.range.clone()];
+
+        let mut start = line.range.start;
+        let mut end = line.range.end;
+
+        for c in line_slice.chars() {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            start += c.len_utf8();
+        }
+
+        let mut rev_iter = line_slice.chars().rev().peekable();
+        while let Some(c) = rev_iter.next() {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            if rev_iter.peek().is_some() {
+                end -= c.len_utf8();
+            }
+        }
+
+        if start > end {
+            start = end;
+        }
+
+        line.range.start = start;
+        line.range.end = end;
+        line.width = text_width(font_atlas, &text[start..end], tab_stop_width);
+    }
+}
+
+// Returns the width of a single line of text, with tabs advancing to the next
+// tab stop, as measured from the start of the text.
+pub(crate) fn text_width<A: Allocator + Clone>(
+    font_atlas: &FontAtlas<A>,
+    text: &str,
+    tab_stop_width: f32,
+) -> f32 {
+    let mut width = 0.0;
+    for c in text.chars() {
+        width += char_advance_width(font_atlas, c, width, tab_stop_width);
+    }
+
+    width
+}
+
+// Returns the advance width of the character placed at line_offset_x from the
+// start of its line. Tabs advance to the next tab stop, if tab stops are
+// enabled.
+pub(crate) fn char_advance_width<A: Allocator + Clone>(
+    font_atlas: &FontAtlas<A>,
+    c: char,
+    line_offset_x: f32,
+    tab_stop_width: f32,
+) -> f32 {
+    if c == '\t' && tab_stop_width > 0.0 {
+        tab_stop_width - line_offset_x % tab_stop_width
+    } else {
+        font_atlas.glyph_info(c).advance_width
+    }
+}
+
+// Returns how far the control's contents can be scrolled on each axis, i.e.
+// how much they overflow the control's inner rect.
+fn scroll_size(ctrl: &CtrlNode) -> Vec2 {
+    let visible_size = ctrl.rect.inset(ctrl.border + ctrl.padding).size();
+    Vec2::ZERO.max(ctrl.layout_cache_content_size - visible_size)
+}
+
+fn scroll_to_rect(ctrl: &mut CtrlNode, rect: Rect, smooth: bool) {
+    let visible_size = ctrl.rect.inset(ctrl.border + ctrl.padding).size();
+    let scroll_size = scroll_size(ctrl);
+
+    let mut target = ctrl.scroll_offset_target;
+
+    if rect.max_x() > target.x + visible_size.x {
+        target.x = rect.max_x() - visible_size.x;
+    }
+    if rect.x < target.x {
+        target.x = rect.x;
+    }
+
+    if rect.max_y() > target.y + visible_size.y {
+        target.y = rect.max_y() - visible_size.y;
+    }
+    if rect.y < target.y {
+        target.y = rect.y;
+    }
+
+    ctrl.scroll_offset_target = target.clamp(Vec2::ZERO, scroll_size);
+    if !smooth {
+        ctrl.scroll_offset = ctrl.scroll_offset_target;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scrollbar {
+    // Absolute rects of the whole scroll bar and the draggable thumb.
+    track: Rect,
+    thumb: Rect,
+    // How far can the control scroll along the scroll bar's axis.
+    scroll_size: f32,
+    // How far can the thumb move within the track.
+    thumb_travel: f32,
+}
+
+impl Scrollbar {
+    // Computes the scroll offset after the thumb was dragged by cursor_delta
+    // since the drag started at start_scroll_offset.
+    fn drag(&self, start_scroll_offset: f32, cursor_delta: f32) -> f32 {
+        if self.thumb_travel > 0.0 {
+            let scroll_offset =
+                start_scroll_offset + cursor_delta * self.scroll_size / self.thumb_travel;
+            scroll_offset.clamp(0.0, self.scroll_size)
+        } else {
+            start_scroll_offset
+        }
+    }
+}
+
+// Computes the vertical (right) and horizontal (bottom) scroll bars of a
+// control from last frame's layout. A scroll bar is only present if the control
+// shows scroll bars and its contents overflow along the scroll bar's axis.
+fn scrollbars(ctrl: &CtrlNode) -> (Option<Scrollbar>, Option<Scrollbar>) {
+    if !ctrl.flags.intersects(CtrlFlags::SHOW_SCROLLBARS) || ctrl.scrollbar_width <= 0.0 {
+        return (None, None);
+    }
+
+    let rect = Rect::new(
+        ctrl.layout_cache_absolute_position.x,
+        ctrl.layout_cache_absolute_position.y,
+        ctrl.rect.width,
+        ctrl.rect.height,
+    )
+    .inset(ctrl.border);
+
+    let visible_size = rect.inset(ctrl.padding).size();
+    let content_size = ctrl.layout_cache_content_size;
+    let scroll_size = scroll_size(ctrl);
+
+    let width = ctrl.scrollbar_width;
+    let show_vertical = scroll_size.y > 0.0;
+    let show_horizontal = scroll_size.x > 0.0;
+
+    // When both scroll bars are shown, they both stop short of the bottom right
+    // corner, so that they don't overlap.
+    let vertical = if show_vertical {
+        let track_height = f32::max(0.0, rect.height - if show_horizontal { width } else { 0.0 });
+        let track = Rect::new(rect.x + rect.width - width, rect.y, width, track_height);
+
+        let thumb_min_height = f32::min(width, track_height);
+        let thumb_height = f32::max(
+            thumb_min_height,
+            track_height * visible_size.y / content_size.y,
+        );
+        let thumb_travel = f32::max(0.0, track_height - thumb_height);
+        let thumb_y =
+            track.y + thumb_travel * (ctrl.scroll_offset.y / scroll_size.y).clamp(0.0, 1.0);
+        let thumb = Rect::new(track.x, thumb_y, width, thumb_height);
+
+        Some(Scrollbar {
+            track,
+            thumb,
+            scroll_size: scroll_size.y,
+            thumb_travel,
+        })
+    } else {
+        None
+    };
+
+    let horizontal = if show_horizontal {
+        let track_width = f32::max(0.0, rect.width - if show_vertical { width } else { 0.0 });
+        let track = Rect::new(rect.x, rect.y + rect.height - width, track_width, width);
+
+        let thumb_min_width = f32::min(width, track_width);
+        let thumb_width = f32::max(
+            thumb_min_width,
+            track_width * visible_size.x / content_size.x,
+        );
+        let thumb_travel = f32::max(0.0, track_width - thumb_width);
+        let thumb_x =
+            track.x + thumb_travel * (ctrl.scroll_offset.x / scroll_size.x).clamp(0.0, 1.0);
+        let thumb = Rect::new(thumb_x, track.y, thumb_width, width);
+
+        Some(Scrollbar {
+            track,
+            thumb,
+            scroll_size: scroll_size.x,
+            thumb_travel,
+        })
+    } else {
+        None
+    };
+
+    (vertical, horizontal)
+}
+
+// Mixes a local id into the parent id namespace. This is FNV-1a over the bytes
+// of both, so the result depends on their order, e.g. namespace 1 with local
+// id 2 produces a different id than namespace 2 with local id 1.
+pub(crate) fn mix_id(namespace: u32, id: u32) -> u32 {
+    fnv1a(namespace.to_le_bytes().into_iter().chain(id.to_le_bytes()))
+}
+
+// Like mix_id, but mixes in bytes of a string instead of a local id.
+fn hash_str(namespace: u32, s: &str) -> u32 {
+    fnv1a(namespace.to_le_bytes().into_iter().chain(s.bytes()))
+}
+
+// Controls in free layout are rendered in the order of this key, and hover is
+// detected in reverse.
+/// Counters collected while building and rendering a frame. See
+/// [`Ui::frame_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    /// The number of controls alive after the frame, including the roots of
+    /// the base layer and the overlay.
+    pub ctrl_count: usize,
+    /// The number of controls not built in the frame, and thus collected.
+    pub collected_ctrl_count: usize,
+    /// The number of rects, lines and other shapes drawn by controls,
+    /// including glyphs of text.
+    pub draw_primitive_count: usize,
+    pub glyph_count: usize,
+    pub command_count: usize,
+    pub vertex_count: usize,
+    pub index_count: usize,
+    /// The number of controls with [`CtrlFlags::CACHE_DRAW`] whose geometry
+    /// was reused from the last frame.
+    pub draw_cache_hit_count: usize,
+    /// The number of controls with [`CtrlFlags::CACHE_DRAW`] whose geometry
+    /// had to be generated, because something changed, or it wasn't cached
+    /// yet.
+    pub draw_cache_miss_count: usize,
+}
+
+/// A read-only snapshot of a control from the last built frame, as reported
+/// by [`Ui::debug_ctrls`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugCtrl {
+    /// The id the control was pushed with, mixed with the id namespace it
+    /// was pushed in. See [`Frame::current_id`].
+    pub id: u32,
+    pub persistent_id: u64,
+    /// Zero for controls directly under the root of their layer.
+    pub depth: usize,
+    pub overlay: bool,
+    pub disabled: bool,
+    pub flags: CtrlFlags,
+    pub layout: Layout,
+    /// The rect as set by the control, relative to its parent.
+    pub rect: Rect,
+    /// Where the control was laid out, in window coordinates.
+    pub absolute_rect: Rect,
+    pub padding: f32,
+    pub border: f32,
+    pub margin: f32,
+    pub content_size: Vec2,
+    pub scroll_offset: Vec2,
+    /// The number of draw primitives, e.g. rects and glyphs, the control drew
+    /// itself.
+    pub draw_primitive_count: usize,
+    /// Like `draw_primitive_count`, but including all descendants. Only
+    /// counted while the debug overlay is enabled, and zero otherwise. See
+    /// [`Ui::set_debug_overlay`].
+    pub subtree_draw_primitive_count: usize,
+}
+
+/// Iterator over the controls of the last built frame. See
+/// [`Ui::debug_ctrls`].
+pub struct DebugCtrls<'a> {
+    tree: &'a [CtrlNode],
+    next_idx: Option<usize>,
+    depth: usize,
+    overlay: bool,
+}
+
+impl<'a> DebugCtrls<'a> {
+    fn new(tree: &'a [CtrlNode]) -> Self {
+        let mut ctrls = Self {
+            tree,
+            next_idx: tree[ROOT_IDX].child_idx.map(CtrlIdx::get),
+            depth: 0,
+            overlay: false,
+        };
+
+        if ctrls.next_idx.is_none() {
+            ctrls.begin_overlay();
+        }
+
+        ctrls
+    }
+
+    fn begin_overlay(&mut self) {
+        self.next_idx = self.tree[OVERLAY_ROOT_IDX].child_idx.map(CtrlIdx::get);
+        self.depth = 0;
+        self.overlay = true;
+    }
+}
+
+impl<'a> Iterator for DebugCtrls<'a> {
+    type Item = DebugCtrl;
+
+    fn next(&mut self) -> Option<DebugCtrl> {
+        let ctrl_idx = self.next_idx?;
+        let ctrl = &self.tree[ctrl_idx];
+
+        let debug_ctrl = DebugCtrl {
+            id: ctrl.id,
+            persistent_id: ctrl.persistent_id,
+            depth: self.depth,
+            overlay: self.overlay,
+            disabled: ctrl.disabled,
+            flags: ctrl.flags,
+            layout: ctrl.layout,
+            rect: ctrl.rect,
+            absolute_rect: debug_ctrl_absolute_rect(ctrl),
+            padding: ctrl.padding,
+            border: ctrl.border,
+            margin: ctrl.margin,
+            content_size: ctrl.layout_cache_content_size,
+            scroll_offset: ctrl.scroll_offset,
+            draw_primitive_count: ctrl.draw_range.len(),
+            subtree_draw_primitive_count: ctrl.debug_subtree_draw_primitive_count,
+        };
+
+        // Advance in pre-order: down to the first child, or to the next
+        // sibling of the closest ancestor that has one.
+        if let Some(child_idx) = ctrl.child_idx {
+            self.next_idx = Some(child_idx.get());
+            self.depth += 1;
+        } else {
+            let mut idx = ctrl_idx;
+            loop {
+                if let Some(sibling_idx) = self.tree[idx].sibling_idx {
+                    self.next_idx = Some(sibling_idx.get());
+                    break;
+                }
+
+                let parent_idx = self.tree[idx].parent_idx.unwrap().get();
+                if parent_idx == ROOT_IDX {
+                    self.begin_overlay();
+                    break;
+                } else if parent_idx == OVERLAY_ROOT_IDX {
+                    self.next_idx = None;
+                    break;
+                }
+
+                idx = parent_idx;
+                self.depth -= 1;
+            }
+        }
+
+        Some(debug_ctrl)
+    }
+}
+
+fn debug_ctrl_absolute_rect(ctrl: &CtrlNode) -> Rect {
+    Rect::new(
+        ctrl.layout_cache_absolute_position.x,
+        ctrl.layout_cache_absolute_position.y,
+        ctrl.rect.width,
+        ctrl.rect.height,
+    )
+}
+
+// Writes the ids from the layer root down to the control, e.g. "0/3/1".
+fn write_debug_id_path<const N: usize>(
+    tree: &[CtrlNode],
+    ctrl_idx: usize,
+    text: &mut ArrayString<N>,
+) {
+    use core::fmt::Write as _;
+
+    let ctrl = &tree[ctrl_idx];
+    let parent_idx = ctrl.parent_idx.unwrap().get();
+    if parent_idx != ROOT_IDX && parent_idx != OVERLAY_ROOT_IDX {
+        write_debug_id_path(tree, parent_idx, text);
+        let _ = write!(text, "/");
+    }
+
+    let _ = write!(text, "{}", ctrl.id);
+}
+
+// Whether the control is the ancestor or one of its descendants, also going
+// from top level overlay controls to their owners, e.g. from a dropdown's
+// options to the dropdown.
+fn ctrl_is_within(tree: &[CtrlNode], ctrl_idx: usize, ancestor_idx: usize) -> bool {
+    let mut ctrl_idx = Some(ctrl_idx);
+    while let Some(idx) = ctrl_idx {
+        if idx == ancestor_idx {
+            return true;
+        }
+
+        let ctrl = &tree[idx];
+        ctrl_idx = ctrl.overlay_owner_idx.or(ctrl.parent_idx).map(CtrlIdx::get);
+    }
+
+    false
+}
+
+fn render_order_key(ctrl: &CtrlNode) -> (i32, u32) {
+    (ctrl.z_index, ctrl.last_frame_in_active_path)
+}
+
+struct Viewport<A: Allocator + Clone> {
+    id: u32,
+    size: Vec2,
+    last_frame: u32,
+    draw_list: DrawList<A>,
+}
+
+// Geometry of controls with CtrlFlags::CACHE_DRAW and their descendants, by
+// persistent id.
+struct DrawCaches<A: Allocator + Clone> {
+    entries: HashMap<u64, DrawCache<A>, DefaultHashBuilder, A>,
+    allocator: A,
+    // Counted while rendering, and moved to frame_stats in end_frame.
+    hit_count: usize,
+    miss_count: usize,
+}
+
+struct DrawCache<A: Allocator + Clone> {
+    // Hash of everything that went into rendering the recording.
+    key: u64,
+    recording: DrawListRecording<A>,
+}
+
+// Hashes what the control and its descendants render, in the order they
+// render it.
+fn hash_ctrl_subtree<A: Allocator>(
+    hasher: &mut Fnv1aHasher,
+    tree: &[CtrlNode],
+    ctrl_idx: usize,
+    draw_primitives: &[DrawPrimitive],
+    siblings: &mut Vec<(usize, (i32, u32)), A>,
+) {
+    let ctrl = &tree[ctrl_idx];
+
+    hasher.write_u32(ctrl.flags.bits());
+    hasher.write_u8(u8::from(ctrl.disabled));
+    hash_vec2(hasher, ctrl.layout_cache_absolute_position);
+    hash_vec2(hasher, ctrl.layout_cache_content_size);
+    hash_rect(hasher, ctrl.rect);
+    hasher.write_u32(ctrl.padding.to_bits());
+    hasher.write_u32(ctrl.border.to_bits());
+    hash_vec2(hasher, ctrl.scroll_offset);
+
+    hasher.write_u8(u8::from(ctrl.draw_self));
+    hasher.write_u32(ctrl.draw_self_border_color);
+    hasher.write_u32(ctrl.draw_self_background_color);
+    hasher.write_u32(ctrl.scrollbar_width.to_bits());
+    hasher.write_u32(ctrl.scrollbar_track_color);
+    hasher.write_u32(ctrl.scrollbar_thumb_color);
+
+    hasher.write_usize(ctrl.draw_range.len());
+    for draw_primitive in &draw_primitives[ctrl.draw_range.clone()] {
+        hash_draw_primitive(hasher, draw_primitive);
+    }
+
+    // NB: Children of free layouts are hashed in render order, same as they
+    // are sorted for rendering.
+    let siblings_start = siblings.len();
+    if let Some(child_idx) = ctrl.child_idx.map(CtrlIdx::get) {
+        let mut child = &tree[child_idx];
+        siblings.push((child_idx, render_order_key(child)));
+
+        while let Some(sibling_idx) = child.sibling_idx.map(CtrlIdx::get) {
+            child = &tree[sibling_idx];
+            siblings.push((sibling_idx, render_order_key(child)));
+        }
+    }
+
+    let siblings_end = siblings.len();
+    if ctrl.layout == Layout::Free {
+        siblings[siblings_start..].sort_unstable_by_key(|&(_, key)| key);
+    }
+
+    hasher.write_usize(siblings_end - siblings_start);
+    for i in siblings_start..siblings_end {
+        let (child_idx, _) = siblings[i];
+        hash_ctrl_subtree(hasher, tree, child_idx, draw_primitives, siblings);
+    }
+
+    siblings.truncate(siblings_start);
+}
+
+fn hash_draw_primitive(hasher: &mut Fnv1aHasher, draw_primitive: &DrawPrimitive) {
+    match *draw_primitive {
+        DrawPrimitive::Rect {
+            rect,
+            texture_rect,
+            texture_id,
+            color,
+            flip_y,
+            sampling,
+            clip_rect,
+        } => {
+            hasher.write_u8(0);
+            hash_rect(hasher, rect);
+            hash_rect(hasher, texture_rect);
+            hasher.write_u64(texture_id);
+            hasher.write_u32(color);
+            hasher.write_u8(u8::from(flip_y));
+            sampling.hash(hasher);
+            hash_clip_rect(hasher, clip_rect);
+        }
+        DrawPrimitive::GradientRect {
+            rect,
+            texture_rect,
+            texture_id,
+            colors,
+            clip_rect,
+        } => {
+            hasher.write_u8(1);
+            hash_rect(hasher, rect);
+            hash_rect(hasher, texture_rect);
+            hasher.write_u64(texture_id);
+            for color in colors {
+                hasher.write_u32(color);
+            }
+            hash_clip_rect(hasher, clip_rect);
+        }
+        DrawPrimitive::Line {
+            a,
+            b,
+            thickness,
+            texture_id,
+            color,
+            clip_rect,
+        } => {
+            hasher.write_u8(2);
+            hash_vec2(hasher, a);
+            hash_vec2(hasher, b);
+            hasher.write_u32(thickness.to_bits());
+            hasher.write_u64(texture_id);
+            hasher.write_u32(color);
+            hash_clip_rect(hasher, clip_rect);
+        }
+        DrawPrimitive::Circle {
+            center,
+            radius,
+            texture_id,
+            color,
+            clip_rect,
+        } => {
+            hasher.write_u8(3);
+            hash_vec2(hasher, center);
+            hasher.write_u32(radius.to_bits());
+            hasher.write_u64(texture_id);
+            hasher.write_u32(color);
+            hash_clip_rect(hasher, clip_rect);
+        }
+        DrawPrimitive::RoundedRect {
+            rect,
+            radius,
+            border_thickness,
+            texture_id,
+            color,
+            clip_rect,
+        } => {
+            hasher.write_u8(4);
+            hash_rect(hasher, rect);
+            hasher.write_u32(radius.to_bits());
+            match border_thickness {
+                Some(border_thickness) => {
+                    hasher.write_u8(1);
+                    hasher.write_u32(border_thickness.to_bits());
+                }
+                None => hasher.write_u8(0),
+            }
+            hasher.write_u64(texture_id);
+            hasher.write_u32(color);
+            hash_clip_rect(hasher, clip_rect);
+        }
+        DrawPrimitive::Callback {
+            rect,
+            user_id,
+            clip_rect,
+        } => {
+            hasher.write_u8(5);
+            hash_rect(hasher, rect);
+            hasher.write_u64(user_id);
+            hash_clip_rect(hasher, clip_rect);
+        }
+    }
+}
+
+fn hash_clip_rect(hasher: &mut Fnv1aHasher, clip_rect: Option<Rect>) {
+    match clip_rect {
+        Some(clip_rect) => {
+            hasher.write_u8(1);
+            hash_rect(hasher, clip_rect);
+        }
+        None => hasher.write_u8(0),
+    }
+}
+
+fn hash_rect(hasher: &mut Fnv1aHasher, rect: Rect) {
+    hasher.write_u32(rect.x.to_bits());
+    hasher.write_u32(rect.y.to_bits());
+    hasher.write_u32(rect.width.to_bits());
+    hasher.write_u32(rect.height.to_bits());
+}
+
+fn hash_vec2(hasher: &mut Fnv1aHasher, v: Vec2) {
+    hasher.write_u32(v.x.to_bits());
+    hasher.write_u32(v.y.to_bits());
+}
+
+// 64-bit FNV-1a, same as for persistent ids, but usable with core::hash.
+struct Fnv1aHasher(u64);
+
+impl Fnv1aHasher {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        const FNV_PRIME: u64 = 0x00000100000001b3;
+
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// Follows the children of the control, matching one id of the path per level.
+fn find_ctrl_by_id_path(tree: &[CtrlNode], ctrl_idx: usize, path: &[u32]) -> Option<usize> {
+    let mut ctrl_idx = ctrl_idx;
+    for &id in path {
+        let mut child_idx = tree[ctrl_idx].child_idx;
+        ctrl_idx = loop {
+            let idx = child_idx?.get();
+            if tree[idx].id == id {
+                break idx;
+            }
+
+            child_idx = tree[idx].sibling_idx;
+        };
+    }
+
+    Some(ctrl_idx)
+}
+
+// Displays the ids of a control and all its ancestors, root first, for
+// diagnostics.
+struct IdPath<'a> {
+    tree: &'a [CtrlNode],
+    idx: usize,
+}
+
+impl fmt::Display for IdPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ctrl = &self.tree[self.idx];
+        if let Some(parent_idx) = ctrl.parent_idx.map(CtrlIdx::get) {
+            let parent = IdPath {
+                tree: self.tree,
+                idx: parent_idx,
+            };
+            write!(f, "{parent}/")?;
+        }
+
+        write!(f, "{}", ctrl.id)
+    }
+}
+
+// Like mix_id, but produces the wider persistent ids from the parent's
+// persistent id.
+fn mix_persistent_id(parent_persistent_id: u64, id: u32) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x00000100000001b3;
+
+    let bytes = parent_persistent_id
+        .to_le_bytes()
+        .into_iter()
+        .chain(id.to_le_bytes());
+
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash
+}
+
+fn fnv1a(bytes: impl Iterator<Item = u8>) -> u32 {
+    const FNV_OFFSET_BASIS: u32 = 0x811c9dc5;
+    const FNV_PRIME: u32 = 0x01000193;
+
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in bytes {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "runtime_rasterize")]
+    use alloc::alloc::Global;
+    #[cfg(feature = "runtime_rasterize")]
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    #[cfg(feature = "runtime_rasterize")]
+    use core::alloc::{AllocError, Layout as AllocLayout};
+    #[cfg(feature = "runtime_rasterize")]
+    use core::cell::Cell;
+    #[cfg(feature = "runtime_rasterize")]
+    use core::fmt;
+    #[cfg(feature = "runtime_rasterize")]
+    use core::ptr::NonNull;
+
+    use super::*;
+    #[cfg(feature = "runtime_rasterize")]
+    use crate::core::draw_list::CommandKind;
+    use crate::core::font_atlas::UnicodeRangeFlags;
+    #[cfg(feature = "runtime_rasterize")]
+    use crate::fixtures::{new_ui, FONT};
+
+    // Counts allocations into the shared counter, and otherwise forwards to
+    // the global allocator.
+    #[cfg(feature = "runtime_rasterize")]
+    #[derive(Clone)]
+    struct CountingAllocator(Rc<Cell<usize>>);
+
+    #[cfg(feature = "runtime_rasterize")]
+    unsafe impl Allocator for CountingAllocator {
+        fn allocate(&self, layout: AllocLayout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: AllocLayout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_str_id_is_stable_across_frames() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            frame.push_id_str("toolbar");
+            let id = frame.id("save");
+            frame.push_ctrl(id);
+            frame.pop_ctrl();
+            frame.pop_id();
+            ui.end_frame();
+
+            id
+        };
+
+        let id = build(&mut ui);
+        let ctrl_count = ui.ctrl_count();
+
+        assert_eq!(build(&mut ui), id);
+        assert_eq!(ui.ctrl_count(), ctrl_count);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_str_ids_under_same_parent_dont_collide() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut ids = Vec::new();
+        for i in 0..256 {
+            let mut s: ArrayString<16> = ArrayString::new();
+            let _ = fmt::write(&mut s, format_args!("button {i}"));
+            ids.push(frame.id(&s));
+        }
+        for s in ["a", "b", "ab", "ba", "", " "] {
+            ids.push(frame.id(s));
+        }
+
+        // Also exercises the sibling id collision check.
+        for &id in &ids {
+            frame.push_ctrl(id);
+            frame.pop_ctrl();
+        }
+        ui.end_frame();
+
+        let len = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+
+        assert_eq!(ids.len(), len);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_str_id_is_seeded_by_id_namespace() {
+        let mut ui = new_ui();
+        let local = line!();
+
+        let mut frame = ui.begin_frame();
+        let str_id = frame.id("row");
+
+        // A string id under a numeric namespace is seeded by it.
+        frame.push_id(3);
+        let nested_str_id = frame.id("row");
+        frame.pop_id();
+        assert_eq!(nested_str_id, hash_str(mix_id(0, 3), "row"));
+        assert_ne!(nested_str_id, str_id);
+
+        // Numeric ids under a string namespace are mixed into it the same way
+        // as under a numeric namespace.
+        frame.push_id_str("row");
+        frame.push_id(5);
+        let under_str = frame.current_id(local);
+        frame.pop_id();
+        frame.pop_id();
+
+        frame.push_id(str_id);
+        frame.push_id(5);
+        let under_numeric = frame.current_id(local);
+        frame.pop_id();
+        frame.pop_id();
+
+        assert_eq!(under_str, mix_id(mix_id(str_id, 5), local));
+        assert_eq!(under_numeric, mix_id(mix_id(mix_id(0, str_id), 5), local));
+
+        // The order of pushes matters, like for numeric namespaces.
+        frame.push_id(5);
+        frame.push_id_str("row");
+        let reversed = frame.current_id(local);
+        frame.pop_id();
+        frame.pop_id();
+        assert_ne!(reversed, under_str);
+
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "Control id 50 (mixed with the id stack:")]
+    fn test_sibling_id_collision_panics_for_distant_siblings() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        frame.push_ctrl(1000);
+        for id in 0..100 {
+            frame.push_ctrl(id);
+            frame.pop_ctrl();
+        }
+        frame.push_ctrl(50);
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_same_id_under_different_parents_doesnt_panic() {
+        let mut ui = new_ui();
+
+        for _ in 0..2 {
+            let mut frame = ui.begin_frame();
+            for parent_id in 0..3 {
+                frame.push_ctrl(parent_id);
+                for id in 0..3 {
+                    frame.push_ctrl(id);
+                    frame.pop_ctrl();
+                }
+                frame.pop_ctrl();
+            }
+
+            frame.begin_overlay();
+            frame.push_ctrl(0);
+            frame.pop_ctrl();
+            frame.end_overlay();
+            ui.end_frame();
+        }
+
+        // The two roots, three parents with three children each, and the
+        // overlay control.
+        assert_eq!(ui.ctrl_count(), 2 + 3 + 9 + 1);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_input_event_bursts_dont_drop_presses_and_releases() {
+        let mut ui = new_ui();
+
+        for _ in 0..100 {
+            ui.scroll(0.0, 1.0);
+        }
+        ui.press_inputs(Inputs::MB_LEFT);
+
+        let frame = ui.begin_frame();
+        assert_eq!(frame.input_events().len(), 2);
+        assert!(matches!(
+            frame.input_events()[0],
+            InputEvent::Scroll(delta) if delta == Vec2::new(0.0, 100.0)
+        ));
+        assert!(frame.inputs_pressed().intersects(Inputs::MB_LEFT));
+        ui.end_frame();
+
+        // Scrolls interleaved with other events can't be merged, and fill the
+        // queue, but the release still arrives.
+        for _ in 0..100 {
+            ui.scroll(0.0, 1.0);
+            ui.send_character('a');
+        }
+        ui.release_inputs(Inputs::MB_LEFT);
+
+        let frame = ui.begin_frame();
+        assert_eq!(frame.input_events().len(), INPUT_EVENT_CAPACITY);
+        assert!(frame.inputs_released().intersects(Inputs::MB_LEFT));
+        ui.end_frame();
+
+        let frame = ui.begin_frame();
+        assert!(!frame.inputs_released().intersects(Inputs::MB_LEFT));
+        ui.end_frame();
+    }
+
+    #[test]
+    fn test_shortcut_matches_exact_modifiers() {
+        let shortcut = Shortcut::new(Inputs::KB_LEFT_ARROW, Modifiers::CTRL | Modifiers::SHIFT);
+
+        assert!(shortcut.matches(Inputs::KB_LEFT_ARROW, Modifiers::CTRL | Modifiers::SHIFT));
+        assert!(!shortcut.matches(Inputs::KB_LEFT_ARROW, Modifiers::CTRL));
+        assert!(!shortcut.matches(Inputs::KB_LEFT_ARROW, Modifiers::SHIFT));
+        assert!(!shortcut.matches(Inputs::KB_LEFT_ARROW, Modifiers::ALL));
+        assert!(!shortcut.matches(Inputs::KB_RIGHT_ARROW, Modifiers::CTRL | Modifiers::SHIFT));
+    }
+
+    #[test]
+    fn test_shortcut_matches_with_other_inputs_pressed() {
+        let copy = Shortcut::new(Inputs::KB_C, Modifiers::CTRL);
+        assert!(copy.matches(Inputs::KB_C | Inputs::MB_LEFT, Modifiers::CTRL));
+
+        let submit = Shortcut::new(Inputs::KB_ENTER, Modifiers::NONE);
+        assert!(submit.matches(Inputs::KB_ENTER | Inputs::KB_A, Modifiers::NONE));
+        assert!(!submit.matches(Inputs::KB_A, Modifiers::NONE));
+    }
+
+    #[test]
+    fn test_shortcut_matches_ignores_unknown_modifiers() {
+        let shortcut = Shortcut::new(Inputs::KB_A, Modifiers::CTRL);
+        let unknown = !Modifiers::ALL;
+
+        assert!(shortcut.matches(Inputs::KB_A, Modifiers::CTRL | unknown));
+    }
+
+    #[test]
+    fn test_mix_id_loop_iterations_produce_distinct_ids() {
+        let local = line!();
+
+        let a = mix_id(mix_id(0, 0), local);
+        let b = mix_id(mix_id(0, 1), local);
+
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_mix_id_nested_loop_iterations_produce_distinct_ids() {
+        let local = line!();
+
+        let mut ids = Vec::new();
+        for i in 0..16 {
+            let outer = mix_id(0, i);
+            for j in 0..16 {
+                let inner = mix_id(outer, j);
+                ids.push(mix_id(inner, local));
+            }
+        }
+
+        let len = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+
+        assert_eq!(ids.len(), len);
+    }
+
+    #[test]
+    fn test_mix_id_is_stable() {
+        let local = line!();
+
+        assert_eq!(mix_id(mix_id(0, 3), local), mix_id(mix_id(0, 3), local));
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_dragging_scrollbar_thumb_scrolls_ctrl() {
+        let mut ui = new_ui();
+
+        // The content is four times as tall as the control, so the thumb is a
+        // quarter of the track, and each pixel the thumb moves scrolls the
+        // content by four pixels.
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::SHOW_SCROLLBARS);
+            ctrl.set_layout(Layout::Vertical);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(0.0);
+            ctrl.set_scrollbar_width(10.0);
+            let scroll_offset = ctrl.scroll_offset_y();
+
+            let mut content = frame.push_ctrl(0);
+            content.set_rect(Rect::new(0.0, 0.0, 50.0, 400.0));
+            frame.pop_ctrl();
+
+            frame.pop_ctrl();
+            ui.end_frame();
+
+            scroll_offset
+        };
+
+        // First frame lays out the control, so that its thumb can be pressed.
+        build(&mut ui);
+
+        ui.set_cursor_position(95.0, 10.0);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert_eq!(build(&mut ui), 0.0);
+
+        ui.set_cursor_position(95.0, 35.0);
+        assert_eq!(build(&mut ui), 100.0);
+
+        // The cursor doesn't have to stay over the scroll bar, and the offset
+        // is clamped to the scrollable size.
+        ui.set_cursor_position(300.0, 1000.0);
+        assert_eq!(build(&mut ui), 300.0);
+
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert_eq!(build(&mut ui), 300.0);
+
+        ui.set_cursor_position(95.0, 10.0);
+        assert_eq!(build(&mut ui), 300.0);
+    }
+
+    // Builds a 100x100 control scrolling ten 50px tall children, and returns
+    // its scroll offset after begin_frame. Optionally scrolls the control to a
+    // rect, or one of the children (ids 1 to 10) into view.
+    #[cfg(feature = "runtime_rasterize")]
+    fn build_scroll_list(
+        ui: &mut Ui<Global>,
+        delta_time: Option<f32>,
+        scroll_to_rect: Option<Rect>,
+        scroll_into_view_id: Option<u32>,
+    ) -> f32 {
+        let mut frame = match delta_time {
+            Some(delta_time) => ui.begin_frame_with_delta_time(delta_time),
+            None => ui.begin_frame(),
+        };
+
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_flags(CtrlFlags::CAPTURE_SCROLL | CtrlFlags::CAPTURE_HOVER);
+        ctrl.set_layout(Layout::Vertical);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+        ctrl.set_padding(0.0);
+        ctrl.set_border(0.0);
+        ctrl.set_margin(0.0);
+        let scroll_offset = ctrl.scroll_offset_y();
+        if let Some(rect) = scroll_to_rect {
+            ctrl.scroll_to_rect(rect);
+        }
+
+        for id in 1..=10 {
+            let mut item = frame.push_ctrl(id);
+            item.set_rect(Rect::new(0.0, 0.0, 100.0, 50.0));
+            item.set_padding(0.0);
+            item.set_border(0.0);
+            item.set_margin(0.0);
+            if scroll_into_view_id == Some(id) {
+                item.scroll_into_view();
+            }
+            frame.pop_ctrl();
+        }
+
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        scroll_offset
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_scroll_to_rect_scrolls_by_smallest_amount() {
+        let mut ui = new_ui();
+        build_scroll_list(&mut ui, None, None, None);
+
+        let mut scroll_to_rect = |rect: Rect| {
+            build_scroll_list(&mut ui, None, Some(rect), None);
+            build_scroll_list(&mut ui, None, None, None)
+        };
+
+        // Below the visible part, so only scrolls until the bottom edge shows.
+        assert_eq!(scroll_to_rect(Rect::new(0.0, 150.0, 10.0, 50.0)), 100.0);
+        // Already visible.
+        assert_eq!(scroll_to_rect(Rect::new(0.0, 120.0, 10.0, 50.0)), 100.0);
+        // Above the visible part, so scrolls until the top edge shows.
+        assert_eq!(scroll_to_rect(Rect::new(0.0, 20.0, 10.0, 50.0)), 20.0);
+        // Taller than the control, so the top edge wins.
+        assert_eq!(scroll_to_rect(Rect::new(0.0, 200.0, 10.0, 300.0)), 200.0);
+        // Past the end of the contents, so clamped to the scrollable size.
+        assert_eq!(scroll_to_rect(Rect::new(0.0, 1000.0, 10.0, 50.0)), 400.0);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_scroll_into_view_scrolls_parent_to_ctrl() {
+        let mut ui = new_ui();
+        build_scroll_list(&mut ui, None, None, None);
+
+        build_scroll_list(&mut ui, None, None, Some(8));
+        assert_eq!(build_scroll_list(&mut ui, None, None, None), 300.0);
+
+        build_scroll_list(&mut ui, None, None, Some(2));
+        assert_eq!(build_scroll_list(&mut ui, None, None, None), 50.0);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_scroll_without_delta_time_is_instant() {
+        let mut ui = new_ui();
+        ui.set_scroll_smoothing(Some(0.1));
+        build_scroll_list(&mut ui, None, None, None);
+
+        ui.set_cursor_position(50.0, 50.0);
+        ui.scroll(0.0, -120.0);
+        assert_eq!(build_scroll_list(&mut ui, None, None, None), 120.0);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_smooth_scroll_converges_to_clamped_target() {
+        let mut ui = new_ui();
+        ui.set_scroll_smoothing(Some(0.1));
+        build_scroll_list(&mut ui, Some(0.1), None, None);
+
+        // Each frame covers 1 - e^-1 of the remaining distance.
+        ui.set_cursor_position(50.0, 50.0);
+        ui.scroll(0.0, -100.0);
+        let first = build_scroll_list(&mut ui, Some(0.1), None, None);
+        assert!((first - 63.212).abs() < 0.01);
+        let second = build_scroll_list(&mut ui, Some(0.1), None, None);
+        assert!((second - 86.466).abs() < 0.01);
+
+        for _ in 0..20 {
+            build_scroll_list(&mut ui, Some(0.1), None, None);
+        }
+        assert_eq!(build_scroll_list(&mut ui, Some(0.1), None, None), 100.0);
+
+        // The target is clamped, so the offset never animates past the end of
+        // the contents.
+        ui.scroll(0.0, -10000.0);
+        let mut previous = 100.0;
+        for _ in 0..30 {
+            let scroll_offset = build_scroll_list(&mut ui, Some(0.1), None, None);
+            assert!(scroll_offset >= previous);
+            assert!(scroll_offset <= 400.0);
+            previous = scroll_offset;
+        }
+        assert_eq!(previous, 400.0);
+    }
+
+    // Builds a 100x100 control with ten 50x150 children laid out
+    // horizontally, so that it overflows 400 horizontally and 50 vertically.
+    // Returns the scroll offset the control had while building.
+    #[cfg(feature = "runtime_rasterize")]
+    fn build_scroll_row(ui: &mut Ui<Global>) -> Vec2 {
+        let mut frame = ui.begin_frame();
+
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_flags(
+            CtrlFlags::CAPTURE_SCROLL
+                | CtrlFlags::CAPTURE_SCROLL_HORIZONTAL
+                | CtrlFlags::CAPTURE_HOVER,
+        );
+        ctrl.set_layout(Layout::Horizontal);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+        ctrl.set_padding(0.0);
+        ctrl.set_border(0.0);
+        ctrl.set_margin(0.0);
+        let scroll_offset = Vec2::new(ctrl.scroll_offset_x(), ctrl.scroll_offset_y());
+
+        for id in 1..=10 {
+            let mut item = frame.push_ctrl(id);
+            item.set_rect(Rect::new(0.0, 0.0, 50.0, 150.0));
+            item.set_padding(0.0);
+            item.set_border(0.0);
+            item.set_margin(0.0);
+            frame.pop_ctrl();
+        }
+
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        scroll_offset
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_shift_scroll_scrolls_horizontally() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+        build_scroll_row(&mut ui);
+        build_scroll_row(&mut ui);
+
+        // Without shift, the wheel scrolls the vertical overflow.
+        ui.scroll(0.0, -30.0);
+        assert_eq!(build_scroll_row(&mut ui), Vec2::new(0.0, 30.0));
+
+        ui.press_modifiers(Modifiers::SHIFT);
+        ui.scroll(0.0, -30.0);
+        assert_eq!(build_scroll_row(&mut ui), Vec2::new(30.0, 30.0));
+
+        // Platforms that already map shift to horizontal scrolling send the
+        // delta in X, which is used as is.
+        ui.scroll(-20.0, 0.0);
+        assert_eq!(build_scroll_row(&mut ui), Vec2::new(50.0, 30.0));
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_horizontal_scroll_is_clamped_to_content_width() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+        build_scroll_row(&mut ui);
+        build_scroll_row(&mut ui);
+
+        ui.press_modifiers(Modifiers::SHIFT);
+        ui.scroll(0.0, -10000.0);
+        assert_eq!(build_scroll_row(&mut ui), Vec2::new(400.0, 0.0));
+
+        ui.scroll(0.0, 10000.0);
+        assert_eq!(build_scroll_row(&mut ui), Vec2::new(0.0, 0.0));
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_draw_text_rtl_mirrors_glyph_positions() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+        ctrl.draw_text(
+            "ab",
+            Align::Start,
+            Align::Start,
+            Wrap::None,
+            Direction::Ltr,
+            0,
+        );
+        ctrl.draw_text(
+            "ab",
+            Align::Start,
+            Align::Start,
+            Wrap::None,
+            Direction::Rtl,
+            0,
+        );
+        frame.pop_ctrl();
+
+        let mut xs: Vec<f32, _> = Vec::new_in(Global);
+        for primitive in &ui.draw_primitives {
+            match primitive {
+                DrawPrimitive::Rect { rect, .. } => xs.push(rect.x),
+                _ => unreachable!(),
+            }
+        }
+
+        // Left-to-right: 'a' is left of 'b', starting at the left edge.
+        // Right-to-left: 'a' is right of 'b', ending at the right edge.
+        let advance_width = ui.font_atlas.glyph_info('a').advance_width;
+        assert!(xs[0] < xs[1]);
+        assert!(xs[2] > xs[3]);
+        assert_eq!(xs[2] - xs[0], 100.0 - advance_width);
+
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_persistent_id_survives_relocation() {
+        let mut ui = new_ui();
+
+        fn build(ui: &mut Ui<Global>, with_first: bool) -> u64 {
+            let mut frame = ui.begin_frame();
+            if with_first {
+                frame.push_ctrl(0);
+                frame.pop_ctrl();
+            }
+
+            frame.push_ctrl(1);
+            let mut ctrl = frame.push_ctrl(2);
+            ctrl.set_rect(Rect::new(10.0, 20.0, 30.0, 40.0));
+            let persistent_id = ctrl.persistent_id();
+            frame.pop_ctrl();
+            frame.pop_ctrl();
+
+            ui.end_frame();
+
+            persistent_id
+        }
+
+        let persistent_id = build(&mut ui, true);
+        // The first control dies and the rest of the tree gets relocated.
+        assert_eq!(build(&mut ui, false), persistent_id);
+
+        let frame = ui.begin_frame();
+        let layout = frame.ctrl_layout(persistent_id).unwrap();
+        assert_eq!(layout.rect, Rect::new(10.0, 20.0, 30.0, 40.0));
+        assert_eq!(layout.absolute_position, Vec2::new(10.0, 20.0));
+        assert_eq!(frame.ctrl_layout(persistent_id.wrapping_add(1)), None);
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_split_text_lines_tab_stops() {
+        let font_atlas = FontAtlas::new_in(
+            FONT,
+            UnicodeRangeFlags::ALL_LATIN,
+            13.0,
+            1.0,
+            u16::MAX,
+            Global,
+        );
+        let advance_width = font_atlas.glyph_info('a').advance_width;
+        let tab_stop_width = 4.0 * font_atlas.glyph_info(' ').advance_width;
+
+        let mut lines: Vec<TextLine, _> = Vec::new_in(Global);
+        split_text_lines(
+            &font_atlas,
+            "a\tb",
+            1000.0,
+            Wrap::Word,
+            tab_stop_width,
+            &mut lines,
+        );
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].width, tab_stop_width + advance_width);
+
+        // The text after the tab doesn't fit, so it wraps and the tab gets
+        // trimmed from the end of the first line.
+        lines.clear();
+        split_text_lines(
+            &font_atlas,
+            "a\tb",
+            tab_stop_width + 1.0,
+            Wrap::Word,
+            tab_stop_width,
+            &mut lines,
+        );
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].range, 0..1);
+        assert_eq!(lines[0].width, advance_width);
+        assert_eq!(lines[1].range, 2..3);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_style_vars_nest() {
+        let mut ui = new_ui();
+        let mut frame = ui.begin_frame();
+        let button_height = frame.theme().button_height;
+
+        frame.push_style_var(StyleVar::ButtonHeight(40.0));
+        frame.push_style_var(StyleVar::ButtonMargin(0.0));
+        frame.push_style_var(StyleVar::ButtonHeight(50.0));
+        assert_eq!(frame.theme().button_height, 50.0);
+
+        frame.pop_style_var();
+        assert_eq!(frame.theme().button_height, 40.0);
+        assert_eq!(frame.theme().button_margin, 0.0);
+
+        frame.pop_style_var();
+        frame.pop_style_var();
+        assert_eq!(frame.theme().button_height, button_height);
+        ui.end_frame();
+
+        assert_eq!(*ui.theme(), Theme::DEFAULT);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "Unpopped: ButtonHeight(40.0)")]
+    fn test_unbalanced_style_vars_panic() {
+        let mut ui = new_ui();
+        let mut frame = ui.begin_frame();
+        frame.push_style_var(StyleVar::ButtonMargin(0.0));
+        frame.push_style_var(StyleVar::ButtonHeight(40.0));
+        frame.pop_style_var();
+        frame.push_style_var(StyleVar::ButtonHeight(40.0));
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "Is there a pop_style_var for every push_style_var before pop_theme?")]
+    fn test_style_var_outliving_theme_panics() {
+        let mut ui = new_ui();
+        let mut frame = ui.begin_frame();
+        frame.push_theme(&Theme::LIGHT);
+        frame.push_style_var(StyleVar::ButtonHeight(40.0));
+        frame.pop_theme();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "Is there a pop_theme for every push_theme after push_style_var?")]
+    fn test_theme_outliving_style_var_panics() {
+        let mut ui = new_ui();
+        let mut frame = ui.begin_frame();
+        frame.push_style_var(StyleVar::ButtonHeight(40.0));
+        frame.push_theme(&Theme::LIGHT);
+        frame.pop_style_var();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_push_id_disambiguates_controls_built_in_loop() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        for i in 0..2 {
+            frame.push_id(i);
+            frame.push_ctrl(line!());
+            frame.pop_ctrl();
+            frame.pop_id();
+        }
+        ui.end_frame();
+
+        // The two roots, and the two controls.
+        assert_eq!(ui.ctrl_count(), 4);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "is used by multiple siblings under control 0")]
+    fn test_sibling_id_collision_panics() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        for _ in 0..2 {
+            frame.push_ctrl(line!());
+            frame.pop_ctrl();
+        }
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    fn assert_tree_integrity(ui: &Ui<Global>) {
+        let mut visited = alloc::vec![false; ui.tree.len()];
+
+        check(&ui.tree, ROOT_IDX, None, ui.current_frame, &mut visited);
+        check(
+            &ui.tree,
+            OVERLAY_ROOT_IDX,
+            None,
+            ui.current_frame,
+            &mut visited,
+        );
+
+        // After GC, every control is live, so every control must be reachable.
+        assert!(visited.iter().all(|&v| v), "Unreachable control found");
+
+        if let Some(active_ctrl_idx) = ui.active_ctrl_idx {
+            assert!(active_ctrl_idx < ui.tree.len());
+        }
+
+        fn check(
+            tree: &[CtrlNode],
+            ctrl_idx: usize,
+            parent_idx: Option<usize>,
+            current_frame: u32,
+            visited: &mut [bool],
+        ) {
+            let ctrl = &tree[ctrl_idx];
+
+            assert!(!visited[ctrl_idx], "Cycle found at {ctrl_idx}");
+            assert_eq!(ctrl.last_frame, current_frame);
+            assert_eq!(ctrl.parent_idx.map(CtrlIdx::get), parent_idx);
+
+            visited[ctrl_idx] = true;
+
+            let mut child_idx = ctrl.child_idx.map(CtrlIdx::get);
+            while let Some(idx) = child_idx {
+                check(tree, idx, Some(ctrl_idx), current_frame, visited);
+                child_idx = tree[idx].sibling_idx.map(CtrlIdx::get);
+            }
+        }
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    fn build_conditional_windows(ui: &mut Ui<Global>, mask: u8) {
+        let mut frame = ui.begin_frame();
+
+        for window in 0..4 {
+            if mask & (1 << window) == 0 {
+                continue;
+            }
+
+            let overlay = window == 3;
+            if overlay {
+                frame.begin_overlay();
+            }
+
+            frame.push_id(window);
+            frame.push_ctrl(line!());
+
+            for child in 0..2 {
+                frame.push_id(child);
+                let mut ctrl = frame.push_ctrl(line!());
+                if mask & 0x10 != 0 && window == 0 && child == 1 {
+                    ctrl.set_active(true);
+                }
+                frame.pop_ctrl();
+                frame.pop_id();
+            }
+
+            frame.pop_ctrl();
+            frame.pop_id();
+
+            if overlay {
+                frame.end_overlay();
+            }
+        }
+
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_conditional_window_shown_again() {
+        let mut ui = new_ui();
+
+        // X,Y -> Y -> X,Y
+        for mask in [0b11, 0b10, 0b11, 0b10, 0b11] {
+            build_conditional_windows(&mut ui, mask);
+            assert_tree_integrity(&ui);
+        }
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_active_ctrl_cleared_when_collected() {
+        let mut ui = new_ui();
+
+        build_conditional_windows(&mut ui, 0x10 | 0b11);
+        assert!(ui.active_ctrl_idx.is_some());
+
+        build_conditional_windows(&mut ui, 0b10);
+        assert_eq!(ui.active_ctrl_idx, None);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[quickcheck]
+    fn test_tree_integrity_with_conditional_windows(masks: Vec<u8>) -> bool {
+        let mut ui = new_ui();
+
+        for mask in masks {
+            build_conditional_windows(&mut ui, mask);
+            assert_tree_integrity(&ui);
+        }
+
+        true
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "Unpopped controls (ids as passed to push_ctrl): [1, 2]")]
+    fn test_missing_pop_ctrl_panics_with_unpopped_ids() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        frame.push_ctrl(1);
+        frame.push_ctrl(2);
+        frame.push_ctrl(3);
+        frame.pop_ctrl();
+        ui.end_frame();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "pop_ctrl without a matching push_ctrl in the overlay")]
+    fn test_extra_pop_ctrl_in_overlay_panics() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        frame.push_ctrl(1);
+        frame.begin_overlay();
+        frame.pop_ctrl();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_clip_rects_nest_and_clip_draw_primitives() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(line!());
+        ctrl.set_rect(Rect::new(10.0, 10.0, 100.0, 100.0));
+
+        ctrl.push_clip_rect(Rect::new(0.0, 0.0, 50.0, 20.0));
+        ctrl.push_clip_rect(Rect::new(40.0, 0.0, 50.0, 50.0));
+        ctrl.draw_rect(Rect::new(0.0, 0.0, 100.0, 100.0), Rect::ZERO, 0, 0);
+        ctrl.pop_clip_rect();
+        ctrl.draw_rect(Rect::new(0.0, 0.0, 100.0, 100.0), Rect::ZERO, 0, 0);
+        ctrl.pop_clip_rect();
+        ctrl.draw_rect(Rect::new(0.0, 0.0, 100.0, 100.0), Rect::ZERO, 0, 0);
+
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        let (commands, _, _) = ui.draw_list();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0].scissor_rect, Rect::new(50.0, 10.0, 10.0, 20.0));
+        assert_eq!(commands[1].scissor_rect, Rect::new(10.0, 10.0, 50.0, 20.0));
+        assert_eq!(
+            commands[2].scissor_rect,
+            Rect::new(10.0, 10.0, 100.0, 100.0)
+        );
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "Is there a pop_clip_rect for every push_clip_rect?")]
+    fn test_unpopped_clip_rect_panics() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(line!());
+        ctrl.push_clip_rect(Rect::new(0.0, 0.0, 10.0, 10.0));
+        frame.pop_ctrl();
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_overlay_ctrl_relinquishes_active_to_owner() {
+        let mut ui = new_ui();
+
+        for frame_idx in 0..3 {
+            let mut frame = ui.begin_frame();
+
+            let mut owner = frame.push_ctrl(line!());
+            owner.set_flags(CtrlFlags::CAPTURE_ACTIVE);
+            if frame_idx == 0 {
+                owner.set_active(true);
+            }
+
+            frame.begin_overlay();
+            let mut overlay_ctrl = frame.push_ctrl(line!());
+            match frame_idx {
+                1 => overlay_ctrl.set_active(true),
+                2 => overlay_ctrl.set_active(false),
+                _ => (),
+            }
+            frame.pop_ctrl();
+            frame.end_overlay();
+
+            frame.pop_ctrl();
+            ui.end_frame();
+        }
+
+        // The owner was the first control pushed, so it's right after the
+        // two roots.
+        assert_eq!(ui.active_ctrl_idx, Some(2));
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_disabled_ctrl_captures_hover_without_being_hovered() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+
+        let build = |ui: &mut Ui<Global>, disabled: bool| {
+            let mut frame = ui.begin_frame();
+
+            let mut parent = frame.push_ctrl(line!());
+            parent.set_flags(CtrlFlags::CAPTURE_HOVER);
+            parent.set_rect(Rect::new(0.0, 0.0, 200.0, 200.0));
+            let parent_hovered = parent.is_hovered();
+
+            frame.push_disabled(disabled);
+            let mut child = frame.push_ctrl(line!());
+            child.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+            child.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            child.set_active(true);
+            let child_hovered = child.is_hovered();
+            let child_active = child.is_active();
+            frame.pop_ctrl();
+            frame.pop_disabled();
+
+            frame.pop_ctrl();
+            ui.end_frame();
+
+            (parent_hovered, child_hovered, child_active)
+        };
+
+        build(&mut ui, false);
+        assert_eq!(build(&mut ui, false), (false, true, true));
+
+        // Neither the disabled child, nor the parent under it is hovered, and
+        // the child loses the active status.
+        assert_eq!(build(&mut ui, true), (false, false, false));
+        assert_eq!(ui.active_ctrl_idx, None);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_hovered_and_active_ctrl_id() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+
+        let build = |ui: &mut Ui<Global>, disabled: bool| {
+            let mut frame = ui.begin_frame();
+            frame.push_disabled(disabled);
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            ctrl.set_active(true);
+            let persistent_id = ctrl.persistent_id();
+            frame.pop_ctrl();
+
+            frame.pop_disabled();
+            ui.end_frame();
+
+            persistent_id
+        };
+
+        build(&mut ui, false);
+        let persistent_id = build(&mut ui, false);
+        assert_eq!(ui.hovered_ctrl_id(), Some(persistent_id));
+        assert_eq!(ui.active_ctrl_id(), Some(persistent_id));
+
+        build(&mut ui, true);
+        assert_eq!(ui.hovered_ctrl_id(), None);
+        assert_eq!(ui.active_ctrl_id(), None);
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_gc_keeps_siblings_contiguous_and_follows_active_ctrl() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>, skip: u32| {
+            let mut frame = ui.begin_frame();
+            let mut active_id = 0;
+
+            for parent_id in 0..3 {
+                if parent_id == skip {
+                    continue;
+                }
+
+                frame.push_ctrl(parent_id);
+                for child_id in 0..3 {
+                    let mut ctrl = frame.push_ctrl(child_id);
+                    ctrl.set_flags(CtrlFlags::CAPTURE_ACTIVE);
+                    if parent_id == 2 && child_id == 1 {
+                        ctrl.set_active(true);
+                        active_id = ctrl.persistent_id();
+                    }
+                    frame.pop_ctrl();
+                }
+                frame.pop_ctrl();
+            }
+
+            ui.end_frame();
+
+            active_id
+        };
+
+        build(&mut ui, u32::MAX);
+        let active_id = build(&mut ui, 0);
+        assert_eq!(ui.active_ctrl_id(), Some(active_id));
+        assert_eq!(ui.tree.len(), 2 + 2 + 2 * 3);
+
+        // Every control's children are stored next to each other.
+        for ctrl in &ui.tree {
+            let mut child_idx = ctrl.child_idx.map(CtrlIdx::get);
+            while let Some(idx) = child_idx {
+                let sibling_idx = ui.tree[idx].sibling_idx.map(CtrlIdx::get);
+                if let Some(sibling_idx) = sibling_idx {
+                    assert_eq!(sibling_idx, idx + 1);
+                }
+
+                child_idx = sibling_idx;
+            }
+        }
+    }
+
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_drag_and_drop() {
+        let mut ui = new_ui();
+
+        // Returns whether the source is dragging, the payload being dragged,
+        // and what was dropped on the target.
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+
+            let mut source = frame.push_ctrl(0);
+            source.set_flags(CtrlFlags::CAPTURE_HOVER);
+            source.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            let dragging = source.drag_source(7);
+            frame.pop_ctrl();
+
+            let mut target = frame.push_ctrl(1);
+            target.set_flags(CtrlFlags::CAPTURE_HOVER);
+            target.set_rect(Rect::new(200.0, 0.0, 100.0, 100.0));
+            let dropped = target.drop_target();
+            frame.pop_ctrl();
+
+            let payload = frame.dragging_payload();
+            ui.end_frame();
+
+            (dragging, payload, dropped)
+        };
+
+        ui.set_cursor_position(50.0, 50.0);
+        build(&mut ui);
+
+        // Clicking without moving is not a drag.
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert_eq!(build(&mut ui), (false, None, None));
+        ui.set_cursor_position(52.0, 50.0);
+        assert_eq!(build(&mut ui), (false, None, None));
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert_eq!(build(&mut ui), (false, None, None));
+
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(&mut ui);
+        ui.set_cursor_position(250.0, 50.0);
+        assert_eq!(build(&mut ui), (true, Some(7), None));
+        assert!(ui.want_capture_mouse());
+
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert_eq!(build(&mut ui), (true, Some(7), Some(7)));
+        assert_eq!(build(&mut ui), (false, None, None));
     }
 
-    pub fn begin_overlay(&mut self) {
-        assert!(!self.ui.building_overlay);
-
-        mem::swap(
-            &mut self.ui.build_parent_idx,
-            &mut self.ui.overlay_build_parent_idx,
-        );
-        mem::swap(
-            &mut self.ui.build_sibling_idx,
-            &mut self.ui.overlay_build_sibling_idx,
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_temp_allocator_takes_per_frame_allocations() {
+        let allocation_count = Rc::new(Cell::new(0));
+        let temp_allocation_count = Rc::new(Cell::new(0));
+        // Borrowed, so that the temp allocator has a different type than the
+        // main one, like a bump allocator reset by the host would.
+        let temp_allocator = CountingAllocator(temp_allocation_count.clone());
+
+        let mut ui = Ui::new_in_with_temp_allocator(
+            800.0,
+            600.0,
+            1.0,
+            FontSource::ttf(FONT, UnicodeRangeFlags::ALL_LATIN, 13.0, 1.0),
+            CountingAllocator(allocation_count.clone()),
+            &temp_allocator,
         );
 
-        self.ui.building_overlay = true;
-    }
+        let build = |ui: &mut Ui<CountingAllocator, &CountingAllocator>| {
+            let mut frame = ui.begin_frame();
+            for id in 0..3 {
+                let mut ctrl = frame.push_ctrl(id);
+                ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+                ctrl.draw_text(
+                    "Hello",
+                    Align::Start,
+                    Align::Start,
+                    Wrap::Word,
+                    Direction::Ltr,
+                    0xffffffff,
+                );
+                frame.pop_ctrl();
+            }
+            // Splits its text into lines in the temp allocator.
+            crate::widgets::selectable_text(&mut frame, 3, "Hello");
+            ui.end_frame();
+        };
 
-    pub fn end_overlay(&mut self) {
-        assert!(self.ui.building_overlay);
+        build(&mut ui);
+        build(&mut ui);
 
-        mem::swap(
-            &mut self.ui.build_parent_idx,
-            &mut self.ui.overlay_build_parent_idx,
-        );
-        mem::swap(
-            &mut self.ui.build_sibling_idx,
-            &mut self.ui.overlay_build_sibling_idx,
-        );
+        let allocations_before = allocation_count.get();
+        let temp_allocations_before = temp_allocation_count.get();
+        build(&mut ui);
 
-        self.ui.building_overlay = false;
+        assert_eq!(allocation_count.get(), allocations_before);
+        assert!(temp_allocation_count.get() > temp_allocations_before);
     }
 
-    pub fn font_atlas(&self) -> &FontAtlas<A> {
-        &self.ui.font_atlas
-    }
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_steady_frames_dont_allocate() {
+        let allocation_count = Rc::new(Cell::new(0));
+        let mut ui = Ui::new_in(
+            800.0,
+            600.0,
+            1.0,
+            FontSource::ttf(FONT, UnicodeRangeFlags::ALL_LATIN, 13.0, 1.0),
+            CountingAllocator(allocation_count.clone()),
+        );
+        ui.set_cursor_position(50.0, 50.0);
+
+        // Nested free layouts sort their children for hover and render, and
+        // the text wraps into several lines.
+        let build = |ui: &mut Ui<CountingAllocator>| {
+            let mut frame = ui.begin_frame();
+            for id in 0..3 {
+                let mut ctrl = frame.push_ctrl(id);
+                ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+                ctrl.set_layout(Layout::Free);
+                ctrl.set_rect(Rect::new(0.0, 0.0, 200.0, 200.0));
+
+                for child_id in 0..3 {
+                    let mut ctrl = frame.push_ctrl(child_id);
+                    ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+                    ctrl.set_rect(Rect::new(0.0, 0.0, 50.0, 100.0));
+                    ctrl.draw_text(
+                        "Hello there, General Kenobi",
+                        Align::Start,
+                        Align::Start,
+                        Wrap::Word,
+                        Direction::Ltr,
+                        0xffffffff,
+                    );
+                    frame.pop_ctrl();
+                }
 
-    pub fn font_atlas_texture_id(&self) -> u64 {
-        self.ui.font_atlas_texture_id
+                frame.pop_ctrl();
+            }
+            ui.end_frame();
+        };
+
+        build(&mut ui);
+        build(&mut ui);
+
+        let allocations_before = allocation_count.get();
+        for _ in 0..3 {
+            build(&mut ui);
+        }
+
+        assert_eq!(allocation_count.get(), allocations_before);
+        assert!(ui.hovered_capturing_ctrl_idx.is_some());
     }
 
-    pub fn window_size(&self) -> Vec2 {
-        self.ui.window_size
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_draw_cache_reuses_geometry_until_something_changes() {
+        type DrawListVecs = (Vec<Command>, Vec<Vertex>, Vec<u32>);
+
+        fn build(ui: &mut Ui<Global>, cache_draw: bool, text: &str) -> DrawListVecs {
+            let mut frame = ui.begin_frame();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 20.0));
+            ctrl.set_draw_self(true);
+            ctrl.set_draw_self_background_color(0xff0000ff);
+            frame.pop_ctrl();
+
+            let mut ctrl = frame.push_ctrl(1);
+            if cache_draw {
+                ctrl.set_flags(CtrlFlags::CACHE_DRAW);
+            }
+            ctrl.set_layout(Layout::Vertical);
+            ctrl.set_rect(Rect::new(0.0, 50.0, 200.0, 200.0));
+            ctrl.draw_circle(Vec2::new(50.0, 50.0), 20.0, 0xffffffff, 0);
+            for id in 0..3 {
+                let mut ctrl = frame.push_ctrl(id);
+                ctrl.set_rect(Rect::new(0.0, 0.0, 200.0, 20.0));
+                ctrl.draw_text(
+                    text,
+                    Align::Start,
+                    Align::Center,
+                    Wrap::Word,
+                    Direction::Ltr,
+                    0xffffffff,
+                );
+                frame.pop_ctrl();
+            }
+            frame.pop_ctrl();
+
+            ui.end_frame();
+
+            let (commands, vertices, indices) = ui.draw_list();
+            (commands.to_vec(), vertices.to_vec(), indices.to_vec())
+        }
+
+        let mut ui = new_ui();
+        let mut uncached_ui = new_ui();
+        uncached_ui.set_anti_aliasing(true);
+        ui.set_anti_aliasing(true);
+
+        let draw_list = build(&mut ui, true, "Hello");
+        assert_eq!(ui.frame_stats().draw_cache_hit_count, 0);
+        assert_eq!(ui.frame_stats().draw_cache_miss_count, 1);
+
+        assert_eq!(build(&mut ui, true, "Hello"), draw_list);
+        assert_eq!(ui.frame_stats().draw_cache_hit_count, 1);
+        assert_eq!(ui.frame_stats().draw_cache_miss_count, 0);
+
+        build(&mut uncached_ui, false, "Hello");
+        assert_eq!(build(&mut uncached_ui, false, "Hello"), draw_list);
+
+        // Changing the contents of a descendant invalidates the cache.
+        let draw_list = build(&mut ui, true, "Hello there");
+        assert_eq!(ui.frame_stats().draw_cache_miss_count, 1);
+        assert_eq!(build(&mut uncached_ui, false, "Hello there"), draw_list);
+
+        assert_eq!(build(&mut ui, true, "Hello there"), draw_list);
+        assert_eq!(ui.frame_stats().draw_cache_hit_count, 1);
     }
 
-    pub fn cursor_position(&self) -> Vec2 {
-        self.ui.cursor_position
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_draw_image_uvs_flip_and_sampling() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+        ctrl.set_padding(0.0);
+        ctrl.set_border(0.0);
+        ctrl.set_margin(0.0);
+        ctrl.draw_image(Rect::new(0.0, 0.0, 10.0, 10.0), 1);
+        ctrl.draw_image_uv_px(
+            Rect::new(0.0, 0.0, 10.0, 10.0),
+            1,
+            Vec2::new(64.0, 32.0),
+            Rect::new(16.0, 8.0, 32.0, 16.0),
+        );
+        ctrl.draw_image_with_options(Rect::new(0.0, 0.0, 10.0, 10.0), 1, &ImageOptions {
+            tint: 0xff0000ff,
+            flip_y: true,
+            sampling: Sampling::NEAREST,
+            ..ImageOptions::DEFAULT
+        });
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        let (commands, vertices, _) = ui.draw_list();
+
+        // The first two images share a command, the nearest one can't.
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].sampling, Sampling::LINEAR);
+        assert_eq!(commands[0].index_count, 12);
+        assert_eq!(commands[1].sampling, Sampling::NEAREST);
+
+        // Vertices go bottom-left, bottom-right, top-right, top-left.
+        assert_eq!(vertices[3].tex_coord, [0.0, 0.0]);
+        assert_eq!(vertices[1].tex_coord, [1.0, 1.0]);
+        assert_eq!(vertices[4].color, 0xffffffff);
+        assert_eq!(vertices[7].tex_coord, [0.25, 0.25]);
+        assert_eq!(vertices[5].tex_coord, [0.75, 0.75]);
+        assert_eq!(vertices[8].color, 0xff0000ff);
+        assert_eq!(vertices[11].tex_coord, [0.0, 1.0]);
+        assert_eq!(vertices[9].tex_coord, [1.0, 0.0]);
     }
 
-    pub fn last_ctrl_is_hovered(&self) -> bool {
-        self.ui.last_ctrl_idx == self.ui.hovered_capturing_ctrl_idx
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_draw_rect_nine_slice_keeps_corners() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 200.0, 200.0));
+        ctrl.set_padding(0.0);
+        ctrl.set_border(0.0);
+        ctrl.set_margin(0.0);
+        ctrl.draw_rect_nine_slice(
+            Rect::new(0.0, 0.0, 100.0, 50.0),
+            Rect::ONE,
+            0xffffffff,
+            1,
+            10.0,
+            Vec2::new(0.25, 0.25),
+        );
+        // Too small for its borders, so only the corners remain.
+        ctrl.draw_rect_nine_slice(
+            Rect::new(0.0, 100.0, 10.0, 10.0),
+            Rect::ONE,
+            0xffffffff,
+            1,
+            10.0,
+            Vec2::new(0.25, 0.25),
+        );
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        let (_, vertices, _) = ui.draw_list();
+        assert_eq!(vertices.len(), (9 + 4) * 4);
+
+        // Vertices go bottom-left, bottom-right, top-right, top-left. The
+        // top-left corner, the center and the bottom-right corner.
+        assert_eq!(vertices[3].position, [0.0, 0.0]);
+        assert_eq!(vertices[1].position, [10.0, 10.0]);
+        assert_eq!(vertices[1].tex_coord, [0.25, 0.25]);
+        assert_eq!(vertices[4 * 4 + 3].position, [10.0, 10.0]);
+        assert_eq!(vertices[4 * 4 + 1].position, [90.0, 40.0]);
+        assert_eq!(vertices[4 * 4 + 1].tex_coord, [0.75, 0.75]);
+        assert_eq!(vertices[8 * 4 + 3].position, [90.0, 40.0]);
+        assert_eq!(vertices[8 * 4 + 3].tex_coord, [0.75, 0.75]);
+        assert_eq!(vertices[8 * 4 + 1].position, [100.0, 50.0]);
+
+        assert_eq!(vertices[9 * 4 + 1].position, [5.0, 105.0]);
+        assert_eq!(vertices[9 * 4 + 1].tex_coord, [0.25, 0.25]);
     }
 
-    pub fn last_ctrl_is_active(&self) -> bool {
-        self.ui.last_ctrl_idx == self.ui.active_ctrl_idx
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_draw_callback_is_clipped_and_survives_draw_cache() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CACHE_DRAW);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(0.0);
+            ctrl.set_draw_self(true);
+            ctrl.set_draw_self_background_color(0xff0000ff);
+            ctrl.draw_callback(7, Rect::new(50.0, 50.0, 100.0, 100.0));
+            ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+            // Entirely clipped, so it never reaches the renderer.
+            ctrl.draw_callback(8, Rect::new(200.0, 0.0, 10.0, 10.0));
+            frame.pop_ctrl();
+
+            ui.end_frame();
+
+            ui.draw_list().0.to_vec()
+        };
+
+        let commands = build(&mut ui);
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0].kind, CommandKind::DRAW);
+        assert_eq!(commands[1].kind, CommandKind::CALLBACK);
+        assert_eq!(commands[1].callback_user_id, 7);
+        assert_eq!(commands[1].scissor_rect, Rect::new(50.0, 50.0, 50.0, 50.0));
+        assert_eq!(commands[2].kind, CommandKind::DRAW);
+
+        assert_eq!(build(&mut ui), commands);
+        assert_eq!(ui.frame_stats().draw_cache_hit_count, 1);
     }
 
-    pub fn last_ctrl_is_new(&self) -> bool {
-        if let Some(last_ctrl_idx) = self.ui.last_ctrl_idx {
-            self.ui.tree[last_ctrl_idx].first_frame == self.ui.current_frame
-        } else {
-            false
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_no_clip_ctrl_and_descendants_overflow_parent() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut parent = frame.push_ctrl(0);
+        parent.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+        parent.set_padding(0.0);
+        parent.set_border(0.0);
+        parent.set_margin(0.0);
+
+        for (id, flags) in [(1, CtrlFlags::NONE), (2, CtrlFlags::NO_CLIP)] {
+            let mut ctrl = frame.push_ctrl(id);
+            ctrl.set_flags(flags);
+            ctrl.set_rect(Rect::new(10.0, 10.0, 50.0, 50.0));
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(0.0);
+            ctrl.draw_callback(u64::from(id), Rect::new(40.0, 40.0, 100.0, 100.0));
+
+            let mut child = frame.push_ctrl(0);
+            child.set_rect(Rect::new(40.0, 40.0, 100.0, 100.0));
+            child.draw_callback(u64::from(10 + id), Rect::new(0.0, 0.0, 100.0, 100.0));
+            frame.pop_ctrl();
+
+            frame.pop_ctrl();
         }
+
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        let (commands, _, _) = ui.draw_list();
+        let scissor_rect = |user_id: u64| {
+            commands
+                .iter()
+                .find(|command| {
+                    command.kind == CommandKind::CALLBACK && command.callback_user_id == user_id
+                })
+                .map(|command| command.scissor_rect)
+        };
+
+        // The clipped control and its child only get the part inside the
+        // control, but children of the no-clip control are clipped just to
+        // themselves.
+        assert_eq!(scissor_rect(1), Some(Rect::new(50.0, 50.0, 10.0, 10.0)));
+        assert_eq!(scissor_rect(11), Some(Rect::new(50.0, 50.0, 10.0, 10.0)));
+        assert_eq!(scissor_rect(2), Some(Rect::new(50.0, 50.0, 100.0, 100.0)));
+        assert_eq!(scissor_rect(12), Some(Rect::new(50.0, 50.0, 100.0, 100.0)));
     }
 
-    pub fn inputs_pressed(&self) -> Inputs {
-        self.ui.inputs_pressed
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_toasts_show_in_top_right_corner_and_fade_out() {
+        let mut ui = new_ui();
+        let theme = Theme::DEFAULT;
+
+        let mut build = |delta_time: f32, text: Option<&str>| {
+            let mut frame = ui.begin_frame_with_delta_time(delta_time);
+            if let Some(text) = text {
+                frame.toast(text, ToastKind::Info);
+            }
+            ui.end_frame();
+
+            let (_, vertices, _) = ui.draw_list();
+            vertices.to_vec()
+        };
+
+        // The border is drawn first, inset from the window edge by the margin.
+        let vertices = build(0.0, Some("Saved!"));
+        let right = vertices.iter().map(|v| v.position[0]).fold(0.0, f32::max);
+        let left = right - theme.toast_width;
+        assert_eq!(right, 800.0 - theme.toast_margin);
+        assert_eq!(vertices[3].position, [left, theme.toast_margin]);
+        assert_eq!(vertices[0].color, theme.toast_border_color);
+
+        // Halfway through the fade, the alpha is halved.
+        let vertices = build(theme.toast_duration + 0.5 * theme.toast_fade_duration, None);
+        assert_eq!(
+            vertices[0].color,
+            color_multiply_alpha(theme.toast_border_color, 0.5),
+        );
+
+        let vertices = build(0.5 * theme.toast_fade_duration, None);
+        assert!(vertices.is_empty());
     }
 
-    pub fn inputs_released(&self) -> Inputs {
-        self.ui.inputs_released
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_fill_takes_space_left_by_siblings() {
+        let mut ui = new_ui();
+
+        let push = |frame: &mut Frame<Global>, id, flags, layout, rect, margin| {
+            let mut ctrl = frame.push_ctrl(id);
+            ctrl.set_flags(flags);
+            ctrl.set_layout(layout);
+            ctrl.set_rect(rect);
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(margin);
+
+            ctrl.persistent_id()
+        };
+
+        let mut frame = ui.begin_frame();
+        let rect = Rect::new(0.0, 0.0, 400.0, 100.0);
+        push(
+            &mut frame,
+            0,
+            CtrlFlags::NONE,
+            Layout::Horizontal,
+            rect,
+            0.0,
+        );
+
+        let rect = Rect::new(0.0, 0.0, 100.0, 10.0);
+        let fixed = push(&mut frame, 0, CtrlFlags::NONE, Layout::Free, rect, 0.0);
+        frame.pop_ctrl();
+
+        // Fills the left over width with its sibling, and the whole height, in
+        // which its child fills what's left below a fixed one.
+        let flags = CtrlFlags::FILL_HORIZONTAL | CtrlFlags::FILL_VERTICAL;
+        let fill = push(&mut frame, 1, flags, Layout::Vertical, Rect::ZERO, 5.0);
+        let rect = Rect::new(0.0, 0.0, 10.0, 30.0);
+        push(&mut frame, 0, CtrlFlags::NONE, Layout::Free, rect, 0.0);
+        frame.pop_ctrl();
+        let nested_fill = push(&mut frame, 1, flags, Layout::Free, Rect::ZERO, 0.0);
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+
+        // Resizing to fit, so filled by its child in that direction.
+        let flags = CtrlFlags::FILL_HORIZONTAL | CtrlFlags::RESIZE_TO_FIT_VERTICAL;
+        let fill_and_fit = push(&mut frame, 2, flags, Layout::Horizontal, Rect::ZERO, 0.0);
+        let rect = Rect::new(0.0, 0.0, 10.0, 30.0);
+        push(&mut frame, 0, CtrlFlags::NONE, Layout::Free, rect, 0.0);
+        frame.pop_ctrl();
+        let flags = CtrlFlags::FILL_VERTICAL;
+        let fill_in_fit = push(&mut frame, 1, flags, Layout::Free, Rect::ZERO, 0.0);
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        let frame = ui.begin_frame();
+        let rect = |persistent_id| frame.ctrl_layout(persistent_id).unwrap().rect;
+        let absolute_position =
+            |persistent_id| frame.ctrl_layout(persistent_id).unwrap().absolute_position;
+
+        assert_eq!(rect(fixed).size(), Vec2::new(100.0, 10.0));
+        assert_eq!(rect(fill).size(), Vec2::new(140.0, 90.0));
+        assert_eq!(absolute_position(fill), Vec2::new(105.0, 5.0));
+        assert_eq!(rect(nested_fill).size(), Vec2::new(140.0, 60.0));
+        assert_eq!(absolute_position(nested_fill), Vec2::new(105.0, 35.0));
+        assert_eq!(rect(fill_and_fit).size(), Vec2::new(150.0, 30.0));
+        assert_eq!(absolute_position(fill_and_fit), Vec2::new(250.0, 0.0));
+        assert_eq!(rect(fill_in_fit).size(), Vec2::new(0.0, 30.0));
     }
 
-    pub fn modifiers(&self) -> Modifiers {
-        self.ui.modifiers
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_aspect_ratio_contains_or_covers_available_space() {
+        let mut ui = new_ui();
+
+        let mut build = |aspect_ratio, fit, flags| {
+            let mut frame = ui.begin_frame();
+            let mut parent = frame.push_ctrl(0);
+            parent.set_layout(Layout::Vertical);
+            parent.set_rect(Rect::new(0.0, 0.0, 400.0, 300.0));
+            parent.set_padding(0.0);
+            parent.set_border(0.0);
+            parent.set_margin(0.0);
+            let parent_id = parent.persistent_id();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(flags);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 400.0, 10.0));
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(0.0);
+            ctrl.set_aspect_ratio_with_fit(aspect_ratio, fit);
+            let ctrl_id = ctrl.persistent_id();
+            frame.pop_ctrl();
+
+            frame.pop_ctrl();
+            ui.end_frame();
+
+            let frame = ui.begin_frame();
+            let rect = frame.ctrl_layout(ctrl_id).unwrap().rect;
+            let parent_content_size = frame.ctrl_layout(parent_id).unwrap().content_size;
+            ui.end_frame();
+
+            (rect.size(), parent_content_size)
+        };
+
+        // Fits the width, then the height.
+        let (size, _) = build(2.0, AspectFit::Contain, CtrlFlags::NONE);
+        assert_eq!(size, Vec2::new(400.0, 200.0));
+        let (size, _) = build(1.0, AspectFit::Contain, CtrlFlags::NONE);
+        assert_eq!(size, Vec2::new(300.0, 300.0));
+
+        // Covering overflows the parent, which shows in its content size.
+        let (size, parent_content_size) = build(2.0, AspectFit::Cover, CtrlFlags::NONE);
+        assert_eq!(size, Vec2::new(600.0, 300.0));
+        assert_eq!(parent_content_size, Vec2::new(600.0, 300.0));
+
+        // Applied after filling.
+        let (size, _) = build(4.0, AspectFit::Contain, CtrlFlags::FILL_HORIZONTAL);
+        assert_eq!(size, Vec2::new(400.0, 100.0));
     }
 
-    pub fn received_characters(&self) -> &str {
-        &self.ui.received_characters
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_draw_cache_invalidated_by_layout_and_collected_with_ctrl() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>, y: f32, cached: bool| {
+            let mut frame = ui.begin_frame();
+            if cached {
+                let mut ctrl = frame.push_ctrl(0);
+                ctrl.set_flags(CtrlFlags::CACHE_DRAW);
+                ctrl.set_rect(Rect::new(0.0, y, 100.0, 100.0));
+                ctrl.draw_rect(Rect::new(0.0, 0.0, 50.0, 50.0), Rect::ZERO, 0xffffffff, 0);
+                frame.pop_ctrl();
+            }
+            ui.end_frame();
+
+            ui.draw_list().1.to_vec()
+        };
+
+        let vertices = build(&mut ui, 0.0, true);
+        build(&mut ui, 0.0, true);
+        assert_eq!(ui.frame_stats().draw_cache_hit_count, 1);
+
+        // Moving the control moves what it draws.
+        let moved_vertices = build(&mut ui, 10.0, true);
+        assert_eq!(ui.frame_stats().draw_cache_miss_count, 1);
+        assert_eq!(
+            moved_vertices[0].position[1],
+            vertices[0].position[1] + 10.0
+        );
+
+        build(&mut ui, 10.0, false);
+        assert!(ui.draw_caches.entries.is_empty());
     }
 
-    pub fn ctrl_state(&self) -> &CtrlState {
-        &self.ui.tree[self.ui.build_parent_idx.unwrap()].state
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_frame_stats() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>, with_text: bool| {
+            let mut frame = ui.begin_frame();
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+            frame.pop_ctrl();
+
+            if with_text {
+                let mut ctrl = frame.push_ctrl(1);
+                ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+                ctrl.draw_text(
+                    "abc",
+                    Align::Start,
+                    Align::Start,
+                    Wrap::None,
+                    Direction::Ltr,
+                    0xffffffff,
+                );
+                frame.pop_ctrl();
+            }
+
+            ui.end_frame();
+            ui.frame_stats()
+        };
+
+        let stats = build(&mut ui, true);
+        assert_eq!(stats.ctrl_count, 4);
+        assert_eq!(stats.collected_ctrl_count, 0);
+        assert_eq!(stats.draw_primitive_count, 4);
+        assert_eq!(stats.glyph_count, 3);
+        assert_eq!(stats.vertex_count, ui.draw_list().1.len());
+        assert_eq!(stats.index_count, ui.draw_list().2.len());
+
+        let stats = build(&mut ui, false);
+        assert_eq!(stats.ctrl_count, 3);
+        assert_eq!(stats.collected_ctrl_count, 1);
+        assert_eq!(stats.draw_primitive_count, 1);
+        assert_eq!(stats.glyph_count, 0);
+        assert_eq!(stats.command_count, ui.draw_list().0.len());
     }
 
-    pub fn ctrl_state_mut(&mut self) -> &mut CtrlState {
-        &mut self.ui.tree[self.ui.build_parent_idx.unwrap()].state
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_debug_overlay_draws_only_when_enabled() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            frame.pop_ctrl();
+            ui.end_frame();
+
+            ui.draw_list().1.len()
+        };
+
+        build(&mut ui);
+        let vertex_count = build(&mut ui);
+
+        ui.set_debug_overlay(true);
+        let outlined_vertex_count = build(&mut ui);
+        assert!(outlined_vertex_count > vertex_count);
+
+        ui.press_modifiers(Modifiers::CTRL | Modifiers::SHIFT);
+        assert!(build(&mut ui) > outlined_vertex_count);
+        ui.release_modifiers(Modifiers::CTRL | Modifiers::SHIFT);
+
+        ui.set_debug_overlay(false);
+        assert_eq!(build(&mut ui), vertex_count);
     }
 
-    pub fn ctrl_absolute_position(&self) -> Vec2 {
-        self.ui.tree[self.ui.build_parent_idx.unwrap()].layout_cache_absolute_position
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_debug_ctrls_walks_layers_depth_first() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_layout(Layout::Vertical);
+        ctrl.set_rect(Rect::new(10.0, 10.0, 200.0, 200.0));
+        {
+            let mut ctrl = frame.push_ctrl(1);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 50.0, 20.0));
+            ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+            frame.pop_ctrl();
+
+            frame.push_ctrl(2);
+            let mut ctrl = frame.push_ctrl(3);
+            ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+            ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+            frame.pop_ctrl();
+            frame.pop_ctrl();
+        }
+        frame.pop_ctrl();
+
+        frame.begin_overlay();
+        frame.push_ctrl(4);
+        frame.pop_ctrl();
+        frame.end_overlay();
+        ui.end_frame();
+
+        let mut walked: Vec<(u32, usize, bool)> = Vec::new();
+        for ctrl in ui.debug_ctrls() {
+            walked.push((ctrl.id, ctrl.depth, ctrl.overlay));
+        }
+
+        // Subtrees are only counted with the debug overlay.
+        assert!(ui
+            .debug_ctrls()
+            .all(|ctrl| ctrl.subtree_draw_primitive_count == 0));
+
+        // Without any id namespaces pushed, ids are mixed with zero.
+        for (id, ..) in &mut walked {
+            *id = (0..5).find(|&local| mix_id(0, local) == *id).unwrap();
+        }
+
+        assert_eq!(walked, [
+            (0, 0, false),
+            (1, 1, false),
+            (2, 1, false),
+            (3, 2, false),
+            (4, 0, true),
+        ],);
+
+        let ctrl = ui.debug_ctrls().nth(1).unwrap();
+        assert_eq!(ctrl.layout, Layout::Free);
+        assert_eq!(ctrl.draw_primitive_count, 1);
+        assert_eq!(ctrl.absolute_rect.width, 50.0);
+        assert_eq!(ctrl.absolute_rect.x, 10.0);
     }
 
-    pub fn ctrl_inner_size(&self) -> Vec2 {
-        let build_parent_idx = self.ui.build_parent_idx.unwrap();
-        let parent = &self.ui.tree[build_parent_idx];
-        let rect = parent.rect.inset(parent.border + parent.padding);
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_debug_overlay_counts_subtree_draw_primitives() {
+        let mut ui = new_ui();
+        ui.set_debug_overlay(true);
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+        for id in 1..3 {
+            let mut ctrl = frame.push_ctrl(id);
+            ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+            ctrl.draw_rect(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::ZERO, 0xffffffff, 0);
+            frame.pop_ctrl();
+        }
+        frame.pop_ctrl();
+        ui.end_frame();
 
-        rect.size()
+        let mut counts: Vec<(usize, usize)> = Vec::new();
+        for ctrl in ui.debug_ctrls() {
+            counts.push((ctrl.draw_primitive_count, ctrl.subtree_draw_primitive_count));
+        }
+
+        assert_eq!(counts, [(1, 5), (2, 2), (2, 2)]);
     }
 
-    pub fn ctrl_count(&self) -> usize {
-        self.ui.ctrl_count()
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_push_disabled_nests() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        assert!(!frame.is_disabled());
+        frame.push_disabled(true);
+        frame.push_disabled(false);
+        assert!(frame.is_disabled());
+        frame.pop_disabled();
+        frame.pop_disabled();
+        assert!(!frame.is_disabled());
+        ui.end_frame();
     }
 
-    pub fn allocator(&self) -> &A {
-        &self.ui.allocator
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "Is there a pop_disabled for every push_disabled?")]
+    fn test_unpopped_disabled_panics() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        frame.push_disabled(false);
+        ui.end_frame();
     }
-}
 
-pub struct Ctrl<'a, A: Allocator + Clone> {
-    idx: usize,
-    ui: &'a mut Ui<A>,
-}
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_hover_transition_advances_with_delta_time() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+
+        let build = |ui: &mut Ui<Global>, animation_speed: f32| {
+            let mut frame = ui.begin_frame_with_delta_time(0.25);
+            let mut ctrl = frame.push_ctrl(line!());
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            let hover_t = ctrl.hover_t(animation_speed);
+            // Querying again in the same frame doesn't advance it.
+            assert_eq!(ctrl.hover_t(animation_speed), hover_t);
+            let active_t = ctrl.active_t(animation_speed);
+            frame.pop_ctrl();
+            ui.end_frame();
+
+            (hover_t, active_t)
+        };
 
-impl<'a, A: Allocator + Clone> Ctrl<'a, A> {
-    pub fn set_active(&mut self, active: bool) {
-        if active {
-            self.ui.active_ctrl_idx = Some(self.idx);
+        // The first frame lays out the control, so that it can be hovered.
+        assert_eq!(build(&mut ui, 2.0), (0.0, 0.0));
+        assert_eq!(build(&mut ui, 2.0), (0.5, 0.0));
+        assert_eq!(build(&mut ui, 2.0), (1.0, 0.0));
+        assert_eq!(build(&mut ui, 2.0), (1.0, 0.0));
 
-            let mut ctrl = &mut self.ui.tree[self.idx];
-            ctrl.last_frame_in_active_path = self.ui.current_frame;
+        ui.set_cursor_position(500.0, 500.0);
+        assert_eq!(build(&mut ui, 2.0), (0.5, 0.0));
 
-            while let Some(ctrl_idx) = ctrl.parent_idx {
-                ctrl = &mut self.ui.tree[ctrl_idx];
-                ctrl.last_frame_in_active_path = self.ui.current_frame;
-            }
-        } else if let Some(active_ctrl_idx) = self.ui.active_ctrl_idx {
-            if active_ctrl_idx == self.idx {
-                // If this was the active control, it relinquishes the active
-                // status the the first control up the tree that wants to
-                // capture it. When that happens, the capturing control and all
-                // its parents get their last_frame_in_active_path updated.
+        // Zero speed snaps to the current state.
+        ui.set_cursor_position(50.0, 50.0);
+        assert_eq!(build(&mut ui, 0.0), (1.0, 0.0));
+        assert_eq!(ui.ctrl_animations.len(), 1);
 
-                let current_ctrl = &self.ui.tree[self.idx];
+        // Animations are collected with their controls.
+        ui.begin_frame();
+        ui.end_frame();
+        assert!(ui.ctrl_animations.is_empty());
+    }
 
-                if let Some(parent_idx) = current_ctrl.parent_idx {
-                    let mut ctrl_idx = parent_idx;
-                    let mut ctrl = &mut self.ui.tree[parent_idx];
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_animate_eases_towards_target() {
+        let mut ui = new_ui();
 
-                    while !ctrl.flags.intersects(CtrlFlags::CAPTURE_ACTIVE)
-                        && ctrl.parent_idx.is_some()
-                    {
-                        ctrl_idx = ctrl.parent_idx.unwrap();
-                        ctrl = &mut self.ui.tree[ctrl_idx];
-                    }
+        let build = |ui: &mut Ui<Global>, target: f32, easing: Easing| {
+            let mut frame = ui.begin_frame_with_delta_time(0.25);
+            let value = frame.animate_with_easing(1, target, 2.0, easing);
+            // Querying again in the same frame doesn't advance it.
+            assert_eq!(frame.animate_with_easing(1, target, 2.0, easing), value);
+            ui.end_frame();
 
-                    if ctrl.flags.intersects(CtrlFlags::CAPTURE_ACTIVE) {
-                        self.ui.active_ctrl_idx = Some(ctrl_idx);
+            value
+        };
 
-                        ctrl.last_frame_in_active_path = self.ui.current_frame;
+        assert_eq!(build(&mut ui, 0.0, Easing::Linear), 0.0);
+        assert_eq!(build(&mut ui, 10.0, Easing::Linear), 5.0);
+        assert_eq!(build(&mut ui, 10.0, Easing::Linear), 10.0);
+        assert_eq!(build(&mut ui, 10.0, Easing::Linear), 10.0);
 
-                        while let Some(ctrl_idx) = ctrl.parent_idx {
-                            ctrl = &mut self.ui.tree[ctrl_idx];
-                            ctrl.last_frame_in_active_path = self.ui.current_frame
-                        }
-                    } else {
-                        self.ui.active_ctrl_idx = None;
-                    }
-                }
-            }
-        }
-    }
+        assert_eq!(build(&mut ui, 2.0, Easing::EaseOut), 3.0);
+        assert_eq!(build(&mut ui, 2.0, Easing::EaseOut), 2.0);
 
-    pub fn set_flags(&mut self, flags: CtrlFlags) {
-        self.ui.tree[self.idx].flags = flags;
+        // Retargeting midway starts from the current value.
+        assert_eq!(build(&mut ui, 6.0, Easing::Linear), 4.0);
+        assert_eq!(build(&mut ui, 0.0, Easing::Linear), 2.0);
+        assert_eq!(build(&mut ui, 0.0, Easing::Linear), 0.0);
     }
 
-    pub fn set_layout(&mut self, layout: Layout) {
-        self.ui.tree[self.idx].layout = layout;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_animate_snaps_without_delta_time_and_is_collected() {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame_with_delta_time(0.25);
+        assert_eq!(frame.animate(1, 0.0, 2.0), 0.0);
+        ui.end_frame();
+
+        let mut frame = ui.begin_frame();
+        assert_eq!(frame.animate(1, 1.0, 2.0), 1.0);
+        ui.end_frame();
+
+        // Ids are scoped to the current control.
+        let mut frame = ui.begin_frame_with_delta_time(0.25);
+        assert_eq!(frame.animate(1, 1.0, 2.0), 1.0);
+        frame.push_ctrl(1);
+        assert_eq!(frame.animate(1, 3.0, 2.0), 3.0);
+        frame.pop_ctrl();
+        ui.end_frame();
+        assert_eq!(ui.animated_values.len(), 2);
+
+        ui.begin_frame();
+        ui.end_frame();
+        assert!(ui.animated_values.is_empty());
     }
 
-    pub fn set_rect(&mut self, rect: Rect) {
-        self.ui.tree[self.idx].rect = rect;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_set_delta_time_and_time() {
+        let mut ui = new_ui();
+
+        ui.set_delta_time(0.5);
+        let frame = ui.begin_frame();
+        assert_eq!(frame.delta_time(), Some(0.5));
+        assert_eq!(frame.delta_time_or_fallback(), 0.5);
+        assert_eq!(frame.time(), 0.5);
+        ui.end_frame();
+
+        // The delta time is only used for one frame, after which the fallback
+        // keeps the time going.
+        let frame = ui.begin_frame();
+        assert_eq!(frame.delta_time(), None);
+        assert_eq!(frame.delta_time_or_fallback(), FALLBACK_DELTA_TIME);
+        assert_eq!(frame.time(), 0.5 + FALLBACK_DELTA_TIME);
+        ui.end_frame();
+
+        // Passing the delta time to begin_frame replaces the one set before,
+        // as it is set last.
+        ui.set_delta_time(0.5);
+        let frame = ui.begin_frame_with_delta_time(0.25);
+        assert_eq!(frame.delta_time(), Some(0.25));
+        assert_eq!(frame.time(), 0.75 + FALLBACK_DELTA_TIME);
+        ui.end_frame();
+
+        let frame = ui.begin_frame();
+        assert_eq!(frame.delta_time(), None);
+        ui.end_frame();
     }
 
-    pub fn set_padding(&mut self, padding: f32) {
-        self.ui.tree[self.idx].padding = padding;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_ctrl_state_by_id() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            frame.push_ctrl(1);
+            frame.push_id(7);
+            let mut ctrl = frame.push_ctrl(2);
+            ctrl.state_mut()[0] = 42;
+            frame.pop_ctrl();
+            frame.pop_id();
+            frame.pop_ctrl();
+
+            frame.begin_overlay();
+            let mut ctrl = frame.push_ctrl(3);
+            ctrl.state_mut()[0] = 43;
+            frame.pop_ctrl();
+            frame.end_overlay();
+            ui.end_frame();
+        };
+
+        build(&mut ui);
+
+        let mut frame = ui.begin_frame();
+        let parent_id = frame.current_id(1);
+        frame.push_id(7);
+        let child_id = frame.current_id(2);
+        frame.pop_id();
+        let overlay_id = frame.current_id(3);
+
+        assert_eq!(
+            frame.ctrl_state_by_id(&[parent_id, child_id]).unwrap()[0],
+            42
+        );
+        assert_eq!(frame.ctrl_state_by_id(&[overlay_id]).unwrap()[0], 43);
+        assert_eq!(frame.ctrl_state_by_id(&[parent_id, parent_id]), None);
+        assert_eq!(frame.ctrl_state_by_id(&[]), None);
+        ui.end_frame();
     }
 
-    pub fn set_border(&mut self, border: f32) {
-        self.ui.tree[self.idx].border = border;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_high_z_index_sibling_keeps_hover_over_active_sibling() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+
+        let build = |ui: &mut Ui<Global>, activate_low: bool| {
+            let mut frame = ui.begin_frame();
+
+            let mut high = frame.push_ctrl(line!());
+            high.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+            high.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            high.set_z_index(1);
+            let high_hovered = high.is_hovered();
+            frame.pop_ctrl();
+
+            let mut low = frame.push_ctrl(line!());
+            low.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+            low.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            if activate_low {
+                low.set_active(true);
+            }
+            frame.pop_ctrl();
+
+            ui.end_frame();
+
+            high_hovered
+        };
+
+        build(&mut ui, false);
+        assert!(build(&mut ui, true));
+        assert!(build(&mut ui, false));
+        assert!(build(&mut ui, false));
     }
 
-    pub fn set_margin(&mut self, margin: f32) {
-        self.ui.tree[self.idx].margin = margin;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_modal_ctrl_blocks_hover_and_active_outside_of_it() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(50.0, 50.0);
+
+        // Returns whether the control under the cursor is hovered and active.
+        let build = |ui: &mut Ui<Global>, activate: bool, modal: bool| {
+            let mut frame = ui.begin_frame();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            if activate {
+                ctrl.set_active(true);
+            }
+            let hovered = ctrl.is_hovered();
+            let active = ctrl.is_active();
+            frame.pop_ctrl();
+
+            if modal {
+                frame.begin_overlay();
+                let mut modal_ctrl = frame.push_ctrl(1);
+                modal_ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+                modal_ctrl.set_rect(Rect::new(200.0, 200.0, 100.0, 100.0));
+                modal_ctrl.set_modal(true);
+                frame.pop_ctrl();
+                frame.end_overlay();
+            }
+
+            ui.end_frame();
+
+            (hovered, active)
+        };
+
+        build(&mut ui, true, false);
+        assert_eq!(build(&mut ui, false, true), (true, true));
+        assert!(ui.modal_active());
+
+        // The modal control doesn't contain the cursor, but still blocks the
+        // control under it.
+        assert_eq!(build(&mut ui, false, true), (false, false));
+        assert_eq!(build(&mut ui, false, false), (false, false));
+        assert!(!ui.modal_active());
+        assert_eq!(build(&mut ui, false, false), (true, false));
     }
 
-    pub fn set_scroll_offset_x(&mut self, scroll_offset: f32) {
-        self.ui.tree[self.idx].scroll_offset.x = scroll_offset;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_shortcut_pressed_claims_keypress() {
+        let mut ui = new_ui();
+        let save = Shortcut::new(Inputs::KB_S, Modifiers::CTRL);
+
+        ui.set_modifiers(Modifiers::CTRL);
+        ui.press_inputs(Inputs::KB_S);
+
+        let mut frame = ui.begin_frame();
+        assert!(frame.shortcut_pressed(save));
+        assert!(!frame.shortcut_pressed(save));
+        ui.end_frame();
+
+        let mut frame = ui.begin_frame();
+        assert!(!frame.shortcut_pressed(save));
+        ui.end_frame();
     }
 
-    pub fn set_scroll_offset_y(&mut self, scroll_offset: f32) {
-        self.ui.tree[self.idx].scroll_offset.y = scroll_offset;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_shortcut_pressed_yields_editing_shortcuts_to_text_input() {
+        let mut ui = new_ui();
+        let save = Shortcut::new(Inputs::KB_S, Modifiers::CTRL);
+        let copy = Shortcut::new(Inputs::KB_C, Modifiers::CTRL);
+
+        // Returns whether the app got the save and copy shortcuts.
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            let pressed = (frame.shortcut_pressed(save), frame.shortcut_pressed(copy));
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_ACTIVE);
+            ctrl.set_active(true);
+            ctrl.request_want_text_input();
+            frame.pop_ctrl();
+
+            ui.end_frame();
+
+            pressed
+        };
+
+        build(&mut ui);
+
+        ui.set_modifiers(Modifiers::CTRL);
+        ui.press_inputs(Inputs::KB_S);
+        ui.press_inputs(Inputs::KB_C);
+        assert_eq!(build(&mut ui), (true, false));
     }
 
-    pub fn set_draw_self(&mut self, draw_self: bool) {
-        self.ui.tree[self.idx].draw_self = draw_self;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_nested_overlay_is_built_above_outer_overlay() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(150.0, 150.0);
+
+        // Returns whether the nested overlay control is hovered.
+        let build = |ui: &mut Ui<Global>, activate_outer: bool| {
+            let mut frame = ui.begin_frame();
+
+            frame.begin_overlay();
+            let mut outer = frame.push_ctrl(0);
+            outer.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+            outer.set_rect(Rect::new(0.0, 0.0, 200.0, 200.0));
+
+            let mut inner = frame.push_ctrl(0);
+            inner.set_flags(CtrlFlags::CAPTURE_ACTIVE);
+            inner.set_rect(Rect::new(0.0, 0.0, 10.0, 10.0));
+            if activate_outer {
+                inner.set_active(true);
+            }
+
+            frame.begin_overlay();
+            let mut nested = frame.push_ctrl(1);
+            nested.set_flags(CtrlFlags::CAPTURE_HOVER);
+            nested.set_rect(Rect::new(100.0, 100.0, 100.0, 100.0));
+            let nested_hovered = nested.is_hovered();
+            frame.pop_ctrl();
+            frame.end_overlay();
+
+            frame.pop_ctrl();
+            frame.pop_ctrl();
+
+            // Top level controls built after the nested overlay are built
+            // after it.
+            let mut after = frame.push_ctrl(2);
+            after.set_rect(Rect::new(500.0, 500.0, 10.0, 10.0));
+            frame.pop_ctrl();
+            frame.end_overlay();
+
+            ui.end_frame();
+
+            nested_hovered
+        };
+
+        build(&mut ui, false);
+        assert!(build(&mut ui, true));
+        assert!(build(&mut ui, false));
+
+        // The nested overlay is built as a top level control of the overlay,
+        // between the outer control and the one built after it.
+        let top_level_count = ui
+            .debug_ctrls()
+            .filter(|ctrl| ctrl.overlay && ctrl.depth == 0)
+            .count();
+        assert_eq!(top_level_count, 3);
     }
 
-    pub fn set_draw_self_border_color(&mut self, border_color: u32) {
-        self.ui.tree[self.idx].draw_self_border_color = border_color;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_clicked_outside_ignores_owned_overlays() {
+        let mut ui = new_ui();
+
+        // Returns whether the owner was clicked outside.
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+
+            let mut owner = frame.push_ctrl(0);
+            owner.set_flags(CtrlFlags::CAPTURE_HOVER);
+            owner.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            owner.close_on_outside_click();
+            let clicked_outside = owner.clicked_outside();
+
+            frame.begin_overlay();
+            let mut popup = frame.push_ctrl(0);
+            popup.set_flags(CtrlFlags::CAPTURE_HOVER);
+            popup.set_rect(Rect::new(300.0, 300.0, 100.0, 100.0));
+            frame.pop_ctrl();
+            frame.end_overlay();
+
+            frame.pop_ctrl();
+            ui.end_frame();
+
+            clicked_outside
+        };
+
+        build(&mut ui);
+
+        let mut click = |x, y| {
+            ui.set_cursor_position(x, y);
+            build(&mut ui);
+            ui.press_inputs(Inputs::MB_LEFT);
+            let clicked_outside = build(&mut ui);
+            ui.release_inputs(Inputs::MB_LEFT);
+
+            clicked_outside
+        };
+
+        assert!(!click(50.0, 50.0));
+        assert!(!click(350.0, 350.0));
+        assert!(click(200.0, 200.0));
     }
 
-    pub fn set_draw_self_background_color(&mut self, background_color: u32) {
-        self.ui.tree[self.idx].draw_self_background_color = background_color;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_viewports_render_into_separate_draw_lists() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>, with_viewport: bool| {
+            let mut frame = ui.begin_frame();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            ctrl.set_draw_self(true);
+            frame.pop_ctrl();
+
+            if with_viewport {
+                frame.begin_viewport(1, Vec2::new(1000.0, 200.0));
+                assert_eq!(frame.window_size(), Vec2::new(1000.0, 200.0));
+
+                // Outside the main window, but inside the viewport.
+                let mut ctrl = frame.push_ctrl(0);
+                ctrl.set_rect(Rect::new(900.0, 0.0, 50.0, 50.0));
+                ctrl.set_draw_self(true);
+                frame.pop_ctrl();
+
+                frame.begin_overlay();
+                let mut ctrl = frame.push_ctrl(0);
+                ctrl.set_rect(Rect::new(0.0, 100.0, 50.0, 50.0));
+                ctrl.set_draw_self(true);
+                frame.pop_ctrl();
+                frame.end_overlay();
+
+                frame.end_viewport();
+            }
+
+            assert_eq!(frame.window_size(), Vec2::new(800.0, 600.0));
+            ui.end_frame();
+        };
+
+        build(&mut ui, true);
+        let (commands, vertices, _) = ui.draw_list();
+        assert_eq!(commands.len(), 1);
+        assert!(vertices.iter().all(|vertex| vertex.position[0] <= 100.0));
+
+        let (commands, vertices, indices) = ui.draw_list_for_viewport(1);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(indices.len(), 12);
+        assert!(vertices.iter().any(|vertex| vertex.position[0] == 950.0));
+        assert!(vertices.iter().any(|vertex| vertex.position[1] == 150.0));
+
+        // Viewports not built are removed.
+        build(&mut ui, false);
+        assert_eq!(ui.draw_list().0.len(), 1);
+        assert!(ui.draw_list_for_viewport(1).0.is_empty());
     }
 
-    pub fn is_new(&self) -> bool {
-        if let Some(build_parent_idx) = self.ui.build_parent_idx {
-            self.ui.tree[build_parent_idx].first_frame == self.ui.current_frame
-        } else {
-            false
-        }
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_hover_is_restricted_to_cursor_viewport() {
+        let mut ui = new_ui();
+
+        // Returns whether the controls in the main viewport and in the other
+        // one are hovered. Both are at the same position.
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            let main_hovered = ctrl.is_hovered();
+            frame.pop_ctrl();
+
+            frame.begin_viewport(1, Vec2::new(1000.0, 200.0));
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            let viewport_hovered = ctrl.is_hovered();
+            frame.pop_ctrl();
+
+            let mut ctrl = frame.push_ctrl(1);
+            ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+            ctrl.set_rect(Rect::new(900.0, 0.0, 50.0, 50.0));
+            let viewport_far_hovered = ctrl.is_hovered();
+            frame.pop_ctrl();
+            frame.end_viewport();
+
+            ui.end_frame();
+
+            (main_hovered, viewport_hovered, viewport_far_hovered)
+        };
+
+        build(&mut ui);
+
+        ui.set_cursor_position(50.0, 50.0);
+        build(&mut ui);
+        assert_eq!(build(&mut ui), (true, false, false));
+
+        ui.set_cursor_position_for_viewport(1, 50.0, 50.0);
+        build(&mut ui);
+        assert_eq!(build(&mut ui), (false, true, false));
+
+        // The viewport is wider than the window.
+        ui.set_cursor_position_for_viewport(1, 920.0, 20.0);
+        build(&mut ui);
+        assert_eq!(build(&mut ui), (false, false, true));
     }
 
-    pub fn is_hovered(&self) -> bool {
-        self.ui.build_parent_idx == self.ui.hovered_capturing_ctrl_idx
-    }
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_visible_range_builds_only_visible_rows() {
+        const ROW_COUNT: usize = 1000;
+        const ROW_HEIGHT: f32 = 20.0;
+
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>, scroll_offset_y: Option<f32>| {
+            let mut frame = ui.begin_frame();
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::CAPTURE_SCROLL);
+            ctrl.set_layout(Layout::Vertical);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 200.0));
+            if let Some(scroll_offset_y) = scroll_offset_y {
+                ctrl.set_scroll_offset_y(scroll_offset_y);
+            }
+
+            let visible_range = frame.visible_range(ROW_HEIGHT, ROW_COUNT);
 
-    pub fn is_active(&self) -> bool {
-        self.ui.active_ctrl_idx == Some(self.idx)
-    }
+            let spacer = |frame: &mut Frame<Global>, id: u32, row_count: usize| {
+                let mut ctrl = frame.push_ctrl(id);
+                ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, row_count as f32 * ROW_HEIGHT));
+                frame.pop_ctrl();
+            };
 
-    pub fn state(&self) -> &CtrlState {
-        &self.ui.tree[self.idx].state
-    }
+            spacer(&mut frame, 0, visible_range.start);
+            frame.push_id(1);
+            for row in visible_range.clone() {
+                let mut ctrl = frame.push_ctrl(row as u32);
+                ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, ROW_HEIGHT));
+                ctrl.set_draw_self(true);
+                frame.pop_ctrl();
+            }
+            frame.pop_id();
+            spacer(&mut frame, 2, ROW_COUNT - visible_range.end);
 
-    pub fn state_mut(&mut self) -> &mut CtrlState {
-        &mut self.ui.tree[self.idx].state
-    }
+            frame.pop_ctrl();
+            ui.end_frame();
 
-    pub fn absolute_position(&self) -> Vec2 {
-        self.ui.tree[self.idx].layout_cache_absolute_position
-    }
+            visible_range
+        };
 
-    pub fn inner_size(&self) -> Vec2 {
-        let ctrl = &self.ui.tree[self.idx];
-        let rect = ctrl.rect.inset(ctrl.border + ctrl.padding);
+        // The roots, the scrolling control and the spacers are built besides
+        // the rows.
+        assert_eq!(build(&mut ui, None), 0..10);
+        assert_eq!(ui.frame_stats().ctrl_count, 5 + 10);
 
-        rect.size()
-    }
+        build(&mut ui, Some(205.0));
+        assert_eq!(build(&mut ui, None), 10..21);
+        assert_eq!(ui.frame_stats().ctrl_count, 5 + 11);
 
-    pub fn scroll_offset_x(&self) -> f32 {
-        self.ui.tree[self.idx].scroll_offset.x
-    }
+        // The first and last row are only partially visible, and are clipped
+        // by the scissor rect.
+        let (commands, vertices, _) = ui.draw_list();
+        assert_eq!(vertices.len(), 11 * 4);
+        assert_eq!(commands[0].scissor_rect, Rect::new(0.0, 0.0, 100.0, 200.0));
 
-    pub fn scroll_offset_y(&self) -> f32 {
-        self.ui.tree[self.idx].scroll_offset.y
+        // Scrolled all the way to the bottom.
+        build(&mut ui, Some(ROW_COUNT as f32 * ROW_HEIGHT - 200.0));
+        assert_eq!(build(&mut ui, None), 990..1000);
     }
 
-    pub fn get_clipboard_text(&self) -> String {
-        (self.ui.clipboard_getter)()
-    }
+    #[cfg(feature = "runtime_rasterize")]
+    fn build_touch_target(ui: &mut Ui<Global>, delta_time: f32) -> (Inputs, Inputs, f32) {
+        let mut frame = ui.begin_frame_with_delta_time(delta_time);
+        let pressed = frame.inputs_pressed();
+        let released = frame.inputs_released();
 
-    pub fn set_clipboard_text(&mut self, text: &str) {
-        (self.ui.clipboard_setter)(text)
-    }
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_flags(CtrlFlags::CAPTURE_SCROLL | CtrlFlags::CAPTURE_HOVER);
+        ctrl.set_layout(Layout::Vertical);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+        let scroll_offset_y = ctrl.scroll_offset_y();
 
-    pub fn request_want_capture_keyboard(&mut self) {
-        self.ui.want_capture_keyboard = true;
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 300.0));
+        frame.pop_ctrl();
+
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        (pressed, released, scroll_offset_y)
     }
 
-    pub fn request_want_capture_mouse(&mut self) {
-        self.ui.want_capture_mouse = true;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_touch_tap_clicks_over_two_frames() {
+        let mut ui = new_ui();
+        build_touch_target(&mut ui, 0.0);
+
+        ui.touch_start(7, 50.0, 50.0);
+        // Other touches are ignored while the first is held.
+        ui.touch_start(8, 90.0, 90.0);
+        assert_eq!(build_touch_target(&mut ui, 0.1).0, Inputs::NONE);
+        assert_eq!(ui.cursor_position, Vec2::new(50.0, 50.0));
+
+        ui.touch_move(7, 53.0, 53.0);
+        ui.touch_end(8);
+        ui.touch_end(7);
+        assert_eq!(
+            build_touch_target(&mut ui, 0.1),
+            (Inputs::MB_LEFT, Inputs::NONE, 0.0),
+        );
+        assert_eq!(
+            build_touch_target(&mut ui, 0.1),
+            (Inputs::NONE, Inputs::MB_LEFT, 0.0),
+        );
     }
 
-    pub fn draw_rect(&mut self, rect: Rect, texture_rect: Rect, color: u32, texture_id: u64) {
-        let build_parent_idx = self.ui.build_parent_idx.unwrap();
-        let next_draw_primitive_idx = self.ui.draw_primitives.len();
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_touch_drag_scrolls_scrollable_ctrl() {
+        let mut ui = new_ui();
+        build_touch_target(&mut ui, 0.0);
+
+        ui.touch_start(7, 50.0, 50.0);
+        build_touch_target(&mut ui, 0.1);
+
+        // Past the slop, the whole distance from the start scrolls, and
+        // nothing is pressed.
+        ui.touch_move(7, 50.0, 45.0);
+        ui.touch_move(7, 50.0, 20.0);
+        assert_eq!(
+            build_touch_target(&mut ui, 0.1),
+            (Inputs::NONE, Inputs::NONE, 30.0),
+        );
 
-        let parent = &mut self.ui.tree[build_parent_idx];
-        assert!(parent.draw_range.end == next_draw_primitive_idx);
+        ui.touch_move(7, 50.0, 10.0);
+        ui.touch_end(7);
+        assert_eq!(
+            build_touch_target(&mut ui, 0.1),
+            (Inputs::NONE, Inputs::NONE, 40.0),
+        );
+        assert_eq!(build_touch_target(&mut ui, 0.1).1, Inputs::NONE);
+    }
 
-        self.ui.draw_primitives.push(DrawPrimitive::Rect {
-            rect,
-            texture_rect,
-            texture_id,
-            color,
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_touch_long_press_is_right_click() {
+        let mut ui = new_ui();
+        ui.set_touch_options(TouchOptions {
+            long_press_duration: Some(0.5),
+            ..TouchOptions::DEFAULT
         });
+        build_touch_target(&mut ui, 0.0);
 
-        parent.draw_range.end += 1;
-    }
+        ui.touch_start(7, 50.0, 50.0);
+        assert_eq!(build_touch_target(&mut ui, 0.25).0, Inputs::NONE);
+        assert_eq!(build_touch_target(&mut ui, 0.25).0, Inputs::MB_RIGHT);
 
-    pub fn draw_text(&mut self, text: &str, halign: Align, valign: Align, wrap: Wrap, color: u32) {
-        self.draw_text_and_do_dishes(false, None, 0.0, text, halign, valign, wrap, color);
+        // Lifting the finger doesn't also tap.
+        ui.touch_end(7);
+        assert_eq!(
+            build_touch_target(&mut ui, 0.25),
+            (Inputs::NONE, Inputs::MB_RIGHT, 0.0),
+        );
+        assert_eq!(build_touch_target(&mut ui, 0.25).1, Inputs::NONE);
     }
 
-    pub fn draw_text_fitted(
-        &mut self,
-        text: &str,
-        halign: Align,
-        valign: Align,
-        wrap: Wrap,
-        color: u32,
-        fitting: Rect,
-    ) {
-        self.draw_text_and_do_dishes(true, Some(fitting), 0.0, text, halign, valign, wrap, color);
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_flags_contains_all_bits_and_intersects_any() {
+        let inputs = Inputs::MB_LEFT | Inputs::KB_ENTER;
+        assert!(inputs.contains(Inputs::MB_LEFT));
+        assert!(!inputs.contains(Inputs::MB_LEFT | Inputs::MB_RIGHT));
+        assert!(inputs.intersects(Inputs::MB_LEFT | Inputs::MB_RIGHT));
+        assert!(!inputs.intersects(Inputs::MB_RIGHT));
+        assert!(inputs.contains(Inputs::NONE));
+        assert!(!inputs.intersects(Inputs::NONE));
+
+        let modifiers = Modifiers::CTRL | Modifiers::SHIFT;
+        assert!(modifiers.contains(Modifiers::CTRL));
+        assert!(!modifiers.contains(Modifiers::CTRL | Modifiers::ALT));
+        assert!(modifiers.intersects(Modifiers::CTRL | Modifiers::ALT));
+
+        let flags = CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE;
+        assert!(flags.contains(CtrlFlags::CAPTURE_HOVER));
+        assert!(!flags.contains(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_SCROLL));
+        assert!(flags.intersects(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_SCROLL));
+
+        let ranges = UnicodeRangeFlags::ALL_LATIN;
+        assert!(ranges.contains(UnicodeRangeFlags::BASIC_LATIN));
+        assert!(!ranges.contains(UnicodeRangeFlags::BASIC_LATIN | UnicodeRangeFlags::GREEK));
+        assert!(ranges.intersects(UnicodeRangeFlags::BASIC_LATIN | UnicodeRangeFlags::GREEK));
     }
 
-    pub fn draw_text_inset_and_extend_content_rect(
-        &mut self,
-        text: &str,
-        halign: Align,
-        valign: Align,
-        wrap: Wrap,
-        color: u32,
-        inset: f32,
-    ) {
-        self.draw_text_and_do_dishes(true, None, inset, text, halign, valign, wrap, color);
+    #[test]
+    fn test_flags_and_not_sub() {
+        let inputs = Inputs::MB_LEFT | Inputs::KB_ENTER;
+        assert_eq!(inputs & Inputs::MB_LEFT, Inputs::MB_LEFT);
+        assert_eq!(inputs & Inputs::MB_RIGHT, Inputs::NONE);
+        assert_eq!(inputs - Inputs::MB_LEFT, Inputs::KB_ENTER);
+        assert_eq!(!Inputs::NONE, Inputs::ALL);
+        assert_eq!(!inputs & inputs, Inputs::NONE);
+        assert_eq!(!inputs | inputs, Inputs::ALL);
+
+        let mut inputs = inputs;
+        inputs &= Inputs::KB_ENTER | Inputs::KB_ESCAPE;
+        assert_eq!(inputs, Inputs::KB_ENTER);
+        inputs -= Inputs::KB_ENTER;
+        assert_eq!(inputs, Inputs::NONE);
+
+        // Complements only contain defined bits.
+        assert_eq!(!Modifiers::CTRL, Modifiers::ALT | Modifiers::SHIFT);
+        assert_eq!(!CtrlFlags::ALL, CtrlFlags::NONE);
+        assert_eq!(
+            CtrlFlags::ALL - CtrlFlags::CAPTURE_HOVER,
+            CtrlFlags::ALL & !CtrlFlags::CAPTURE_HOVER,
+        );
+        assert_eq!(
+            UnicodeRangeFlags::ALL_LATIN - UnicodeRangeFlags::BASIC_LATIN,
+            UnicodeRangeFlags::ALL_LATIN & !UnicodeRangeFlags::BASIC_LATIN,
+        );
     }
 
-    fn draw_text_and_do_dishes(
-        &mut self,
-        extend_inline_content_rect: bool,
-        fitting: Option<Rect>,
-        inset: f32,
-        text: &str,
-        halign: Align,
-        valign: Align,
-        wrap: Wrap,
-        color: u32,
-    ) {
-        assert!(inset >= 0.0);
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_translucent_rect_over_opaque_rect_in_both_alpha_modes() {
+        let mut ui = new_ui();
 
-        // TODO(yan): @Bug @Correctness #Antialiasing? This has layout issues
-        // (characters not being aligned vertically to the baseline) on Roboto,
-        // IBM Plex Mono, and Liberation Mono fonts, but not on Proggy
-        // Clean. Pixel peeping in RenderDoc showed a consistent 1px or
-        // sometimes 2px error in some characters, e.g. for 'r' and 'i' in
-        // Liberation Mono, 'i' is rendered one pixel higher than expected (or
-        // 'r' is rendered one pixel lower). Weirdly, they have the same ymin
-        // (zero) in metrics returned by fontdue, even though they are visibly
-        // offset in the rasterized atlas. Could this be an error in fontdue -
-        // either in metrics, or rasterization? Do we want to remove fontdue and
-        // do our own thing?
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
 
-        let build_parent_idx = self.ui.build_parent_idx.unwrap();
-        let next_draw_primitive_idx = self.ui.draw_primitives.len();
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            ctrl.set_draw_self(true);
+            ctrl.set_draw_self_background_color(0xff0000ff);
 
-        let parent = &mut self.ui.tree[build_parent_idx];
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 50.0, 50.0));
+            ctrl.set_draw_self(true);
+            ctrl.set_draw_self_background_color(0x0000ff80);
+            frame.pop_ctrl();
 
-        assert!(parent.draw_range.end == next_draw_primitive_idx);
+            frame.pop_ctrl();
+            ui.end_frame();
 
-        // NB: Vertical align only makes sense, if there is any free space to
-        // align in. If we are going to shrink/resize, there is no free space
-        // and it simplifies things for us to align to start and not care later.
-        //
-        // Note that horizontal align still makes sense for shrinking, because
-        // the lines will still be jagged and the width difference between
-        // longest line and current line will provide the alignment space.
-        let valign = if parent.flags.intersects(CtrlFlags::RESIZE_TO_FIT_VERTICAL) {
-            Align::Start
-        } else {
-            valign
+            let (_, vertices, _) = ui.draw_list();
+            assert_eq!(vertices.len(), 8);
+            (vertices[0].color, vertices[4].color)
         };
 
-        // NB: We zero X and Y of the default parent rect, because emiting draw
-        // commands insider a control already uses that control's transform. Not
-        // zeroing would apply them twice.
-        let fitting = fitting
-            .unwrap_or_else(|| Rect::new(0.0, 0.0, parent.rect.width, parent.rect.height))
-            .inset(inset);
-        let available_width = fitting.width;
-        let available_height = fitting.height;
-
-        // If we are expected to wrap text, but there's not enough space to
-        // render a missing character, don't attempt anything.
-        if wrap != Wrap::None
-            && self.ui.font_atlas.missing_glyph_info().advance_width > available_width
-        {
-            return;
-        }
-
-        struct Line {
-            range: Range<usize>,
-            width: f32,
-        }
+        assert!(!ui.draw_list_stats().premultiplied_alpha);
+        assert_eq!(build(&mut ui), (0xff0000ff, 0x0000ff80));
 
-        // TODO(yan): @Memory If the allocator is a bump allocator, we
-        // potentially prevent it from reclaiming memory if draw_primitives
-        // grow.
-        let mut lines: Vec<Line, _> = Vec::new_in(&self.ui.allocator);
+        ui.set_premultiplied_alpha(true);
+        assert_eq!(build(&mut ui), (0xff0000ff, 0x00008080));
+        assert!(ui.draw_list_stats().premultiplied_alpha);
 
-        let mut last_char_was_whitespace = false;
-        let mut begun_word: bool;
-        let mut begun_word_start = 0;
+        ui.set_premultiplied_alpha(false);
+        assert_eq!(build(&mut ui), (0xff0000ff, 0x0000ff80));
+    }
 
-        let mut line_range = 0..0;
-        let mut line_width = 0.0;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_linear_color_output_linearizes_cached_vertex_colors() {
+        let mut ui = new_ui();
 
-        for (i, c) in text.char_indices() {
-            begun_word = !c.is_whitespace();
-            if last_char_was_whitespace && !c.is_whitespace() {
-                begun_word_start = i;
-            }
-            last_char_was_whitespace = c.is_whitespace();
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
 
-            if c == '\n' && !line_range.is_empty() {
-                // Note that this could be an empty line, but that's fine.
-                lines.push(Line {
-                    range: line_range,
-                    width: line_width,
-                });
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 50.0, 50.0));
+            ctrl.set_draw_self(true);
+            ctrl.set_draw_self_border_color(0x80808080);
+            ctrl.set_draw_self_background_color(0x80808080);
+            frame.pop_ctrl();
 
-                // 1 is the byte width of the '\n', so i + 1 is ok.
-                line_range = i + 1..i + 1;
-                line_width = 0.0;
+            ui.end_frame();
 
-                continue;
-            }
+            let (_, vertices, _) = ui.draw_list();
+            vertices[0].color
+        };
 
-            let glyph_info = self.ui.font_atlas.glyph_info(c);
-            let glyph_advance_width = glyph_info.advance_width;
+        assert_eq!(build(&mut ui), 0x80808080);
 
-            if line_width + glyph_advance_width > available_width {
-                match wrap {
-                    Wrap::Word => {
-                        let begun_word_width = if begun_word {
-                            let slice = &text[begun_word_start..i];
+        // The geometry is cached, but flipping the flag must not reuse it.
+        ui.set_linear_color_output(true);
+        assert_eq!(build(&mut ui), 0x37373780);
+        assert!(ui.draw_list_stats().linear_color_output);
 
-                            let mut width = 0.0;
-                            for c in slice.chars() {
-                                width += self.ui.font_atlas.glyph_info(c).advance_width;
-                            }
+        // Linearized first, then premultiplied.
+        ui.set_premultiplied_alpha(true);
+        assert_eq!(build(&mut ui), 0x1c1c1c80);
+    }
 
-                            width
-                        } else {
-                            0.0
-                        };
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_held_key_repeats_after_delay() {
+        let mut ui = new_ui();
 
-                        if !begun_word || begun_word_width + glyph_advance_width > available_width {
-                            // If we are not inside a word right now, or the
-                            // begun word is wide enough to cause wrapping by
-                            // itself, fall back to letter wrapping.
-                            lines.push(Line {
-                                range: line_range,
-                                width: line_width,
-                            });
-
-                            line_range = i..i + c.len_utf8();
-                            line_width = glyph_advance_width;
-                        } else {
-                            // Otherwise commit previous line and move the word
-                            // to the next.
-                            lines.push(Line {
-                                range: line_range.start..begun_word_start,
-                                width: line_width - begun_word_width,
-                            });
-
-                            line_range = begun_word_start..i + c.len_utf8();
-                            line_width = begun_word_width + glyph_advance_width;
-                        }
+        // Returns the pressed and the pressed or repeated inputs.
+        let build = |ui: &mut Ui<Global>| {
+            let frame = ui.begin_frame_with_delta_time(0.25);
+            let inputs = (frame.inputs_pressed(), frame.inputs_pressed_or_repeated());
+            ui.end_frame();
 
-                        continue;
-                    }
-                    Wrap::Letter => {
-                        lines.push(Line {
-                            range: line_range,
-                            width: line_width,
-                        });
+            inputs
+        };
 
-                        line_range = i..i + c.len_utf8();
-                        line_width = glyph_advance_width;
+        // Disabled by default.
+        ui.press_inputs(Inputs::KB_LEFT_ARROW);
+        build(&mut ui);
+        for _ in 0..4 {
+            assert_eq!(build(&mut ui), (Inputs::NONE, Inputs::NONE));
+        }
+        ui.release_inputs(Inputs::KB_LEFT_ARROW);
+        build(&mut ui);
+
+        ui.set_key_repeat(Some(KeyRepeat {
+            delay: 0.5,
+            interval: 0.25,
+        }));
+
+        let left = Inputs::KB_LEFT_ARROW;
+        ui.press_inputs(left);
+        assert_eq!(build(&mut ui), (left, left));
+        assert_eq!(build(&mut ui), (Inputs::NONE, Inputs::NONE));
+        assert_eq!(build(&mut ui), (Inputs::NONE, left));
+        assert_eq!(build(&mut ui), (Inputs::NONE, left));
+
+        ui.release_inputs(left);
+        assert_eq!(build(&mut ui), (Inputs::NONE, Inputs::NONE));
+        assert_eq!(build(&mut ui), (Inputs::NONE, Inputs::NONE));
+
+        // Keys that don't navigate or delete don't repeat.
+        ui.press_inputs(Inputs::KB_ENTER);
+        build(&mut ui);
+        for _ in 0..4 {
+            assert_eq!(build(&mut ui), (Inputs::NONE, Inputs::NONE));
+        }
+    }
 
-                        continue;
-                    }
-                    Wrap::None => (),
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_ctrl_state_is_allocated_on_demand_and_reused() {
+        let mut ui = new_ui();
+
+        let build = |ui: &mut Ui<Global>, ids: &[u32]| {
+            let mut frame = ui.begin_frame();
+            for &id in ids {
+                let mut ctrl = frame.push_ctrl(id);
+                // Stateless controls read zeroed state.
+                if id == 0 {
+                    assert_eq!(ctrl.state(), &[0; 64]);
+                } else {
+                    assert_eq!(ctrl.state()[0], 0);
+                    ctrl.state_mut()[0] = 1;
                 }
+                frame.pop_ctrl();
             }
+            ui.end_frame();
+        };
 
-            line_range.end += c.len_utf8();
-            line_width += glyph_advance_width;
-        }
-
-        lines.push(Line {
-            range: line_range,
-            width: line_width,
-        });
-
-        //
-        // Trim whitespace.
-        //
-        // Shorten ranges and decrease widths. The widths can only be decreased
-        // here, because the lines were already split and the whitespace widths
-        // already contributed to computing text wrap.
-        for line in &mut lines {
-            let line_slice = &text[line.range.clone()];
+        build(&mut ui, &[0, 1, 2]);
+        assert_eq!(ui.ctrl_states.len(), 2);
 
-            let mut start = line.range.start;
-            let mut end = line.range.end;
-            let mut trim_width = 0.0;
+        // Collected control state is reused and zeroed. Control 2 would see
+        // leftover state above, if it wasn't.
+        build(&mut ui, &[0]);
+        assert_eq!(ui.ctrl_state_free_list.len(), 2);
+        build(&mut ui, &[0, 2]);
+        assert_eq!(ui.ctrl_states.len(), 2);
+        assert_eq!(ui.ctrl_state_free_list.len(), 1);
+    }
 
-            for c in line_slice.chars() {
-                if !c.is_whitespace() {
-                    break;
-                }
+    #[test]
+    fn test_ctrl_idx_is_compact() {
+        assert_eq!(mem::size_of::<Option<CtrlIdx>>(), 4);
+        assert_eq!(CtrlIdx::new(ROOT_IDX).get(), ROOT_IDX);
+        assert_eq!(CtrlIdx::new(1234).get(), 1234);
+    }
 
-                start += c.len_utf8();
-                trim_width += self.ui.font_atlas.glyph_info(c).advance_width;
-            }
+    #[test]
+    fn test_snap_rect_to_physical_pixels() {
+        let rect = Rect::new(0.375, 0.875, 5.0, 7.0);
 
-            let mut rev_iter = line_slice.chars().rev().peekable();
-            while let Some(c) = rev_iter.next() {
-                if !c.is_whitespace() {
-                    break;
-                }
+        let snapped = snap_rect_to_physical_pixels(rect, Vec2::new(10.0, 20.0), 1.0);
+        assert_eq!(snapped, Rect::new(0.0, 1.0, 5.0, 7.0));
 
-                if rev_iter.peek().is_some() {
-                    end -= c.len_utf8();
-                    trim_width += self.ui.font_atlas.glyph_info(c).advance_width;
-                }
-            }
+        // Origin contributes to the absolute position being snapped.
+        let snapped = snap_rect_to_physical_pixels(rect, Vec2::new(0.5, 0.0), 1.0);
+        assert_eq!(snapped, Rect::new(0.5, 1.0, 5.0, 7.0));
 
-            if start > end {
-                start = end;
-            }
+        // At 2x scale, half logical pixels are whole physical pixels.
+        let snapped = snap_rect_to_physical_pixels(rect, Vec2::ZERO, 2.0);
+        assert_eq!(snapped, Rect::new(0.5, 1.0, 5.0, 7.0));
+    }
 
-            line.range.start = start;
-            line.range.end = end;
-            line.width = f32::max(line.width - trim_width, 0.0)
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_snap_rect_edges_to_physical_pixels() {
+        let is_whole = |value: f32| f32::abs(value - libm::roundf(value)) < 0.001;
+
+        // A 1px border in logical pixels, in physical pixels at each scale.
+        let border = Rect::new(10.2, 20.6, 1.0, 1.0);
+        for (scale_factor, physical_width) in [(1.0, 1.0), (1.25, 1.0), (1.5, 2.0), (2.0, 2.0)] {
+            let snapped = snap_rect_edges_to_physical_pixels(border, scale_factor);
+
+            assert!(is_whole(snapped.x * scale_factor));
+            assert!(is_whole(snapped.y * scale_factor));
+            assert!(is_whole(snapped.max_x() * scale_factor));
+            assert!(is_whole(snapped.max_y() * scale_factor));
+            assert!(f32::abs(snapped.width * scale_factor - physical_width) < 0.001);
+            assert!(f32::abs(snapped.x - border.x) <= 0.5 / scale_factor);
         }
 
-        //
-        // Emit rects based on generated line data.
-        //
-        let line_metrics = self.ui.font_atlas.font_horizontal_line_metrics();
+        // Hairlines don't disappear, but empty rects stay empty.
+        let snapped = snap_rect_edges_to_physical_pixels(Rect::new(10.2, 0.0, 0.1, 0.0), 1.25);
+        assert_eq!(snapped.width * 1.25, 1.0);
+        assert_eq!(snapped.height, 0.0);
+    }
 
-        let mut position_y = if lines.len() as f32 * line_metrics.new_line_size < available_height {
-            match valign {
-                Align::Start => line_metrics.line_gap + fitting.y,
-                Align::Center => {
-                    let line_gap = line_metrics.line_gap;
-                    let new_line_size = line_metrics.new_line_size;
-                    let text_block_size = new_line_size * lines.len() as f32 - line_gap;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_pixel_snapping_snaps_solid_rects_but_not_images() {
+        let mut ui = new_ui();
+        ui.set_window_scale_factor(1.5);
+        ui.set_pixel_snapping(true);
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 200.0, 200.0));
+        ctrl.set_padding(0.0);
+        ctrl.set_border(0.0);
+        ctrl.set_margin(0.0);
+        ctrl.draw_rect(Rect::new(10.2, 10.2, 1.0, 1.0), Rect::ZERO, 0xffffffff, 0);
+        ctrl.draw_rect(Rect::new(10.2, 10.2, 1.0, 1.0), Rect::ONE, 0xffffffff, 1);
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        let (_, vertices, _) = ui.draw_list();
+        // Vertices go bottom-left, bottom-right, top-right, top-left.
+        let [x, y] = vertices[3].position;
+        assert_eq!([x * 1.5, y * 1.5], [15.0, 15.0]);
+        let [x, y] = vertices[1].position;
+        assert_eq!([x * 1.5, y * 1.5], [17.0, 17.0]);
+
+        assert_eq!(vertices[4 + 3].position, [10.2, 10.2]);
+    }
 
-                    line_gap + fitting.y + (available_height - text_block_size) / 2.0
-                }
-                Align::End => {
-                    let line_gap = line_metrics.line_gap;
-                    let new_line_size = line_metrics.new_line_size;
-                    let text_block_size = new_line_size * lines.len() as f32 - line_gap;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_rebuild_font_atlas_bumps_generation() {
+        let mut ui = new_ui();
+        let generation = ui.font_atlas_generation();
+        let advance_width = ui.font_atlas().glyph_info('M').advance_width;
+        let image_size = ui.font_atlas_image_size();
+
+        // Layout metrics follow the font size, but not the scale factor, which
+        // only affects the rasterized image.
+        ui.rebuild_font_atlas(13.0, 2.0);
+        assert_ne!(ui.font_atlas_generation(), generation);
+        assert_eq!(ui.font_atlas().glyph_info('M').advance_width, advance_width);
+        assert!(ui.font_atlas_image_size() > image_size);
+
+        let generation = ui.font_atlas_generation();
+        ui.rebuild_font_atlas(26.0, 2.0);
+        assert_ne!(ui.font_atlas_generation(), generation);
+        assert!(ui.font_atlas().glyph_info('M').advance_width > advance_width);
+    }
 
-                    line_gap + fitting.y + available_height - text_block_size
-                }
-            }
-        } else {
-            line_metrics.line_gap
-        };
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_with_theme_lends_current_theme() {
+        let mut ui = new_ui();
+        ui.set_theme(Theme::LIGHT);
 
-        for line in &lines {
-            let line_slice = &text[line.range.clone()];
+        let mut frame = ui.begin_frame();
+        frame.with_theme(|_, theme| assert_eq!(*theme, Theme::LIGHT));
 
-            let mut position_x = match halign {
-                Align::Start => fitting.x,
-                Align::Center => fitting.x + (available_width - line.width) / 2.0,
-                Align::End => fitting.x + available_width - line.width,
-            };
+        frame.push_theme(&Theme::DEFAULT);
+        frame.with_theme(|_, theme| assert_eq!(*theme, Theme::DEFAULT));
+        frame.pop_theme();
 
-            for c in line_slice.chars() {
-                let glyph_info = self.ui.font_atlas.glyph_info(c);
+        // The theme is back on the stack after the closure returns.
+        assert_eq!(*frame.theme(), Theme::LIGHT);
+        ui.end_frame();
+    }
 
-                let position = Vec2::new(position_x, position_y);
-                let rect = glyph_info.rect + position + Vec2::y(line_metrics.ascent);
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    #[should_panic(expected = "The theme stack can't be used while a widget borrows the theme")]
+    fn test_push_theme_inside_with_theme_panics() {
+        let mut ui = new_ui();
 
-                // TODO(yan): @Speed @Memory Does early software scissor make
-                // sense here? We also do it later, when translating to the
-                // low-level draw list, but we could have less things to
-                // translate.
-                self.ui.draw_primitives.push(DrawPrimitive::Rect {
-                    rect,
-                    texture_rect: glyph_info.atlas_rect,
-                    texture_id: self.ui.font_atlas_texture_id,
-                    color,
-                });
+        let mut frame = ui.begin_frame();
+        frame.with_theme(|frame, theme| frame.push_theme(theme));
+    }
 
-                parent.draw_range.end += 1;
-                if extend_inline_content_rect {
-                    if let Some(inline_content_rect) = &mut parent.inline_content_rect {
-                        *inline_content_rect = inline_content_rect.extend_by_rect(rect);
-                    } else {
-                        parent.inline_content_rect = Some(rect);
-                    }
-                }
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_persistent_state_roundtrip() {
+        fn build(ui: &mut Ui<Global>, mark: bool) {
+            let mut frame = ui.begin_frame();
 
-                position_x += glyph_info.advance_width;
+            let mut ctrl = frame.push_ctrl(0);
+            if mark {
+                ctrl.mark_persistent();
             }
+            ctrl.state_mut()[0] += 1;
 
-            position_y += line_metrics.new_line_size;
+            let mut ctrl = frame.push_ctrl(1);
+            ctrl.state_mut()[0] += 1;
+            frame.pop_ctrl();
+
+            frame.pop_ctrl();
+            ui.end_frame();
         }
 
-        // NB: Because this isn't real padding/border, we need to ensure that if
-        // we used inset, the final content rect reflects that. This happens
-        // automatically for top and left, but we need to add the inset
-        // to its size.
-        if extend_inline_content_rect {
-            if let Some(inline_content_rect) = &mut parent.inline_content_rect {
-                *inline_content_rect = inline_content_rect.resize(Vec2::splat(inset));
-            }
+        fn save(ui: &Ui<Global>) -> Vec<(u64, CtrlState)> {
+            let mut saved = Vec::new();
+            ui.save_persistent_state(|persistent_id, state| {
+                saved.push((persistent_id, *state));
+            });
+
+            saved
         }
-    }
 
-    pub fn cursor_position(&self) -> Vec2 {
-        self.ui.cursor_position
-    }
+        let mut ui = new_ui();
+        build(&mut ui, true);
+        build(&mut ui, true);
 
-    pub fn font_atlas(&self) -> &FontAtlas<A> {
-        &self.ui.font_atlas
-    }
+        // Only the marked control is saved.
+        let saved = save(&ui);
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].0, mix_persistent_id(0, mix_id(0, 0)));
+        assert_eq!(saved[0].1[0], 2);
 
-    pub fn font_atlas_texture_id(&self) -> u64 {
-        self.ui.font_atlas_texture_id
+        let mut restored_ui = new_ui();
+        for (persistent_id, state) in &saved {
+            restored_ui.load_persistent_state(*persistent_id, state);
+        }
+
+        let mut frame = restored_ui.begin_frame();
+        let ctrl = frame.push_ctrl(0);
+        assert_eq!(ctrl.state()[0], 2);
+        frame.pop_ctrl();
+        restored_ui.end_frame();
+
+        // The loaded state is used once the control asks for mutable state.
+        build(&mut restored_ui, true);
+        let resaved = save(&restored_ui);
+        assert_eq!(resaved.len(), 1);
+        assert_eq!(resaved[0].0, saved[0].0);
+        assert_eq!(resaved[0].1[0], 3);
+        assert!(restored_ui.loaded_ctrl_states.is_empty());
+
+        // Controls have to opt in every frame.
+        build(&mut ui, false);
+        assert!(save(&ui).is_empty());
     }
 
-    pub fn allocator(&self) -> &A {
-        &self.ui.allocator
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_draw_text_scaled() {
+        fn build(ui: &mut Ui<Global>, scale: f32) -> Vec<Rect> {
+            let mut frame = ui.begin_frame();
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 400.0, 200.0));
+            ctrl.draw_text_scaled(
+                "Hello",
+                Align::Start,
+                Align::Start,
+                Wrap::Word,
+                Direction::Ltr,
+                0xffffffff,
+                scale,
+            );
+            frame.pop_ctrl();
+            ui.end_frame();
+
+            ui.draw_primitives
+                .iter()
+                .map(|primitive| match primitive {
+                    DrawPrimitive::Rect { rect, .. } => *rect,
+                    _ => unreachable!(),
+                })
+                .collect()
+        }
+
+        let mut ui = new_ui();
+        ui.set_text_pixel_snap(false);
+
+        let rects = build(&mut ui, 1.0);
+        let scaled_rects = build(&mut ui, 2.0);
+
+        assert_eq!(rects.len(), 5);
+        assert_eq!(scaled_rects.len(), 5);
+        for (rect, scaled_rect) in rects.iter().zip(&scaled_rects) {
+            assert_eq!(scaled_rect.x, 2.0 * rect.x);
+            assert_eq!(scaled_rect.y, 2.0 * rect.y);
+            assert_eq!(scaled_rect.width, 2.0 * rect.width);
+            assert_eq!(scaled_rect.height, 2.0 * rect.height);
+        }
     }
-}
 
-fn join_id(id_base: u32, id_ctrl: u32) -> u64 {
-    let id_base_u64 = id_base as u64;
-    let id_ctrl_u64 = id_ctrl as u64;
+    #[cfg(feature = "runtime_rasterize")]
+    #[test]
+    fn test_ui_with_baked_font_atlas_draws_the_same() {
+        fn build(ui: &mut Ui<Global>) -> (Vec<Command>, Vec<Vertex>, Vec<u32>) {
+            let mut frame = ui.begin_frame();
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+            ctrl.draw_text(
+                "Baked ahead of time",
+                Align::Center,
+                Align::Center,
+                Wrap::Word,
+                Direction::Ltr,
+                0xffffffff,
+            );
+            frame.pop_ctrl();
+            ui.end_frame();
 
-    id_base_u64 | id_ctrl_u64 << 32
-}
+            let (commands, vertices, indices) = ui.draw_list();
+            (commands.to_vec(), vertices.to_vec(), indices.to_vec())
+        }
 
-fn empty_clipboard_getter() -> String {
-    String::new()
-}
+        let mut ui = new_ui();
+        let baked = ui.font_atlas().to_baked();
+        let font_atlas = FontAtlas::from_baked_in(&baked, Global).unwrap();
+        let mut baked_ui = Ui::new_in(800.0, 600.0, 1.0, FontSource::Atlas(font_atlas), Global);
 
-fn empty_clipboard_setter(_: &str) {}
+        assert_eq!(build(&mut baked_ui), build(&mut ui));
+    }
+}
@@ -0,0 +1,15 @@
+use alloc::alloc::Global;
+
+use crate::core::{FontSource, Ui, UnicodeRangeFlags};
+
+pub static FONT: &[u8] = include_bytes!("../assets/ProggyClean.ttf");
+
+// The font shared by tests: latin ranges at 13px.
+pub fn font_source() -> FontSource<'static, Global> {
+    FontSource::ttf(FONT, UnicodeRangeFlags::ALL_LATIN, 13.0, 1.0)
+}
+
+// The UI shared by tests: an 800x600 window and the shared font.
+pub fn new_ui() -> Ui<Global> {
+    Ui::new_in(800.0, 600.0, 1.0, font_source(), Global)
+}
@@ -26,6 +26,14 @@ mod convert;
 mod core;
 mod widgets;
 
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod fixtures;
+
+#[cfg(any(test, feature = "softraster"))]
+pub mod softraster;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 pub use crate::core::*;
 pub use crate::widgets::*;
 
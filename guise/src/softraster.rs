@@ -0,0 +1,509 @@
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+
+use crate::convert::{cast_u32, cast_usize};
+use crate::core::{Command, CommandKind, Rect, Sampling, Vertex};
+
+struct Texture {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    sdf: bool,
+}
+
+/// A reference renderer that rasterizes draw lists on the CPU into an RGBA8
+/// framebuffer, for running where there is no GPU, e.g. to take screenshots
+/// of the UI in CI.
+///
+/// It matches the example wgpu renderer: triangles are filled with the
+/// texture of their command, sampled bilinearly (or nearest, see
+/// [`Sampling`]) and multiplied with the vertex colors, then blended with
+/// straight or premultiplied alpha over the framebuffer. Like the example
+/// pipeline, back-facing triangles are culled and the framebuffer is treated
+/// as a non-sRGB format, so blending happens on the stored values.
+///
+/// Speed only needs to be good enough for tests and tools.
+pub struct SoftRasterizer {
+    textures: HashMap<u64, Texture>,
+    textures_next_id: u64,
+}
+
+impl SoftRasterizer {
+    pub fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            textures_next_id: 0,
+        }
+    }
+
+    pub fn add_texture_rgba8_unorm(&mut self, width: u32, height: u32, data: &[u8]) -> u64 {
+        assert_eq!(data.len(), cast_usize(width) * cast_usize(height) * 4);
+
+        let texture_id = self.textures_next_id;
+        self.textures_next_id += 1;
+
+        self.textures.insert(texture_id, Texture {
+            width,
+            height,
+            data: data.to_vec(),
+            sdf: false,
+        });
+
+        texture_id
+    }
+
+    /// Marks the texture as containing signed distance fields, e.g. for the
+    /// font atlas with [`crate::Ui::font_atlas_sdf`] set.
+    pub fn set_texture_sdf(&mut self, id: u64, sdf: bool) {
+        if let Some(texture) = self.textures.get_mut(&id) {
+            texture.sdf = sdf;
+        }
+    }
+
+    pub fn remove_texture(&mut self, id: u64) {
+        self.textures.remove(&id);
+    }
+
+    /// Draws the draw list over the contents of the framebuffer, which is
+    /// row-major RGBA8 with no padding between rows. Commands with missing
+    /// textures are skipped, as are callback commands, because there is no
+    /// renderer code to call back into.
+    pub fn draw(
+        &self,
+        framebuffer: &mut [u8],
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        viewport_scale: f32,
+        premultiplied_alpha: bool,
+        commands: &[Command],
+        vertices: &[Vertex],
+        indices: &[u32],
+    ) {
+        assert_eq!(
+            framebuffer.len(),
+            cast_usize(framebuffer_width) * cast_usize(framebuffer_height) * 4,
+        );
+
+        let mut consumed_index_count: usize = 0;
+
+        for command in commands {
+            let index_count = cast_usize(command.index_count);
+            let command_indices =
+                &indices[consumed_index_count..consumed_index_count + index_count];
+            consumed_index_count += index_count;
+
+            if command.kind == CommandKind::CALLBACK {
+                continue;
+            }
+
+            let Some(texture) = self.textures.get(&command.texture_id) else {
+                continue;
+            };
+
+            let Some(scissor_rect) = physical_scissor_rect(
+                command.scissor_rect,
+                viewport_scale,
+                framebuffer_width,
+                framebuffer_height,
+            ) else {
+                continue;
+            };
+
+            let base_vertex = cast_usize(command.base_vertex);
+            for triangle in command_indices.chunks_exact(3) {
+                let vertex = |i: usize| vertices[base_vertex + cast_usize(triangle[i])];
+
+                draw_triangle(
+                    framebuffer,
+                    framebuffer_width,
+                    scissor_rect,
+                    viewport_scale,
+                    premultiplied_alpha,
+                    texture,
+                    command.sampling,
+                    [vertex(0), vertex(1), vertex(2)],
+                );
+            }
+        }
+    }
+}
+
+impl Default for SoftRasterizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Like the example renderer's scissor rect conversion, but clamps to the
+// framebuffer instead of rejecting rects that reach outside. Returns min and
+// max pixel coordinates, max exclusive.
+fn physical_scissor_rect(
+    scissor_rect: Rect,
+    viewport_scale: f32,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+) -> Option<[u32; 4]> {
+    let x = libm::floorf(viewport_scale * scissor_rect.x).max(0.0) as u32;
+    let y = libm::floorf(viewport_scale * scissor_rect.y).max(0.0) as u32;
+    let w = libm::roundf(viewport_scale * scissor_rect.width) as u32;
+    let h = libm::roundf(viewport_scale * scissor_rect.height) as u32;
+
+    let min_x = u32::min(x, framebuffer_width);
+    let min_y = u32::min(y, framebuffer_height);
+    let max_x = u32::min(x.saturating_add(w), framebuffer_width);
+    let max_y = u32::min(y.saturating_add(h), framebuffer_height);
+
+    if min_x == max_x || min_y == max_y {
+        return None;
+    }
+
+    Some([min_x, min_y, max_x, max_y])
+}
+
+fn draw_triangle(
+    framebuffer: &mut [u8],
+    framebuffer_width: u32,
+    scissor_rect: [u32; 4],
+    viewport_scale: f32,
+    premultiplied_alpha: bool,
+    texture: &Texture,
+    sampling: Sampling,
+    vertices: [Vertex; 3],
+) {
+    let positions = vertices.map(|vertex| {
+        [
+            vertex.position[0] * viewport_scale,
+            vertex.position[1] * viewport_scale,
+        ]
+    });
+
+    // NB: Front faces are counter-clockwise in the example pipeline's clip
+    // space, which has y going up, so they have a negative area in pixel
+    // coordinates, where y goes down. Back faces are culled like there.
+    let area = edge(positions[0], positions[1], positions[2]);
+    if area >= 0.0 {
+        return;
+    }
+
+    // Swap two vertices, so that the edge functions are positive inside the
+    // triangle and the top-left rule below holds.
+    let [p0, p2, p1] = positions;
+    let [v0, v2, v1] = vertices;
+    let area = -area;
+
+    let [scissor_min_x, scissor_min_y, scissor_max_x, scissor_max_y] = scissor_rect;
+    let min_x = libm::floorf(p0[0].min(p1[0]).min(p2[0])).max(scissor_min_x as f32) as u32;
+    let min_y = libm::floorf(p0[1].min(p1[1]).min(p2[1])).max(scissor_min_y as f32) as u32;
+    let max_x = libm::ceilf(p0[0].max(p1[0]).max(p2[0])).min(scissor_max_x as f32) as u32;
+    let max_y = libm::ceilf(p0[1].max(p1[1]).max(p2[1])).min(scissor_max_y as f32) as u32;
+
+    // Pixels whose centers lie exactly on an edge shared by two triangles
+    // must only be drawn once, or translucent geometry would show seams.
+    // These are the top and left edges, like on the GPU.
+    let top_left = [
+        is_top_left(p1, p2),
+        is_top_left(p2, p0),
+        is_top_left(p0, p1),
+    ];
+
+    let colors = [v0, v1, v2].map(|vertex| unpack_color(vertex.color));
+    let tex_coords = [v0.tex_coord, v1.tex_coord, v2.tex_coord];
+
+    // Barycentric weights change linearly across the triangle, so texture
+    // coordinate derivatives are constant. The SDF path needs them to compute
+    // the width of the antialiased edge, like fwidth does in the shader.
+    let d_weights_dx = [p1[1] - p2[1], p2[1] - p0[1], p0[1] - p1[1]].map(|d| d / area);
+    let d_weights_dy = [p2[0] - p1[0], p0[0] - p2[0], p1[0] - p0[0]].map(|d| d / area);
+    let d_tex_coord_dx = interpolate2(tex_coords, d_weights_dx);
+    let d_tex_coord_dy = interpolate2(tex_coords, d_weights_dy);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = [x as f32 + 0.5, y as f32 + 0.5];
+            let w = [edge(p1, p2, p), edge(p2, p0, p), edge(p0, p1, p)];
+
+            let inside = (0..3).all(|i| w[i] > 0.0 || (w[i] == 0.0 && top_left[i]));
+            if !inside {
+                continue;
+            }
+
+            let weights = w.map(|w| w / area);
+            let color = interpolate4(colors, weights);
+            let tex_coord = interpolate2(tex_coords, weights);
+
+            let src = if texture.sdf {
+                let distance = sample(texture, sampling, tex_coord)[3];
+                let distance_dx = sample(texture, sampling, [
+                    tex_coord[0] + d_tex_coord_dx[0],
+                    tex_coord[1] + d_tex_coord_dx[1],
+                ])[3];
+                let distance_dy = sample(texture, sampling, [
+                    tex_coord[0] + d_tex_coord_dy[0],
+                    tex_coord[1] + d_tex_coord_dy[1],
+                ])[3];
+
+                let width = 0.5
+                    * (libm::fabsf(distance_dx - distance) + libm::fabsf(distance_dy - distance));
+                let coverage = smoothstep(0.5 - width, 0.5 + width, distance);
+
+                if premultiplied_alpha {
+                    color.map(|c| c * coverage)
+                } else {
+                    [color[0], color[1], color[2], color[3] * coverage]
+                }
+            } else {
+                let texel = sample(texture, sampling, tex_coord);
+                [
+                    color[0] * texel[0],
+                    color[1] * texel[1],
+                    color[2] * texel[2],
+                    color[3] * texel[3],
+                ]
+            };
+
+            let i = (cast_usize(y) * cast_usize(framebuffer_width) + cast_usize(x)) * 4;
+            let dst = &mut framebuffer[i..i + 4];
+
+            // Same blend state as the example pipeline, for both color and
+            // alpha: src * src_factor + dst * (1 - src_alpha).
+            let src_factor = if premultiplied_alpha { 1.0 } else { src[3] };
+            for c in 0..4 {
+                let d = f32::from(dst[c]) / 255.0;
+                let blended = src[c] * src_factor + d * (1.0 - src[3]);
+                dst[c] = unorm_to_u8(blended);
+            }
+        }
+    }
+}
+
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+// For triangles with positive edge functions inside, top edges go right and
+// left edges go up.
+fn is_top_left(a: [f32; 2], b: [f32; 2]) -> bool {
+    let dx = b[0] - a[0];
+    let dy = b[1] - a[1];
+
+    (dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+fn interpolate2(values: [[f32; 2]; 3], weights: [f32; 3]) -> [f32; 2] {
+    let mut result = [0.0; 2];
+    for (value, weight) in values.iter().zip(weights) {
+        for c in 0..2 {
+            result[c] += value[c] * weight;
+        }
+    }
+
+    result
+}
+
+fn interpolate4(values: [[f32; 4]; 3], weights: [f32; 3]) -> [f32; 4] {
+    let mut result = [0.0; 4];
+    for (value, weight) in values.iter().zip(weights) {
+        for c in 0..4 {
+            result[c] += value[c] * weight;
+        }
+    }
+
+    result
+}
+
+// Samples with clamp-to-edge addressing, like the example renderer's samplers.
+fn sample(texture: &Texture, sampling: Sampling, tex_coord: [f32; 2]) -> [f32; 4] {
+    let texel = |x: i64, y: i64| -> [f32; 4] {
+        let x = cast_usize(cast_u32(x.clamp(0, i64::from(texture.width) - 1)));
+        let y = cast_usize(cast_u32(y.clamp(0, i64::from(texture.height) - 1)));
+        let i = (y * cast_usize(texture.width) + x) * 4;
+
+        [0, 1, 2, 3].map(|c| f32::from(texture.data[i + c]) / 255.0)
+    };
+
+    // Texel centers are at half coordinates.
+    let x = tex_coord[0] * texture.width as f32 - 0.5;
+    let y = tex_coord[1] * texture.height as f32 - 0.5;
+
+    if sampling == Sampling::NEAREST {
+        return texel(libm::roundf(x) as i64, libm::roundf(y) as i64);
+    }
+
+    let x0 = libm::floorf(x);
+    let y0 = libm::floorf(y);
+    let tx = x - x0;
+    let ty = y - y0;
+    let x0 = x0 as i64;
+    let y0 = y0 as i64;
+
+    let t00 = texel(x0, y0);
+    let t10 = texel(x0 + 1, y0);
+    let t01 = texel(x0, y0 + 1);
+    let t11 = texel(x0 + 1, y0 + 1);
+
+    [0, 1, 2, 3].map(|c| {
+        let top = t00[c] + (t10[c] - t00[c]) * tx;
+        let bottom = t01[c] + (t11[c] - t01[c]) * tx;
+
+        top + (bottom - top) * ty
+    })
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    if edge0 == edge1 {
+        return if x < edge0 { 0.0 } else { 1.0 };
+    }
+
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn unpack_color(color: u32) -> [f32; 4] {
+    color.to_be_bytes().map(|c| f32::from(c) / 255.0)
+}
+
+fn unorm_to_u8(c: f32) -> u8 {
+    libm::roundf(c.clamp(0.0, 1.0) * 255.0) as u8
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+    use alloc::vec;
+
+    use super::*;
+    use crate::core::{Align, CtrlFlags, Direction, Frame, Wrap};
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+
+    fn pixel(framebuffer: &[u8], framebuffer_width: u32, x: u32, y: u32) -> [u8; 4] {
+        let i = cast_usize(y * framebuffer_width + x) * 4;
+        [
+            framebuffer[i],
+            framebuffer[i + 1],
+            framebuffer[i + 2],
+            framebuffer[i + 3],
+        ]
+    }
+
+    #[test]
+    fn test_soft_rasterizer_fills_rects_once_and_clips_to_scissor() {
+        let mut harness = TestHarness::new(10.0, 10.0, font_source());
+
+        let mut rasterizer = SoftRasterizer::new();
+        let texture_id = rasterizer.add_texture_rgba8_unorm(1, 1, &[255; 4]);
+
+        harness.run_frame(|frame: &mut Frame<Global>| {
+            // The control's rect is the scissor rect of what it draws.
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::NONE);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 6.0, 8.0));
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(0.0);
+            // The pixel centers on the diagonal lie exactly on the edge shared
+            // by the rect's triangles.
+            ctrl.draw_rect(
+                Rect::new(0.0, 0.0, 8.0, 8.0),
+                Rect::ONE,
+                0xff000080,
+                texture_id,
+            );
+            ctrl.draw_rect(
+                Rect::new(2.0, 2.0, 2.0, 2.0),
+                Rect::ONE,
+                0x00ff00ff,
+                texture_id,
+            );
+            frame.pop_ctrl();
+        });
+
+        let mut framebuffer = vec![0; 10 * 10 * 4];
+        let (commands, vertices, indices) = harness.ui().draw_list();
+        rasterizer.draw(
+            &mut framebuffer,
+            10,
+            10,
+            1.0,
+            false,
+            commands,
+            vertices,
+            indices,
+        );
+
+        for y in 0..10 {
+            for x in 0..10 {
+                let expected = if (2..4).contains(&x) && (2..4).contains(&y) {
+                    [0, 255, 0, 255]
+                } else if x < 6 && y < 8 {
+                    [128, 0, 0, 64]
+                } else {
+                    [0, 0, 0, 0]
+                };
+
+                assert_eq!(pixel(&framebuffer, 10, x, y), expected, "{x} {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_soft_rasterizer_draws_ui() {
+        let mut harness = TestHarness::new(40.0, 20.0, font_source());
+
+        let mut rasterizer = SoftRasterizer::new();
+        let ui = harness.ui_mut();
+        let (width, height) = ui.font_atlas_image_size();
+        let texture_id = rasterizer.add_texture_rgba8_unorm(
+            u32::from(width),
+            u32::from(height),
+            ui.font_atlas_image_rgba8_unorm(),
+        );
+        rasterizer.set_texture_sdf(texture_id, ui.font_atlas_sdf());
+        ui.set_font_atlas_texture_id(texture_id);
+
+        harness.run_frame(|frame: &mut Frame<Global>| {
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_flags(CtrlFlags::NONE);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 40.0, 20.0));
+            ctrl.set_padding(0.0);
+            ctrl.set_border(0.0);
+            ctrl.set_margin(0.0);
+            ctrl.set_draw_self(true);
+            ctrl.set_draw_self_background_color(0x204060ff);
+            ctrl.draw_text(
+                "X",
+                Align::Center,
+                Align::Center,
+                Wrap::None,
+                Direction::Ltr,
+                0xffffffff,
+            );
+            frame.pop_ctrl();
+        });
+
+        // Drawn at twice the size, as if on a high DPI screen.
+        let mut framebuffer = vec![0; 80 * 40 * 4];
+        let (commands, vertices, indices) = harness.ui().draw_list();
+        rasterizer.draw(
+            &mut framebuffer,
+            80,
+            40,
+            2.0,
+            false,
+            commands,
+            vertices,
+            indices,
+        );
+
+        assert_eq!(pixel(&framebuffer, 80, 79, 39), [0x20, 0x40, 0x60, 0xff]);
+
+        // The white glyph brightens the background, but only where it is.
+        let glyph_pixel_count = framebuffer
+            .chunks_exact(4)
+            .filter(|pixel| pixel[0] > 0x80)
+            .count();
+        assert!(glyph_pixel_count > 0);
+        assert!(glyph_pixel_count < 80 * 40 / 4);
+    }
+}
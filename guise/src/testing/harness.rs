@@ -0,0 +1,266 @@
+use alloc::alloc::Global;
+use alloc::vec::Vec;
+
+use crate::core::{mix_id, DebugCtrl, FontSource, Frame, Inputs, Modifiers, Rect, Ui, Vec2};
+use crate::testing::DrawListSnapshot;
+
+/// Drives a [`Ui`] without a platform or a renderer, e.g. to test the UI logic
+/// of an application in CI.
+///
+/// Interactions span multiple frames, just like they would with a platform,
+/// e.g. a click is a frame with the button pressed and another with it
+/// released. The functions simulating input therefore take the function
+/// building the UI, and run as many frames with it as the interaction needs.
+/// Results of widgets can be collected by the build function into captured
+/// variables.
+///
+/// Controls are looked up by id path: the ids passed to
+/// [`Frame::push_ctrl`], or to widget functions, of the control and all its
+/// ancestors, from the top of its layer down. The ids are matched as if they
+/// were pushed without an id namespace, so controls built after
+/// [`Frame::push_id`] can not be looked up.
+pub struct TestHarness {
+    ui: Ui<Global>,
+}
+
+impl TestHarness {
+    pub fn new(window_width: f32, window_height: f32, font_source: FontSource<Global>) -> Self {
+        let ui = Ui::new_in(window_width, window_height, 1.0, font_source, Global);
+
+        Self { ui }
+    }
+
+    pub fn ui(&self) -> &Ui<Global> {
+        &self.ui
+    }
+
+    pub fn ui_mut(&mut self) -> &mut Ui<Global> {
+        &mut self.ui
+    }
+
+    /// Copies the draw list of the last frame, e.g. to compare it against a
+    /// checked-in snapshot.
+    pub fn draw_list_snapshot(&self) -> DrawListSnapshot {
+        let (commands, vertices, indices) = self.ui.draw_list();
+        DrawListSnapshot::new(commands, vertices, indices)
+    }
+
+    /// Builds a single frame with the input received so far.
+    pub fn run_frame<F, R>(&mut self, build: F) -> R
+    where
+        F: FnOnce(&mut Frame<Global>) -> R,
+    {
+        let mut frame = self.ui.begin_frame();
+        let result = build(&mut frame);
+        self.ui.end_frame();
+
+        result
+    }
+
+    /// Moves the cursor and runs a frame, so that whatever is under the
+    /// cursor becomes hovered.
+    pub fn move_cursor_to<F>(&mut self, x: f32, y: f32, build: F)
+    where
+        F: FnMut(&mut Frame<Global>),
+    {
+        self.ui.set_cursor_position(x, y);
+        self.run_frame(build);
+    }
+
+    /// Moves the cursor, presses the left mouse button, and releases it. Runs
+    /// three frames.
+    pub fn click_at<F>(&mut self, x: f32, y: f32, mut build: F)
+    where
+        F: FnMut(&mut Frame<Global>),
+    {
+        self.move_cursor_to(x, y, &mut build);
+
+        self.ui.press_inputs(Inputs::MB_LEFT);
+        self.run_frame(&mut build);
+
+        self.ui.release_inputs(Inputs::MB_LEFT);
+        self.run_frame(&mut build);
+    }
+
+    /// Presses the left mouse button at one position, moves the cursor to the
+    /// other, and releases the button there. Runs four frames.
+    pub fn drag<F>(&mut self, from: Vec2, to: Vec2, mut build: F)
+    where
+        F: FnMut(&mut Frame<Global>),
+    {
+        self.move_cursor_to(from.x, from.y, &mut build);
+
+        self.ui.press_inputs(Inputs::MB_LEFT);
+        self.run_frame(&mut build);
+
+        self.move_cursor_to(to.x, to.y, &mut build);
+
+        self.ui.release_inputs(Inputs::MB_LEFT);
+        self.run_frame(&mut build);
+    }
+
+    /// Sends the text one character per frame, as if typed.
+    pub fn type_text<F>(&mut self, text: &str, mut build: F)
+    where
+        F: FnMut(&mut Frame<Global>),
+    {
+        for c in text.chars() {
+            self.ui.send_character(c);
+            self.run_frame(&mut build);
+        }
+    }
+
+    /// Presses the inputs while holding the modifiers, and releases both.
+    /// Runs two frames.
+    pub fn press<F>(&mut self, inputs: Inputs, modifiers: Modifiers, mut build: F)
+    where
+        F: FnMut(&mut Frame<Global>),
+    {
+        self.ui.press_modifiers(modifiers);
+        self.ui.press_inputs(inputs);
+        self.run_frame(&mut build);
+
+        self.ui.release_inputs(inputs);
+        self.ui.release_modifiers(modifiers);
+        self.run_frame(&mut build);
+    }
+
+    /// Looks up the control in the last built frame. The base layer is
+    /// searched before the overlay.
+    pub fn find(&self, id_path: &[u32]) -> Option<DebugCtrl> {
+        let mut path: Vec<u32> = Vec::new();
+        for ctrl in self.ui.debug_ctrls() {
+            path.truncate(ctrl.depth);
+            path.push(ctrl.id);
+
+            if path.len() == id_path.len()
+                && path
+                    .iter()
+                    .zip(id_path)
+                    .all(|(&id, &local)| id == mix_id(0, local))
+            {
+                return Some(ctrl);
+            }
+        }
+
+        None
+    }
+
+    /// Where the control was laid out in the last built frame, in window
+    /// coordinates.
+    pub fn rect_of(&self, id_path: &[u32]) -> Option<Rect> {
+        self.find(id_path).map(|ctrl| ctrl.absolute_rect)
+    }
+
+    /// The center of the control's rect, e.g. for clicking it.
+    pub fn center_of(&self, id_path: &[u32]) -> Option<Vec2> {
+        self.rect_of(id_path)
+            .map(|rect| rect.min_point() + 0.5 * rect.size())
+    }
+
+    pub fn is_hovered(&self, id_path: &[u32]) -> bool {
+        self.ui.hovered_ctrl_id().is_some()
+            && self.find(id_path).map(|ctrl| ctrl.persistent_id) == self.ui.hovered_ctrl_id()
+    }
+
+    pub fn is_active(&self, id_path: &[u32]) -> bool {
+        self.ui.active_ctrl_id().is_some()
+            && self.find(id_path).map(|ctrl| ctrl.persistent_id) == self.ui.active_ctrl_id()
+    }
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use core::cell::Cell;
+
+    use arrayvec::ArrayString;
+
+    use super::*;
+    use crate::fixtures::font_source;
+    use crate::widgets::{begin_window, button, text_input_with_callback, TextInputAction};
+
+    fn new_harness() -> TestHarness {
+        TestHarness::new(800.0, 600.0, font_source())
+    }
+
+    #[test]
+    fn test_harness_clicks_button() {
+        let mut harness = new_harness();
+        let click_count = Cell::new(0);
+        let mut build = |frame: &mut Frame<Global>| {
+            if button(frame, 7, "Click me") {
+                click_count.set(click_count.get() + 1);
+            }
+        };
+
+        harness.run_frame(build);
+        let center = harness.center_of(&[7]).unwrap();
+
+        harness.move_cursor_to(center.x, center.y, &mut build);
+        assert!(harness.is_hovered(&[7]));
+
+        harness.ui_mut().press_inputs(Inputs::MB_LEFT);
+        harness.run_frame(build);
+        assert!(harness.is_active(&[7]));
+
+        harness.ui_mut().release_inputs(Inputs::MB_LEFT);
+        harness.run_frame(build);
+        assert!(!harness.is_active(&[7]));
+
+        harness.click_at(center.x, center.y, &mut build);
+        assert_eq!(click_count.get(), 2);
+
+        // Clicking outside of the button doesn't count.
+        harness.click_at(center.x, 500.0, &mut build);
+        assert_eq!(click_count.get(), 2);
+    }
+
+    #[test]
+    fn test_harness_types_into_text_input_and_submits() {
+        let mut harness = new_harness();
+        let mut text: ArrayString<64> = ArrayString::new();
+        let mut submitted: Option<ArrayString<64>> = None;
+        let mut build = |frame: &mut Frame<Global>| {
+            text_input_with_callback(frame, 3, &mut text, "Name", |data, text| {
+                if data.action == TextInputAction::Submit {
+                    submitted = Some(*text);
+                }
+            });
+        };
+
+        harness.run_frame(&mut build);
+
+        // The text field itself is a child of the row with the label.
+        let center = harness.center_of(&[3, 0]).unwrap();
+        harness.click_at(center.x, center.y, &mut build);
+        assert!(harness.is_active(&[3, 0]));
+
+        harness.type_text("Hello", &mut build);
+        harness.press(Inputs::KB_ENTER, Modifiers::empty(), &mut build);
+        assert!(!harness.is_active(&[3, 0]));
+
+        assert_eq!(text.as_str(), "Hello");
+        assert_eq!(submitted.as_ref().map(|s| s.as_str()), Some("Hello"));
+    }
+
+    #[test]
+    fn test_harness_drags_window() {
+        let mut harness = new_harness();
+        let mut build = |frame: &mut Frame<Global>| {
+            if let Some((window, _)) = begin_window(frame, 1, 100.0, 100.0, 200.0, 150.0) {
+                window.end(frame);
+            }
+        };
+
+        harness.run_frame(build);
+        let rect = harness.rect_of(&[1]).unwrap();
+        assert_eq!(rect.min_point(), Vec2::new(100.0, 100.0));
+
+        harness.drag(Vec2::new(150.0, 150.0), Vec2::new(250.0, 180.0), &mut build);
+        harness.run_frame(build);
+
+        let rect = harness.rect_of(&[1]).unwrap();
+        assert_eq!(rect.min_point(), Vec2::new(200.0, 130.0));
+        assert_eq!(rect.size(), Vec2::new(200.0, 150.0));
+    }
+}
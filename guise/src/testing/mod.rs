@@ -0,0 +1,5 @@
+mod harness;
+mod snapshot;
+
+pub use harness::*;
+pub use snapshot::*;
@@ -0,0 +1,717 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write as _};
+use core::mem;
+
+use crate::core::{Command, CommandKind, Rect, Vec2, Vertex};
+
+// Texture coordinates are normalized, so they get a tolerance of their own,
+// small enough to tell neighboring glyphs apart.
+const TEX_COORD_TOLERANCE: f32 = 1e-4;
+
+/// The draw list snapshot format starts with this header, followed by
+/// `command_count` [`Command`]s, `vertex_count` [`Vertex`]es, and
+/// `index_count` 32-bit indices. Like with baked font atlases, all values are
+/// stored in native endianness, without any padding between the sections.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+pub struct DrawListSnapshotHeader {
+    pub magic: [u8; 4],
+    pub version: u32,
+    pub command_count: u32,
+    pub vertex_count: u32,
+    pub index_count: u32,
+}
+
+impl DrawListSnapshotHeader {
+    pub const MAGIC: [u8; 4] = *b"GDLS";
+    pub const VERSION: u32 = 2;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawListSnapshotError {
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    // The data ends before all sections announced by the header are read.
+    UnexpectedEnd,
+    // The commands use more indices than there are, or an index refers to a
+    // vertex that doesn't exist.
+    InvalidIndex,
+}
+
+/// A copy of a draw list, e.g. from [`crate::Ui::draw_list`], for snapshot
+/// testing the rendering without a GPU.
+///
+/// Snapshots are stored in a compact binary format (see
+/// [`DrawListSnapshotHeader`]), and compared as lists of primitives, where
+/// positions can differ by a tolerance, so that float noise doesn't fail
+/// tests. For reviewing changes, snapshots can also be printed as text, with
+/// one primitive per line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawListSnapshot {
+    commands: Vec<Command>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl DrawListSnapshot {
+    pub fn new(commands: &[Command], vertices: &[Vertex], indices: &[u32]) -> Self {
+        Self {
+            commands: commands.to_vec(),
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DrawListSnapshotError> {
+        let mut reader = Reader { bytes };
+
+        let header: DrawListSnapshotHeader = reader.read()?;
+        if header.magic != DrawListSnapshotHeader::MAGIC {
+            return Err(DrawListSnapshotError::InvalidMagic);
+        }
+        if header.version != DrawListSnapshotHeader::VERSION {
+            return Err(DrawListSnapshotError::UnsupportedVersion(header.version));
+        }
+
+        let commands: Vec<Command> = reader.read_slice(header.command_count as usize)?;
+        let vertices: Vec<Vertex> = reader.read_slice(header.vertex_count as usize)?;
+        let indices: Vec<u32> = reader.read_slice(header.index_count as usize)?;
+
+        let command_index_count: usize = commands
+            .iter()
+            .map(|command| command.index_count as usize)
+            .sum();
+        if command_index_count > indices.len() {
+            return Err(DrawListSnapshotError::InvalidIndex);
+        }
+
+        let mut index_offset = 0;
+        for command in &commands {
+            let index_count = command.index_count as usize;
+            for &index in &indices[index_offset..index_offset + index_count] {
+                let vertex_idx = command.base_vertex as usize + index as usize;
+                if vertex_idx >= vertices.len() {
+                    return Err(DrawListSnapshotError::InvalidIndex);
+                }
+            }
+
+            index_offset += index_count;
+        }
+
+        Ok(Self {
+            commands,
+            vertices,
+            indices,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header = DrawListSnapshotHeader {
+            magic: DrawListSnapshotHeader::MAGIC,
+            version: DrawListSnapshotHeader::VERSION,
+            command_count: u32::try_from(self.commands.len()).unwrap(),
+            vertex_count: u32::try_from(self.vertices.len()).unwrap(),
+            index_count: u32::try_from(self.indices.len()).unwrap(),
+        };
+
+        let mut bytes = Vec::with_capacity(
+            mem::size_of::<DrawListSnapshotHeader>()
+                + mem::size_of_val(&self.commands[..])
+                + mem::size_of_val(&self.vertices[..])
+                + mem::size_of_val(&self.indices[..]),
+        );
+        bytes.extend_from_slice(bytemuck::bytes_of(&header));
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.commands));
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.vertices));
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.indices));
+
+        bytes
+    }
+
+    /// Splits the draw list into primitives, in draw order. Pairs of triangles
+    /// forming an axis-aligned rect with a single color, e.g. glyphs, become
+    /// rects. Everything else stays a triangle.
+    ///
+    /// Primitives don't depend on the order of vertices within a rect, or on
+    /// which vertex a triangle starts with, as long as it keeps its winding.
+    pub fn primitives(&self) -> Vec<SnapshotPrimitive> {
+        let mut primitives = Vec::new();
+
+        let mut index_offset = 0;
+        for command in &self.commands {
+            if command.kind == CommandKind::CALLBACK {
+                primitives.push(SnapshotPrimitive::Callback {
+                    user_id: command.callback_user_id,
+                    scissor_rect: command.scissor_rect,
+                });
+
+                continue;
+            }
+
+            let index_count = command.index_count as usize;
+            let indices = &self.indices[index_offset..index_offset + index_count];
+            index_offset += index_count;
+
+            let vertex = |index: u32| self.vertices[command.base_vertex as usize + index as usize];
+            let triangle = |i: usize| {
+                [
+                    vertex(indices[3 * i]),
+                    vertex(indices[3 * i + 1]),
+                    vertex(indices[3 * i + 2]),
+                ]
+            };
+
+            let triangle_count = index_count / 3;
+            let mut i = 0;
+            while i < triangle_count {
+                if i + 1 < triangle_count {
+                    if let Some((rect, texture_rect, color)) =
+                        triangles_to_rect(triangle(i), triangle(i + 1))
+                    {
+                        primitives.push(SnapshotPrimitive::Rect {
+                            rect,
+                            texture_rect,
+                            color,
+                            texture_id: command.texture_id,
+                            scissor_rect: command.scissor_rect,
+                        });
+
+                        i += 2;
+                        continue;
+                    }
+                }
+
+                // Start with the top-left most vertex, keeping the winding.
+                let mut vertices = triangle(i);
+                let first = (0..3)
+                    .min_by(|&a, &b| {
+                        let a = vertices[a].position;
+                        let b = vertices[b].position;
+                        a[1].total_cmp(&b[1]).then(a[0].total_cmp(&b[0]))
+                    })
+                    .unwrap();
+                vertices.rotate_left(first);
+
+                primitives.push(SnapshotPrimitive::Triangle {
+                    positions: vertices.map(|vertex| Vec2::from(vertex.position)),
+                    tex_coords: vertices.map(|vertex| Vec2::from(vertex.tex_coord)),
+                    colors: vertices.map(|vertex| vertex.color),
+                    texture_id: command.texture_id,
+                    scissor_rect: command.scissor_rect,
+                });
+
+                i += 1;
+            }
+        }
+
+        primitives
+    }
+
+    /// Prints the snapshot with one primitive per line.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for primitive in self.primitives() {
+            let _ = writeln!(text, "{primitive}");
+        }
+
+        text
+    }
+
+    /// Compares the primitives of the snapshots. Positions and sizes, including
+    /// those of scissor rects, may differ by up to the tolerance in logical
+    /// pixels. Colors and textures must match exactly. Returns the first
+    /// mismatching primitive.
+    pub fn compare(
+        &self,
+        expected: &DrawListSnapshot,
+        tolerance: f32,
+    ) -> Result<(), Box<SnapshotMismatch>> {
+        let actual_primitives = self.primitives();
+        let expected_primitives = expected.primitives();
+
+        let len = usize::max(actual_primitives.len(), expected_primitives.len());
+        for index in 0..len {
+            let actual = actual_primitives.get(index).copied();
+            let expected = expected_primitives.get(index).copied();
+
+            let matches = match (actual, expected) {
+                (Some(actual), Some(expected)) => actual.approx_eq(&expected, tolerance),
+                _ => false,
+            };
+
+            if !matches {
+                return Err(Box::new(SnapshotMismatch {
+                    index,
+                    actual,
+                    expected,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotPrimitive {
+    Rect {
+        rect: Rect,
+        texture_rect: Rect,
+        color: u32,
+        texture_id: u64,
+        scissor_rect: Rect,
+    },
+    Triangle {
+        positions: [Vec2; 3],
+        tex_coords: [Vec2; 3],
+        colors: [u32; 3],
+        texture_id: u64,
+        scissor_rect: Rect,
+    },
+    Callback {
+        user_id: u64,
+        scissor_rect: Rect,
+    },
+}
+
+impl SnapshotPrimitive {
+    pub fn approx_eq(&self, other: &SnapshotPrimitive, tolerance: f32) -> bool {
+        match (*self, *other) {
+            (
+                Self::Rect {
+                    rect: a_rect,
+                    texture_rect: a_texture_rect,
+                    color: a_color,
+                    texture_id: a_texture_id,
+                    scissor_rect: a_scissor_rect,
+                },
+                Self::Rect {
+                    rect: b_rect,
+                    texture_rect: b_texture_rect,
+                    color: b_color,
+                    texture_id: b_texture_id,
+                    scissor_rect: b_scissor_rect,
+                },
+            ) => {
+                rect_approx_eq(a_rect, b_rect, tolerance)
+                    && rect_approx_eq(a_texture_rect, b_texture_rect, TEX_COORD_TOLERANCE)
+                    && a_color == b_color
+                    && a_texture_id == b_texture_id
+                    && rect_approx_eq(a_scissor_rect, b_scissor_rect, tolerance)
+            }
+            (
+                Self::Triangle {
+                    positions: a_positions,
+                    tex_coords: a_tex_coords,
+                    colors: a_colors,
+                    texture_id: a_texture_id,
+                    scissor_rect: a_scissor_rect,
+                },
+                Self::Triangle {
+                    positions: b_positions,
+                    tex_coords: b_tex_coords,
+                    colors: b_colors,
+                    texture_id: b_texture_id,
+                    scissor_rect: b_scissor_rect,
+                },
+            ) => {
+                (0..3).all(|i| {
+                    vec2_approx_eq(a_positions[i], b_positions[i], tolerance)
+                        && vec2_approx_eq(a_tex_coords[i], b_tex_coords[i], TEX_COORD_TOLERANCE)
+                }) && a_colors == b_colors
+                    && a_texture_id == b_texture_id
+                    && rect_approx_eq(a_scissor_rect, b_scissor_rect, tolerance)
+            }
+            (
+                Self::Callback {
+                    user_id: a_user_id,
+                    scissor_rect: a_scissor_rect,
+                },
+                Self::Callback {
+                    user_id: b_user_id,
+                    scissor_rect: b_scissor_rect,
+                },
+            ) => {
+                a_user_id == b_user_id && rect_approx_eq(a_scissor_rect, b_scissor_rect, tolerance)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for SnapshotPrimitive {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Rect {
+                rect,
+                texture_rect,
+                color,
+                texture_id,
+                scissor_rect,
+            } => write!(
+                f,
+                "rect {:.2} {:.2} {:.2} {:.2} uv {:.4} {:.4} {:.4} {:.4} color {:08x} texture {} \
+                 scissor {:.2} {:.2} {:.2} {:.2}",
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                texture_rect.x,
+                texture_rect.y,
+                texture_rect.width,
+                texture_rect.height,
+                color,
+                texture_id,
+                scissor_rect.x,
+                scissor_rect.y,
+                scissor_rect.width,
+                scissor_rect.height,
+            ),
+            Self::Triangle {
+                positions,
+                tex_coords,
+                colors,
+                texture_id,
+                scissor_rect,
+            } => {
+                write!(f, "tri")?;
+                for position in positions {
+                    write!(f, " {:.2} {:.2}", position.x, position.y)?;
+                }
+                write!(f, " uv")?;
+                for tex_coord in tex_coords {
+                    write!(f, " {:.4} {:.4}", tex_coord.x, tex_coord.y)?;
+                }
+                write!(f, " color")?;
+                for color in colors {
+                    write!(f, " {color:08x}")?;
+                }
+                write!(
+                    f,
+                    " texture {} scissor {:.2} {:.2} {:.2} {:.2}",
+                    texture_id,
+                    scissor_rect.x,
+                    scissor_rect.y,
+                    scissor_rect.width,
+                    scissor_rect.height,
+                )
+            }
+            Self::Callback {
+                user_id,
+                scissor_rect,
+            } => write!(
+                f,
+                "callback {} scissor {:.2} {:.2} {:.2} {:.2}",
+                user_id, scissor_rect.x, scissor_rect.y, scissor_rect.width, scissor_rect.height,
+            ),
+        }
+    }
+}
+
+/// The first primitive that differs between two snapshots. Either side is
+/// [`None`], if that snapshot has fewer primitives.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapshotMismatch {
+    pub index: usize,
+    pub actual: Option<SnapshotPrimitive>,
+    pub expected: Option<SnapshotPrimitive>,
+}
+
+impl fmt::Display for SnapshotMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Primitive {} differs", self.index)?;
+        match self.actual {
+            Some(actual) => writeln!(f, "  actual:   {actual}")?,
+            None => writeln!(f, "  actual:   <none>")?,
+        }
+        match self.expected {
+            Some(expected) => write!(f, "  expected: {expected}"),
+            None => write!(f, "  expected: <none>"),
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DrawListSnapshotError> {
+        if self.bytes.len() < len {
+            return Err(DrawListSnapshotError::UnexpectedEnd);
+        }
+
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(bytes)
+    }
+
+    fn read<T: bytemuck::Pod>(&mut self) -> Result<T, DrawListSnapshotError> {
+        let bytes = self.read_bytes(mem::size_of::<T>())?;
+        Ok(bytemuck::pod_read_unaligned(bytes))
+    }
+
+    fn read_slice<T: bytemuck::Pod>(
+        &mut self,
+        count: usize,
+    ) -> Result<Vec<T>, DrawListSnapshotError> {
+        let len = count
+            .checked_mul(mem::size_of::<T>())
+            .ok_or(DrawListSnapshotError::UnexpectedEnd)?;
+        let bytes = self.read_bytes(len)?;
+
+        Ok(bytes
+            .chunks_exact(mem::size_of::<T>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect())
+    }
+}
+
+// Returns the rect, texture rect and color, if the two triangles cover an
+// axis-aligned rect with a single color, and texture coordinates that map its
+// corners to the corners of a texture rect.
+fn triangles_to_rect(a: [Vertex; 3], b: [Vertex; 3]) -> Option<(Rect, Rect, u32)> {
+    let mut corners: [Option<Vertex>; 4] = [None; 4];
+    let mut corner_count = 0;
+    for vertex in a.into_iter().chain(b) {
+        if !corners[..corner_count].contains(&Some(vertex)) {
+            if corner_count == 4 {
+                return None;
+            }
+
+            corners[corner_count] = Some(vertex);
+            corner_count += 1;
+        }
+    }
+
+    if corner_count != 4 {
+        return None;
+    }
+
+    let corners = corners.map(Option::unwrap);
+    let color = corners[0].color;
+
+    let mut min = Vec2::from(corners[0].position);
+    let mut max = min;
+    for corner in corners {
+        min = min.min(Vec2::from(corner.position));
+        max = max.max(Vec2::from(corner.position));
+    }
+
+    if min.x == max.x || min.y == max.y {
+        return None;
+    }
+
+    let find_corner = |x: f32, y: f32| {
+        corners
+            .iter()
+            .find(|corner| corner.position == [x, y])
+            .copied()
+    };
+
+    // All four corners of the rect must be present, and because there are
+    // exactly four distinct vertices, nothing else can be.
+    let top_left = find_corner(min.x, min.y)?;
+    let top_right = find_corner(max.x, min.y)?;
+    let bottom_left = find_corner(min.x, max.y)?;
+    let bottom_right = find_corner(max.x, max.y)?;
+
+    if corners.iter().any(|corner| corner.color != color)
+        || top_right.tex_coord != [bottom_right.tex_coord[0], top_left.tex_coord[1]]
+        || bottom_left.tex_coord != [top_left.tex_coord[0], bottom_right.tex_coord[1]]
+    {
+        return None;
+    }
+
+    let rect = Rect::from_points(min, max);
+    // NB: Not normalized, so that flipped textures can be told apart.
+    let texture_rect = Rect::new(
+        top_left.tex_coord[0],
+        top_left.tex_coord[1],
+        bottom_right.tex_coord[0] - top_left.tex_coord[0],
+        bottom_right.tex_coord[1] - top_left.tex_coord[1],
+    );
+
+    Some((rect, texture_rect, color))
+}
+
+fn vec2_approx_eq(a: Vec2, b: Vec2, tolerance: f32) -> bool {
+    libm::fabsf(a.x - b.x) <= tolerance && libm::fabsf(a.y - b.y) <= tolerance
+}
+
+fn rect_approx_eq(a: Rect, b: Rect, tolerance: f32) -> bool {
+    vec2_approx_eq(a.min_point(), b.min_point(), tolerance)
+        && vec2_approx_eq(a.max_point(), b.max_point(), tolerance)
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    extern crate std;
+
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::{Layout, Sampling};
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+    use crate::widgets::{button, text};
+
+    const TOLERANCE: f32 = 0.25;
+
+    // Compares the draw list of the last frame with a checked-in snapshot. Run
+    // with GUISE_UPDATE_SNAPSHOTS set to write the snapshots instead, together
+    // with their text versions for review.
+    fn assert_snapshot(name: &str, harness: &TestHarness) {
+        let snapshot = harness.draw_list_snapshot();
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("snapshots");
+        path.push(name);
+
+        if env::var_os("GUISE_UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path.with_extension("snap"), snapshot.to_bytes()).unwrap();
+            fs::write(path.with_extension("txt"), snapshot.to_text()).unwrap();
+
+            return;
+        }
+
+        let bytes = fs::read(path.with_extension("snap")).unwrap_or_else(|err| {
+            panic!(
+                "Failed to read snapshot {name}: {err}. Set GUISE_UPDATE_SNAPSHOTS to create it."
+            )
+        });
+        let expected = DrawListSnapshot::from_bytes(&bytes).unwrap();
+
+        if let Err(mismatch) = snapshot.compare(&expected, TOLERANCE) {
+            panic!("Snapshot {name} differs:\n{mismatch}");
+        }
+    }
+
+    fn vertex(x: f32, y: f32, u: f32, v: f32) -> Vertex {
+        Vertex {
+            position: [x, y],
+            tex_coord: [u, v],
+            color: 0xff0000ff,
+        }
+    }
+
+    fn quad(vertices: [Vertex; 4], indices: [u32; 6]) -> DrawListSnapshot {
+        let command = Command {
+            scissor_rect: Rect::new(0.0, 0.0, 100.0, 100.0),
+            texture_id: 1,
+            index_count: 6,
+            base_vertex: 0,
+            vertex_offset: 0,
+            vertex_count: 4,
+            kind: CommandKind::DRAW,
+            sampling: Sampling::LINEAR,
+            callback_user_id: 0,
+        };
+
+        DrawListSnapshot::new(&[command], &vertices, &indices)
+    }
+
+    fn square(offset: f32) -> DrawListSnapshot {
+        quad(
+            [
+                vertex(10.0 + offset, 10.0, 0.0, 0.0),
+                vertex(20.0 + offset, 10.0, 1.0, 0.0),
+                vertex(20.0 + offset, 20.0, 1.0, 1.0),
+                vertex(10.0 + offset, 20.0, 0.0, 1.0),
+            ],
+            [0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    #[test]
+    fn test_snapshot_bytes_roundtrip() {
+        let snapshot = square(0.0);
+        let bytes = snapshot.to_bytes();
+        assert_eq!(DrawListSnapshot::from_bytes(&bytes), Ok(snapshot));
+
+        assert_eq!(
+            DrawListSnapshot::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DrawListSnapshotError::UnexpectedEnd),
+        );
+
+        let mut bytes = bytes;
+        bytes[0] = b'X';
+        assert_eq!(
+            DrawListSnapshot::from_bytes(&bytes),
+            Err(DrawListSnapshotError::InvalidMagic),
+        );
+    }
+
+    #[test]
+    fn test_snapshot_compare_tolerance() {
+        let snapshot = square(0.0);
+        assert_eq!(snapshot.primitives(), [SnapshotPrimitive::Rect {
+            rect: Rect::new(10.0, 10.0, 10.0, 10.0),
+            texture_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+            color: 0xff0000ff,
+            texture_id: 1,
+            scissor_rect: Rect::new(0.0, 0.0, 100.0, 100.0),
+        }],);
+
+        assert!(snapshot.compare(&square(0.2), TOLERANCE).is_ok());
+
+        let mismatch = snapshot.compare(&square(0.5), TOLERANCE).unwrap_err();
+        assert_eq!(mismatch.index, 0);
+        assert_eq!(mismatch.actual, Some(snapshot.primitives()[0]));
+    }
+
+    #[test]
+    fn test_snapshot_compare_ignores_quad_vertex_order() {
+        // The same square, split along the other diagonal, with vertices
+        // starting in a different corner.
+        let other = quad(
+            [
+                vertex(20.0, 20.0, 1.0, 1.0),
+                vertex(10.0, 20.0, 0.0, 1.0),
+                vertex(10.0, 10.0, 0.0, 0.0),
+                vertex(20.0, 10.0, 1.0, 0.0),
+            ],
+            [0, 1, 3, 1, 2, 3],
+        );
+
+        assert!(square(0.0).compare(&other, TOLERANCE).is_ok());
+        assert_eq!(square(0.0).to_text(), other.to_text());
+    }
+
+    #[test]
+    fn test_snapshot_button() {
+        let mut harness = TestHarness::new(320.0, 240.0, font_source());
+        harness.run_frame(|frame| {
+            button(frame, 0, "Snapshot");
+        });
+
+        assert_snapshot("button", &harness);
+    }
+
+    #[test]
+    fn test_snapshot_wrapped_text() {
+        let mut harness = TestHarness::new(320.0, 240.0, font_source());
+        harness.run_frame(|frame| {
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_layout(Layout::Vertical);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 120.0, 240.0));
+
+            text(
+                frame,
+                0,
+                "The quick brown fox jumps over the lazy dog, wrapping onto several lines.",
+            );
+
+            frame.pop_ctrl();
+        });
+
+        assert_snapshot("wrapped_text", &harness);
+    }
+}
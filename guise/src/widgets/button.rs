@@ -1,11 +1,67 @@
 use core::alloc::Allocator;
+use core::mem;
 
-use crate::core::{Align, CtrlFlags, Frame, Inputs, Layout, Rect, Wrap};
-use crate::widgets::theme::Theme;
+use crate::core::{
+    color_lerp,
+    Align,
+    Ctrl,
+    CtrlFlags,
+    CtrlState,
+    CursorIcon,
+    Direction,
+    Frame,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Wrap,
+};
+use crate::widgets::response::Response;
 use crate::widgets::tooltip;
 
+const DEFAULT_OPTIONS: ButtonOptions = ButtonOptions {
+    enabled: true,
+    repeat_delay: 0.4,
+    repeat_interval: None,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ButtonOptions {
+    /// Disabled buttons are drawn with dimmed colors, don't react to the
+    /// cursor and never fire.
+    pub enabled: bool,
+    /// Seconds the button must be held down, before it starts repeating.
+    pub repeat_delay: f32,
+    /// If set, the button fires as soon as it is pressed instead of when
+    /// released, and then repeatedly every this many seconds while held down
+    /// and hovered, e.g. for increment/decrement buttons. Repeating requires
+    /// the frame time to be passed to
+    /// [`crate::Ui::begin_frame_with_delta_time`], otherwise the button only
+    /// fires on press.
+    pub repeat_interval: Option<f32>,
+}
+
+impl Default for ButtonOptions {
+    fn default() -> Self {
+        DEFAULT_OPTIONS
+    }
+}
+
 pub fn button<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, label: &str) -> bool {
-    do_button(frame, id, label, None, None, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_button(
+            frame,
+            id,
+            label,
+            None,
+            None,
+            None,
+            false,
+            &DEFAULT_OPTIONS,
+            theme,
+        )
+        .clicked
+    })
 }
 
 pub fn button_with_theme<A: Allocator + Clone>(
@@ -14,7 +70,18 @@ pub fn button_with_theme<A: Allocator + Clone>(
     label: &str,
     theme: &Theme,
 ) -> bool {
-    do_button(frame, id, label, None, None, theme)
+    do_button(
+        frame,
+        id,
+        label,
+        None,
+        None,
+        None,
+        false,
+        &DEFAULT_OPTIONS,
+        theme,
+    )
+    .clicked
 }
 
 pub fn button_with_tooltip<A: Allocator + Clone>(
@@ -23,7 +90,20 @@ pub fn button_with_tooltip<A: Allocator + Clone>(
     label: &str,
     tooltip: &str,
 ) -> bool {
-    do_button(frame, id, label, None, Some(tooltip), &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_button(
+            frame,
+            id,
+            label,
+            None,
+            None,
+            Some(tooltip),
+            false,
+            &DEFAULT_OPTIONS,
+            theme,
+        )
+        .clicked
+    })
 }
 
 pub fn button_with_tooltip_theme<A: Allocator + Clone>(
@@ -33,40 +113,169 @@ pub fn button_with_tooltip_theme<A: Allocator + Clone>(
     tooltip: &str,
     theme: &Theme,
 ) -> bool {
-    do_button(frame, id, label, None, Some(tooltip), theme)
+    do_button(
+        frame,
+        id,
+        label,
+        None,
+        None,
+        Some(tooltip),
+        false,
+        &DEFAULT_OPTIONS,
+        theme,
+    )
+    .clicked
 }
 
-pub fn image_button<A: Allocator + Clone>(
+pub fn button_with_options<A: Allocator + Clone>(
     frame: &mut Frame<A>,
     id: u32,
-    image_texture_id: u64,
+    label: &str,
+    options: &ButtonOptions,
 ) -> bool {
-    do_button(frame, id, "", Some(image_texture_id), None, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_button(frame, id, label, None, None, None, false, options, theme).clicked
+    })
 }
 
-pub fn image_button_with_theme<A: Allocator + Clone>(
+pub fn button_with_options_theme<A: Allocator + Clone>(
     frame: &mut Frame<A>,
     id: u32,
-    image_texture_id: u64,
+    label: &str,
+    options: &ButtonOptions,
     theme: &Theme,
 ) -> bool {
-    do_button(frame, id, "", Some(image_texture_id), None, theme)
+    do_button(frame, id, label, None, None, None, false, options, theme).clicked
 }
 
-pub fn image_button_with_tooltip<A: Allocator + Clone>(
+/// Like [`button`], but reports hover, whether the button is held down, and
+/// where it was laid out, in addition to clicks.
+#[inline]
+pub fn button_response<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+) -> Response {
+    frame.with_theme(|frame, theme| button_response_with_theme(frame, id, label, theme))
+}
+
+pub fn button_response_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    theme: &Theme,
+) -> Response {
+    do_button(
+        frame,
+        id,
+        label,
+        None,
+        None,
+        None,
+        false,
+        &DEFAULT_OPTIONS,
+        theme,
+    )
+}
+
+/// A button that is drawn as active while `value` is set, and flips it when
+/// clicked, e.g. for toolbars. Returns whether the value changed.
+pub fn toggle_button<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut bool,
+    label: &str,
+) -> bool {
+    frame.with_theme(|frame, theme| toggle_button_with_theme(frame, id, value, label, theme))
+}
+
+pub fn toggle_button_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut bool,
+    label: &str,
+    theme: &Theme,
+) -> bool {
+    let clicked = do_button(
+        frame,
+        id,
+        label,
+        None,
+        None,
+        None,
+        *value,
+        &DEFAULT_OPTIONS,
+        theme,
+    )
+    .clicked;
+    if clicked {
+        *value = !*value;
+    }
+
+    clicked
+}
+
+pub fn image_button<A: Allocator + Clone>(
     frame: &mut Frame<A>,
     id: u32,
     image_texture_id: u64,
-    tooltip: &str,
+) -> bool {
+    frame.with_theme(|frame, theme| {
+        do_button(
+            frame,
+            id,
+            "",
+            None,
+            Some(image_texture_id),
+            None,
+            false,
+            &DEFAULT_OPTIONS,
+            theme,
+        )
+        .clicked
+    })
+}
+
+pub fn image_button_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    image_texture_id: u64,
+    theme: &Theme,
 ) -> bool {
     do_button(
         frame,
         id,
         "",
+        None,
         Some(image_texture_id),
-        Some(tooltip),
-        &Theme::DEFAULT,
+        None,
+        false,
+        &DEFAULT_OPTIONS,
+        theme,
     )
+    .clicked
+}
+
+pub fn image_button_with_tooltip<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    image_texture_id: u64,
+    tooltip: &str,
+) -> bool {
+    frame.with_theme(|frame, theme| {
+        do_button(
+            frame,
+            id,
+            "",
+            None,
+            Some(image_texture_id),
+            Some(tooltip),
+            false,
+            &DEFAULT_OPTIONS,
+            theme,
+        )
+        .clicked
+    })
 }
 
 pub fn image_button_with_tooltip_theme<A: Allocator + Clone>(
@@ -76,20 +285,38 @@ pub fn image_button_with_tooltip_theme<A: Allocator + Clone>(
     tooltip: &str,
     theme: &Theme,
 ) -> bool {
-    do_button(frame, id, "", Some(image_texture_id), Some(tooltip), theme)
+    do_button(
+        frame,
+        id,
+        "",
+        None,
+        Some(image_texture_id),
+        Some(tooltip),
+        false,
+        &DEFAULT_OPTIONS,
+        theme,
+    )
+    .clicked
 }
 
-fn do_button<A: Allocator + Clone>(
+// Toggled buttons are drawn with the toggled colors, unless pressed. The
+// label highlight draws characters at the given byte indices in a different
+// color, see Ctrl::draw_text_highlighted.
+pub(crate) fn do_button<A: Allocator + Clone>(
     frame: &mut Frame<A>,
     id: u32,
     label: &str,
+    label_highlight: Option<(&[usize], u32)>,
     image_texture_id: Option<u64>,
     tooltip: Option<&str>,
+    toggled: bool,
+    options: &ButtonOptions,
     theme: &Theme,
-) -> bool {
+) -> Response {
     let parent_size = frame.ctrl_inner_size();
-    let lmb_pressed = frame.inputs_pressed() == Inputs::MB_LEFT;
-    let lmb_released = frame.inputs_released() == Inputs::MB_LEFT;
+    let delta_time = frame.delta_time();
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
 
     let (width, height, border, margin) = if image_texture_id.is_some() {
         (
@@ -108,6 +335,8 @@ fn do_button<A: Allocator + Clone>(
     };
 
     let mut ctrl = frame.push_ctrl(id);
+    // NB: Disabled buttons still capture hover, so that clicking them doesn't
+    // interact with whatever is behind them.
     ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
     ctrl.set_layout(Layout::Vertical);
     ctrl.set_rect(Rect::new(0.0, 0.0, width, height));
@@ -115,60 +344,133 @@ fn do_button<A: Allocator + Clone>(
     ctrl.set_border(border);
     ctrl.set_margin(margin);
 
-    let hovered = ctrl.is_hovered();
+    let hovered = ctrl.is_hovered() && options.enabled;
     let active = ctrl.is_active();
 
-    let (active, changed) = if active && lmb_released {
-        ctrl.set_active(false);
-        if hovered {
-            // Make the control inactive once again after release, as the
-            // platform may not be running us on every frame, but only for
-            // new events. Also better latency this way.
-            (false, true)
+    if hovered {
+        ctrl.set_cursor_icon(CursorIcon::Hand);
+    }
+
+    // NB: Whether the control is active is read back from the control when
+    // picking colors, so we only need to know about clicks here.
+    let clicked = if !options.enabled {
+        if active {
+            ctrl.set_active(false);
+        }
+
+        false
+    } else if let Some(repeat_interval) = options.repeat_interval {
+        let state = cast_state_mut(ctrl.state_mut());
+
+        if active && lmb_released {
+            ctrl.set_active(false);
+            false
+        } else if hovered && lmb_pressed {
+            state.held_time = 0.0;
+            ctrl.set_active(true);
+            true
+        } else if active && hovered {
+            let mut fired = false;
+            if let Some(delta_time) = delta_time {
+                let previous_held_time = state.held_time;
+                state.held_time += delta_time;
+
+                fired = repeat_fired(
+                    previous_held_time,
+                    state.held_time,
+                    options.repeat_delay,
+                    repeat_interval,
+                );
+            }
+
+            fired
         } else {
-            (false, false)
+            false
         }
+    } else if active && lmb_released {
+        ctrl.set_active(false);
+        // Make the control inactive once again after release, as the
+        // platform may not be running us on every frame, but only for new
+        // events. Also better latency this way.
+        hovered
     } else if hovered && lmb_pressed {
         ctrl.set_active(true);
-        (true, false)
+        false
     } else {
-        (active, false)
+        false
     };
 
-    let (text_color, background_color, border_color) =
-        match (image_texture_id.is_some(), hovered, active) {
-            (false, false, false) => (
-                theme.button_text_color,
-                theme.button_background_color,
-                theme.button_border_color,
-            ),
-            (false, true, false) => (
-                theme.button_text_color_hovered,
-                theme.button_background_color_hovered,
-                theme.button_border_color_hovered,
-            ),
-            (false, _, true) => (
+    let mut image_color = 0xffffffff;
+    let [text_color, background_color, border_color] = if !options.enabled {
+        if image_texture_id.is_some() {
+            image_color = theme.image_button_image_color_disabled;
+            [
+                0,
+                theme.image_button_background_color_disabled,
+                theme.image_button_border_color_disabled,
+            ]
+        } else {
+            [
+                theme.button_text_color_disabled,
+                theme.button_background_color_disabled,
+                theme.button_border_color_disabled,
+            ]
+        }
+    } else {
+        let (normal, hovered, active) = if image_texture_id.is_some() {
+            (
+                [
+                    0,
+                    theme.image_button_background_color,
+                    theme.image_button_border_color,
+                ],
+                [
+                    0,
+                    theme.image_button_background_color_hovered,
+                    theme.image_button_border_color_hovered,
+                ],
+                [
+                    0,
+                    theme.image_button_background_color_active,
+                    theme.image_button_border_color_active,
+                ],
+            )
+        } else {
+            let active = [
                 theme.button_text_color_active,
                 theme.button_background_color_active,
                 theme.button_border_color_active,
-            ),
-            (true, false, false) => (
-                0,
-                theme.image_button_background_color,
-                theme.image_button_border_color,
-            ),
-            (true, true, false) => (
-                0,
-                theme.image_button_background_color_hovered,
-                theme.image_button_border_color_hovered,
-            ),
-            (true, _, true) => (
-                0,
-                theme.image_button_background_color_active,
-                theme.image_button_border_color_active,
-            ),
+            ];
+
+            if toggled {
+                // Toggled buttons don't react to hover.
+                let toggled = [
+                    theme.button_text_color_toggled,
+                    theme.button_background_color_toggled,
+                    theme.button_border_color_toggled,
+                ];
+
+                (toggled, toggled, active)
+            } else {
+                (
+                    [
+                        theme.button_text_color,
+                        theme.button_background_color,
+                        theme.button_border_color,
+                    ],
+                    [
+                        theme.button_text_color_hovered,
+                        theme.button_background_color_hovered,
+                        theme.button_border_color_hovered,
+                    ],
+                    active,
+                )
+            }
         };
 
+        animated_colors(&mut ctrl, normal, hovered, active, theme.animation_speed)
+    };
+
     ctrl.set_draw_self(true);
     ctrl.set_draw_self_border_color(border_color);
     ctrl.set_draw_self_background_color(background_color);
@@ -177,20 +479,371 @@ fn do_button<A: Allocator + Clone>(
         ctrl.draw_rect(
             Rect::new(0.0, 0.0, width, height),
             Rect::ONE,
-            0xffffffff,
+            image_color,
             image_texture_id,
         )
+    } else if let Some((highlighted, highlight_color)) = label_highlight {
+        ctrl.draw_text_highlighted(
+            label,
+            Align::Center,
+            Align::Center,
+            Wrap::Word,
+            Direction::Ltr,
+            text_color,
+            highlighted,
+            highlight_color,
+        );
     } else {
-        ctrl.draw_text(label, Align::Center, Align::Center, Wrap::Word, text_color);
+        ctrl.draw_text(
+            label,
+            Align::Center,
+            Align::Center,
+            Wrap::Word,
+            Direction::Ltr,
+            text_color,
+        );
     }
 
+    let response = Response {
+        clicked,
+        changed: false,
+        hovered,
+        held: ctrl.is_active(),
+        rect: ctrl.absolute_rect(),
+    };
+
+    frame.pop_ctrl();
+
     if let Some(tooltip) = tooltip {
-        if hovered {
-            tooltip::tooltip_with_theme(frame, 0, tooltip, theme);
+        if options.enabled && tooltip::last_ctrl_tooltip_due(frame, theme) {
+            tooltip::tooltip_with_theme(frame, id, tooltip, theme);
         }
     }
 
-    frame.pop_ctrl();
+    response
+}
+
+// Whether something held down for between previous_time and time seconds
+// should repeat, if it repeats first after delay, and then every interval.
+fn repeat_fired(previous_time: f32, time: f32, delay: f32, interval: f32) -> bool {
+    if time < delay {
+        return false;
+    }
+
+    if previous_time < delay || interval <= 0.0 {
+        return true;
+    }
+
+    let previous_count = libm::floorf((previous_time - delay) / interval);
+    let count = libm::floorf((time - delay) / interval);
+
+    count > previous_count
+}
+
+// Blends the control's colors towards the hovered and then the active ones, as
+// the control's hover and active transitions progress. With zero animation
+// speed, the transitions are exactly 0 or 1, so the colors snap between states.
+pub(crate) fn animated_colors<A: Allocator + Clone, const N: usize>(
+    ctrl: &mut Ctrl<A>,
+    normal: [u32; N],
+    hovered: [u32; N],
+    active: [u32; N],
+    animation_speed: f32,
+) -> [u32; N] {
+    let hover_t = ctrl.hover_t(animation_speed);
+    let active_t = ctrl.active_t(animation_speed);
+
+    let mut colors = normal;
+    for ((color, hovered), active) in colors.iter_mut().zip(hovered).zip(active) {
+        *color = color_lerp(color_lerp(*color, hovered, hover_t), active, active_t);
+    }
+
+    colors
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    // Seconds the button has been held down and hovered, when repeating.
+    held_time: f32,
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::{disabled_color, Ui};
+    use crate::fixtures::new_ui;
+
+    fn new_ui_with_hovered_button() -> Ui<Global> {
+        let mut ui = new_ui();
+
+        // First frame lays out the control, so that it can be hovered.
+        build(&mut ui, &DEFAULT_OPTIONS);
+        ui.set_cursor_position(100.0, 15.0);
+
+        ui
+    }
+
+    fn build(ui: &mut Ui<Global>, options: &ButtonOptions) -> bool {
+        build_with_delta_time(ui, options, 0.0)
+    }
+
+    fn build_with_delta_time(
+        ui: &mut Ui<Global>,
+        options: &ButtonOptions,
+        delta_time: f32,
+    ) -> bool {
+        let mut frame = ui.begin_frame_with_delta_time(delta_time);
+        let clicked = button_with_options(&mut frame, 0, "Label", options);
+        ui.end_frame();
+
+        clicked
+    }
+
+    #[test]
+    fn test_button_fires_on_release() {
+        let mut ui = new_ui_with_hovered_button();
+
+        build(&mut ui, &DEFAULT_OPTIONS);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert!(!build(&mut ui, &DEFAULT_OPTIONS));
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert!(build(&mut ui, &DEFAULT_OPTIONS));
+    }
+
+    #[test]
+    fn test_button_requests_hand_cursor_only_when_hovered() {
+        let mut ui = new_ui_with_hovered_button();
+
+        build(&mut ui, &DEFAULT_OPTIONS);
+        assert_eq!(ui.cursor_icon(), CursorIcon::Hand);
+
+        ui.set_cursor_position(700.0, 500.0);
+        build(&mut ui, &DEFAULT_OPTIONS);
+        assert_eq!(ui.cursor_icon(), CursorIcon::Default);
+
+        // Disabled buttons can't be clicked, so they don't suggest it.
+        ui.set_cursor_position(100.0, 15.0);
+        build(&mut ui, &ButtonOptions {
+            enabled: false,
+            ..DEFAULT_OPTIONS
+        });
+        assert_eq!(ui.cursor_icon(), CursorIcon::Default);
+    }
+
+    #[test]
+    fn test_button_does_not_fire_when_released_elsewhere() {
+        let mut ui = new_ui_with_hovered_button();
+
+        build(&mut ui, &DEFAULT_OPTIONS);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert!(!build(&mut ui, &DEFAULT_OPTIONS));
+        ui.set_cursor_position(100.0, 300.0);
+        assert!(!build(&mut ui, &DEFAULT_OPTIONS));
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert!(!build(&mut ui, &DEFAULT_OPTIONS));
 
-    changed
+        // Coming back doesn't fire either.
+        ui.set_cursor_position(100.0, 15.0);
+        assert!(!build(&mut ui, &DEFAULT_OPTIONS));
+    }
+
+    #[test]
+    fn test_disabled_button_never_fires() {
+        let mut ui = new_ui_with_hovered_button();
+        let options = ButtonOptions {
+            enabled: false,
+            ..DEFAULT_OPTIONS
+        };
+
+        build(&mut ui, &options);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert!(!build(&mut ui, &options));
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert!(!build(&mut ui, &options));
+        assert!(ui.want_capture_mouse());
+    }
+
+    #[test]
+    fn test_button_response_reports_hover_hold_and_rect() {
+        let mut ui = new_ui_with_hovered_button();
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            let response = button_response(&mut frame, 0, "Label");
+            ui.end_frame();
+
+            response
+        };
+
+        let theme = Theme::DEFAULT;
+        let margin = theme.button_margin;
+        let expected_rect = Rect::new(margin, margin, 800.0 - 2.0 * margin, theme.button_height);
+
+        let response = build(&mut ui);
+        assert!(response.hovered);
+        assert!(!response.held);
+        assert_eq!(response.rect, expected_rect);
+
+        ui.press_inputs(Inputs::MB_LEFT);
+        let response = build(&mut ui);
+        assert!(response.held);
+        assert!(!response.clicked);
+
+        ui.release_inputs(Inputs::MB_LEFT);
+        let response = build(&mut ui);
+        assert!(response.clicked);
+        assert!(!response.held);
+        assert!(!response.changed);
+    }
+
+    #[test]
+    fn test_toggle_button_flips_value() {
+        let mut ui = new_ui_with_hovered_button();
+        let mut value = false;
+
+        let mut build_toggle = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            let changed = toggle_button(&mut frame, 0, &mut value, "Label");
+            ui.end_frame();
+
+            changed
+        };
+
+        build_toggle(&mut ui);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert!(!build_toggle(&mut ui));
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert!(build_toggle(&mut ui));
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert!(!build_toggle(&mut ui));
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert!(build_toggle(&mut ui));
+        assert!(!value);
+    }
+
+    #[test]
+    fn test_repeat_button_fires_while_held() {
+        let mut ui = new_ui_with_hovered_button();
+        let options = ButtonOptions {
+            repeat_delay: 0.5,
+            repeat_interval: Some(0.1),
+            ..DEFAULT_OPTIONS
+        };
+
+        build(&mut ui, &options);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert!(build_with_delta_time(&mut ui, &options, 0.04));
+
+        let mut fire_count = 0;
+        for _ in 0..25 {
+            if build_with_delta_time(&mut ui, &options, 0.04) {
+                fire_count += 1;
+            }
+        }
+
+        // Held for 1s: fires at 0.5s, then every 0.1s.
+        assert_eq!(fire_count, 6);
+
+        // Holding outside of the button pauses repeating.
+        ui.set_cursor_position(100.0, 300.0);
+        for _ in 0..25 {
+            assert!(!build_with_delta_time(&mut ui, &options, 0.04));
+        }
+
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert!(!build_with_delta_time(&mut ui, &options, 0.04));
+    }
+
+    fn has_vertex_with_color(ui: &Ui<Global>, color: u32) -> bool {
+        let (_, vertices, _) = ui.draw_list();
+        vertices.iter().any(|vertex| vertex.color == color)
+    }
+
+    #[test]
+    fn test_button_uses_ui_theme() {
+        const BORDER_COLOR: u32 = 0x12345678;
+        const BORDER_COLOR_PUSHED: u32 = 0x87654321;
+
+        let mut ui = new_ui();
+        ui.set_cursor_position(700.0, 500.0);
+        ui.set_theme(Theme {
+            button_border_color: BORDER_COLOR,
+            ..Theme::DEFAULT
+        });
+
+        build(&mut ui, &DEFAULT_OPTIONS);
+        assert!(has_vertex_with_color(&ui, BORDER_COLOR));
+
+        let mut frame = ui.begin_frame();
+        frame.push_theme(&Theme {
+            button_border_color: BORDER_COLOR_PUSHED,
+            ..Theme::DEFAULT
+        });
+        button(&mut frame, 0, "Label");
+        frame.pop_theme();
+        ui.end_frame();
+
+        assert!(has_vertex_with_color(&ui, BORDER_COLOR_PUSHED));
+        assert!(!has_vertex_with_color(&ui, BORDER_COLOR));
+    }
+
+    #[test]
+    fn test_button_in_disabled_scope_never_fires_and_fades() {
+        let mut ui = new_ui_with_hovered_button();
+
+        let build_disabled = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            frame.push_disabled(true);
+            let clicked = button(&mut frame, 0, "Label");
+            let hovered = frame.last_ctrl_is_hovered();
+            frame.pop_disabled();
+            ui.end_frame();
+
+            (clicked, hovered)
+        };
+
+        build_disabled(&mut ui);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert_eq!(build_disabled(&mut ui), (false, false));
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert_eq!(build_disabled(&mut ui), (false, false));
+
+        // The click is still captured, so it doesn't reach the platform.
+        assert!(ui.want_capture_mouse());
+
+        assert!(has_vertex_with_color(
+            &ui,
+            disabled_color(Theme::DEFAULT.button_border_color),
+        ));
+        assert!(!has_vertex_with_color(
+            &ui,
+            Theme::DEFAULT.button_border_color,
+        ));
+    }
+
+    #[test]
+    fn test_button_animates_hover_colors() {
+        let mut ui = new_ui_with_hovered_button();
+        ui.set_theme(Theme {
+            button_border_color: 0x000000ff,
+            button_border_color_hovered: 0x808080ff,
+            animation_speed: 2.0,
+            ..Theme::DEFAULT
+        });
+
+        // Hovered from the start, so halfway there after a quarter second.
+        // Halfway in linear space, which is lighter than halfway in sRGB.
+        build_with_delta_time(&mut ui, &DEFAULT_OPTIONS, 0.25);
+        assert!(has_vertex_with_color(&ui, 0x5c5c5cff));
+
+        build_with_delta_time(&mut ui, &DEFAULT_OPTIONS, 0.25);
+        assert!(has_vertex_with_color(&ui, 0x808080ff));
+    }
 }
@@ -1,7 +1,8 @@
 use core::alloc::Allocator;
 
-use crate::core::{Align, CtrlFlags, Frame, Inputs, Layout, Rect, Wrap};
-use crate::widgets::theme::Theme;
+use crate::core::{Align, CtrlFlags, Direction, Frame, Inputs, Layout, Rect, Theme, Wrap};
+use crate::widgets::button::animated_colors;
+use crate::widgets::response::Response;
 
 #[inline]
 pub fn checkbox<A: Allocator + Clone>(
@@ -10,7 +11,7 @@ pub fn checkbox<A: Allocator + Clone>(
     value: &mut bool,
     label: &str,
 ) -> bool {
-    checkbox_with_theme(frame, id, value, label, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| checkbox_with_theme(frame, id, value, label, theme))
 }
 
 pub fn checkbox_with_theme<A: Allocator + Clone>(
@@ -20,10 +21,119 @@ pub fn checkbox_with_theme<A: Allocator + Clone>(
     label: &str,
     theme: &Theme,
 ) -> bool {
+    checkbox_response_with_theme(frame, id, value, label, theme).changed
+}
+
+/// Like [`checkbox`], but reports hover, whether the checkbox is held down,
+/// and where it was laid out, in addition to changes.
+#[inline]
+pub fn checkbox_response<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut bool,
+    label: &str,
+) -> Response {
+    frame.with_theme(|frame, theme| checkbox_response_with_theme(frame, id, value, label, theme))
+}
+
+pub fn checkbox_response_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut bool,
+    label: &str,
+    theme: &Theme,
+) -> Response {
+    let mut response = do_checkbox(frame, id, Some(*value), label, theme);
+    if response.clicked {
+        *value = !*value;
+        response.changed = true;
+    }
+
+    response
+}
+
+/// How clicking a checkbox with the indeterminate state changes its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckboxTriPolicy {
+    /// Clicking cycles through indeterminate, checked and unchecked.
+    Cycle,
+    /// Only the application makes the checkbox indeterminate, e.g. a "select
+    /// all" checkbox over a partially selected list. Clicking goes from
+    /// indeterminate to checked, and then toggles between checked and
+    /// unchecked.
+    Toggle,
+}
+
+/// A checkbox with an additional indeterminate state, represented by `None`,
+/// e.g. for when only some of the things the checkbox stands for are
+/// selected. Clicking cycles through indeterminate, checked and unchecked.
+#[inline]
+pub fn checkbox_tri<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut Option<bool>,
+    label: &str,
+) -> bool {
+    frame.with_theme(|frame, theme| checkbox_tri_with_theme(frame, id, value, label, theme))
+}
+
+pub fn checkbox_tri_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut Option<bool>,
+    label: &str,
+    theme: &Theme,
+) -> bool {
+    checkbox_tri_with_policy_theme(frame, id, value, label, CheckboxTriPolicy::Cycle, theme)
+}
+
+#[inline]
+pub fn checkbox_tri_with_policy<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut Option<bool>,
+    label: &str,
+    policy: CheckboxTriPolicy,
+) -> bool {
+    frame.with_theme(|frame, theme| {
+        checkbox_tri_with_policy_theme(frame, id, value, label, policy, theme)
+    })
+}
+
+pub fn checkbox_tri_with_policy_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut Option<bool>,
+    label: &str,
+    policy: CheckboxTriPolicy,
+    theme: &Theme,
+) -> bool {
+    let clicked = do_checkbox(frame, id, *value, label, theme).clicked;
+    if clicked {
+        *value = match (*value, policy) {
+            (None, _) => Some(true),
+            (Some(true), _) => Some(false),
+            (Some(false), CheckboxTriPolicy::Cycle) => None,
+            (Some(false), CheckboxTriPolicy::Toggle) => Some(true),
+        };
+    }
+
+    clicked
+}
+
+// The whole row, including the label, is the click target. The response
+// never reports changes, as the value is flipped by the caller.
+fn do_checkbox<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: Option<bool>,
+    label: &str,
+    theme: &Theme,
+) -> Response {
     let texture_id = frame.font_atlas_texture_id();
     let parent_size = frame.ctrl_inner_size();
-    let lmb_pressed = frame.inputs_pressed() == Inputs::MB_LEFT;
-    let lmb_released = frame.inputs_released() == Inputs::MB_LEFT;
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
 
     let width = f32::max(0.0, parent_size.x - 2.0 * theme.checkbox_margin);
 
@@ -38,39 +148,38 @@ pub fn checkbox_with_theme<A: Allocator + Clone>(
     let hovered = ctrl.is_hovered();
     let active = ctrl.is_active();
 
-    let (active, changed) = if active && lmb_released {
+    let clicked = if active && lmb_released {
         ctrl.set_active(false);
-        if hovered {
-            // Make the control inactive once again after release, as the
-            // platform may not be running us on every frame, but only for
-            // new events. Also better latency this way.
-            *value = !*value;
-            (false, true)
-        } else {
-            (false, false)
-        }
-    } else if hovered && lmb_pressed {
-        ctrl.set_active(true);
-        (true, false)
+        // Make the control inactive once again after release, as the platform
+        // may not be running us on every frame, but only for new events. Also
+        // better latency this way.
+        hovered
     } else {
-        (active, false)
+        if hovered && lmb_pressed {
+            ctrl.set_active(true);
+        }
+
+        false
     };
 
-    let (handle_color, text_color) = match (hovered, active) {
-        (false, false) => (theme.checkbox_handle_color, theme.checkbox_text_color),
-        (true, false) => (
+    let [handle_color, text_color] = animated_colors(
+        &mut ctrl,
+        [theme.checkbox_handle_color, theme.checkbox_text_color],
+        [
             theme.checkbox_handle_color_hovered,
             theme.checkbox_text_color_hovered,
-        ),
-        (_, true) => (
+        ],
+        [
             theme.checkbox_handle_color_active,
             theme.checkbox_text_color_active,
-        ),
-    };
+        ],
+        theme.animation_speed,
+    );
 
     const CHECKBOX_LEFT_PADDING: f32 = 5.0;
     const CHECKBOX_INNER_DIM: f32 = 12.0;
     const CHECKBOX_OUTER_DIM: f32 = 18.0;
+    const CHECKBOX_INDETERMINATE_HEIGHT: f32 = 4.0;
 
     ctrl.set_draw_self(false);
     ctrl.draw_rect(
@@ -85,18 +194,35 @@ pub fn checkbox_with_theme<A: Allocator + Clone>(
         texture_id,
     );
 
-    if *value {
-        ctrl.draw_rect(
-            Rect::new(
-                CHECKBOX_LEFT_PADDING + 0.5 * (CHECKBOX_OUTER_DIM - CHECKBOX_INNER_DIM),
-                0.5 * theme.checkbox_height - 0.5 * CHECKBOX_INNER_DIM,
-                CHECKBOX_INNER_DIM,
-                CHECKBOX_INNER_DIM,
-            ),
-            Rect::ZERO,
-            0xffffffff,
-            texture_id,
-        );
+    match value {
+        Some(true) => {
+            ctrl.draw_rect(
+                Rect::new(
+                    CHECKBOX_LEFT_PADDING + 0.5 * (CHECKBOX_OUTER_DIM - CHECKBOX_INNER_DIM),
+                    0.5 * theme.checkbox_height - 0.5 * CHECKBOX_INNER_DIM,
+                    CHECKBOX_INNER_DIM,
+                    CHECKBOX_INNER_DIM,
+                ),
+                Rect::ZERO,
+                theme.checkbox_mark_color,
+                texture_id,
+            );
+        }
+        Some(false) => (),
+        None => {
+            // A dash, so that it can't be mistaken for the checked mark.
+            ctrl.draw_rect(
+                Rect::new(
+                    CHECKBOX_LEFT_PADDING + 0.5 * (CHECKBOX_OUTER_DIM - CHECKBOX_INNER_DIM),
+                    0.5 * theme.checkbox_height - 0.5 * CHECKBOX_INDETERMINATE_HEIGHT,
+                    CHECKBOX_INNER_DIM,
+                    CHECKBOX_INDETERMINATE_HEIGHT,
+                ),
+                Rect::ZERO,
+                theme.checkbox_mark_color_indeterminate,
+                texture_id,
+            );
+        }
     }
 
     ctrl.draw_text_fitted(
@@ -104,6 +230,7 @@ pub fn checkbox_with_theme<A: Allocator + Clone>(
         Align::Start,
         Align::Center,
         Wrap::Word,
+        Direction::Ltr,
         text_color,
         Rect::new(
             40.0,
@@ -113,7 +240,101 @@ pub fn checkbox_with_theme<A: Allocator + Clone>(
         ),
     );
 
+    let response = Response {
+        clicked,
+        changed: false,
+        hovered,
+        held: ctrl.is_active(),
+        rect: ctrl.absolute_rect(),
+    };
+
     frame.pop_ctrl();
 
-    changed
+    response
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::new_ui;
+
+    fn build(ui: &mut Ui<Global>, value: &mut Option<bool>) -> bool {
+        build_with_policy(ui, value, CheckboxTriPolicy::Cycle)
+    }
+
+    fn build_with_policy(
+        ui: &mut Ui<Global>,
+        value: &mut Option<bool>,
+        policy: CheckboxTriPolicy,
+    ) -> bool {
+        let mut frame = ui.begin_frame();
+        let changed = checkbox_tri_with_policy(&mut frame, 0, value, "Label", policy);
+        ui.end_frame();
+
+        changed
+    }
+
+    fn click(ui: &mut Ui<Global>, x: f32, value: &mut Option<bool>) -> bool {
+        click_with_policy(ui, x, value, CheckboxTriPolicy::Cycle)
+    }
+
+    fn click_with_policy(
+        ui: &mut Ui<Global>,
+        x: f32,
+        value: &mut Option<bool>,
+        policy: CheckboxTriPolicy,
+    ) -> bool {
+        ui.set_cursor_position(x, 15.0);
+        build_with_policy(ui, value, policy);
+        ui.press_inputs(Inputs::MB_LEFT);
+        build_with_policy(ui, value, policy);
+        ui.release_inputs(Inputs::MB_LEFT);
+        build_with_policy(ui, value, policy)
+    }
+
+    #[test]
+    fn test_checkbox_tri_cycles_through_states() {
+        let mut ui = new_ui();
+        let mut value = None;
+
+        // First frame lays out the control, so that it can be hovered.
+        build(&mut ui, &mut value);
+
+        assert!(click(&mut ui, 10.0, &mut value));
+        assert_eq!(value, Some(true));
+        assert!(click(&mut ui, 10.0, &mut value));
+        assert_eq!(value, Some(false));
+        assert!(click(&mut ui, 10.0, &mut value));
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn test_checkbox_tri_toggle_policy_skips_indeterminate() {
+        let mut ui = new_ui();
+        let mut value = None;
+        let policy = CheckboxTriPolicy::Toggle;
+
+        build_with_policy(&mut ui, &mut value, policy);
+
+        assert!(click_with_policy(&mut ui, 10.0, &mut value, policy));
+        assert_eq!(value, Some(true));
+        assert!(click_with_policy(&mut ui, 10.0, &mut value, policy));
+        assert_eq!(value, Some(false));
+        assert!(click_with_policy(&mut ui, 10.0, &mut value, policy));
+        assert_eq!(value, Some(true));
+    }
+
+    #[test]
+    fn test_checkbox_label_is_click_target() {
+        let mut ui = new_ui();
+        let mut value = Some(true);
+
+        build(&mut ui, &mut value);
+
+        assert!(click(&mut ui, 300.0, &mut value));
+        assert_eq!(value, Some(false));
+    }
 }
@@ -0,0 +1,591 @@
+use core::alloc::Allocator;
+use core::mem;
+use core::str;
+
+use arrayvec::ArrayVec;
+
+use crate::convert::{cast_u32, cast_usize};
+use crate::core::{
+    fuzzy_match,
+    Align,
+    CtrlFlags,
+    CtrlState,
+    Direction,
+    Frame,
+    InputEvent,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Wrap,
+};
+use crate::widgets::dropdown::option;
+
+const LABEL_WIDTH_RATIO: f32 = 0.35;
+const LABEL_SPACING: f32 = 5.0;
+
+const FILTER_CAPACITY: usize = 48;
+
+/// A dropdown for choosing from long lists of options. When open, typing
+/// filters the options to those fuzzy matching the typed text, which is
+/// shown above them, see [`fuzzy_match`]. Arrow keys move the highlight
+/// between the shown options, and enter or a click selects one and closes the
+/// combo. Backspace removes the last typed character.
+#[inline]
+pub fn combo<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &[T],
+    selected: &mut Option<usize>,
+) -> bool
+where
+    T: AsRef<str>,
+    A: Allocator + Clone,
+{
+    frame.with_theme(|frame, theme| do_combo(frame, id, label, options, selected, theme))
+}
+
+#[inline]
+pub fn combo_with_theme<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &[T],
+    selected: &mut Option<usize>,
+    theme: &Theme,
+) -> bool
+where
+    T: AsRef<str>,
+    A: Allocator + Clone,
+{
+    do_combo(frame, id, label, options, selected, theme)
+}
+
+fn do_combo<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &[T],
+    selected: &mut Option<usize>,
+    theme: &Theme,
+) -> bool
+where
+    T: AsRef<str>,
+    A: Allocator + Clone,
+{
+    const OVERLAY_SPACING: f32 = 5.0;
+
+    let parent_size = frame.ctrl_inner_size();
+    let window_size = frame.window_size();
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+
+    // NB: Copy the events out of the frame, because processing them requires
+    // borrowing the frame mutably through the control. The capacity matches
+    // the event queue in Ui.
+    let mut input_events: ArrayVec<InputEvent, 64> = ArrayVec::new();
+    let _ = input_events.try_extend_from_slice(frame.input_events());
+
+    let outer_width = f32::max(0.0, parent_size.x - 2.0 * theme.dropdown_margin);
+    let label_width = LABEL_WIDTH_RATIO * outer_width;
+    let inner_width = f32::max(0.0, outer_width - label_width - LABEL_SPACING);
+
+    let filter_row_height = theme.text_input_height + 2.0 * theme.text_input_margin;
+    let option_row_height = theme.button_height + 2.0 * theme.button_margin;
+
+    let mut outer_ctrl = frame.push_ctrl(id);
+    outer_ctrl.set_flags(CtrlFlags::NONE);
+    outer_ctrl.set_layout(Layout::Horizontal);
+    outer_ctrl.set_rect(Rect::new(0.0, 0.0, outer_width, theme.dropdown_height));
+    outer_ctrl.set_padding(0.0);
+    outer_ctrl.set_border(0.0);
+    outer_ctrl.set_margin(theme.dropdown_margin);
+
+    outer_ctrl.set_draw_self(false);
+    outer_ctrl.draw_text_fitted(
+        label,
+        Align::Start,
+        Align::Center,
+        Wrap::Word,
+        Direction::Ltr,
+        theme.dropdown_text_color,
+        Rect::new(0.0, 0.0, label_width, theme.dropdown_height),
+    );
+
+    let mut active_area_ctrl = frame.push_ctrl(0);
+    active_area_ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+    active_area_ctrl.set_layout(Layout::Vertical);
+    active_area_ctrl.set_rect(Rect::new(
+        label_width + LABEL_SPACING,
+        0.0,
+        inner_width,
+        theme.dropdown_height,
+    ));
+    active_area_ctrl.set_padding(0.0);
+    active_area_ctrl.set_border(theme.dropdown_border);
+    active_area_ctrl.set_margin(0.0);
+
+    let absolute_position = active_area_ctrl.absolute_position();
+
+    let hovered = active_area_ctrl.is_hovered();
+    let mut active = active_area_ctrl.is_active();
+
+    let mut state = *cast_state(active_area_ctrl.state());
+    let mut open = state.open == OPEN;
+    let mut highlight_changed = false;
+    let mut changed = false;
+
+    // The overlay depends on what the typed text filters, so we process the
+    // keyboard first, and only then check where the mouse clicked.
+    let was_open = open;
+
+    if active {
+        for event in input_events {
+            match event {
+                InputEvent::KeyPress { input, .. } => {
+                    if !open {
+                        if input.intersects(Inputs::KB_ENTER | Inputs::KB_SPACE) {
+                            open = true;
+                            state.filter_len = 0;
+                            state.highlighted = highlight_selected(*selected);
+                            highlight_changed = true;
+                        } else if input.contains(Inputs::KB_ESCAPE) {
+                            active_area_ctrl.set_active(false);
+                            active = false;
+
+                            // We are no longer active, the rest of the events
+                            // is not for us.
+                            break;
+                        }
+                    } else if input.contains(Inputs::KB_UP_ARROW) {
+                        let highlighted = cast_usize(state.highlighted);
+                        let index = (0..usize::min(highlighted, options.len()))
+                            .rev()
+                            .find(|&i| matches(&state, options, i));
+
+                        if let Some(index) = index {
+                            state.highlighted = cast_u32(index);
+                            highlight_changed = true;
+                        }
+                    } else if input.contains(Inputs::KB_DOWN_ARROW) {
+                        // Nothing highlighted wraps to the first option.
+                        let start = if state.highlighted == NONE {
+                            0
+                        } else {
+                            cast_usize(state.highlighted) + 1
+                        };
+                        let index = (start..options.len()).find(|&i| matches(&state, options, i));
+
+                        if let Some(index) = index {
+                            state.highlighted = cast_u32(index);
+                            highlight_changed = true;
+                        }
+                    } else if input.contains(Inputs::KB_BACKSPACE) {
+                        let filter = filter(&state);
+                        if let Some((last_char_start, _)) = filter.char_indices().next_back() {
+                            state.filter_len = cast_u32(last_char_start);
+                            state.highlighted = highlight_first_match(&state, options);
+                            highlight_changed = true;
+                        }
+                    } else if input.contains(Inputs::KB_ENTER) {
+                        let highlighted = cast_usize(state.highlighted);
+                        if highlighted < options.len() && matches(&state, options, highlighted) {
+                            *selected = Some(highlighted);
+                            changed = true;
+                        }
+
+                        open = false;
+                    } else if input.contains(Inputs::KB_ESCAPE) {
+                        open = false;
+                    }
+                }
+
+                InputEvent::Char(c) => {
+                    // The space that opened the combo also arrives as a
+                    // character, don't filter by it.
+                    if !was_open || !open || c.is_control() {
+                        continue;
+                    }
+
+                    let mut buf = [0; 4];
+                    let c = c.encode_utf8(&mut buf).as_bytes();
+
+                    let len = cast_usize(state.filter_len);
+                    if len + c.len() <= FILTER_CAPACITY {
+                        state.filter[len..len + c.len()].copy_from_slice(c);
+                        state.filter_len += cast_u32(c.len());
+                        state.highlighted = highlight_first_match(&state, options);
+                        highlight_changed = true;
+                    }
+                }
+
+                _ => (),
+            }
+        }
+    }
+
+    let match_count = (0..options.len())
+        .filter(|&i| matches(&state, options, i))
+        .count();
+
+    let overlay_y = absolute_position.y + theme.dropdown_height + OVERLAY_SPACING;
+
+    let available_height_up = overlay_y;
+    let available_height_down = f32::max(window_size.y - overlay_y, 0.0);
+
+    // The typed text is always shown, even if nothing matches it.
+    let overlay_height_requested = filter_row_height
+        + f32::min(
+            match_count as f32 * option_row_height,
+            theme.dropdown_overlay_max_height,
+        )
+        + 2.0 * theme.dropdown_border;
+
+    let overlay_rect = if overlay_height_requested > available_height_down {
+        if available_height_down > available_height_up {
+            Rect::new(
+                absolute_position.x,
+                overlay_y,
+                inner_width,
+                available_height_down,
+            )
+        } else {
+            let height = f32::min(available_height_up, overlay_height_requested);
+            Rect::new(
+                absolute_position.x,
+                absolute_position.y - height - OVERLAY_SPACING,
+                inner_width,
+                height,
+            )
+        }
+    } else {
+        Rect::new(
+            absolute_position.x,
+            overlay_y,
+            inner_width,
+            overlay_height_requested,
+        )
+    };
+
+    // Clicking outside of the combo and its overlay closes the overlay, and
+    // so does clicking the combo again.
+    if open && (active_area_ctrl.clicked_outside() || lmb_pressed && hovered) {
+        active_area_ctrl.set_active(false);
+        active = false;
+        open = false;
+    } else if !open && lmb_pressed && hovered {
+        active_area_ctrl.set_active(true);
+        active = true;
+        open = true;
+        state.filter_len = 0;
+        state.highlighted = highlight_selected(*selected);
+        highlight_changed = true;
+    }
+
+    if active {
+        active_area_ctrl.request_want_capture_keyboard();
+        if open {
+            active_area_ctrl.request_want_text_input();
+        }
+    }
+
+    let (text_color, background_color, border_color) = match (hovered, active) {
+        (false, false) => (
+            theme.dropdown_text_color,
+            theme.dropdown_background_color,
+            theme.dropdown_border_color,
+        ),
+        (true, false) => (
+            theme.dropdown_text_color_hovered,
+            theme.dropdown_background_color_hovered,
+            theme.dropdown_border_color_hovered,
+        ),
+        (_, true) => (
+            theme.dropdown_text_color_active,
+            theme.dropdown_background_color_active,
+            theme.dropdown_border_color_active,
+        ),
+    };
+
+    active_area_ctrl.set_draw_self(true);
+    active_area_ctrl.set_draw_self_border_color(border_color);
+    active_area_ctrl.set_draw_self_background_color(background_color);
+
+    let label = if let Some(selected) = selected {
+        options[*selected].as_ref()
+    } else {
+        ""
+    };
+
+    active_area_ctrl.draw_text(
+        label,
+        Align::Center,
+        Align::Center,
+        Wrap::Word,
+        Direction::Ltr,
+        text_color,
+    );
+
+    if open {
+        active_area_ctrl.close_on_outside_click();
+
+        let overlay_inner_width = f32::max(0.0, overlay_rect.width - 2.0 * theme.dropdown_border);
+        let overlay_inner_height = f32::max(0.0, overlay_rect.height - 2.0 * theme.dropdown_border);
+
+        frame.begin_overlay();
+
+        let mut ctrl = frame.push_ctrl(id);
+        ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+        ctrl.set_layout(Layout::Vertical);
+        ctrl.set_rect(overlay_rect);
+
+        // Margin is zero, because we are setting an absolute position.
+        ctrl.set_padding(0.0);
+        ctrl.set_border(theme.dropdown_border);
+        ctrl.set_margin(0.0);
+
+        ctrl.set_draw_self(true);
+        ctrl.set_draw_self_border_color(theme.dropdown_border_color_active);
+        ctrl.set_draw_self_background_color(theme.dropdown_background_color_active);
+
+        let mut filter_ctrl = frame.push_ctrl(0);
+        filter_ctrl.set_flags(CtrlFlags::NONE);
+        filter_ctrl.set_layout(Layout::Vertical);
+        filter_ctrl.set_rect(Rect::new(
+            0.0,
+            0.0,
+            f32::max(0.0, overlay_inner_width - 2.0 * theme.text_input_margin),
+            theme.text_input_height,
+        ));
+        filter_ctrl.set_padding(0.0);
+        filter_ctrl.set_border(theme.text_input_border);
+        filter_ctrl.set_margin(theme.text_input_margin);
+
+        filter_ctrl.set_draw_self(true);
+        filter_ctrl.set_draw_self_border_color(theme.text_input_border_color_active);
+        filter_ctrl.set_draw_self_background_color(theme.text_input_background_color_active);
+        filter_ctrl.draw_text(
+            filter(&state),
+            Align::Center,
+            Align::Center,
+            Wrap::None,
+            Direction::Ltr,
+            theme.text_input_text_color_active,
+        );
+
+        frame.pop_ctrl();
+
+        // The options scroll separately, so that the typed text stays in view.
+        let mut list_ctrl = frame.push_ctrl(1);
+        list_ctrl.set_flags(CtrlFlags::CAPTURE_SCROLL);
+        list_ctrl.set_layout(Layout::Vertical);
+        list_ctrl.set_rect(Rect::new(
+            0.0,
+            0.0,
+            overlay_inner_width,
+            f32::max(0.0, overlay_inner_height - filter_row_height),
+        ));
+        list_ctrl.set_padding(0.0);
+        list_ctrl.set_border(0.0);
+        list_ctrl.set_margin(0.0);
+        list_ctrl.set_draw_self(false);
+
+        let highlighted = cast_usize(state.highlighted);
+        if highlight_changed && highlighted < options.len() {
+            let row = (0..highlighted)
+                .filter(|&i| matches(&state, options, i))
+                .count();
+            let y = row as f32 * option_row_height;
+
+            list_ctrl.scroll_to_rect(Rect::new(0.0, y, overlay_inner_width, option_row_height));
+        }
+
+        // NB: Clicking the options makes them active for as long as the mouse
+        // button is held, but once released, they hand the active status back
+        // to us, because we own the overlay.
+        for i in 0..options.len() {
+            if !matches(&state, options, i) {
+                continue;
+            }
+
+            let label = options[i].as_ref();
+            if option(
+                frame,
+                cast_u32(i),
+                label,
+                "",
+                None,
+                true,
+                highlighted == i,
+                theme,
+            ) {
+                *selected = Some(i);
+                changed = true;
+            }
+        }
+
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+
+        frame.end_overlay();
+    }
+
+    if changed {
+        open = false;
+    }
+    if !open {
+        state.filter_len = 0;
+    }
+
+    state.open = if open { OPEN } else { CLOSED };
+    *cast_state_mut(frame.ctrl_state_mut()) = state;
+
+    frame.pop_ctrl();
+    frame.pop_ctrl();
+
+    changed
+}
+
+fn filter(state: &State) -> &str {
+    // NB: Only whole characters are ever added to the filter, and removed
+    // from it, so it's always valid UTF-8.
+    str::from_utf8(&state.filter[..cast_usize(state.filter_len)]).unwrap()
+}
+
+fn matches<T: AsRef<str>>(state: &State, options: &[T], index: usize) -> bool {
+    fuzzy_match(filter(state), options[index].as_ref()).is_some()
+}
+
+fn highlight_first_match<T: AsRef<str>>(state: &State, options: &[T]) -> u32 {
+    (0..options.len())
+        .find(|&i| matches(state, options, i))
+        .map_or(NONE, cast_u32)
+}
+
+fn highlight_selected(selected: Option<usize>) -> u32 {
+    selected.map_or(NONE, cast_u32)
+}
+
+const CLOSED: u32 = 0;
+const OPEN: u32 = 1;
+
+// Highlighted when nothing is.
+const NONE: u32 = u32::MAX;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    open: u32,
+    // The index of the option highlighted for keyboard selection.
+    highlighted: u32,
+    filter_len: u32,
+    _pad0: u32,
+    filter: [u8; FILTER_CAPACITY],
+}
+
+fn cast_state(state: &CtrlState) -> &State {
+    bytemuck::from_bytes(&state[..mem::size_of::<State>()])
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Modifiers;
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+
+    const OPTIONS: &[&str] = &["Apple", "Banana", "Blueberry", "Cherry", "Mango"];
+
+    fn open(harness: &mut TestHarness, build: &mut impl FnMut(&mut Frame<Global>)) {
+        // First frame lays out the control, so that it can be hovered.
+        harness.run_frame(&mut *build);
+        harness.click_at(700.0, 10.0, &mut *build);
+    }
+
+    #[test]
+    fn test_combo_filters_options_by_typed_text() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut selected = None;
+
+        {
+            let mut build = |frame: &mut Frame<Global>| {
+                combo(frame, 0, "Fruit", OPTIONS, &mut selected);
+            };
+
+            open(&mut harness, &mut build);
+            assert!(harness.ui().want_capture_keyboard());
+
+            harness.type_text("an", &mut build);
+            assert!(harness.find(&[0, 1, 0]).is_none());
+            assert!(harness.find(&[0, 1, 1]).is_some());
+            assert!(harness.find(&[0, 1, 4]).is_some());
+
+            harness.press(Inputs::KB_DOWN_ARROW, Modifiers::NONE, &mut build);
+            harness.press(Inputs::KB_ENTER, Modifiers::NONE, &mut build);
+        }
+
+        assert_eq!(selected, Some(4));
+    }
+
+    #[test]
+    fn test_combo_backspace_and_escape() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut selected = Some(0);
+
+        {
+            let mut build = |frame: &mut Frame<Global>| {
+                combo(frame, 0, "Fruit", OPTIONS, &mut selected);
+            };
+
+            open(&mut harness, &mut build);
+
+            // Nothing contains "Bx", but removing the "x" shows both berries
+            // and bananas again.
+            harness.type_text("Bx", &mut build);
+            assert!(harness.find(&[0, 1, 1]).is_none());
+
+            harness.press(Inputs::KB_BACKSPACE, Modifiers::NONE, &mut build);
+            assert!(harness.find(&[0, 1, 1]).is_some());
+            assert!(harness.find(&[0, 1, 2]).is_some());
+            assert!(harness.find(&[0, 1, 3]).is_none());
+
+            harness.press(Inputs::KB_ESCAPE, Modifiers::NONE, &mut build);
+            assert!(harness.find(&[0, 1]).is_none());
+        }
+
+        assert_eq!(selected, Some(0));
+    }
+
+    #[test]
+    fn test_combo_click_selects_filtered_option() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut selected = None;
+
+        {
+            let mut build = |frame: &mut Frame<Global>| {
+                combo(frame, 0, "Fruit", OPTIONS, &mut selected);
+            };
+
+            open(&mut harness, &mut build);
+            harness.type_text("rr", &mut build);
+
+            // Only the berry and the cherry are left, in their original order.
+            let cherry = harness.center_of(&[0, 1, 3]).unwrap();
+            let berry = harness.center_of(&[0, 1, 2]).unwrap();
+            assert!(cherry.y > berry.y);
+
+            harness.click_at(cherry.x, cherry.y, &mut build);
+            harness.run_frame(&mut build);
+            assert!(harness.find(&[0, 1]).is_none());
+        }
+
+        assert_eq!(selected, Some(3));
+    }
+}
@@ -0,0 +1,401 @@
+use core::alloc::Allocator;
+use core::mem;
+use core::ops::Deref;
+use core::str;
+
+use arrayvec::ArrayVec;
+
+use crate::convert::{cast_u32, cast_usize};
+use crate::core::{
+    Align,
+    CtrlFlags,
+    CtrlState,
+    Direction,
+    Frame,
+    InputEvent,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Wrap,
+};
+use crate::widgets::text_input::{fuzzy_candidate_button, fuzzy_search};
+
+/// The most bytes of text that can be typed into the command palette.
+pub const COMMAND_PALETTE_QUERY_CAPACITY: usize = 48;
+
+// The most results shown. The list scrolls, if they don't fit.
+const RESULT_CAPACITY: usize = 64;
+
+/// Opens a searchable list of commands in the upper part of the window while
+/// `open` is true. Typing filters the commands to those fuzzy matching the
+/// typed text, best matches first, see [`crate::fuzzy_match`]. Arrow keys
+/// move the highlight between them. Enter or a click returns the index of the
+/// command in `commands` and sets `open` to false, while Escape or clicking
+/// outside just sets `open` to false.
+///
+/// The typed text is kept by the palette, and cleared when it opens. At most
+/// [`COMMAND_PALETTE_QUERY_CAPACITY`] bytes can be typed.
+#[inline]
+pub fn command_palette<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    open: &mut bool,
+    commands: &[T],
+) -> Option<usize>
+where
+    T: Deref<Target = str>,
+    A: Allocator + Clone,
+{
+    frame.with_theme(|frame, theme| command_palette_with_theme(frame, id, open, commands, theme))
+}
+
+pub fn command_palette_with_theme<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    open: &mut bool,
+    commands: &[T],
+    theme: &Theme,
+) -> Option<usize>
+where
+    T: Deref<Target = str>,
+    A: Allocator + Clone,
+{
+    if !*open {
+        return None;
+    }
+
+    let window_size = frame.window_size();
+
+    // NB: Copy the events out of the frame, because processing them requires
+    // borrowing the frame mutably through the control. The capacity matches
+    // the event queue in Ui.
+    let mut input_events: ArrayVec<InputEvent, 64> = ArrayVec::new();
+    let _ = input_events.try_extend_from_slice(frame.input_events());
+
+    let query_row_height = theme.text_input_height + 2.0 * theme.text_input_margin;
+    let result_row_height = theme.button_height + 2.0 * theme.button_margin;
+    let border = theme.window_border;
+
+    frame.begin_overlay();
+
+    let mut ctrl = frame.push_ctrl(id);
+
+    // The palette isn't built while closed, so it is new every time it opens,
+    // with an empty query. Take the keyboard, so that typing goes to us.
+    let active = ctrl.is_active();
+    if ctrl.is_new() {
+        ctrl.set_active(true);
+    }
+
+    let mut state = *cast_state(ctrl.state());
+    let mut highlight_changed = false;
+    let mut enter_pressed = false;
+
+    if active {
+        for event in input_events {
+            match event {
+                InputEvent::KeyPress { input, .. } => {
+                    if input.contains(Inputs::KB_UP_ARROW) {
+                        state.highlighted = state.highlighted.saturating_sub(1);
+                        highlight_changed = true;
+                    } else if input.contains(Inputs::KB_DOWN_ARROW) {
+                        // Clamped to the results below.
+                        state.highlighted = state.highlighted.saturating_add(1);
+                        highlight_changed = true;
+                    } else if input.contains(Inputs::KB_BACKSPACE) {
+                        let query = query(&state);
+                        if let Some((last_char_start, _)) = query.char_indices().next_back() {
+                            state.query_len = cast_u32(last_char_start);
+                            state.highlighted = 0;
+                            highlight_changed = true;
+                        }
+                    } else if input.contains(Inputs::KB_ENTER) {
+                        enter_pressed = true;
+                        break;
+                    } else if input.contains(Inputs::KB_ESCAPE) {
+                        *open = false;
+                        break;
+                    }
+                }
+
+                InputEvent::Char(c) => {
+                    if c.is_control() {
+                        continue;
+                    }
+
+                    let mut buf = [0; 4];
+                    let c = c.encode_utf8(&mut buf).as_bytes();
+
+                    let len = cast_usize(state.query_len);
+                    if len + c.len() <= COMMAND_PALETTE_QUERY_CAPACITY {
+                        state.query[len..len + c.len()].copy_from_slice(c);
+                        state.query_len += cast_u32(c.len());
+                        state.highlighted = 0;
+                        highlight_changed = true;
+                    }
+                }
+
+                _ => (),
+            }
+        }
+    }
+
+    if ctrl.clicked_outside() {
+        *open = false;
+    }
+
+    let mut results: ArrayVec<usize, RESULT_CAPACITY> = ArrayVec::new();
+    fuzzy_search(query(&state), commands, &mut results);
+
+    let highlighted = usize::min(
+        cast_usize(state.highlighted),
+        results.len().saturating_sub(1),
+    );
+    state.highlighted = cast_u32(highlighted);
+
+    let mut selected = None;
+    if enter_pressed {
+        selected = results.get(highlighted).copied();
+        *open = false;
+    }
+
+    if !*open {
+        // We are still built this frame, so we must not block anything next
+        // frame.
+        ctrl.set_flags(CtrlFlags::NONE);
+        ctrl.set_rect(Rect::ZERO);
+        if ctrl.is_active() {
+            ctrl.set_active(false);
+        }
+
+        frame.pop_ctrl();
+        frame.end_overlay();
+
+        return selected;
+    }
+
+    ctrl.close_on_outside_click();
+    ctrl.request_want_capture_keyboard();
+    ctrl.request_want_text_input();
+
+    let width = f32::min(theme.command_palette_width, window_size.x);
+    let list_height = f32::min(
+        results.len() as f32 * result_row_height,
+        theme.command_palette_max_height,
+    );
+    let height = query_row_height + list_height + 2.0 * border;
+
+    // The palette hangs from a fixed point instead of being centered
+    // vertically, so that the typed text doesn't move as the results change.
+    let x = 0.5 * (window_size.x - width);
+    let y = f32::max(0.0, f32::min(0.2 * window_size.y, window_size.y - height));
+
+    ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+    ctrl.set_layout(Layout::Vertical);
+    ctrl.set_rect(Rect::new(x, y, width, height));
+    ctrl.set_padding(0.0);
+    ctrl.set_border(border);
+    ctrl.set_margin(0.0);
+
+    ctrl.set_draw_self(true);
+    ctrl.set_draw_self_border_color(theme.window_border_color_hovered);
+    ctrl.set_draw_self_background_color(theme.window_background_color);
+
+    let inner_width = f32::max(0.0, width - 2.0 * border);
+
+    let mut query_ctrl = frame.push_ctrl(0);
+    query_ctrl.set_flags(CtrlFlags::NONE);
+    query_ctrl.set_layout(Layout::Vertical);
+    query_ctrl.set_rect(Rect::new(
+        0.0,
+        0.0,
+        f32::max(0.0, inner_width - 2.0 * theme.text_input_margin),
+        theme.text_input_height,
+    ));
+    query_ctrl.set_padding(0.0);
+    query_ctrl.set_border(theme.text_input_border);
+    query_ctrl.set_margin(theme.text_input_margin);
+
+    query_ctrl.set_draw_self(true);
+    query_ctrl.set_draw_self_border_color(theme.text_input_border_color_active);
+    query_ctrl.set_draw_self_background_color(theme.text_input_background_color_active);
+    query_ctrl.draw_text(
+        query(&state),
+        Align::Center,
+        Align::Center,
+        Wrap::None,
+        Direction::Ltr,
+        theme.text_input_text_color_active,
+    );
+
+    frame.pop_ctrl();
+
+    // The results scroll separately, so that the typed text stays in view.
+    let mut list_ctrl = frame.push_ctrl(1);
+    list_ctrl.set_flags(CtrlFlags::CAPTURE_SCROLL);
+    list_ctrl.set_layout(Layout::Vertical);
+    list_ctrl.set_rect(Rect::new(0.0, 0.0, inner_width, list_height));
+    list_ctrl.set_padding(0.0);
+    list_ctrl.set_border(0.0);
+    list_ctrl.set_margin(0.0);
+    list_ctrl.set_draw_self(false);
+
+    if highlight_changed {
+        let y = highlighted as f32 * result_row_height;
+        list_ctrl.scroll_to_rect(Rect::new(0.0, y, inner_width, result_row_height));
+    }
+
+    // NB: Clicking the results makes them active for as long as the mouse
+    // button is held, but once released, they hand the active status back to
+    // us, because they are built inside of us.
+    for (row, &command_index) in results.iter().enumerate() {
+        let command: &str = commands[command_index].deref();
+        if fuzzy_candidate_button(
+            frame,
+            cast_u32(command_index),
+            query(&state),
+            command,
+            row == highlighted,
+            theme,
+        ) {
+            selected = Some(command_index);
+        }
+    }
+
+    frame.pop_ctrl();
+
+    *cast_state_mut(frame.ctrl_state_mut()) = state;
+
+    frame.pop_ctrl();
+    frame.end_overlay();
+
+    if selected.is_some() {
+        *open = false;
+    }
+
+    selected
+}
+
+fn query(state: &State) -> &str {
+    // NB: Only whole characters are ever added to the query, and removed from
+    // it, so it's always valid UTF-8.
+    str::from_utf8(&state.query[..cast_usize(state.query_len)]).unwrap()
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    // The row of the result highlighted for keyboard selection.
+    highlighted: u32,
+    query_len: u32,
+    query: [u8; COMMAND_PALETTE_QUERY_CAPACITY],
+}
+
+fn cast_state(state: &CtrlState) -> &State {
+    bytemuck::from_bytes(&state[..mem::size_of::<State>()])
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Modifiers;
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+
+    const COMMANDS: &[&str] = &["Toggle Word Wrap", "Reload Window", "Open Recent"];
+
+    fn build<'a>(
+        open: &'a mut bool,
+        selected: &'a mut Option<usize>,
+    ) -> impl FnMut(&mut Frame<Global>) + 'a {
+        move |frame| {
+            if let Some(index) = command_palette(frame, 0, open, COMMANDS) {
+                *selected = Some(index);
+            }
+        }
+    }
+
+    #[test]
+    fn test_command_palette_sorts_results_and_selects_with_enter() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut open = true;
+        let mut selected = None;
+
+        {
+            let mut build = build(&mut open, &mut selected);
+
+            harness.run_frame(&mut build);
+            assert!(harness.ui().want_capture_keyboard());
+
+            // Both the word wrap and the window match, but the window matches
+            // better, at the start of both words.
+            harness.type_text("rw", &mut build);
+            assert!(harness.find(&[0, 1, 2]).is_none());
+
+            let wrap = harness.center_of(&[0, 1, 0]).unwrap();
+            let window = harness.center_of(&[0, 1, 1]).unwrap();
+            assert!(window.y < wrap.y);
+
+            harness.press(Inputs::KB_ENTER, Modifiers::NONE, &mut build);
+            assert!(harness.find(&[0]).is_none());
+        }
+
+        assert!(!open);
+        assert_eq!(selected, Some(1));
+    }
+
+    #[test]
+    fn test_command_palette_arrows_escape_and_outside_click() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut open = true;
+        let mut selected = None;
+
+        {
+            let mut build = build(&mut open, &mut selected);
+
+            // Moving past the last result stays on it.
+            harness.run_frame(&mut build);
+            harness.press(Inputs::KB_DOWN_ARROW, Modifiers::NONE, &mut build);
+            harness.press(Inputs::KB_DOWN_ARROW, Modifiers::NONE, &mut build);
+            harness.press(Inputs::KB_DOWN_ARROW, Modifiers::NONE, &mut build);
+            harness.press(Inputs::KB_UP_ARROW, Modifiers::NONE, &mut build);
+            harness.press(Inputs::KB_ENTER, Modifiers::NONE, &mut build);
+        }
+
+        assert!(!open);
+        assert_eq!(selected, Some(1));
+
+        selected = None;
+        open = true;
+
+        {
+            let mut build = build(&mut open, &mut selected);
+
+            harness.run_frame(&mut build);
+            harness.press(Inputs::KB_ESCAPE, Modifiers::NONE, &mut build);
+        }
+
+        assert!(!open);
+        assert_eq!(selected, None);
+
+        open = true;
+
+        {
+            let mut build = build(&mut open, &mut selected);
+
+            harness.run_frame(&mut build);
+            harness.click_at(10.0, 590.0, &mut build);
+        }
+
+        assert!(!open);
+        assert_eq!(selected, None);
+    }
+}
@@ -0,0 +1,314 @@
+use core::alloc::Allocator;
+use core::mem;
+
+use crate::core::{CtrlFlags, CtrlState, Frame, Inputs, Layout, Rect, Theme};
+use crate::widgets::tooltip::tooltip_position;
+
+/// Opens a popup menu at the cursor when the last control is right-clicked.
+/// If called before any children of the current parent control were built,
+/// the menu belongs to the parent instead, e.g. to the empty space of a
+/// window.
+///
+/// The menu stays open across frames, until a control inside it is clicked,
+/// Escape is pressed, or a click lands outside of it. Returns [`None`] while
+/// the menu is closed. Otherwise, the contents, e.g. buttons, are built
+/// inside the menu until [`ContextMenu::end`] is called.
+#[inline]
+pub fn begin_context_menu<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+) -> Option<ContextMenu> {
+    frame.with_theme(|frame, theme| begin_context_menu_with_theme(frame, id, theme))
+}
+
+pub fn begin_context_menu_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    theme: &Theme,
+) -> Option<ContextMenu> {
+    let window_size = frame.window_size();
+    let cursor_position = frame.cursor_position();
+    let inputs_pressed = frame.inputs_pressed();
+    let rmb_pressed = inputs_pressed.contains(Inputs::MB_RIGHT);
+    let escape_pressed = inputs_pressed.contains(Inputs::KB_ESCAPE);
+
+    let target_hovered = if frame.last_ctrl_is_sibling() {
+        frame.last_ctrl_is_hovered()
+    } else {
+        frame.ctrl_is_hovered()
+    };
+
+    // The state lives in an empty control in the base layer, which also owns
+    // the overlay with the menu, so that controls in the menu hand their
+    // active status to it once released.
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(CtrlFlags::CAPTURE_ACTIVE);
+    ctrl.set_layout(Layout::Free);
+    ctrl.set_rect(Rect::ZERO);
+    ctrl.set_padding(0.0);
+    ctrl.set_border(0.0);
+    ctrl.set_margin(0.0);
+    ctrl.set_draw_self(false);
+
+    // We were handed the active status last frame, and closed the menu in
+    // ContextMenu::end. Pass it on.
+    if ctrl.is_active() {
+        ctrl.set_active(false);
+    }
+
+    let mut state = *cast_state(ctrl.state());
+    let mut open = state.open == OPEN;
+
+    if open && (escape_pressed || ctrl.clicked_outside()) {
+        open = false;
+    }
+
+    // Right-clicking the target again moves the menu to the new position.
+    if rmb_pressed && target_hovered {
+        open = true;
+        state.anchor_x = cursor_position.x;
+        state.anchor_y = cursor_position.y;
+    }
+
+    if !open {
+        state.open = CLOSED;
+        *cast_state_mut(ctrl.state_mut()) = state;
+        frame.pop_ctrl();
+
+        return None;
+    }
+
+    ctrl.close_on_outside_click();
+    frame.begin_overlay();
+
+    let border = theme.context_menu_border;
+    let padding = theme.context_menu_padding;
+
+    let mut menu_ctrl = frame.push_ctrl(id);
+
+    // NB: The menu is resized to fit its contents during layout, so we only
+    // know its height from last frame. New menus are placed as if they were
+    // empty, and flip away from the bottom window edge a frame late.
+    let width = f32::min(theme.context_menu_width, window_size.x);
+    let height = if menu_ctrl.is_new() {
+        0.0
+    } else {
+        menu_ctrl.inner_size().y + 2.0 * (border + padding)
+    };
+
+    let x = tooltip_position(state.anchor_x, width, window_size.x, 0.0);
+    let y = tooltip_position(state.anchor_y, height, window_size.y, 0.0);
+
+    menu_ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+    menu_ctrl.set_layout(Layout::Vertical);
+    // The height is resized to fit the contents during layout.
+    menu_ctrl.set_rect(Rect::new(x, y, width, window_size.y));
+    menu_ctrl.set_padding(padding);
+    menu_ctrl.set_border(border);
+    menu_ctrl.set_margin(0.0);
+
+    menu_ctrl.set_draw_self(true);
+    menu_ctrl.set_draw_self_border_color(theme.context_menu_border_color);
+    menu_ctrl.set_draw_self_background_color(theme.context_menu_background_color);
+
+    state.open = OPEN;
+
+    // The state is written back to the owning control in ContextMenu::end,
+    // once we are back in the base layer.
+    Some(ContextMenu {
+        state,
+        ended: false,
+    })
+}
+
+pub struct ContextMenu {
+    state: State,
+    ended: bool,
+}
+
+impl ContextMenu {
+    pub fn end<A: Allocator + Clone>(mut self, frame: &mut Frame<A>) {
+        assert!(!self.ended);
+
+        frame.pop_ctrl();
+        frame.end_overlay();
+
+        // Clicking a control in the menu hands the active status back to us
+        // once released, which closes the menu.
+        if frame.ctrl_is_active() {
+            self.state.open = CLOSED;
+        }
+
+        *cast_state_mut(frame.ctrl_state_mut()) = self.state;
+
+        frame.pop_ctrl();
+        self.ended = true;
+    }
+}
+
+impl Drop for ContextMenu {
+    fn drop(&mut self) {
+        debug_assert!(
+            self.ended,
+            "ContextMenu dropped without calling ContextMenu::end"
+        )
+    }
+}
+
+const CLOSED: u32 = 0;
+const OPEN: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    open: u32,
+    // Where the menu was opened.
+    anchor_x: f32,
+    anchor_y: f32,
+}
+
+fn cast_state(state: &CtrlState) -> &State {
+    bytemuck::from_bytes(&state[..mem::size_of::<State>()])
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::new_ui;
+    use crate::widgets::button::{button, button_response};
+
+    // Two buttons with a context menu each. The first menu has an item.
+    struct Built {
+        first_open: bool,
+        second_open: bool,
+        item_clicked: bool,
+        item_rect: Rect,
+    }
+
+    fn new_ui_with_buttons() -> Ui<Global> {
+        let mut ui = new_ui();
+
+        // First frame lays out the buttons, so that they can be hovered.
+        build(&mut ui);
+
+        ui
+    }
+
+    fn build(ui: &mut Ui<Global>) -> Built {
+        let mut frame = ui.begin_frame();
+        let mut built = Built {
+            first_open: false,
+            second_open: false,
+            item_clicked: false,
+            item_rect: Rect::ZERO,
+        };
+
+        // The root control has free layout, so stack the buttons in a
+        // vertical container.
+        let mut ctrl = frame.push_ctrl(100);
+        ctrl.set_flags(CtrlFlags::NONE);
+        ctrl.set_layout(Layout::Vertical);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 800.0, 600.0));
+
+        button(&mut frame, 0, "First");
+        if let Some(menu) = begin_context_menu(&mut frame, 1) {
+            let response = button_response(&mut frame, 0, "Item");
+            built.first_open = true;
+            built.item_clicked = response.clicked;
+            built.item_rect = response.rect;
+            menu.end(&mut frame);
+        }
+
+        button(&mut frame, 2, "Second");
+        if let Some(menu) = begin_context_menu(&mut frame, 3) {
+            built.second_open = true;
+            menu.end(&mut frame);
+        }
+
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        built
+    }
+
+    fn press(ui: &mut Ui<Global>, inputs: Inputs) -> Built {
+        ui.press_inputs(inputs);
+        let built = build(ui);
+        ui.release_inputs(inputs);
+
+        built
+    }
+
+    fn button_center_y(index: f32) -> f32 {
+        let theme = Theme::DEFAULT;
+        let row_height = theme.button_height + 2.0 * theme.button_margin;
+
+        index * row_height + 0.5 * row_height
+    }
+
+    #[test]
+    fn test_context_menu_closes_on_item_click() {
+        let mut ui = new_ui_with_buttons();
+
+        ui.set_cursor_position(100.0, button_center_y(0.0));
+        assert!(!build(&mut ui).first_open);
+        assert!(press(&mut ui, Inputs::MB_RIGHT).first_open);
+
+        // Lay out the menu, so that the item can be hovered.
+        let item_rect = build(&mut ui).item_rect;
+        let item_center = item_rect.min_point() + 0.5 * item_rect.size();
+        ui.set_cursor_position(item_center.x, item_center.y);
+        assert!(build(&mut ui).first_open);
+
+        assert!(!press(&mut ui, Inputs::MB_LEFT).item_clicked);
+        let built = build(&mut ui);
+        assert!(built.item_clicked);
+
+        assert!(!build(&mut ui).first_open);
+        assert!(!build(&mut ui).first_open);
+    }
+
+    #[test]
+    fn test_context_menu_closes_on_escape_and_outside_click() {
+        let mut ui = new_ui_with_buttons();
+
+        ui.set_cursor_position(100.0, button_center_y(0.0));
+        build(&mut ui);
+        assert!(press(&mut ui, Inputs::MB_RIGHT).first_open);
+        assert!(build(&mut ui).first_open);
+        assert!(!press(&mut ui, Inputs::KB_ESCAPE).first_open);
+
+        assert!(press(&mut ui, Inputs::MB_RIGHT).first_open);
+        build(&mut ui);
+        ui.set_cursor_position(100.0, 500.0);
+        build(&mut ui);
+        assert!(!press(&mut ui, Inputs::MB_LEFT).first_open);
+    }
+
+    #[test]
+    fn test_context_menu_right_click_elsewhere_switches_menus() {
+        let mut ui = new_ui_with_buttons();
+
+        // Open near the right window edge, so that the menu flips left.
+        ui.set_cursor_position(790.0, button_center_y(0.0));
+        build(&mut ui);
+        assert!(press(&mut ui, Inputs::MB_RIGHT).first_open);
+
+        let item_rect = build(&mut ui).item_rect;
+        assert!(item_rect.max_x() <= 790.0);
+
+        ui.set_cursor_position(100.0, button_center_y(1.0));
+        build(&mut ui);
+        let built = press(&mut ui, Inputs::MB_RIGHT);
+        assert!(!built.first_open);
+        assert!(built.second_open);
+    }
+}
@@ -0,0 +1,100 @@
+use core::alloc::Allocator;
+
+use crate::core::{CtrlFlags, Frame, Layout, Theme};
+
+/// Shows a translucent rect the size of the drag source under the cursor in
+/// the overlay, while a drag started by [`crate::Ctrl::drag_source`] is in
+/// progress. Build it once per frame, anywhere. Returns whether it was shown.
+///
+/// The ghost doesn't capture hover, so that drop targets under it still
+/// receive the drop.
+#[inline]
+pub fn drag_ghost<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32) -> bool {
+    frame.with_theme(|frame, theme| drag_ghost_with_theme(frame, id, theme))
+}
+
+pub fn drag_ghost_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    theme: &Theme,
+) -> bool {
+    let Some(rect) = frame.drag_ghost_rect() else {
+        return false;
+    };
+
+    frame.begin_overlay();
+
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(CtrlFlags::NONE);
+    ctrl.set_layout(Layout::Free);
+    ctrl.set_rect(rect);
+    ctrl.set_padding(0.0);
+    ctrl.set_border(theme.drag_ghost_border);
+    ctrl.set_margin(0.0);
+
+    ctrl.set_draw_self(true);
+    ctrl.set_draw_self_border_color(theme.drag_ghost_border_color);
+    ctrl.set_draw_self_background_color(theme.drag_ghost_background_color);
+
+    frame.pop_ctrl();
+
+    frame.end_overlay();
+
+    true
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::{Inputs, Rect};
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+
+    #[test]
+    fn test_drag_ghost_follows_cursor_and_lets_target_receive_drop() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut dropped = None;
+
+        let mut build = |frame: &mut Frame<Global>| {
+            let mut source = frame.push_ctrl(0);
+            source.set_flags(CtrlFlags::CAPTURE_HOVER);
+            source.set_rect(Rect::new(0.0, 0.0, 100.0, 40.0));
+            source.set_margin(0.0);
+            source.drag_source(7);
+            frame.pop_ctrl();
+
+            let mut target = frame.push_ctrl(1);
+            target.set_flags(CtrlFlags::CAPTURE_HOVER);
+            target.set_rect(Rect::new(200.0, 0.0, 300.0, 300.0));
+            if let Some(payload) = target.drop_target() {
+                dropped = Some(payload);
+            }
+            frame.pop_ctrl();
+
+            drag_ghost(frame, 2);
+        };
+
+        harness.move_cursor_to(10.0, 10.0, &mut build);
+        assert!(harness.rect_of(&[2]).is_none());
+
+        harness.ui_mut().press_inputs(Inputs::MB_LEFT);
+        harness.run_frame(&mut build);
+        harness.move_cursor_to(210.0, 210.0, &mut build);
+
+        // Keeps the offset between the cursor and the source's corner.
+        assert_eq!(
+            harness.rect_of(&[2]).unwrap(),
+            Rect::new(200.0, 200.0, 100.0, 40.0)
+        );
+        assert!(harness.is_hovered(&[1]));
+
+        harness.ui_mut().release_inputs(Inputs::MB_LEFT);
+        harness.run_frame(&mut build);
+        harness.run_frame(&mut build);
+        assert!(harness.rect_of(&[2]).is_none());
+
+        assert_eq!(dropped, Some(7));
+    }
+}
@@ -1,12 +1,28 @@
 use core::alloc::Allocator;
 use core::convert::AsRef;
+use core::fmt::Write;
+use core::mem;
 
-use crate::convert::cast_u32;
-use crate::core::{Align, CtrlFlags, CtrlState, Frame, Inputs, Layout, Rect, Wrap};
-use crate::widgets::button::button;
-use crate::widgets::theme::Theme;
+use arrayvec::{ArrayString, ArrayVec};
 
-// TODO(yan): Searchable dropdown, analogous to autocomplete text input?
+use crate::convert::{cast_u32, cast_usize};
+use crate::core::{
+    Align,
+    CtrlFlags,
+    CtrlState,
+    CursorIcon,
+    Direction,
+    Frame,
+    InputEvent,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Vec2,
+    Wrap,
+};
+use crate::widgets::checkbox::checkbox_with_theme;
+use crate::widgets::separator::separator_with_theme;
 
 // TODO(yan): Consider a more granular api, where opening the dropdown is
 // independent from drawing its contents. Something like:
@@ -25,6 +41,59 @@ use crate::widgets::theme::Theme;
 const LABEL_WIDTH_RATIO: f32 = 0.35;
 const LABEL_SPACING: f32 = 5.0;
 
+// Typed characters are appended to the type-ahead prefix, unless this many
+// seconds passed since the last one. Only works if the frame time is known.
+const TYPE_AHEAD_TIMEOUT: f32 = 1.0;
+const TYPE_AHEAD_CAPACITY: usize = 32;
+
+const OPTION_HINT_SPACING: f32 = 5.0;
+
+const OVERLAY_SPACING: f32 = 5.0;
+
+/// Options of a dropdown. Implemented for slices of strings, but richer option
+/// models can implement it to have disabled options, separators, icons, or
+/// hints, such as keyboard shortcuts.
+pub trait DropdownOptions {
+    fn count(&self) -> usize;
+
+    fn label(&self, index: usize) -> &str;
+
+    /// Disabled options are drawn dimmed, and can't be selected.
+    fn enabled(&self, index: usize) -> bool {
+        let _ = index;
+        true
+    }
+
+    /// Separators are drawn as thin lines instead of the label, and can't be
+    /// selected.
+    fn separator(&self, index: usize) -> bool {
+        let _ = index;
+        false
+    }
+
+    /// The texture to draw as an icon before the option's label.
+    fn texture_id(&self, index: usize) -> Option<u64> {
+        let _ = index;
+        None
+    }
+
+    /// Text drawn aligned to the end of the option, e.g. a keyboard shortcut.
+    fn hint(&self, index: usize) -> &str {
+        let _ = index;
+        ""
+    }
+}
+
+impl<T: AsRef<str>> DropdownOptions for [T] {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn label(&self, index: usize) -> &str {
+        self[index].as_ref()
+    }
+}
+
 #[inline]
 pub fn dropdown<T, A>(
     frame: &mut Frame<A>,
@@ -37,7 +106,9 @@ where
     T: AsRef<str>,
     A: Allocator + Clone,
 {
-    do_dropdown_and_take_out_trash(frame, id, label, options, selected, false, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_dropdown_and_take_out_trash(frame, id, label, options, selected, false, theme)
+    })
 }
 
 #[inline]
@@ -68,7 +139,9 @@ where
     T: AsRef<str>,
     A: Allocator + Clone,
 {
-    do_dropdown_and_take_out_trash(frame, id, label, options, selected, true, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_dropdown_and_take_out_trash(frame, id, label, options, selected, true, theme)
+    })
 }
 
 #[inline]
@@ -87,30 +160,124 @@ where
     do_dropdown_and_take_out_trash(frame, id, label, options, selected, true, theme)
 }
 
-fn do_dropdown_and_take_out_trash<T, A>(
+#[inline]
+pub fn dropdown_with_options<O, A>(
     frame: &mut Frame<A>,
     id: u32,
     label: &str,
-    options: &[T],
+    options: &O,
+    selected: &mut Option<usize>,
+) -> bool
+where
+    O: DropdownOptions + ?Sized,
+    A: Allocator + Clone,
+{
+    frame.with_theme(|frame, theme| {
+        do_dropdown_and_take_out_trash(frame, id, label, options, selected, false, theme)
+    })
+}
+
+#[inline]
+pub fn dropdown_with_options_theme<O, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &O,
     selected: &mut Option<usize>,
-    allow_unselect: bool,
     theme: &Theme,
 ) -> bool
 where
-    T: AsRef<str>,
+    O: DropdownOptions + ?Sized,
+    A: Allocator + Clone,
+{
+    do_dropdown_and_take_out_trash(frame, id, label, options, selected, false, theme)
+}
+
+#[inline]
+pub fn dropdown_with_options_unselect<O, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &O,
+    selected: &mut Option<usize>,
+) -> bool
+where
+    O: DropdownOptions + ?Sized,
+    A: Allocator + Clone,
+{
+    frame.with_theme(|frame, theme| {
+        do_dropdown_and_take_out_trash(frame, id, label, options, selected, true, theme)
+    })
+}
+
+#[inline]
+pub fn dropdown_with_options_unselect_theme<O, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &O,
+    selected: &mut Option<usize>,
+    theme: &Theme,
+) -> bool
+where
+    O: DropdownOptions + ?Sized,
     A: Allocator + Clone,
 {
-    const OVERLAY_SPACING: f32 = 5.0;
+    do_dropdown_and_take_out_trash(frame, id, label, options, selected, true, theme)
+}
 
+fn do_dropdown_and_take_out_trash<O, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &O,
+    selected: &mut Option<usize>,
+    allow_unselect: bool,
+    theme: &Theme,
+) -> bool
+where
+    O: DropdownOptions + ?Sized,
+    A: Allocator + Clone,
+{
     let parent_size = frame.ctrl_inner_size();
     let window_size = frame.window_size();
-    let cursor_position = frame.cursor_position();
-    let lmb_pressed = frame.inputs_pressed() == Inputs::MB_LEFT;
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+    let delta_time = frame.delta_time();
+
+    // NB: Copy the events out of the frame, because processing them requires
+    // borrowing the frame mutably through the control. The capacity matches
+    // the event queue in Ui.
+    let mut input_events: ArrayVec<InputEvent, 64> = ArrayVec::new();
+    let _ = input_events.try_extend_from_slice(frame.input_events());
 
     let outer_width = f32::max(0.0, parent_size.x - 2.0 * theme.dropdown_margin);
     let label_width = LABEL_WIDTH_RATIO * outer_width;
     let inner_width = f32::max(0.0, outer_width - label_width - LABEL_SPACING);
 
+    // The overlay has a row for each option, and an empty row for unselecting
+    // before them, if allowed.
+    let first_option_row = usize::from(allow_unselect);
+    let row_count = first_option_row + options.count();
+    let row_selectable = |row: usize| {
+        if row < first_option_row {
+            true
+        } else {
+            let index = row - first_option_row;
+            options.enabled(index) && !options.separator(index)
+        }
+    };
+    let row_height = |row: usize| {
+        if row >= first_option_row && options.separator(row - first_option_row) {
+            theme.separator_height + 2.0 * theme.separator_margin
+        } else {
+            theme.button_height + 2.0 * theme.button_margin
+        }
+    };
+    let selected_row = match selected {
+        Some(selected) => first_option_row + *selected,
+        None => 0,
+    };
+
     let mut outer_ctrl = frame.push_ctrl(id);
     outer_ctrl.set_flags(CtrlFlags::NONE);
     outer_ctrl.set_layout(Layout::Horizontal);
@@ -125,6 +292,7 @@ where
         Align::Start,
         Align::Center,
         Wrap::Word,
+        Direction::Ltr,
         theme.dropdown_text_color,
         Rect::new(0.0, 0.0, label_width, theme.dropdown_height),
     );
@@ -142,64 +310,124 @@ where
     active_area_ctrl.set_border(theme.dropdown_border);
     active_area_ctrl.set_margin(0.0);
 
-    let absolute_position = active_area_ctrl.absolute_position();
-
-    let overlay_y = absolute_position.y + theme.dropdown_height + OVERLAY_SPACING;
-
-    let available_height_up = overlay_y;
-    let available_height_down = f32::max(window_size.y - overlay_y, 0.0);
-
     let overlay_height_requested = f32::min(
-        options.len() as f32 * (theme.button_height + 2.0 * theme.button_margin),
+        (0..row_count).map(row_height).sum(),
         theme.dropdown_overlay_max_height,
     );
-
-    let overlay_rect = if overlay_height_requested > available_height_down {
-        if available_height_down > available_height_up {
-            Rect::new(
-                absolute_position.x,
-                overlay_y,
-                inner_width,
-                available_height_down,
-            )
-        } else {
-            let height = f32::min(available_height_up, overlay_height_requested);
-            Rect::new(
-                absolute_position.x,
-                absolute_position.y - height - OVERLAY_SPACING,
-                inner_width,
-                height,
-            )
-        }
-    } else {
-        Rect::new(
-            absolute_position.x,
-            overlay_y,
-            inner_width,
-            overlay_height_requested,
-        )
-    };
+    let overlay_rect = overlay_rect(
+        active_area_ctrl.absolute_position(),
+        inner_width,
+        overlay_height_requested,
+        window_size,
+        theme,
+    );
 
     let hovered = active_area_ctrl.is_hovered();
     let mut active = active_area_ctrl.is_active();
 
-    let state = active_area_ctrl.state_mut();
-    let mut open = open(state);
+    let mut state = *cast_state(active_area_ctrl.state());
+    let mut open = state.open == OPEN;
+    let mut highlight_changed = false;
+    let mut changed = false;
 
-    if lmb_pressed {
-        if open {
-            if !overlay_rect.contains_point(cursor_position) {
-                set_open(state, false);
-                active_area_ctrl.set_active(false);
-                active = false;
-                open = false;
+    // Clicking outside of the dropdown and its overlay closes the overlay,
+    // and so does clicking the dropdown again.
+    if open && (active_area_ctrl.clicked_outside() || lmb_pressed && hovered) {
+        active_area_ctrl.set_active(false);
+        active = false;
+        open = false;
+    } else if !open && lmb_pressed && hovered {
+        active_area_ctrl.set_active(true);
+        active = true;
+        open = true;
+        state.highlighted_row = cast_u32(selected_row);
+        highlight_changed = true;
+    }
+
+    if let Some(delta_time) = delta_time {
+        state.type_ahead_time += delta_time;
+    }
+
+    if active {
+        let was_closed = !open;
+
+        for event in input_events {
+            match event {
+                InputEvent::KeyPress { input, .. } => {
+                    if !open {
+                        if input.intersects(Inputs::KB_ENTER | Inputs::KB_SPACE) {
+                            open = true;
+                            state.highlighted_row = cast_u32(selected_row);
+                            highlight_changed = true;
+                        } else if input.contains(Inputs::KB_ESCAPE) {
+                            active_area_ctrl.set_active(false);
+                            active = false;
+
+                            // We are no longer active, the rest of the events
+                            // is not for us.
+                            break;
+                        }
+                    } else if input.contains(Inputs::KB_UP_ARROW) {
+                        let highlighted_row = cast_usize(state.highlighted_row);
+                        let row = (0..usize::min(highlighted_row, row_count))
+                            .rev()
+                            .find(|&row| row_selectable(row));
+
+                        if let Some(row) = row {
+                            state.highlighted_row = cast_u32(row);
+                            highlight_changed = true;
+                        }
+                    } else if input.contains(Inputs::KB_DOWN_ARROW) {
+                        let highlighted_row = cast_usize(state.highlighted_row);
+                        let row = (highlighted_row + 1..row_count).find(|&row| row_selectable(row));
+
+                        if let Some(row) = row {
+                            state.highlighted_row = cast_u32(row);
+                            highlight_changed = true;
+                        }
+                    } else if input.contains(Inputs::KB_ENTER) {
+                        let highlighted_row = cast_usize(state.highlighted_row);
+                        if highlighted_row < row_count && row_selectable(highlighted_row) {
+                            if highlighted_row < first_option_row {
+                                *selected = None;
+                            } else {
+                                *selected = Some(highlighted_row - first_option_row);
+                            }
+
+                            changed = true;
+                        }
+
+                        open = false;
+                    } else if input.contains(Inputs::KB_ESCAPE) {
+                        open = false;
+                    }
+                }
+
+                InputEvent::Char(c) => {
+                    // The space that opened the dropdown also arrives as a
+                    // character, don't use it for type-ahead.
+                    if !open || was_closed || c.is_control() {
+                        continue;
+                    }
+
+                    if let Some(row) = type_ahead(&mut state, c, options) {
+                        state.highlighted_row = cast_u32(first_option_row + row);
+                        highlight_changed = true;
+                    }
+                }
+
+                _ => (),
             }
-        } else if hovered {
-            set_open(state, true);
-            active_area_ctrl.set_active(true);
-            active = true;
-            open = true;
         }
+
+        active_area_ctrl.request_want_capture_keyboard();
+        if open {
+            active_area_ctrl.request_want_text_input();
+        }
+    }
+
+    if !open {
+        state.type_ahead_len = 0;
     }
 
     let (text_color, background_color, border_color) = match (hovered, active) {
@@ -225,16 +453,24 @@ where
     active_area_ctrl.set_draw_self_background_color(background_color);
 
     let label = if let Some(selected) = selected {
-        options[*selected].as_ref()
+        options.label(*selected)
     } else {
         ""
     };
 
-    active_area_ctrl.draw_text(label, Align::Center, Align::Center, Wrap::Word, text_color);
+    active_area_ctrl.draw_text(
+        label,
+        Align::Center,
+        Align::Center,
+        Wrap::Word,
+        Direction::Ltr,
+        text_color,
+    );
 
-    let mut changed = false;
+    let highlighted_row = cast_usize(state.highlighted_row);
 
     if open {
+        active_area_ctrl.close_on_outside_click();
         frame.begin_overlay();
 
         let mut ctrl = frame.push_ctrl(id);
@@ -251,17 +487,44 @@ where
         ctrl.set_draw_self_border_color(theme.dropdown_border_color_active);
         ctrl.set_draw_self_background_color(theme.dropdown_background_color_active);
 
+        if highlight_changed && highlighted_row < row_count {
+            let y = (0..highlighted_row).map(row_height).sum();
+            ctrl.scroll_to_rect(Rect::new(0.0, y, inner_width, row_height(highlighted_row)));
+        }
+
+        // NB: Clicking the options makes them active for as long as the mouse
+        // button is held, but once released, they hand the active status back
+        // to us, because we own the overlay.
+
         if allow_unselect {
-            if button(frame, 0, "") {
+            let highlighted = highlighted_row == 0;
+            if option(frame, 0, "", "", None, true, highlighted, theme) {
                 *selected = None;
                 changed = true;
             }
         }
 
-        for (i, option) in options.iter().enumerate() {
-            if button(frame, 1 + cast_u32(i), option.as_ref()) {
-                *selected = Some(i);
-                changed = true;
+        for i in 0..options.count() {
+            let id = 1 + cast_u32(i);
+
+            if options.separator(i) {
+                separator_with_theme(frame, id, theme);
+            } else {
+                let highlighted = highlighted_row == first_option_row + i;
+
+                if option(
+                    frame,
+                    id,
+                    options.label(i),
+                    options.hint(i),
+                    options.texture_id(i),
+                    options.enabled(i),
+                    highlighted,
+                    theme,
+                ) {
+                    *selected = Some(i);
+                    changed = true;
+                }
             }
         }
 
@@ -271,19 +534,662 @@ where
     }
 
     if changed {
-        set_open(frame.ctrl_state_mut(), false);
+        open = false;
+        state.type_ahead_len = 0;
+    }
+
+    state.open = if open { OPEN } else { CLOSED };
+    *cast_state_mut(frame.ctrl_state_mut()) = state;
+
+    frame.pop_ctrl();
+    frame.pop_ctrl();
+
+    changed
+}
+
+/// Like [`dropdown`], but any number of options can be selected. The overlay
+/// shows a checkbox for each option, and stays open while they are toggled.
+/// The closed dropdown summarizes the selection, e.g. "3 selected". Returns
+/// whether any option was toggled.
+///
+/// There must be as many selected flags as there are options.
+#[inline]
+pub fn dropdown_multi<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &[T],
+    selected: &mut [bool],
+) -> bool
+where
+    T: AsRef<str>,
+    A: Allocator + Clone,
+{
+    frame.with_theme(|frame, theme| {
+        dropdown_multi_with_theme(frame, id, label, options, selected, theme)
+    })
+}
+
+pub fn dropdown_multi_with_theme<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    options: &[T],
+    selected: &mut [bool],
+    theme: &Theme,
+) -> bool
+where
+    T: AsRef<str>,
+    A: Allocator + Clone,
+{
+    assert_eq!(
+        options.len(),
+        selected.len(),
+        "There must be as many selected flags as there are options",
+    );
+
+    let parent_size = frame.ctrl_inner_size();
+    let window_size = frame.window_size();
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+
+    // NB: Copy the events out of the frame, because processing them requires
+    // borrowing the frame mutably through the control. The capacity matches
+    // the event queue in Ui.
+    let mut input_events: ArrayVec<InputEvent, 64> = ArrayVec::new();
+    let _ = input_events.try_extend_from_slice(frame.input_events());
+
+    let outer_width = f32::max(0.0, parent_size.x - 2.0 * theme.dropdown_margin);
+    let label_width = LABEL_WIDTH_RATIO * outer_width;
+    let inner_width = f32::max(0.0, outer_width - label_width - LABEL_SPACING);
+
+    let mut outer_ctrl = frame.push_ctrl(id);
+    outer_ctrl.set_flags(CtrlFlags::NONE);
+    outer_ctrl.set_layout(Layout::Horizontal);
+    outer_ctrl.set_rect(Rect::new(0.0, 0.0, outer_width, theme.dropdown_height));
+    outer_ctrl.set_padding(0.0);
+    outer_ctrl.set_border(0.0);
+    outer_ctrl.set_margin(theme.dropdown_margin);
+
+    outer_ctrl.set_draw_self(false);
+    outer_ctrl.draw_text_fitted(
+        label,
+        Align::Start,
+        Align::Center,
+        Wrap::Word,
+        Direction::Ltr,
+        theme.dropdown_text_color,
+        Rect::new(0.0, 0.0, label_width, theme.dropdown_height),
+    );
+
+    let mut active_area_ctrl = frame.push_ctrl(0);
+    active_area_ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+    active_area_ctrl.set_layout(Layout::Vertical);
+    active_area_ctrl.set_rect(Rect::new(
+        label_width + LABEL_SPACING,
+        0.0,
+        inner_width,
+        theme.dropdown_height,
+    ));
+    active_area_ctrl.set_padding(0.0);
+    active_area_ctrl.set_border(theme.dropdown_border);
+    active_area_ctrl.set_margin(0.0);
+
+    let overlay_height_requested = f32::min(
+        options.len() as f32 * (theme.checkbox_height + 2.0 * theme.checkbox_margin),
+        theme.dropdown_overlay_max_height,
+    );
+    let overlay_rect = overlay_rect(
+        active_area_ctrl.absolute_position(),
+        inner_width,
+        overlay_height_requested,
+        window_size,
+        theme,
+    );
+
+    let hovered = active_area_ctrl.is_hovered();
+    let mut active = active_area_ctrl.is_active();
+
+    let mut state = *cast_state(active_area_ctrl.state());
+    let mut open = state.open == OPEN;
+    let mut changed = false;
+
+    // Unlike single-select dropdowns, clicking inside the overlay doesn't
+    // close it, only clicking outside does, or clicking the dropdown again.
+    if open && (active_area_ctrl.clicked_outside() || lmb_pressed && hovered) {
+        active_area_ctrl.set_active(false);
+        active = false;
+        open = false;
+    } else if !open && lmb_pressed && hovered {
+        active_area_ctrl.set_active(true);
+        active = true;
+        open = true;
+    }
+
+    if active {
+        for event in input_events {
+            if let InputEvent::KeyPress { input, .. } = event {
+                if !open && input.intersects(Inputs::KB_ENTER | Inputs::KB_SPACE) {
+                    open = true;
+                } else if open && input.intersects(Inputs::KB_ENTER | Inputs::KB_ESCAPE) {
+                    open = false;
+                } else if input.contains(Inputs::KB_ESCAPE) {
+                    active_area_ctrl.set_active(false);
+                    active = false;
+
+                    // We are no longer active, the rest of the events is not
+                    // for us.
+                    break;
+                }
+            }
+        }
+
+        active_area_ctrl.request_want_capture_keyboard();
+    }
+
+    let (text_color, background_color, border_color) = match (hovered, active) {
+        (false, false) => (
+            theme.dropdown_text_color,
+            theme.dropdown_background_color,
+            theme.dropdown_border_color,
+        ),
+        (true, false) => (
+            theme.dropdown_text_color_hovered,
+            theme.dropdown_background_color_hovered,
+            theme.dropdown_border_color_hovered,
+        ),
+        (_, true) => (
+            theme.dropdown_text_color_active,
+            theme.dropdown_background_color_active,
+            theme.dropdown_border_color_active,
+        ),
+    };
+
+    active_area_ctrl.set_draw_self(true);
+    active_area_ctrl.set_draw_self_border_color(border_color);
+    active_area_ctrl.set_draw_self_background_color(background_color);
+
+    // A single selected option is shown by its label, more are counted.
+    let mut summary: ArrayString<32> = ArrayString::new();
+    let selected_count = selected.iter().filter(|&&selected| selected).count();
+    let summary = match selected_count {
+        0 => "",
+        1 => {
+            let index = selected.iter().position(|&selected| selected).unwrap();
+            options[index].as_ref()
+        }
+        _ => {
+            let _ = write!(summary, "{selected_count} selected");
+            &summary
+        }
+    };
+
+    active_area_ctrl.draw_text(
+        summary,
+        Align::Center,
+        Align::Center,
+        Wrap::Word,
+        Direction::Ltr,
+        text_color,
+    );
+
+    if open {
+        active_area_ctrl.close_on_outside_click();
+        frame.begin_overlay();
+
+        let mut ctrl = frame.push_ctrl(id);
+        ctrl.set_flags(CtrlFlags::CAPTURE_SCROLL | CtrlFlags::CAPTURE_HOVER);
+        ctrl.set_layout(Layout::Vertical);
+        ctrl.set_rect(overlay_rect);
+
+        // Margin is zero, because we are setting an absolute position.
+        ctrl.set_padding(0.0);
+        ctrl.set_border(theme.dropdown_border);
+        ctrl.set_margin(0.0);
+
+        ctrl.set_draw_self(true);
+        ctrl.set_draw_self_border_color(theme.dropdown_border_color_active);
+        ctrl.set_draw_self_background_color(theme.dropdown_background_color_active);
+
+        // NB: Clicking the checkboxes makes them active for as long as the
+        // mouse button is held, but once released, they hand the active status
+        // back to us, because we own the overlay.
+        for (i, option) in options.iter().enumerate() {
+            let id = cast_u32(i);
+            if checkbox_with_theme(frame, id, &mut selected[i], option.as_ref(), theme) {
+                changed = true;
+            }
+        }
+
+        frame.pop_ctrl();
+
+        frame.end_overlay();
     }
 
+    state.open = if open { OPEN } else { CLOSED };
+    *cast_state_mut(frame.ctrl_state_mut()) = state;
+
     frame.pop_ctrl();
     frame.pop_ctrl();
 
     changed
 }
 
-fn open(state: &CtrlState) -> bool {
-    state[0] == 1
+// Places the overlay below the dropdown, or above it, if there's more space
+// there, and the overlay doesn't fit below.
+fn overlay_rect(
+    absolute_position: Vec2,
+    width: f32,
+    height_requested: f32,
+    window_size: Vec2,
+    theme: &Theme,
+) -> Rect {
+    let overlay_y = absolute_position.y + theme.dropdown_height + OVERLAY_SPACING;
+
+    let available_height_up = overlay_y;
+    let available_height_down = f32::max(window_size.y - overlay_y, 0.0);
+
+    if height_requested > available_height_down {
+        if available_height_down > available_height_up {
+            Rect::new(absolute_position.x, overlay_y, width, available_height_down)
+        } else {
+            let height = f32::min(available_height_up, height_requested);
+            Rect::new(
+                absolute_position.x,
+                absolute_position.y - height - OVERLAY_SPACING,
+                width,
+                height,
+            )
+        }
+    } else {
+        Rect::new(absolute_position.x, overlay_y, width, height_requested)
+    }
 }
 
-fn set_open(state: &mut CtrlState, open: bool) {
-    state[0] = u8::from(open)
+// Draws an option in the overlay. Behaves like a button, unless disabled.
+// Highlighted options are drawn as if hovered. Also used by combos.
+pub(crate) fn option<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    hint: &str,
+    texture_id: Option<u64>,
+    enabled: bool,
+    highlighted: bool,
+    theme: &Theme,
+) -> bool {
+    let parent_size = frame.ctrl_inner_size();
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
+
+    let width = f32::max(0.0, parent_size.x - 2.0 * theme.button_margin);
+    let height = theme.button_height;
+
+    let mut ctrl = frame.push_ctrl(id);
+    if enabled {
+        ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+    } else {
+        // Disabled options still capture hover, so that hovering them doesn't
+        // fall through to the controls below the overlay.
+        ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
+    }
+    ctrl.set_layout(Layout::Vertical);
+    ctrl.set_rect(Rect::new(0.0, 0.0, width, height));
+    ctrl.set_padding(0.0);
+    ctrl.set_border(theme.button_border);
+    ctrl.set_margin(theme.button_margin);
+
+    let hovered = enabled && ctrl.is_hovered();
+    let active = enabled && ctrl.is_active();
+
+    if hovered {
+        ctrl.set_cursor_icon(CursorIcon::Hand);
+    }
+
+    let (active, changed) = if active && lmb_released {
+        ctrl.set_active(false);
+        (false, hovered)
+    } else if hovered && lmb_pressed {
+        ctrl.set_active(true);
+        (true, false)
+    } else {
+        (active, false)
+    };
+
+    let (text_color, background_color, border_color) =
+        match (enabled, hovered || highlighted, active) {
+            (false, _, _) => (
+                theme.dropdown_option_text_color_disabled,
+                theme.button_background_color,
+                theme.button_border_color,
+            ),
+            (true, false, false) => (
+                theme.button_text_color,
+                theme.button_background_color,
+                theme.button_border_color,
+            ),
+            (true, true, false) => (
+                theme.button_text_color_hovered,
+                theme.button_background_color_hovered,
+                theme.button_border_color_hovered,
+            ),
+            (true, _, true) => (
+                theme.button_text_color_active,
+                theme.button_background_color_active,
+                theme.button_border_color_active,
+            ),
+        };
+
+    ctrl.set_draw_self(true);
+    ctrl.set_draw_self_border_color(border_color);
+    ctrl.set_draw_self_background_color(background_color);
+
+    let inner_width = f32::max(0.0, width - 2.0 * theme.button_border);
+    let inner_height = f32::max(0.0, height - 2.0 * theme.button_border);
+
+    if let Some(texture_id) = texture_id {
+        let color = if enabled {
+            0xffffffff
+        } else {
+            theme.dropdown_option_text_color_disabled
+        };
+
+        ctrl.draw_rect(
+            Rect::new(0.0, 0.0, inner_height, inner_height),
+            Rect::ONE,
+            color,
+            texture_id,
+        );
+    }
+
+    ctrl.draw_text(
+        label,
+        Align::Center,
+        Align::Center,
+        Wrap::Word,
+        Direction::Ltr,
+        text_color,
+    );
+
+    if hint.len() > 0 {
+        ctrl.draw_text_fitted(
+            hint,
+            Align::End,
+            Align::Center,
+            Wrap::None,
+            Direction::Ltr,
+            text_color,
+            Rect::new(
+                0.0,
+                0.0,
+                f32::max(0.0, inner_width - OPTION_HINT_SPACING),
+                inner_height,
+            ),
+        );
+    }
+
+    frame.pop_ctrl();
+
+    changed
+}
+
+// Appends the character to the type-ahead prefix, and returns the index of the
+// first selectable option that starts with it, ignoring ASCII case. If nothing
+// matches, starts over with just the new character, so that typing without
+// pauses still works if the frame time is unknown.
+fn type_ahead<O>(state: &mut State, c: char, options: &O) -> Option<usize>
+where
+    O: DropdownOptions + ?Sized,
+{
+    if state.type_ahead_time > TYPE_AHEAD_TIMEOUT {
+        state.type_ahead_len = 0;
+    }
+    state.type_ahead_time = 0.0;
+
+    let mut buf = [0; 4];
+    let c = c.encode_utf8(&mut buf).as_bytes();
+
+    let len = cast_usize(state.type_ahead_len);
+    if len + c.len() <= TYPE_AHEAD_CAPACITY {
+        state.type_ahead[len..len + c.len()].copy_from_slice(c);
+        state.type_ahead_len += cast_u32(c.len());
+    }
+
+    let find = |prefix: &[u8]| {
+        (0..options.count()).find(|&i| {
+            let label = options.label(i).as_bytes();
+
+            options.enabled(i)
+                && !options.separator(i)
+                && label.len() >= prefix.len()
+                && label[..prefix.len()].eq_ignore_ascii_case(prefix)
+        })
+    };
+
+    let prefix = &state.type_ahead[..cast_usize(state.type_ahead_len)];
+    if let Some(position) = find(prefix) {
+        return Some(position);
+    }
+
+    state.type_ahead[..c.len()].copy_from_slice(c);
+    state.type_ahead_len = cast_u32(c.len());
+
+    find(c)
+}
+
+const CLOSED: u32 = 0;
+const OPEN: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    open: u32,
+    // The row highlighted for keyboard selection.
+    highlighted_row: u32,
+    // Seconds since the last character was added to the type-ahead prefix.
+    type_ahead_time: f32,
+    type_ahead_len: u32,
+    type_ahead: [u8; TYPE_AHEAD_CAPACITY],
+}
+
+fn cast_state(state: &CtrlState) -> &State {
+    bytemuck::from_bytes(&state[..mem::size_of::<State>()])
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::{font_source, new_ui};
+    use crate::testing::TestHarness;
+
+    const OPTIONS: &[&str] = &["Apple", "Banana", "Blueberry", "Cherry"];
+
+    struct Fruits;
+
+    impl DropdownOptions for Fruits {
+        fn count(&self) -> usize {
+            4
+        }
+
+        fn label(&self, index: usize) -> &str {
+            ["Apple", "", "Banana", "Cherry"][index]
+        }
+
+        fn enabled(&self, index: usize) -> bool {
+            index != 2
+        }
+
+        fn separator(&self, index: usize) -> bool {
+            index == 1
+        }
+    }
+
+    fn build(ui: &mut Ui<Global>, selected: &mut Option<usize>) -> bool {
+        build_with_options(ui, OPTIONS, selected)
+    }
+
+    fn build_with_options<O: DropdownOptions + ?Sized>(
+        ui: &mut Ui<Global>,
+        options: &O,
+        selected: &mut Option<usize>,
+    ) -> bool {
+        let mut frame = ui.begin_frame();
+        let changed = dropdown_with_options(&mut frame, 0, "Label", options, selected);
+        ui.end_frame();
+
+        changed
+    }
+
+    fn open_dropdown(ui: &mut Ui<Global>, selected: &mut Option<usize>) {
+        // First frame lays out the control, so that it can be hovered.
+        build(ui, selected);
+
+        ui.set_cursor_position(700.0, 10.0);
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(ui, selected);
+        ui.release_inputs(Inputs::MB_LEFT);
+        build(ui, selected);
+    }
+
+    #[test]
+    fn test_dropdown_keyboard_selection() {
+        let mut selected = Some(0);
+        let mut ui = new_ui();
+        open_dropdown(&mut ui, &mut selected);
+        assert!(ui.want_capture_keyboard());
+
+        ui.press_inputs(Inputs::KB_DOWN_ARROW);
+        ui.release_inputs(Inputs::KB_DOWN_ARROW);
+        ui.press_inputs(Inputs::KB_DOWN_ARROW);
+        ui.release_inputs(Inputs::KB_DOWN_ARROW);
+        ui.press_inputs(Inputs::KB_UP_ARROW);
+        assert!(!build(&mut ui, &mut selected));
+        ui.release_inputs(Inputs::KB_UP_ARROW);
+
+        ui.press_inputs(Inputs::KB_ENTER);
+        assert!(build(&mut ui, &mut selected));
+        ui.release_inputs(Inputs::KB_ENTER);
+        assert_eq!(selected, Some(1));
+
+        // Escape closes without changing the selection, and stays active, so
+        // that the dropdown can be opened again from the keyboard.
+        ui.press_inputs(Inputs::KB_SPACE);
+        build(&mut ui, &mut selected);
+        ui.release_inputs(Inputs::KB_SPACE);
+        ui.press_inputs(Inputs::KB_DOWN_ARROW);
+        ui.release_inputs(Inputs::KB_DOWN_ARROW);
+        ui.press_inputs(Inputs::KB_ESCAPE);
+        assert!(!build(&mut ui, &mut selected));
+        ui.release_inputs(Inputs::KB_ESCAPE);
+        assert_eq!(selected, Some(1));
+        assert!(ui.want_capture_keyboard());
+    }
+
+    #[test]
+    fn test_dropdown_type_ahead() {
+        let mut selected = None;
+        let mut ui = new_ui();
+        open_dropdown(&mut ui, &mut selected);
+
+        ui.send_character('b');
+        ui.send_character('L');
+        build(&mut ui, &mut selected);
+
+        ui.press_inputs(Inputs::KB_ENTER);
+        assert!(build(&mut ui, &mut selected));
+        ui.release_inputs(Inputs::KB_ENTER);
+        assert_eq!(selected, Some(2));
+
+        // Nothing starts with "blc", so we start over from "c".
+        ui.press_inputs(Inputs::KB_ENTER);
+        build(&mut ui, &mut selected);
+        ui.release_inputs(Inputs::KB_ENTER);
+        ui.send_character('b');
+        ui.send_character('l');
+        ui.send_character('c');
+        ui.press_inputs(Inputs::KB_ENTER);
+        assert!(build(&mut ui, &mut selected));
+        assert_eq!(selected, Some(3));
+    }
+
+    #[test]
+    fn test_dropdown_skips_disabled_options_and_separators() {
+        let mut selected = Some(0);
+        let mut ui = new_ui();
+        open_dropdown(&mut ui, &mut selected);
+
+        ui.press_inputs(Inputs::KB_DOWN_ARROW);
+        build_with_options(&mut ui, &Fruits, &mut selected);
+        ui.release_inputs(Inputs::KB_DOWN_ARROW);
+
+        ui.press_inputs(Inputs::KB_ENTER);
+        assert!(build_with_options(&mut ui, &Fruits, &mut selected));
+        ui.release_inputs(Inputs::KB_ENTER);
+        assert_eq!(selected, Some(3));
+
+        // Type-ahead doesn't find the disabled option either.
+        ui.press_inputs(Inputs::KB_ENTER);
+        build_with_options(&mut ui, &Fruits, &mut selected);
+        ui.release_inputs(Inputs::KB_ENTER);
+        ui.send_character('b');
+        build_with_options(&mut ui, &Fruits, &mut selected);
+        ui.press_inputs(Inputs::KB_ENTER);
+        build_with_options(&mut ui, &Fruits, &mut selected);
+        assert_eq!(selected, Some(3));
+    }
+
+    #[test]
+    fn test_dropdown_multi_toggles_without_closing() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut selected = [false; 4];
+
+        {
+            let mut changed = false;
+            let mut build = |frame: &mut Frame<Global>| {
+                changed |= dropdown_multi(frame, 0, "Label", OPTIONS, &mut selected);
+            };
+
+            harness.run_frame(&mut build);
+            harness.click_at(700.0, 10.0, &mut build);
+
+            // NB: The first checkbox shares its id path with the active area,
+            // so we toggle the ones after it.
+            let banana = harness.center_of(&[0, 1]).unwrap();
+            harness.click_at(banana.x, banana.y, &mut build);
+            let blueberry = harness.center_of(&[0, 2]).unwrap();
+            harness.click_at(blueberry.x, blueberry.y, &mut build);
+
+            // The overlay is still open.
+            assert!(harness.find(&[0, 3]).is_some());
+            assert!(changed);
+        }
+
+        assert_eq!(selected, [false, true, true, false]);
+    }
+
+    #[test]
+    fn test_dropdown_multi_closes_on_click_outside() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut selected = [true, false, false, true];
+
+        let mut changed = false;
+        let mut build = |frame: &mut Frame<Global>| {
+            changed |= dropdown_multi(frame, 0, "Label", OPTIONS, &mut selected);
+        };
+
+        harness.run_frame(&mut build);
+        harness.click_at(700.0, 10.0, &mut build);
+        assert!(harness.find(&[0, 3]).is_some());
+
+        harness.click_at(100.0, 500.0, &mut build);
+        assert!(harness.find(&[0, 3]).is_none());
+        assert!(!changed);
+    }
 }
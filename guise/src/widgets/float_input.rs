@@ -4,8 +4,8 @@ use core::str::FromStr;
 
 use arrayvec::ArrayString;
 
-use crate::core::Frame;
-use crate::widgets::{do_text_input_and_file_taxes, Theme};
+use crate::core::{Direction, Frame, Theme};
+use crate::widgets::do_text_input_and_file_taxes;
 
 // TODO(yan): float2_input, float3_input, float4_input
 // TODO(yan): Consider adding a slider handle to float inputs and removing float sliders.
@@ -15,16 +15,18 @@ pub fn float_input<A>(frame: &mut Frame<A>, id: u32, value: &mut f32, label: &st
 where
     A: Allocator + Clone,
 {
-    float_input_with_min_max_precision_theme(
-        frame,
-        id,
-        value,
-        label,
-        f32::MIN,
-        f32::MAX,
-        3,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        float_input_with_min_max_precision_theme(
+            frame,
+            id,
+            value,
+            label,
+            f32::MIN,
+            f32::MAX,
+            3,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -40,16 +42,11 @@ pub fn float_input_with_min_max_precision<A>(
 where
     A: Allocator + Clone,
 {
-    float_input_with_min_max_precision_theme(
-        frame,
-        id,
-        value,
-        label,
-        min,
-        max,
-        precision,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        float_input_with_min_max_precision_theme(
+            frame, id, value, label, min, max, precision, theme,
+        )
+    })
 }
 
 #[inline]
@@ -94,6 +91,7 @@ where
         None,
         Some(&float_filter),
         &[],
+        Direction::Ltr,
         theme,
     ) {
         match f32::from_str(&buf) {
@@ -6,8 +6,20 @@ use core::slice;
 use arrayvec::ArrayString;
 
 use crate::convert::cast_u32;
-use crate::core::{Align, CtrlFlags, CtrlState, Frame, Inputs, Layout, Rect, Wrap};
-use crate::widgets::theme::Theme;
+use crate::core::{
+    Align,
+    CtrlFlags,
+    CtrlState,
+    Direction,
+    Frame,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Wrap,
+};
+use crate::widgets::button::animated_colors;
+use crate::widgets::response::Response;
 
 pub fn float_slider<A: Allocator + Clone>(
     frame: &mut Frame<A>,
@@ -15,17 +27,20 @@ pub fn float_slider<A: Allocator + Clone>(
     value: &mut f32,
     label: &str,
 ) -> bool {
-    do_float_slider_and_take_kids_to_school(
-        frame,
-        id,
-        slice::from_mut(value),
-        label,
-        1.0,
-        f32::MIN,
-        f32::MAX,
-        3,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame,
+            id,
+            slice::from_mut(value),
+            label,
+            1.0,
+            f32::MIN,
+            f32::MAX,
+            3,
+            theme,
+        )
+        .changed
+    })
 }
 
 pub fn float_slider_with_speed_min_max_precision<A: Allocator + Clone>(
@@ -37,6 +52,33 @@ pub fn float_slider_with_speed_min_max_precision<A: Allocator + Clone>(
     min: f32,
     max: f32,
     precision: u16,
+) -> bool {
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame,
+            id,
+            slice::from_mut(value),
+            label,
+            speed,
+            min,
+            max,
+            precision,
+            theme,
+        )
+        .changed
+    })
+}
+
+pub fn float_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut f32,
+    label: &str,
+    speed: f32,
+    min: f32,
+    max: f32,
+    precision: u16,
+    theme: &Theme,
 ) -> bool {
     do_float_slider_and_take_kids_to_school(
         frame,
@@ -47,11 +89,36 @@ pub fn float_slider_with_speed_min_max_precision<A: Allocator + Clone>(
         min,
         max,
         precision,
-        &Theme::DEFAULT,
+        theme,
     )
+    .changed
 }
 
-pub fn float_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
+/// Like [`float_slider`], but reports hover, whether the slider is being
+/// dragged, and where it was laid out, in addition to changes.
+#[inline]
+pub fn float_slider_response<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut f32,
+    label: &str,
+) -> Response {
+    frame.with_theme(|frame, theme| {
+        float_slider_response_with_speed_min_max_precision_theme(
+            frame,
+            id,
+            value,
+            label,
+            1.0,
+            f32::MIN,
+            f32::MAX,
+            3,
+            theme,
+        )
+    })
+}
+
+pub fn float_slider_response_with_speed_min_max_precision_theme<A: Allocator + Clone>(
     frame: &mut Frame<A>,
     id: u32,
     value: &mut f32,
@@ -61,7 +128,7 @@ pub fn float_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
     max: f32,
     precision: u16,
     theme: &Theme,
-) -> bool {
+) -> Response {
     do_float_slider_and_take_kids_to_school(
         frame,
         id,
@@ -81,17 +148,20 @@ pub fn float2_slider<A: Allocator + Clone>(
     value: &mut [f32; 2],
     label: &str,
 ) -> bool {
-    do_float_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        1.0,
-        f32::MIN,
-        f32::MAX,
-        3,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame,
+            id,
+            value,
+            label,
+            1.0,
+            f32::MIN,
+            f32::MAX,
+            3,
+            theme,
+        )
+        .changed
+    })
 }
 
 pub fn float2_slider_with_speed_min_max_precision<A: Allocator + Clone>(
@@ -104,17 +174,12 @@ pub fn float2_slider_with_speed_min_max_precision<A: Allocator + Clone>(
     max: f32,
     precision: u16,
 ) -> bool {
-    do_float_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        speed,
-        min,
-        max,
-        precision,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame, id, value, label, speed, min, max, precision, theme,
+        )
+        .changed
+    })
 }
 
 pub fn float2_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
@@ -131,6 +196,7 @@ pub fn float2_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
     do_float_slider_and_take_kids_to_school(
         frame, id, value, label, speed, min, max, precision, theme,
     )
+    .changed
 }
 
 pub fn float3_slider<A: Allocator + Clone>(
@@ -139,17 +205,20 @@ pub fn float3_slider<A: Allocator + Clone>(
     value: &mut [f32; 3],
     label: &str,
 ) -> bool {
-    do_float_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        1.0,
-        f32::MIN,
-        f32::MAX,
-        3,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame,
+            id,
+            value,
+            label,
+            1.0,
+            f32::MIN,
+            f32::MAX,
+            3,
+            theme,
+        )
+        .changed
+    })
 }
 
 pub fn float3_slider_with_speed_min_max_precision<A: Allocator + Clone>(
@@ -162,17 +231,12 @@ pub fn float3_slider_with_speed_min_max_precision<A: Allocator + Clone>(
     max: f32,
     precision: u16,
 ) -> bool {
-    do_float_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        speed,
-        min,
-        max,
-        precision,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame, id, value, label, speed, min, max, precision, theme,
+        )
+        .changed
+    })
 }
 
 pub fn float3_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
@@ -189,6 +253,7 @@ pub fn float3_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
     do_float_slider_and_take_kids_to_school(
         frame, id, value, label, speed, min, max, precision, theme,
     )
+    .changed
 }
 
 pub fn float4_slider<A: Allocator + Clone>(
@@ -197,17 +262,20 @@ pub fn float4_slider<A: Allocator + Clone>(
     value: &mut [f32; 4],
     label: &str,
 ) -> bool {
-    do_float_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        1.0,
-        f32::MIN,
-        f32::MAX,
-        3,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame,
+            id,
+            value,
+            label,
+            1.0,
+            f32::MIN,
+            f32::MAX,
+            3,
+            theme,
+        )
+        .changed
+    })
 }
 
 pub fn float4_slider_with_speed_min_max_precision<A: Allocator + Clone>(
@@ -220,17 +288,12 @@ pub fn float4_slider_with_speed_min_max_precision<A: Allocator + Clone>(
     max: f32,
     precision: u16,
 ) -> bool {
-    do_float_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        speed,
-        min,
-        max,
-        precision,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_float_slider_and_take_kids_to_school(
+            frame, id, value, label, speed, min, max, precision, theme,
+        )
+        .changed
+    })
 }
 
 pub fn float4_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
@@ -247,6 +310,7 @@ pub fn float4_slider_with_speed_min_max_precision_theme<A: Allocator + Clone>(
     do_float_slider_and_take_kids_to_school(
         frame, id, value, label, speed, min, max, precision, theme,
     )
+    .changed
 }
 
 fn do_float_slider_and_take_kids_to_school<A: Allocator + Clone>(
@@ -259,7 +323,7 @@ fn do_float_slider_and_take_kids_to_school<A: Allocator + Clone>(
     max: f32,
     display_precision: u16,
     theme: &Theme,
-) -> bool {
+) -> Response {
     const LABEL_WIDTH_RATIO: f32 = 0.35;
     const LABEL_SPACING: f32 = 5.0;
     const INPUT_SPACING: f32 = 2.0;
@@ -296,11 +360,21 @@ fn do_float_slider_and_take_kids_to_school<A: Allocator + Clone>(
         Align::Start,
         Align::Center,
         Wrap::Word,
+        Direction::Ltr,
         theme.float_slider_text_color,
         Rect::new(0.0, 0.0, label_width, theme.float_slider_height),
     );
 
-    let mut changed = false;
+    // The response covers the whole row, and is hovered or held, if any of the
+    // inputs is.
+    let mut response = Response {
+        clicked: false,
+        changed: false,
+        hovered: false,
+        held: false,
+        rect: outer_ctrl.absolute_rect(),
+    };
+
     for (i, value_mut_slot) in value_mut.iter_mut().enumerate() {
         let mut inner_ctrl = frame.push_ctrl(cast_u32(i));
         inner_ctrl.set_flags(CtrlFlags::CAPTURE_HOVER);
@@ -324,8 +398,9 @@ fn do_float_slider_and_take_kids_to_school<A: Allocator + Clone>(
             let x = state.x;
             let delta = cursor_position.x - x;
 
-            let new_active = if inputs_released == Inputs::MB_LEFT {
+            let new_active = if inputs_released.contains(Inputs::MB_LEFT) {
                 inner_ctrl.set_active(false);
+                response.clicked |= hovered;
                 false
             } else {
                 true
@@ -336,7 +411,7 @@ fn do_float_slider_and_take_kids_to_school<A: Allocator + Clone>(
 
             *value_mut_slot = new_value;
             (new_active, old_value != new_value)
-        } else if hovered && inputs_pressed == Inputs::MB_LEFT {
+        } else if hovered && inputs_pressed.contains(Inputs::MB_LEFT) {
             inner_ctrl.set_active(true);
 
             let state = cast_state_mut(inner_ctrl.state_mut());
@@ -352,25 +427,29 @@ fn do_float_slider_and_take_kids_to_school<A: Allocator + Clone>(
             inner_ctrl.request_want_capture_keyboard();
         }
 
-        changed |= changed_i;
+        response.changed |= changed_i;
+        response.hovered |= hovered;
+        response.held |= active;
 
-        let (text_color, background_color, border_color) = match (hovered, active) {
-            (false, false) => (
+        let [text_color, background_color, border_color] = animated_colors(
+            &mut inner_ctrl,
+            [
                 theme.float_slider_text_color,
                 theme.float_slider_background_color,
                 theme.float_slider_border_color,
-            ),
-            (true, false) => (
+            ],
+            [
                 theme.float_slider_text_color_hovered,
                 theme.float_slider_background_color_hovered,
                 theme.float_slider_border_color_hovered,
-            ),
-            (_, true) => (
+            ],
+            [
                 theme.float_slider_text_color_active,
                 theme.float_slider_background_color_active,
                 theme.float_slider_border_color_active,
-            ),
-        };
+            ],
+            theme.animation_speed,
+        );
 
         inner_ctrl.set_draw_self(true);
         inner_ctrl.set_draw_self_border_color(border_color);
@@ -378,14 +457,21 @@ fn do_float_slider_and_take_kids_to_school<A: Allocator + Clone>(
 
         s.clear();
         let _ = write!(s, "{:.1$}", value_mut_slot, usize::from(display_precision));
-        inner_ctrl.draw_text(&s, Align::Center, Align::Center, Wrap::Word, text_color);
+        inner_ctrl.draw_text(
+            &s,
+            Align::Center,
+            Align::Center,
+            Wrap::Word,
+            Direction::Ltr,
+            text_color,
+        );
 
         frame.pop_ctrl();
     }
 
     frame.pop_ctrl();
 
-    changed
+    response
 }
 
 #[repr(C)]
@@ -403,3 +489,45 @@ fn cast_state(state: &CtrlState) -> &State {
 fn cast_state_mut(state: &mut CtrlState) -> &mut State {
     bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
 }
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+
+    #[test]
+    fn test_float_slider_animates_hover_colors() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        harness.ui_mut().set_theme(Theme {
+            float_slider_border_color: 0x000000ff,
+            float_slider_border_color_hovered: 0x808080ff,
+            animation_speed: 2.0,
+            ..Theme::DEFAULT
+        });
+
+        let mut value = 0.0;
+        let mut build = |frame: &mut Frame<Global>| {
+            float_slider(frame, 0, &mut value, "Label");
+        };
+
+        let has_vertex_with_color = |harness: &TestHarness, color: u32| {
+            let (_, vertices, _) = harness.ui().draw_list();
+            vertices.iter().any(|vertex| vertex.color == color)
+        };
+
+        harness.run_frame(&mut build);
+        let center = harness.center_of(&[0, 0]).unwrap();
+
+        // Halfway in linear space after a quarter second, like buttons.
+        harness.ui_mut().set_delta_time(0.25);
+        harness.move_cursor_to(center.x, center.y, &mut build);
+        assert!(has_vertex_with_color(&harness, 0x5c5c5cff));
+
+        harness.ui_mut().set_delta_time(0.25);
+        harness.run_frame(&mut build);
+        assert!(has_vertex_with_color(&harness, 0x808080ff));
+    }
+}
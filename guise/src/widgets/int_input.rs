@@ -4,8 +4,8 @@ use core::str::FromStr;
 
 use arrayvec::ArrayString;
 
-use crate::core::Frame;
-use crate::widgets::{do_text_input_and_file_taxes, Theme};
+use crate::core::{Direction, Frame, Theme};
+use crate::widgets::do_text_input_and_file_taxes;
 
 // TODO(yan): int2_input, int3_input, int4_input
 // TODO(yan): Consider adding a slider handle to int inputs and removing int sliders.
@@ -15,7 +15,9 @@ pub fn int_input<A>(frame: &mut Frame<A>, id: u32, value: &mut i32, label: &str)
 where
     A: Allocator + Clone,
 {
-    int_input_with_min_max_theme(frame, id, value, label, i32::MIN, i32::MAX, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        int_input_with_min_max_theme(frame, id, value, label, i32::MIN, i32::MAX, theme)
+    })
 }
 
 #[inline]
@@ -30,7 +32,9 @@ pub fn int_input_with_min_max<A>(
 where
     A: Allocator + Clone,
 {
-    int_input_with_min_max_theme(frame, id, value, label, min, max, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        int_input_with_min_max_theme(frame, id, value, label, min, max, theme)
+    })
 }
 
 #[inline]
@@ -74,6 +78,7 @@ where
         None,
         Some(&int_filter),
         &[],
+        Direction::Ltr,
         theme,
     ) {
         match i32::from_str(&buf) {
@@ -6,8 +6,19 @@ use core::slice;
 use arrayvec::ArrayString;
 
 use crate::convert::cast_u32;
-use crate::core::{Align, CtrlFlags, CtrlState, Frame, Inputs, Layout, Rect, Wrap};
-use crate::widgets::theme::Theme;
+use crate::core::{
+    Align,
+    CtrlFlags,
+    CtrlState,
+    Direction,
+    Frame,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Wrap,
+};
+use crate::widgets::button::animated_colors;
 
 #[inline]
 pub fn int_slider<A: Allocator + Clone>(
@@ -16,16 +27,18 @@ pub fn int_slider<A: Allocator + Clone>(
     value: &mut i32,
     label: &str,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(
-        frame,
-        id,
-        slice::from_mut(value),
-        label,
-        1.0,
-        i32::MIN,
-        i32::MAX,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(
+            frame,
+            id,
+            slice::from_mut(value),
+            label,
+            1.0,
+            i32::MIN,
+            i32::MAX,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -38,16 +51,18 @@ pub fn int_slider_with_speed_min_max<A: Allocator + Clone>(
     min: i32,
     max: i32,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(
-        frame,
-        id,
-        slice::from_mut(value),
-        label,
-        speed,
-        min,
-        max,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(
+            frame,
+            id,
+            slice::from_mut(value),
+            label,
+            speed,
+            min,
+            max,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -80,16 +95,18 @@ pub fn int2_slider<A: Allocator + Clone>(
     value: &mut [i32; 2],
     label: &str,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        1.0,
-        i32::MIN,
-        i32::MAX,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(
+            frame,
+            id,
+            value,
+            label,
+            1.0,
+            i32::MIN,
+            i32::MAX,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -102,7 +119,9 @@ pub fn int2_slider_with_speed_min_max<A: Allocator + Clone>(
     min: i32,
     max: i32,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(frame, id, value, label, speed, min, max, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(frame, id, value, label, speed, min, max, theme)
+    })
 }
 
 #[inline]
@@ -126,16 +145,18 @@ pub fn int3_slider<A: Allocator + Clone>(
     value: &mut [i32; 3],
     label: &str,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        1.0,
-        i32::MIN,
-        i32::MAX,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(
+            frame,
+            id,
+            value,
+            label,
+            1.0,
+            i32::MIN,
+            i32::MAX,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -148,7 +169,9 @@ pub fn int3_slider_with_speed_min_max<A: Allocator + Clone>(
     min: i32,
     max: i32,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(frame, id, value, label, speed, min, max, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(frame, id, value, label, speed, min, max, theme)
+    })
 }
 
 #[inline]
@@ -172,16 +195,18 @@ pub fn int4_slider<A: Allocator + Clone>(
     value: &mut [i32; 4],
     label: &str,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(
-        frame,
-        id,
-        value,
-        label,
-        1.0,
-        i32::MIN,
-        i32::MAX,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(
+            frame,
+            id,
+            value,
+            label,
+            1.0,
+            i32::MIN,
+            i32::MAX,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -194,7 +219,9 @@ pub fn int4_slider_with_speed_min_max<A: Allocator + Clone>(
     min: i32,
     max: i32,
 ) -> bool {
-    do_int_slider_and_take_kids_to_school(frame, id, value, label, speed, min, max, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        do_int_slider_and_take_kids_to_school(frame, id, value, label, speed, min, max, theme)
+    })
 }
 
 #[inline]
@@ -257,6 +284,7 @@ fn do_int_slider_and_take_kids_to_school<A: Allocator + Clone>(
         Align::Start,
         Align::Center,
         Wrap::Word,
+        Direction::Ltr,
         theme.int_slider_text_color,
         Rect::new(0.0, 0.0, label_width, theme.int_slider_height),
     );
@@ -285,7 +313,7 @@ fn do_int_slider_and_take_kids_to_school<A: Allocator + Clone>(
             let x = state.x;
             let delta = cursor_position.x - x;
 
-            let new_active = if inputs_released == Inputs::MB_LEFT {
+            let new_active = if inputs_released.contains(Inputs::MB_LEFT) {
                 inner_ctrl.set_active(false);
                 false
             } else {
@@ -300,7 +328,7 @@ fn do_int_slider_and_take_kids_to_school<A: Allocator + Clone>(
 
             *value_mut_slot = new_value;
             (new_active, old_value != new_value)
-        } else if hovered && inputs_pressed == Inputs::MB_LEFT {
+        } else if hovered && inputs_pressed.contains(Inputs::MB_LEFT) {
             inner_ctrl.set_active(true);
 
             let state = cast_state_mut(inner_ctrl.state_mut());
@@ -318,23 +346,25 @@ fn do_int_slider_and_take_kids_to_school<A: Allocator + Clone>(
 
         changed |= changed_i;
 
-        let (text_color, background_color, border_color) = match (hovered, active) {
-            (false, false) => (
+        let [text_color, background_color, border_color] = animated_colors(
+            &mut inner_ctrl,
+            [
                 theme.int_slider_text_color,
                 theme.int_slider_background_color,
                 theme.int_slider_border_color,
-            ),
-            (true, false) => (
+            ],
+            [
                 theme.int_slider_text_color_hovered,
                 theme.int_slider_background_color_hovered,
                 theme.int_slider_border_color_hovered,
-            ),
-            (_, true) => (
+            ],
+            [
                 theme.int_slider_text_color_active,
                 theme.int_slider_background_color_active,
                 theme.int_slider_border_color_active,
-            ),
-        };
+            ],
+            theme.animation_speed,
+        );
 
         inner_ctrl.set_draw_self(true);
         inner_ctrl.set_draw_self_border_color(border_color);
@@ -342,7 +372,14 @@ fn do_int_slider_and_take_kids_to_school<A: Allocator + Clone>(
 
         s.clear();
         let _ = write!(s, "{value_mut_slot}");
-        inner_ctrl.draw_text(&s, Align::Center, Align::Center, Wrap::Word, text_color);
+        inner_ctrl.draw_text(
+            &s,
+            Align::Center,
+            Align::Center,
+            Wrap::Word,
+            Direction::Ltr,
+            text_color,
+        );
 
         frame.pop_ctrl();
     }
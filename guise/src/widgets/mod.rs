@@ -1,31 +1,51 @@
 mod button;
 mod checkbox;
+mod combo;
+mod command_palette;
+mod context_menu;
+mod drag_ghost;
 mod dropdown;
 mod float_input;
 mod float_slider;
 mod int_input;
 mod int_slider;
+mod modal;
 mod panel;
+mod reorderable_list;
+mod response;
+mod selectable_text;
 mod separator;
 mod size;
+mod spinner;
+mod splitter;
 mod text;
 mod text_input;
-mod theme;
+mod theme_editor;
 mod tooltip;
 mod window;
 
 pub use button::*;
 pub use checkbox::*;
+pub use combo::*;
+pub use command_palette::*;
+pub use context_menu::*;
+pub use drag_ghost::*;
 pub use dropdown::*;
 pub use float_input::*;
 pub use float_slider::*;
 pub use int_input::*;
 pub use int_slider::*;
+pub use modal::*;
 pub use panel::*;
+pub use reorderable_list::*;
+pub use response::*;
+pub use selectable_text::*;
 pub use separator::*;
 pub use size::*;
+pub use spinner::*;
+pub use splitter::*;
 pub use text::*;
 pub use text_input::*;
-pub use theme::*;
+pub use theme_editor::*;
 pub use tooltip::*;
 pub use window::*;
@@ -0,0 +1,262 @@
+use core::alloc::Allocator;
+
+use crate::core::{
+    Align,
+    Ctrl,
+    CtrlFlags,
+    Direction,
+    Frame,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Vec2,
+    Wrap,
+};
+
+/// Opens a dialog with a title, centered in the window over a scrim dimming
+/// everything else, while `open` is true. Until the dialog closes, controls
+/// outside of it can't be hovered or become active, see [`Ctrl::set_modal`].
+/// Pressing Escape or clicking the scrim sets `open` to false.
+///
+/// Returns [`None`] while closed. Otherwise, the contents, e.g. confirm
+/// buttons, are built inside the dialog until [`Modal::end`] is called. The
+/// dialog's height fits its contents.
+#[inline]
+pub fn begin_modal<'f, A: Allocator + Clone>(
+    frame: &'f mut Frame<A>,
+    id: u32,
+    title: &str,
+    open: &mut bool,
+) -> Option<(Modal, Ctrl<'f, A>)> {
+    let modal = frame.with_theme(|frame, theme| {
+        begin_modal_with_theme(frame, id, title, open, theme).map(|(modal, _)| modal)
+    })?;
+
+    Some((modal, frame.ctrl()))
+}
+
+pub fn begin_modal_with_theme<'f, A: Allocator + Clone>(
+    frame: &'f mut Frame<A>,
+    id: u32,
+    title: &str,
+    open: &mut bool,
+    theme: &Theme,
+) -> Option<(Modal, Ctrl<'f, A>)> {
+    if !*open {
+        return None;
+    }
+
+    let window_size = frame.window_size();
+    let inputs_pressed = frame.inputs_pressed();
+    let lmb_pressed = inputs_pressed.contains(Inputs::MB_LEFT);
+    let escape_pressed = inputs_pressed.contains(Inputs::KB_ESCAPE);
+
+    frame.begin_overlay();
+
+    let mut scrim_ctrl = frame.push_ctrl(id);
+    scrim_ctrl.set_layout(Layout::Free);
+    scrim_ctrl.set_padding(0.0);
+    scrim_ctrl.set_border(0.0);
+    scrim_ctrl.set_margin(0.0);
+
+    // The scrim is only hovered if the dialog isn't, as the dialog captures
+    // hover too.
+    if escape_pressed || lmb_pressed && scrim_ctrl.is_hovered() {
+        *open = false;
+
+        // The scrim is still built this frame, so it must not block anything
+        // next frame.
+        scrim_ctrl.set_flags(CtrlFlags::NONE);
+        scrim_ctrl.set_rect(Rect::ZERO);
+
+        frame.pop_ctrl();
+        frame.end_overlay();
+
+        return None;
+    }
+
+    scrim_ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+    scrim_ctrl.set_rect(Rect::from_points(Vec2::ZERO, window_size));
+    scrim_ctrl.set_modal(true);
+
+    scrim_ctrl.set_draw_self(true);
+    scrim_ctrl.set_draw_self_background_color(theme.modal_scrim_color);
+
+    let border = theme.window_border;
+
+    let mut dialog_ctrl = frame.push_ctrl(0);
+
+    // NB: The dialog is resized to fit its contents during layout, so we only
+    // know its height from last frame, and new dialogs are centered a frame
+    // late.
+    let width = f32::min(theme.modal_width, window_size.x);
+    let height = if dialog_ctrl.is_new() {
+        0.0
+    } else {
+        dialog_ctrl.inner_size().y + 2.0 * border
+    };
+
+    let x = 0.5 * (window_size.x - width);
+    let y = f32::max(0.0, 0.5 * (window_size.y - height));
+
+    dialog_ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+    dialog_ctrl.set_layout(Layout::Vertical);
+    // The height is resized to fit the contents during layout.
+    dialog_ctrl.set_rect(Rect::new(x, y, width, 0.0));
+    dialog_ctrl.set_padding(0.0);
+    dialog_ctrl.set_border(border);
+    dialog_ctrl.set_margin(0.0);
+
+    dialog_ctrl.set_draw_self(true);
+    dialog_ctrl.set_draw_self_border_color(theme.window_border_color_hovered);
+    dialog_ctrl.set_draw_self_background_color(theme.window_background_color);
+
+    let inner_width = f32::max(0.0, width - 2.0 * border);
+
+    let mut header_ctrl = frame.push_ctrl(0);
+    header_ctrl.set_flags(CtrlFlags::NONE);
+    header_ctrl.set_layout(Layout::Free);
+    header_ctrl.set_rect(Rect::new(0.0, 0.0, inner_width, theme.panel_header_height));
+    header_ctrl.set_padding(0.0);
+    header_ctrl.set_border(0.0);
+    header_ctrl.set_margin(0.0);
+
+    header_ctrl.set_draw_self(true);
+    header_ctrl.set_draw_self_background_color(theme.panel_header_background_color);
+    if title.len() > 0 {
+        header_ctrl.draw_text(
+            title,
+            Align::Center,
+            Align::Center,
+            Wrap::Word,
+            Direction::Ltr,
+            theme.panel_header_text_color,
+        );
+    }
+
+    frame.pop_ctrl();
+
+    let mut body_ctrl = frame.push_ctrl(1);
+    body_ctrl.set_flags(CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+    body_ctrl.set_layout(Layout::Vertical);
+    body_ctrl.set_rect(Rect::new(0.0, 0.0, inner_width, 0.0));
+    body_ctrl.set_padding(theme.window_padding);
+    body_ctrl.set_border(0.0);
+    body_ctrl.set_margin(0.0);
+
+    Some((Modal(false), body_ctrl))
+}
+
+pub struct Modal(bool);
+
+impl Modal {
+    pub fn end<A: Allocator + Clone>(mut self, frame: &mut Frame<A>) {
+        assert!(!self.0);
+
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+        frame.end_overlay();
+        self.0 = true;
+    }
+}
+
+impl Drop for Modal {
+    fn drop(&mut self) {
+        debug_assert!(self.0, "Modal dropped without calling Modal::end")
+    }
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::new_ui;
+    use crate::widgets::button::button_response;
+
+    // A button under the modal, and a button in it.
+    struct Built {
+        open: bool,
+        button_clicked: bool,
+        modal_button_clicked: bool,
+        modal_button_rect: Rect,
+    }
+
+    fn build(ui: &mut Ui<Global>, mut open: bool) -> Built {
+        let mut frame = ui.begin_frame();
+        let mut built = Built {
+            open: false,
+            button_clicked: false,
+            modal_button_clicked: false,
+            modal_button_rect: Rect::ZERO,
+        };
+
+        built.button_clicked = button_response(&mut frame, 0, "Delete").clicked;
+
+        if let Some((modal, _)) = begin_modal(&mut frame, 1, "Are you sure?", &mut open) {
+            let response = button_response(&mut frame, 0, "Yes");
+            built.modal_button_clicked = response.clicked;
+            built.modal_button_rect = response.rect;
+            modal.end(&mut frame);
+        }
+        built.open = open;
+
+        ui.end_frame();
+
+        built
+    }
+
+    fn press(ui: &mut Ui<Global>, inputs: Inputs, open: bool) -> Built {
+        ui.press_inputs(inputs);
+        let built = build(ui, open);
+        ui.release_inputs(inputs);
+
+        built
+    }
+
+    #[test]
+    fn test_modal_blocks_controls_under_it() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(5.0, 5.0);
+
+        build(&mut ui, false);
+        press(&mut ui, Inputs::MB_LEFT, false);
+        assert!(build(&mut ui, false).button_clicked);
+
+        // The click lands on the scrim, closing the modal, but not clicking
+        // the button.
+        build(&mut ui, true);
+        assert!(!press(&mut ui, Inputs::MB_LEFT, true).open);
+        assert!(!build(&mut ui, false).button_clicked);
+
+        // Once closed, the button can be clicked again.
+        press(&mut ui, Inputs::MB_LEFT, false);
+        assert!(build(&mut ui, false).button_clicked);
+    }
+
+    #[test]
+    fn test_modal_closes_on_escape_and_scrim_click() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(5.0, 5.0);
+
+        assert!(build(&mut ui, true).open);
+        assert!(!press(&mut ui, Inputs::KB_ESCAPE, true).open);
+
+        // Clicking inside the dialog keeps it open. New dialogs are centered
+        // a frame late.
+        build(&mut ui, true);
+        build(&mut ui, true);
+        let rect = build(&mut ui, true).modal_button_rect;
+        let center = rect.min_point() + 0.5 * rect.size();
+        ui.set_cursor_position(center.x, center.y);
+        build(&mut ui, true);
+
+        assert!(press(&mut ui, Inputs::MB_LEFT, true).open);
+        let built = build(&mut ui, true);
+        assert!(built.modal_button_clicked);
+        assert!(built.open);
+    }
+}
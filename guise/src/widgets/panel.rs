@@ -1,14 +1,29 @@
 use core::alloc::Allocator;
 use core::fmt::Debug;
-
-use crate::core::{Align, Ctrl, CtrlFlags, Frame, Layout, Rect, Wrap};
+use core::mem;
+
+use crate::core::{
+    Align,
+    Ctrl,
+    CtrlFlags,
+    CtrlState,
+    Direction,
+    Frame,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Vec2,
+    Wrap,
+};
 use crate::widgets::size::Size;
-use crate::widgets::theme::Theme;
 
 const DEFAULT_OPTIONS: PanelOptions = PanelOptions {
     draw_padding: true,
     draw_border: true,
     draw_header: true,
+    collapsible: false,
+    scrollable: true,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,6 +31,15 @@ pub struct PanelOptions {
     pub draw_padding: bool,
     pub draw_border: bool,
     pub draw_header: bool,
+    /// Whether clicking the header collapses the panel to just the header. The
+    /// begin functions return [`None`] for collapsed panels, so that their
+    /// contents are not built. Has no effect without the header.
+    pub collapsible: bool,
+    /// Whether the body scrolls with the wheel when its contents overflow,
+    /// showing scrollbars. Scrolling is vertical, and also horizontal for
+    /// horizontal layouts. Panels resizing to fit their height never overflow
+    /// vertically, so the wheel scrolls their parent instead.
+    pub scrollable: bool,
 }
 
 impl Default for PanelOptions {
@@ -42,19 +66,25 @@ where
     let width = width.try_into().unwrap();
     let height = height.try_into().unwrap();
 
-    let ctrl = do_panel_and_plot_mandelbrot_set(
-        frame,
-        id,
-        width,
-        height,
-        label,
-        Layout::Vertical,
-        false,
-        &DEFAULT_OPTIONS,
-        &Theme::DEFAULT,
-    );
+    let expanded = frame.with_theme(|frame, theme| {
+        do_panel_and_plot_mandelbrot_set(
+            frame,
+            id,
+            width,
+            height,
+            label,
+            Layout::Vertical,
+            &DEFAULT_OPTIONS,
+            None,
+            theme,
+        )
+        .is_some()
+    });
+    if !expanded {
+        return None;
+    }
 
-    Some((Panel(false), ctrl))
+    Some((Panel(false), frame.ctrl()))
 }
 
 #[inline]
@@ -76,19 +106,25 @@ where
     let width = width.try_into().unwrap();
     let height = height.try_into().unwrap();
 
-    let ctrl = do_panel_and_plot_mandelbrot_set(
-        frame,
-        id,
-        width,
-        height,
-        label,
-        layout,
-        false,
-        &DEFAULT_OPTIONS,
-        &Theme::DEFAULT,
-    );
+    let expanded = frame.with_theme(|frame, theme| {
+        do_panel_and_plot_mandelbrot_set(
+            frame,
+            id,
+            width,
+            height,
+            label,
+            layout,
+            &DEFAULT_OPTIONS,
+            None,
+            theme,
+        )
+        .is_some()
+    });
+    if !expanded {
+        return None;
+    }
 
-    Some((Panel(false), ctrl))
+    Some((Panel(false), frame.ctrl()))
 }
 
 #[inline]
@@ -105,19 +141,25 @@ where
 {
     let width = width.try_into().unwrap();
 
-    let ctrl = do_panel_and_plot_mandelbrot_set(
-        frame,
-        id,
-        width,
-        Size::new_absolute(0.0),
-        label,
-        Layout::Vertical,
-        true,
-        &DEFAULT_OPTIONS,
-        &Theme::DEFAULT,
-    );
+    let expanded = frame.with_theme(|frame, theme| {
+        do_panel_and_plot_mandelbrot_set(
+            frame,
+            id,
+            width,
+            Size::new_content(),
+            label,
+            Layout::Vertical,
+            &DEFAULT_OPTIONS,
+            None,
+            theme,
+        )
+        .is_some()
+    });
+    if !expanded {
+        return None;
+    }
 
-    Some((Panel(false), ctrl))
+    Some((Panel(false), frame.ctrl()))
 }
 
 #[inline]
@@ -135,19 +177,25 @@ where
 {
     let width = width.try_into().unwrap();
 
-    let ctrl = do_panel_and_plot_mandelbrot_set(
-        frame,
-        id,
-        width,
-        Size::new_absolute(0.0),
-        label,
-        layout,
-        true,
-        &DEFAULT_OPTIONS,
-        &Theme::DEFAULT,
-    );
+    let expanded = frame.with_theme(|frame, theme| {
+        do_panel_and_plot_mandelbrot_set(
+            frame,
+            id,
+            width,
+            Size::new_content(),
+            label,
+            layout,
+            &DEFAULT_OPTIONS,
+            None,
+            theme,
+        )
+        .is_some()
+    });
+    if !expanded {
+        return None;
+    }
 
-    Some((Panel(false), ctrl))
+    Some((Panel(false), frame.ctrl()))
 }
 
 #[inline]
@@ -170,19 +218,17 @@ where
     let width = width.try_into().unwrap();
     let height = height.try_into().unwrap();
 
-    let ctrl = do_panel_and_plot_mandelbrot_set(
-        frame,
-        id,
-        width,
-        height,
-        label,
-        layout,
-        false,
-        options,
-        &Theme::DEFAULT,
-    );
+    let expanded = frame.with_theme(|frame, theme| {
+        do_panel_and_plot_mandelbrot_set(
+            frame, id, width, height, label, layout, options, None, theme,
+        )
+        .is_some()
+    });
+    if !expanded {
+        return None;
+    }
 
-    Some((Panel(false), ctrl))
+    Some((Panel(false), frame.ctrl()))
 }
 
 #[inline]
@@ -201,19 +247,25 @@ where
 {
     let width = width.try_into().unwrap();
 
-    let ctrl = do_panel_and_plot_mandelbrot_set(
-        frame,
-        id,
-        width,
-        Size::new_absolute(0.0),
-        label,
-        layout,
-        true,
-        options,
-        &Theme::DEFAULT,
-    );
+    let expanded = frame.with_theme(|frame, theme| {
+        do_panel_and_plot_mandelbrot_set(
+            frame,
+            id,
+            width,
+            Size::new_content(),
+            label,
+            layout,
+            options,
+            None,
+            theme,
+        )
+        .is_some()
+    });
+    if !expanded {
+        return None;
+    }
 
-    Some((Panel(false), ctrl))
+    Some((Panel(false), frame.ctrl()))
 }
 
 #[inline]
@@ -238,8 +290,56 @@ where
     let height = height.try_into().unwrap();
 
     let ctrl = do_panel_and_plot_mandelbrot_set(
-        frame, id, width, height, label, layout, false, options, theme,
-    );
+        frame, id, width, height, label, layout, options, None, theme,
+    )?;
+
+    Some((Panel(false), ctrl))
+}
+
+/// Like [`begin_panel_with_layout_options_theme`], but also builds small
+/// widgets, e.g. image buttons for closing or configuring the panel, at the end
+/// of the header. The widgets are laid out horizontally and are not part of
+/// the header's click target for collapsing. The header is drawn regardless of
+/// [`PanelOptions::draw_header`]. If the theme is lent by
+/// [`Frame::with_theme`], the header widgets must be the `*_with_theme`
+/// variants.
+pub fn begin_panel_with_header_widgets<'f, W, H, A, F>(
+    frame: &'f mut Frame<A>,
+    id: u32,
+    width: W,
+    height: H,
+    label: &str,
+    layout: Layout,
+    options: &PanelOptions,
+    theme: &Theme,
+    mut header_widgets: F,
+) -> Option<(Panel, Ctrl<'f, A>)>
+where
+    W: TryInto<Size>,
+    H: TryInto<Size>,
+    <W as TryInto<Size>>::Error: Debug,
+    <H as TryInto<Size>>::Error: Debug,
+    A: Allocator + Clone,
+    F: FnMut(&mut Frame<A>),
+{
+    let width = width.try_into().unwrap();
+    let height = height.try_into().unwrap();
+    let options = PanelOptions {
+        draw_header: true,
+        ..*options
+    };
+
+    let ctrl = do_panel_and_plot_mandelbrot_set(
+        frame,
+        id,
+        width,
+        height,
+        label,
+        layout,
+        &options,
+        Some(&mut header_widgets),
+        theme,
+    )?;
 
     Some((Panel(false), ctrl))
 }
@@ -258,10 +358,11 @@ impl Panel {
 
 impl Drop for Panel {
     fn drop(&mut self) {
-        debug_assert!(self.0)
+        debug_assert!(self.0, "Panel dropped without calling Panel::end")
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn do_panel_and_plot_mandelbrot_set<'f, A: Allocator + Clone>(
     frame: &'f mut Frame<A>,
     id: u32,
@@ -269,39 +370,45 @@ fn do_panel_and_plot_mandelbrot_set<'f, A: Allocator + Clone>(
     height: Size,
     label: &str,
     layout: Layout,
-    fit_height: bool,
     options: &PanelOptions,
+    header_widgets: Option<&mut dyn FnMut(&mut Frame<A>)>,
     theme: &Theme,
-) -> Ctrl<'f, A> {
+) -> Option<Ctrl<'f, A>> {
+    let texture_id = frame.font_atlas_texture_id();
     let parent_size = frame.ctrl_inner_size();
-    let outer_flags = if fit_height {
-        CtrlFlags::RESIZE_TO_FIT_VERTICAL
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
+
+    // NB: Fill and content sizes are computed by the layout for both the
+    // outer control and the body, while the header fills the width of the
+    // outer control.
+    let fit_height = height.is_content();
+    let size_flags = width.layout_flags(true) | height.layout_flags(false);
+    let header_flags = if width.is_fill() || width.is_content() {
+        CtrlFlags::FILL_HORIZONTAL
     } else {
         CtrlFlags::NONE
     };
-    let body_flags = if fit_height {
-        CtrlFlags::CAPTURE_SCROLL | CtrlFlags::RESIZE_TO_FIT_VERTICAL
+
+    let mut body_flags = size_flags;
+    if options.scrollable {
+        body_flags |= CtrlFlags::CAPTURE_SCROLL | CtrlFlags::SHOW_SCROLLBARS;
+        if layout == Layout::Horizontal {
+            body_flags |= CtrlFlags::CAPTURE_SCROLL_HORIZONTAL;
+        }
+    }
+
+    let border = if options.draw_border {
+        theme.panel_border
     } else {
-        CtrlFlags::CAPTURE_SCROLL
+        0.0
     };
 
-    let outer_width = f32::max(0.0, width.resolve(parent_size.x) - 2.0 * theme.panel_margin);
-    let outer_height = f32::max(
-        0.0,
-        height.resolve(parent_size.y) - 2.0 * theme.panel_margin,
-    );
-
     let mut outer_ctrl = frame.push_ctrl(id);
-    outer_ctrl.set_flags(outer_flags);
     outer_ctrl.set_layout(Layout::Vertical);
-    outer_ctrl.set_rect(Rect::new(0.0, 0.0, outer_width, outer_height));
 
     outer_ctrl.set_padding(0.0);
-    outer_ctrl.set_border(if options.draw_border {
-        theme.panel_border
-    } else {
-        0.0
-    });
+    outer_ctrl.set_border(border);
     outer_ctrl.set_margin(theme.panel_margin);
 
     if options.draw_border {
@@ -309,17 +416,79 @@ fn do_panel_and_plot_mandelbrot_set<'f, A: Allocator + Clone>(
         outer_ctrl.set_draw_self_border_color(theme.panel_border_color);
     }
 
+    let collapsible = options.draw_header && options.collapsible;
+    let collapsed = if collapsible {
+        outer_ctrl.mark_persistent();
+        let state = cast_state_mut(outer_ctrl.state_mut());
+        state.collapsed != 0
+    } else {
+        false
+    };
+
+    // Fill and content sizes start from last frame's layout, so that
+    // whatever is built inside sees the size from last frame, until the
+    // layout corrects it.
+    let last_outer_size = outer_ctrl.absolute_rect().size() + 2.0 * theme.panel_margin;
+    let outer_width = f32::max(
+        0.0,
+        width.resolve_with_last_size(parent_size.x, last_outer_size.x) - 2.0 * theme.panel_margin,
+    );
+    let outer_height = f32::max(
+        0.0,
+        height.resolve_with_last_size(parent_size.y, last_outer_size.y) - 2.0 * theme.panel_margin,
+    );
+
+    // NB: Collapsed panels only contain the header, so they can't fill their
+    // parent vertically. Panels resizing to fit get there on their own.
+    if collapsed {
+        outer_ctrl.set_flags(size_flags - CtrlFlags::FILL_VERTICAL);
+    } else {
+        outer_ctrl.set_flags(size_flags);
+    }
+    outer_ctrl.set_rect(Rect::new(
+        0.0,
+        0.0,
+        outer_width,
+        if collapsed && !fit_height {
+            f32::min(outer_height, theme.panel_header_height + 2.0 * border)
+        } else {
+            outer_height
+        },
+    ));
+
+    let mut toggle_collapsed = false;
+
     if options.draw_header {
         let mut header_ctrl = frame.push_ctrl(0);
-        header_ctrl.set_flags(CtrlFlags::NONE);
+        header_ctrl.set_flags(if collapsible {
+            header_flags | CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE
+        } else {
+            header_flags
+        });
         header_ctrl.set_layout(Layout::Free);
         header_ctrl.set_rect(Rect::new(0.0, 0.0, outer_width, theme.panel_header_height));
         header_ctrl.set_padding(0.0);
         header_ctrl.set_border(0.0);
         header_ctrl.set_margin(0.0);
 
+        let hovered = header_ctrl.is_hovered();
+        let active = header_ctrl.is_active();
+
+        if collapsible {
+            if active && lmb_released {
+                header_ctrl.set_active(false);
+                toggle_collapsed = hovered;
+            } else if hovered && lmb_pressed {
+                header_ctrl.set_active(true);
+            }
+        }
+
         header_ctrl.set_draw_self(true);
-        header_ctrl.set_draw_self_background_color(theme.panel_header_background_color);
+        header_ctrl.set_draw_self_background_color(if hovered {
+            theme.panel_header_background_color_hovered
+        } else {
+            theme.panel_header_background_color
+        });
 
         if label.len() > 0 {
             header_ctrl.draw_text(
@@ -327,11 +496,79 @@ fn do_panel_and_plot_mandelbrot_set<'f, A: Allocator + Clone>(
                 Align::Center,
                 Align::Center,
                 Wrap::Word,
+                Direction::Ltr,
                 theme.panel_header_text_color,
             );
         }
 
+        if collapsible {
+            // A chevron pointing right when collapsed, and down when expanded.
+            const CHEVRON_SIZE: f32 = 4.0;
+            const CHEVRON_THICKNESS: f32 = 1.5;
+
+            let c = Vec2::new(
+                0.5 * theme.panel_header_height,
+                0.5 * theme.panel_header_height,
+            );
+            let s = CHEVRON_SIZE;
+            let (a, b, d) = if collapsed {
+                (
+                    Vec2::new(c.x - 0.5 * s, c.y - s),
+                    Vec2::new(c.x + 0.5 * s, c.y),
+                    Vec2::new(c.x - 0.5 * s, c.y + s),
+                )
+            } else {
+                (
+                    Vec2::new(c.x - s, c.y - 0.5 * s),
+                    Vec2::new(c.x, c.y + 0.5 * s),
+                    Vec2::new(c.x + s, c.y - 0.5 * s),
+                )
+            };
+
+            let color = theme.panel_header_text_color;
+            header_ctrl.draw_line(a, b, CHEVRON_THICKNESS, color, texture_id);
+            header_ctrl.draw_line(b, d, CHEVRON_THICKNESS, color, texture_id);
+        }
+
+        if let Some(header_widgets) = header_widgets {
+            let mut widgets_ctrl = frame.push_ctrl(0);
+
+            // NB: The widgets are resized to fit during layout, so we align
+            // them to the end with their width from last frame.
+            let widgets_width = if widgets_ctrl.is_new() {
+                0.0
+            } else {
+                widgets_ctrl.inner_size().x
+            };
+
+            widgets_ctrl.set_flags(CtrlFlags::RESIZE_TO_FIT_HORIZONTAL);
+            widgets_ctrl.set_layout(Layout::Horizontal);
+            widgets_ctrl.set_rect(Rect::new(
+                f32::max(0.0, outer_width - widgets_width),
+                0.0,
+                outer_width,
+                theme.panel_header_height,
+            ));
+            widgets_ctrl.set_padding(0.0);
+            widgets_ctrl.set_border(0.0);
+            widgets_ctrl.set_margin(0.0);
+
+            header_widgets(frame);
+
+            frame.pop_ctrl();
+        }
+
+        frame.pop_ctrl();
+    }
+
+    if toggle_collapsed {
+        let state = cast_state_mut(frame.ctrl_state_mut());
+        state.collapsed = u32::from(!collapsed);
+    }
+
+    if collapsed {
         frame.pop_ctrl();
+        return None;
     }
 
     let mut body_ctrl = frame.push_ctrl(1);
@@ -359,5 +596,217 @@ fn do_panel_and_plot_mandelbrot_set<'f, A: Allocator + Clone>(
     body_ctrl.set_draw_self_border_color(theme.panel_border_color);
     body_ctrl.set_draw_self_background_color(theme.panel_background_color);
 
-    body_ctrl
+    body_ctrl.set_scrollbar_width(theme.scrollbar_width);
+    body_ctrl.set_scrollbar_track_color(theme.scrollbar_track_color);
+    body_ctrl.set_scrollbar_thumb_color(theme.scrollbar_thumb_color);
+
+    Some(body_ctrl)
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    collapsed: u32,
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::{font_source, new_ui};
+    use crate::testing::TestHarness;
+    use crate::widgets::text::text;
+
+    // Returns whether the panel body was built.
+    fn build(ui: &mut Ui<Global>) -> bool {
+        let mut frame = ui.begin_frame();
+        let built = if let Some((panel, _)) = begin_panel_with_layout_fit_height_options(
+            &mut frame,
+            0,
+            "100%",
+            "Panel",
+            Layout::Vertical,
+            &PanelOptions {
+                collapsible: true,
+                ..PanelOptions::default()
+            },
+        ) {
+            text(&mut frame, 0, "Body");
+            panel.end(&mut frame);
+
+            true
+        } else {
+            false
+        };
+        ui.end_frame();
+
+        built
+    }
+
+    fn click(ui: &mut Ui<Global>) -> bool {
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(ui);
+        ui.release_inputs(Inputs::MB_LEFT);
+        build(ui);
+
+        build(ui)
+    }
+
+    #[test]
+    fn test_panel_collapses_on_header_click() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(100.0, 15.0);
+
+        // First frame lays out the header, so that it can be hovered.
+        assert!(build(&mut ui));
+        assert!(build(&mut ui));
+
+        assert!(!click(&mut ui));
+        assert!(click(&mut ui));
+    }
+
+    fn build_overflowing(frame: &mut Frame<Global>, options: &PanelOptions) {
+        let (panel, _) = begin_panel_with_layout_options(
+            frame,
+            0,
+            "100%",
+            200.0,
+            "Panel",
+            Layout::Vertical,
+            options,
+        )
+        .unwrap();
+        for i in 0..30 {
+            text(frame, i, "Line");
+        }
+        panel.end(frame);
+    }
+
+    #[test]
+    fn test_panel_scrolls_overflowing_content() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let build = |frame: &mut Frame<Global>| {
+            build_overflowing(frame, &PanelOptions::default());
+        };
+
+        harness.move_cursor_to(100.0, 100.0, build);
+        assert_eq!(harness.find(&[0, 1]).unwrap().scroll_offset, Vec2::ZERO);
+
+        harness.ui_mut().scroll(0.0, -30.0);
+        harness.run_frame(build);
+        harness.run_frame(build);
+
+        let body = harness.find(&[0, 1]).unwrap();
+        assert!(body.content_size.y > body.rect.height);
+        assert!(body.scroll_offset.y > 0.0);
+    }
+
+    #[test]
+    fn test_panel_not_scrollable() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let build = |frame: &mut Frame<Global>| {
+            build_overflowing(frame, &PanelOptions {
+                scrollable: false,
+                ..PanelOptions::default()
+            });
+        };
+
+        harness.move_cursor_to(100.0, 100.0, build);
+        harness.ui_mut().scroll(0.0, -30.0);
+        harness.run_frame(build);
+        harness.run_frame(build);
+
+        let body = harness.find(&[0, 1]).unwrap();
+        assert!(!body.flags.intersects(CtrlFlags::CAPTURE_SCROLL));
+        assert_eq!(body.scroll_offset, Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_panel_fills_percent_sized_parent() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let build = |frame: &mut Frame<Global>| {
+            let (parent, _) =
+                begin_panel_with_layout(frame, 0, "50%", 200.0, "", Layout::Horizontal).unwrap();
+
+            let (panel, _) = begin_panel(frame, 0, 100.0, "fill", "").unwrap();
+            panel.end(frame);
+            let (panel, _) = begin_panel(frame, 1, "fill", "fill", "").unwrap();
+            panel.end(frame);
+
+            parent.end(frame);
+        };
+
+        harness.run_frame(build);
+
+        let theme = Theme::DEFAULT;
+        let m = theme.panel_margin;
+        let p = theme.panel_padding;
+        let h = theme.panel_header_height;
+        let parent_body = harness.find(&[0, 1]).unwrap();
+        assert_eq!(
+            parent_body.rect.size(),
+            Vec2::new(400.0 - 2.0 * m, 200.0 - 2.0 * m - h)
+        );
+
+        let parent_inner_size = parent_body.rect.size() - 2.0 * p;
+        let fixed = harness.find(&[0, 1, 0]).unwrap();
+        let fill = harness.find(&[0, 1, 1]).unwrap();
+        assert_eq!(fixed.rect.height, parent_inner_size.y - 2.0 * m);
+        assert_eq!(
+            fill.rect.size(),
+            Vec2::new(
+                parent_inner_size.x - 100.0 - 2.0 * m,
+                parent_inner_size.y - 2.0 * m,
+            ),
+        );
+
+        let fill_body = harness.find(&[0, 1, 1, 1]).unwrap();
+        assert_eq!(fill_body.rect.size(), fill.rect.size() - Vec2::new(0.0, h));
+    }
+
+    #[test]
+    fn test_panel_content_size_lags_one_frame_behind() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let build = |frame: &mut Frame<Global>, width: f32| {
+            let (panel, _) = begin_panel(frame, 0, "content", "content", "").unwrap();
+            let inner_width = frame.ctrl_inner_size().x;
+
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, width, 20.0));
+            frame.pop_ctrl();
+
+            panel.end(frame);
+
+            inner_width
+        };
+
+        let p = Theme::DEFAULT.panel_padding;
+        let h = Theme::DEFAULT.panel_header_height;
+
+        // Laid out to fit in the same frame, but built with last frame's size.
+        let mut inner_width = 0.0;
+        harness.run_frame(|frame| inner_width = build(frame, 100.0));
+        assert_eq!(inner_width, 0.0);
+        let body = harness.find(&[0, 1]).unwrap();
+        assert_eq!(body.rect.size(), Vec2::new(100.0 + 2.0 * p, 20.0 + 2.0 * p));
+        let outer = harness.find(&[0]).unwrap();
+        assert_eq!(
+            outer.rect.size(),
+            Vec2::new(100.0 + 2.0 * p, 20.0 + 2.0 * p + h)
+        );
+
+        harness.run_frame(|frame| inner_width = build(frame, 200.0));
+        assert_eq!(inner_width, 100.0);
+        let body = harness.find(&[0, 1]).unwrap();
+        assert_eq!(body.rect.width, 200.0 + 2.0 * p);
+        let header = harness.find(&[0, 0]).unwrap();
+        assert_eq!(header.rect.width, 200.0 + 2.0 * p);
+    }
 }
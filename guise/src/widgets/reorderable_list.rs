@@ -0,0 +1,411 @@
+use core::alloc::Allocator;
+use core::mem;
+
+use crate::core::{CtrlFlags, CtrlState, Frame, Inputs, Layout, Rect, Theme};
+
+const GRIP_LINE_COUNT: usize = 3;
+const GRIP_LINE_HEIGHT: f32 = 1.0;
+const GRIP_LINE_SPACING: f32 = 3.0;
+
+/// Lays out the items vertically, each row with a handle on its left that can
+/// be dragged to move the item elsewhere in the list, e.g. for reordering
+/// layers or a playlist. The contents of the rows are built by `render`, which
+/// receives the item's index and the item itself.
+///
+/// Rows are identified by `item_id`, not by their position, so that the state
+/// of the controls built by `render` follows the item when it moves. The ids
+/// must be unique within the list. While dragging, a line shows where the item
+/// will be inserted, and the moved row slides into its new place, if the
+/// theme's animation speed is set.
+///
+/// The handles are drag sources carrying the item id as their payload (see
+/// [`crate::Ctrl::drag_source`]), so that [`crate::drag_ghost`] can show the
+/// dragged handle. Returns the old and new index of the item moved this frame,
+/// if any.
+#[inline]
+pub fn reorderable_list<A, T, K, R>(
+    frame: &mut Frame<A>,
+    id: u32,
+    items: &mut [T],
+    item_id: K,
+    render: R,
+) -> Option<(usize, usize)>
+where
+    A: Allocator + Clone,
+    K: Fn(&T) -> u32,
+    R: FnMut(&mut Frame<A>, usize, &mut T),
+{
+    // NB: The theme is only lent while we read the style, so that the widgets
+    // built by render can use it too.
+    let style = frame.with_theme(|_, theme| Style::new(theme));
+    do_reorderable_list(frame, id, items, item_id, render, &style)
+}
+
+pub fn reorderable_list_with_theme<A, T, K, R>(
+    frame: &mut Frame<A>,
+    id: u32,
+    items: &mut [T],
+    item_id: K,
+    render: R,
+    theme: &Theme,
+) -> Option<(usize, usize)>
+where
+    A: Allocator + Clone,
+    K: Fn(&T) -> u32,
+    R: FnMut(&mut Frame<A>, usize, &mut T),
+{
+    do_reorderable_list(frame, id, items, item_id, render, &Style::new(theme))
+}
+
+fn do_reorderable_list<A, T, K, R>(
+    frame: &mut Frame<A>,
+    id: u32,
+    items: &mut [T],
+    item_id: K,
+    mut render: R,
+    style: &Style,
+) -> Option<(usize, usize)>
+where
+    A: Allocator + Clone,
+    K: Fn(&T) -> u32,
+    R: FnMut(&mut Frame<A>, usize, &mut T),
+{
+    let parent_size = frame.ctrl_inner_size();
+    let cursor_position = frame.cursor_position();
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
+    let delta_time = frame.delta_time();
+    let texture_id = frame.font_atlas_texture_id();
+
+    let handle_width = style.handle_width;
+    let content_width = f32::max(0.0, parent_size.x - handle_width);
+
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+    ctrl.set_layout(Layout::Vertical);
+    ctrl.set_rect(Rect::new(0.0, 0.0, parent_size.x, 0.0));
+    ctrl.set_padding(0.0);
+    ctrl.set_border(0.0);
+    ctrl.set_margin(0.0);
+    ctrl.set_draw_self(false);
+
+    let list_position = ctrl.absolute_position();
+
+    // The row moved last slides from where it was dropped to its new place.
+    let state = cast_state_mut(ctrl.state_mut());
+    let moved_row_id = state.moved_row_id;
+    let moved_row_offset = match delta_time {
+        Some(delta_time) if style.animation_speed > 0.0 && state.moved_t < 1.0 => {
+            state.moved_t = f32::min(1.0, state.moved_t + style.animation_speed * delta_time);
+            state.moved_offset * (1.0 - state.moved_t)
+        }
+        _ => {
+            state.moved_t = 1.0;
+            0.0
+        }
+    };
+
+    // The insertion point is found from last frame's layout of the rows other
+    // than the dragged one: the item is inserted before the first row whose
+    // middle is below the cursor.
+    let mut dragged: Option<(usize, u32, Rect)> = None;
+    let mut insertion_idx = 0;
+    let mut insertion_y: Option<f32> = None;
+    let mut rows_bottom = list_position.y;
+
+    for (i, item) in items.iter_mut().enumerate() {
+        let row_id = item_id(item);
+
+        let mut row = frame.push_ctrl(row_id);
+        // Read before the rect is set, while it still has last frame's height.
+        let row_rect = row.absolute_rect();
+
+        let row_offset = if row_id == moved_row_id {
+            moved_row_offset
+        } else {
+            0.0
+        };
+
+        row.set_flags(CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+        row.set_layout(Layout::Free);
+        row.set_rect(Rect::new(0.0, row_offset, parent_size.x, 0.0));
+        row.set_padding(0.0);
+        row.set_border(0.0);
+        row.set_margin(0.0);
+        row.set_draw_self(false);
+
+        let mut content = frame.push_ctrl(0);
+        let content_height = content.absolute_rect().height;
+
+        content.set_flags(CtrlFlags::RESIZE_TO_FIT_VERTICAL);
+        content.set_layout(Layout::Vertical);
+        content.set_rect(Rect::new(handle_width, 0.0, content_width, 0.0));
+        content.set_padding(0.0);
+        content.set_border(0.0);
+        content.set_margin(0.0);
+        content.set_draw_self(false);
+
+        render(frame, i, item);
+
+        frame.pop_ctrl();
+
+        // The handle is as tall as the row's contents were last frame.
+        let mut handle = frame.push_ctrl(1);
+        handle.set_flags(CtrlFlags::CAPTURE_HOVER);
+        handle.set_layout(Layout::Free);
+        handle.set_rect(Rect::new(0.0, 0.0, handle_width, content_height));
+        handle.set_padding(0.0);
+        handle.set_border(0.0);
+        handle.set_margin(0.0);
+        handle.set_draw_self(false);
+
+        let handle_dragging = handle.drag_source(u64::from(row_id));
+        let color = if handle_dragging {
+            style.handle_color_active
+        } else if handle.is_hovered() {
+            style.handle_color_hovered
+        } else {
+            style.handle_color
+        };
+
+        let grip_width = libm::roundf(0.5 * handle_width);
+        let grip_height = GRIP_LINE_COUNT as f32 * GRIP_LINE_HEIGHT
+            + (GRIP_LINE_COUNT - 1) as f32 * (GRIP_LINE_SPACING - GRIP_LINE_HEIGHT);
+        let grip_x = libm::roundf(0.5 * (handle_width - grip_width));
+        let grip_y = libm::roundf(0.5 * (content_height - grip_height));
+
+        for line in 0..GRIP_LINE_COUNT {
+            handle.draw_rect(
+                Rect::new(
+                    grip_x,
+                    grip_y + line as f32 * GRIP_LINE_SPACING,
+                    grip_width,
+                    GRIP_LINE_HEIGHT,
+                ),
+                Rect::ZERO,
+                color,
+                texture_id,
+            );
+        }
+
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+
+        if handle_dragging {
+            dragged = Some((i, row_id, row_rect));
+        } else {
+            if row_rect.y + 0.5 * row_rect.height < cursor_position.y {
+                insertion_idx += 1;
+            } else if insertion_y.is_none() {
+                insertion_y = Some(row_rect.y);
+            }
+
+            rows_bottom = f32::max(rows_bottom, row_rect.max_y());
+        }
+    }
+
+    let mut result = None;
+
+    if let Some((from_idx, row_id, row_rect)) = dragged {
+        let insertion_y = insertion_y.unwrap_or(rows_bottom);
+        let to_idx = insertion_idx;
+
+        if lmb_released {
+            if to_idx != from_idx {
+                if from_idx < to_idx {
+                    items[from_idx..=to_idx].rotate_left(1);
+                } else {
+                    items[to_idx..=from_idx].rotate_right(1);
+                }
+
+                // Rows after the dragged one move up by its height, once it
+                // is taken out.
+                let new_y = if from_idx < to_idx {
+                    insertion_y - row_rect.height
+                } else {
+                    insertion_y
+                };
+
+                let state = cast_state_mut(frame.ctrl_state_mut());
+                state.moved_row_id = row_id;
+                state.moved_offset = row_rect.y - new_y;
+                state.moved_t = 0.0;
+
+                result = Some((from_idx, to_idx));
+            }
+        } else {
+            let indicator_height = style.indicator_height;
+
+            frame.begin_overlay();
+
+            let mut indicator = frame.push_ctrl(id);
+            indicator.set_flags(CtrlFlags::NONE);
+            indicator.set_layout(Layout::Free);
+            indicator.set_rect(Rect::new(
+                list_position.x,
+                libm::roundf(insertion_y - 0.5 * indicator_height),
+                parent_size.x,
+                indicator_height,
+            ));
+            indicator.set_padding(0.0);
+            indicator.set_border(0.0);
+            indicator.set_margin(0.0);
+
+            indicator.set_draw_self(true);
+            indicator.set_draw_self_border_color(style.indicator_color);
+            indicator.set_draw_self_background_color(style.indicator_color);
+
+            frame.pop_ctrl();
+
+            frame.end_overlay();
+        }
+    }
+
+    frame.pop_ctrl();
+
+    result
+}
+
+// The theme values the list needs, copied out so that the theme isn't borrowed
+// while the rows are built.
+struct Style {
+    animation_speed: f32,
+    handle_width: f32,
+    handle_color: u32,
+    handle_color_hovered: u32,
+    handle_color_active: u32,
+    indicator_height: f32,
+    indicator_color: u32,
+}
+
+impl Style {
+    fn new(theme: &Theme) -> Self {
+        Self {
+            animation_speed: theme.animation_speed,
+            handle_width: f32::max(0.0, theme.reorderable_list_handle_width),
+            handle_color: theme.reorderable_list_handle_color,
+            handle_color_hovered: theme.reorderable_list_handle_color_hovered,
+            handle_color_active: theme.reorderable_list_handle_color_active,
+            indicator_height: f32::max(0.0, theme.reorderable_list_indicator_height),
+            indicator_color: theme.reorderable_list_indicator_color,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    moved_row_id: u32,
+    moved_offset: f32,
+    moved_t: f32,
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Vec2;
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+    use crate::widgets::button;
+
+    #[test]
+    fn test_reorderable_list_moves_dragged_item_and_keeps_row_ids() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut items = [10, 20, 30, 40];
+        let mut moves = [None; 2];
+
+        let mut build = |frame: &mut Frame<Global>| {
+            let moved = reorderable_list(
+                frame,
+                0,
+                &mut items,
+                |item| *item,
+                |frame, _, item| {
+                    button(frame, 0, if *item == 10 { "first" } else { "other" });
+                },
+            );
+
+            if moved.is_some() {
+                moves[moves.iter().position(Option::is_none).unwrap()] = moved;
+            }
+        };
+
+        harness.run_frame(&mut build);
+        harness.run_frame(&mut build);
+
+        let first = harness.rect_of(&[0, 10]).unwrap();
+        let third = harness.rect_of(&[0, 30]).unwrap();
+        let handle = harness.center_of(&[0, 10, 1]).unwrap();
+        assert_eq!(first.height, third.height);
+
+        // Dropped on the lower half of the third row, so after it.
+        harness.drag(
+            handle,
+            Vec2::new(handle.x, third.y + 0.75 * third.height),
+            &mut build,
+        );
+        harness.run_frame(&mut build);
+
+        // The rows are found by item id, at their new positions.
+        assert_eq!(harness.rect_of(&[0, 20]).unwrap().y, first.y);
+        assert_eq!(harness.rect_of(&[0, 10]).unwrap().y, third.y);
+
+        // Dropped on the upper half of the first row, so before it.
+        let handle = harness.center_of(&[0, 40, 1]).unwrap();
+        harness.drag(handle, Vec2::new(handle.x, first.y + 1.0), &mut build);
+
+        assert_eq!(items, [40, 20, 30, 10]);
+        assert_eq!(moves, [Some((0, 2)), Some((3, 0))]);
+    }
+
+    #[test]
+    fn test_reorderable_list_shows_insertion_indicator_while_dragging() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut items = [1, 2, 3];
+
+        let mut build = |frame: &mut Frame<Global>| {
+            let mut ctrl = frame.push_ctrl(0);
+            ctrl.set_rect(Rect::new(0.0, 0.0, 400.0, 400.0));
+
+            reorderable_list(
+                frame,
+                7,
+                &mut items,
+                |item| *item,
+                |frame, i, _| {
+                    button(frame, 0, if i == 0 { "a" } else { "b" });
+                },
+            );
+
+            frame.pop_ctrl();
+        };
+
+        harness.run_frame(&mut build);
+        harness.run_frame(&mut build);
+
+        let second = harness.rect_of(&[0, 7, 2]).unwrap();
+        let handle = harness.center_of(&[0, 7, 3, 1]).unwrap();
+
+        harness.move_cursor_to(handle.x, handle.y, &mut build);
+        harness.ui_mut().press_inputs(Inputs::MB_LEFT);
+        harness.run_frame(&mut build);
+        harness.move_cursor_to(handle.x, second.y + 1.0, &mut build);
+
+        // Overlay controls are looked up from the overlay root.
+        let indicator = harness.rect_of(&[7]).unwrap();
+        assert_eq!(indicator.y, second.y - 1.0);
+        assert_eq!(indicator.height, 2.0);
+
+        harness.ui_mut().release_inputs(Inputs::MB_LEFT);
+        harness.run_frame(&mut build);
+        harness.run_frame(&mut build);
+
+        assert_eq!(items, [1, 3, 2]);
+    }
+}
@@ -0,0 +1,20 @@
+use crate::core::Rect;
+
+/// What happened to a widget this frame, for when a bare `bool` is not
+/// enough, e.g. to place a popup next to the widget, or to show something
+/// while it is held down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Response {
+    /// Whether the widget was pressed and released while hovered.
+    pub clicked: bool,
+    /// Whether the widget changed the value it edits. Always false for
+    /// widgets that don't edit a value, such as buttons.
+    pub changed: bool,
+    pub hovered: bool,
+    /// Whether the widget is active, e.g. a button held down, or a slider
+    /// being dragged.
+    pub held: bool,
+    /// The widget's rect in window coordinates, as laid out last frame. See
+    /// [`crate::Ctrl::absolute_rect`].
+    pub rect: Rect,
+}
@@ -0,0 +1,428 @@
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::mem;
+
+use crate::core::{
+    char_advance_width,
+    split_text_lines,
+    text_width,
+    Align,
+    CtrlFlags,
+    CtrlState,
+    CursorIcon,
+    Direction,
+    FontAtlas,
+    Frame,
+    Inputs,
+    Layout,
+    Modifiers,
+    Rect,
+    Shortcut,
+    TextLine,
+    Theme,
+    Vec2,
+    Wrap,
+};
+
+const SHORTCUT_COPY: Shortcut = Shortcut::new(Inputs::KB_C, Modifiers::CTRL);
+
+#[inline]
+pub fn selectable_text<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, text: &str) {
+    frame.with_theme(|frame, theme| {
+        selectable_text_with_direction_theme(frame, id, text, Direction::Ltr, theme)
+    })
+}
+
+#[inline]
+pub fn selectable_text_with_direction<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    text: &str,
+    direction: Direction,
+) {
+    frame.with_theme(|frame, theme| {
+        selectable_text_with_direction_theme(frame, id, text, direction, theme)
+    })
+}
+
+#[inline]
+pub fn selectable_text_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    text: &str,
+    theme: &Theme,
+) {
+    selectable_text_with_direction_theme(frame, id, text, Direction::Ltr, theme)
+}
+
+pub fn selectable_text_with_direction_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    text: &str,
+    direction: Direction,
+    theme: &Theme,
+) {
+    let parent_size = frame.ctrl_inner_size();
+    let cursor_position = frame.cursor_position();
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
+    // Taken from the frame, so that the lines can outlive the control.
+    let temp_allocator = frame.temp_allocator();
+
+    let mut ctrl = frame.push_ctrl(id);
+
+    // Unlike text, selectable text captures hover, so that clicking and
+    // dragging to select doesn't also drag the window it is in.
+    ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::ALL_RESIZE_TO_FIT);
+    ctrl.set_layout(Layout::Vertical);
+    ctrl.set_rect(Rect::new(0.0, 0.0, parent_size.x, parent_size.y));
+
+    // Padding is not set through the control, but applied with drawing,
+    // because the text layout uses its own inset.
+    ctrl.set_border(theme.text_border);
+    ctrl.set_margin(theme.text_margin);
+
+    let hovered = ctrl.is_hovered();
+    if hovered {
+        ctrl.set_cursor_icon(CursorIcon::Text);
+    }
+
+    // Lay out the text the same way draw_text will, so that we know where the
+    // characters end up for hit-testing and drawing the selection.
+    let inset = theme.text_border + theme.text_padding;
+    let available_width = f32::max(0.0, parent_size.x - 2.0 * inset);
+    let available_height = f32::max(0.0, parent_size.y - 2.0 * inset);
+
+    let tab_stop_width = ctrl.tab_stop_width();
+    let mut lines: Vec<TextLine, _> = Vec::new_in(temp_allocator);
+    let font_atlas = ctrl.font_atlas();
+    if font_atlas.missing_glyph_info().advance_width <= available_width {
+        split_text_lines(
+            font_atlas,
+            text,
+            available_width,
+            Wrap::Word,
+            tab_stop_width,
+            &mut lines,
+        );
+    }
+
+    let line_metrics = font_atlas.font_horizontal_line_metrics();
+    let line_height = line_metrics.ascent - line_metrics.descent;
+    let new_line_size = line_metrics.new_line_size;
+    let origin = if lines.len() as f32 * new_line_size < available_height {
+        Vec2::new(inset, line_metrics.line_gap + inset)
+    } else {
+        Vec2::new(inset, line_metrics.line_gap)
+    };
+
+    let cursor_text_position = {
+        let position = cursor_position - ctrl.absolute_position();
+        hit_test(
+            font_atlas,
+            text,
+            &lines,
+            origin,
+            available_width,
+            new_line_size,
+            tab_stop_width,
+            direction,
+            position,
+        )
+    };
+
+    // The text could have changed since last frame, so the selection has to
+    // be clamped to valid char boundaries.
+    let state = cast_state(ctrl.state());
+    let mut selection_start = text.floor_char_boundary(state.selection_start);
+    let mut selection_end = text.floor_char_boundary(state.selection_end);
+    let mut selecting = state.selecting == 1;
+
+    if hovered && lmb_pressed {
+        ctrl.set_active(true);
+
+        selection_start = cursor_text_position;
+        selection_end = cursor_text_position;
+        selecting = true;
+    } else if selecting {
+        selection_end = cursor_text_position;
+        if lmb_released {
+            selecting = false;
+        }
+    } else if lmb_pressed {
+        // Clicking elsewhere clears the selection.
+        if ctrl.is_active() {
+            ctrl.set_active(false);
+        }
+
+        selection_start = 0;
+        selection_end = 0;
+    }
+
+    let selection_min = usize::min(selection_start, selection_end);
+    let selection_max = usize::max(selection_start, selection_end);
+
+    if ctrl.is_active() && selection_min != selection_max && ctrl.shortcut_pressed(SHORTCUT_COPY) {
+        ctrl.set_clipboard_text(&text[selection_min..selection_max]);
+    }
+
+    let state = cast_state_mut(ctrl.state_mut());
+    state.selection_start = selection_start;
+    state.selection_end = selection_end;
+    state.selecting = u32::from(selecting);
+
+    ctrl.set_draw_self(true);
+    ctrl.set_draw_self_border_color(theme.text_border_color);
+    ctrl.set_draw_self_background_color(theme.text_background_color);
+
+    // Draw the selection first, so that it is behind the text.
+    if selection_min != selection_max {
+        let texture_id = ctrl.font_atlas_texture_id();
+
+        for (i, line) in lines.iter().enumerate() {
+            let start = usize::clamp(selection_min, line.range.start, line.range.end);
+            let end = usize::clamp(selection_max, line.range.start, line.range.end);
+
+            if start == end {
+                continue;
+            }
+
+            // NB: Measure from the line start, so that tabs end up at the
+            // same tab stops as when drawing.
+            let font_atlas = ctrl.font_atlas();
+            let start_x = text_width(font_atlas, &text[line.range.start..start], tab_stop_width);
+            let end_x = text_width(font_atlas, &text[line.range.start..end], tab_stop_width);
+
+            let line_x = line_x(line, origin.x, available_width, direction);
+            let x = match direction {
+                Direction::Ltr => line_x + start_x,
+                Direction::Rtl => line_x + line.width - end_x,
+            };
+            let width = end_x - start_x;
+            let y = origin.y + i as f32 * new_line_size;
+
+            ctrl.draw_rect(
+                Rect::new(x, y, width, line_height),
+                Rect::ZERO,
+                theme.text_selection_color,
+                texture_id,
+            );
+        }
+    }
+
+    ctrl.draw_text_inset_and_extend_content_rect(
+        text,
+        Align::Start,
+        Align::Start,
+        Wrap::Word,
+        direction,
+        theme.text_text_color,
+        inset,
+    );
+
+    frame.pop_ctrl();
+}
+
+// Finds the byte index of the character boundary in text closest to the
+// position, which is relative to the control.
+fn hit_test<A: Allocator + Clone>(
+    font_atlas: &FontAtlas<A>,
+    text: &str,
+    lines: &[TextLine],
+    origin: Vec2,
+    available_width: f32,
+    new_line_size: f32,
+    tab_stop_width: f32,
+    direction: Direction,
+    position: Vec2,
+) -> usize {
+    if lines.is_empty() {
+        return 0;
+    }
+
+    let line_index = f32::max(0.0, (position.y - origin.y) / new_line_size) as usize;
+    let line = &lines[usize::min(line_index, lines.len() - 1)];
+    let line_x = line_x(line, origin.x, available_width, direction);
+
+    let mut line_offset_x = 0.0;
+    for (i, c) in text[line.range.clone()].char_indices() {
+        let advance_width = char_advance_width(font_atlas, c, line_offset_x, tab_stop_width);
+        let hit = match direction {
+            Direction::Ltr => position.x < line_x + line_offset_x + advance_width / 2.0,
+            Direction::Rtl => {
+                position.x > line_x + line.width - line_offset_x - advance_width / 2.0
+            }
+        };
+
+        if hit {
+            return line.range.start + i;
+        }
+
+        line_offset_x += advance_width;
+    }
+
+    line.range.end
+}
+
+// Returns the X coordinate of the left edge of the line, as laid out by
+// draw_text with Align::Start.
+fn line_x(line: &TextLine, origin_x: f32, available_width: f32, direction: Direction) -> f32 {
+    match direction {
+        Direction::Ltr => origin_x,
+        Direction::Rtl => origin_x + available_width - line.width,
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    selection_start: usize,
+    selection_end: usize,
+    selecting: u32,
+    _pad0: u32,
+}
+
+fn cast_state(state: &CtrlState) -> &State {
+    bytemuck::from_bytes(&state[..mem::size_of::<State>()])
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    extern crate std;
+
+    use alloc::alloc::Global;
+    use core::cell::RefCell;
+    use std::string::String;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::new_ui;
+
+    // Tests run on separate threads, so each gets its own clipboard.
+    std::thread_local! {
+        static CLIPBOARD: RefCell<String> = RefCell::new(String::new());
+    }
+
+    fn set_clipboard(text: &str) {
+        CLIPBOARD.with(|clipboard| {
+            let mut clipboard = clipboard.borrow_mut();
+            clipboard.clear();
+            clipboard.push_str(text);
+        });
+    }
+
+    fn take_clipboard() -> String {
+        CLIPBOARD.with(|clipboard| mem::take(&mut *clipboard.borrow_mut()))
+    }
+
+    fn selection_drawn(ui: &Ui<Global>) -> bool {
+        let (_, vertices, _) = ui.draw_list();
+        vertices
+            .iter()
+            .any(|vertex| vertex.color == Theme::DEFAULT.text_selection_color)
+    }
+
+    fn build(ui: &mut Ui<Global>, text: &str) {
+        let mut frame = ui.begin_frame();
+        selectable_text(&mut frame, 0, text);
+        ui.end_frame();
+    }
+
+    // Returns the window position of the boundary before the i-th character
+    // of a single line of text, assuming the glyphs have the same advance.
+    fn char_position(ui: &Ui<Global>, i: usize) -> (f32, f32) {
+        let inset = Theme::DEFAULT.text_border + Theme::DEFAULT.text_padding;
+        let advance_width = ui.font_atlas().glyph_info('a').advance_width;
+
+        (inset + i as f32 * advance_width, inset + 5.0)
+    }
+
+    fn select(ui: &mut Ui<Global>, text: &str, start: usize, end: usize) {
+        let (x, y) = char_position(ui, start);
+        ui.set_cursor_position(x, y);
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(ui, text);
+
+        let (x, y) = char_position(ui, end);
+        ui.set_cursor_position(x, y);
+        build(ui, text);
+
+        ui.release_inputs(Inputs::MB_LEFT);
+        build(ui, text);
+    }
+
+    fn copy(ui: &mut Ui<Global>, text: &str) -> String {
+        ui.press_modifiers(Modifiers::CTRL);
+        ui.press_inputs(Inputs::KB_C);
+        build(ui, text);
+        ui.release_inputs(Inputs::KB_C);
+        ui.release_modifiers(Modifiers::CTRL);
+        build(ui, text);
+
+        take_clipboard()
+    }
+
+    #[test]
+    fn test_selectable_text_selection_and_copy() {
+        let mut ui = new_ui();
+        ui.set_clipboard_setter(set_clipboard);
+
+        // First frame lays out the control, so that it can be hovered.
+        build(&mut ui, "Hello, world");
+
+        select(&mut ui, "Hello, world", 2, 9);
+        assert_eq!(copy(&mut ui, "Hello, world"), "llo, wo");
+
+        // Dragging backwards selects the same range.
+        select(&mut ui, "Hello, world", 9, 2);
+        assert_eq!(copy(&mut ui, "Hello, world"), "llo, wo");
+
+        // Dragging past the end of the line selects until its end.
+        select(&mut ui, "Hello, world", 7, 50);
+        assert_eq!(copy(&mut ui, "Hello, world"), "world");
+    }
+
+    #[test]
+    fn test_selectable_text_selection_is_clamped_when_text_shrinks() {
+        let mut ui = new_ui();
+        ui.set_clipboard_setter(set_clipboard);
+
+        build(&mut ui, "abcdef");
+        select(&mut ui, "abcdef", 0, 2);
+
+        // Byte 2 is in the middle of 'é', so the selection ends before it.
+        build(&mut ui, "aé");
+        assert_eq!(copy(&mut ui, "aé"), "a");
+
+        // The selection is now past the end of the text.
+        build(&mut ui, "");
+        assert_eq!(copy(&mut ui, ""), "");
+    }
+
+    #[test]
+    fn test_selectable_text_click_outside_clears_selection() {
+        let mut ui = new_ui();
+        ui.set_clipboard_setter(set_clipboard);
+
+        build(&mut ui, "Hello, world");
+        assert!(!selection_drawn(&ui));
+
+        select(&mut ui, "Hello, world", 0, 5);
+        assert!(selection_drawn(&ui));
+
+        // Click below the text, which is outside the control.
+        ui.set_cursor_position(400.0, 500.0);
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(&mut ui, "Hello, world");
+        ui.release_inputs(Inputs::MB_LEFT);
+        build(&mut ui, "Hello, world");
+
+        assert!(!selection_drawn(&ui));
+        assert_eq!(copy(&mut ui, "Hello, world"), "");
+    }
+}
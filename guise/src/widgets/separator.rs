@@ -1,11 +1,10 @@
 use core::alloc::Allocator;
 
-use crate::core::{CtrlFlags, Frame, Layout, Rect};
-use crate::widgets::theme::Theme;
+use crate::core::{text_width, Align, CtrlFlags, Direction, Frame, Layout, Rect, Theme, Wrap};
 
 #[inline]
 pub fn separator<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32) {
-    separator_with_theme(frame, id, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| separator_with_theme(frame, id, theme))
 }
 
 pub fn separator_with_theme<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, theme: &Theme) {
@@ -27,3 +26,213 @@ pub fn separator_with_theme<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32,
 
     frame.pop_ctrl();
 }
+
+/// A separator with a label centered between two lines, e.g. for naming
+/// sections of a panel.
+#[inline]
+pub fn separator_with_label<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, label: &str) {
+    frame.with_theme(|frame, theme| separator_with_label_theme(frame, id, label, theme))
+}
+
+pub fn separator_with_label_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    theme: &Theme,
+) {
+    separator_with_label_align_theme(frame, id, label, Align::Center, theme)
+}
+
+/// Like [`separator_with_label`], but the label can also be placed at the
+/// start or the end, with a single line filling the rest, like a form section
+/// header.
+#[inline]
+pub fn separator_with_label_align<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    align: Align,
+) {
+    frame
+        .with_theme(|frame, theme| separator_with_label_align_theme(frame, id, label, align, theme))
+}
+
+pub fn separator_with_label_align_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    label: &str,
+    align: Align,
+    theme: &Theme,
+) {
+    let texture_id = frame.font_atlas_texture_id();
+    let parent_size = frame.ctrl_inner_size();
+    let line_metrics = frame.font_atlas().font_horizontal_line_metrics();
+
+    let x = parent_size.x * 0.1 - theme.separator_margin;
+    let width = f32::max(0.0, parent_size.x * 0.8 - theme.separator_margin);
+    let height = line_metrics.new_line_size;
+
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(CtrlFlags::NONE);
+    ctrl.set_layout(Layout::Vertical);
+    ctrl.set_rect(Rect::new(x, 0.0, width, height));
+    ctrl.set_padding(0.0);
+    ctrl.set_border(0.0);
+    ctrl.set_margin(theme.separator_margin);
+
+    // NB: If the label doesn't fit, it is clipped by the control and the lines
+    // shrink to nothing.
+    let label_width = f32::min(
+        text_width(ctrl.font_atlas(), label, ctrl.tab_stop_width()),
+        width,
+    );
+    let line_y = 0.5 * (height - theme.separator_height);
+
+    // The lines before and after the label, as x and width.
+    let (line_before, line_after) = match align {
+        Align::Start => {
+            let line_width = width - label_width - theme.separator_label_spacing;
+            ((0.0, 0.0), (width - line_width, line_width))
+        }
+        Align::Center => {
+            let line_width = 0.5 * (width - label_width) - theme.separator_label_spacing;
+            ((0.0, line_width), (width - line_width, line_width))
+        }
+        Align::End => {
+            let line_width = width - label_width - theme.separator_label_spacing;
+            ((0.0, line_width), (width, 0.0))
+        }
+    };
+
+    ctrl.set_draw_self(false);
+    for (line_x, line_width) in [line_before, line_after] {
+        if line_width > 0.0 {
+            ctrl.draw_rect(
+                Rect::new(line_x, line_y, line_width, theme.separator_height),
+                Rect::ZERO,
+                theme.separator_color,
+                texture_id,
+            );
+        }
+    }
+
+    ctrl.draw_text(
+        label,
+        align,
+        Align::Center,
+        Wrap::None,
+        Direction::Ltr,
+        theme.separator_label_color,
+    );
+
+    frame.pop_ctrl();
+}
+
+/// A vertical separator for horizontal layouts, e.g. rows of buttons. It is as
+/// tall as the parent's inner height.
+#[inline]
+pub fn vertical_separator<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32) {
+    frame.with_theme(|frame, theme| vertical_separator_with_theme(frame, id, theme))
+}
+
+pub fn vertical_separator_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    theme: &Theme,
+) {
+    let parent_size = frame.ctrl_inner_size();
+
+    let height = f32::max(0.0, parent_size.y - 2.0 * theme.separator_margin);
+
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(CtrlFlags::NONE);
+    ctrl.set_layout(Layout::Horizontal);
+    ctrl.set_rect(Rect::new(0.0, 0.0, theme.separator_height, height));
+    ctrl.set_padding(0.0);
+    ctrl.set_border(0.0);
+    ctrl.set_margin(theme.separator_margin);
+
+    ctrl.set_draw_self(true);
+    ctrl.set_draw_self_background_color(theme.separator_color);
+
+    frame.pop_ctrl();
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::core::Vertex;
+    use crate::fixtures::new_ui;
+
+    // Returns the x extents of the lines, which are the only untextured rects.
+    fn build(align: Align) -> Vec<(f32, f32)> {
+        let mut ui = new_ui();
+
+        let mut frame = ui.begin_frame();
+        separator_with_label_align(&mut frame, 0, "Section", align);
+        ui.end_frame();
+
+        let (_, vertices, _) = ui.draw_list();
+        vertices
+            .chunks_exact(4)
+            .filter(|quad| quad.iter().all(|vertex| vertex.tex_coord == [0.0, 0.0]))
+            .map(|quad: &[Vertex]| {
+                let min_x = quad.iter().map(|v| v.position[0]).fold(f32::MAX, f32::min);
+                let max_x = quad.iter().map(|v| v.position[0]).fold(f32::MIN, f32::max);
+                (min_x, max_x)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_separator_with_label_align() {
+        let center = build(Align::Center);
+        assert_eq!(center.len(), 2);
+
+        // Left aligned labels have a single line after them, reaching as far
+        // right as the centered one. Rects are snapped to pixels, so the ends
+        // can differ by a fraction of a pixel.
+        let start = build(Align::Start);
+        assert_eq!(start.len(), 1);
+        assert!(f32::abs(start[0].1 - center[1].1) < 0.5);
+        assert!(start[0].0 < center[1].0);
+
+        let end = build(Align::End);
+        assert_eq!(end.len(), 1);
+        assert!(f32::abs(end[0].0 - center[0].0) < 0.5);
+        assert!(end[0].1 > center[0].1);
+    }
+
+    #[test]
+    fn test_vertical_separator_fills_parent_height() {
+        let mut ui = new_ui();
+        let theme = Theme::DEFAULT;
+
+        let mut frame = ui.begin_frame();
+        let mut ctrl = frame.push_ctrl(0);
+        ctrl.set_layout(Layout::Horizontal);
+        ctrl.set_rect(Rect::new(0.0, 0.0, 200.0, 40.0));
+        vertical_separator(&mut frame, 0);
+        vertical_separator(&mut frame, 1);
+        frame.pop_ctrl();
+        ui.end_frame();
+
+        let rects: Vec<Rect> = ui
+            .debug_ctrls()
+            .filter(|ctrl| ctrl.depth == 1)
+            .map(|ctrl| ctrl.absolute_rect)
+            .collect();
+
+        assert_eq!(rects.len(), 2);
+        for rect in &rects {
+            assert_eq!(rect.width, theme.separator_height);
+            assert_eq!(rect.height, 40.0 - 2.0 * theme.separator_margin);
+        }
+
+        // Laid out side by side.
+        assert_eq!(rects[0].y, rects[1].y);
+        assert!(rects[1].x > rects[0].x);
+    }
+}
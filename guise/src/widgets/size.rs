@@ -1,5 +1,7 @@
 use core::str::FromStr;
 
+use crate::core::CtrlFlags;
+
 #[derive(Debug)]
 pub struct TryFromStrError;
 
@@ -8,10 +10,14 @@ enum PositionType {
     Relative,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum SizeType {
-    Absolute,
-    AbsoluteNegative,
-    Relative,
+    Absolute(f32),
+    AbsoluteNegative(f32),
+    Relative(f32),
+    Calc { relative: f32, absolute: f32 },
+    Fill,
+    Content,
 }
 
 pub struct Position(PositionType, f32);
@@ -55,37 +61,87 @@ impl TryFrom<&str> for Position {
     }
 }
 
-pub struct Size(SizeType, f32);
+/// Width or height of a widget. Can be converted from pixels (negative values
+/// are subtracted from the parent size), and from strings: percentages of the
+/// parent size, e.g. "41%", percentages with a pixel offset, e.g. "100% - 200",
+/// "fill" and "content".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Size(SizeType);
 
 impl Size {
     pub const fn new_absolute(value: f32) -> Self {
         if value.is_sign_positive() {
-            Self(SizeType::Absolute, value)
+            Self(SizeType::Absolute(value))
         } else {
-            Self(SizeType::AbsoluteNegative, value)
+            Self(SizeType::AbsoluteNegative(value))
         }
     }
 
     pub const fn new_relative(value: f32) -> Self {
-        Self(SizeType::Relative, value)
+        Self(SizeType::Relative(value))
     }
 
+    /// A fraction of the parent size plus a pixel offset, e.g. for a main
+    /// area next to a sidebar 200 pixels wide, `new_calc(1.0, -200.0)`.
+    pub const fn new_calc(relative: f32, absolute: f32) -> Self {
+        Self(SizeType::Calc { relative, absolute })
+    }
+
+    /// Fills the space left over in the parent, after its other children are
+    /// laid out. See [`CtrlFlags::FILL_HORIZONTAL`].
+    pub const fn new_fill() -> Self {
+        Self(SizeType::Fill)
+    }
+
+    /// Fits the widget's contents. See
+    /// [`CtrlFlags::RESIZE_TO_FIT_HORIZONTAL`].
+    pub const fn new_content() -> Self {
+        Self(SizeType::Content)
+    }
+
+    /// Resolves the size in the parent of the given size. Fill and content
+    /// sizes are only known after layout, so they resolve to zero.
     pub fn resolve(&self, parent_size: f32) -> f32 {
+        self.resolve_with_last_size(parent_size, 0.0)
+    }
+
+    pub fn is_fill(&self) -> bool {
+        self.0 == SizeType::Fill
+    }
+
+    pub fn is_content(&self) -> bool {
+        self.0 == SizeType::Content
+    }
+
+    // Like resolve, but fill and content sizes resolve to what the layout
+    // computed last frame, so that the widget doesn't jump before the layout
+    // corrects it.
+    pub(crate) fn resolve_with_last_size(&self, parent_size: f32, last_size: f32) -> f32 {
         match self.0 {
-            SizeType::Absolute => self.1,
-            SizeType::AbsoluteNegative => self.1 + parent_size,
-            SizeType::Relative => self.1 * parent_size,
+            SizeType::Absolute(value) => value,
+            SizeType::AbsoluteNegative(value) => value + parent_size,
+            SizeType::Relative(value) => value * parent_size,
+            SizeType::Calc { relative, absolute } => relative * parent_size + absolute,
+            SizeType::Fill | SizeType::Content => last_size,
+        }
+    }
+
+    // The flags making the layout compute the size, if it's a fill or content
+    // size.
+    pub(crate) fn layout_flags(&self, horizontal: bool) -> CtrlFlags {
+        match (self.0, horizontal) {
+            (SizeType::Fill, true) => CtrlFlags::FILL_HORIZONTAL,
+            (SizeType::Fill, false) => CtrlFlags::FILL_VERTICAL,
+            (SizeType::Content, true) => CtrlFlags::RESIZE_TO_FIT_HORIZONTAL,
+            (SizeType::Content, false) => CtrlFlags::RESIZE_TO_FIT_VERTICAL,
+            _ => CtrlFlags::NONE,
         }
     }
 }
 
 impl From<f32> for Size {
     fn from(value: f32) -> Self {
-        if value.is_sign_positive() {
-            Self(SizeType::Absolute, value)
-        } else {
-            Self(SizeType::AbsoluteNegative, value)
-        }
+        Self::new_absolute(value)
     }
 }
 
@@ -93,14 +149,62 @@ impl TryFrom<&str> for Size {
     type Error = TryFromStrError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value.ends_with('%') {
-            let percent = &value[0..value.len() - 1];
-            match f32::from_str(percent) {
-                Ok(value) => Ok(Self(SizeType::Relative, 0.01 * value)),
-                Err(_) => Err(TryFromStrError),
-            }
+        let value = value.trim();
+        match value {
+            "fill" => return Ok(Self::new_fill()),
+            "content" => return Ok(Self::new_content()),
+            _ => (),
+        }
+
+        let (percent, offset) = value.split_once('%').ok_or(TryFromStrError)?;
+        let relative = match f32::from_str(percent.trim()) {
+            Ok(percent) => 0.01 * percent,
+            Err(_) => return Err(TryFromStrError),
+        };
+
+        // NB: Pixel offsets are only allowed after the percentage, and need a
+        // sign, e.g. "100% - 200" or "50%+10".
+        let offset = offset.trim_start();
+        if offset.is_empty() {
+            return Ok(Self::new_relative(relative));
+        }
+
+        let (sign, absolute) = if let Some(absolute) = offset.strip_prefix('+') {
+            (1.0, absolute)
+        } else if let Some(absolute) = offset.strip_prefix('-') {
+            (-1.0, absolute)
         } else {
-            Err(TryFromStrError)
+            return Err(TryFromStrError);
+        };
+
+        match f32::from_str(absolute.trim_start()) {
+            Ok(absolute) if absolute.is_sign_positive() => {
+                Ok(Self::new_calc(relative, sign * absolute))
+            }
+            _ => Err(TryFromStrError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_try_from_str() {
+        let size = |value: &str| Size::try_from(value).unwrap();
+
+        assert_eq!(size("41%"), Size::new_relative(0.41));
+        assert_eq!(size("100% - 200"), Size::new_calc(1.0, -200.0));
+        assert_eq!(size("50%+10"), Size::new_calc(0.5, 10.0));
+        assert_eq!(size(" fill "), Size::new_fill());
+        assert_eq!(size("content"), Size::new_content());
+
+        assert_eq!(size("100%-200").resolve(1000.0), 800.0);
+        assert_eq!(size("50% + 10").resolve(1000.0), 510.0);
+
+        for value in ["100", "100% 200", "100% - -200", "100% - x", "%", "fil"] {
+            assert!(Size::try_from(value).is_err(), "{value}");
         }
     }
 }
@@ -0,0 +1,126 @@
+use core::alloc::Allocator;
+use core::f32::consts::{FRAC_PI_2, TAU};
+use core::mem;
+
+use crate::core::{color_lerp, CtrlFlags, CtrlState, Frame, Layout, Rect, Theme, Vec2};
+
+const DOT_COUNT: usize = 8;
+// Relative to the spinner radius.
+const DOT_RADIUS: f32 = 0.15;
+
+/// Draws a ring of dots with a highlight running around it, e.g. to show that
+/// work is happening in the background.
+///
+/// The spinner advances each time it is built, by the frame time passed to
+/// [`crate::Ui::begin_frame_with_delta_time`], or as if running at 60 frames
+/// per second without it. It only moves if the platform keeps building
+/// frames while it is shown.
+#[inline]
+pub fn spinner<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, radius: f32) {
+    frame.with_theme(|frame, theme| spinner_with_theme(frame, id, radius, theme))
+}
+
+pub fn spinner_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    radius: f32,
+    theme: &Theme,
+) {
+    let texture_id = frame.font_atlas_texture_id();
+    let delta_time = frame.delta_time_or_fallback();
+
+    let radius = f32::max(0.0, radius);
+
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(CtrlFlags::NONE);
+    ctrl.set_layout(Layout::Vertical);
+    ctrl.set_rect(Rect::new(0.0, 0.0, 2.0 * radius, 2.0 * radius));
+    ctrl.set_padding(0.0);
+    ctrl.set_border(0.0);
+    ctrl.set_margin(theme.spinner_margin);
+
+    let state = cast_state_mut(ctrl.state_mut());
+    // Wrap around, so that the phase doesn't lose precision over time.
+    let phase = state.phase + delta_time * theme.spinner_speed;
+    state.phase = phase - libm::floorf(phase);
+
+    let head = state.phase * DOT_COUNT as f32;
+    let dot_radius = DOT_RADIUS * radius;
+    let center = Vec2::splat(radius);
+    let transparent_color = theme.spinner_color & 0xffffff00;
+
+    ctrl.set_draw_self(false);
+    for i in 0..DOT_COUNT {
+        // Dots fade out the further they trail behind the head.
+        let behind = head - i as f32;
+        let behind = behind - DOT_COUNT as f32 * libm::floorf(behind / DOT_COUNT as f32);
+        let color = color_lerp(
+            theme.spinner_color,
+            transparent_color,
+            behind / DOT_COUNT as f32,
+        );
+
+        // Start at the top and go clockwise.
+        let angle = TAU * i as f32 / DOT_COUNT as f32 - FRAC_PI_2;
+        let direction = Vec2::new(libm::cosf(angle), libm::sinf(angle));
+
+        ctrl.draw_circle(
+            center + (radius - dot_radius) * direction,
+            dot_radius,
+            color,
+            texture_id,
+        );
+    }
+
+    frame.pop_ctrl();
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    // How far around the ring the head is, from 0 to 1.
+    phase: f32,
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::new_ui;
+
+    fn build(ui: &mut Ui<Global>, delta_time: f32) -> Vec<u32> {
+        let mut frame = ui.begin_frame_with_delta_time(delta_time);
+        spinner(&mut frame, 0, 20.0);
+        ui.end_frame();
+
+        ui.draw_list().1.iter().map(|vertex| vertex.color).collect()
+    }
+
+    #[test]
+    fn test_spinner_advances_with_delta_time() {
+        let mut ui = new_ui();
+
+        let colors = build(&mut ui, 0.0);
+        assert_eq!(ui.frame_stats().draw_primitive_count, DOT_COUNT);
+        assert_eq!(build(&mut ui, 0.0), colors);
+
+        // With the default speed of a turn per second, an eighth of a second
+        // moves the head to the next dot.
+        let next_colors = build(&mut ui, 0.125);
+        assert_ne!(next_colors, colors);
+
+        // After a full turn, the spinner looks the same again.
+        for _ in 0..DOT_COUNT {
+            build(&mut ui, 0.125);
+        }
+        assert_eq!(build(&mut ui, 0.0), next_colors);
+    }
+}
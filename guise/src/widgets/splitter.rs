@@ -0,0 +1,341 @@
+use core::alloc::Allocator;
+use core::mem;
+
+use crate::core::{CtrlFlags, CtrlState, CursorIcon, Frame, Inputs, Layout, Rect, Theme, Vec2};
+
+const PANE_FLAGS: CtrlFlags = CtrlFlags::CAPTURE_SCROLL | CtrlFlags::SHOW_SCROLLBARS;
+
+const STAGE_FIRST_PANE: u8 = 0;
+const STAGE_SECOND_PANE: u8 = 1;
+const STAGE_ENDED: u8 = 2;
+
+/// Splits the parent's inner size into two panes side by side, separated by a
+/// divider that can be dragged to resize them, e.g. for a tree on the left and
+/// content on the right. The ratio is the fraction of the width, from 0 to 1,
+/// taken by the left pane, and is updated while the divider is dragged.
+///
+/// The left pane is built after this call, the right pane after
+/// [`Split::next`], and the split is finished with [`Split::end`]. The panes
+/// lay out their contents vertically and scroll independently.
+#[inline]
+pub fn begin_split_horizontal<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    ratio: &mut f32,
+) -> Split {
+    frame.with_theme(|frame, theme| do_split(frame, id, ratio, Layout::Horizontal, theme))
+}
+
+pub fn begin_split_horizontal_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    ratio: &mut f32,
+    theme: &Theme,
+) -> Split {
+    do_split(frame, id, ratio, Layout::Horizontal, theme)
+}
+
+/// Like [`begin_split_horizontal`], but the panes are stacked on top of each
+/// other, and the ratio is the fraction of the height taken by the top pane.
+#[inline]
+pub fn begin_split_vertical<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    ratio: &mut f32,
+) -> Split {
+    frame.with_theme(|frame, theme| do_split(frame, id, ratio, Layout::Vertical, theme))
+}
+
+pub fn begin_split_vertical_with_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    ratio: &mut f32,
+    theme: &Theme,
+) -> Split {
+    do_split(frame, id, ratio, Layout::Vertical, theme)
+}
+
+pub struct Split {
+    layout: Layout,
+    // The size of the whole split, and the length of the second pane along
+    // the split's layout.
+    size: Vec2,
+    second_pane_length: f32,
+    ratio: f32,
+    dragging: bool,
+    divider_width: f32,
+    divider_color: u32,
+    divider_color_hovered: u32,
+    divider_color_active: u32,
+    scrollbar_width: f32,
+    scrollbar_track_color: u32,
+    scrollbar_thumb_color: u32,
+    stage: u8,
+}
+
+impl Split {
+    /// Finishes the first pane, and starts the second.
+    pub fn next<A: Allocator + Clone>(&mut self, frame: &mut Frame<A>) {
+        assert!(
+            self.stage == STAGE_FIRST_PANE,
+            "Split::next called more than once",
+        );
+
+        frame.pop_ctrl();
+
+        let cursor_position = frame.cursor_position();
+        let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+        let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
+
+        let mut ctrl = frame.push_ctrl(1);
+        ctrl.set_flags(CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE);
+        ctrl.set_layout(Layout::Free);
+        ctrl.set_rect(self.rect(self.divider_width));
+        ctrl.set_padding(0.0);
+        ctrl.set_border(0.0);
+        ctrl.set_margin(0.0);
+
+        let hovered = ctrl.is_hovered();
+        let start_dragging = !self.dragging && hovered && lmb_pressed;
+
+        if start_dragging {
+            ctrl.set_active(true);
+        } else if ctrl.is_active() && lmb_released {
+            ctrl.set_active(false);
+        }
+
+        let dragging = self.dragging || start_dragging;
+        if dragging || hovered {
+            ctrl.set_cursor_icon(match self.layout {
+                Layout::Horizontal => CursorIcon::ResizeHorizontal,
+                _ => CursorIcon::ResizeVertical,
+            });
+        }
+
+        let color = if dragging {
+            self.divider_color_active
+        } else if hovered {
+            self.divider_color_hovered
+        } else {
+            self.divider_color
+        };
+
+        ctrl.set_draw_self(true);
+        ctrl.set_draw_self_border_color(color);
+        ctrl.set_draw_self_background_color(color);
+
+        frame.pop_ctrl();
+
+        if start_dragging {
+            let state = cast_state_mut(frame.ctrl_state_mut());
+            state.dragging = 1;
+            state.drag_start_cursor = self.axis(cursor_position);
+            state.drag_start_ratio = self.ratio;
+        }
+
+        push_pane(frame, 2, self.rect(self.second_pane_length), self);
+
+        self.stage = STAGE_SECOND_PANE;
+    }
+
+    /// Finishes the second pane, and the whole split.
+    pub fn end<A: Allocator + Clone>(mut self, frame: &mut Frame<A>) {
+        assert!(
+            self.stage == STAGE_SECOND_PANE,
+            "Split::end called without Split::next",
+        );
+
+        frame.pop_ctrl();
+        frame.pop_ctrl();
+
+        self.stage = STAGE_ENDED;
+    }
+
+    // A rect spanning the whole split across its layout, and having the length
+    // along it.
+    fn rect(&self, length: f32) -> Rect {
+        match self.layout {
+            Layout::Horizontal => Rect::new(0.0, 0.0, length, self.size.y),
+            _ => Rect::new(0.0, 0.0, self.size.x, length),
+        }
+    }
+
+    fn axis(&self, v: Vec2) -> f32 {
+        match self.layout {
+            Layout::Horizontal => v.x,
+            _ => v.y,
+        }
+    }
+}
+
+impl Drop for Split {
+    fn drop(&mut self) {
+        debug_assert!(
+            self.stage == STAGE_ENDED,
+            "Split dropped without calling Split::end",
+        );
+    }
+}
+
+fn do_split<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    ratio: &mut f32,
+    layout: Layout,
+    theme: &Theme,
+) -> Split {
+    let parent_size = frame.ctrl_inner_size();
+    let cursor_position = frame.cursor_position();
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
+
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(CtrlFlags::NONE);
+    ctrl.set_layout(layout);
+    ctrl.set_rect(Rect::new(0.0, 0.0, parent_size.x, parent_size.y));
+    ctrl.set_padding(0.0);
+    ctrl.set_border(0.0);
+    ctrl.set_margin(0.0);
+    ctrl.set_draw_self(false);
+
+    let mut split = Split {
+        layout,
+        size: parent_size,
+        second_pane_length: 0.0,
+        ratio: 0.0,
+        dragging: false,
+        divider_width: f32::max(0.0, theme.splitter_width),
+        divider_color: theme.splitter_color,
+        divider_color_hovered: theme.splitter_color_hovered,
+        divider_color_active: theme.splitter_color_active,
+        scrollbar_width: theme.scrollbar_width,
+        scrollbar_track_color: theme.scrollbar_track_color,
+        scrollbar_thumb_color: theme.scrollbar_thumb_color,
+        stage: STAGE_FIRST_PANE,
+    };
+
+    let available_length = f32::max(0.0, split.axis(parent_size) - split.divider_width);
+
+    // The drag is started by the divider, but the ratio must be known before
+    // the first pane is pushed, so we continue it here.
+    let state = cast_state_mut(ctrl.state_mut());
+    if state.dragging == 1 {
+        if lmb_released {
+            state.dragging = 0;
+        } else if available_length > 0.0 {
+            let delta = split.axis(cursor_position) - state.drag_start_cursor;
+            *ratio = state.drag_start_ratio + delta / available_length;
+        }
+    }
+
+    *ratio = f32::clamp(*ratio, 0.0, 1.0);
+
+    // Rounded, so that the divider doesn't straddle pixels.
+    let first_pane_length = libm::roundf(*ratio * available_length);
+
+    split.second_pane_length = available_length - first_pane_length;
+    split.ratio = *ratio;
+    split.dragging = state.dragging == 1;
+
+    push_pane(frame, 0, split.rect(first_pane_length), &split);
+
+    split
+}
+
+fn push_pane<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, rect: Rect, split: &Split) {
+    let mut ctrl = frame.push_ctrl(id);
+    ctrl.set_flags(PANE_FLAGS);
+    ctrl.set_layout(Layout::Vertical);
+    ctrl.set_rect(rect);
+    ctrl.set_padding(0.0);
+    ctrl.set_border(0.0);
+    ctrl.set_margin(0.0);
+    ctrl.set_draw_self(false);
+
+    ctrl.set_scrollbar_width(split.scrollbar_width);
+    ctrl.set_scrollbar_track_color(split.scrollbar_track_color);
+    ctrl.set_scrollbar_thumb_color(split.scrollbar_thumb_color);
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[derive(bytemuck::Zeroable, bytemuck::Pod)]
+struct State {
+    drag_start_cursor: f32,
+    drag_start_ratio: f32,
+    dragging: u8,
+    _pad0: u8,
+    _pad1: u8,
+    _pad2: u8,
+}
+
+fn cast_state_mut(state: &mut CtrlState) -> &mut State {
+    bytemuck::from_bytes_mut(&mut state[..mem::size_of::<State>()])
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::fixtures::font_source;
+    use crate::testing::TestHarness;
+
+    #[test]
+    fn test_split_horizontal_drag_updates_ratio() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut ratio = 0.5;
+
+        {
+            let mut build = |frame: &mut Frame<Global>| {
+                let mut split = begin_split_horizontal(frame, 0, &mut ratio);
+                split.next(frame);
+                split.end(frame);
+            };
+
+            harness.run_frame(&mut build);
+
+            let divider = harness.rect_of(&[0, 1]).unwrap();
+            assert_eq!(divider.x, 398.0);
+            assert_eq!(divider.width, 4.0);
+            assert_eq!(divider.height, 600.0);
+
+            harness.drag(Vec2::new(400.0, 300.0), Vec2::new(500.0, 300.0), &mut build);
+            assert!(!harness.is_active(&[0, 1]));
+
+            let left = harness.rect_of(&[0, 0]).unwrap();
+            let right = harness.rect_of(&[0, 2]).unwrap();
+            assert_eq!(left.width, 498.0);
+            assert_eq!(right.x, 502.0);
+            assert_eq!(right.width, 298.0);
+
+            // Moving the cursor after release doesn't move the divider.
+            harness.move_cursor_to(600.0, 300.0, &mut build);
+        }
+
+        assert_eq!(ratio, 0.5 + 100.0 / 796.0);
+    }
+
+    #[test]
+    fn test_split_vertical_clamps_ratio() {
+        let mut harness = TestHarness::new(800.0, 600.0, font_source());
+        let mut ratio = 0.25;
+
+        {
+            let mut build = |frame: &mut Frame<Global>| {
+                let mut split = begin_split_vertical(frame, 0, &mut ratio);
+                split.next(frame);
+                split.end(frame);
+            };
+
+            harness.run_frame(&mut build);
+            assert_eq!(harness.rect_of(&[0, 0]).unwrap().height, 149.0);
+            assert_eq!(harness.rect_of(&[0, 2]).unwrap().width, 800.0);
+
+            // Dragging past the bottom collapses the bottom pane.
+            harness.drag(Vec2::new(400.0, 150.0), Vec2::new(400.0, 900.0), &mut build);
+            assert_eq!(harness.rect_of(&[0, 2]).unwrap().height, 0.0);
+        }
+
+        assert_eq!(ratio, 1.0);
+    }
+}
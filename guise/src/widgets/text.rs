@@ -1,11 +1,12 @@
 use core::alloc::Allocator;
 
-use crate::core::{Align, CtrlFlags, Frame, Layout, Rect, Wrap};
-use crate::widgets::theme::Theme;
+use crate::core::{Align, CtrlFlags, Direction, Frame, Layout, Rect, Theme, Wrap};
 
 #[inline]
 pub fn text<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, text: &str) {
-    text_with_align_theme(frame, id, text, Align::Center, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        text_with_align_direction_theme(frame, id, text, Align::Center, Direction::Ltr, theme)
+    })
 }
 
 #[inline]
@@ -15,15 +16,42 @@ pub fn text_with_align<A: Allocator + Clone>(
     text: &str,
     align: Align,
 ) {
-    text_with_align_theme(frame, id, text, align, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| {
+        text_with_align_direction_theme(frame, id, text, align, Direction::Ltr, theme)
+    })
 }
 
+#[inline]
+pub fn text_with_align_direction<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    text: &str,
+    align: Align,
+    direction: Direction,
+) {
+    frame.with_theme(|frame, theme| {
+        text_with_align_direction_theme(frame, id, text, align, direction, theme)
+    })
+}
+
+#[inline]
 pub fn text_with_align_theme<A: Allocator + Clone>(
     frame: &mut Frame<A>,
     id: u32,
     text: &str,
     align: Align,
     theme: &Theme,
+) {
+    text_with_align_direction_theme(frame, id, text, align, Direction::Ltr, theme)
+}
+
+pub fn text_with_align_direction_theme<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    text: &str,
+    align: Align,
+    direction: Direction,
+    theme: &Theme,
 ) {
     let parent_size = frame.ctrl_inner_size();
 
@@ -47,6 +75,7 @@ pub fn text_with_align_theme<A: Allocator + Clone>(
         // Vertical align does not make sense with shrunk-to-fit controls.
         Align::Start,
         Wrap::Word,
+        direction,
         theme.text_text_color,
         theme.text_border + theme.text_padding,
     );
@@ -3,25 +3,44 @@ use core::alloc::Allocator;
 use core::mem;
 use core::ops::{Deref, Range};
 
-use arrayvec::{ArrayString, ArrayVec};
+use arrayvec::ArrayVec;
 
 use crate::convert::cast_u32;
 use crate::core::{
+    fuzzy_match,
+    fuzzy_match_with_positions,
     Align,
     Ctrl,
     CtrlFlags,
     CtrlState,
+    CursorIcon,
+    Direction,
     Frame,
+    InputEvent,
     Inputs,
     Layout,
     Modifiers,
     Rect,
+    Shortcut,
     TextStorage,
+    Theme,
     Vec2,
     Wrap,
 };
-use crate::widgets::button::button;
-use crate::widgets::theme::Theme;
+use crate::widgets::button::{do_button, ButtonOptions};
+
+// NB: The keypresses aren't claimed, but Frame::shortcut_pressed doesn't report
+// these while we want text input. Keep in sync with ui.rs.
+const SHORTCUT_SELECT_ALL: Shortcut = Shortcut::new(Inputs::KB_A, Modifiers::CTRL);
+const SHORTCUT_CUT: Shortcut = Shortcut::new(Inputs::KB_X, Modifiers::CTRL);
+const SHORTCUT_COPY: Shortcut = Shortcut::new(Inputs::KB_C, Modifiers::CTRL);
+const SHORTCUT_PASTE: Shortcut = Shortcut::new(Inputs::KB_V, Modifiers::CTRL);
+const SHORTCUT_EMACS_LEFT: Shortcut = Shortcut::new(Inputs::KB_B, Modifiers::CTRL);
+const SHORTCUT_EMACS_SELECT_LEFT: Shortcut =
+    Shortcut::new(Inputs::KB_B, Modifiers::CTRL | Modifiers::SHIFT);
+const SHORTCUT_EMACS_RIGHT: Shortcut = Shortcut::new(Inputs::KB_F, Modifiers::CTRL);
+const SHORTCUT_EMACS_SELECT_RIGHT: Shortcut =
+    Shortcut::new(Inputs::KB_F, Modifiers::CTRL | Modifiers::SHIFT);
 
 const LABEL_WIDTH_RATIO: f32 = 0.35;
 const LABEL_SPACING: f32 = 5.0;
@@ -46,16 +65,46 @@ where
     T: TextStorage,
     A: Allocator + Clone,
 {
-    do_text_input_and_file_taxes::<_, _, &str>(
-        frame,
-        id,
-        text,
-        label,
-        None,
-        None,
-        &[],
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_text_input_and_file_taxes::<_, _, &str>(
+            frame,
+            id,
+            text,
+            label,
+            None,
+            None,
+            &[],
+            Direction::Ltr,
+            theme,
+        )
+    })
+}
+
+#[inline]
+pub fn text_input_with_direction<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    text: &mut T,
+    label: &str,
+    direction: Direction,
+) -> bool
+where
+    T: TextStorage,
+    A: Allocator + Clone,
+{
+    frame.with_theme(|frame, theme| {
+        do_text_input_and_file_taxes::<_, _, &str>(
+            frame,
+            id,
+            text,
+            label,
+            None,
+            None,
+            &[],
+            direction,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -71,31 +120,70 @@ where
     A: Allocator + Clone,
     D: Deref<Target = str>,
 {
-    do_text_input_and_file_taxes(
+    frame.with_theme(|frame, theme| {
+        do_text_input_and_file_taxes(
+            frame,
+            id,
+            text,
+            label,
+            None,
+            None,
+            autocomplete,
+            Direction::Ltr,
+            theme,
+        )
+    })
+}
+
+#[inline]
+pub fn text_input_with_theme<T, A>(
+    frame: &mut Frame<A>,
+    id: u32,
+    text: &mut T,
+    label: &str,
+    theme: &Theme,
+) -> bool
+where
+    T: TextStorage,
+    A: Allocator + Clone,
+{
+    do_text_input_and_file_taxes::<_, _, &str>(
         frame,
         id,
         text,
         label,
         None,
         None,
-        autocomplete,
-        &Theme::DEFAULT,
+        &[],
+        Direction::Ltr,
+        theme,
     )
 }
 
 #[inline]
-pub fn text_input_with_theme<T, A>(
+pub fn text_input_with_direction_theme<T, A>(
     frame: &mut Frame<A>,
     id: u32,
     text: &mut T,
     label: &str,
+    direction: Direction,
     theme: &Theme,
 ) -> bool
 where
     T: TextStorage,
     A: Allocator + Clone,
 {
-    do_text_input_and_file_taxes::<_, _, &str>(frame, id, text, label, None, None, &[], theme)
+    do_text_input_and_file_taxes::<_, _, &str>(
+        frame,
+        id,
+        text,
+        label,
+        None,
+        None,
+        &[],
+        direction,
+        theme,
+    )
 }
 
 #[inline]
@@ -112,7 +200,17 @@ where
     A: Allocator + Clone,
     D: Deref<Target = str>,
 {
-    do_text_input_and_file_taxes(frame, id, text, label, None, None, autocomplete, theme)
+    do_text_input_and_file_taxes(
+        frame,
+        id,
+        text,
+        label,
+        None,
+        None,
+        autocomplete,
+        Direction::Ltr,
+        theme,
+    )
 }
 
 #[inline]
@@ -128,16 +226,19 @@ where
     A: Allocator + Clone,
     C: FnMut(&TextInputCallbackData, &mut T),
 {
-    do_text_input_and_file_taxes::<_, _, &str>(
-        frame,
-        id,
-        text,
-        label,
-        Some(&mut callback),
-        None,
-        &[],
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_text_input_and_file_taxes::<_, _, &str>(
+            frame,
+            id,
+            text,
+            label,
+            Some(&mut callback),
+            None,
+            &[],
+            Direction::Ltr,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -155,16 +256,19 @@ where
     C: FnMut(&TextInputCallbackData, &mut T),
     D: Deref<Target = str>,
 {
-    do_text_input_and_file_taxes(
-        frame,
-        id,
-        text,
-        label,
-        Some(&mut callback),
-        None,
-        autocomplete,
-        &Theme::DEFAULT,
-    )
+    frame.with_theme(|frame, theme| {
+        do_text_input_and_file_taxes(
+            frame,
+            id,
+            text,
+            label,
+            Some(&mut callback),
+            None,
+            autocomplete,
+            Direction::Ltr,
+            theme,
+        )
+    })
 }
 
 #[inline]
@@ -189,6 +293,7 @@ where
         Some(&mut callback),
         None,
         &[],
+        Direction::Ltr,
         theme,
     )
 }
@@ -217,6 +322,7 @@ where
         Some(&mut callback),
         None,
         autocomplete,
+        Direction::Ltr,
         theme,
     )
 }
@@ -232,6 +338,7 @@ pub(crate) fn do_text_input_and_file_taxes<T, A, D>(
     result_callback: Option<&mut dyn FnMut(&TextInputCallbackData, &mut T)>,
     filter_map_callback: Option<&dyn Fn(char) -> Option<char>>,
     autocomplete: &[D],
+    direction: Direction,
     theme: &Theme,
 ) -> bool
 where
@@ -241,20 +348,12 @@ where
 {
     let parent_size = frame.ctrl_inner_size();
     let inputs_pressed = frame.inputs_pressed();
-    let modifiers = frame.modifiers();
 
-    let received_characters_unfiltered_count = frame.received_characters().len();
-    let mut received_characters: ArrayString<32> = ArrayString::new();
-
-    if let Some(fmc) = filter_map_callback {
-        for c in frame.received_characters().chars() {
-            if let Some(c) = fmc(c) {
-                received_characters.push(c);
-            }
-        }
-    } else {
-        received_characters.push_str(frame.received_characters());
-    }
+    // NB: Copy the events out of the frame, because processing them requires
+    // borrowing the frame mutably through the control. The capacity matches
+    // the event queue in Ui.
+    let mut input_events: ArrayVec<InputEvent, 64> = ArrayVec::new();
+    let _ = input_events.try_extend_from_slice(frame.input_events());
 
     let outer_width = f32::max(0.0, parent_size.x - 2.0 * theme.text_input_margin);
     let label_width = LABEL_WIDTH_RATIO * outer_width;
@@ -274,12 +373,17 @@ where
         Align::Start,
         Align::Center,
         Wrap::Word,
+        Direction::Ltr,
         theme.text_input_text_color,
         Rect::new(0.0, 0.0, label_width, theme.text_input_height),
     );
 
     let mut inner_ctrl = frame.push_ctrl(0);
-    inner_ctrl.set_flags(CtrlFlags::CAPTURE_SCROLL | CtrlFlags::CAPTURE_HOVER);
+    // NB: Capturing active status makes it return to us, after an autocomplete
+    // candidate from our overlay is clicked.
+    inner_ctrl.set_flags(
+        CtrlFlags::CAPTURE_SCROLL | CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE,
+    );
     inner_ctrl.set_layout(Layout::Vertical);
     inner_ctrl.set_rect(Rect::new(
         label_width + LABEL_SPACING,
@@ -294,290 +398,257 @@ where
     let hovered = inner_ctrl.is_hovered();
     let active_orig = inner_ctrl.is_active();
 
+    if hovered {
+        inner_ctrl.set_cursor_icon(CursorIcon::Text);
+    }
+
     let state = cast_state(inner_ctrl.state());
     let mut text_cursor = usize::clamp(state.text_cursor, 0, text.len());
     let mut text_selection_start = usize::clamp(state.text_selection_start, 0, text.len());
     let mut text_selection_end = usize::clamp(state.text_selection_end, 0, text.len());
-    let autocomplete_open = state.autocomplete_open;
+    // Clicking outside of us and our autocomplete overlay closes the overlay.
+    let autocomplete_open = if inner_ctrl.clicked_outside() {
+        AUTOCOMPLETE_CLOSED
+    } else {
+        state.autocomplete_open
+    };
 
+    let mut active = active_orig;
+    let mut changed = false;
+    let mut action = TextInputAction::None;
     let mut deactivated_from_kb = false;
 
-    let (active, changed, action) = if active_orig
-        && (received_characters_unfiltered_count > 0 || inputs_pressed != Inputs::NONE)
-    {
-        let (handled, active, changed, action) = match inputs_pressed {
-            Inputs::KB_BACKSPACE => {
-                if text.len() > 0 {
-                    let start = usize::min(text_selection_start, text_selection_end);
-                    let end = usize::max(text_selection_start, text_selection_end);
-
-                    if start != end {
-                        // Ok to unwrap, because we are only removing.
-                        text.try_splice(start, end - start, "").unwrap();
-
-                        text_cursor = start;
-                        text_selection_start = start;
-                        text_selection_end = start;
-                    } else if text_cursor == text.len() {
-                        let text_cursor_after_trunc = seek_prev(text_cursor, text);
-
-                        text.truncate(text_cursor_after_trunc);
-
-                        text_cursor = text_cursor_after_trunc;
-                        text_selection_start = text_cursor;
+    if active_orig {
+        // Process events in the order they were received, so that multiple
+        // keypresses and characters in a single frame are all applied
+        // deterministically.
+        for event in input_events {
+            match event {
+                InputEvent::KeyPress {
+                    input, modifiers, ..
+                } => {
+                    if input.contains(Inputs::KB_BACKSPACE) {
+                        if text.len() > 0 {
+                            let start = usize::min(text_selection_start, text_selection_end);
+                            let end = usize::max(text_selection_start, text_selection_end);
+
+                            if start != end {
+                                // Ok to unwrap, because we are only removing.
+                                text.try_splice(start, end - start, "").unwrap();
+
+                                text_cursor = start;
+                                text_selection_start = start;
+                                text_selection_end = start;
+                            } else if text_cursor == text.len() {
+                                let text_cursor_after_trunc = seek_prev(text_cursor, text);
+
+                                text.truncate(text_cursor_after_trunc);
+
+                                text_cursor = text_cursor_after_trunc;
+                                text_selection_start = text_cursor;
+                                text_selection_end = text_cursor;
+                            } else if text_cursor > 0 {
+                                let text_cursor_after = seek_prev(text_cursor, text);
+                                let delete_count = text_cursor - text_cursor_after;
+
+                                // Ok to unwrap, because we are only removing.
+                                text.try_splice(text_cursor_after, delete_count, "")
+                                    .unwrap();
+
+                                text_cursor = text_cursor_after;
+                                text_selection_start = text_cursor;
+                                text_selection_end = text_cursor;
+                            }
+
+                            changed = true;
+                        }
+                    } else if input.contains(Inputs::KB_DELETE) {
+                        if text.len() > 0 {
+                            let last_char_index = seek_prev(text.len(), text);
+
+                            if text_selection_start != text_selection_end {
+                                let start = usize::min(text_selection_start, text_selection_end);
+                                let end = usize::max(text_selection_start, text_selection_end);
+
+                                // Ok to unwrap, because we are only removing.
+                                text.try_splice(start, end - start, "").unwrap();
+
+                                text_cursor = start;
+                                text_selection_start = text_cursor;
+                                text_selection_end = text_cursor;
+                            } else if text_cursor == last_char_index {
+                                text.truncate(last_char_index);
+
+                                text_selection_start = text_cursor;
+                                text_selection_end = text_cursor;
+                            } else if text_cursor < last_char_index {
+                                let delete_count = seek_next(text_cursor, text) - text_cursor;
+
+                                // Ok to unwrap, because we are only removing.
+                                text.try_splice(text_cursor, delete_count, "").unwrap();
+
+                                text_selection_start = text_cursor;
+                                text_selection_end = text_cursor;
+                            }
+
+                            changed = true;
+                        }
+                    } else if SHORTCUT_SELECT_ALL.matches(input, modifiers) {
+                        text_cursor = 0;
+                        text_selection_start = 0;
+                        text_selection_end = text.len();
+                    } else if input.contains(Inputs::KB_LEFT_ARROW)
+                        || input.contains(Inputs::KB_RIGHT_ARROW)
+                        || SHORTCUT_EMACS_LEFT.matches(input, modifiers)
+                        || SHORTCUT_EMACS_SELECT_LEFT.matches(input, modifiers)
+                        || SHORTCUT_EMACS_RIGHT.matches(input, modifiers)
+                        || SHORTCUT_EMACS_SELECT_RIGHT.matches(input, modifiers)
+                    {
+                        // The arrow keys move the cursor in visual order, which
+                        // for right-to-left text is the reverse of the logical
+                        // order the Emacs shortcuts move it in.
+                        let forward = if input.contains(Inputs::KB_LEFT_ARROW) {
+                            direction == Direction::Rtl
+                        } else if input.contains(Inputs::KB_RIGHT_ARROW) {
+                            direction == Direction::Ltr
+                        } else {
+                            SHORTCUT_EMACS_RIGHT.matches(input, modifiers)
+                                || SHORTCUT_EMACS_SELECT_RIGHT.matches(input, modifiers)
+                        };
+
+                        text_cursor = if forward {
+                            seek_next(text_cursor, text)
+                        } else {
+                            seek_prev(text_cursor, text)
+                        };
                         text_selection_end = text_cursor;
-                    } else if text_cursor > 0 {
-                        let text_cursor_after = seek_prev(text_cursor, text);
-                        let delete_count = text_cursor - text_cursor_after;
-
-                        // Ok to unwrap, because we are only removing.
-                        text.try_splice(text_cursor_after, delete_count, "")
-                            .unwrap();
-
-                        text_cursor = text_cursor_after;
-                        text_selection_start = text_cursor;
+                        if !modifiers.contains(Modifiers::SHIFT) {
+                            text_selection_start = text_cursor;
+                        }
+                    } else if input.contains(Inputs::KB_HOME) || input.contains(Inputs::KB_END) {
+                        // Home and End go to the logical start and end of the
+                        // text, i.e. to the right and left for right-to-left
+                        // text.
+                        text_cursor = if input.contains(Inputs::KB_HOME) {
+                            0
+                        } else {
+                            text.len()
+                        };
                         text_selection_end = text_cursor;
+                        if !modifiers.contains(Modifiers::SHIFT) {
+                            text_selection_start = text_cursor;
+                        }
+                    } else if SHORTCUT_CUT.matches(input, modifiers) {
+                        if text_selection_start != text_selection_end {
+                            let start = usize::min(text_selection_start, text_selection_end);
+                            let end = usize::max(text_selection_start, text_selection_end);
+
+                            let s = &text[start..end];
+                            inner_ctrl.set_clipboard_text(s);
+
+                            // Ok to unwrap, because we are only removing.
+                            text.try_splice(start, end - start, "").unwrap();
+
+                            text_cursor = start;
+                            text_selection_start = text_cursor;
+                            text_selection_end = text_cursor;
+
+                            changed = true;
+                        }
+                    } else if SHORTCUT_COPY.matches(input, modifiers) {
+                        if text_selection_start != text_selection_end {
+                            let start = usize::min(text_selection_start, text_selection_end);
+                            let end = usize::max(text_selection_start, text_selection_end);
+
+                            let s = &text[start..end];
+                            inner_ctrl.set_clipboard_text(s);
+                        }
+                    } else if SHORTCUT_PASTE.matches(input, modifiers) {
+                        let s = inner_ctrl.get_clipboard_text();
+                        if s.len() > 0 {
+                            changed |= insert(
+                                text,
+                                s,
+                                &mut text_cursor,
+                                &mut text_selection_start,
+                                &mut text_selection_end,
+                            );
+                        }
+                    } else if input.contains(Inputs::KB_ENTER) {
+                        inner_ctrl.set_active(false);
+                        deactivated_from_kb = true;
+                        active = false;
+                        action = TextInputAction::Submit;
+
+                        // We are no longer active, the rest of the events is
+                        // not for us.
+                        break;
+                    } else if input.contains(Inputs::KB_ESCAPE) {
+                        inner_ctrl.set_active(false);
+                        deactivated_from_kb = true;
+                        active = false;
+                        action = TextInputAction::Cancel;
+
+                        // We are no longer active, the rest of the events is
+                        // not for us.
+                        break;
                     }
-
-                    (true, true, true, TextInputAction::None)
-                } else {
-                    (true, true, false, TextInputAction::None)
-                }
-            }
-
-            Inputs::KB_DELETE => {
-                if text.len() > 0 {
-                    let last_char_index = seek_prev(text.len(), text);
-
-                    if text_selection_start != text_selection_end {
-                        let start = usize::min(text_selection_start, text_selection_end);
-                        let end = usize::max(text_selection_start, text_selection_end);
-
-                        // Ok to unwrap, because we are only removing.
-                        text.try_splice(start, end - start, "").unwrap();
-
-                        text_cursor = start;
-                        text_selection_start = text_cursor;
-                        text_selection_end = text_cursor;
-                    } else if text_cursor == last_char_index {
-                        text.truncate(last_char_index);
-
-                        text_selection_start = text_cursor;
-                        text_selection_end = text_cursor;
-                    } else if text_cursor < last_char_index {
-                        let delete_count = seek_next(text_cursor, text) - text_cursor;
-
-                        // Ok to unwrap, because we are only removing.
-                        text.try_splice(text_cursor, delete_count, "").unwrap();
-
-                        text_selection_start = text_cursor;
-                        text_selection_end = text_cursor;
-                    }
-
-                    (true, true, true, TextInputAction::None)
-                } else {
-                    (true, true, false, TextInputAction::None)
-                }
-            }
-
-            Inputs::KB_A => {
-                if modifiers == Modifiers::CTRL {
-                    text_cursor = 0;
-                    text_selection_start = 0;
-                    text_selection_end = text.len();
-
-                    (true, true, false, TextInputAction::None)
-                } else {
-                    (false, true, false, TextInputAction::None)
-                }
-            }
-
-            Inputs::KB_LEFT_ARROW => {
-                text_cursor = seek_prev(text_cursor, text);
-                text_selection_end = text_cursor;
-                if !modifiers.intersects(Modifiers::SHIFT) {
-                    text_selection_start = text_cursor;
-                }
-
-                (true, true, false, TextInputAction::None)
-            }
-
-            Inputs::KB_B => {
-                if modifiers == Modifiers::CTRL {
-                    text_cursor = seek_prev(text_cursor, text);
-                    text_selection_start = text_cursor;
-                    text_selection_end = text_cursor;
-
-                    (true, true, false, TextInputAction::None)
-                } else if modifiers == Modifiers::CTRL | Modifiers::SHIFT {
-                    text_cursor = seek_prev(text_cursor, text);
-                    text_selection_end = text_cursor;
-
-                    (true, true, false, TextInputAction::None)
-                } else {
-                    (false, true, false, TextInputAction::None)
-                }
-            }
-
-            Inputs::KB_RIGHT_ARROW => {
-                text_cursor = seek_next(text_cursor, text);
-                text_selection_end = text_cursor;
-                if !modifiers.intersects(Modifiers::SHIFT) {
-                    text_selection_start = text_cursor;
-                }
-
-                (true, true, false, TextInputAction::None)
-            }
-
-            Inputs::KB_F => {
-                if modifiers == Modifiers::CTRL {
-                    text_cursor = seek_next(text_cursor, text);
-                    text_selection_start = text_cursor;
-                    text_selection_end = text_cursor;
-
-                    (true, true, false, TextInputAction::None)
-                } else if modifiers == Modifiers::CTRL | Modifiers::SHIFT {
-                    text_cursor = seek_next(text_cursor, text);
-                    text_selection_end = text_cursor;
-
-                    (true, true, false, TextInputAction::None)
-                } else {
-                    (false, true, false, TextInputAction::None)
                 }
-            }
-
-            Inputs::KB_X => {
-                if modifiers == Modifiers::CTRL {
-                    if text_selection_start != text_selection_end {
-                        let start = usize::min(text_selection_start, text_selection_end);
-                        let end = usize::max(text_selection_start, text_selection_end);
-
-                        let s = &text[start..end];
-                        inner_ctrl.set_clipboard_text(s);
-
-                        text.try_splice(start, end - start, "").unwrap();
-
-                        text_cursor = start;
-                        text_selection_start = text_cursor;
-                        text_selection_end = text_cursor;
 
-                        (true, true, false, TextInputAction::None)
-                    } else {
-                        (true, true, false, TextInputAction::None)
+                InputEvent::Char(c) => {
+                    // Control characters are either already handled as key
+                    // presses (backspace, enter, ...), or are generated by
+                    // platforms for shortcuts (e.g. Ctrl+A), and should never be
+                    // inserted.
+                    if c.is_control() {
+                        continue;
                     }
-                } else {
-                    (false, true, false, TextInputAction::None)
-                }
-            }
 
-            Inputs::KB_C => {
-                if modifiers == Modifiers::CTRL {
-                    if text_selection_start != text_selection_end {
-                        let start = usize::min(text_selection_start, text_selection_end);
-                        let end = usize::max(text_selection_start, text_selection_end);
-
-                        let s = &text[start..end];
-                        inner_ctrl.set_clipboard_text(s);
-
-                        (true, true, false, TextInputAction::None)
+                    let c = if let Some(fmc) = filter_map_callback {
+                        match fmc(c) {
+                            Some(c) => c,
+                            None => continue,
+                        }
                     } else {
-                        (true, true, false, TextInputAction::None)
-                    }
-                } else {
-                    (false, true, false, TextInputAction::None)
+                        c
+                    };
+
+                    let mut buf = [0; 4];
+                    changed |= insert(
+                        text,
+                        c.encode_utf8(&mut buf),
+                        &mut text_cursor,
+                        &mut text_selection_start,
+                        &mut text_selection_end,
+                    );
                 }
-            }
-
-            Inputs::KB_V => {
-                if modifiers == Modifiers::CTRL {
-                    // start and end can be the same index here, in which
-                    // case the splice will not remove anything, only insert
-                    // stuff from the clipboard. If they are not the same,
-                    // the selected text gets replaced.
-                    let start = usize::min(text_selection_start, text_selection_end);
-                    let end = usize::max(text_selection_start, text_selection_end);
-
-                    let s = inner_ctrl.get_clipboard_text();
-                    let _ = text.try_splice(start, end - start, &s);
-
-                    text_cursor += s.len();
-                    text_selection_start = text_cursor;
-                    text_selection_end = text_cursor;
-
-                    (true, true, false, TextInputAction::None)
-                } else {
-                    (false, true, false, TextInputAction::None)
-                }
-            }
-
-            Inputs::KB_ENTER => {
-                inner_ctrl.set_active(false);
-                deactivated_from_kb = true;
-
-                (true, false, false, TextInputAction::Submit)
-            }
-
-            Inputs::KB_ESCAPE => {
-                inner_ctrl.set_active(false);
-                deactivated_from_kb = true;
-
-                (true, false, false, TextInputAction::Cancel)
-            }
-
-            _ => (false, true, false, TextInputAction::None),
-        };
 
-        if handled {
-            (active, changed, action)
-        } else {
-            // TODO(yan): @Correctness If we missed frames, this structure
-            // of handling inputs drops inputs received characters. Oh well.
-            if text_selection_start != text_selection_end {
-                let start = usize::min(text_selection_start, text_selection_end);
-                let end = usize::max(text_selection_start, text_selection_end);
-
-                let _ = text.try_splice(start, end - start, &received_characters);
-
-                text_cursor = start + received_characters.len();
-                text_selection_start = text_cursor;
-                text_selection_end = text_cursor;
-            } else if text_cursor == text.len() {
-                let _ = text.try_extend(&received_characters);
-
-                text_cursor = text.len();
-                text_selection_start = text_cursor;
-                text_selection_end = text_cursor;
-            } else {
-                let _ = text.try_splice(text_cursor, 0, &received_characters);
-
-                text_cursor += received_characters.len();
-                text_selection_start = text_cursor;
-                text_selection_end = text_cursor;
+                _ => (),
             }
-
-            (true, true, TextInputAction::None)
         }
-    } else if hovered && inputs_pressed == Inputs::MB_LEFT {
+    } else if hovered && inputs_pressed.contains(Inputs::MB_LEFT) {
         inner_ctrl.set_active(true);
         text_cursor = text.len();
         text_selection_start = text_cursor;
         text_selection_end = text_cursor;
 
-        (true, false, TextInputAction::None)
-    } else {
-        (active_orig, false, TextInputAction::None)
-    };
+        active = true;
+    }
 
     let mut state = cast_state_mut(inner_ctrl.state_mut());
     state.text_cursor = text_cursor;
     state.text_selection_start = text_selection_start;
     state.text_selection_end = text_selection_end;
-    if active {
+    state.autocomplete_open = autocomplete_open;
+    // Only open autocomplete when we become active, or when the text is
+    // edited, so that it doesn't open again right after a candidate is picked.
+    if active && (!active_orig || changed) {
         state.autocomplete_open = AUTOCOMPLETE_OPEN;
     }
 
     if active {
         inner_ctrl.request_want_capture_keyboard();
+        inner_ctrl.request_want_text_input();
     }
 
     if let Some(result_callback) = result_callback {
@@ -619,32 +690,28 @@ where
             text,
             Align::Center,
             Align::Center,
+            direction,
             text_color,
         );
     } else {
-        inner_ctrl.draw_text(text, Align::Center, Align::Center, Wrap::None, text_color);
+        inner_ctrl.draw_text(
+            text,
+            Align::Center,
+            Align::Center,
+            Wrap::None,
+            direction,
+            text_color,
+        );
     }
 
     let mut changed_from_autocomplete = false;
     if autocomplete_open == AUTOCOMPLETE_OPEN && autocomplete.len() > 0 {
-        let mut results: ArrayVec<&str, 20> = ArrayVec::new();
-
-        // TODO(yan): Ignore case (but don't allocate!).
-        // TODO(yan): Fuzzy string matching and sorting by score.
-        for candidate in autocomplete {
-            let text_str: &str = text.deref();
-            let candidate_str: &str = candidate.deref();
-
-            if candidate_str.contains(text_str) {
-                results.push(candidate_str);
-            }
-
-            if results.is_full() {
-                break;
-            }
-        }
+        let mut results: ArrayVec<usize, 20> = ArrayVec::new();
+        fuzzy_search(text.deref(), autocomplete, &mut results);
 
         if results.len() > 0 {
+            inner_ctrl.close_on_outside_click();
+
             let overlay_rect = {
                 const OVERLAY_SPACING: f32 = 5.0;
 
@@ -704,8 +771,10 @@ where
             ctrl.set_draw_self_border_color(theme.text_input_border_color_active);
             ctrl.set_draw_self_background_color(theme.text_input_background_color_active);
 
-            for (i, result) in results.into_iter().enumerate() {
-                if button(frame, cast_u32(i), result) {
+            for (i, candidate_index) in results.into_iter().enumerate() {
+                let result: &str = autocomplete[candidate_index].deref();
+
+                if fuzzy_candidate_button(frame, cast_u32(i), text.deref(), result, false, theme) {
                     text.truncate(0);
                     let _ = text.try_extend(result);
 
@@ -719,12 +788,8 @@ where
         }
     }
 
-    // TODO(yan): @Cleanup @Hack We have to track the open state of our
-    // autocomplete dropdown manually, because we can't rely on us being active
-    // after we render the overlay with the autcomplete choices, as those
-    // buttons can take away the focus from us. the better way of doing this may
-    // be to tell the button we don't want it to steal focus from us, in which
-    // case we could rely on our own active state. This would help dropdown too.
+    // Picking a candidate closes the autocomplete, and so does leaving with
+    // the keyboard.
     if changed_from_autocomplete || deactivated_from_kb {
         let state = cast_state_mut(frame.ctrl_state_mut());
         state.autocomplete_open = AUTOCOMPLETE_CLOSED;
@@ -736,6 +801,65 @@ where
     changed || changed_from_autocomplete
 }
 
+// Collects the indices of the best fuzzy matching candidates into results,
+// sorted by score. Earlier candidates win ties. Also used by the command
+// palette.
+pub(crate) fn fuzzy_search<D: Deref<Target = str>, const N: usize>(
+    needle: &str,
+    candidates: &[D],
+    results: &mut ArrayVec<usize, N>,
+) {
+    let mut scored: ArrayVec<(usize, i32), N> = ArrayVec::new();
+
+    for (i, candidate) in candidates.iter().enumerate() {
+        if let Some(score) = fuzzy_match(needle, candidate.deref()) {
+            if !scored.is_full() {
+                scored.push((i, score));
+            } else if let Some(worst) = scored.iter_mut().min_by_key(|(_, score)| *score) {
+                if score > worst.1 {
+                    *worst = (i, score);
+                }
+            }
+        }
+    }
+
+    // NB: Sorting by index too keeps the sort stable without allocating.
+    scored.sort_unstable_by_key(|&(i, score)| (-score, i));
+
+    results.clear();
+    results.extend(scored.into_iter().map(|(i, _)| i));
+}
+
+// A button for a candidate found by fuzzy_search, with the characters
+// matching the needle highlighted. Also used by the command palette.
+pub(crate) fn fuzzy_candidate_button<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    needle: &str,
+    candidate: &str,
+    toggled: bool,
+    theme: &Theme,
+) -> bool {
+    // Matched characters past the capacity aren't highlighted.
+    let mut highlighted: ArrayVec<usize, 64> = ArrayVec::new();
+    fuzzy_match_with_positions(needle, candidate, |position| {
+        let _ = highlighted.try_push(position);
+    });
+
+    do_button(
+        frame,
+        id,
+        candidate,
+        Some((&highlighted, theme.text_input_overlay_match_text_color)),
+        None,
+        None,
+        toggled,
+        &ButtonOptions::default(),
+        theme,
+    )
+    .clicked
+}
+
 const AUTOCOMPLETE_CLOSED: u32 = 0;
 const AUTOCOMPLETE_OPEN: u32 = 1;
 
@@ -766,6 +890,7 @@ fn draw<A: Allocator + Clone>(
     text: &str,
     halign: Align,
     valign: Align,
+    direction: Direction,
     color: u32,
 ) {
     let state = cast_state(ctrl.state());
@@ -789,7 +914,7 @@ fn draw<A: Allocator + Clone>(
     // TODO(yan): @Memory If the allocator is a bump allocator, we
     // potentially prevent it from reclaiming memory if draw_primitives
     // grow.
-    let mut lines: Vec<Line, _> = Vec::new_in(ctrl.allocator().clone());
+    let mut lines: Vec<Line, _> = Vec::new_in(ctrl.temp_allocator());
 
     let mut line_range = 0..0;
     let mut line_width = 0.0;
@@ -855,17 +980,35 @@ fn draw<A: Allocator + Clone>(
     for line in &lines {
         let line_slice = &text[line.range.clone()];
 
-        position_x = match halign {
-            Align::Start => 0.0,
-            Align::Center => (available_width - line.width) / 2.0,
-            Align::End => available_width - line.width,
+        // For right-to-left text, alignment is mirrored and glyphs are
+        // positioned leftwards from the right edge of the line, same as in
+        // Ctrl::draw_text.
+        let line_x = match (direction, halign) {
+            (Direction::Ltr, Align::Start) | (Direction::Rtl, Align::End) => 0.0,
+            (_, Align::Center) => (available_width - line.width) / 2.0,
+            (Direction::Ltr, Align::End) | (Direction::Rtl, Align::Start) => {
+                available_width - line.width
+            }
         };
+        let mut line_offset_x = 0.0;
 
-        for (i, c) in line_slice.chars().enumerate() {
+        for (i, c) in line_slice.char_indices() {
             // Reborrow font_atlas, so that the globally borrowed one is
             // released and we can call Ctrl::draw_rect.
             let font_atlas = ctrl.font_atlas();
             let glyph_info = font_atlas.glyph_info(c);
+            let advance_width = glyph_info.advance_width;
+
+            // The cursor before a character is at its left edge for
+            // left-to-right text, and at its right edge for right-to-left.
+            let (position_x, cursor_x) = match direction {
+                Direction::Ltr => (line_x + line_offset_x, line_x + line_offset_x),
+                Direction::Rtl => {
+                    let x = line_x + line.width - line_offset_x - advance_width;
+                    (x, x + advance_width)
+                }
+            };
+            line_offset_x += advance_width;
 
             let position = Vec2::new(position_x, position_y);
             let rect = glyph_info.rect + position + Vec2::y(line_metrics.ascent);
@@ -874,7 +1017,7 @@ fn draw<A: Allocator + Clone>(
             if text_position == text_cursor {
                 ctrl.draw_rect(
                     Rect::new(
-                        position_x,
+                        cursor_x,
                         position_y,
                         1.0,
                         line_metrics.ascent - line_metrics.descent,
@@ -888,7 +1031,7 @@ fn draw<A: Allocator + Clone>(
 
             if text_position >= text_selection_start && text_position <= text_selection_end {
                 let r = Rect::new(
-                    position.x,
+                    cursor_x,
                     position_y,
                     0.0,
                     line_metrics.ascent - line_metrics.descent,
@@ -905,11 +1048,15 @@ fn draw<A: Allocator + Clone>(
             // sense here? We also do it later, when translating to the
             // low-level draw list, but we could have less things to
             // translate.
+            let rect = ctrl.snap_text_rect(rect);
             ctrl.draw_rect(rect, glyph_info.atlas_rect, color, font_atlas_texture_id);
-
-            position_x += glyph_info.advance_width;
         }
 
+        // After the last character, the cursor is at the end of the line.
+        position_x = match direction {
+            Direction::Ltr => line_x + line.width,
+            Direction::Rtl => line_x,
+        };
         position_y += line_metrics.new_line_size;
     }
 
@@ -927,10 +1074,15 @@ fn draw<A: Allocator + Clone>(
     }
 
     if !cursor_drawn {
+        let width = font_size / 2.0;
+        let x = match direction {
+            Direction::Ltr => position_x,
+            Direction::Rtl => position_x - width,
+        };
         let rect = Rect::new(
-            position_x,
+            x,
             position_y - line_metrics.ascent + line_metrics.descent,
-            font_size / 2.0,
+            width,
             line_metrics.ascent - line_metrics.descent,
         );
 
@@ -938,6 +1090,41 @@ fn draw<A: Allocator + Clone>(
     }
 }
 
+// Inserts s at the cursor, replacing the selection, if any. Returns whether the
+// text changed.
+fn insert<T: TextStorage>(
+    text: &mut T,
+    s: &str,
+    text_cursor: &mut usize,
+    text_selection_start: &mut usize,
+    text_selection_end: &mut usize,
+) -> bool {
+    // start and end can be the same index here, in which case the splice will
+    // not remove anything, only insert. If they are not the same, the selected
+    // text gets replaced.
+    let start = usize::min(*text_selection_start, *text_selection_end);
+    let end = usize::max(*text_selection_start, *text_selection_end);
+
+    let result = if start == end && *text_cursor == text.len() {
+        text.try_extend(s)
+    } else if start == end {
+        text.try_splice(*text_cursor, 0, s)
+    } else {
+        text.try_splice(start, end - start, s)
+    };
+
+    match result {
+        Ok(()) => {
+            *text_cursor = if start == end { *text_cursor } else { start } + s.len();
+            *text_selection_start = *text_cursor;
+            *text_selection_end = *text_cursor;
+
+            true
+        }
+        Err(_) => false,
+    }
+}
+
 fn seek_prev(index: usize, text: &str) -> usize {
     debug_assert!(index <= text.len());
     text.floor_char_boundary(index.saturating_sub(1))
@@ -953,3 +1140,169 @@ fn seek_next(index: usize, text: &str) -> usize {
         index
     }
 }
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use arrayvec::ArrayString;
+
+    use super::*;
+    use crate::core::{KeyRepeat, Ui};
+    use crate::fixtures::new_ui;
+
+    fn build(ui: &mut Ui<Global>, text: &mut ArrayString<64>) {
+        let mut frame = ui.begin_frame();
+        text_input(&mut frame, 0, text, "Label");
+        ui.end_frame();
+    }
+
+    fn build_rtl(ui: &mut Ui<Global>, text: &mut ArrayString<64>) {
+        let mut frame = ui.begin_frame();
+        text_input_with_direction(&mut frame, 0, text, "Label", Direction::Rtl);
+        ui.end_frame();
+    }
+
+    fn press_rtl(
+        ui: &mut Ui<Global>,
+        text: &mut ArrayString<64>,
+        input: Inputs,
+        modifiers: Modifiers,
+    ) {
+        ui.set_modifiers(modifiers);
+        ui.press_inputs(input);
+        build_rtl(ui, text);
+        ui.release_inputs(input);
+        ui.set_modifiers(Modifiers::NONE);
+    }
+
+    #[test]
+    fn test_text_input_held_backspace_repeats() {
+        let mut ui = new_ui();
+        // Repeats on every frame after the press.
+        ui.set_key_repeat(Some(KeyRepeat {
+            delay: 0.0,
+            interval: 0.0,
+        }));
+        let mut text = ArrayString::from("hello").unwrap();
+
+        build(&mut ui, &mut text);
+        ui.set_cursor_position(700.0, 10.0);
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(&mut ui, &mut text);
+        ui.release_inputs(Inputs::MB_LEFT);
+        build(&mut ui, &mut text);
+
+        ui.press_inputs(Inputs::KB_END);
+        ui.press_inputs(Inputs::KB_BACKSPACE);
+        build(&mut ui, &mut text);
+        assert_eq!(&text, "hell");
+
+        build(&mut ui, &mut text);
+        build(&mut ui, &mut text);
+        assert_eq!(&text, "he");
+
+        ui.release_inputs(Inputs::KB_BACKSPACE);
+        build(&mut ui, &mut text);
+        build(&mut ui, &mut text);
+        assert_eq!(&text, "he");
+    }
+
+    #[test]
+    fn test_text_input_capture_flags_across_frames() {
+        let mut ui = new_ui();
+        let mut text = ArrayString::new();
+
+        // First frame lays out the control, so that it can be hovered.
+        build(&mut ui, &mut text);
+        assert!(!ui.want_capture_keyboard());
+        assert!(!ui.want_text_input());
+
+        ui.set_cursor_position(700.0, 10.0);
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(&mut ui, &mut text);
+        assert!(ui.want_capture_keyboard());
+        assert!(ui.want_text_input());
+
+        ui.release_inputs(Inputs::MB_LEFT);
+        ui.send_character('a');
+        build(&mut ui, &mut text);
+        assert!(ui.want_capture_keyboard());
+        assert!(ui.want_text_input());
+
+        ui.press_inputs(Inputs::KB_ENTER);
+        ui.send_character('b');
+        build(&mut ui, &mut text);
+        assert!(!ui.want_capture_keyboard());
+        assert!(!ui.want_text_input());
+        assert_eq!(text.as_str(), "a");
+    }
+
+    #[test]
+    fn test_text_input_rtl_moves_cursor_in_visual_order() {
+        let mut ui = new_ui();
+        // Alef, bet, gimel, two bytes each.
+        let mut text = ArrayString::from("אבג").unwrap();
+
+        // Activating puts the cursor at the end, i.e. visually on the left.
+        build_rtl(&mut ui, &mut text);
+        ui.set_cursor_position(700.0, 10.0);
+        press_rtl(&mut ui, &mut text, Inputs::MB_LEFT, Modifiers::NONE);
+
+        // Right arrow moves towards the start of the text.
+        press_rtl(&mut ui, &mut text, Inputs::KB_RIGHT_ARROW, Modifiers::NONE);
+        ui.send_character('x');
+        build_rtl(&mut ui, &mut text);
+        assert_eq!(text.as_str(), "אבxג");
+
+        // Home goes to the start, and Shift+Left selects towards the end.
+        press_rtl(&mut ui, &mut text, Inputs::KB_HOME, Modifiers::NONE);
+        press_rtl(&mut ui, &mut text, Inputs::KB_LEFT_ARROW, Modifiers::SHIFT);
+        ui.send_character('y');
+        build_rtl(&mut ui, &mut text);
+        assert_eq!(text.as_str(), "yבxג");
+
+        press_rtl(&mut ui, &mut text, Inputs::KB_END, Modifiers::NONE);
+        press_rtl(&mut ui, &mut text, Inputs::KB_RIGHT_ARROW, Modifiers::NONE);
+        press_rtl(&mut ui, &mut text, Inputs::KB_RIGHT_ARROW, Modifiers::NONE);
+        ui.send_character('z');
+        build_rtl(&mut ui, &mut text);
+        assert_eq!(text.as_str(), "yבzxג");
+
+        // The Emacs shortcuts still move in logical order.
+        press_rtl(&mut ui, &mut text, Inputs::KB_F, Modifiers::CTRL);
+        ui.send_character('w');
+        build_rtl(&mut ui, &mut text);
+        assert_eq!(text.as_str(), "yבzxwג");
+    }
+
+    #[test]
+    fn test_text_input_autocomplete_closes_on_outside_click() {
+        let mut ui = new_ui();
+        let mut text: ArrayString<64> = ArrayString::new();
+
+        let mut build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame();
+            text_input_with_autocomplete(&mut frame, 0, &mut text, "Label", &["apple", "apricot"]);
+            ui.end_frame();
+
+            ui.ctrl_count()
+        };
+
+        let closed_ctrl_count = build(&mut ui);
+
+        // Activating the text input opens the autocomplete next frame.
+        ui.set_cursor_position(700.0, 10.0);
+        ui.press_inputs(Inputs::MB_LEFT);
+        build(&mut ui);
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert!(build(&mut ui) > closed_ctrl_count);
+        assert!(build(&mut ui) > closed_ctrl_count);
+
+        ui.set_cursor_position(100.0, 500.0);
+        ui.press_inputs(Inputs::MB_LEFT);
+        assert_eq!(build(&mut ui), closed_ctrl_count);
+        ui.release_inputs(Inputs::MB_LEFT);
+        assert_eq!(build(&mut ui), closed_ctrl_count);
+    }
+}
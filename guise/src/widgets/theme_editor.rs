@@ -0,0 +1,110 @@
+use core::alloc::Allocator;
+
+use crate::core::{with_theme_fields, Frame, Layout, Theme};
+use crate::widgets::float_slider::float_slider_with_speed_min_max_precision;
+use crate::widgets::int_slider::int4_slider_with_speed_min_max;
+use crate::widgets::panel::{begin_panel_with_layout_fit_height_options, PanelOptions};
+
+/// Builds rows for editing every field of the theme, grouped into collapsible
+/// sections per widget family. Colors are edited as RGBA channels, metrics as
+/// floats. Returns whether any field changed.
+///
+/// The editor itself is always built with the default theme, so that it stays
+/// usable no matter what the edited theme looks like.
+pub fn theme_editor<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    theme: &mut Theme,
+) -> bool {
+    let options = PanelOptions {
+        collapsible: true,
+        ..PanelOptions::default()
+    };
+
+    macro_rules! edit_fields {
+        ($(
+            $section:literal {
+                colors: [$($color:ident),* $(,)?],
+                metrics: [$($metric:ident),* $(,)?] $(,)?
+            }
+        )*) => {{
+            let mut changed = false;
+            $(
+                let section_id = frame.id($section);
+                if let Some((panel, _)) = begin_panel_with_layout_fit_height_options(
+                    frame,
+                    section_id,
+                    "100%",
+                    $section,
+                    Layout::Vertical,
+                    &options,
+                ) {
+                    $(
+                        let field_id = frame.id(stringify!($color));
+                        changed |= color_row(frame, field_id, &mut theme.$color, stringify!($color));
+                    )*
+                    $(
+                        let field_id = frame.id(stringify!($metric));
+                        changed |= metric_row(frame, field_id, &mut theme.$metric, stringify!($metric));
+                    )*
+
+                    panel.end(frame);
+                }
+            )*
+
+            changed
+        }};
+    }
+
+    frame.push_id(id);
+    frame.push_theme(&Theme::DEFAULT);
+    let changed = with_theme_fields!(edit_fields);
+    frame.pop_theme();
+    frame.pop_id();
+
+    changed
+}
+
+fn color_row<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut u32,
+    label: &str,
+) -> bool {
+    let mut channels = value.to_be_bytes().map(i32::from);
+    if int4_slider_with_speed_min_max(frame, id, &mut channels, label, 1.0, 0, 255) {
+        *value = u32::from_be_bytes(channels.map(|channel| channel.clamp(0, 255) as u8));
+        true
+    } else {
+        false
+    }
+}
+
+fn metric_row<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    value: &mut f32,
+    label: &str,
+) -> bool {
+    float_slider_with_speed_min_max_precision(frame, id, value, label, 0.1, 0.0, f32::MAX, 2)
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use super::*;
+    use crate::fixtures::new_ui;
+
+    #[test]
+    fn test_theme_editor_builds_all_sections() {
+        let mut ui = new_ui();
+
+        let mut theme = Theme::LIGHT;
+        for _ in 0..2 {
+            let mut frame = ui.begin_frame();
+            assert!(!theme_editor(&mut frame, 0, &mut theme));
+            ui.end_frame();
+        }
+
+        assert_eq!(theme, Theme::LIGHT);
+    }
+}
@@ -1,11 +1,13 @@
 use core::alloc::Allocator;
 
-use crate::core::{Align, CtrlFlags, Frame, Layout, Rect, Wrap};
-use crate::widgets::theme::Theme;
+use crate::core::{Align, Ctrl, CtrlFlags, Direction, Frame, Layout, Rect, Theme, Vec2, Wrap};
 
+/// Shows a text tooltip next to the cursor this frame. To show a tooltip only
+/// after a control has been hovered for a while, use
+/// [`tooltip_for_last_ctrl`].
 #[inline]
 pub fn tooltip<A: Allocator + Clone>(frame: &mut Frame<A>, id: u32, text: &str) {
-    tooltip_with_theme(frame, id, text, &Theme::DEFAULT)
+    frame.with_theme(|frame, theme| tooltip_with_theme(frame, id, text, theme))
 }
 
 pub fn tooltip_with_theme<A: Allocator + Clone>(
@@ -14,42 +16,209 @@ pub fn tooltip_with_theme<A: Allocator + Clone>(
     text: &str,
     theme: &Theme,
 ) {
+    // Padding is not set, because there's no child controls, and the text
+    // layout computes uses its own inset.
+    let mut ctrl = begin_tooltip(frame, id, 0.0, theme);
+    ctrl.draw_text_inset_and_extend_content_rect(
+        text,
+        // Horizontal aligns don't make much sense with text tooltips.
+        Align::Start,
+        // Vertical align does not make sense with shrunk-to-fit controls.
+        Align::Start,
+        Wrap::Word,
+        Direction::Ltr,
+        theme.text_tooltip_text_color,
+        theme.text_tooltip_border + theme.text_tooltip_padding,
+    );
+
+    end_tooltip(frame);
+}
+
+/// Shows a tooltip with arbitrary contents, once the last control has been
+/// hovered for longer than the theme's tooltip delay. The contents are built
+/// by the closure inside the tooltip, which resizes to fit them. Returns
+/// whether the tooltip was shown.
+///
+/// The delay is measured with the frame time passed to
+/// [`crate::Ui::begin_frame_with_delta_time`]. Without it, the tooltip shows
+/// right away.
+#[inline]
+pub fn tooltip_for_last_ctrl<A, F>(frame: &mut Frame<A>, id: u32, add_contents: F) -> bool
+where
+    A: Allocator + Clone,
+    F: FnOnce(&mut Frame<A>),
+{
+    // NB: The contents are built after the theme is returned to the frame, so
+    // that they can use widgets taking the theme from the frame.
+    let due = frame.with_theme(|frame, theme| begin_last_ctrl_tooltip(frame, id, theme));
+    if due {
+        add_contents(frame);
+        end_tooltip(frame);
+    }
+
+    due
+}
+
+pub fn tooltip_for_last_ctrl_with_theme<A, F>(
+    frame: &mut Frame<A>,
+    id: u32,
+    add_contents: F,
+    theme: &Theme,
+) -> bool
+where
+    A: Allocator + Clone,
+    F: FnOnce(&mut Frame<A>),
+{
+    let due = begin_last_ctrl_tooltip(frame, id, theme);
+    if due {
+        add_contents(frame);
+        end_tooltip(frame);
+    }
+
+    due
+}
+
+// Begins the tooltip for the last control, if it is due. The tooltip must then
+// be ended with end_tooltip.
+fn begin_last_ctrl_tooltip<A: Allocator + Clone>(
+    frame: &mut Frame<A>,
+    id: u32,
+    theme: &Theme,
+) -> bool {
+    if !last_ctrl_tooltip_due(frame, theme) {
+        return false;
+    }
+
+    begin_tooltip(frame, id, theme.text_tooltip_padding, theme);
+
+    true
+}
+
+// Whether the last control has been hovered for long enough to show its
+// tooltip.
+pub(crate) fn last_ctrl_tooltip_due<A: Allocator + Clone>(frame: &Frame<A>, theme: &Theme) -> bool {
+    match frame.last_ctrl_hover_duration() {
+        Some(hover_duration) => {
+            frame.delta_time().is_none() || hover_duration >= theme.tooltip_delay
+        }
+        None => false,
+    }
+}
+
+fn begin_tooltip<'f, A: Allocator + Clone>(
+    frame: &'f mut Frame<A>,
+    id: u32,
+    padding: f32,
+    theme: &Theme,
+) -> Ctrl<'f, A> {
     frame.begin_overlay();
 
-    let parent_size = frame.ctrl_inner_size();
+    let window_size = frame.window_size();
     let cursor_position = frame.cursor_position();
+    let max_width = f32::min(theme.tooltip_max_width, window_size.x);
+    let border = theme.text_tooltip_border;
 
     let mut ctrl = frame.push_ctrl(id);
+
+    // NB: The tooltip is resized to fit its contents during layout, so we only
+    // know its size from last frame. New tooltips are placed as if they were
+    // empty, and flip away from the window edges a frame late.
+    let size = if ctrl.is_new() {
+        Vec2::ZERO
+    } else {
+        ctrl.inner_size() + 2.0 * Vec2::new(border + padding, border + padding)
+    };
+
+    let offset = theme.tooltip_cursor_offset;
+    let x = tooltip_position(cursor_position.x, size.x, window_size.x, offset);
+    let y = tooltip_position(cursor_position.y, size.y, window_size.y, offset);
+
+    // The tooltip doesn't capture hover, so that it never steals it from the
+    // control it describes.
     ctrl.set_flags(CtrlFlags::ALL_RESIZE_TO_FIT);
     ctrl.set_layout(Layout::Vertical);
     ctrl.set_rect(Rect::new(
-        cursor_position.x,
-        cursor_position.y,
-        // Set to parent size so that the text layout can happen with realistic
-        // clipping. This rect is however resized to fit the text during the
-        // layout phase.
-        f32::max(0.0, parent_size.x - cursor_position.x),
-        parent_size.y,
+        x,
+        y,
+        // Set to maximum size so that the content layout can happen with
+        // realistic clipping. This rect is however resized to fit the contents
+        // during the layout phase.
+        max_width,
+        window_size.y,
     ));
-    // Padding is not set, because there's no child controls, and the text
-    // layout computes uses its own inset.
-    ctrl.set_border(theme.text_tooltip_border);
+    ctrl.set_padding(padding);
+    ctrl.set_border(border);
 
     ctrl.set_draw_self(true);
     ctrl.set_draw_self_border_color(theme.text_tooltip_border_color);
     ctrl.set_draw_self_background_color(theme.text_tooltip_background_color);
-    ctrl.draw_text_inset_and_extend_content_rect(
-        text,
-        // Horizontal aligns don't make much sense with text tooltips.
-        Align::Start,
-        // Vertical align does not make sense with shrunk-to-fit controls.
-        Align::Start,
-        Wrap::Word,
-        theme.text_tooltip_text_color,
-        theme.text_tooltip_border + theme.text_tooltip_padding,
-    );
 
-    frame.pop_ctrl();
+    ctrl
+}
 
+fn end_tooltip<A: Allocator + Clone>(frame: &mut Frame<A>) {
+    frame.pop_ctrl();
     frame.end_overlay();
 }
+
+// Places the tooltip after the cursor, if it fits in the window, or flips it
+// before the cursor otherwise. If it doesn't fit either way, it is clamped to
+// stay as visible as possible.
+pub(crate) fn tooltip_position(cursor: f32, size: f32, window_size: f32, offset: f32) -> f32 {
+    let position = if cursor + offset + size <= window_size {
+        cursor + offset
+    } else {
+        cursor - offset - size
+    };
+
+    position.clamp(0.0, f32::max(0.0, window_size - size))
+}
+
+#[cfg(all(test, feature = "runtime_rasterize"))]
+mod tests {
+    use alloc::alloc::Global;
+
+    use super::*;
+    use crate::core::Ui;
+    use crate::fixtures::new_ui;
+    use crate::widgets::button::button;
+    use crate::widgets::text::text;
+
+    #[test]
+    fn test_tooltip_position_flips_and_clamps() {
+        assert_eq!(tooltip_position(100.0, 50.0, 800.0, 10.0), 110.0);
+        assert_eq!(tooltip_position(780.0, 50.0, 800.0, 10.0), 720.0);
+        assert_eq!(tooltip_position(30.0, 790.0, 800.0, 10.0), 0.0);
+        assert_eq!(tooltip_position(780.0, 900.0, 800.0, 10.0), 0.0);
+    }
+
+    #[test]
+    fn test_tooltip_for_last_ctrl_shows_after_delay() {
+        let mut ui = new_ui();
+        ui.set_cursor_position(10.0, 10.0);
+
+        let build = |ui: &mut Ui<Global>| {
+            let mut frame = ui.begin_frame_with_delta_time(0.2);
+            button(&mut frame, 0, "Button");
+            let shown = tooltip_for_last_ctrl(&mut frame, 0, |frame| {
+                text(frame, 0, "Tooltip");
+            });
+            ui.end_frame();
+
+            shown
+        };
+
+        // The first frame lays out the button, the second starts hovering.
+        assert!(!build(&mut ui));
+        assert!(!build(&mut ui));
+        assert!(!build(&mut ui));
+        assert!(!build(&mut ui));
+        assert!(build(&mut ui));
+
+        // Hovering something else restarts the delay.
+        ui.set_cursor_position(10.0, 500.0);
+        assert!(!build(&mut ui));
+        ui.set_cursor_position(10.0, 10.0);
+        assert!(!build(&mut ui));
+    }
+}
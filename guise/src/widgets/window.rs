@@ -2,12 +2,24 @@ use core::alloc::Allocator;
 use core::fmt::Debug;
 use core::mem;
 
-use crate::core::{Ctrl, CtrlFlags, CtrlState, Frame, Inputs, Layout, Rect, Vec2};
+use crate::core::{
+    Ctrl,
+    CtrlFlags,
+    CtrlState,
+    CursorIcon,
+    Frame,
+    Inputs,
+    Layout,
+    Rect,
+    Theme,
+    Vec2,
+};
 use crate::widgets::size::{Position, Size};
-use crate::widgets::theme::Theme;
 
-const FLAGS: CtrlFlags =
-    CtrlFlags::CAPTURE_SCROLL | CtrlFlags::CAPTURE_HOVER | CtrlFlags::CAPTURE_ACTIVE;
+const FLAGS: CtrlFlags = CtrlFlags::CAPTURE_SCROLL
+    | CtrlFlags::CAPTURE_HOVER
+    | CtrlFlags::CAPTURE_ACTIVE
+    | CtrlFlags::SHOW_SCROLLBARS;
 
 const ACTIVITY_NONE: u8 = 0;
 const ACTIVITY_MOVE: u8 = 1;
@@ -60,19 +72,21 @@ where
     let width = width.try_into().unwrap();
     let height = height.try_into().unwrap();
 
-    let ctrl = do_window_and_pay_bills(
-        frame,
-        id,
-        x,
-        y,
-        width,
-        height,
-        Layout::Vertical,
-        &DEFAULT_OPTIONS,
-        &Theme::DEFAULT,
-    );
+    frame.with_theme(|frame, theme| {
+        do_window_and_pay_bills(
+            frame,
+            id,
+            x,
+            y,
+            width,
+            height,
+            Layout::Vertical,
+            &DEFAULT_OPTIONS,
+            theme,
+        );
+    });
 
-    Some((Window(false), ctrl))
+    Some((Window(false), frame.ctrl()))
 }
 
 #[inline]
@@ -101,19 +115,21 @@ where
     let width = width.try_into().unwrap();
     let height = height.try_into().unwrap();
 
-    let ctrl = do_window_and_pay_bills(
-        frame,
-        id,
-        x,
-        y,
-        width,
-        height,
-        layout,
-        &DEFAULT_OPTIONS,
-        &Theme::DEFAULT,
-    );
+    frame.with_theme(|frame, theme| {
+        do_window_and_pay_bills(
+            frame,
+            id,
+            x,
+            y,
+            width,
+            height,
+            layout,
+            &DEFAULT_OPTIONS,
+            theme,
+        );
+    });
 
-    Some((Window(false), ctrl))
+    Some((Window(false), frame.ctrl()))
 }
 
 #[inline]
@@ -143,19 +159,11 @@ where
     let width = width.try_into().unwrap();
     let height = height.try_into().unwrap();
 
-    let ctrl = do_window_and_pay_bills(
-        frame,
-        id,
-        x,
-        y,
-        width,
-        height,
-        layout,
-        options,
-        &Theme::DEFAULT,
-    );
+    frame.with_theme(|frame, theme| {
+        do_window_and_pay_bills(frame, id, x, y, width, height, layout, options, theme);
+    });
 
-    Some((Window(false), ctrl))
+    Some((Window(false), frame.ctrl()))
 }
 
 #[inline]
@@ -204,7 +212,7 @@ impl Window {
 
 impl Drop for Window {
     fn drop(&mut self) {
-        debug_assert!(self.0)
+        debug_assert!(self.0, "Window dropped without calling Window::end")
     }
 }
 
@@ -222,10 +230,13 @@ fn do_window_and_pay_bills<'f, A: Allocator + Clone>(
     let texture_id = frame.font_atlas_texture_id();
     let parent_size = frame.ctrl_inner_size();
     let cursor_position = frame.cursor_position();
-    let lmb_pressed = frame.inputs_pressed() == Inputs::MB_LEFT;
-    let lmb_released = frame.inputs_released() == Inputs::MB_LEFT;
+    let lmb_pressed = frame.inputs_pressed().contains(Inputs::MB_LEFT);
+    let lmb_released = frame.inputs_released().contains(Inputs::MB_LEFT);
+    let size_flags = width.layout_flags(true) | height.layout_flags(false);
 
     let mut ctrl = frame.push_ctrl(id);
+    // Position and size can be restored on the next run.
+    ctrl.mark_persistent();
     let hovered = ctrl.is_hovered();
 
     let state = cast_state(ctrl.state());
@@ -260,7 +271,21 @@ fn do_window_and_pay_bills<'f, A: Allocator + Clone>(
         )
     };
 
-    ctrl.set_flags(FLAGS);
+    // NB: Fill and content sizes are computed by the layout, so resizing
+    // doesn't affect them, and they start from last frame's size.
+    let last_size = ctrl.absolute_rect().size();
+    if size_flags.intersects(CtrlFlags::FILL_HORIZONTAL | CtrlFlags::RESIZE_TO_FIT_HORIZONTAL) {
+        width = last_size.x;
+    }
+    if size_flags.intersects(CtrlFlags::FILL_VERTICAL | CtrlFlags::RESIZE_TO_FIT_VERTICAL) {
+        height = last_size.y;
+    }
+
+    ctrl.set_flags(if layout == Layout::Horizontal {
+        FLAGS | size_flags | CtrlFlags::CAPTURE_SCROLL_HORIZONTAL
+    } else {
+        FLAGS | size_flags
+    });
     ctrl.set_layout(layout);
     ctrl.set_rect(Rect::new(x, y, width, height));
     ctrl.set_padding(theme.window_padding);
@@ -354,6 +379,17 @@ fn do_window_and_pay_bills<'f, A: Allocator + Clone>(
         state.activity_start_cursor_y = cursor_position.y;
     }
 
+    // Use the activity updated by this frame's input, so that the cursor icon
+    // changes as soon as dragging starts or stops.
+    let current_activity = cast_state(ctrl.state()).activity;
+    if current_activity == ACTIVITY_MOVE {
+        ctrl.set_cursor_icon(CursorIcon::Move);
+    } else if current_activity == ACTIVITY_RESIZE
+        || options.resizable && hovered && resize_handle_hovered
+    {
+        ctrl.set_cursor_icon(CursorIcon::ResizeNWSE);
+    }
+
     if hovered && lmb_pressed || options.open_on_top && !initialized {
         ctrl.set_active(true);
     }
@@ -383,6 +419,10 @@ fn do_window_and_pay_bills<'f, A: Allocator + Clone>(
     ctrl.set_draw_self_border_color(border_color);
     ctrl.set_draw_self_background_color(background_color);
 
+    ctrl.set_scrollbar_width(theme.scrollbar_width);
+    ctrl.set_scrollbar_track_color(theme.scrollbar_track_color);
+    ctrl.set_scrollbar_thumb_color(theme.scrollbar_thumb_color);
+
     if options.resizable {
         let offset_x = ctrl.scroll_offset_x();
         let offset_y = ctrl.scroll_offset_y();
@@ -23,21 +23,82 @@ pub struct Stats {
     pub frame_count: u64,
     pub frame_build_duration: Duration,
     pub frame_total_duration: Duration,
-    pub frame_draw_list_command_count: usize,
-    pub frame_draw_list_vertex_count: usize,
-    pub frame_draw_list_index_count: usize,
-    pub frame_ctrl_count: usize,
+    pub frame_stats: guise::FrameStats,
     pub want_capture_keyboard: bool,
     pub want_capture_mouse: bool,
 }
 
 pub const GRAPH_LEN: usize = 60;
 
+// Recognized by the platform, which draws its own content for it.
+pub const CUSTOM_DRAW_CALLBACK_ID: u64 = 1;
+
+pub const DND_ROW_LABELS: [&str; 5] = ["Apples", "Bananas", "Cherries", "Dates", "Elderberries"];
+
+// Fake commands for the command palette.
+pub const COMMANDS: [&str; 50] = [
+    "Open File",
+    "Open Window",
+    "Open Sidebar",
+    "Open Terminal",
+    "Open Panel",
+    "Open Settings",
+    "Open Theme",
+    "Open Grid",
+    "Open Minimap",
+    "Open Console",
+    "Close File",
+    "Close Window",
+    "Close Sidebar",
+    "Close Terminal",
+    "Close Panel",
+    "Close Settings",
+    "Close Theme",
+    "Close Grid",
+    "Close Minimap",
+    "Close Console",
+    "Toggle File",
+    "Toggle Window",
+    "Toggle Sidebar",
+    "Toggle Terminal",
+    "Toggle Panel",
+    "Toggle Settings",
+    "Toggle Theme",
+    "Toggle Grid",
+    "Toggle Minimap",
+    "Toggle Console",
+    "Reload File",
+    "Reload Window",
+    "Reload Sidebar",
+    "Reload Terminal",
+    "Reload Panel",
+    "Reload Settings",
+    "Reload Theme",
+    "Reload Grid",
+    "Reload Minimap",
+    "Reload Console",
+    "Reset File",
+    "Reset Window",
+    "Reset Sidebar",
+    "Reset Terminal",
+    "Reset Panel",
+    "Reset Settings",
+    "Reset Theme",
+    "Reset Grid",
+    "Reset Minimap",
+    "Reset Console",
+];
+
 pub struct State {
     pub button_click_count: u64,
+    pub reset_modal_open: bool,
+    pub command_palette_open: bool,
     pub text_input_submit_count: u64,
     pub text_input_cancel_count: u64,
     pub poll_platform_events: bool,
+    pub sdf_text: bool,
+    pub premultiplied_alpha: bool,
+    pub text_scale: f32,
     pub graph: [f32; GRAPH_LEN],
     pub graph_max: f32,
     pub graph_frame_build: [f32; GRAPH_LEN],
@@ -62,6 +123,15 @@ pub struct State {
     pub int4_value: [i32; 4],
     pub dropdown1_selected_option: Option<usize>,
     pub dropdown2_selected_option: Option<usize>,
+    pub combo_selected_option: Option<usize>,
+    pub dropdown_multi_selected_options: [bool; 7],
+    pub theme: guise::Theme,
+    pub theme_light: bool,
+    pub read_only: bool,
+    // Indices into DND_ROW_LABELS, in the order the rows are shown.
+    pub dnd_row_order: [usize; 5],
+    // Same as above, but for the reorderable list widget.
+    pub reorderable_list_order: [usize; 5],
 }
 
 pub fn draw_ui<A: Allocator + Clone>(
@@ -105,6 +175,53 @@ pub fn draw_ui<A: Allocator + Clone>(
                     "Poll Platform Events",
                 );
 
+                // The spinner only moves while frames are built continuously.
+                if state.poll_platform_events {
+                    guise::spinner(frame, line!(), 10.0);
+                }
+
+                guise::separator(frame, line!());
+
+                // Scaled text stays sharp with the SDF atlas, and gets blurry
+                // without it.
+                guise::checkbox(frame, line!(), &mut state.sdf_text, "SDF Text");
+                guise::checkbox(
+                    frame,
+                    line!(),
+                    &mut state.premultiplied_alpha,
+                    "Premultiplied Alpha",
+                );
+                guise::float_slider_with_speed_min_max_precision(
+                    frame,
+                    line!(),
+                    &mut state.text_scale,
+                    "Text Scale",
+                    0.01,
+                    0.5,
+                    4.0,
+                    2,
+                );
+
+                let text_scale = state.text_scale;
+                let line_height = frame
+                    .font_atlas()
+                    .font_horizontal_line_metrics()
+                    .new_line_size;
+                let width = frame.ctrl_inner_size().x;
+
+                let mut ctrl = frame.push_ctrl(line!());
+                ctrl.set_rect(guise::Rect::new(0.0, 0.0, width, text_scale * line_height));
+                ctrl.draw_text_scaled(
+                    "Scaled",
+                    guise::Align::Start,
+                    guise::Align::Center,
+                    guise::Wrap::None,
+                    guise::Direction::Ltr,
+                    0xffffffff,
+                    text_scale,
+                );
+                frame.pop_ctrl();
+
                 guise::separator(frame, line!());
 
                 guise::text(
@@ -128,14 +245,16 @@ pub fn draw_ui<A: Allocator + Clone>(
                     fmt!(
                         s,
                         "running time: {:.3}s\nframe count:  {}\nframe build time: {:.3}/{:.3}s \
-                         (current/max)\nframe total time: {:.3}s\nframe ctrl count: {}\nwant \
-                         capture keyboard {}\nwant capture mouse {}",
+                         (current/max)\nframe total time: {:.3}s\nframe ctrl count: {}\ndraw \
+                         cache hits/misses: {}/{}\nwant capture keyboard {}\nwant capture mouse {}",
                         time,
                         stats.frame_count,
                         stats.frame_build_duration.as_secs_f32(),
                         state.graph_frame_build_max,
                         stats.frame_total_duration.as_secs_f32(),
-                        stats.frame_ctrl_count,
+                        stats.frame_stats.ctrl_count,
+                        stats.frame_stats.draw_cache_hit_count,
+                        stats.frame_stats.draw_cache_miss_count,
                         stats.want_capture_keyboard,
                         stats.want_capture_mouse,
                     ),
@@ -153,6 +272,7 @@ pub fn draw_ui<A: Allocator + Clone>(
                 guise::Layout::Vertical,
                 &guise::PanelOptions {
                     draw_border: false,
+                    collapsible: true,
                     ..guise::PanelOptions::default()
                 },
             ) {
@@ -164,12 +284,70 @@ pub fn draw_ui<A: Allocator + Clone>(
                     state.button_click_count += 1;
                 }
 
+                if guise::button(frame, line!(), "A button with rich tooltip") {
+                    state.button_click_count += 1;
+                }
+                guise::tooltip_for_last_ctrl(frame, line!(), |frame| {
+                    guise::text(frame, line!(), "Tooltips can contain any controls.");
+                    guise::separator(frame, line!());
+                    guise::text(
+                        frame,
+                        line!(),
+                        fmt!(s, "Clicked {} times", state.button_click_count),
+                    );
+                });
+
+                if guise::button(frame, line!(), "A button with toast") {
+                    state.button_click_count += 1;
+                    frame.toast(
+                        fmt!(s, "Clicked {} times", state.button_click_count),
+                        guise::ToastKind::Info,
+                    );
+                }
+
+                if guise::button(frame, line!(), "Reset clicks") {
+                    state.reset_modal_open = true;
+                }
+                if let Some((modal, _)) =
+                    guise::begin_modal(frame, line!(), "Reset clicks?", &mut state.reset_modal_open)
+                {
+                    if guise::button(frame, line!(), "Yes") {
+                        state.button_click_count = 0;
+                        state.reset_modal_open = false;
+                    }
+                    if guise::button(frame, line!(), "No") {
+                        state.reset_modal_open = false;
+                    }
+
+                    modal.end(frame);
+                }
+
+                let command_palette_shortcut =
+                    guise::Shortcut::new(guise::Inputs::KB_P, guise::Modifiers::CTRL);
+                if guise::button(frame, line!(), "Command palette (Ctrl+P)") {
+                    state.command_palette_open = true;
+                }
+                if frame.shortcut_pressed(command_palette_shortcut) {
+                    state.command_palette_open = !state.command_palette_open;
+                }
+                if let Some(index) = guise::command_palette(
+                    frame,
+                    line!(),
+                    &mut state.command_palette_open,
+                    &COMMANDS,
+                ) {
+                    frame.toast(
+                        fmt!(s, "Ran \"{}\"", COMMANDS[index]),
+                        guise::ToastKind::Info,
+                    );
+                }
+
                 for i in 0..=10 {
-                    frame.push_id_namespace(i);
+                    frame.push_id(i);
                     if guise::button(frame, line!(), fmt!(s, "Button {}", i)) {
                         state.button_click_count += 1;
                     }
-                    frame.pop_id_namespace();
+                    frame.pop_id();
                 }
 
                 panel.end(frame);
@@ -178,6 +356,28 @@ pub fn draw_ui<A: Allocator + Clone>(
             panel.end(frame);
         }
 
+        if let Some((panel, _)) = guise::begin_panel_with_layout_fit_height_options(
+            frame,
+            line!(),
+            "100%",
+            "A wide row of buttons (Shift+wheel scrolls horizontally)",
+            guise::Layout::Horizontal,
+            &guise::PanelOptions {
+                draw_border: false,
+                ..guise::PanelOptions::default()
+            },
+        ) {
+            for i in 0..50 {
+                frame.push_id(i);
+                if guise::image_button_with_tooltip(frame, line!(), 0, fmt!(s, "Button {}", i)) {
+                    state.button_click_count += 1;
+                }
+                frame.pop_id();
+            }
+
+            panel.end(frame);
+        }
+
         guise::separator(frame, line!());
 
         if let Some((panel, _)) = guise::begin_panel_with_layout_options(
@@ -193,7 +393,7 @@ pub fn draw_ui<A: Allocator + Clone>(
                 ..guise::PanelOptions::default()
             },
         ) {
-            if let Some((panel, _)) = guise::begin_panel_with_layout_options(
+            if let Some((panel, mut panel_ctrl)) = guise::begin_panel_with_layout_options(
                 frame,
                 line!(),
                 "50%",
@@ -205,6 +405,9 @@ pub fn draw_ui<A: Allocator + Clone>(
                     ..guise::PanelOptions::default()
                 },
             ) {
+                // Lots of text that rarely changes, so reuse its geometry.
+                panel_ctrl.set_flags(panel_ctrl.flags() | guise::CtrlFlags::CACHE_DRAW);
+
                 for i in 0..3 {
                     let i = i * 3;
                     let j = i + 1;
@@ -215,6 +418,15 @@ pub fn draw_ui<A: Allocator + Clone>(
                     guise::text_with_align(frame, k, TEXT, guise::Align::End);
                 }
 
+                guise::selectable_text(frame, 9, TEXT);
+                guise::text_with_align_direction(
+                    frame,
+                    10,
+                    TEXT,
+                    guise::Align::Start,
+                    guise::Direction::Rtl,
+                );
+
                 panel.end(frame);
             }
 
@@ -237,9 +449,9 @@ pub fn draw_ui<A: Allocator + Clone>(
 
                 let current_idx = stats.frame_count as usize % GRAPH_LEN;
                 let current_frame_build_duration = stats.frame_build_duration.as_secs_f32();
-                let current_draw_list_command_count = stats.frame_draw_list_command_count;
-                let current_draw_list_vertex_count = stats.frame_draw_list_vertex_count;
-                let current_draw_list_index_count = stats.frame_draw_list_index_count;
+                let current_draw_list_command_count = stats.frame_stats.command_count;
+                let current_draw_list_vertex_count = stats.frame_stats.vertex_count;
+                let current_draw_list_index_count = stats.frame_stats.index_count;
 
                 state.graph_frame_build[current_idx] = current_frame_build_duration;
                 state.graph_command_count[current_idx] = current_draw_list_command_count;
@@ -364,7 +576,7 @@ pub fn draw_ui<A: Allocator + Clone>(
         window.end(frame);
     }
 
-    if let Some((window, mut window_ctrl)) = guise::begin_window_with_layout(
+    if let Some((window, window_ctrl)) = guise::begin_window_with_layout(
         frame,
         line!(),
         "1%",
@@ -373,13 +585,26 @@ pub fn draw_ui<A: Allocator + Clone>(
         "48%",
         guise::Layout::Free,
     ) {
+        // The atlas keeps its aspect ratio as the window resizes, letterboxed
+        // in the window.
         let inner_size = window_ctrl.inner_size();
-        window_ctrl.draw_rect(
-            guise::Rect::new(0.0, 0.0, inner_size.x, inner_size.y),
-            guise::Rect::ONE,
-            0xffffffff,
+        let (atlas_width, atlas_height) = frame.font_atlas().image_size();
+
+        let mut atlas_ctrl = frame.push_ctrl(line!());
+        // NB: The layout applies the aspect ratio, so we draw with the size
+        // from last frame.
+        let atlas_size = atlas_ctrl.inner_size();
+        atlas_ctrl.set_rect(guise::Rect::new(0.0, 0.0, inner_size.x, inner_size.y));
+        atlas_ctrl.set_padding(0.0);
+        atlas_ctrl.set_border(0.0);
+        atlas_ctrl.set_margin(0.0);
+        atlas_ctrl.set_aspect_ratio(f32::from(atlas_width) / f32::from(atlas_height));
+
+        atlas_ctrl.draw_image(
+            guise::Rect::new(0.0, 0.0, atlas_size.x, atlas_size.y),
             texture_id,
         );
+        frame.pop_ctrl();
 
         if let Some((window, mut window_ctrl)) = guise::begin_window_with_layout_options(
             frame,
@@ -399,6 +624,7 @@ pub fn draw_ui<A: Allocator + Clone>(
                 guise::Align::Center,
                 guise::Align::Center,
                 guise::Wrap::Word,
+                guise::Direction::Ltr,
                 0x907030ff,
             );
 
@@ -423,6 +649,7 @@ pub fn draw_ui<A: Allocator + Clone>(
                 guise::Align::Center,
                 guise::Align::Center,
                 guise::Wrap::Word,
+                guise::Direction::Ltr,
                 0x907030ff,
             );
 
@@ -448,6 +675,7 @@ pub fn draw_ui<A: Allocator + Clone>(
                 guise::Align::Center,
                 guise::Align::Center,
                 guise::Wrap::Word,
+                guise::Direction::Ltr,
                 0x907030ff,
             );
             window.end(frame);
@@ -461,6 +689,7 @@ pub fn draw_ui<A: Allocator + Clone>(
                 guise::Align::Center,
                 guise::Align::Center,
                 guise::Wrap::Word,
+                guise::Direction::Ltr,
                 0x907030ff,
             );
 
@@ -471,6 +700,12 @@ pub fn draw_ui<A: Allocator + Clone>(
     }
 
     if let Some((window, _)) = guise::begin_window(frame, line!(), "1%", "51%", "39%", "48%") {
+        guise::checkbox(frame, line!(), &mut state.read_only, "Read-only");
+        guise::separator(frame, line!());
+
+        // Everything below is disabled together, when read-only.
+        frame.push_disabled(state.read_only);
+
         guise::text(frame, line!(), "Dropdowns");
 
         static DAMAGE_TYPES: &[&str] = &[
@@ -497,6 +732,20 @@ pub fn draw_ui<A: Allocator + Clone>(
             DAMAGE_TYPES,
             &mut state.dropdown2_selected_option,
         );
+        guise::combo(
+            frame,
+            line!(),
+            "Damage Type (type to filter)",
+            DAMAGE_TYPES,
+            &mut state.combo_selected_option,
+        );
+        guise::dropdown_multi(
+            frame,
+            line!(),
+            "Damage Types (multiple)",
+            DAMAGE_TYPES,
+            &mut state.dropdown_multi_selected_options,
+        );
 
         guise::separator(frame, line!());
         guise::text(frame, line!(), "Text inputs");
@@ -541,6 +790,16 @@ pub fn draw_ui<A: Allocator + Clone>(
             state.text_input_heap.clear();
             state.text_input_inline.clear();
         }
+        if let Some(menu) = guise::begin_context_menu(frame, line!()) {
+            if guise::button(frame, line!(), "Clear heap") {
+                state.text_input_heap.clear();
+            }
+            if guise::button(frame, line!(), "Clear inline") {
+                state.text_input_inline.clear();
+            }
+
+            menu.end(frame);
+        }
 
         guise::separator(frame, line!());
         guise::text(frame, line!(), "Sliders");
@@ -607,9 +866,33 @@ pub fn draw_ui<A: Allocator + Clone>(
             100,
         );
 
+        frame.pop_disabled();
+
         window.end(frame);
     }
 
+    // The theme is pushed for the whole window, so that the plain widget
+    // functions pick it up.
+    frame.push_theme(&state.theme);
+    if let Some((window, _)) = guise::begin_window(frame, line!(), "60%", "50%", 400.0, "48%") {
+        if guise::toggle_button(frame, line!(), &mut state.theme_light, "Light theme") {
+            state.theme = if state.theme_light {
+                guise::Theme::LIGHT
+            } else {
+                guise::Theme::DEFAULT
+            };
+        }
+
+        guise::button(frame, line!(), "Themed button");
+        guise::float_slider(frame, line!(), &mut state.float_value, "Themed slider");
+        guise::separator(frame, line!());
+
+        guise::theme_editor(frame, line!(), &mut state.theme);
+
+        window.end(frame);
+    }
+    frame.pop_theme();
+
     if let Some((window, _)) = guise::begin_window(frame, line!(), "1%", "1%", 350.0, 300.0) {
         if let Some((panel, _)) =
             guise::begin_panel_with_fit_height(frame, line!(), "100%", "RESIZE_TO_FIT test")
@@ -622,4 +905,150 @@ pub fn draw_ui<A: Allocator + Clone>(
         }
         window.end(frame);
     }
+
+    if let Some((window, _)) = guise::begin_window(frame, line!(), "20%", "1%", 250.0, 220.0) {
+        guise::text(frame, line!(), "Drag the rows to reorder them");
+
+        let width = frame.ctrl_inner_size().x - 4.0;
+        let dragging_payload = frame.dragging_payload();
+        let mut dropped = None;
+
+        frame.push_id(line!());
+        for (row, &item) in state.dnd_row_order.iter().enumerate() {
+            // Keyed by the item, so that rows keep their state when reordered.
+            let mut ctrl = frame.push_ctrl(item as u32);
+            ctrl.set_flags(guise::CtrlFlags::CAPTURE_HOVER);
+            ctrl.set_layout(guise::Layout::Vertical);
+            ctrl.set_rect(guise::Rect::new(0.0, 0.0, width, 30.0));
+            ctrl.set_border(1.0);
+            ctrl.set_margin(2.0);
+
+            let dragged = ctrl.drag_source(item as u64);
+            if let Some(payload) = ctrl.drop_target() {
+                dropped = Some((payload as usize, row));
+            }
+
+            let background_color = if dragged {
+                0x20202080
+            } else if dragging_payload.is_some() && ctrl.is_hovered() {
+                0x505080ff
+            } else {
+                0x303030ff
+            };
+
+            ctrl.set_draw_self(true);
+            ctrl.set_draw_self_border_color(0x808080ff);
+            ctrl.set_draw_self_background_color(background_color);
+            ctrl.draw_text(
+                DND_ROW_LABELS[item],
+                guise::Align::Center,
+                guise::Align::Center,
+                guise::Wrap::Word,
+                guise::Direction::Ltr,
+                0xffffffff,
+            );
+
+            frame.pop_ctrl();
+        }
+        frame.pop_id();
+
+        if let Some((item, row)) = dropped {
+            let from = state.dnd_row_order.iter().position(|&i| i == item).unwrap();
+            if from < row {
+                state.dnd_row_order.copy_within(from + 1..=row, from);
+            } else {
+                state.dnd_row_order.copy_within(row..from, row + 1);
+            }
+            state.dnd_row_order[row] = item;
+        }
+
+        // The ghost of the row and its label follow the cursor in the overlay.
+        guise::drag_ghost(frame, line!());
+        if let Some(payload) = dragging_payload {
+            guise::tooltip(frame, line!(), DND_ROW_LABELS[payload as usize]);
+        }
+
+        window.end(frame);
+    }
+
+    if let Some((window, _)) = guise::begin_window(frame, line!(), "20%", "35%", 250.0, 200.0) {
+        guise::text(frame, line!(), "Drag the handles to reorder");
+
+        guise::reorderable_list(
+            frame,
+            line!(),
+            &mut state.reorderable_list_order,
+            |&item| item as u32,
+            |frame, _, &mut item| {
+                guise::button(frame, line!(), DND_ROW_LABELS[item]);
+            },
+        );
+
+        window.end(frame);
+    }
+
+    if let Some((window, _)) = guise::begin_window(frame, line!(), "40%", "20%", 300.0, 250.0) {
+        const LOG_ROW_COUNT: usize = 10_000;
+        const LOG_ROW_HEIGHT: f32 = 16.0;
+
+        // Only the visible rows are built, the rest is replaced by spacers, so
+        // that building the frame takes the same time anywhere in the log.
+        let width = frame.ctrl_inner_size().x;
+        let visible_range = frame.visible_range(LOG_ROW_HEIGHT, LOG_ROW_COUNT);
+
+        let mut ctrl = frame.push_ctrl(line!());
+        ctrl.set_rect(guise::Rect::new(
+            0.0,
+            0.0,
+            width,
+            visible_range.start as f32 * LOG_ROW_HEIGHT,
+        ));
+        frame.pop_ctrl();
+
+        let mut s: ArrayString<64> = ArrayString::new();
+
+        frame.push_id(line!());
+        for row in visible_range.clone() {
+            let mut ctrl = frame.push_ctrl(row as u32);
+            ctrl.set_rect(guise::Rect::new(0.0, 0.0, width, LOG_ROW_HEIGHT));
+            ctrl.draw_text(
+                fmt!(s, "[{row:05}] Something happened"),
+                guise::Align::Start,
+                guise::Align::Center,
+                guise::Wrap::None,
+                guise::Direction::Ltr,
+                0xffffffff,
+            );
+            frame.pop_ctrl();
+        }
+        frame.pop_id();
+
+        let mut ctrl = frame.push_ctrl(line!());
+        ctrl.set_rect(guise::Rect::new(
+            0.0,
+            0.0,
+            width,
+            (LOG_ROW_COUNT - visible_range.end) as f32 * LOG_ROW_HEIGHT,
+        ));
+        frame.pop_ctrl();
+
+        window.end(frame);
+    }
+
+    if let Some((window, _)) = guise::begin_window(frame, line!(), "60%", "60%", 250.0, 200.0) {
+        guise::text(frame, line!(), "Drawn by the platform:");
+
+        let size = frame.ctrl_inner_size();
+        let height = f32::max(0.0, size.y - 30.0);
+
+        let mut ctrl = frame.push_ctrl(line!());
+        ctrl.set_rect(guise::Rect::new(0.0, 0.0, size.x, height));
+        ctrl.draw_callback(
+            CUSTOM_DRAW_CALLBACK_ID,
+            guise::Rect::new(0.0, 0.0, size.x, height),
+        );
+        frame.pop_ctrl();
+
+        window.end(frame);
+    }
 }
@@ -0,0 +1,31 @@
+#version 330 core
+
+uniform sampler2D u_texture;
+// Whether the texture contains signed distance fields in its alpha channel,
+// with 0.5 on the glyph outline.
+uniform bool u_sdf;
+uniform bool u_premultiplied_alpha;
+
+in vec2 v_tex_coord;
+in vec4 v_color;
+
+out vec4 f_color;
+
+void main() {
+    vec4 texel = texture(u_texture, v_tex_coord);
+
+    if (u_sdf) {
+        float distance = texel.a;
+        float width = 0.5 * fwidth(distance);
+        float coverage = smoothstep(0.5 - width, 0.5 + width, distance);
+
+        // Premultiplied vertex colors are scaled by coverage in all channels.
+        if (u_premultiplied_alpha) {
+            f_color = v_color * coverage;
+        } else {
+            f_color = vec4(v_color.rgb, v_color.a * coverage);
+        }
+    } else {
+        f_color = v_color * texel;
+    }
+}
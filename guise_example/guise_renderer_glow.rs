@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::mem;
+
+use glow::HasContext as _;
+
+struct TextureResource {
+    texture: glow::Texture,
+    sdf: bool,
+}
+
+/// An OpenGL 3.3 renderer, for integrating into existing GL engines. Unlike
+/// the wgpu renderer, it doesn't own any render targets and draws into
+/// whatever framebuffer is bound. The GL objects must be deleted with
+/// [`Renderer::destroy`] while the context is still current.
+pub struct Renderer {
+    program: glow::Program,
+    u_matrix: Option<glow::UniformLocation>,
+    u_texture: Option<glow::UniformLocation>,
+    u_sdf: Option<glow::UniformLocation>,
+    u_premultiplied_alpha: Option<glow::UniformLocation>,
+    vertex_array: glow::VertexArray,
+    vertex_buffer: glow::Buffer,
+    index_buffer: glow::Buffer,
+    sampler: glow::Sampler,
+    sampler_nearest: glow::Sampler,
+
+    texture_resources: HashMap<u64, TextureResource>,
+    texture_resources_next_id: u64,
+}
+
+impl Renderer {
+    pub fn new(gl: &glow::Context) -> Self {
+        const VERTEX_SHADER_SOURCE: &str = include_str!("guise_renderer_glow.vert");
+        const FRAGMENT_SHADER_SOURCE: &str = include_str!("guise_renderer_glow.frag");
+
+        unsafe {
+            let program = gl.create_program().expect("Failed to create program");
+
+            let shaders = [
+                (glow::VERTEX_SHADER, VERTEX_SHADER_SOURCE),
+                (glow::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE),
+            ]
+            .map(|(shader_type, shader_source)| {
+                let shader = gl
+                    .create_shader(shader_type)
+                    .expect("Failed to create shader");
+                gl.shader_source(shader, shader_source);
+                gl.compile_shader(shader);
+                if !gl.get_shader_compile_status(shader) {
+                    panic!(
+                        "Failed to compile shader: {}",
+                        gl.get_shader_info_log(shader)
+                    );
+                }
+
+                gl.attach_shader(program, shader);
+                shader
+            });
+
+            gl.link_program(program);
+            if !gl.get_program_link_status(program) {
+                panic!(
+                    "Failed to link program: {}",
+                    gl.get_program_info_log(program)
+                );
+            }
+
+            for shader in shaders {
+                gl.detach_shader(program, shader);
+                gl.delete_shader(shader);
+            }
+
+            let u_matrix = gl.get_uniform_location(program, "u_matrix");
+            let u_texture = gl.get_uniform_location(program, "u_texture");
+            let u_sdf = gl.get_uniform_location(program, "u_sdf");
+            let u_premultiplied_alpha = gl.get_uniform_location(program, "u_premultiplied_alpha");
+
+            let vertex_array = gl
+                .create_vertex_array()
+                .expect("Failed to create vertex array");
+            let vertex_buffer = gl.create_buffer().expect("Failed to create buffer");
+            let index_buffer = gl.create_buffer().expect("Failed to create buffer");
+
+            // The element array buffer binding is part of the vertex array
+            // state, so it only has to be bound once here.
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(index_buffer));
+
+            let stride = mem::size_of::<guise::Vertex>() as i32;
+
+            // a_position
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, stride, 0);
+            // a_tex_coord
+            gl.enable_vertex_attrib_array(1);
+            gl.vertex_attrib_pointer_f32(1, 2, glow::FLOAT, false, stride, 8);
+            // a_color, read as an integer and unpacked in the shader.
+            gl.enable_vertex_attrib_array(2);
+            gl.vertex_attrib_pointer_i32(2, 1, glow::UNSIGNED_INT, stride, 16);
+
+            gl.bind_vertex_array(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+
+            let create_sampler = |filter: u32| {
+                let sampler = gl.create_sampler().expect("Failed to create sampler");
+                gl.sampler_parameter_i32(sampler, glow::TEXTURE_MIN_FILTER, filter as i32);
+                gl.sampler_parameter_i32(sampler, glow::TEXTURE_MAG_FILTER, filter as i32);
+                gl.sampler_parameter_i32(sampler, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+                gl.sampler_parameter_i32(sampler, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+
+                sampler
+            };
+
+            // Used for commands that request nearest sampling, e.g. pixel art.
+            let sampler = create_sampler(glow::LINEAR);
+            let sampler_nearest = create_sampler(glow::NEAREST);
+
+            Self {
+                program,
+                u_matrix,
+                u_texture,
+                u_sdf,
+                u_premultiplied_alpha,
+                vertex_array,
+                vertex_buffer,
+                index_buffer,
+                sampler,
+                sampler_nearest,
+
+                texture_resources: HashMap::new(),
+                texture_resources_next_id: 0,
+            }
+        }
+    }
+
+    pub fn destroy(&mut self, gl: &glow::Context) {
+        unsafe {
+            for (_, texture_resource) in self.texture_resources.drain() {
+                gl.delete_texture(texture_resource.texture);
+            }
+
+            gl.delete_sampler(self.sampler);
+            gl.delete_sampler(self.sampler_nearest);
+            gl.delete_buffer(self.index_buffer);
+            gl.delete_buffer(self.vertex_buffer);
+            gl.delete_vertex_array(self.vertex_array);
+            gl.delete_program(self.program);
+        }
+    }
+
+    pub fn add_texture_rgba8_unorm(
+        &mut self,
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> u64 {
+        assert_eq!(data.len() % 4, 0);
+
+        let texture = unsafe {
+            let texture = gl.create_texture().expect("Failed to create texture");
+
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                Some(data),
+            );
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAX_LEVEL, 0);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            texture
+        };
+
+        let texture_id = self.texture_resources_next_id;
+        self.texture_resources_next_id += 1;
+
+        self.texture_resources.insert(texture_id, TextureResource {
+            texture,
+            sdf: false,
+        });
+
+        texture_id
+    }
+
+    /// Marks the texture as containing signed distance fields, e.g. for the
+    /// font atlas with [`guise::Ui::font_atlas_sdf`] set.
+    pub fn set_texture_sdf(&mut self, id: u64, sdf: bool) {
+        if let Some(texture_resource) = self.texture_resources.get_mut(&id) {
+            texture_resource.sdf = sdf;
+        }
+    }
+
+    pub fn remove_texture(&mut self, gl: &glow::Context, id: u64) {
+        if let Some(texture_resource) = self.texture_resources.remove(&id) {
+            unsafe { gl.delete_texture(texture_resource.texture) };
+        }
+    }
+
+    /// Draws the draw list into the bound framebuffer, without clearing it.
+    /// Callback commands (see [`guise::CommandKind::CALLBACK`]) call
+    /// `callback` with the user id and the scissor rect in GL window
+    /// coordinates (x, y, width, height), with y going up. The callback can
+    /// change any GL state, and the renderer sets up its own again after it
+    /// returns.
+    ///
+    /// Uses 32-bit indices, which need GL 3.x or OES_element_index_uint on
+    /// GLES 2. Older targets can use [`guise::Ui::draw_list_u16`] with
+    /// glDrawElementsBaseVertex instead.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        gl: &glow::Context,
+        viewport_physical_width: u32,
+        viewport_physical_height: u32,
+        viewport_scale: f32,
+        premultiplied_alpha: bool,
+        commands: &[guise::Command],
+        vertices: &[guise::Vertex],
+        indices: &[u32],
+        callback: &mut dyn FnMut(&glow::Context, u64, [i32; 4]),
+    ) {
+        if commands.is_empty() || vertices.is_empty() || indices.is_empty() {
+            return;
+        }
+
+        if viewport_physical_width == 0 || viewport_physical_height == 0 {
+            return;
+        }
+
+        let matrix = {
+            // Setup orthographic projection matrix.
+            let l = 0.0;
+            let r = viewport_physical_width as f32 / viewport_scale;
+            let t = 0.0;
+            let b = viewport_physical_height as f32 / viewport_scale;
+
+            #[rustfmt::skip]
+            let matrix = [
+                2.0 / (r - l)    , 0.0              , 0.0, 0.0,
+                0.0              , 2.0 / (t - b)    , 0.0, 0.0,
+                0.0              , 0.0              , 0.5, 0.0,
+                (r + l) / (l - r), (t + b) / (b - t), 0.5, 1.0,
+            ];
+
+            matrix
+        };
+
+        let vw = viewport_physical_width;
+        let vh = viewport_physical_height;
+
+        unsafe {
+            // TODO(yan): @Speed Orphan or persistently map the buffers.
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertex_buffer));
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                bytemuck::cast_slice(vertices),
+                glow::STREAM_DRAW,
+            );
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+
+            gl.bind_vertex_array(Some(self.vertex_array));
+            gl.buffer_data_u8_slice(
+                glow::ELEMENT_ARRAY_BUFFER,
+                bytemuck::cast_slice(indices),
+                glow::STREAM_DRAW,
+            );
+            gl.bind_vertex_array(None);
+
+            self.setup_state(gl, vw, vh, premultiplied_alpha, &matrix);
+
+            let mut sdf = false;
+            gl.uniform_1_i32(self.u_sdf.as_ref(), 0);
+
+            let mut consumed_index_count: u32 = 0;
+
+            for command in commands {
+                let Some([x, y, w, h]) =
+                    physical_scissor_rect(command.scissor_rect, viewport_scale, vw, vh)
+                else {
+                    consumed_index_count += command.index_count;
+                    continue;
+                };
+
+                if command.kind == guise::CommandKind::CALLBACK {
+                    gl.bind_vertex_array(None);
+                    gl.bind_sampler(0, None);
+                    gl.use_program(None);
+
+                    callback(gl, command.callback_user_id, [x, y, w, h]);
+
+                    self.setup_state(gl, vw, vh, premultiplied_alpha, &matrix);
+                    gl.uniform_1_i32(self.u_sdf.as_ref(), i32::from(sdf));
+
+                    continue;
+                }
+
+                let texture_resource = match self.texture_resources.get(&command.texture_id) {
+                    Some(texture_resource) => texture_resource,
+                    None => {
+                        log::error!("Missing texture {}", command.texture_id);
+                        consumed_index_count += command.index_count;
+                        continue;
+                    }
+                };
+
+                if texture_resource.sdf != sdf {
+                    sdf = texture_resource.sdf;
+                    gl.uniform_1_i32(self.u_sdf.as_ref(), i32::from(sdf));
+                }
+
+                let sampler = if command.sampling == guise::Sampling::NEAREST {
+                    self.sampler_nearest
+                } else {
+                    self.sampler
+                };
+
+                gl.scissor(x, y, w, h);
+                gl.bind_texture(glow::TEXTURE_2D, Some(texture_resource.texture));
+                gl.bind_sampler(0, Some(sampler));
+                gl.draw_elements(
+                    glow::TRIANGLES,
+                    command.index_count as i32,
+                    glow::UNSIGNED_INT,
+                    (consumed_index_count as usize * mem::size_of::<u32>()) as i32,
+                );
+
+                consumed_index_count += command.index_count;
+            }
+
+            gl.bind_vertex_array(None);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+            gl.bind_sampler(0, None);
+            gl.use_program(None);
+            gl.disable(glow::SCISSOR_TEST);
+            gl.disable(glow::BLEND);
+        }
+    }
+
+    unsafe fn setup_state(
+        &self,
+        gl: &glow::Context,
+        viewport_physical_width: u32,
+        viewport_physical_height: u32,
+        premultiplied_alpha: bool,
+        matrix: &[f32; 16],
+    ) {
+        // NB: Straight alpha is the default for guise, premultiplied alpha
+        // only differs in the source factor. See Ui::set_premultiplied_alpha.
+        let src_factor = if premultiplied_alpha {
+            glow::ONE
+        } else {
+            glow::SRC_ALPHA
+        };
+
+        gl.viewport(
+            0,
+            0,
+            viewport_physical_width as i32,
+            viewport_physical_height as i32,
+        );
+        gl.enable(glow::BLEND);
+        gl.blend_equation(glow::FUNC_ADD);
+        gl.blend_func_separate(
+            src_factor,
+            glow::ONE_MINUS_SRC_ALPHA,
+            src_factor,
+            glow::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.disable(glow::CULL_FACE);
+        gl.disable(glow::DEPTH_TEST);
+        gl.enable(glow::SCISSOR_TEST);
+
+        gl.use_program(Some(self.program));
+        gl.uniform_matrix_4_f32_slice(self.u_matrix.as_ref(), false, matrix);
+        gl.uniform_1_i32(self.u_texture.as_ref(), 0);
+        gl.uniform_1_i32(
+            self.u_premultiplied_alpha.as_ref(),
+            i32::from(premultiplied_alpha),
+        );
+
+        gl.active_texture(glow::TEXTURE0);
+        gl.bind_vertex_array(Some(self.vertex_array));
+    }
+}
+
+// NB: GL window coordinates start at the bottom left, so unlike in the wgpu
+// renderer, the scissor rect is flipped vertically.
+fn physical_scissor_rect(
+    scissor_rect: guise::Rect,
+    viewport_scale: f32,
+    viewport_physical_width: u32,
+    viewport_physical_height: u32,
+) -> Option<[i32; 4]> {
+    let x = f32::floor(viewport_scale * scissor_rect.x) as u32;
+    let y = f32::floor(viewport_scale * scissor_rect.y) as u32;
+    let w = f32::round(viewport_scale * scissor_rect.width) as u32;
+    let h = f32::round(viewport_scale * scissor_rect.height) as u32;
+
+    if w == 0 || h == 0 || x + w > viewport_physical_width || y + h > viewport_physical_height {
+        log::error!("Scissor rect ({x} {y} {w} {h}) invalid");
+        return None;
+    }
+
+    let y = viewport_physical_height - (y + h);
+
+    Some([x as i32, y as i32, w as i32, h as i32])
+}
@@ -0,0 +1,21 @@
+#version 330 core
+
+uniform mat4 u_matrix;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_tex_coord;
+layout(location = 2) in uint a_color;
+
+out vec2 v_tex_coord;
+out vec4 v_color;
+
+void main() {
+    v_tex_coord = a_tex_coord;
+    // Vertex colors are passed through as they are, like in the wgpu
+    // renderer. See Ui::set_linear_color_output for sRGB framebuffers.
+    v_color = vec4((a_color >> 24) & 0xffu,
+                   (a_color >> 16) & 0xffu,
+                   (a_color >> 8) & 0xffu,
+                   (a_color >> 0) & 0xffu) / 255.0;
+    gl_Position = u_matrix * vec4(a_position, 0.0, 1.0);
+}
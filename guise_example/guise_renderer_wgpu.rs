@@ -7,6 +7,8 @@ use wgpu::util::DeviceExt as _;
 
 struct TextureResource {
     bind_group: wgpu::BindGroup,
+    bind_group_nearest: wgpu::BindGroup,
+    sdf: bool,
 }
 
 #[repr(C)]
@@ -18,10 +20,14 @@ struct TransformUniforms {
 
 pub struct Renderer {
     sampler: wgpu::Sampler,
+    sampler_nearest: wgpu::Sampler,
     transform_uniform_buffer: wgpu::Buffer,
     transform_uniform_bind_group: wgpu::BindGroup,
     texture_bind_group_layout: wgpu::BindGroupLayout,
     render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_sdf: wgpu::RenderPipeline,
+    render_pipeline_premultiplied: wgpu::RenderPipeline,
+    render_pipeline_sdf_premultiplied: wgpu::RenderPipeline,
 
     texture_resources: HashMap<u64, TextureResource>,
     texture_resources_next_id: u64,
@@ -110,75 +116,95 @@ impl Renderer {
 
         // Setup render state: alpha-blending enabled, no face
         // culling, no depth testing
+        //
+        // NB: There are pipelines for both straight alpha, which is the default
+        // for guise, and premultiplied alpha, which only differ in the blend
+        // state's src_factor. See Ui::set_premultiplied_alpha.
+
+        let create_render_pipeline = |fragment_entry_point: &str, premultiplied_alpha: bool| {
+            let src_factor = if premultiplied_alpha {
+                wgpu::BlendFactor::One
+            } else {
+                wgpu::BlendFactor::SrcAlpha
+            };
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vs_main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: size_of::<guise::Vertex>(),
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[
-                        // a_position
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x2,
-                            offset: 0,
-                            shader_location: 0,
-                        },
-                        // a_tex_coord
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x2,
-                            offset: 8,
-                            shader_location: 1,
-                        },
-                        // a_color
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Uint32,
-                            offset: 16,
-                            shader_location: 2,
-                        },
-                    ],
-                }],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: render_attachment_format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        alpha: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                    }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-        });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: size_of::<guise::Vertex>(),
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[
+                            // a_position
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x2,
+                                offset: 0,
+                                shader_location: 0,
+                            },
+                            // a_tex_coord
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x2,
+                                offset: 8,
+                                shader_location: 1,
+                            },
+                            // a_color
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Uint32,
+                                offset: 16,
+                                shader_location: 2,
+                            },
+                        ],
+                    }],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: fragment_entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: render_attachment_format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor,
+                                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent {
+                                src_factor,
+                                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+
+        // Textures with signed distance fields, e.g. the font atlas in SDF
+        // mode, are drawn with a different fragment shader.
+        let render_pipeline = create_render_pipeline("fs_main", false);
+        let render_pipeline_sdf = create_render_pipeline("fs_main_sdf", false);
+        let render_pipeline_premultiplied = create_render_pipeline("fs_main", true);
+        let render_pipeline_sdf_premultiplied =
+            create_render_pipeline("fs_main_sdf_premultiplied", true);
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: None,
@@ -195,9 +221,29 @@ impl Renderer {
             border_color: None,
         });
 
+        // Used for commands that request nearest sampling, e.g. pixel art.
+        let sampler_nearest = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: None,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: f32::MAX,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        });
+
         Self {
             sampler,
+            sampler_nearest,
             render_pipeline,
+            render_pipeline_sdf,
+            render_pipeline_premultiplied,
+            render_pipeline_sdf_premultiplied,
             transform_uniform_buffer,
             transform_uniform_bind_group,
             texture_bind_group_layout,
@@ -252,37 +298,57 @@ impl Renderer {
             },
         );
 
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &self.texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(
-                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&self.sampler),
-                },
-            ],
-        });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let create_texture_bind_group = |sampler: &wgpu::Sampler| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &self.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            })
+        };
+
+        let texture_bind_group = create_texture_bind_group(&self.sampler);
+        let texture_bind_group_nearest = create_texture_bind_group(&self.sampler_nearest);
 
         let texture_id = self.texture_resources_next_id;
         self.texture_resources_next_id += 1;
 
         self.texture_resources.insert(texture_id, TextureResource {
             bind_group: texture_bind_group,
+            bind_group_nearest: texture_bind_group_nearest,
+            sdf: false,
         });
 
         texture_id
     }
 
+    /// Marks the texture as containing signed distance fields, e.g. for the
+    /// font atlas with [`guise::Ui::font_atlas_sdf`] set.
+    pub fn set_texture_sdf(&mut self, id: u64, sdf: bool) {
+        if let Some(texture_resource) = self.texture_resources.get_mut(&id) {
+            texture_resource.sdf = sdf;
+        }
+    }
+
     pub fn remove_texture(&mut self, id: u64) {
         self.texture_resources.remove(&id);
     }
 
+    /// Draws the draw list. Callback commands (see
+    /// [`guise::CommandKind::CALLBACK`]) end the current render pass and call
+    /// `callback` with the encoder, the color attachment, the user id and the
+    /// scissor rect in physical pixels (x, y, width, height). The callback
+    /// can record its own passes, and the renderer starts a new pass with its
+    /// own state after it returns.
     #[allow(clippy::too_many_arguments)]
     pub fn draw(
         &mut self,
@@ -294,9 +360,11 @@ impl Renderer {
         viewport_physical_width: u32,
         viewport_physical_height: u32,
         viewport_scale: f32,
+        premultiplied_alpha: bool,
         commands: &[guise::Command],
         vertices: &[guise::Vertex],
         indices: &[u32],
+        callback: &mut dyn FnMut(&mut wgpu::CommandEncoder, &wgpu::TextureView, u64, [u32; 4]),
     ) {
         if commands.is_empty() || vertices.is_empty() || indices.is_empty() {
             return;
@@ -343,60 +411,134 @@ impl Renderer {
             bytemuck::bytes_of(&transform),
         );
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: color_attachment,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(clear_color),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
-
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        render_pass.set_bind_group(0, &self.transform_uniform_bind_group, &[]);
+        let (render_pipeline, render_pipeline_sdf) = if premultiplied_alpha {
+            (
+                &self.render_pipeline_premultiplied,
+                &self.render_pipeline_sdf_premultiplied,
+            )
+        } else {
+            (&self.render_pipeline, &self.render_pipeline_sdf)
+        };
 
         let vw = viewport_physical_width;
         let vh = viewport_physical_height;
 
+        // Only the first pass clears, the passes after callbacks continue
+        // drawing over what is there.
+        let mut load = wgpu::LoadOp::Clear(clear_color);
         let mut consumed_index_count: u32 = 0;
-        for command in commands {
-            let x = f32::floor(viewport_scale * command.scissor_rect.x) as u32;
-            let y = f32::floor(viewport_scale * command.scissor_rect.y) as u32;
-            let w = f32::round(viewport_scale * command.scissor_rect.width) as u32;
-            let h = f32::round(viewport_scale * command.scissor_rect.height) as u32;
-
-            if w == 0 || h == 0 || x + w > vw || y + h > vh {
-                log::error!("Scissor rect ({x} {y} {w} {h}) invalid");
-                continue;
+        let mut commands = commands;
+
+        loop {
+            let callback_position = commands
+                .iter()
+                .position(|command| command.kind == guise::CommandKind::CALLBACK)
+                .unwrap_or(commands.len());
+            let (draw_commands, rest) = commands.split_at(callback_position);
+
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: color_attachment,
+                        resolve_target: None,
+                        ops: wgpu::Operations { load, store: true },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+
+                let mut sdf = false;
+                render_pass.set_pipeline(render_pipeline);
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.set_bind_group(0, &self.transform_uniform_bind_group, &[]);
+
+                for command in draw_commands {
+                    let Some([x, y, w, h]) =
+                        physical_scissor_rect(command.scissor_rect, viewport_scale, vw, vh)
+                    else {
+                        consumed_index_count += command.index_count;
+                        continue;
+                    };
+
+                    let texture_resource = match self.texture_resources.get(&command.texture_id) {
+                        Some(texture_resource) => texture_resource,
+                        None => {
+                            log::error!("Missing texture {}", command.texture_id);
+                            consumed_index_count += command.index_count;
+                            continue;
+                        }
+                    };
+
+                    if texture_resource.sdf != sdf {
+                        sdf = texture_resource.sdf;
+                        render_pass.set_pipeline(if sdf {
+                            render_pipeline_sdf
+                        } else {
+                            render_pipeline
+                        });
+                    }
+
+                    let bind_group = if command.sampling == guise::Sampling::NEAREST {
+                        &texture_resource.bind_group_nearest
+                    } else {
+                        &texture_resource.bind_group
+                    };
+
+                    render_pass.set_scissor_rect(x, y, w, h);
+                    render_pass.set_bind_group(1, bind_group, &[]);
+                    render_pass.draw_indexed(
+                        consumed_index_count..(consumed_index_count + command.index_count),
+                        command.base_vertex as i32,
+                        0..1,
+                    );
+
+                    consumed_index_count += command.index_count;
+                }
             }
 
-            let texture_resource = match self.texture_resources.get(&command.texture_id) {
-                Some(texture_resource) => texture_resource,
-                None => {
-                    log::error!("Missing texture {}", command.texture_id);
-                    continue;
+            load = wgpu::LoadOp::Load;
+
+            match rest.split_first() {
+                Some((callback_command, rest)) => {
+                    if let Some(scissor_rect) =
+                        physical_scissor_rect(callback_command.scissor_rect, viewport_scale, vw, vh)
+                    {
+                        callback(
+                            encoder,
+                            color_attachment,
+                            callback_command.callback_user_id,
+                            scissor_rect,
+                        );
+                    }
+
+                    commands = rest;
                 }
-            };
-
-            render_pass.set_scissor_rect(x, y, w, h);
-            render_pass.set_bind_group(1, &texture_resource.bind_group, &[]);
-            render_pass.draw_indexed(
-                consumed_index_count..(consumed_index_count + command.index_count),
-                0,
-                0..1,
-            );
-
-            consumed_index_count += command.index_count;
+                None => break,
+            }
         }
     }
 }
 
+fn physical_scissor_rect(
+    scissor_rect: guise::Rect,
+    viewport_scale: f32,
+    viewport_physical_width: u32,
+    viewport_physical_height: u32,
+) -> Option<[u32; 4]> {
+    let x = f32::floor(viewport_scale * scissor_rect.x) as u32;
+    let y = f32::floor(viewport_scale * scissor_rect.y) as u32;
+    let w = f32::round(viewport_scale * scissor_rect.width) as u32;
+    let h = f32::round(viewport_scale * scissor_rect.height) as u32;
+
+    if w == 0 || h == 0 || x + w > viewport_physical_width || y + h > viewport_physical_height {
+        log::error!("Scissor rect ({x} {y} {w} {h}) invalid");
+        return None;
+    }
+
+    Some([x, y, w, h])
+}
+
 fn size_of<T>() -> wgpu::BufferAddress {
     let size = mem::size_of::<T>();
     wgpu::BufferAddress::try_from(size)
@@ -24,6 +24,10 @@ fn vs_main(
 
     out.position  = u_transform.matrix * vec4<f32>(in_position, 0.0, 1.0);
     out.tex_coord = in_tex_coord;
+    // Vertex colors are passed through as they are. They are sRGB, which is
+    // right for non-sRGB surfaces like the Bgra8Unorm one in the example. For
+    // sRGB surfaces, have guise linearize them instead (see
+    // Ui::set_linear_color_output), rather than converting them here.
     out.color     = vec4<f32>(
         f32((in_color >> 24u) & mask) / 255.0,
         f32((in_color >> 16u) & mask) / 255.0,
@@ -38,3 +42,25 @@ fn vs_main(
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return in.color * textureSample(u_texture, u_sampler, in.tex_coord);
 }
+
+// For font atlases with signed distance fields. The alpha channel stores the
+// distance from the glyph outline, with 0.5 on the outline.
+@fragment
+fn fs_main_sdf(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = textureSample(u_texture, u_sampler, in.tex_coord).a;
+    let width = 0.5 * fwidth(distance);
+    let coverage = smoothstep(0.5 - width, 0.5 + width, distance);
+
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
+
+// Like fs_main_sdf, but the vertex colors are premultiplied, so coverage
+// scales all channels.
+@fragment
+fn fs_main_sdf_premultiplied(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = textureSample(u_texture, u_sampler, in.tex_coord).a;
+    let width = 0.5 * fwidth(distance);
+    let coverage = smoothstep(0.5 - width, 0.5 + width, distance);
+
+    return in.color * coverage;
+}
@@ -0,0 +1,396 @@
+#![feature(allocator_api)]
+
+#[path = "../../guise_example/guise_demo.rs"]
+mod demo;
+#[path = "../../guise_example/guise_renderer_glow.rs"]
+mod renderer_glow;
+
+use std::alloc::Global;
+use std::ffi::CString;
+use std::num::NonZeroU32;
+use std::ops::DerefMut;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use arrayvec::ArrayString;
+use glow::HasContext as _;
+use glutin::display::GetGlDisplay as _;
+use glutin::prelude::*;
+use glutin_winit::GlWindow as _;
+use raw_window_handle::HasRawWindowHandle as _;
+
+static CLIPBOARD: Mutex<Option<copypasta::ClipboardContext>> = Mutex::new(None);
+
+fn init_clipboard_or_not() {
+    let mut guard = CLIPBOARD.lock().unwrap();
+
+    if guard.is_none() {
+        let clipboard = copypasta::ClipboardContext::new().unwrap();
+        guard.replace(clipboard);
+    }
+}
+
+fn get_clipboard(text: &mut dyn guise::TextStorage) {
+    use copypasta::ClipboardProvider;
+
+    let mut guard = CLIPBOARD.lock().unwrap();
+    if let Some(c) = guard.deref_mut() {
+        if let Ok(s) = c.get_contents() {
+            let _ = text.try_extend(&s);
+        }
+    }
+}
+
+fn set_clipboard(text: &str) {
+    use copypasta::ClipboardProvider;
+
+    let mut guard = CLIPBOARD.lock().unwrap();
+    if let Some(c) = guard.deref_mut() {
+        let s = String::from(text);
+        let _ = c.set_contents(s);
+    }
+}
+
+// Window positions and other persistent state are kept here between runs.
+const UI_STATE_PATH: &str = "guise_ui_state.bin";
+const UI_STATE_RECORD_SIZE: usize = 8 + 64;
+
+fn load_ui_state(ui: &mut guise::Ui<Global>) {
+    // Missing or truncated state just means starting from scratch.
+    let Ok(bytes) = std::fs::read(UI_STATE_PATH) else {
+        return;
+    };
+
+    for record in bytes.chunks_exact(UI_STATE_RECORD_SIZE) {
+        let (persistent_id, state) = record.split_at(8);
+        let persistent_id = u64::from_le_bytes(persistent_id.try_into().unwrap());
+        let state: guise::CtrlState = state.try_into().unwrap();
+
+        ui.load_persistent_state(persistent_id, &state);
+    }
+}
+
+fn save_ui_state(ui: &guise::Ui<Global>) {
+    let mut bytes = Vec::new();
+    ui.save_persistent_state(|persistent_id, state| {
+        bytes.extend_from_slice(&persistent_id.to_le_bytes());
+        bytes.extend_from_slice(state);
+    });
+
+    if let Err(err) = std::fs::write(UI_STATE_PATH, bytes) {
+        log::error!("Failed to save UI state: {err}");
+    }
+}
+
+fn main() {
+    pretty_env_logger::init();
+
+    let event_loop = winit::event_loop::EventLoop::new();
+    let window_builder = winit::window::WindowBuilder::new()
+        .with_title("Look, a demo!")
+        .with_inner_size(winit::dpi::LogicalSize::new(1200, 800));
+
+    let (window, gl_config) = glutin_winit::DisplayBuilder::new()
+        .with_window_builder(Some(window_builder))
+        .build(
+            &event_loop,
+            glutin::config::ConfigTemplateBuilder::new(),
+            |mut configs| configs.next().unwrap(),
+        )
+        .expect("Failed to create window");
+    let window = window.unwrap();
+
+    init_clipboard_or_not();
+
+    let gl_display = gl_config.display();
+    let gl_context_attributes = glutin::context::ContextAttributesBuilder::new()
+        .with_context_api(glutin::context::ContextApi::OpenGl(Some(
+            glutin::context::Version::new(3, 3),
+        )))
+        .build(Some(window.raw_window_handle()));
+
+    // NB: The surface is created without an sRGB format, and the renderer
+    // doesn't enable GL_FRAMEBUFFER_SRGB. If either is changed, call
+    // ui.set_linear_color_output(true) too.
+    let gl_surface_attributes = window.build_surface_attributes(Default::default());
+    let gl_surface = unsafe {
+        gl_display
+            .create_window_surface(&gl_config, &gl_surface_attributes)
+            .expect("Failed to create gl surface")
+    };
+    let gl_context = unsafe {
+        gl_display
+            .create_context(&gl_config, &gl_context_attributes)
+            .expect("Failed to create gl context")
+    }
+    .make_current(&gl_surface)
+    .expect("Failed to make gl context current");
+
+    if let Err(err) = gl_surface.set_swap_interval(
+        &gl_context,
+        glutin::surface::SwapInterval::Wait(NonZeroU32::new(1).unwrap()),
+    ) {
+        log::error!("Failed to set swap interval: {err}");
+    }
+
+    let gl = unsafe {
+        glow::Context::from_loader_function(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            gl_display.get_proc_address(&symbol)
+        })
+    };
+
+    let initial_window_physical_size = window.inner_size();
+    let initial_window_width = initial_window_physical_size.width;
+    let initial_window_height = initial_window_physical_size.height;
+
+    let mut ui = {
+        let scale_factor = window.scale_factor();
+        let logical_size = window.inner_size().to_logical(scale_factor);
+
+        guise::Ui::new_in(
+            logical_size.width,
+            logical_size.height,
+            scale_factor as f32,
+            guise::FontSource::ttf(
+                guise::FONT_IBM_PLEX_MONO,
+                // guise::FONT_IBM_PLEX_SANS_JP,
+                // guise::FONT_PROGGY_CLEAN,
+                // guise::FONT_ROBOTO,
+                // guise::FONT_LIBERATION_MONO,
+                guise::UnicodeRangeFlags::ALL,
+                14.0,
+                scale_factor as f32,
+            ),
+            std::alloc::Global,
+        )
+    };
+
+    load_ui_state(&mut ui);
+
+    ui.set_clipboard_getter(get_clipboard);
+    ui.set_clipboard_setter(set_clipboard);
+    ui.set_scroll_smoothing(Some(0.05));
+
+    let mut renderer = renderer_glow::Renderer::new(&gl);
+
+    let font_atlas_image = ui.font_atlas_image_rgba8_unorm();
+    let (font_atlas_width, font_atlas_height) = ui.font_atlas_image_size();
+    let mut font_atlas_texture_id = renderer.add_texture_rgba8_unorm(
+        &gl,
+        u32::from(font_atlas_width),
+        u32::from(font_atlas_height),
+        font_atlas_image,
+    );
+    renderer.set_texture_sdf(font_atlas_texture_id, ui.font_atlas_sdf());
+    ui.set_font_atlas_texture_id(font_atlas_texture_id);
+    let mut font_atlas_generation = ui.font_atlas_generation();
+
+    let mut state = demo::State {
+        button_click_count: 0,
+        reset_modal_open: false,
+        command_palette_open: false,
+        text_input_submit_count: 0,
+        text_input_cancel_count: 0,
+        poll_platform_events: true,
+        sdf_text: false,
+        premultiplied_alpha: false,
+        text_scale: 2.0,
+        graph: [0.0; demo::GRAPH_LEN],
+        graph_max: 0.0,
+        graph_frame_build: [0.0; demo::GRAPH_LEN],
+        graph_frame_build_max: 0.0,
+        graph_command_count: [0; demo::GRAPH_LEN],
+        graph_command_count_max: 0,
+        graph_vertex_count: [0; demo::GRAPH_LEN],
+        graph_vertex_count_max: 0,
+        graph_index_count: [0; demo::GRAPH_LEN],
+        graph_index_count_max: 0,
+        text_input_heap: guise::VecString::new_in(Global),
+        text_input_inline: ArrayString::new(),
+        float_value: 1.0,
+        float_value_clamped: 0.0,
+        float2_value: [0.0; 2],
+        float3_value: [0.0; 3],
+        float4_value: [0.0; 4],
+        int_value: 1,
+        int_value_clamped: 0,
+        int2_value: [0; 2],
+        int3_value: [0; 3],
+        int4_value: [0; 4],
+        dropdown1_selected_option: None,
+        dropdown2_selected_option: None,
+        combo_selected_option: None,
+        dropdown_multi_selected_options: [false; 7],
+        theme: guise::Theme::DEFAULT,
+        theme_light: false,
+        read_only: false,
+        dnd_row_order: [0, 1, 2, 3, 4],
+        reorderable_list_order: [0, 1, 2, 3, 4],
+    };
+
+    let time_start = Instant::now();
+    let mut time = time_start;
+    let mut time_last_frame = time_start;
+
+    let mut window_width = initial_window_width;
+    let mut window_height = initial_window_height;
+    let mut window_size_stale = false;
+
+    let mut frame_count = 0;
+    let mut frame_build_duration = Duration::new(0, 0);
+    let mut frame_total_duration = Duration::new(0, 0);
+    let mut frame_stats = guise::FrameStats::default();
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = if state.poll_platform_events {
+            winit::event_loop::ControlFlow::Poll
+        } else {
+            winit::event_loop::ControlFlow::Wait
+        };
+
+        match event {
+            winit::event::Event::NewEvents(_) => {
+                frame_count += 1;
+                time = Instant::now();
+            }
+            winit::event::Event::WindowEvent {
+                event: window_event,
+                ..
+            } => {
+                guise_winit::handle_event(&mut ui, &window, &window_event);
+
+                match window_event {
+                    winit::event::WindowEvent::Resized(physical_size) => {
+                        if (physical_size.width, physical_size.height)
+                            != (window_width, window_height)
+                        {
+                            window_width = physical_size.width;
+                            window_height = physical_size.height;
+                            window_size_stale = true;
+                        }
+                    }
+                    winit::event::WindowEvent::CloseRequested => {
+                        save_ui_state(&ui);
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
+                    }
+                    _ => (),
+                }
+            }
+            winit::event::Event::MainEventsCleared => {
+                let want_capture_keyboard = ui.want_capture_keyboard();
+                let want_capture_mouse = ui.want_capture_mouse();
+                let delta_time = (time - time_last_frame).as_secs_f32();
+                time_last_frame = time;
+
+                // Regenerates the atlas, which is then uploaded below.
+                if ui.font_atlas_sdf() != state.sdf_text {
+                    ui.set_font_atlas_sdf(state.sdf_text);
+                }
+
+                // Converts the atlas, which is then uploaded below. The
+                // renderer picks the blend state from the draw list stats.
+                ui.set_premultiplied_alpha(state.premultiplied_alpha);
+
+                if ui.font_atlas_generation() != font_atlas_generation {
+                    renderer.remove_texture(&gl, font_atlas_texture_id);
+
+                    let font_atlas_image = ui.font_atlas_image_rgba8_unorm();
+                    let (font_atlas_width, font_atlas_height) = ui.font_atlas_image_size();
+                    font_atlas_texture_id = renderer.add_texture_rgba8_unorm(
+                        &gl,
+                        u32::from(font_atlas_width),
+                        u32::from(font_atlas_height),
+                        font_atlas_image,
+                    );
+                    renderer.set_texture_sdf(font_atlas_texture_id, ui.font_atlas_sdf());
+                    ui.set_font_atlas_texture_id(font_atlas_texture_id);
+                    font_atlas_generation = ui.font_atlas_generation();
+                }
+
+                guise_winit::begin(&mut ui, &window);
+                let mut frame = ui.begin_frame_with_delta_time(delta_time);
+                demo::draw_ui(
+                    &mut frame,
+                    &demo::Stats {
+                        running_duration: time - time_start,
+                        frame_count,
+                        frame_build_duration,
+                        frame_total_duration,
+                        frame_stats,
+                        want_capture_keyboard,
+                        want_capture_mouse,
+                    },
+                    &mut state,
+                );
+                ui.end_frame();
+
+                guise_winit::end(&ui, &window);
+
+                frame_stats = ui.frame_stats();
+                frame_build_duration = Instant::now() - time;
+
+                window.request_redraw();
+            }
+            winit::event::Event::RedrawRequested(_) => {
+                let physical_size = window.inner_size();
+                if physical_size.width == 0 || physical_size.height == 0 {
+                    return;
+                }
+
+                if window_size_stale {
+                    window.resize_surface(&gl_surface, &gl_context);
+                    window_size_stale = false;
+                }
+
+                unsafe {
+                    gl.viewport(0, 0, window_width as i32, window_height as i32);
+                    gl.disable(glow::SCISSOR_TEST);
+                    gl.clear_color(0.0, 0.0, 0.0, 1.0);
+                    gl.clear(glow::COLOR_BUFFER_BIT);
+                }
+
+                let premultiplied_alpha = ui.draw_list_stats().premultiplied_alpha;
+                let (commands, vertices, indices) = ui.draw_list();
+                renderer.draw(
+                    &gl,
+                    window_width,
+                    window_height,
+                    window.scale_factor() as f32,
+                    premultiplied_alpha,
+                    commands,
+                    vertices,
+                    indices,
+                    &mut |gl, user_id, [x, y, w, h]| {
+                        // Stands in for the user's own rendering, e.g. a 3D
+                        // scene preview. Only has to respect the scissor rect.
+                        if user_id == demo::CUSTOM_DRAW_CALLBACK_ID {
+                            unsafe {
+                                gl.enable(glow::SCISSOR_TEST);
+                                gl.scissor(x, y, w, h);
+                                gl.clear_color(0.2, 0.3, 0.5, 1.0);
+                                gl.clear(glow::COLOR_BUFFER_BIT);
+                            }
+                        }
+                    },
+                );
+
+                if let Err(err) = gl_surface.swap_buffers(&gl_context) {
+                    log::error!("Failed to swap buffers: {err}");
+                }
+            }
+            winit::event::Event::RedrawEventsCleared => {
+                frame_total_duration = Instant::now() - time;
+            }
+            winit::event::Event::LoopDestroyed => {
+                // TODO(yan): @Cleanup Removing the font atlas explicitly from the
+                // renderer is not necessary and the renderer would clean it up
+                // itself, but this shows the API exists. Remove once we have
+                // more textures.
+                renderer.remove_texture(&gl, font_atlas_texture_id);
+                renderer.destroy(&gl);
+            }
+            _ => (),
+        }
+    });
+}
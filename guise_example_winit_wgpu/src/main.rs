@@ -24,18 +24,14 @@ fn init_clipboard_or_not() {
     }
 }
 
-fn get_clipboard() -> String {
+fn get_clipboard(text: &mut dyn guise::TextStorage) {
     use copypasta::ClipboardProvider;
 
     let mut guard = CLIPBOARD.lock().unwrap();
     if let Some(c) = guard.deref_mut() {
         if let Ok(s) = c.get_contents() {
-            s
-        } else {
-            String::new()
+            let _ = text.try_extend(&s);
         }
-    } else {
-        String::new()
     }
 }
 
@@ -49,6 +45,37 @@ fn set_clipboard(text: &str) {
     }
 }
 
+// Window positions and other persistent state are kept here between runs.
+const UI_STATE_PATH: &str = "guise_ui_state.bin";
+const UI_STATE_RECORD_SIZE: usize = 8 + 64;
+
+fn load_ui_state(ui: &mut guise::Ui<Global>) {
+    // Missing or truncated state just means starting from scratch.
+    let Ok(bytes) = std::fs::read(UI_STATE_PATH) else {
+        return;
+    };
+
+    for record in bytes.chunks_exact(UI_STATE_RECORD_SIZE) {
+        let (persistent_id, state) = record.split_at(8);
+        let persistent_id = u64::from_le_bytes(persistent_id.try_into().unwrap());
+        let state: guise::CtrlState = state.try_into().unwrap();
+
+        ui.load_persistent_state(persistent_id, &state);
+    }
+}
+
+fn save_ui_state(ui: &guise::Ui<Global>) {
+    let mut bytes = Vec::new();
+    ui.save_persistent_state(|persistent_id, state| {
+        bytes.extend_from_slice(&persistent_id.to_le_bytes());
+        bytes.extend_from_slice(state);
+    });
+
+    if let Err(err) = std::fs::write(UI_STATE_PATH, bytes) {
+        log::error!("Failed to save UI state: {err}");
+    }
+}
+
 fn main() {
     pretty_env_logger::init();
 
@@ -84,6 +111,8 @@ fn main() {
     .expect("Failed to acquire gpu device and queue");
 
     let surface_present_mode = wgpu::PresentMode::Fifo;
+    // NB: If this is changed to an sRGB format, call
+    // ui.set_linear_color_output(true) too.
     let surface_format = wgpu::TextureFormat::Bgra8Unorm;
     let initial_window_physical_size = window.inner_size();
     let initial_window_width = initial_window_physical_size.width;
@@ -107,39 +136,52 @@ fn main() {
             logical_size.width,
             logical_size.height,
             scale_factor as f32,
-            guise::FONT_IBM_PLEX_MONO,
-            // guise::FONT_IBM_PLEX_SANS_JP,
-            // guise::FONT_PROGGY_CLEAN,
-            // guise::FONT_ROBOTO,
-            // guise::FONT_LIBERATION_MONO,
-            guise::UnicodeRangeFlags::ALL,
-            14.0,
-            scale_factor as f32,
+            guise::FontSource::ttf(
+                guise::FONT_IBM_PLEX_MONO,
+                // guise::FONT_IBM_PLEX_SANS_JP,
+                // guise::FONT_PROGGY_CLEAN,
+                // guise::FONT_ROBOTO,
+                // guise::FONT_LIBERATION_MONO,
+                guise::UnicodeRangeFlags::ALL,
+                14.0,
+                scale_factor as f32,
+            ),
             std::alloc::Global,
         )
     };
 
+    load_ui_state(&mut ui);
+
     ui.set_clipboard_getter(get_clipboard);
     ui.set_clipboard_setter(set_clipboard);
+    ui.set_scroll_smoothing(Some(0.05));
 
     let mut renderer = renderer_wgpu::Renderer::new(&device, surface_format);
+    let custom_draw_pipeline = create_custom_draw_pipeline(&device, surface_format);
 
     let font_atlas_image = ui.font_atlas_image_rgba8_unorm();
     let (font_atlas_width, font_atlas_height) = ui.font_atlas_image_size();
-    let font_atlas_texture_id = renderer.add_texture_rgba8_unorm(
+    let mut font_atlas_texture_id = renderer.add_texture_rgba8_unorm(
         &device,
         &mut queue,
         u32::from(font_atlas_width),
         u32::from(font_atlas_height),
         font_atlas_image,
     );
+    renderer.set_texture_sdf(font_atlas_texture_id, ui.font_atlas_sdf());
     ui.set_font_atlas_texture_id(font_atlas_texture_id);
+    let mut font_atlas_generation = ui.font_atlas_generation();
 
     let mut state = demo::State {
         button_click_count: 0,
+        reset_modal_open: false,
+        command_palette_open: false,
         text_input_submit_count: 0,
         text_input_cancel_count: 0,
         poll_platform_events: true,
+        sdf_text: false,
+        premultiplied_alpha: false,
+        text_scale: 2.0,
         graph: [0.0; demo::GRAPH_LEN],
         graph_max: 0.0,
         graph_frame_build: [0.0; demo::GRAPH_LEN],
@@ -164,10 +206,18 @@ fn main() {
         int4_value: [0; 4],
         dropdown1_selected_option: None,
         dropdown2_selected_option: None,
+        combo_selected_option: None,
+        dropdown_multi_selected_options: [false; 7],
+        theme: guise::Theme::DEFAULT,
+        theme_light: false,
+        read_only: false,
+        dnd_row_order: [0, 1, 2, 3, 4],
+        reorderable_list_order: [0, 1, 2, 3, 4],
     };
 
     let time_start = Instant::now();
     let mut time = time_start;
+    let mut time_last_frame = time_start;
 
     let mut window_width = initial_window_width;
     let mut window_height = initial_window_height;
@@ -176,10 +226,7 @@ fn main() {
     let mut frame_count = 0;
     let mut frame_build_duration = Duration::new(0, 0);
     let mut frame_total_duration = Duration::new(0, 0);
-    let mut frame_draw_list_command_count = 0;
-    let mut frame_draw_list_vertex_count = 0;
-    let mut frame_draw_list_index_count = 0;
-    let mut frame_ctrl_count = 0;
+    let mut frame_stats = guise::FrameStats::default();
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = if state.poll_platform_events {
@@ -196,217 +243,60 @@ fn main() {
             winit::event::Event::WindowEvent {
                 event: window_event,
                 ..
-            } => match window_event {
-                winit::event::WindowEvent::Resized(physical_size) => {
-                    let logical_size = physical_size.to_logical(window.scale_factor());
-                    ui.set_window_size(logical_size.width, logical_size.height);
-
-                    if (physical_size.width, physical_size.height) != (window_width, window_height)
-                    {
-                        window_width = physical_size.width;
-                        window_height = physical_size.height;
-                        window_size_stale = true;
-                    }
-                }
-                winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                    ui.set_window_scale_factor(scale_factor as f32);
-                }
-                winit::event::WindowEvent::CloseRequested => {
-                    *control_flow = winit::event_loop::ControlFlow::Exit;
-                }
-                winit::event::WindowEvent::ReceivedCharacter(character) => {
-                    ui.send_character(character);
-                }
-                winit::event::WindowEvent::CursorMoved { position, .. } => {
-                    let scale_factor = window.scale_factor();
-                    let logical_position = position.to_logical(scale_factor);
-                    ui.set_cursor_position(logical_position.x, logical_position.y);
-                }
-                winit::event::WindowEvent::MouseWheel { delta, .. } => match delta {
-                    winit::event::MouseScrollDelta::LineDelta(dx, dy) => {
-                        ui.scroll(dx * 10.0, dy * 10.0);
-                    }
-                    winit::event::MouseScrollDelta::PixelDelta(physical_position) => {
-                        let scale_factor = window.scale_factor();
-                        let logical_position = physical_position.to_logical::<f32>(scale_factor);
-                        ui.scroll(logical_position.x, logical_position.y);
-                    }
-                },
-                winit::event::WindowEvent::MouseInput { state, button, .. } => match state {
-                    winit::event::ElementState::Pressed => match button {
-                        winit::event::MouseButton::Left => {
-                            ui.press_inputs(guise::Inputs::MB_LEFT);
-                        }
-                        winit::event::MouseButton::Right => {
-                            ui.press_inputs(guise::Inputs::MB_RIGHT);
-                        }
-                        winit::event::MouseButton::Middle => {
-                            ui.press_inputs(guise::Inputs::MB_MIDDLE);
-                        }
-                        _ => (),
-                    },
-                    winit::event::ElementState::Released => match button {
-                        winit::event::MouseButton::Left => {
-                            ui.release_inputs(guise::Inputs::MB_LEFT);
-                        }
-                        winit::event::MouseButton::Right => {
-                            ui.release_inputs(guise::Inputs::MB_RIGHT);
-                        }
-                        winit::event::MouseButton::Middle => {
-                            ui.release_inputs(guise::Inputs::MB_MIDDLE);
-                        }
-                        _ => (),
-                    },
-                },
-                winit::event::WindowEvent::KeyboardInput { input, .. } => match input.state {
-                    winit::event::ElementState::Pressed => match input.virtual_keycode {
-                        Some(winit::event::VirtualKeyCode::Tab) => {
-                            ui.press_inputs(guise::Inputs::KB_TAB);
-                        }
-                        Some(winit::event::VirtualKeyCode::Left) => {
-                            ui.press_inputs(guise::Inputs::KB_LEFT_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::Right) => {
-                            ui.press_inputs(guise::Inputs::KB_RIGHT_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::Up) => {
-                            ui.press_inputs(guise::Inputs::KB_UP_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::Down) => {
-                            ui.press_inputs(guise::Inputs::KB_DOWN_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::PageUp) => {
-                            ui.press_inputs(guise::Inputs::KB_PAGE_UP);
-                        }
-                        Some(winit::event::VirtualKeyCode::PageDown) => {
-                            ui.press_inputs(guise::Inputs::KB_PAGE_DOWN);
-                        }
-                        Some(winit::event::VirtualKeyCode::Home) => {
-                            ui.press_inputs(guise::Inputs::KB_HOME);
-                        }
-                        Some(winit::event::VirtualKeyCode::End) => {
-                            ui.press_inputs(guise::Inputs::KB_END);
-                        }
-                        Some(winit::event::VirtualKeyCode::Insert) => {
-                            ui.press_inputs(guise::Inputs::KB_INSERT);
-                        }
-                        Some(winit::event::VirtualKeyCode::Delete) => {
-                            ui.press_inputs(guise::Inputs::KB_DELETE);
-                        }
-                        Some(winit::event::VirtualKeyCode::Back) => {
-                            ui.press_inputs(guise::Inputs::KB_BACKSPACE);
-                        }
-                        Some(winit::event::VirtualKeyCode::Return) => {
-                            ui.press_inputs(guise::Inputs::KB_ENTER);
-                        }
-                        Some(winit::event::VirtualKeyCode::Escape) => {
-                            ui.press_inputs(guise::Inputs::KB_ESCAPE);
-                        }
-                        Some(winit::event::VirtualKeyCode::A) => {
-                            ui.press_inputs(guise::Inputs::KB_A);
-                        }
-                        Some(winit::event::VirtualKeyCode::F) => {
-                            ui.press_inputs(guise::Inputs::KB_F);
-                        }
-                        Some(winit::event::VirtualKeyCode::B) => {
-                            ui.press_inputs(guise::Inputs::KB_B);
-                        }
-                        Some(winit::event::VirtualKeyCode::X) => {
-                            ui.press_inputs(guise::Inputs::KB_X);
-                        }
-                        Some(winit::event::VirtualKeyCode::C) => {
-                            ui.press_inputs(guise::Inputs::KB_C);
-                        }
-                        Some(winit::event::VirtualKeyCode::V) => {
-                            ui.press_inputs(guise::Inputs::KB_V);
+            } => {
+                guise_winit::handle_event(&mut ui, &window, &window_event);
+
+                match window_event {
+                    winit::event::WindowEvent::Resized(physical_size) => {
+                        if (physical_size.width, physical_size.height)
+                            != (window_width, window_height)
+                        {
+                            window_width = physical_size.width;
+                            window_height = physical_size.height;
+                            window_size_stale = true;
                         }
-                        _ => (),
-                    },
-                    winit::event::ElementState::Released => match input.virtual_keycode {
-                        Some(winit::event::VirtualKeyCode::Tab) => {
-                            ui.release_inputs(guise::Inputs::KB_TAB);
-                        }
-                        Some(winit::event::VirtualKeyCode::Left) => {
-                            ui.release_inputs(guise::Inputs::KB_LEFT_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::Right) => {
-                            ui.release_inputs(guise::Inputs::KB_RIGHT_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::Up) => {
-                            ui.release_inputs(guise::Inputs::KB_UP_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::Down) => {
-                            ui.release_inputs(guise::Inputs::KB_DOWN_ARROW);
-                        }
-                        Some(winit::event::VirtualKeyCode::PageUp) => {
-                            ui.release_inputs(guise::Inputs::KB_PAGE_UP);
-                        }
-                        Some(winit::event::VirtualKeyCode::PageDown) => {
-                            ui.release_inputs(guise::Inputs::KB_PAGE_DOWN);
-                        }
-                        Some(winit::event::VirtualKeyCode::Home) => {
-                            ui.release_inputs(guise::Inputs::KB_HOME);
-                        }
-                        Some(winit::event::VirtualKeyCode::End) => {
-                            ui.release_inputs(guise::Inputs::KB_END);
-                        }
-                        Some(winit::event::VirtualKeyCode::Insert) => {
-                            ui.release_inputs(guise::Inputs::KB_INSERT);
-                        }
-                        Some(winit::event::VirtualKeyCode::Delete) => {
-                            ui.release_inputs(guise::Inputs::KB_DELETE);
-                        }
-                        Some(winit::event::VirtualKeyCode::Back) => {
-                            ui.release_inputs(guise::Inputs::KB_BACKSPACE);
-                        }
-                        Some(winit::event::VirtualKeyCode::Return) => {
-                            ui.release_inputs(guise::Inputs::KB_ENTER);
-                        }
-                        Some(winit::event::VirtualKeyCode::Escape) => {
-                            ui.release_inputs(guise::Inputs::KB_ESCAPE);
-                        }
-                        Some(winit::event::VirtualKeyCode::A) => {
-                            ui.release_inputs(guise::Inputs::KB_A);
-                        }
-                        Some(winit::event::VirtualKeyCode::F) => {
-                            ui.release_inputs(guise::Inputs::KB_F);
-                        }
-                        Some(winit::event::VirtualKeyCode::B) => {
-                            ui.release_inputs(guise::Inputs::KB_B);
-                        }
-                        Some(winit::event::VirtualKeyCode::X) => {
-                            ui.release_inputs(guise::Inputs::KB_X);
-                        }
-                        Some(winit::event::VirtualKeyCode::C) => {
-                            ui.release_inputs(guise::Inputs::KB_C);
-                        }
-                        Some(winit::event::VirtualKeyCode::V) => {
-                            ui.release_inputs(guise::Inputs::KB_V);
-                        }
-                        _ => (),
-                    },
-                },
-                winit::event::WindowEvent::ModifiersChanged(state) => {
-                    let mut modifiers = guise::Modifiers::empty();
-
-                    if state.ctrl() {
-                        modifiers |= guise::Modifiers::CTRL;
-                    }
-                    if state.alt() {
-                        modifiers |= guise::Modifiers::ALT;
                     }
-                    if state.shift() {
-                        modifiers |= guise::Modifiers::SHIFT;
+                    winit::event::WindowEvent::CloseRequested => {
+                        save_ui_state(&ui);
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
                     }
-
-                    ui.set_modifiers(modifiers);
+                    _ => (),
                 }
-                _ => (),
-            },
+            }
             winit::event::Event::MainEventsCleared => {
                 let want_capture_keyboard = ui.want_capture_keyboard();
                 let want_capture_mouse = ui.want_capture_mouse();
-                let mut frame = ui.begin_frame();
+                let delta_time = (time - time_last_frame).as_secs_f32();
+                time_last_frame = time;
+
+                // Regenerates the atlas, which is then uploaded below.
+                if ui.font_atlas_sdf() != state.sdf_text {
+                    ui.set_font_atlas_sdf(state.sdf_text);
+                }
+
+                // Converts the atlas, which is then uploaded below. The
+                // renderer picks the blend state from the draw list stats.
+                ui.set_premultiplied_alpha(state.premultiplied_alpha);
+
+                if ui.font_atlas_generation() != font_atlas_generation {
+                    renderer.remove_texture(font_atlas_texture_id);
+
+                    let font_atlas_image = ui.font_atlas_image_rgba8_unorm();
+                    let (font_atlas_width, font_atlas_height) = ui.font_atlas_image_size();
+                    font_atlas_texture_id = renderer.add_texture_rgba8_unorm(
+                        &device,
+                        &mut queue,
+                        u32::from(font_atlas_width),
+                        u32::from(font_atlas_height),
+                        font_atlas_image,
+                    );
+                    renderer.set_texture_sdf(font_atlas_texture_id, ui.font_atlas_sdf());
+                    ui.set_font_atlas_texture_id(font_atlas_texture_id);
+                    font_atlas_generation = ui.font_atlas_generation();
+                }
+
+                guise_winit::begin(&mut ui, &window);
+                let mut frame = ui.begin_frame_with_delta_time(delta_time);
                 demo::draw_ui(
                     &mut frame,
                     &demo::Stats {
@@ -414,10 +304,7 @@ fn main() {
                         frame_count,
                         frame_build_duration,
                         frame_total_duration,
-                        frame_draw_list_command_count,
-                        frame_draw_list_vertex_count,
-                        frame_draw_list_index_count,
-                        frame_ctrl_count,
+                        frame_stats,
                         want_capture_keyboard,
                         want_capture_mouse,
                     },
@@ -425,7 +312,9 @@ fn main() {
                 );
                 ui.end_frame();
 
-                frame_ctrl_count = ui.ctrl_count();
+                guise_winit::end(&ui, &window);
+
+                frame_stats = ui.frame_stats();
                 frame_build_duration = Instant::now() - time;
 
                 window.request_redraw();
@@ -455,6 +344,7 @@ fn main() {
                         .texture
                         .create_view(&wgpu::TextureViewDescriptor::default());
 
+                    let premultiplied_alpha = ui.draw_list_stats().premultiplied_alpha;
                     let (commands, vertices, indices) = ui.draw_list();
                     renderer.draw(
                         &device,
@@ -470,9 +360,33 @@ fn main() {
                         window_width,
                         window_height,
                         window.scale_factor() as f32,
+                        premultiplied_alpha,
                         commands,
                         vertices,
                         indices,
+                        &mut |encoder, view, user_id, [x, y, w, h]| {
+                            if user_id == demo::CUSTOM_DRAW_CALLBACK_ID {
+                                let mut render_pass =
+                                    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                                        label: None,
+                                        color_attachments: &[Some(
+                                            wgpu::RenderPassColorAttachment {
+                                                view,
+                                                resolve_target: None,
+                                                ops: wgpu::Operations {
+                                                    load: wgpu::LoadOp::Load,
+                                                    store: true,
+                                                },
+                                            },
+                                        )],
+                                        depth_stencil_attachment: None,
+                                    });
+
+                                render_pass.set_pipeline(&custom_draw_pipeline);
+                                render_pass.set_scissor_rect(x, y, w, h);
+                                render_pass.draw(0..3, 0..1);
+                            }
+                        },
                     );
 
                     queue.submit(iter::once(encoder.finish()));
@@ -482,12 +396,6 @@ fn main() {
             }
             winit::event::Event::RedrawEventsCleared => {
                 frame_total_duration = Instant::now() - time;
-
-                let (commands, vertices, indices) = ui.draw_list();
-
-                frame_draw_list_command_count = commands.len();
-                frame_draw_list_vertex_count = vertices.len();
-                frame_draw_list_index_count = indices.len();
             }
             winit::event::Event::LoopDestroyed => {
                 // TODO(yan): @Cleanup Removing the font atlas explicitly from the
@@ -500,3 +408,53 @@ fn main() {
         }
     });
 }
+
+// Draws a full screen triangle with a striped pattern, clipped by the scissor
+// rect. Stands in for the user's own rendering, e.g. a 3D scene preview.
+fn create_custom_draw_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    const SHADER_SOURCE: &str = r"
+        @vertex
+        fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+            let x = f32(i32(vertex_index & 1u) * 4 - 1);
+            let y = f32(i32(vertex_index >> 1u) * 4 - 1);
+            return vec4<f32>(x, y, 0.0, 1.0);
+        }
+
+        @fragment
+        fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+            let stripe = step(0.5, fract((position.x + position.y) / 32.0));
+            return vec4<f32>(0.2 + 0.3 * stripe, 0.3, 0.5 - 0.2 * stripe, 1.0);
+        }
+    ";
+
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(SHADER_SOURCE.into()),
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
+}
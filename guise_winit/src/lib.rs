@@ -0,0 +1,316 @@
+//! Translates winit events to guise inputs, so that applications don't have to.
+//!
+//! Each frame, call [`begin`] before [`guise::Ui::begin_frame`], pass window
+//! events to [`handle_event`], and call [`end`] after [`guise::Ui::end_frame`]
+//! to apply the cursor icon and IME state the UI asked for.
+
+#![feature(allocator_api)]
+
+use core::alloc::Allocator;
+
+use winit::event::{
+    ElementState,
+    Ime,
+    KeyboardInput,
+    MouseButton,
+    MouseScrollDelta,
+    Touch,
+    TouchPhase,
+    VirtualKeyCode,
+    WindowEvent,
+};
+use winit::window::{CursorIcon, Window};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EventOptions {
+    /// How many logical pixels to scroll per line, for mice that scroll by
+    /// lines instead of pixels.
+    pub line_scroll_factor: f32,
+    /// Whether the macOS Command key acts as Ctrl, so that e.g. Cmd+C copies.
+    pub logo_as_ctrl: bool,
+}
+
+impl EventOptions {
+    pub const DEFAULT: Self = Self {
+        line_scroll_factor: 10.0,
+        logo_as_ctrl: cfg!(target_os = "macos"),
+    };
+}
+
+impl Default for EventOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Syncs the window size and scale factor to the UI. Call before
+/// [`guise::Ui::begin_frame`], so that the UI also picks up changes that
+/// happened before its first frame, or were missed in events.
+pub fn begin<A: Allocator + Clone>(ui: &mut guise::UiCore<A>, window: &Window) {
+    let scale_factor = window.scale_factor();
+    let logical_size = window.inner_size().to_logical::<f32>(scale_factor);
+
+    ui.set_window_size(logical_size.width, logical_size.height);
+    ui.set_window_scale_factor(scale_factor as f32);
+}
+
+/// Applies the cursor icon and text input state requested by the last built
+/// frame to the window. Call after [`guise::Ui::end_frame`].
+pub fn end<A: Allocator + Clone>(ui: &guise::UiCore<A>, window: &Window) {
+    let cursor_icon = match ui.cursor_icon() {
+        guise::CursorIcon::Default => CursorIcon::Default,
+        guise::CursorIcon::Text => CursorIcon::Text,
+        guise::CursorIcon::ResizeNESW => CursorIcon::NeswResize,
+        guise::CursorIcon::ResizeNWSE => CursorIcon::NwseResize,
+        guise::CursorIcon::ResizeHorizontal => CursorIcon::EwResize,
+        guise::CursorIcon::ResizeVertical => CursorIcon::NsResize,
+        guise::CursorIcon::Hand => CursorIcon::Hand,
+        guise::CursorIcon::Move => CursorIcon::Move,
+    };
+
+    window.set_cursor_icon(cursor_icon);
+    window.set_ime_allowed(ui.want_text_input());
+}
+
+/// Like [`handle_event_with_options`], but uses [`EventOptions::DEFAULT`].
+#[inline]
+pub fn handle_event<A: Allocator + Clone>(
+    ui: &mut guise::UiCore<A>,
+    window: &Window,
+    event: &WindowEvent<'_>,
+) -> bool {
+    handle_event_with_options(ui, window, event, &EventOptions::DEFAULT)
+}
+
+/// Passes the window event to the UI. Returns whether the UI consumed the
+/// event, i.e. whether it is a mouse event while the UI wants to capture the
+/// mouse, or a keyboard event while it wants to capture the keyboard. The
+/// application should not handle consumed events itself, e.g. to move a
+/// camera.
+///
+/// Capturing is based on the last built frame, so the UI sees all events
+/// regardless, e.g. so that releasing a mouse button is never missed.
+pub fn handle_event_with_options<A: Allocator + Clone>(
+    ui: &mut guise::UiCore<A>,
+    window: &Window,
+    event: &WindowEvent<'_>,
+    options: &EventOptions,
+) -> bool {
+    match event {
+        WindowEvent::Resized(physical_size) => {
+            let logical_size = physical_size.to_logical::<f32>(window.scale_factor());
+            ui.set_window_size(logical_size.width, logical_size.height);
+
+            false
+        }
+        WindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size,
+        } => {
+            let logical_size = new_inner_size.to_logical::<f32>(*scale_factor);
+            ui.set_window_size(logical_size.width, logical_size.height);
+            ui.set_window_scale_factor(*scale_factor as f32);
+
+            // Re-rasterize the font, if we moved to a monitor with higher
+            // scale factor than the atlas was prepared for. The application
+            // uploads the new atlas once it sees the font atlas generation
+            // change.
+            #[cfg(feature = "runtime_rasterize")]
+            {
+                let font_size = ui.font_atlas().font_size();
+                if *scale_factor as f32 > ui.font_atlas().font_scale_factor() {
+                    ui.rebuild_font_atlas(font_size, *scale_factor as f32);
+                }
+            }
+
+            false
+        }
+        WindowEvent::CursorMoved { position, .. } => {
+            let logical_position = position.to_logical::<f32>(window.scale_factor());
+            ui.set_cursor_position(logical_position.x, logical_position.y);
+
+            ui.want_capture_mouse()
+        }
+        WindowEvent::MouseWheel { delta, .. } => {
+            match delta {
+                MouseScrollDelta::LineDelta(dx, dy) => {
+                    ui.scroll(
+                        dx * options.line_scroll_factor,
+                        dy * options.line_scroll_factor,
+                    );
+                }
+                MouseScrollDelta::PixelDelta(physical_position) => {
+                    let logical_position =
+                        physical_position.to_logical::<f32>(window.scale_factor());
+                    ui.scroll(logical_position.x, logical_position.y);
+                }
+            }
+
+            ui.want_capture_mouse()
+        }
+        WindowEvent::MouseInput { state, button, .. } => {
+            let inputs = mouse_button_inputs(*button);
+            if inputs != guise::Inputs::NONE {
+                match state {
+                    ElementState::Pressed => ui.press_inputs(inputs),
+                    ElementState::Released => ui.release_inputs(inputs),
+                }
+            }
+
+            ui.want_capture_mouse()
+        }
+        WindowEvent::Touch(Touch {
+            phase,
+            location,
+            id,
+            ..
+        }) => {
+            let logical_location = location.to_logical::<f32>(window.scale_factor());
+            let (x, y) = (logical_location.x, logical_location.y);
+
+            match phase {
+                TouchPhase::Started => ui.touch_start(*id, x, y),
+                TouchPhase::Moved => ui.touch_move(*id, x, y),
+                TouchPhase::Ended => ui.touch_end(*id),
+                TouchPhase::Cancelled => ui.touch_cancel(*id),
+            }
+
+            ui.want_capture_mouse()
+        }
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state,
+                    virtual_keycode: Some(virtual_keycode),
+                    ..
+                },
+            ..
+        } => {
+            let inputs = virtual_keycode_inputs(*virtual_keycode);
+            if inputs != guise::Inputs::NONE {
+                match state {
+                    ElementState::Pressed => ui.press_inputs(inputs),
+                    ElementState::Released => ui.release_inputs(inputs),
+                }
+            }
+
+            ui.want_capture_keyboard()
+        }
+        WindowEvent::ReceivedCharacter(character) => {
+            ui.send_character(*character);
+            ui.want_capture_keyboard()
+        }
+        // TODO(yan): Show preedit text once guise has a notion of it. Until
+        // then, only committed text reaches the UI.
+        WindowEvent::Ime(Ime::Commit(text)) => {
+            for character in text.chars() {
+                ui.send_character(character);
+            }
+
+            ui.want_capture_keyboard()
+        }
+        WindowEvent::ModifiersChanged(state) => {
+            let mut modifiers = guise::Modifiers::empty();
+
+            if state.ctrl() || options.logo_as_ctrl && state.logo() {
+                modifiers |= guise::Modifiers::CTRL;
+            }
+            if state.alt() {
+                modifiers |= guise::Modifiers::ALT;
+            }
+            if state.shift() {
+                modifiers |= guise::Modifiers::SHIFT;
+            }
+
+            ui.set_modifiers(modifiers);
+
+            // NB: Modifiers are state, not input, so the application always
+            // needs to see their changes too.
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Maps the mouse button to guise inputs, or [`guise::Inputs::NONE`], if
+/// guise has no matching input.
+pub fn mouse_button_inputs(button: MouseButton) -> guise::Inputs {
+    // NB: Winit 0.28 passes the platform's own numbers for other buttons. These
+    // are the back and forward buttons (and the two after them, if any) for
+    // each platform.
+    #[cfg(target_os = "windows")]
+    const OTHER_BUTTONS: [&[u16]; 4] = [&[1], &[2], &[], &[]];
+    #[cfg(target_os = "macos")]
+    const OTHER_BUTTONS: [&[u16]; 4] = [&[3], &[4], &[5], &[6]];
+    #[cfg(target_arch = "wasm32")]
+    const OTHER_BUTTONS: [&[u16]; 4] = [&[0], &[1], &[2], &[3]];
+    // X11 and Wayland, respectively.
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_arch = "wasm32")))]
+    const OTHER_BUTTONS: [&[u16]; 4] = [&[8, 0x113], &[9, 0x114], &[10, 0x115], &[11, 0x116]];
+
+    const OTHER_INPUTS: [guise::Inputs; 4] = [
+        guise::Inputs::MB_4,
+        guise::Inputs::MB_5,
+        guise::Inputs::MB_6,
+        guise::Inputs::MB_7,
+    ];
+
+    match button {
+        MouseButton::Left => guise::Inputs::MB_LEFT,
+        MouseButton::Right => guise::Inputs::MB_RIGHT,
+        MouseButton::Middle => guise::Inputs::MB_MIDDLE,
+        MouseButton::Other(n) => OTHER_BUTTONS
+            .iter()
+            .position(|numbers| numbers.contains(&n))
+            .map_or(guise::Inputs::NONE, |i| OTHER_INPUTS[i]),
+    }
+}
+
+/// Maps the key to guise inputs, or [`guise::Inputs::NONE`], if guise has no
+/// matching input.
+pub fn virtual_keycode_inputs(virtual_keycode: VirtualKeyCode) -> guise::Inputs {
+    match virtual_keycode {
+        VirtualKeyCode::Tab => guise::Inputs::KB_TAB,
+        VirtualKeyCode::Left => guise::Inputs::KB_LEFT_ARROW,
+        VirtualKeyCode::Right => guise::Inputs::KB_RIGHT_ARROW,
+        VirtualKeyCode::Up => guise::Inputs::KB_UP_ARROW,
+        VirtualKeyCode::Down => guise::Inputs::KB_DOWN_ARROW,
+        VirtualKeyCode::PageUp => guise::Inputs::KB_PAGE_UP,
+        VirtualKeyCode::PageDown => guise::Inputs::KB_PAGE_DOWN,
+        VirtualKeyCode::Home => guise::Inputs::KB_HOME,
+        VirtualKeyCode::End => guise::Inputs::KB_END,
+        VirtualKeyCode::Insert => guise::Inputs::KB_INSERT,
+        VirtualKeyCode::Delete => guise::Inputs::KB_DELETE,
+        VirtualKeyCode::Back => guise::Inputs::KB_BACKSPACE,
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => guise::Inputs::KB_ENTER,
+        VirtualKeyCode::Escape => guise::Inputs::KB_ESCAPE,
+        VirtualKeyCode::Space => guise::Inputs::KB_SPACE,
+        VirtualKeyCode::A => guise::Inputs::KB_A,
+        VirtualKeyCode::B => guise::Inputs::KB_B,
+        VirtualKeyCode::C => guise::Inputs::KB_C,
+        VirtualKeyCode::D => guise::Inputs::KB_D,
+        VirtualKeyCode::E => guise::Inputs::KB_E,
+        VirtualKeyCode::F => guise::Inputs::KB_F,
+        VirtualKeyCode::G => guise::Inputs::KB_G,
+        VirtualKeyCode::H => guise::Inputs::KB_H,
+        VirtualKeyCode::I => guise::Inputs::KB_I,
+        VirtualKeyCode::J => guise::Inputs::KB_J,
+        VirtualKeyCode::K => guise::Inputs::KB_K,
+        VirtualKeyCode::L => guise::Inputs::KB_L,
+        VirtualKeyCode::M => guise::Inputs::KB_M,
+        VirtualKeyCode::N => guise::Inputs::KB_N,
+        VirtualKeyCode::O => guise::Inputs::KB_O,
+        VirtualKeyCode::P => guise::Inputs::KB_P,
+        VirtualKeyCode::Q => guise::Inputs::KB_Q,
+        VirtualKeyCode::R => guise::Inputs::KB_R,
+        VirtualKeyCode::S => guise::Inputs::KB_S,
+        VirtualKeyCode::T => guise::Inputs::KB_T,
+        VirtualKeyCode::U => guise::Inputs::KB_U,
+        VirtualKeyCode::V => guise::Inputs::KB_V,
+        VirtualKeyCode::W => guise::Inputs::KB_W,
+        VirtualKeyCode::X => guise::Inputs::KB_X,
+        VirtualKeyCode::Y => guise::Inputs::KB_Y,
+        VirtualKeyCode::Z => guise::Inputs::KB_Z,
+        _ => guise::Inputs::NONE,
+    }
+}